@@ -0,0 +1,28 @@
+//! Aggregate signature size report across batch sizes.
+//!
+//! Prints the encoded size of one aggregate next to the summed size of the signatures it
+//! replaces, and the time to aggregate the batch. Run with `cargo bench --bench aggregate`.
+
+use loquat_vc::signature::aggregate::LoquatAggregate;
+use loquat_vc::signature::config::LoquatConfig;
+use std::time::Instant;
+
+fn main() {
+    let config = LoquatConfig::default();
+    let batch_sizes = [1, 16, 256, 4096];
+
+    let start = Instant::now();
+    let reports = LoquatAggregate::size_report(&batch_sizes, &config);
+    let elapsed = start.elapsed();
+
+    for report in &reports {
+        println!(
+            "batch {:>5}  aggregate {:>5} B  individual {:>8} B  ratio {:>8.1}x",
+            report.batch_size,
+            report.aggregate_bytes,
+            report.individual_bytes,
+            report.compression_ratio()
+        );
+    }
+    println!("signed and aggregated {} signatures in {:.2?}", batch_sizes.iter().sum::<usize>(), elapsed);
+}
@@ -0,0 +1,49 @@
+//! FRI prover timings, sequential against multi-threaded.
+//!
+//! Proves tables of 2^16 to 2^20 values with the default configuration through
+//! `proof_system::fri` and `proof_system::parallel`, checks that both give the same proof and
+//! prints the speedup on this machine's threads. Run with
+//! `cargo bench --bench fri --features fri,parallel`.
+
+use loquat_vc::crypto::goldilocks::Goldilocks;
+use loquat_vc::proof_system::fri::{self, EvaluationDomain, FriConfig};
+use loquat_vc::proof_system::parallel;
+use loquat_vc::proof_system::transcript::Transcript;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 3;
+
+fn main() {
+    let config = FriConfig::default();
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for log_size in [16, 18, 20] {
+        let log_degree_bound = log_size - config.log_blowup;
+        let domain = EvaluationDomain::coset(log_size).unwrap();
+        let coeffs: Vec<Goldilocks> = (0..1u64 << log_degree_bound).map(|i| Goldilocks::new(i * i + 3)).collect();
+        let values = domain.evaluate(&coeffs).unwrap();
+
+        let start = Instant::now();
+        let mut sequential = None;
+        for _ in 0..ITERATIONS {
+            sequential = fri::prove(&values, log_degree_bound, &domain, &config, &mut Transcript::new(b"fri-bench"));
+        }
+        let sequential_time = start.elapsed() / ITERATIONS;
+
+        let start = Instant::now();
+        let mut threaded = None;
+        for _ in 0..ITERATIONS {
+            threaded = parallel::prove(&values, log_degree_bound, &domain, &config, &mut Transcript::new(b"fri-bench"));
+        }
+        let threaded_time = start.elapsed() / ITERATIONS;
+        assert_eq!(sequential, threaded, "Parallel proof differs");
+
+        println!(
+            "2^{:<2}  sequential {:>10.2?}  parallel {:>10.2?}  speedup {:>5.2}x on {} threads",
+            log_size,
+            sequential_time,
+            threaded_time,
+            sequential_time.as_secs_f64() / threaded_time.as_secs_f64(),
+            threads
+        );
+    }
+}
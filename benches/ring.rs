@@ -0,0 +1,72 @@
+//! Ring signature benchmarks for rings of 2^10 and 2^20 members.
+//!
+//! Building the ring tree is linear in the ring size and done once per ring; signing against
+//! the tree and verifying against its root are logarithmic. Run with
+//! `cargo bench --bench ring --features experimental`.
+
+use loquat_vc::signature::config::LoquatConfig;
+use loquat_vc::prelude::Loquat;
+use loquat_vc::signature::ring_signature::LoquatRingSignature;
+use rand::RngCore;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100;
+
+fn average(total: Duration) -> Duration {
+    total / ITERATIONS
+}
+
+fn bench_ring(log_size: u32) {
+    let config = LoquatConfig::default();
+    let size = 1usize << log_size;
+    let signer = Loquat::keygen();
+    let signer_index = size / 3;
+
+    // Random 32-byte leaves stand in for the other members' public keys
+    let mut rng = rand::thread_rng();
+    let public_keys: Vec<Vec<u8>> = (0..size)
+        .map(|i| {
+            if i == signer_index {
+                signer.public_key.clone()
+            } else {
+                let mut pk = vec![0u8; 32];
+                rng.fill_bytes(&mut pk);
+                pk
+            }
+        })
+        .collect();
+
+    let start = Instant::now();
+    let tree = LoquatRingSignature::ring_tree(&public_keys, &config);
+    let root = tree.root().unwrap();
+    let build = start.elapsed();
+
+    let start = Instant::now();
+    let mut signature = None;
+    for _ in 0..ITERATIONS {
+        signature = LoquatRingSignature::sign_with_tree(signer.secret_key, b"ring benchmark", &tree, signer_index, &config);
+    }
+    let sign = average(start.elapsed());
+    let signature = signature.unwrap();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(LoquatRingSignature::verify_with_root(&root, b"ring benchmark", &signature, &config));
+    }
+    let verify = average(start.elapsed());
+
+    println!(
+        "ring 2^{:<2}  tree build {:>10.2?}  sign {:>10.2?}  verify {:>10.2?}  path length {}",
+        log_size,
+        build,
+        sign,
+        verify,
+        signature.membership.len()
+    );
+}
+
+fn main() {
+    for log_size in [10, 20] {
+        bench_ring(log_size);
+    }
+}
@@ -0,0 +1,199 @@
+//! Reference issuer daemon: persistent keys, credential issuance and status lists over HTTP
+//!
+//! The server speaks just enough HTTP/1.1 over `std::net` to be driven with curl, so the example
+//! builds without an HTTP framework; a deployment would mount the same handlers in one.
+//! Run with: cargo run --example issuer_server -- [address] [key file]
+//!
+//! Endpoints:
+//! - `GET /.well-known/did.json`: the issuer's DID document
+//! - `POST /credentials`: issues a credential for the JSON subject in the body
+//! - `POST /credentials/batch`: issues one credential per subject in a JSON array
+//! - `GET /status/1`: the encoded revocation list
+//! - `POST /status/1/<index>/revoke`: revokes the credential at a status index
+
+use loquat_vc::prelude::{CredentialBuilder, DidDocument, Loquat, LoquatConfig, LoquatKeyPair};
+use loquat_vc::utils::encoding::Encoding;
+use loquat_vc::vc::status::StatusList;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::{env, fs, thread};
+
+const ISSUER_DID: &str = "did:example:issuer";
+const STATUS_LIST_LEN: usize = 1 << 16;
+// Largest request body read; bigger requests are refused before reading
+const MAX_BODY: usize = 1 << 20;
+// Most credentials signed per batch request, and so signing threads per request
+const MAX_BATCH: usize = 64;
+
+struct Issuer {
+    keypair: LoquatKeyPair,
+    document: DidDocument,
+    status_url: String,
+    status_list: Mutex<StatusList>,
+    next_index: Mutex<usize>,
+}
+
+impl Issuer {
+    fn new(keypair: LoquatKeyPair, address: &str) -> Self {
+        let mut document = DidDocument::new(ISSUER_DID);
+        document.add_key("key-1", &keypair.public_key);
+        Self {
+            keypair,
+            document,
+            status_url: format!("http://{}/status/1", address),
+            status_list: Mutex::new(StatusList::new(STATUS_LIST_LEN)),
+            next_index: Mutex::new(0),
+        }
+    }
+
+    // Reserves `count` consecutive status indices
+    fn reserve(&self, count: usize) -> Option<usize> {
+        let mut next = self.next_index.lock().expect("Index lock poisoned");
+        let start = *next;
+        if start + count > STATUS_LIST_LEN {
+            return None;
+        }
+        *next += count;
+        Some(start)
+    }
+
+    fn issue(&self, subject: Value, index: usize) -> Value {
+        let credential = CredentialBuilder::new(ISSUER_DID)
+            .credential_type("ExampleCredential")
+            .subject(subject)
+            .expires_in(365)
+            .status_list(&self.status_url, index)
+            .build_and_sign(self.keypair.secret_key)
+            .expect("Signing refused after a failed randomness selftest");
+        serde_json::to_value(&credential).expect("Credential serialization failed")
+    }
+
+    // Signs a batch on one thread per subject; Loquat signing is independent per credential
+    fn issue_batch(&self, subjects: Vec<Value>) -> Option<Value> {
+        let start = self.reserve(subjects.len())?;
+        let credentials: Vec<Value> = thread::scope(|scope| {
+            let handles: Vec<_> = subjects
+                .into_iter()
+                .enumerate()
+                .map(|(i, subject)| scope.spawn(move || self.issue(subject, start + i)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().expect("Signing thread panicked")).collect()
+        });
+        Some(Value::Array(credentials))
+    }
+
+    fn handle(&self, method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            ("GET", [".well-known", "did.json"]) => {
+                (200, serde_json::to_value(&self.document).expect("DID document serialization failed"))
+            }
+            ("POST", ["credentials"]) => match serde_json::from_slice::<Value>(body) {
+                Ok(subject) if subject.is_object() => match self.reserve(1) {
+                    Some(index) => (201, self.issue(subject, index)),
+                    None => (503, json!({"error": "status_list_full"})),
+                },
+                _ => (400, json!({"error": "invalid_subject"})),
+            },
+            ("POST", ["credentials", "batch"]) => match serde_json::from_slice::<Vec<Value>>(body) {
+                Ok(subjects) if subjects.len() <= MAX_BATCH && subjects.iter().all(Value::is_object) => match self.issue_batch(subjects) {
+                    Some(credentials) => (201, credentials),
+                    None => (503, json!({"error": "status_list_full"})),
+                },
+                _ => (400, json!({"error": "invalid_subject"})),
+            },
+            ("GET", ["status", "1"]) => {
+                let list = self.status_list.lock().expect("Status list lock poisoned");
+                (200, json!({"id": self.status_url, "encodedList": list.encode()}))
+            }
+            ("POST", ["status", "1", index, "revoke"]) => {
+                let mut list = self.status_list.lock().expect("Status list lock poisoned");
+                match index.parse() {
+                    Ok(index) if list.set_revoked(index, true) => (200, json!({"revoked": index})),
+                    _ => (404, json!({"error": "unknown_index"})),
+                }
+            }
+            _ => (404, json!({"error": "not_found"})),
+        }
+    }
+}
+
+// Loads the issuer key from `path`, generating and storing one on first start
+fn load_or_create_key(path: &str) -> LoquatKeyPair {
+    let config = LoquatConfig::default();
+    if let Ok(stored) = fs::read_to_string(path) {
+        let keypair = Encoding::from_hex(stored.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u128::from_be_bytes)
+            .and_then(|secret_key| Loquat::keypair_from_secret(secret_key, &config));
+        return keypair.unwrap_or_else(|| panic!("{} does not hold a Loquat secret key", path));
+    }
+
+    let keypair = Loquat::keygen_with_config(&config);
+    // A real keystore would encrypt the key at rest and restrict the file's permissions
+    fs::write(path, Encoding::to_hex(&keypair.secret_key.to_be_bytes())).expect("Failed to write the key file");
+    println!("Generated a new issuer key in {}", path);
+    keypair
+}
+
+fn serve(issuer: &Issuer, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, response) = if content_length > MAX_BODY {
+        (413, json!({"error": "body_too_large"}))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        issuer.handle(method, path, &body)
+    };
+
+    let body = serde_json::to_string_pretty(&response).expect("Response serialization failed");
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        if status < 400 { "OK" } else { "Error" },
+        body.len(),
+        body
+    )
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let key_path = args.next().unwrap_or_else(|| "issuer.key".to_string());
+
+    let issuer = Arc::new(Issuer::new(load_or_create_key(&key_path), &address));
+    println!("Issuer {} with public key {}", ISSUER_DID, issuer.keypair);
+
+    let listener = TcpListener::bind(&address).expect("Failed to bind the listen address");
+    println!("Listening on http://{}", address);
+    for stream in listener.incoming().flatten() {
+        let issuer = Arc::clone(&issuer);
+        thread::spawn(move || {
+            if let Err(error) = serve(&issuer, stream) {
+                eprintln!("Connection error: {}", error);
+            }
+        });
+    }
+}
@@ -3,7 +3,7 @@
 //! This example shows how to generate a new key pair for the Loquat signature scheme.
 //! Run with: cargo run --example keygen
 
-use loquat_vc::signature::loquat::Loquat;
+use loquat_vc::prelude::Loquat;
 
 fn main() {
     println!("Generating a new Loquat key pair...");
@@ -14,8 +14,9 @@ fn main() {
     // Print the generated keys in a user-friendly format
     println!("\nGenerated Key Pair:");
     println!("------------------");
-    println!("Secret Key: {:?}", keypair.secret_key);
-    println!("Public Key: {:?}", keypair.public_key);
+    // Debug output redacts the secret key; store it through a key manager instead of logs
+    println!("Key Pair: {:?}", keypair);
+    println!("Public Key: {}", keypair);
     
     println!("\nKey generation successful!");
 }
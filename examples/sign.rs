@@ -1,6 +1,6 @@
 //! Example demonstrating how to sign a message using Loquat
 
-use loquat_vc::signature::loquat::Loquat;
+use loquat_vc::prelude::Loquat;
 
 fn main() {
     // Define a sample message to sign
@@ -12,7 +12,7 @@ fn main() {
     println!("Secret key generated successfully");
 
     // Sign the message using the secret key
-    let signature = Loquat::sign(keypair.secret_key, message);
+    let signature = Loquat::sign(keypair.secret_key, message).expect("Signing refused after a failed randomness selftest");
     
     // Print the signature components
     println!("\nSignature created successfully:");
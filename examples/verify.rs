@@ -3,7 +3,7 @@
 //! This example shows how to verify a digital signature using the Loquat signature scheme.
 //! It generates a key pair, signs a message, and then verifies the signature.
 
-use loquat_vc::signature::loquat::Loquat;
+use loquat_vc::prelude::Loquat;
 
 fn main() {
     // Set up a sample message to be signed
@@ -17,7 +17,7 @@ fn main() {
     
     // Sign the message using the private key
     println!("Signing message...");
-    let signature = Loquat::sign(keypair.secret_key, message);
+    let signature = Loquat::sign(keypair.secret_key, message).expect("Signing refused after a failed randomness selftest");
     println!("Signature created: {:?}", signature);
     
     // Verify the signature using the public key
@@ -0,0 +1,142 @@
+//! Holder wallet: storing a credential and presenting selected claims to a verifier
+//!
+//! The issuer delivers an SD-JWT credential bound to the holder's key. The wallet checks it
+//! before storing it, and later answers a verifier's challenge by disclosing only the claims
+//! asked for, with a key binding JWT over the verifier's audience and nonce.
+//! Run with: cargo run --example wallet --features jose
+
+use loquat_vc::prelude::{CredentialBuilder, DidDocument, DidResolver, Loquat, LoquatKeyPair};
+use loquat_vc::utils::time;
+use loquat_vc::vc::challenge::ChallengeManager;
+use loquat_vc::vc::did::StaticDidResolver;
+use loquat_vc::vc::jwt::Jwt;
+use loquat_vc::vc::sd_jwt::SdJwt;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::{env, fs};
+
+const ISSUER_DID: &str = "did:example:university";
+const HOLDER_DID: &str = "did:example:alice";
+const VERIFIER_ID: &str = "https://verifier.example";
+
+// Holder-side storage of received credentials, keyed by a local name
+struct Wallet {
+    keypair: LoquatKeyPair,
+    kid: String,
+    credentials: BTreeMap<String, String>, // Serialized SD-JWTs
+}
+
+impl Wallet {
+    fn new(did: &str) -> Self {
+        Self {
+            keypair: Loquat::keygen(),
+            kid: format!("{}#key-1", did),
+            credentials: BTreeMap::new(),
+        }
+    }
+
+    fn did_document(&self) -> DidDocument {
+        let (did, _) = self.kid.split_once('#').expect("Key id is a DID URL");
+        let mut document = DidDocument::new(did);
+        document.add_key("key-1", &self.keypair.public_key);
+        document
+    }
+
+    // Stores a credential after checking its issuer signature and that it is bound to this wallet
+    fn receive<R: DidResolver>(&mut self, name: &str, serialized: &str, resolver: &R) -> Result<(), &'static str> {
+        let sd_jwt = SdJwt::parse(serialized).ok_or("malformed credential")?;
+        let issuer_kid = Jwt::parse(&sd_jwt.jwt).and_then(|jwt| jwt.header.kid).ok_or("missing issuer key id")?;
+        let issuer_pk = resolver.resolve_key(&issuer_kid).ok_or("unknown issuer key")?;
+        let claims = sd_jwt.verify(&issuer_pk).ok_or("invalid issuer signature")?;
+        if claims["cnf"]["kid"] != Value::String(self.kid.clone()) {
+            return Err("credential is bound to another holder");
+        }
+        self.credentials.insert(name.to_string(), serialized.to_string());
+        Ok(())
+    }
+
+    // Discloses the requested claims of a stored credential to one verifier, for one challenge
+    fn present(&self, name: &str, claims: &[&str], audience: &str, nonce: &str) -> Option<String> {
+        let stored = SdJwt::parse(self.credentials.get(name)?)?;
+        let mut presentation = stored.present(claims)?;
+        presentation.bind(self.keypair.secret_key, audience, nonce, time::now_unix()).ok()?;
+        Some(presentation.serialize())
+    }
+
+    // The stored credentials as JSON, for persisting between sessions
+    fn export(&self) -> String {
+        serde_json::to_string_pretty(&self.credentials).expect("Wallet serialization failed")
+    }
+
+    fn import(&mut self, exported: &str) -> bool {
+        match serde_json::from_str(exported) {
+            Ok(credentials) => {
+                self.credentials = credentials;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+// Verifier side: issuer signature, disclosures, key binding to the cnf key and the challenge
+fn verify_presentation<R: DidResolver>(serialized: &str, resolver: &R, challenges: &mut ChallengeManager, nonce: &str) -> Option<Value> {
+    let presentation = SdJwt::parse(serialized)?;
+    let issuer_kid = Jwt::parse(&presentation.jwt)?.header.kid?;
+    let claims = presentation.verify(&resolver.resolve_key(&issuer_kid)?)?;
+    let holder_pk = resolver.resolve_key(claims["cnf"]["kid"].as_str()?)?;
+    if !presentation.verify_key_binding(&holder_pk, VERIFIER_ID, nonce) {
+        return None;
+    }
+    challenges.consume(nonce, time::now_unix()).ok()?;
+    Some(claims)
+}
+
+fn main() {
+    let mut wallet = Wallet::new(HOLDER_DID);
+    let issuer = Loquat::keygen();
+    let mut issuer_document = DidDocument::new(ISSUER_DID);
+    let issuer_kid = issuer_document.add_key("key-1", &issuer.public_key);
+
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(issuer_document);
+    resolver.insert(wallet.did_document());
+
+    // Issuance: every subject claim is selectively disclosable
+    println!("Issuing a degree credential to {}...", HOLDER_DID);
+    let credential = CredentialBuilder::new(ISSUER_DID)
+        .credential_type("UniversityDegreeCredential")
+        .subject(json!({"id": HOLDER_DID, "name": "Alice", "birthdate": "2000-01-01", "degree": "BSc Computer Science"}))
+        .expires_in(365)
+        .build();
+    let sd_jwt = SdJwt::issue_credential(&credential, &["name", "birthdate", "degree"], Some(&wallet.kid), &issuer_kid, issuer.secret_key)
+        .expect("Credential claims are disclosable");
+    wallet.receive("degree", &sd_jwt.serialize(), &resolver).expect("Issued credential is accepted");
+    println!("Stored {} credential(s)", wallet.credentials.len());
+
+    // Storage survives a restart of the wallet
+    let path = env::temp_dir().join("loquat-wallet.json");
+    fs::write(&path, wallet.export()).expect("Failed to write the wallet file");
+    let mut restored = Wallet { credentials: BTreeMap::new(), ..wallet };
+    assert!(restored.import(&fs::read_to_string(&path).expect("Failed to read the wallet file")));
+    fs::remove_file(&path).ok();
+    println!("Restored the wallet from {}", path.display());
+
+    // Presentation: the verifier asks for the degree only
+    let mut challenges = ChallengeManager::new(300);
+    let challenge = challenges.issue();
+    println!("\nVerifier {} requests `degree` with nonce {}", VERIFIER_ID, challenge.value);
+    let presentation = restored.present("degree", &["degree"], VERIFIER_ID, &challenge.value).expect("Credential is stored");
+
+    match verify_presentation(&presentation, &resolver, &mut challenges, &challenge.value) {
+        Some(claims) => {
+            println!("Presentation verified, revealed degree: {}", claims["degree"]);
+            println!("Name disclosed: {}, birthdate disclosed: {}", claims.get("name").is_some(), claims.get("birthdate").is_some());
+        }
+        None => println!("Presentation rejected"),
+    }
+
+    // The challenge was consumed, so replaying the same presentation fails
+    let replayed = verify_presentation(&presentation, &resolver, &mut challenges, &challenge.value);
+    println!("Replayed presentation accepted: {}", replayed.is_some());
+}
@@ -0,0 +1,191 @@
+// One circuit for a mixture of statements
+// A presentation carrying several credentials and their revocation checks would otherwise need
+// a proof per signature and per non-membership check. BatchRelation lays all of them out in a
+// single constraint system, so one proof from any R1CS backend (see export::r1cs) covers the
+// lot. The first public input is a digest of the statement descriptor list, binding the kind,
+// order and public data of every statement into the proven instance: a proof for one list
+// does not verify against an instance assembled from another.
+// - Signature: the indexed public key's PRF symbols are L_K(i) for the private key K, and
+//   sigma = K - h + 2 * L_K(h) * h for the message hash h, as in Loquat::sign
+// - NonRevocation: a private serial is not revoked in the revocation tree with this root
+
+use crate::circuit::legendre::{enforce_indexed_public_key, legendre_prf_gadget};
+use crate::circuit::merkle::PathStep;
+use crate::circuit::r1cs::{ConstraintSystem, Variable};
+use crate::circuit::revocation::{enforce_non_membership, NonMembershipWitness};
+use crate::circuit::witness::Circuit;
+use crate::compact::field;
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::LoquatSignature;
+use crate::signature::message::SignableMessage;
+use crate::signature::public_indices::IndexedPublicKey;
+use crate::utils::encoding::Encoding;
+use num_traits::ToPrimitive;
+
+// Domain separation of the descriptor digest
+const BATCH_DOMAIN: &[u8] = b"loquat-batch-statements";
+
+// Fills authentication paths shorter than the tree depth
+const PADDING_STEP: PathStep = PathStep { sibling: 0, sibling_is_left: false };
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementDescriptor {
+  Signature { public_key: IndexedPublicKey, message_hash: u128, sigma: u128 },
+  NonRevocation { root: u128, depth: usize },
+}
+
+impl StatementDescriptor {
+  // Descriptor of a signature over message, hashed as the signature's config prescribes
+  pub fn signature<M: SignableMessage + ?Sized>(public_key: IndexedPublicKey, message: &M, signature: &LoquatSignature) -> Option<Self> {
+    Some(Self::Signature {
+      public_key,
+      message_hash: message.message_hash(signature.config.msg_hash),
+      sigma: signature.sigma.to_u128().filter(|sigma| *sigma < field::P)?,
+    })
+  }
+
+  fn write(&self, out: &mut Vec<u8>) {
+    match self {
+      Self::Signature { public_key, message_hash, sigma } => {
+        out.push(0);
+        Encoding::write_bytes(out, &public_key.encode(&LoquatConfig::default()));
+        out.extend_from_slice(&message_hash.to_be_bytes());
+        out.extend_from_slice(&sigma.to_be_bytes());
+      }
+      Self::NonRevocation { root, depth } => {
+        out.push(1);
+        out.extend_from_slice(&root.to_be_bytes());
+        out.extend_from_slice(&(*depth as u32).to_be_bytes());
+      }
+    }
+  }
+}
+
+// Private input of one statement, in descriptor order
+#[derive(Debug, Clone)]
+pub enum StatementWitness {
+  SecretKey(u128),
+  NonRevocation(NonMembershipWitness),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchStatement {
+  pub descriptors: Vec<StatementDescriptor>,
+}
+
+impl BatchStatement {
+  pub fn new(descriptors: Vec<StatementDescriptor>) -> Self {
+    Self { descriptors }
+  }
+
+  // Digest of the descriptor list, as a field element
+  pub fn digest(&self) -> u128 {
+    let mut out = BATCH_DOMAIN.to_vec();
+    out.extend_from_slice(&(self.descriptors.len() as u32).to_be_bytes());
+    for descriptor in &self.descriptors {
+      descriptor.write(&mut out);
+    }
+    field::from_be_bytes_mod(&Hash::new(HashFunction::Sha3_256).compute(&out))
+  }
+}
+
+pub struct BatchRelation;
+
+impl Circuit for BatchRelation {
+  type Statement = BatchStatement;
+  type PrivateInput = Vec<StatementWitness>;
+
+  // A missing witness or one of the wrong kind is replaced by a placeholder of the right
+  // shape, so the constraints still depend on the statement only
+  fn synthesize(cs: &mut ConstraintSystem, statement: &BatchStatement, witnesses: &Vec<StatementWitness>) {
+    cs.alloc_input(statement.digest());
+    for (position, descriptor) in statement.descriptors.iter().enumerate() {
+      match descriptor {
+        StatementDescriptor::Signature { public_key, message_hash, sigma } => {
+          let sk = match witnesses.get(position) {
+            Some(StatementWitness::SecretKey(sk)) => *sk,
+            _ => 0,
+          };
+          let key = cs.alloc_witness(sk).into();
+          enforce_indexed_public_key(cs, &key, public_key);
+          let bit = legendre_prf_gadget(cs, &key, *message_hash);
+          let sigma = cs.alloc_input(*sigma);
+          let expected = key.minus(Variable::One, *message_hash).plus(bit, field::add(*message_hash, *message_hash));
+          cs.enforce_equal(sigma.into(), expected);
+        }
+        StatementDescriptor::NonRevocation { root, depth } => {
+          let placeholder = NonMembershipWitness::empty(*depth);
+          let witness = match witnesses.get(position) {
+            Some(StatementWitness::NonRevocation(witness)) => witness,
+            _ => &placeholder,
+          };
+          let mut shaped = witness.clone();
+          shaped.path.resize(*depth, PADDING_STEP);
+          let root = cs.alloc_input(*root);
+          let serial = cs.alloc_witness(shaped.serial as u128);
+          enforce_non_membership(cs, &serial.into(), &shaped, &root.into());
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::circuit::indexer::VerifyingKey;
+  use crate::circuit::revocation::RevocationTree;
+  use crate::circuit::witness::WitnessBuilder;
+  use crate::signature::loquat::Loquat;
+
+  // Two signed credentials and the revocation checks of their serials
+  fn presentation() -> (BatchStatement, Vec<StatementWitness>) {
+    let config = LoquatConfig::default();
+    let tree = RevocationTree::new(&[3, 17, 90]);
+    let mut descriptors = vec![];
+    let mut witnesses = vec![];
+    for (sk, message, serial) in [(0x1234_5678_9abc_def0_u128, &b"credential one"[..], 20u64), (0x0fed_cba9_8765_4321, b"credential two", 91)] {
+      let public_key = IndexedPublicKey::new(sk, &[9u8; 32], 8, &config);
+      let signature = Loquat::sign(sk, message).unwrap();
+      descriptors.push(StatementDescriptor::signature(public_key, message, &signature).unwrap());
+      witnesses.push(StatementWitness::SecretKey(sk));
+      descriptors.push(StatementDescriptor::NonRevocation { root: tree.root(), depth: tree.depth() });
+      witnesses.push(StatementWitness::NonRevocation(tree.non_membership(serial).unwrap()));
+    }
+    (BatchStatement::new(descriptors), witnesses)
+  }
+
+  #[test]
+  fn test_heterogeneous_batch_satisfied() {
+    let (statement, witnesses) = presentation();
+    let builder = WitnessBuilder::<BatchRelation>::new(&statement);
+    let cs = builder.constraint_system(&witnesses);
+    assert!(cs.is_satisfied());
+    assert_eq!(cs.inputs()[0], statement.digest());
+
+    // Wrong or missing witnesses keep the shape but fail
+    let mut wrong = witnesses.clone();
+    wrong[2] = StatementWitness::SecretKey(5);
+    let wrong_cs = builder.constraint_system(&wrong);
+    assert!(!wrong_cs.is_satisfied());
+    assert!(VerifyingKey::index(&cs).matches(&wrong_cs));
+    assert!(!builder.constraint_system(&witnesses[..3].to_vec()).is_satisfied());
+  }
+
+  #[test]
+  fn test_descriptor_list_is_bound() {
+    let (statement, witnesses) = presentation();
+    let mut reordered = statement.clone();
+    reordered.descriptors.swap(0, 2);
+    assert_ne!(reordered.digest(), statement.digest());
+
+    // A different message under the same signature changes the instance and fails
+    let mut forged = statement.clone();
+    if let StatementDescriptor::Signature { message_hash, .. } = &mut forged.descriptors[0] {
+      *message_hash = field::add(*message_hash, 1);
+    }
+    assert_ne!(forged.digest(), statement.digest());
+    assert!(!WitnessBuilder::<BatchRelation>::new(&forged).constraint_system(&witnesses).is_satisfied());
+  }
+}
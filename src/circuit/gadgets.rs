@@ -0,0 +1,121 @@
+// Bit decomposition, range checks, comparisons and selectors
+// Field elements have no order, so integer predicates (age thresholds, expiry dates, canonical
+// encodings) are stated on bit decompositions: a value lies in [0, 2^n) when it equals a sum of
+// n boolean variables weighted by powers of two. n stays below 127 so that the sum cannot wrap
+// around the modulus. Comparisons assume both operands are already known to be n-bit.
+
+use crate::circuit::r1cs::{ConstraintSystem, LinearCombination, Variable};
+use crate::compact::field;
+
+// Largest supported bit width
+pub const MAX_BITS: usize = 126;
+
+// Little-endian bits of value, constrained to recompose to it; unsatisfiable unless value < 2^n
+pub fn to_bits(cs: &mut ConstraintSystem, value: &LinearCombination, n: usize) -> Vec<Variable> {
+  assert!(n <= MAX_BITS, "bit width {} exceeds {}", n, MAX_BITS);
+  let assigned = cs.evaluate(value);
+  let mut sum = LinearCombination::zero();
+  let bits = (0..n)
+    .map(|i| {
+      let bit = cs.alloc_witness((assigned >> i) & 1);
+      cs.enforce_boolean(bit);
+      sum = std::mem::take(&mut sum).plus(bit, 1 << i);
+      bit
+    })
+    .collect();
+  cs.enforce_equal(sum, value.clone());
+  bits
+}
+
+// Enforces 0 <= value < 2^n
+pub fn enforce_range(cs: &mut ConstraintSystem, value: &LinearCombination, n: usize) {
+  to_bits(cs, value, n);
+}
+
+// Enforces a < b for n-bit a and b: b - a - 1 is n-bit exactly when a < b
+pub fn enforce_less_than(cs: &mut ConstraintSystem, a: &LinearCombination, b: &LinearCombination, n: usize) {
+  let difference = b.clone().minus_lc(a).minus(Variable::One, 1);
+  enforce_range(cs, &difference, n);
+}
+
+// Boolean a < b for n-bit a and b: bit n of a - b + 2^n is set exactly when a >= b
+pub fn less_than(cs: &mut ConstraintSystem, a: &LinearCombination, b: &LinearCombination, n: usize) -> Variable {
+  assert!(n < MAX_BITS, "bit width {} exceeds {}", n, MAX_BITS - 1);
+  let shifted = a.clone().minus_lc(b).plus(Variable::One, 1 << n);
+  let bits = to_bits(cs, &shifted, n + 1);
+  let lt = cs.alloc_witness(1 - cs.value(bits[n]));
+  cs.enforce_equal(lt.into(), LinearCombination::constant(1).minus(bits[n], 1));
+  lt
+}
+
+// Boolean a == b
+pub fn is_equal(cs: &mut ConstraintSystem, a: &LinearCombination, b: &LinearCombination) -> Variable {
+  let difference = a.clone().minus_lc(b);
+  let d = cs.evaluate(&difference);
+  let inverse = cs.alloc_witness(if d == 0 { 0 } else { field::pow(d, field::P - 2) });
+  let eq = cs.alloc_witness((d == 0) as u128);
+  // d * inverse = 1 - eq, and d * eq = 0
+  cs.enforce(difference.clone(), inverse.into(), LinearCombination::constant(1).minus(eq, 1));
+  cs.enforce(difference, eq.into(), LinearCombination::zero());
+  eq
+}
+
+// if_true when bit is 1, if_false when it is 0; bit must already be boolean
+pub fn select(cs: &mut ConstraintSystem, bit: Variable, if_true: &LinearCombination, if_false: &LinearCombination) -> LinearCombination {
+  let t = cs.mul(bit.into(), if_true.clone().minus_lc(if_false));
+  if_false.clone().plus(t, 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_range_check() {
+    let mut cs = ConstraintSystem::new();
+    let value = cs.alloc_witness(200);
+    let bits = to_bits(&mut cs, &value.into(), 8);
+    assert_eq!(bits.iter().map(|bit| cs.value(*bit)).collect::<Vec<_>>(), vec![0, 0, 0, 1, 0, 0, 1, 1]);
+    assert!(cs.is_satisfied());
+
+    let mut cs = ConstraintSystem::new();
+    let value = cs.alloc_witness(256);
+    enforce_range(&mut cs, &value.into(), 8);
+    assert!(!cs.is_satisfied());
+
+    // Negative values are huge field elements and fail too
+    let mut cs = ConstraintSystem::new();
+    let value = cs.alloc_witness(field::P - 1);
+    enforce_range(&mut cs, &value.into(), 64);
+    assert!(!cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_comparisons() {
+    for (a, b) in [(17u128, 18u128), (18, 18), (19, 18), (0, 255), (255, 0)] {
+      let mut cs = ConstraintSystem::new();
+      let a_var = cs.alloc_witness(a);
+      let b_var = cs.alloc_input(b);
+      let lt = less_than(&mut cs, &a_var.into(), &b_var.into(), 8);
+      assert_eq!(cs.value(lt), (a < b) as u128);
+      assert!(cs.is_satisfied());
+
+      let mut cs = ConstraintSystem::new();
+      let a_var = cs.alloc_witness(a);
+      enforce_less_than(&mut cs, &a_var.into(), &LinearCombination::constant(b), 8);
+      assert_eq!(cs.is_satisfied(), a < b);
+    }
+  }
+
+  #[test]
+  fn test_equality_and_select() {
+    let mut cs = ConstraintSystem::new();
+    let x = cs.alloc_witness(5);
+    let same = is_equal(&mut cs, &x.into(), &LinearCombination::constant(5));
+    let different = is_equal(&mut cs, &x.into(), &LinearCombination::constant(6));
+    assert_eq!((cs.value(same), cs.value(different)), (1, 0));
+    let chosen = select(&mut cs, different, &LinearCombination::constant(10), &x.into());
+    assert_eq!(cs.evaluate(&chosen), 5);
+    assert!(cs.is_satisfied());
+  }
+}
@@ -0,0 +1,204 @@
+// Preprocessed verification keys
+// The indexer reduces a constraint system to what a holographic verifier needs to know about
+// it: its dimensions and a commitment to each of the A, B and C matrices, so verification
+// never reads the circuit itself. The commitments are SHA3-256 digests of the canonical
+// row-by-row matrix encoding. A VerifyingKey is meant to be distributed like an SRS/VK
+// artifact: verifiers pin its content hash, cache it, and can rebuild it from the circuit to
+// audit it.
+// Encoding: version | u32 inputs | u32 witnesses | u32 constraints | A | B | C, each matrix
+// commitment length-prefixed.
+// A ProverKey keeps the matrices themselves next to their verifying key, for provers that
+// store it (see prover_cache) instead of synthesizing the circuit shape and indexing it again.
+
+use crate::circuit::r1cs::{Constraint, ConstraintSystem, LinearCombination, Variable};
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::utils::encoding::Encoding;
+use serde::{Deserialize, Serialize};
+
+// Version of the verifying key encoding
+pub const VERIFYING_KEY_VERSION: u8 = 1;
+
+// Domain separation of matrix commitments and content hashes
+const MATRIX_DOMAIN: &[u8] = b"loquat-r1cs-matrix";
+const VERIFYING_KEY_DOMAIN: &[u8] = b"loquat-verifying-key";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyingKey {
+  pub num_inputs: u32,
+  pub num_witnesses: u32,
+  pub num_constraints: u32,
+  pub matrix_commitments: [Vec<u8>; 3], // A, B, C
+}
+
+// Canonical encoding of one row: term count, then (kind, index, coefficient) per term
+fn write_row(out: &mut Vec<u8>, row: &LinearCombination) {
+  let terms = row.normalized();
+  out.extend_from_slice(&(terms.len() as u32).to_be_bytes());
+  for (variable, coefficient) in terms {
+    let (kind, index) = match variable {
+      Variable::One => (0u8, 0),
+      Variable::Input(i) => (1, i as u32),
+      Variable::Witness(i) => (2, i as u32),
+    };
+    out.push(kind);
+    out.extend_from_slice(&index.to_be_bytes());
+    out.extend_from_slice(&coefficient.to_be_bytes());
+  }
+}
+
+fn read_u32(bytes: &[u8], offset: &mut usize) -> Option<u32> {
+  let value = u32::from_be_bytes(bytes.get(*offset..*offset + 4)?.try_into().ok()?);
+  *offset += 4;
+  Some(value)
+}
+
+impl VerifyingKey {
+  // Indexes a constraint system; only its shape matters, not the assigned values
+  pub fn index(cs: &ConstraintSystem) -> Self {
+    let hash = Hash::new(HashFunction::Sha3_256);
+    let commit = |matrix: u8, row: fn(&Constraint) -> &LinearCombination| {
+      let mut out = MATRIX_DOMAIN.to_vec();
+      out.push(matrix);
+      for constraint in cs.constraints() {
+        write_row(&mut out, row(constraint));
+      }
+      hash.compute(&out)
+    };
+    Self {
+      num_inputs: cs.inputs().len() as u32,
+      num_witnesses: cs.witnesses().len() as u32,
+      num_constraints: cs.num_constraints() as u32,
+      matrix_commitments: [commit(0, |c| &c.a), commit(1, |c| &c.b), commit(2, |c| &c.c)],
+    }
+  }
+
+  // Whether this key is the index of the given constraint system
+  pub fn matches(&self, cs: &ConstraintSystem) -> bool {
+    *self == Self::index(cs)
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![VERIFYING_KEY_VERSION];
+    out.extend_from_slice(&self.num_inputs.to_be_bytes());
+    out.extend_from_slice(&self.num_witnesses.to_be_bytes());
+    out.extend_from_slice(&self.num_constraints.to_be_bytes());
+    for commitment in &self.matrix_commitments {
+      Encoding::write_bytes(&mut out, commitment);
+    }
+    out
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    if *bytes.first()? != VERIFYING_KEY_VERSION {
+      return None;
+    }
+    let mut offset = 1;
+    let num_inputs = read_u32(bytes, &mut offset)?;
+    let num_witnesses = read_u32(bytes, &mut offset)?;
+    let num_constraints = read_u32(bytes, &mut offset)?;
+    let mut read_commitment = || Encoding::read_bytes(bytes, &mut offset).map(|commitment| commitment.to_vec());
+    let matrix_commitments = [read_commitment()?, read_commitment()?, read_commitment()?];
+    if offset != bytes.len() {
+      return None;
+    }
+    Some(Self {
+      num_inputs,
+      num_witnesses,
+      num_constraints,
+      matrix_commitments,
+    })
+  }
+
+  // Digest of the encoding, for pinning a distributed key
+  pub fn content_hash(&self) -> Vec<u8> {
+    Hash::new(HashFunction::Sha3_256).compute(&[VERIFYING_KEY_DOMAIN, &self.to_bytes()].concat())
+  }
+}
+
+// Constraint matrices of a circuit together with their verifying key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProverKey {
+  pub verifying_key: VerifyingKey,
+  pub constraints: Vec<Constraint>, // Rows of A, B and C, terms normalized
+}
+
+impl ProverKey {
+  pub fn index(cs: &ConstraintSystem) -> Self {
+    let normalize = |row: &LinearCombination| LinearCombination { terms: row.normalized() };
+    Self {
+      verifying_key: VerifyingKey::index(cs),
+      constraints: cs
+        .constraints()
+        .iter()
+        .map(|constraint| Constraint {
+          a: normalize(&constraint.a),
+          b: normalize(&constraint.b),
+          c: normalize(&constraint.c),
+        })
+        .collect(),
+    }
+  }
+
+  // Whether the constraint system has this shape, compared row by row without hashing
+  pub fn matches(&self, cs: &ConstraintSystem) -> bool {
+    let key = &self.verifying_key;
+    let dimensions = (key.num_inputs as usize, key.num_witnesses as usize, key.num_constraints as usize);
+    dimensions == (cs.inputs().len(), cs.witnesses().len(), cs.num_constraints())
+      && self.constraints.len() == cs.num_constraints()
+      && cs.constraints().iter().zip(&self.constraints).all(|(row, stored)| {
+        row.a.normalized() == stored.a.terms && row.b.normalized() == stored.b.terms && row.c.normalized() == stored.c.terms
+      })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::circuit::witness::{KeyRelation, WitnessBuilder};
+  use crate::signature::config::LoquatConfig;
+  use crate::signature::public_indices::IndexedPublicKey;
+
+  const SK: u128 = 0x7f3a_91c2_0d5e_b864_2c17_e9a0_5b3d_f481;
+
+  #[test]
+  fn test_verifying_key_roundtrip() {
+    let key = IndexedPublicKey::new(SK, &[3u8; 32], 16, &LoquatConfig::default());
+    let cs = WitnessBuilder::<KeyRelation>::new(&key).constraint_system(&SK);
+    let vk = VerifyingKey::index(&cs);
+    assert_eq!((vk.num_inputs, vk.num_constraints), (16, 64));
+
+    let bytes = vk.to_bytes();
+    let decoded = VerifyingKey::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, vk);
+    assert_eq!(decoded.content_hash(), vk.content_hash());
+    assert!(VerifyingKey::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_none());
+    assert!(VerifyingKey::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+  }
+
+  #[test]
+  fn test_index_depends_on_shape_only() {
+    let key = IndexedPublicKey::new(SK, &[3u8; 32], 16, &LoquatConfig::default());
+    let builder = WitnessBuilder::<KeyRelation>::new(&key);
+    let vk = VerifyingKey::index(&builder.constraint_system(&SK));
+    // Same circuit with another witness: same key
+    assert!(vk.matches(&builder.constraint_system(&(SK + 1))));
+
+    // Other public indices change the matrices and the pinned hash
+    let other = IndexedPublicKey::new(SK, &[4u8; 32], 16, &LoquatConfig::default());
+    let other_vk = VerifyingKey::index(&WitnessBuilder::<KeyRelation>::new(&other).constraint_system(&SK));
+    assert_ne!(other_vk.matrix_commitments, vk.matrix_commitments);
+    assert_ne!(other_vk.content_hash(), vk.content_hash());
+  }
+
+  #[test]
+  fn test_prover_key_matches_shape() {
+    let key = IndexedPublicKey::new(SK, &[3u8; 32], 16, &LoquatConfig::default());
+    let builder = WitnessBuilder::<KeyRelation>::new(&key);
+    let prover_key = ProverKey::index(&builder.constraint_system(&SK));
+    assert_eq!(prover_key.verifying_key, VerifyingKey::index(&builder.constraint_system(&SK)));
+    assert!(prover_key.matches(&builder.constraint_system(&(SK + 1))));
+
+    let other = IndexedPublicKey::new(SK, &[4u8; 32], 16, &LoquatConfig::default());
+    assert!(!prover_key.matches(&WitnessBuilder::<KeyRelation>::new(&other).constraint_system(&SK)));
+  }
+}
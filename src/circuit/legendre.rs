@@ -0,0 +1,94 @@
+// In-circuit Legendre PRF
+// Loquat's core relation is that bit i of the public key is the Legendre PRF of the secret key
+// at the public index I_i: 0 when K + I_i is a square, 1 when it is not. Since p = 3 mod 4, -1
+// is a non-square, so a non-zero a is a square exactly when -a is not; the gadget takes a
+// witness s with s^2 = a * (1 - 2 * bit), which exists only for the correct bit, at 4
+// constraints instead of the ~250 of Euler's criterion.
+// For a = 0 both bits admit s = 0, so a prover could claim either; the native PRF rejects
+// K + I_i = 0, and the gadget does the same by requiring an inverse of a.
+
+use crate::circuit::r1cs::{ConstraintSystem, LinearCombination, Variable};
+use crate::compact::field;
+use crate::signature::public_indices::IndexedPublicKey;
+
+// Square root exponent (p + 1) / 4, valid for squares since p = 3 mod 4
+const SQRT_EXP: u128 = (field::P + 1) / 4;
+
+// Enforces bit = L(key + index), with key + index non-zero
+pub fn enforce_legendre_prf(cs: &mut ConstraintSystem, key: &LinearCombination, index: u128, bit: Variable) {
+  let value = key.clone().plus(Variable::One, index);
+  let a = cs.evaluate(&value);
+  cs.enforce_boolean(bit);
+
+  // a * a_inv = 1
+  let inverse = cs.alloc_witness(if a == 0 { 0 } else { field::pow(a, field::P - 2) });
+  cs.enforce(value.clone(), inverse.into(), LinearCombination::constant(1));
+
+  // s^2 = a - 2 * a * bit
+  let product = cs.mul(value.clone(), bit.into());
+  let target = value.minus(product, 2);
+  let root = cs.alloc_witness(field::pow(cs.evaluate(&target), SQRT_EXP));
+  cs.enforce(root.into(), root.into(), target);
+}
+
+// Allocates the PRF output bit as a witness and constrains it
+pub fn legendre_prf_gadget(cs: &mut ConstraintSystem, key: &LinearCombination, index: u128) -> Variable {
+  let a = field::add(cs.evaluate(key), field::reduce(index));
+  let bit = cs.alloc_witness((field::legendre_symbol(a) == -1) as u128);
+  enforce_legendre_prf(cs, key, index, bit);
+  bit
+}
+
+// Enforces that the PRF symbols of an indexed public key, allocated as public inputs, are
+// L_K(i) at its public indices
+pub fn enforce_indexed_public_key(cs: &mut ConstraintSystem, key: &LinearCombination, public_key: &IndexedPublicKey) {
+  for (position, index) in public_key.indices.indices().iter().enumerate() {
+    let symbol = public_key.symbols.get(position / 8).map_or(0, |byte| (byte >> (7 - position % 8)) & 1);
+    let bit = cs.alloc_input(symbol as u128);
+    enforce_legendre_prf(cs, key, *index, bit);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::crypto::legendre_prf::LegendrePRF;
+
+  const KEY: u128 = 0x1d3c_5a7f_9b2e_4c61_8f0a_3b5d_7e9c_1a2b;
+
+  #[test]
+  fn test_gadget_matches_prf() {
+    let prf = LegendrePRF::with_key(KEY);
+    let mut cs = ConstraintSystem::new();
+    let key = cs.alloc_witness(KEY);
+    for index in 1..40u128 {
+      let bit = legendre_prf_gadget(&mut cs, &key.into(), index);
+      assert_eq!(cs.value(bit), prf.evaluate(index) as u128);
+    }
+    assert!(cs.is_satisfied());
+    assert_eq!(cs.num_constraints(), 4 * 39);
+  }
+
+  #[test]
+  fn test_wrong_bit_rejected() {
+    let prf = LegendrePRF::with_key(KEY);
+    for claimed in 0..2u128 {
+      let mut cs = ConstraintSystem::new();
+      let key = cs.alloc_witness(KEY);
+      let bit = cs.alloc_input(claimed);
+      enforce_legendre_prf(&mut cs, &key.into(), 7, bit);
+      assert_eq!(cs.is_satisfied(), claimed == prf.evaluate(7) as u128);
+    }
+  }
+
+  #[test]
+  fn test_zero_rejected_for_either_bit() {
+    for claimed in 0..2u128 {
+      let mut cs = ConstraintSystem::new();
+      let key = cs.alloc_witness(field::P - 5);
+      let bit = cs.alloc_input(claimed);
+      enforce_legendre_prf(&mut cs, &key.into(), 5, bit);
+      assert!(!cs.is_satisfied());
+    }
+  }
+}
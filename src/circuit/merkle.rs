@@ -0,0 +1,224 @@
+// In-circuit Merkle authentication paths over the Griffin compression
+// The byte-oriented MerkleTree hashes variable-length big-endian encodings, which has no
+// cheap arithmetic description. GriffinMerkleTree instead works on field elements with
+// Hash::griffin_compress, whose permutation is made of power maps and additions, so the same
+// tree can be recomputed inside a ConstraintSystem. Ring signatures (membership of a key),
+// selective disclosure (membership of an attribute) and revocation non-membership (the
+// list node around a gap in an indexed tree) all reduce to enforce_merkle_path.
+// Each path step carries the sibling and a bit that is true when the sibling is the left
+// input of the compression.
+
+use crate::circuit::r1cs::{ConstraintSystem, LinearCombination, Variable};
+use crate::compact::field;
+use crate::crypto::hash_functions::{Hash, GRIFFIN_INV_SBOX_EXP, GRIFFIN_ROUNDS, GRIFFIN_SBOX_EXP, GRIFFIN_WIDTH};
+
+// One step of an authentication path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStep {
+  pub sibling: u128,
+  pub sibling_is_left: bool,
+}
+
+// Binary Merkle tree over field elements, padded with zero leaves to a power of two
+#[derive(Debug, Clone)]
+pub struct GriffinMerkleTree {
+  levels: Vec<Vec<u128>>, // levels[0] are the leaves, the last level is the root
+}
+
+impl GriffinMerkleTree {
+  pub fn new(leaves: &[u128]) -> Self {
+    let mut level: Vec<u128> = leaves.iter().map(|leaf| field::reduce(*leaf)).collect();
+    level.resize(leaves.len().max(1).next_power_of_two(), 0);
+    let mut levels = vec![level];
+    while levels.last().unwrap().len() > 1 {
+      let next = levels.last().unwrap().chunks(2).map(|pair| Hash::griffin_compress(pair[0], pair[1])).collect();
+      levels.push(next);
+    }
+    Self { levels }
+  }
+
+  pub fn root(&self) -> u128 {
+    self.levels.last().unwrap()[0]
+  }
+
+  pub fn depth(&self) -> usize {
+    self.levels.len() - 1
+  }
+
+  // Replaces one leaf and rehashes its path to the root; false if the index is outside the tree
+  pub fn set_leaf(&mut self, mut index: usize, leaf: u128) -> bool {
+    if index >= self.levels[0].len() {
+      return false;
+    }
+    self.levels[0][index] = field::reduce(leaf);
+    for level in 1..self.levels.len() {
+      index /= 2;
+      self.levels[level][index] = Hash::griffin_compress(self.levels[level - 1][2 * index], self.levels[level - 1][2 * index + 1]);
+    }
+    true
+  }
+
+  pub fn path(&self, mut index: usize) -> Option<Vec<PathStep>> {
+    if index >= self.levels[0].len() {
+      return None;
+    }
+    let mut path = Vec::with_capacity(self.depth());
+    for level in &self.levels[..self.depth()] {
+      path.push(PathStep {
+        sibling: level[index ^ 1],
+        sibling_is_left: index % 2 == 1,
+      });
+      index /= 2;
+    }
+    Some(path)
+  }
+
+  // Root obtained by hashing the leaf up the path
+  pub fn root_from_path(leaf: u128, path: &[PathStep]) -> u128 {
+    path.iter().fold(field::reduce(leaf), |current, step| {
+      if step.sibling_is_left {
+        Hash::griffin_compress(step.sibling, current)
+      } else {
+        Hash::griffin_compress(current, step.sibling)
+      }
+    })
+  }
+}
+
+// x^exponent by square-and-multiply from the most significant bit
+fn pow_gadget(cs: &mut ConstraintSystem, x: &LinearCombination, exponent: u128) -> LinearCombination {
+  if exponent == 0 {
+    return LinearCombination::constant(1);
+  }
+  let mut result = x.clone();
+  for bit in (0..127 - exponent.leading_zeros()).rev() {
+    result = cs.mul(result.clone(), result).into();
+    if (exponent >> bit) & 1 == 1 {
+      result = cs.mul(result, x.clone()).into();
+    }
+  }
+  result
+}
+
+// Hash::griffin_permutation; the mixing layer is linear and costs no constraints
+pub fn griffin_permutation_gadget(cs: &mut ConstraintSystem, state: [LinearCombination; GRIFFIN_WIDTH]) -> [LinearCombination; GRIFFIN_WIDTH] {
+  let mut state = state;
+  for round in 0..GRIFFIN_ROUNDS {
+    let exponent = if round % 2 == 0 { GRIFFIN_SBOX_EXP } else { GRIFFIN_INV_SBOX_EXP };
+    for value in state.iter_mut() {
+      *value = pow_gadget(cs, value, exponent);
+    }
+    let temp = state.clone();
+    for i in 0..GRIFFIN_WIDTH {
+      state[i] = temp[i].clone().plus_lc(&temp[(i + 1) % GRIFFIN_WIDTH]);
+    }
+  }
+  state
+}
+
+// Hash::griffin_compress
+pub fn griffin_compress_gadget(cs: &mut ConstraintSystem, left: LinearCombination, right: LinearCombination) -> LinearCombination {
+  let [_, second, _] = griffin_permutation_gadget(cs, [left, right, LinearCombination::zero()]);
+  second
+}
+
+// Witness variables of an authentication path
+#[derive(Debug, Clone)]
+pub struct PathVar {
+  pub steps: Vec<(Variable, Variable)>, // (sibling, sibling_is_left)
+}
+
+impl PathVar {
+  // Allocates the path as witnesses, constraining the direction bits to be boolean
+  pub fn alloc(cs: &mut ConstraintSystem, path: &[PathStep]) -> Self {
+    let steps = path
+      .iter()
+      .map(|step| {
+        let sibling = cs.alloc_witness(step.sibling);
+        let bit = cs.alloc_witness(step.sibling_is_left as u128);
+        cs.enforce_boolean(bit);
+        (sibling, bit)
+      })
+      .collect();
+    Self { steps }
+  }
+
+  // Leaf position encoded by the direction bits: bit i is set when the sibling at level i is
+  // on the left
+  pub fn position(&self) -> LinearCombination {
+    self.steps.iter().enumerate().fold(LinearCombination::zero(), |acc, (level, (_, bit))| acc.plus(*bit, 1 << level))
+  }
+}
+
+// Recomputes the root from a leaf and a path inside the circuit
+pub fn merkle_root_gadget(cs: &mut ConstraintSystem, leaf: LinearCombination, path: &PathVar) -> LinearCombination {
+  let mut current = leaf;
+  for &(sibling, sibling_is_left) in &path.steps {
+    // t = b * (sibling - current); left = current + t, right = sibling - t
+    let sibling_lc = LinearCombination::from(sibling);
+    let t = LinearCombination::from(cs.mul(sibling_is_left.into(), sibling_lc.clone().minus_lc(&current)));
+    let left = current.plus_lc(&t);
+    let right = sibling_lc.minus_lc(&t);
+    current = griffin_compress_gadget(cs, left, right);
+  }
+  current
+}
+
+// Enforces that leaf is authenticated by path under root
+pub fn enforce_merkle_path(cs: &mut ConstraintSystem, leaf: LinearCombination, path: &PathVar, root: LinearCombination) {
+  let computed = merkle_root_gadget(cs, leaf, path);
+  cs.enforce_equal(computed, root);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_gadget_matches_native_compression() {
+    let mut cs = ConstraintSystem::new();
+    let left = cs.alloc_witness(12345);
+    let right = cs.alloc_witness(field::P - 7);
+    let digest = griffin_compress_gadget(&mut cs, left.into(), right.into());
+    assert_eq!(cs.evaluate(&digest), Hash::griffin_compress(12345, field::P - 7));
+    assert!(cs.is_satisfied());
+    assert_ne!(Hash::griffin_compress(3, 4), Hash::griffin_compress(4, 3));
+  }
+
+  #[test]
+  fn test_merkle_path_in_circuit() {
+    let leaves: Vec<u128> = (1..=5).map(|i| i * 1_000_003).collect();
+    let tree = GriffinMerkleTree::new(&leaves);
+    let path = tree.path(3).unwrap();
+    assert_eq!(GriffinMerkleTree::root_from_path(leaves[3], &path), tree.root());
+
+    let mut cs = ConstraintSystem::new();
+    let root = cs.alloc_input(tree.root());
+    let leaf = cs.alloc_witness(leaves[3]);
+    let path_var = PathVar::alloc(&mut cs, &path);
+    enforce_merkle_path(&mut cs, leaf.into(), &path_var, root.into());
+    assert!(cs.is_satisfied());
+  }
+
+  #[test]
+  fn test_wrong_leaf_or_direction_rejected() {
+    let leaves: Vec<u128> = (1..=4).collect();
+    let tree = GriffinMerkleTree::new(&leaves);
+    let mut path = tree.path(2).unwrap();
+
+    let mut cs = ConstraintSystem::new();
+    let root = cs.alloc_input(tree.root());
+    let leaf = cs.alloc_witness(99);
+    let path_var = PathVar::alloc(&mut cs, &path);
+    enforce_merkle_path(&mut cs, leaf.into(), &path_var, root.into());
+    assert!(!cs.is_satisfied());
+
+    path[0].sibling_is_left = !path[0].sibling_is_left;
+    let mut cs = ConstraintSystem::new();
+    let root = cs.alloc_input(tree.root());
+    let leaf = cs.alloc_witness(leaves[2]);
+    let path_var = PathVar::alloc(&mut cs, &path);
+    enforce_merkle_path(&mut cs, leaf.into(), &path_var, root.into());
+    assert!(!cs.is_satisfied());
+  }
+}
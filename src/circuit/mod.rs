@@ -0,0 +1,29 @@
+//! # Circuit Module
+//!
+//! This module provides rank-1 constraint systems over the Loquat field and gadgets that
+//! re-express native computations as constraints, for use by SNARK-based proofs.
+//!
+//! ## Module Structure
+//! - `r1cs`: Variables, linear combinations and a constraint system with assignment checking
+//! - `batch`: A single relation over a list of signature and non-revocation statements
+//! - `gadgets`: Bit decomposition, range checks, comparisons, equality and selection
+//! - `indexer`: Verifying keys committing to a constraint system, with content hashes
+//! - `legendre`: Legendre PRF relation between a committed key, a public index and a bit
+//! - `merkle`: Griffin permutation gadget and in-circuit Merkle authentication paths
+//! - `prover_cache`: Prover keys, NTT twiddles and round constants saved and loaded with an integrity digest
+//! - `revocation`: Indexed revocation trees, registry deltas and in-circuit non-membership proofs
+//! - `ring`: Ring membership and a signature with the signer's position kept in the witness
+//! - `signature`: Knowledge of a signature under an indexed public key, with sigma kept private
+//! - `witness`: Circuit synthesis and `WitnessBuilder` producing assignments without a prover
+
+pub mod batch;
+pub mod gadgets;
+pub mod indexer;
+pub mod legendre;
+pub mod merkle;
+pub mod prover_cache;
+pub mod r1cs;
+pub mod revocation;
+pub mod ring;
+pub mod signature;
+pub mod witness;
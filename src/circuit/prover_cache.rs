@@ -0,0 +1,278 @@
+// Prover precomputation saved across processes
+// A serverless issuer starts cold on every invocation and would otherwise redo the same
+// precomputation before its first proof: synthesizing and indexing the shape of each circuit
+// it proves (ProverKey), the twiddle factors of its NTT sizes and, with `poseidon`, the
+// Poseidon2 round constants. ProverCache collects them once, is written to any io::Write with
+// save and read back with load at startup:
+//   codec envelope (utils::codec, type "loquat-vc/prover-cache") | SHA3-256 digest (32 bytes)
+// where the digest is over CACHE_DOMAIN and the envelope. A self-consistent digest only shows
+// the cache was not corrupted or truncated, so load also takes the digest the deployment
+// pinned when it built the cache (digest()), as it pins verifying keys, and rejects any other
+// cache before decoding anything. Decoded twiddle tables are then checked element by element
+// against the powers of their domain's root of unity, and warm installs round constants only
+// if they equal this build's own derivation.
+
+use crate::circuit::indexer::{ProverKey, VerifyingKey};
+use crate::crypto::goldilocks::{self, Goldilocks, TWO_ADICITY};
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::utils::codec::{self, CodecError, CodecLimits, Encodable};
+use crate::utils::constant_time;
+use crate::utils::wire::DIGEST_BYTES;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+const CACHE_DOMAIN: &[u8] = b"loquat-vc-prover-cache";
+
+// Largest cache load accepts; the twiddles of a 2^20-point NTT alone take 4 MiB
+pub const MAX_CACHE_BYTES: usize = 1 << 28;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+  Io(std::io::ErrorKind),
+  IntegrityMismatch, // The digest does not match the contents, or there is no digest
+  UnexpectedDigest, // An intact cache, but not the one whose digest was pinned
+  Codec(CodecError),
+  Invalid, // Decodes, but holds a twiddle table that is not the one of its domain
+}
+
+// As encoded; tables are checked when converted into a ProverCache
+#[derive(Serialize, Deserialize)]
+struct Stored {
+  prover_keys: Vec<ProverKey>,
+  twiddles: Vec<(u32, Vec<u64>)>, // (log size, canonical values)
+  poseidon2: Option<(Vec<u128>, Vec<u128>)>, // Width 3, width 8; kept by builds without `poseidon`
+}
+
+impl Encodable for Stored {
+  const TYPE_TAG: &'static str = "loquat-vc/prover-cache";
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverCache {
+  prover_keys: Vec<ProverKey>,
+  twiddles: Vec<(u32, Vec<Goldilocks>)>,
+  poseidon2: Option<(Vec<u128>, Vec<u128>)>,
+}
+
+impl ProverCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // Adds a prover key, replacing one with the same verifying key
+  pub fn add_prover_key(&mut self, key: ProverKey) {
+    self.prover_keys.retain(|stored| stored.verifying_key != key.verifying_key);
+    self.prover_keys.push(key);
+  }
+
+  pub fn prover_key(&self, verifying_key: &VerifyingKey) -> Option<&ProverKey> {
+    self.prover_keys.iter().find(|key| key.verifying_key == *verifying_key)
+  }
+
+  pub fn prover_keys(&self) -> &[ProverKey] {
+    &self.prover_keys
+  }
+
+  // Computes and keeps the twiddles of a 2^log_size-point NTT; false beyond the two-adicity
+  pub fn add_twiddles(&mut self, log_size: u32) -> bool {
+    if self.twiddles(log_size).is_some() {
+      return true;
+    }
+    match goldilocks::twiddles(log_size) {
+      Some(table) => {
+        self.twiddles.push((log_size, table));
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn twiddles(&self, log_size: u32) -> Option<&[Goldilocks]> {
+    self.twiddles.iter().find(|(size, _)| *size == log_size).map(|(_, table)| table.as_slice())
+  }
+
+  // goldilocks::ntt, from the cached twiddles when the cache has them for this size
+  pub fn ntt(&self, values: &mut [Goldilocks]) -> bool {
+    match self.twiddles(values.len().trailing_zeros()) {
+      Some(table) if values.len().is_power_of_two() => goldilocks::ntt_with_twiddles(values, table),
+      _ => goldilocks::ntt(values),
+    }
+  }
+
+  // Keeps the Poseidon2 round constants of this build
+  #[cfg(feature = "poseidon")]
+  pub fn add_poseidon2_constants(&mut self) {
+    self.poseidon2 = Some(crate::crypto::poseidon2::round_constants());
+  }
+
+  // Installs what the process can take from the cache ahead of first use, i.e. the Poseidon2
+  // round constants; false if there were none, they differ from this build's or they had been
+  // derived already
+  pub fn warm(&self) -> bool {
+    #[cfg(feature = "poseidon")]
+    if let Some((width_3, width_8)) = &self.poseidon2 {
+      return crate::crypto::poseidon2::preload_round_constants(width_3, width_8);
+    }
+    false
+  }
+
+  fn envelope(&self) -> Result<Vec<u8>, CacheError> {
+    let stored = Stored {
+      prover_keys: self.prover_keys.clone(),
+      twiddles: self.twiddles.iter().map(|(log_size, table)| (*log_size, table.iter().map(Goldilocks::value).collect())).collect(),
+      poseidon2: self.poseidon2.clone(),
+    };
+    codec::encode_with_limits(&stored, &CodecLimits { max_bytes: MAX_CACHE_BYTES - DIGEST_BYTES }).map_err(CacheError::Codec)
+  }
+
+  fn integrity_digest(envelope: &[u8]) -> Vec<u8> {
+    Hash::new(HashFunction::Sha3_256).compute(&[CACHE_DOMAIN, envelope].concat())
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>, CacheError> {
+    let mut out = self.envelope()?;
+    let digest = Self::integrity_digest(&out);
+    out.extend_from_slice(&digest);
+    Ok(out)
+  }
+
+  // Integrity digest of the encoding, for pinning a distributed cache
+  pub fn digest(&self) -> Result<Vec<u8>, CacheError> {
+    self.envelope().map(|envelope| Self::integrity_digest(&envelope))
+  }
+
+  // Decodes a cache whose digest is expected_digest, as digest() returned it when it was built
+  pub fn from_bytes(bytes: &[u8], expected_digest: &[u8]) -> Result<Self, CacheError> {
+    if bytes.len() > MAX_CACHE_BYTES {
+      return Err(CacheError::Codec(CodecError::TooLarge));
+    }
+    let split = bytes.len().checked_sub(DIGEST_BYTES).ok_or(CacheError::IntegrityMismatch)?;
+    let (envelope, digest) = bytes.split_at(split);
+    if !constant_time::bytes_eq(&Self::integrity_digest(envelope), digest) {
+      return Err(CacheError::IntegrityMismatch);
+    }
+    if !constant_time::bytes_eq(digest, expected_digest) {
+      return Err(CacheError::UnexpectedDigest);
+    }
+    let stored: Stored = codec::decode_with_limits(envelope, &CodecLimits { max_bytes: MAX_CACHE_BYTES }).map_err(CacheError::Codec)?;
+
+    let mut twiddles = Vec::with_capacity(stored.twiddles.len());
+    for (log_size, table) in stored.twiddles {
+      let valid_size = log_size <= TWO_ADICITY && table.len() == (1usize << log_size) / 2;
+      if !valid_size || twiddles.iter().any(|(size, _)| *size == log_size) {
+        return Err(CacheError::Invalid);
+      }
+      twiddles.push((log_size, domain_twiddles(log_size, table).ok_or(CacheError::Invalid)?));
+    }
+    Ok(Self {
+      prover_keys: stored.prover_keys,
+      twiddles,
+      poseidon2: stored.poseidon2,
+    })
+  }
+
+  pub fn save<W: Write>(&self, out: &mut W) -> Result<(), CacheError> {
+    out.write_all(&self.to_bytes()?).map_err(|e| CacheError::Io(e.kind()))
+  }
+
+  // Reads a cache written by save to the end of the input; from_bytes on what was read
+  pub fn load<R: Read>(input: R, expected_digest: &[u8]) -> Result<Self, CacheError> {
+    let mut bytes = vec![];
+    input.take(MAX_CACHE_BYTES as u64 + 1).read_to_end(&mut bytes).map_err(|e| CacheError::Io(e.kind()))?;
+    Self::from_bytes(&bytes, expected_digest)
+  }
+}
+
+// The stored table if it is w^0, w^1, ... for the 2^log_size-th root of unity w, as
+// goldilocks::twiddles computes it
+fn domain_twiddles(log_size: u32, table: Vec<u64>) -> Option<Vec<Goldilocks>> {
+  let root = Goldilocks::root_of_unity(log_size)?;
+  let mut expected = Goldilocks::ONE;
+  table
+    .into_iter()
+    .map(|value| {
+      let twiddle = (value == expected.value()).then_some(expected);
+      expected = expected * root;
+      twiddle
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::circuit::witness::{KeyRelation, WitnessBuilder};
+  use crate::signature::config::LoquatConfig;
+  use crate::signature::public_indices::IndexedPublicKey;
+
+  const SK: u128 = 0x1f2e_3d4c_5b6a_7988_a7b6_c5d4_e3f2_0112;
+
+  fn cache() -> ProverCache {
+    let key = IndexedPublicKey::new(SK, &[9u8; 32], 16, &LoquatConfig::default());
+    let mut cache = ProverCache::new();
+    cache.add_prover_key(ProverKey::index(&WitnessBuilder::<KeyRelation>::new(&key).constraint_system(&SK)));
+    assert!(cache.add_twiddles(4));
+    assert!(cache.add_twiddles(10));
+    assert!(!cache.add_twiddles(TWO_ADICITY + 1));
+    cache
+  }
+
+  #[test]
+  fn test_cache_round_trip() {
+    let cache = cache();
+    let mut saved = vec![];
+    cache.save(&mut saved).unwrap();
+    let loaded = ProverCache::load(saved.as_slice(), &cache.digest().unwrap()).unwrap();
+    assert_eq!(loaded, cache);
+    assert_eq!(loaded.digest(), cache.digest());
+    assert_eq!(&saved[saved.len() - DIGEST_BYTES..], cache.digest().unwrap().as_slice());
+
+    let verifying_key = &cache.prover_keys()[0].verifying_key;
+    assert_eq!(loaded.prover_key(verifying_key), Some(&cache.prover_keys()[0]));
+
+    // Cached and recomputed transforms agree, with or without a table for the size
+    let coeffs: Vec<Goldilocks> = (0..1024u64).map(|i| Goldilocks::new(i * 7 + 1)).collect();
+    for len in [16, 1024, 256] {
+      let (mut cached, mut direct) = (coeffs[..len].to_vec(), coeffs[..len].to_vec());
+      assert!(loaded.ntt(&mut cached) && goldilocks::ntt(&mut direct));
+      assert_eq!(cached, direct);
+    }
+  }
+
+  #[test]
+  fn test_corrupted_caches_rejected() {
+    let bytes = cache().to_bytes().unwrap();
+    let pinned = cache().digest().unwrap();
+    let mut flipped = bytes.clone();
+    flipped[bytes.len() / 2] ^= 1;
+    assert_eq!(ProverCache::from_bytes(&flipped, &pinned), Err(CacheError::IntegrityMismatch));
+    assert_eq!(ProverCache::from_bytes(&bytes[..bytes.len() - 1], &pinned), Err(CacheError::IntegrityMismatch));
+    assert_eq!(ProverCache::from_bytes(&bytes[..16], &pinned), Err(CacheError::IntegrityMismatch));
+
+    // A rewritten cache carries a valid digest of its own, but not the pinned one
+    let mut rewritten = cache();
+    rewritten.twiddles.pop();
+    let rewritten = rewritten.to_bytes().unwrap();
+    assert_eq!(ProverCache::from_bytes(&rewritten, &pinned), Err(CacheError::UnexpectedDigest));
+
+    // Tables of the wrong size or with values off their domain are refused even when pinned
+    let mut short = cache();
+    short.twiddles[0].1.pop();
+    assert_eq!(ProverCache::from_bytes(&short.to_bytes().unwrap(), &short.digest().unwrap()), Err(CacheError::Invalid));
+    let mut shifted = cache();
+    shifted.twiddles[1].1[3] = shifted.twiddles[1].1[3] + Goldilocks::ONE;
+    assert_eq!(ProverCache::from_bytes(&shifted.to_bytes().unwrap(), &shifted.digest().unwrap()), Err(CacheError::Invalid));
+  }
+
+  #[cfg(feature = "poseidon")]
+  #[test]
+  fn test_poseidon2_constants_cached() {
+    let mut cache = cache();
+    assert!(!cache.clone().warm());
+    cache.add_poseidon2_constants();
+    let loaded = ProverCache::from_bytes(&cache.to_bytes().unwrap(), &cache.digest().unwrap()).unwrap();
+    assert_eq!(loaded.poseidon2, Some(crate::crypto::poseidon2::round_constants()));
+    // This process derived them already
+    assert!(!loaded.warm());
+  }
+}
@@ -0,0 +1,252 @@
+// Rank-1 constraint systems over the Loquat field 2^127 - 1
+// A constraint <a, z> * <b, z> = <c, z> relates linear combinations of the assignment
+// z = (1, public inputs, witnesses). Variables are allocated together with their values, so a
+// gadget both adds its constraints and computes the values that satisfy them; is_satisfied
+// checks the assignment without running a prover.
+
+use crate::compact::field;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A variable of the assignment; One is the constant 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Variable {
+  One,
+  Input(usize),
+  Witness(usize),
+}
+
+// Sum of coefficient * variable terms
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearCombination {
+  pub terms: Vec<(Variable, u128)>,
+}
+
+impl LinearCombination {
+  pub fn zero() -> Self {
+    Self::default()
+  }
+
+  pub fn constant(value: u128) -> Self {
+    Self::zero().plus(Variable::One, value)
+  }
+
+  // Adds coefficient * variable
+  pub fn plus(mut self, variable: Variable, coefficient: u128) -> Self {
+    self.terms.push((variable, field::reduce(coefficient)));
+    self
+  }
+
+  // Subtracts coefficient * variable
+  pub fn minus(self, variable: Variable, coefficient: u128) -> Self {
+    self.plus(variable, field::sub(0, field::reduce(coefficient)))
+  }
+
+  pub fn plus_lc(mut self, other: &LinearCombination) -> Self {
+    self.terms.extend_from_slice(&other.terms);
+    self
+  }
+
+  pub fn minus_lc(self, other: &LinearCombination) -> Self {
+    self.plus_lc(&other.scaled(field::P - 1))
+  }
+
+  // Terms merged per variable in variable order, without zero coefficients
+  pub fn normalized(&self) -> Vec<(Variable, u128)> {
+    let mut terms = BTreeMap::new();
+    for (variable, coefficient) in &self.terms {
+      let entry = terms.entry(*variable).or_insert(0);
+      *entry = field::add(*entry, *coefficient);
+    }
+    terms.into_iter().filter(|(_, coefficient)| *coefficient != 0).collect()
+  }
+
+  pub fn scaled(&self, factor: u128) -> Self {
+    Self {
+      terms: self.terms.iter().map(|(variable, coefficient)| (*variable, field::mul(*coefficient, factor))).collect(),
+    }
+  }
+}
+
+impl From<Variable> for LinearCombination {
+  fn from(variable: Variable) -> Self {
+    Self::zero().plus(variable, 1)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Constraint {
+  pub a: LinearCombination,
+  pub b: LinearCombination,
+  pub c: LinearCombination,
+}
+
+// Values of the public inputs and witnesses, in allocation order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assignment {
+  pub inputs: Vec<u128>,
+  pub witnesses: Vec<u128>,
+}
+
+impl Assignment {
+  pub fn value(&self, variable: Variable) -> Option<u128> {
+    match variable {
+      Variable::One => Some(1),
+      Variable::Input(i) => self.inputs.get(i).copied(),
+      Variable::Witness(i) => self.witnesses.get(i).copied(),
+    }
+  }
+
+  pub fn evaluate(&self, lc: &LinearCombination) -> Option<u128> {
+    lc.terms.iter().try_fold(0, |acc, (variable, coefficient)| Some(field::add(acc, field::mul(*coefficient, self.value(*variable)?))))
+  }
+
+  // The full assignment z = (1, inputs, witnesses)
+  pub fn to_vector(&self) -> Vec<u128> {
+    let mut z = Vec::with_capacity(1 + self.inputs.len() + self.witnesses.len());
+    z.push(1);
+    z.extend_from_slice(&self.inputs);
+    z.extend_from_slice(&self.witnesses);
+    z
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintSystem {
+  inputs: Vec<u128>,
+  witnesses: Vec<u128>,
+  constraints: Vec<Constraint>,
+}
+
+impl ConstraintSystem {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // Allocates a public input
+  pub fn alloc_input(&mut self, value: u128) -> Variable {
+    self.inputs.push(field::reduce(value));
+    Variable::Input(self.inputs.len() - 1)
+  }
+
+  // Allocates a private witness
+  pub fn alloc_witness(&mut self, value: u128) -> Variable {
+    self.witnesses.push(field::reduce(value));
+    Variable::Witness(self.witnesses.len() - 1)
+  }
+
+  pub fn value(&self, variable: Variable) -> u128 {
+    match variable {
+      Variable::One => 1,
+      Variable::Input(i) => self.inputs[i],
+      Variable::Witness(i) => self.witnesses[i],
+    }
+  }
+
+  pub fn evaluate(&self, lc: &LinearCombination) -> u128 {
+    lc.terms.iter().fold(0, |acc, (variable, coefficient)| field::add(acc, field::mul(*coefficient, self.value(*variable))))
+  }
+
+  // Adds the constraint a * b = c
+  pub fn enforce(&mut self, a: LinearCombination, b: LinearCombination, c: LinearCombination) {
+    self.constraints.push(Constraint { a, b, c });
+  }
+
+  // Adds a = b
+  pub fn enforce_equal(&mut self, a: LinearCombination, b: LinearCombination) {
+    self.enforce(a, LinearCombination::constant(1), b);
+  }
+
+  // Adds v * (1 - v) = 0
+  pub fn enforce_boolean(&mut self, variable: Variable) {
+    self.enforce(variable.into(), LinearCombination::constant(1).minus(variable, 1), LinearCombination::zero());
+  }
+
+  // Allocates the product of two linear combinations as a witness
+  pub fn mul(&mut self, a: LinearCombination, b: LinearCombination) -> Variable {
+    let product = self.alloc_witness(field::mul(self.evaluate(&a), self.evaluate(&b)));
+    self.enforce(a, b, product.into());
+    product
+  }
+
+  pub fn constraints(&self) -> &[Constraint] {
+    &self.constraints
+  }
+
+  pub fn inputs(&self) -> &[u128] {
+    &self.inputs
+  }
+
+  pub fn witnesses(&self) -> &[u128] {
+    &self.witnesses
+  }
+
+  pub fn num_constraints(&self) -> usize {
+    self.constraints.len()
+  }
+
+  pub fn assignment(&self) -> Assignment {
+    Assignment {
+      inputs: self.inputs.clone(),
+      witnesses: self.witnesses.clone(),
+    }
+  }
+
+  // Checks an externally produced assignment against these constraints; its shape must match
+  // the allocations of this system
+  pub fn is_satisfied_by(&self, assignment: &Assignment) -> bool {
+    if assignment.inputs.len() != self.inputs.len() || assignment.witnesses.len() != self.witnesses.len() {
+      return false;
+    }
+    self.constraints.iter().all(|constraint| {
+      match (assignment.evaluate(&constraint.a), assignment.evaluate(&constraint.b), assignment.evaluate(&constraint.c)) {
+        (Some(a), Some(b), Some(c)) => field::mul(a, b) == c,
+        _ => false,
+      }
+    })
+  }
+
+  // Index of the first constraint the assignment violates
+  pub fn first_unsatisfied(&self) -> Option<usize> {
+    self
+      .constraints
+      .iter()
+      .position(|constraint| field::mul(self.evaluate(&constraint.a), self.evaluate(&constraint.b)) != self.evaluate(&constraint.c))
+  }
+
+  pub fn is_satisfied(&self) -> bool {
+    self.first_unsatisfied().is_none()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_product_constraint() {
+    let mut cs = ConstraintSystem::new();
+    let x = cs.alloc_witness(6);
+    let y = cs.alloc_witness(7);
+    let product = cs.mul(x.into(), y.into());
+    let expected = cs.alloc_input(42);
+    cs.enforce_equal(product.into(), expected.into());
+    let bit = cs.alloc_witness(1);
+    cs.enforce_boolean(bit);
+    assert!(cs.is_satisfied());
+    assert_eq!(cs.num_constraints(), 3);
+
+    // Linear combinations wrap around the field
+    assert_eq!(cs.evaluate(&LinearCombination::constant(3).minus(Variable::One, 5)), field::P - 2);
+  }
+
+  #[test]
+  fn test_unsatisfied_constraint_located() {
+    let mut cs = ConstraintSystem::new();
+    let bit = cs.alloc_witness(2);
+    let x = cs.alloc_witness(3);
+    cs.enforce_equal(x.into(), LinearCombination::constant(3));
+    cs.enforce_boolean(bit);
+    assert_eq!(cs.first_unsatisfied(), Some(1));
+  }
+}
@@ -0,0 +1,430 @@
+// Revocation non-membership in an indexed Griffin Merkle tree
+// The revoked serial numbers form a sorted linked list framed by the sentinels 0 and u64::MAX.
+// Its nodes sit in a tree of fixed depth in insertion order, each leaf committing to one node
+// as griffin_compress(low, next) and unused leaves being zero. A serial is unrevoked exactly
+// when some leaf satisfies low < serial < next, so non-membership is one authenticated leaf
+// and two comparisons. Revoking r rewrites the node before it to (low, r) and appends
+// (r, next): two leaves change and every other node keeps its position. A RevocationDelta
+// lists those writes with their new paths, from which holders refresh their witnesses without
+// the full registry and mirrors replay the revocations. Serials are 64-bit and must lie
+// strictly between the sentinels.
+//
+// Delta encoding: u64 epoch | from root (16 bytes) | root (16 bytes) | depth byte |
+// u32 write count | per write u64 position, u64 low, u64 next and depth path steps, each a
+// side flag (0 or 1) and a 16-byte sibling
+
+use crate::circuit::gadgets::{enforce_less_than, enforce_range};
+use crate::circuit::merkle::{enforce_merkle_path, griffin_compress_gadget, GriffinMerkleTree, PathStep, PathVar};
+use crate::circuit::r1cs::{ConstraintSystem, LinearCombination};
+use crate::compact::field;
+use crate::crypto::hash_functions::Hash;
+use std::collections::BTreeMap;
+
+// Bit width of serial numbers
+pub const SERIAL_BITS: usize = 64;
+
+// Deepest tree a registry or a delta may describe
+pub const MAX_DEPTH: usize = 32;
+
+// Leaf committing to the list node (low, next)
+pub fn leaf_hash(low: u64, next: u64) -> u128 {
+  Hash::griffin_compress(low as u128, next as u128)
+}
+
+// Leaf position encoded by the direction bits of a path
+fn position(path: &[PathStep]) -> usize {
+  path.iter().rev().fold(0, |acc, step| 2 * acc + step.sibling_is_left as usize)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonMembershipWitness {
+  pub serial: u64,
+  pub low: u64,
+  pub high: u64,
+  pub path: Vec<PathStep>, // Authentication path of the leaf (low, high)
+}
+
+impl NonMembershipWitness {
+  // Placeholder of the right shape, used when synthesizing without a witness; never satisfies
+  pub fn empty(depth: usize) -> Self {
+    Self {
+      serial: 0,
+      low: 0,
+      high: 0,
+      path: vec![PathStep { sibling: 0, sibling_is_left: false }; depth],
+    }
+  }
+
+  // Brings the witness to the registry state after delta; false, leaving it unchanged, if it
+  // is not a witness for the delta's starting root, the serial was revoked or the delta does
+  // not lead to its root
+  pub fn update(&mut self, delta: &RevocationDelta) -> bool {
+    if self.path.len() != delta.depth || GriffinMerkleTree::root_from_path(leaf_hash(self.low, self.high), &self.path) != delta.from_root {
+      return false;
+    }
+    let mut updated = self.clone();
+    for write in &delta.writes {
+      if write.path.len() != delta.depth || position(&write.path) != write.position {
+        return false;
+      }
+      let own = position(&updated.path);
+      if write.low < updated.serial && updated.serial < write.high {
+        // The written leaf now brackets the serial
+        updated.low = write.low;
+        updated.high = write.high;
+        updated.path = write.path.clone();
+      } else if write.position == own {
+        updated.low = write.low;
+        updated.high = write.high;
+      } else {
+        // Where the two paths meet, this path's sibling is the written leaf's node one level down
+        let level = (usize::BITS - 1 - (write.position ^ own).leading_zeros()) as usize;
+        updated.path[level].sibling = GriffinMerkleTree::root_from_path(leaf_hash(write.low, write.high), &write.path[..level]);
+      }
+    }
+    if !RevocationTree::verify_non_membership(delta.root, &updated) {
+      return false;
+    }
+    *self = updated;
+    true
+  }
+}
+
+// One leaf as a revocation left it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafWrite {
+  pub position: usize,
+  pub low: u64,
+  pub high: u64,
+  pub path: Vec<PathStep>, // Authentication path of the position once written
+}
+
+// Changes between two consecutive registry epochs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevocationDelta {
+  pub epoch: u64, // Epoch the delta moves the registry to
+  pub from_root: u128,
+  pub root: u128,
+  pub depth: usize,
+  pub writes: Vec<LeafWrite>, // Two per revoked serial, in the order they were made
+}
+
+fn read_u64(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+  let value = u64::from_be_bytes(bytes.get(*offset..*offset + 8)?.try_into().ok()?);
+  *offset += 8;
+  Some(value)
+}
+
+fn read_u128(bytes: &[u8], offset: &mut usize) -> Option<u128> {
+  let value = u128::from_be_bytes(bytes.get(*offset..*offset + 16)?.try_into().ok()?);
+  *offset += 16;
+  Some(value)
+}
+
+impl RevocationDelta {
+  // Serials the delta revokes: the low value of every appended leaf
+  pub fn revoked(&self) -> Vec<u64> {
+    self.writes.iter().skip(1).step_by(2).map(|write| write.low).collect()
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = self.epoch.to_be_bytes().to_vec();
+    out.extend_from_slice(&self.from_root.to_be_bytes());
+    out.extend_from_slice(&self.root.to_be_bytes());
+    out.push(self.depth as u8);
+    out.extend_from_slice(&(self.writes.len() as u32).to_be_bytes());
+    for write in &self.writes {
+      out.extend_from_slice(&(write.position as u64).to_be_bytes());
+      out.extend_from_slice(&write.low.to_be_bytes());
+      out.extend_from_slice(&write.high.to_be_bytes());
+      for step in &write.path {
+        out.push(step.sibling_is_left as u8);
+        out.extend_from_slice(&step.sibling.to_be_bytes());
+      }
+    }
+    out
+  }
+
+  // Parses a delta written by to_bytes; roots and siblings must be below P
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let mut offset = 0;
+    let epoch = read_u64(bytes, &mut offset)?;
+    let from_root = read_u128(bytes, &mut offset)?;
+    let root = read_u128(bytes, &mut offset)?;
+    let depth = *bytes.get(offset)? as usize;
+    let count = u32::from_be_bytes(bytes.get(offset + 1..offset + 5)?.try_into().ok()?) as usize;
+    offset += 5;
+    // Every write takes the same number of bytes, which bounds the count before allocating
+    let write_bytes = 24 + depth * 17;
+    if depth > MAX_DEPTH || from_root >= field::P || root >= field::P || count.checked_mul(write_bytes) != Some(bytes.len() - offset) {
+      return None;
+    }
+
+    let mut writes = Vec::with_capacity(count);
+    for _ in 0..count {
+      let position = read_u64(bytes, &mut offset)?.try_into().ok()?;
+      let low = read_u64(bytes, &mut offset)?;
+      let high = read_u64(bytes, &mut offset)?;
+      let mut path = Vec::with_capacity(depth);
+      for _ in 0..depth {
+        let sibling_is_left = match bytes[offset] {
+          0 => false,
+          1 => true,
+          _ => return None,
+        };
+        offset += 1;
+        let sibling = read_u128(bytes, &mut offset).filter(|sibling| *sibling < field::P)?;
+        path.push(PathStep { sibling, sibling_is_left });
+      }
+      writes.push(LeafWrite { position, low, high, path });
+    }
+    Some(Self { epoch, from_root, root, depth, writes })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RevocationTree {
+  nodes: Vec<(u64, u64)>, // List nodes (low, next); leaf i holds node i
+  positions: BTreeMap<u64, usize>, // Leaf of each node, by its low value
+  tree: GriffinMerkleTree,
+}
+
+impl RevocationTree {
+  // Tree of the smallest depth holding the sentinel node and every revoked serial
+  pub fn new(revoked: &[u64]) -> Self {
+    let mut serials: Vec<u64> = revoked.iter().copied().filter(|serial| *serial != 0 && *serial != u64::MAX).collect();
+    serials.sort_unstable();
+    serials.dedup();
+    let mut tree = Self::with_depth((serials.len() + 1).next_power_of_two().trailing_zeros() as usize);
+    for serial in serials {
+      tree.revoke(serial).expect("Tree holds every serial");
+    }
+    tree
+  }
+
+  // Empty tree with room for 2^depth - 1 revocations
+  pub fn with_depth(depth: usize) -> Self {
+    assert!(depth <= MAX_DEPTH, "Revocation tree deeper than {} levels", MAX_DEPTH);
+    let mut leaves = vec![0; 1 << depth];
+    leaves[0] = leaf_hash(0, u64::MAX);
+    Self {
+      nodes: vec![(0, u64::MAX)],
+      positions: BTreeMap::from([(0, 0)]),
+      tree: GriffinMerkleTree::new(&leaves),
+    }
+  }
+
+  pub fn root(&self) -> u128 {
+    self.tree.root()
+  }
+
+  pub fn depth(&self) -> usize {
+    self.tree.depth()
+  }
+
+  pub fn is_revoked(&self, serial: u64) -> bool {
+    serial == u64::MAX || self.positions.contains_key(&serial)
+  }
+
+  // Revokes a serial and returns the leaves it wrote; None for sentinels, revoked serials and
+  // a full tree
+  pub fn revoke(&mut self, serial: u64) -> Option<Vec<LeafWrite>> {
+    if self.is_revoked(serial) || self.nodes.len() == 1 << self.depth() {
+      return None;
+    }
+    let (&low, &before) = self.positions.range(..serial).next_back()?;
+    let next = self.nodes[before].1;
+    let appended = self.nodes.len();
+    self.nodes.push((serial, next));
+    self.positions.insert(serial, appended);
+
+    let mut writes = Vec::with_capacity(2);
+    for (position, (low, high)) in [(before, (low, serial)), (appended, (serial, next))] {
+      self.nodes[position] = (low, high);
+      self.tree.set_leaf(position, leaf_hash(low, high));
+      writes.push(LeafWrite { position, low, high, path: self.tree.path(position)? });
+    }
+    Some(writes)
+  }
+
+  // The node bracketing an unrevoked serial; None for revoked serials and the sentinels
+  pub fn non_membership(&self, serial: u64) -> Option<NonMembershipWitness> {
+    if self.is_revoked(serial) {
+      return None;
+    }
+    let (_, &position) = self.positions.range(..serial).next_back()?;
+    let (low, high) = self.nodes[position];
+    Some(NonMembershipWitness {
+      serial,
+      low,
+      high,
+      path: self.tree.path(position)?,
+    })
+  }
+
+  // Native counterpart of enforce_non_membership
+  pub fn verify_non_membership(root: u128, witness: &NonMembershipWitness) -> bool {
+    witness.low < witness.serial
+      && witness.serial < witness.high
+      && GriffinMerkleTree::root_from_path(leaf_hash(witness.low, witness.high), &witness.path) == root
+  }
+}
+
+// Issuer-side registry publishing a delta per epoch, or a mirror kept current by applying them
+#[derive(Debug, Clone)]
+pub struct RevocationRegistry {
+  tree: RevocationTree,
+  epoch: u64, // Deltas made or applied since creation
+}
+
+impl RevocationRegistry {
+  pub fn new(depth: usize) -> Self {
+    Self {
+      tree: RevocationTree::with_depth(depth),
+      epoch: 0,
+    }
+  }
+
+  pub fn tree(&self) -> &RevocationTree {
+    &self.tree
+  }
+
+  pub fn epoch(&self) -> u64 {
+    self.epoch
+  }
+
+  pub fn root(&self) -> u128 {
+    self.tree.root()
+  }
+
+  // Revokes serials as one epoch and returns the delta to publish; None, changing nothing, if
+  // a serial is a sentinel or already revoked or the tree would overflow
+  pub fn revoke(&mut self, serials: &[u64]) -> Option<RevocationDelta> {
+    let mut tree = self.tree.clone();
+    let mut writes = vec![];
+    for serial in serials {
+      writes.extend(tree.revoke(*serial)?);
+    }
+    let delta = RevocationDelta {
+      epoch: self.epoch + 1,
+      from_root: self.root(),
+      root: tree.root(),
+      depth: tree.depth(),
+      writes,
+    };
+    self.tree = tree;
+    self.epoch += 1;
+    Some(delta)
+  }
+
+  // Applies the next epoch's delta by replaying its revocations; false, changing nothing,
+  // unless the replay reproduces its writes and root exactly
+  pub fn apply_delta(&mut self, delta: &RevocationDelta) -> bool {
+    if delta.epoch != self.epoch + 1 || delta.from_root != self.root() {
+      return false;
+    }
+    let mut replay = self.clone();
+    match replay.revoke(&delta.revoked()) {
+      Some(replayed) if replayed == *delta => {
+        *self = replay;
+        true
+      }
+      _ => false,
+    }
+  }
+}
+
+// Enforces that serial is not revoked in the tree with the given root
+pub fn enforce_non_membership(cs: &mut ConstraintSystem, serial: &LinearCombination, witness: &NonMembershipWitness, root: &LinearCombination) {
+  let low: LinearCombination = cs.alloc_witness(witness.low as u128).into();
+  let high: LinearCombination = cs.alloc_witness(witness.high as u128).into();
+  for value in [serial, &low, &high] {
+    enforce_range(cs, value, SERIAL_BITS);
+  }
+  enforce_less_than(cs, &low, serial, SERIAL_BITS);
+  enforce_less_than(cs, serial, &high, SERIAL_BITS);
+
+  let path = PathVar::alloc(cs, &witness.path);
+  let leaf = griffin_compress_gadget(cs, low, high);
+  enforce_merkle_path(cs, leaf, &path, root.clone());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn satisfies(root: u128, witness: &NonMembershipWitness) -> bool {
+    let mut cs = ConstraintSystem::new();
+    let root = cs.alloc_input(root);
+    let serial = cs.alloc_witness(witness.serial as u128);
+    enforce_non_membership(&mut cs, &serial.into(), witness, &root.into());
+    cs.is_satisfied()
+  }
+
+  #[test]
+  fn test_non_membership_witnesses() {
+    let tree = RevocationTree::new(&[40, 7, 1_000, 7]);
+    assert_eq!(tree.depth(), 2);
+    assert!(tree.is_revoked(7) && !tree.is_revoked(8));
+    assert!(tree.non_membership(40).is_none());
+    assert!(tree.non_membership(0).is_none());
+
+    for serial in [1, 8, 999, 1_001, u64::MAX - 1] {
+      let witness = tree.non_membership(serial).unwrap();
+      assert!(RevocationTree::verify_non_membership(tree.root(), &witness));
+      assert!(satisfies(tree.root(), &witness));
+    }
+  }
+
+  #[test]
+  fn test_revoked_serial_cannot_reuse_witness() {
+    let tree = RevocationTree::new(&[7, 40, 1_000]);
+    // The node of 20 is (7, 40); claiming it for the revoked 40 fails the comparison
+    let mut witness = tree.non_membership(20).unwrap();
+    witness.serial = 40;
+    assert!(!RevocationTree::verify_non_membership(tree.root(), &witness));
+    assert!(!satisfies(tree.root(), &witness));
+
+    // A node (7, 1_000) that skips a revoked serial is not in the tree
+    let mut skipping = tree.non_membership(20).unwrap();
+    skipping.high = 1_000;
+    skipping.serial = 40;
+    assert!(!RevocationTree::verify_non_membership(tree.root(), &skipping));
+    assert!(!satisfies(tree.root(), &skipping));
+  }
+
+  #[test]
+  fn test_deltas_refresh_witnesses_and_mirrors() {
+    let mut registry = RevocationRegistry::new(3);
+    registry.revoke(&[40, 7]).unwrap();
+    let mut mirror = registry.clone();
+    let mut witnesses: Vec<_> = [3, 20, 45, 50, 100].iter().map(|serial| registry.tree().non_membership(*serial).unwrap()).collect();
+
+    let delta = registry.revoke(&[30, 50, 45]).unwrap();
+    assert_eq!(delta.revoked(), [30, 50, 45]);
+    assert!(registry.revoke(&[60, 7]).is_none());
+    let bytes = delta.to_bytes();
+    assert_eq!(RevocationDelta::from_bytes(&bytes), Some(delta.clone()));
+    assert_eq!(RevocationDelta::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+    // Holders end up with the witnesses the registry would hand out, except the revoked one
+    for witness in witnesses.iter_mut() {
+      let serial = witness.serial;
+      let before = witness.clone();
+      assert_eq!(witness.update(&delta), !delta.revoked().contains(&serial), "{}", serial);
+      match registry.tree().non_membership(serial) {
+        Some(fresh) => assert_eq!(*witness, fresh),
+        None => assert_eq!(*witness, before),
+      }
+    }
+    assert!(!witnesses[0].update(&delta));
+
+    // Mirrors accept the delta once, in order, and only as published
+    let mut tampered = delta.clone();
+    tampered.writes[3].low = 31;
+    assert!(!mirror.clone().apply_delta(&tampered));
+    assert!(mirror.apply_delta(&delta));
+    assert_eq!((mirror.epoch(), mirror.root()), (registry.epoch(), registry.root()));
+    assert!(!mirror.apply_delta(&delta));
+  }
+}
@@ -0,0 +1,188 @@
+// Ring membership with a hidden signer position
+// LoquatRingSignature carries the signer's authentication path in the clear, and the direction
+// bits of that path spell out the signer's position in the ring. RingRelation moves the path
+// and the position into the witness: the public instance is only the ring root and the
+// message hash, and the circuit shows that the prover holds a key K whose member commitment is
+// the leaf at the witnessed position under the root, and that K signs the message as in
+// Loquat::sign, sigma = K - h + 2 * L_K(h) * h with K + h non-zero. sigma stays private too,
+// since together with h it determines K.
+// Members are committed to as griffin_compress(K, MEMBER_DOMAIN) in a GriffinMerkleTree, which
+// the circuit can recompute; the byte-oriented tree of ring_signature has no cheap arithmetic
+// description. The constraints depend on the tree depth alone, so every member and every
+// message of a ring yield the same constraint system and one verifying key covers them all.
+
+use crate::circuit::indexer::VerifyingKey;
+use crate::circuit::legendre::legendre_prf_gadget;
+use crate::circuit::merkle::{enforce_merkle_path, griffin_compress_gadget, GriffinMerkleTree, PathStep, PathVar};
+use crate::circuit::r1cs::{Assignment, ConstraintSystem, LinearCombination};
+use crate::circuit::witness::{Circuit, WitnessBuilder};
+use crate::crypto::hash_functions::Hash;
+use crate::signature::config::LoquatConfig;
+use crate::signature::message::SignableMessage;
+
+// Second compression input of member commitments, "loquat-ring" in ASCII
+const MEMBER_DOMAIN: u128 = 0x006c_6f71_7561_742d_7269_6e67;
+
+// Fills authentication paths shorter than the tree depth
+const PADDING_STEP: PathStep = PathStep { sibling: 0, sibling_is_left: false };
+
+// Leaf committing to a member's key
+pub fn member_leaf(sk: u128) -> u128 {
+  Hash::griffin_compress(sk, MEMBER_DOMAIN)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingStatement {
+  pub ring_root: u128,
+  pub msg: u128, // Message hash, a field element
+  pub depth: usize, // Depth of the ring tree, which fixes the path length
+}
+
+impl RingStatement {
+  // Statement for a message hashed as config prescribes
+  pub fn new<M: SignableMessage + ?Sized>(tree: &GriffinMerkleTree, message: &M, config: &LoquatConfig) -> Self {
+    Self {
+      ring_root: tree.root(),
+      msg: message.message_hash(config.msg_hash),
+      depth: tree.depth(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingWitness {
+  pub sk: u128,
+  pub merkle_path: Vec<PathStep>,
+  pub index: usize, // Leaf position of the signer, never part of the instance
+}
+
+impl RingWitness {
+  // Witness of the member at index; None if the index is outside the tree or its leaf is
+  // not the commitment of sk
+  pub fn new(sk: u128, tree: &GriffinMerkleTree, index: usize) -> Option<Self> {
+    let merkle_path = tree.path(index)?;
+    if GriffinMerkleTree::root_from_path(member_leaf(sk), &merkle_path) != tree.root() {
+      return None;
+    }
+    Some(Self { sk, merkle_path, index })
+  }
+
+  // Placeholder of the right shape, used when synthesizing without a witness; never satisfies
+  pub fn empty(depth: usize) -> Self {
+    Self {
+      sk: 0,
+      merkle_path: vec![PADDING_STEP; depth],
+      index: 0,
+    }
+  }
+}
+
+pub struct RingRelation;
+
+impl Circuit for RingRelation {
+  type Statement = RingStatement;
+  type PrivateInput = RingWitness;
+
+  // A path of the wrong length is padded or cut to the depth, which keeps the shape fixed and
+  // leaves the system unsatisfied
+  fn synthesize(cs: &mut ConstraintSystem, statement: &RingStatement, witness: &RingWitness) {
+    let root = cs.alloc_input(statement.ring_root);
+    let msg = cs.alloc_input(statement.msg);
+    let key: LinearCombination = cs.alloc_witness(witness.sk).into();
+
+    // Membership: the key's commitment is the leaf the path authenticates, at the position
+    // its direction bits encode
+    let mut steps = witness.merkle_path.clone();
+    steps.resize(statement.depth, PADDING_STEP);
+    let path = PathVar::alloc(cs, &steps);
+    let leaf = griffin_compress_gadget(cs, key.clone(), LinearCombination::constant(MEMBER_DOMAIN));
+    enforce_merkle_path(cs, leaf, &path, root.into());
+    let index = cs.alloc_witness(witness.index as u128);
+    cs.enforce_equal(index.into(), path.position());
+
+    // Signature: the PRF symbol at K + h, then sigma
+    let bit = legendre_prf_gadget(cs, &key.clone().plus(msg, 1), 0);
+    let shift = cs.mul(bit.into(), msg.into());
+    let expected = key.minus(msg, 1).plus(shift, 2);
+    let sigma = cs.alloc_witness(cs.evaluate(&expected));
+    cs.enforce_equal(sigma.into(), expected);
+  }
+}
+
+// Proves RingRelation through any R1CS backend (see export::r1cs)
+pub struct RingProver<'a> {
+  statement: &'a RingStatement,
+  builder: WitnessBuilder<'a, RingRelation>,
+}
+
+impl<'a> RingProver<'a> {
+  pub fn new(statement: &'a RingStatement) -> Self {
+    Self {
+      statement,
+      builder: WitnessBuilder::new(statement),
+    }
+  }
+
+  // Verifying key of the relation; the same for every member and message at this depth
+  pub fn verifying_key(&self) -> VerifyingKey {
+    VerifyingKey::index(&self.builder.constraint_system(&RingWitness::empty(self.statement.depth)))
+  }
+
+  // Satisfying assignment for the backend; None if the witness does not show membership and a
+  // signature. Its public inputs are exactly [ring_root, msg]
+  pub fn prove(&self, witness: &RingWitness) -> Option<Assignment> {
+    let cs = self.builder.constraint_system(witness);
+    cs.is_satisfied().then(|| cs.assignment())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const KEYS: [u128; 5] = [0x1111_2222_3333_4444, 0x5555_6666_7777_8888, 0x0123_4567_89ab_cdef, 0x7edc_ba98_7654_3210, 0x0bad_cafe_f00d_d00d];
+
+  fn ring() -> GriffinMerkleTree {
+    GriffinMerkleTree::new(&KEYS.map(member_leaf))
+  }
+
+  #[test]
+  fn test_membership_with_hidden_index() {
+    let tree = ring();
+    let statement = RingStatement::new(&tree, b"ring message", &LoquatConfig::default());
+    let prover = RingProver::new(&statement);
+
+    // Every member proves against the same instance and the same verifying key
+    let vk = prover.verifying_key();
+    for index in [0, 3, 4] {
+      let witness = RingWitness::new(KEYS[index], &tree, index).unwrap();
+      let assignment = prover.prove(&witness).unwrap();
+      assert_eq!(assignment.inputs, [statement.ring_root, statement.msg]);
+      let cs = WitnessBuilder::<RingRelation>::new(&statement).constraint_system(&witness);
+      assert!(vk.matches(&cs));
+    }
+    let other = RingStatement::new(&tree, b"other message", &LoquatConfig::default());
+    assert_eq!(RingProver::new(&other).verifying_key(), vk);
+  }
+
+  #[test]
+  fn test_non_members_and_wrong_positions_rejected() {
+    let tree = ring();
+    let statement = RingStatement::new(&tree, b"ring message", &LoquatConfig::default());
+    let prover = RingProver::new(&statement);
+    let honest = RingWitness::new(KEYS[1], &tree, 1).unwrap();
+
+    // An outsider reusing a member's path, or a member claiming another position
+    let outsider = 0x0f0f_0f0f_0f0f_0f0f;
+    assert!(RingWitness::new(outsider, &tree, 1).is_none());
+    assert!(prover.prove(&RingWitness { sk: outsider, ..honest.clone() }).is_none());
+    assert!(prover.prove(&RingWitness { index: 2, ..honest.clone() }).is_none());
+    assert!(prover.prove(&RingWitness { merkle_path: honest.merkle_path[1..].to_vec(), ..honest.clone() }).is_none());
+    assert!(prover.prove(&RingWitness::empty(statement.depth)).is_none());
+
+    // A member of one ring cannot prove against another root
+    let other = GriffinMerkleTree::new(&[member_leaf(KEYS[1]), member_leaf(outsider)]);
+    let foreign = RingStatement { depth: tree.depth(), ..RingStatement::new(&other, b"ring message", &LoquatConfig::default()) };
+    assert!(RingProver::new(&foreign).prove(&honest).is_none());
+  }
+}
@@ -0,0 +1,171 @@
+// Knowledge of a signature under an indexed public key
+// SignatureRelation shows that the prover holds a Loquat signature sigma on a message under an
+// IndexedPublicKey without revealing sigma: the witness is sigma together with the key K it
+// opens to, the public instance is the key's PRF symbols and the message hash h, and the
+// circuit checks that K reproduces every symbol and that sigma = K - h + 2 * L_K(h) * h as in
+// Loquat::sign. The message hash and the symbols are inputs rather than constants, so the
+// constraints depend on the key's public indices alone and one verifying key covers every
+// message signed under it.
+// The hash commitment of the key is not part of the circuit, as in KeyRelation.
+
+use crate::circuit::indexer::VerifyingKey;
+use crate::circuit::legendre::{enforce_indexed_public_key, legendre_prf_gadget};
+use crate::circuit::r1cs::{Assignment, ConstraintSystem, LinearCombination};
+use crate::circuit::witness::{Circuit, WitnessBuilder};
+use crate::compact::field;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::LoquatSignature;
+use crate::signature::message::SignableMessage;
+use crate::signature::params::ParamsHandle;
+use crate::signature::public_indices::IndexedPublicKey;
+use num_traits::ToPrimitive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureStatement {
+  pub public_key: IndexedPublicKey,
+  pub message_hash: u128, // A field element
+}
+
+impl SignatureStatement {
+  // Statement for a message hashed as config prescribes; None for parameter sets other than
+  // L128, since the circuits are over 2^127 - 1
+  pub fn new<M: SignableMessage + ?Sized>(public_key: IndexedPublicKey, message: &M, config: &LoquatConfig) -> Option<Self> {
+    if config.params != ParamsHandle::L128 {
+      return None;
+    }
+    Some(Self {
+      public_key,
+      message_hash: message.message_hash(config.msg_hash),
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureWitness {
+  pub sk: u128,
+  pub sigma: u128,
+}
+
+impl SignatureWitness {
+  // Witness of a signature on the statement's message; None if the signature does not open to
+  // a key reproducing the public symbols
+  pub fn new(statement: &SignatureStatement, signature: &LoquatSignature) -> Option<Self> {
+    let sigma = signature.sigma.to_u128().filter(|sigma| *sigma < field::P)?;
+    let h = statement.message_hash;
+    let sk = [field::sub(sigma, h), field::add(sigma, h)].into_iter().find(|&sk| {
+      let expected = match field::legendre_symbol(field::add(sk, h)) {
+        1 => field::sub(sk, h),
+        -1 => field::add(sk, h),
+        _ => return false,
+      };
+      expected == sigma && statement.public_key.indices.evaluate(sk) == statement.public_key.symbols
+    })?;
+    Some(Self { sk, sigma })
+  }
+
+  // Placeholder used when synthesizing without a witness; never satisfies
+  pub fn empty() -> Self {
+    Self { sk: 0, sigma: 0 }
+  }
+}
+
+pub struct SignatureRelation;
+
+impl Circuit for SignatureRelation {
+  type Statement = SignatureStatement;
+  type PrivateInput = SignatureWitness;
+
+  fn synthesize(cs: &mut ConstraintSystem, statement: &SignatureStatement, witness: &SignatureWitness) {
+    let key: LinearCombination = cs.alloc_witness(witness.sk).into();
+    enforce_indexed_public_key(cs, &key, &statement.public_key);
+    let msg = cs.alloc_input(statement.message_hash);
+
+    // The PRF symbol at K + h, then sigma
+    let bit = legendre_prf_gadget(cs, &key.clone().plus(msg, 1), 0);
+    let shift = cs.mul(bit.into(), msg.into());
+    let sigma = cs.alloc_witness(witness.sigma);
+    cs.enforce_equal(sigma.into(), key.minus(msg, 1).plus(shift, 2));
+  }
+}
+
+// Proves SignatureRelation through any R1CS backend (see export::r1cs)
+pub struct SignatureProver<'a> {
+  builder: WitnessBuilder<'a, SignatureRelation>,
+}
+
+impl<'a> SignatureProver<'a> {
+  pub fn new(statement: &'a SignatureStatement) -> Self {
+    Self {
+      builder: WitnessBuilder::new(statement),
+    }
+  }
+
+  // Verifying key of the relation; the same for every message and every key over the indices
+  pub fn verifying_key(&self) -> VerifyingKey {
+    VerifyingKey::index(&self.builder.constraint_system(&SignatureWitness::empty()))
+  }
+
+  // Public inputs of the instance: the PRF symbols in index order, then the message hash
+  pub fn public_inputs(&self) -> Vec<u128> {
+    self.builder.constraint_system(&SignatureWitness::empty()).inputs().to_vec()
+  }
+
+  // Constraint system assigned from the witness, for backends that prove from it directly;
+  // None if the witness does not satisfy it
+  pub fn constraint_system(&self, witness: &SignatureWitness) -> Option<ConstraintSystem> {
+    let cs = self.builder.constraint_system(witness);
+    cs.is_satisfied().then_some(cs)
+  }
+
+  // Satisfying assignment for the backend; None if the witness is not a signature
+  pub fn prove(&self, witness: &SignatureWitness) -> Option<Assignment> {
+    self.constraint_system(witness).map(|cs| cs.assignment())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+
+  const SK: u128 = 0x3c6e_f372_fe94_f82b_a54f_f53a_5f1d_36f1;
+
+  fn statement_for(message: &[u8]) -> SignatureStatement {
+    let config = LoquatConfig::default();
+    SignatureStatement::new(IndexedPublicKey::new(SK, &[4u8; 32], 16, &config), message, &config).unwrap()
+  }
+
+  #[test]
+  fn test_signature_knowledge() {
+    let statement = statement_for(b"signed message");
+    let signature = Loquat::sign(SK, b"signed message").unwrap();
+    let witness = SignatureWitness::new(&statement, &signature).unwrap();
+    assert_eq!(witness.sk, SK);
+
+    let prover = SignatureProver::new(&statement);
+    let assignment = prover.prove(&witness).unwrap();
+    assert_eq!(assignment.inputs, prover.public_inputs());
+    assert_eq!(assignment.inputs.last(), Some(&statement.message_hash));
+
+    // Another message under the same key shares the verifying key
+    let other = statement_for(b"another message");
+    assert_eq!(SignatureProver::new(&other).verifying_key(), prover.verifying_key());
+    assert!(SignatureProver::new(&other).prove(&witness).is_none());
+  }
+
+  #[test]
+  fn test_foreign_signatures_rejected() {
+    let statement = statement_for(b"signed message");
+    let prover = SignatureProver::new(&statement);
+    assert!(SignatureWitness::new(&statement, &Loquat::sign(SK, b"other message").unwrap()).is_none());
+    assert!(SignatureWitness::new(&statement, &Loquat::sign(SK ^ 1, b"signed message").unwrap()).is_none());
+
+    // A tampered sigma next to the right key fails in the circuit
+    let honest = SignatureWitness::new(&statement, &Loquat::sign(SK, b"signed message").unwrap()).unwrap();
+    assert!(prover.prove(&SignatureWitness { sigma: field::add(honest.sigma, 1), ..honest }).is_none());
+    assert!(prover.prove(&SignatureWitness::empty()).is_none());
+
+    let m61 = LoquatConfig::default().with_params(ParamsHandle::M61);
+    assert!(SignatureStatement::new(statement.public_key.clone(), b"signed message", &m61).is_none());
+  }
+}
@@ -0,0 +1,94 @@
+// Witness generation independent of proving
+// A Circuit describes a relation by allocating its variables and constraints into a
+// ConstraintSystem from a public statement and the private inputs. WitnessBuilder runs that
+// synthesis on its own and hands back the assignment, so constraint satisfaction can be
+// tested with is_satisfied without producing a proof and external provers can consume the
+// exported witness. The constraints a circuit emits depend only on the statement, never on
+// the private input values, so any private input yields the same constraint system shape.
+
+use crate::circuit::legendre::enforce_indexed_public_key;
+use crate::circuit::r1cs::{Assignment, ConstraintSystem};
+use crate::signature::public_indices::IndexedPublicKey;
+use std::marker::PhantomData;
+
+pub trait Circuit {
+  type Statement;
+  type PrivateInput;
+
+  fn synthesize(cs: &mut ConstraintSystem, statement: &Self::Statement, private_input: &Self::PrivateInput);
+}
+
+pub struct WitnessBuilder<'a, C: Circuit> {
+  statement: &'a C::Statement,
+  _circuit: PhantomData<C>,
+}
+
+impl<'a, C: Circuit> WitnessBuilder<'a, C> {
+  pub fn new(statement: &'a C::Statement) -> Self {
+    Self {
+      statement,
+      _circuit: PhantomData,
+    }
+  }
+
+  // Constraint system with the assignment computed from the private input
+  pub fn constraint_system(&self, private_input: &C::PrivateInput) -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    C::synthesize(&mut cs, self.statement, private_input);
+    cs
+  }
+
+  // Full assignment of the public inputs and witnesses
+  pub fn build(&self, private_input: &C::PrivateInput) -> Assignment {
+    self.constraint_system(private_input).assignment()
+  }
+}
+
+// Loquat key relation: the PRF symbols of an indexed public key are L_K(i) at its public
+// indices for the private key K. The hash commitment of the key is not part of the circuit.
+pub struct KeyRelation;
+
+impl Circuit for KeyRelation {
+  type Statement = IndexedPublicKey;
+  type PrivateInput = u128;
+
+  fn synthesize(cs: &mut ConstraintSystem, statement: &IndexedPublicKey, sk: &u128) {
+    let key = cs.alloc_witness(*sk).into();
+    enforce_indexed_public_key(cs, &key, statement);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::config::LoquatConfig;
+
+  const SK: u128 = 0x2b7e_1516_28ae_d2a6_abf7_1588_09cf_4f3c;
+
+  #[test]
+  fn test_key_relation_witness() {
+    let key = IndexedPublicKey::new(SK, &[7u8; 32], 24, &LoquatConfig::default());
+    let builder = WitnessBuilder::<KeyRelation>::new(&key);
+    let cs = builder.constraint_system(&SK);
+    assert!(cs.is_satisfied());
+
+    let assignment = builder.build(&SK);
+    assert_eq!(assignment.inputs.len(), 24);
+    assert_eq!(assignment.to_vector().len(), 1 + cs.inputs().len() + cs.witnesses().len());
+    assert!(cs.is_satisfied_by(&assignment));
+  }
+
+  #[test]
+  fn test_wrong_key_unsatisfied() {
+    let key = IndexedPublicKey::new(SK, &[7u8; 32], 24, &LoquatConfig::default());
+    let builder = WitnessBuilder::<KeyRelation>::new(&key);
+    assert!(!builder.constraint_system(&(SK + 1)).is_satisfied());
+
+    // The constraints do not depend on the key, so the honest system rejects the other witness
+    let honest = builder.constraint_system(&SK);
+    let other = builder.build(&(SK + 1));
+    assert_eq!(builder.constraint_system(&(SK + 1)).constraints(), honest.constraints());
+    assert!(!honest.is_satisfied_by(&other));
+    assert!(!honest.is_satisfied_by(&Assignment::default()));
+  }
+}
@@ -0,0 +1,200 @@
+// Arithmetic modulo p = 2^127 - 1 on u128 only
+// Since 2^127 = 1 (mod p), wide products fold back into the field with shifts and adds.
+
+// Prime field modulus (p = 2^127 - 1)
+pub const P: u128 = (1 << 127) - 1;
+
+// Reduces any u128 into [0, p)
+pub fn reduce(a: u128) -> u128 {
+  let folded = (a & P) + (a >> 127);
+  if folded >= P {
+    folded - P
+  } else {
+    folded
+  }
+}
+
+pub fn add(a: u128, b: u128) -> u128 {
+  reduce(reduce(a) + reduce(b))
+}
+
+pub fn sub(a: u128, b: u128) -> u128 {
+  add(a, P - reduce(b))
+}
+
+// Multiplies via a 256-bit schoolbook product: hi * 2^128 + lo = 2 * hi + lo (mod p)
+pub fn mul(a: u128, b: u128) -> u128 {
+  let (a, b) = (reduce(a), reduce(b));
+  let (a1, a0) = (a >> 64, a & u64::MAX as u128);
+  let (b1, b0) = (b >> 64, b & u64::MAX as u128);
+
+  let low = a0 * b0;
+  let (mid, mid_carry) = (a0 * b1).overflowing_add(a1 * b0);
+  let (lo, lo_carry) = low.overflowing_add(mid << 64);
+  let hi = a1 * b1 + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+
+  // Inputs are below 2^127, so hi < 2^126 and 2 * hi does not overflow
+  add(hi << 1, reduce(lo))
+}
+
+pub fn pow(base: u128, mut exp: u128) -> u128 {
+  let mut result = 1;
+  let mut base = reduce(base);
+  while exp > 0 {
+    if exp & 1 == 1 {
+      result = mul(result, base);
+    }
+    base = mul(base, base);
+    exp >>= 1;
+  }
+  result
+}
+
+// a^(2^k - 1) by the addition chain x_(2j) = x_j^(2^j) * x_j, x_(j+1) = x_j^2 * a: k - 1
+// squarings and about 2 log2 k multiplications, against k of each for square-and-multiply
+pub fn pow_ones(a: u128, k: u32) -> u128 {
+  match k {
+    0 => 1,
+    1 => reduce(a),
+    _ if k.is_multiple_of(2) => {
+      let half = pow_ones(a, k / 2);
+      mul(square_n(half, k / 2), half)
+    }
+    _ => mul(square_n(pow_ones(a, k - 1), 1), reduce(a)),
+  }
+}
+
+// a^(2^n)
+pub fn square_n(mut a: u128, n: u32) -> u128 {
+  for _ in 0..n {
+    a = mul(a, a);
+  }
+  a
+}
+
+// Legendre symbol by Euler's criterion: 1 for non-zero squares, -1 for non-squares, 0 for zero
+// The exponent (p - 1) / 2 = 2^126 - 1 is all ones, so it goes through the addition chain
+pub fn legendre_symbol(a: u128) -> i8 {
+  match pow_ones(a, 126) {
+    0 => 0,
+    1 => 1,
+    _ => -1,
+  }
+}
+
+// Bits of exponent per table row
+pub const WINDOW_BITS: u32 = 4;
+const WINDOWS: usize = (128 / WINDOW_BITS) as usize;
+
+// Powers of a fixed base: row i holds base^(d * 16^i) for every digit d, so base^e is one
+// multiplication per non-zero base-16 digit of e and no squarings. Worth its 512
+// multiplications of setup from a handful of exponentiations on.
+#[derive(Debug, Clone)]
+pub struct FixedBaseTable {
+  rows: [[u128; 1 << WINDOW_BITS]; WINDOWS],
+}
+
+impl FixedBaseTable {
+  pub fn new(base: u128) -> Self {
+    let mut rows = [[1; 1 << WINDOW_BITS]; WINDOWS];
+    let mut row_base = reduce(base);
+    for row in rows.iter_mut() {
+      for d in 1..row.len() {
+        row[d] = mul(row[d - 1], row_base);
+      }
+      row_base = mul(row[row.len() - 1], row_base);
+    }
+    Self { rows }
+  }
+
+  pub fn base(&self) -> u128 {
+    self.rows[0][1]
+  }
+
+  pub fn pow(&self, exp: u128) -> u128 {
+    self.rows.iter().enumerate().fold(1, |acc, (i, row)| {
+      match (exp >> (i as u32 * WINDOW_BITS)) as usize & ((1 << WINDOW_BITS) - 1) {
+        0 => acc,
+        digit => mul(acc, row[digit]),
+      }
+    })
+  }
+}
+
+// prod bases[i]^exps[i] by Shamir's trick: the squarings are shared across all bases, so n
+// exponentiations cost 127 squarings plus one multiplication per set exponent bit
+// Panics if the slices differ in length
+pub fn multi_pow(bases: &[u128], exps: &[u128]) -> u128 {
+  assert_eq!(bases.len(), exps.len(), "multi_pow needs one exponent per base");
+  let top = exps.iter().map(|e| 128 - e.leading_zeros()).max().unwrap_or(0);
+  (0..top).rev().fold(1, |acc, bit| {
+    let acc = mul(acc, acc);
+    bases.iter().zip(exps).filter(|(_, e)| (*e >> bit) & 1 == 1).fold(acc, |acc, (b, _)| mul(acc, *b))
+  })
+}
+
+// Reduces a big-endian integer of any length modulo p
+// Multiplying by 2^8 is a rotation of the 127-bit representation
+pub fn from_be_bytes_mod(bytes: &[u8]) -> u128 {
+  bytes.iter().fold(0, |acc, &byte| {
+    let shifted = ((acc << 8) & P) | (acc >> 119);
+    add(shifted, byte as u128)
+  })
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::crypto::legendre_prf::LegendrePRF;
+  use num_bigint::BigUint;
+  use num_traits::ToPrimitive;
+
+  fn reference_mul(a: u128, b: u128) -> u128 {
+    ((BigUint::from(a) * BigUint::from(b)) % BigUint::from(P)).to_u128().unwrap()
+  }
+
+  #[test]
+  fn test_mul_matches_biguint() {
+    let samples = [0, 1, 2, P - 1, P - 2, 1 << 126, u64::MAX as u128, 0x1234_5678_9abc_def0_0fed_cba9_8765_4321];
+    for &a in &samples {
+      for &b in &samples {
+        assert_eq!(mul(a, b), reference_mul(a % P, b % P), "{} * {}", a, b);
+      }
+    }
+    assert_eq!(reduce(u128::MAX), 1);
+    assert_eq!(sub(0, 1), P - 1);
+  }
+
+  #[test]
+  fn test_legendre_matches_prf() {
+    for a in [1u128, 2, 3, 4, 5, 42, P - 1, 1 << 100] {
+      assert_eq!(legendre_symbol(a), LegendrePRF::legendre_symbol(a));
+    }
+    assert_eq!(legendre_symbol(0), 0);
+  }
+
+  #[test]
+  fn test_exponentiation_helpers() {
+    let samples = [2u128, 3, 42, P - 2, 0xdead_beef_0123_4567_89ab_cdef];
+    let exps = [0u128, 1, 15, 16, 255, (P - 1) / 2, P - 2, u128::MAX];
+    for &base in &samples {
+      let table = FixedBaseTable::new(base);
+      for &exp in &exps {
+        assert_eq!(table.pow(exp), pow(base, exp), "{}^{}", base, exp);
+      }
+      assert_eq!(pow_ones(base, 126), pow(base, (P - 1) / 2));
+      assert_eq!(pow_ones(base, 7), pow(base, 127));
+    }
+    assert_eq!(multi_pow(&samples, &exps[2..7]), samples.iter().zip(&exps[2..7]).fold(1, |acc, (&b, &e)| mul(acc, pow(b, e))));
+    assert_eq!(multi_pow(&[], &[]), 1);
+  }
+
+  #[test]
+  fn test_bytes_reduction() {
+    let bytes = [0xFFu8; 32];
+    let expected = (BigUint::from_bytes_be(&bytes) % BigUint::from(P)).to_u128().unwrap();
+    assert_eq!(from_be_bytes_mod(&bytes), expected);
+    assert_eq!(from_be_bytes_mod(&[]), 0);
+    assert_eq!(from_be_bytes_mod(&[1, 0]), 256);
+  }
+}
@@ -0,0 +1,18 @@
+//! # Compact Verifier Module
+//!
+//! This module provides a stripped-down Loquat verifier that builds without the standard
+//! library and without BigUint, for Substrate runtimes, ink! contracts and other `no_std`
+//! environments. It is the only module compiled when the crate is built with
+//! `--no-default-features`.
+//!
+//! ## Module Structure
+//! - `field`: Fixed-size arithmetic modulo the Mersenne prime 2^127 - 1
+//! - `verify`: `verify_compact` over serialized public keys and signatures
+//! - `wasm`: C-ABI export of `verify_compact` for WebAssembly hosts (feature `wasm`)
+
+pub mod field;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use verify::verify_compact;
@@ -0,0 +1,226 @@
+// Fixed-size Loquat verification over serialized keys and signatures
+// Accepts the encodings written by Loquat::encode_public_key and LoquatSignature::to_bytes for
+// suites built from SHA3-256, SHAKE-128 and Keccak-256; the algebraic hashes (Poseidon, Griffin)
+// are not available here. The message is its canonical signing encoding (SignableMessage::signing_bytes).
+
+use crate::compact::field;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake128};
+use subtle::ConstantTimeEq;
+use tiny_keccak::{Hasher, Keccak};
+
+// Must match SUITE_VERSION and the suite identifiers in signature::suite
+const SUITE_VERSION: u8 = 2;
+const SUITE_SHA3: u8 = 0x01;
+const SUITE_SHAKE128: u8 = 0x02;
+const SUITE_KECCAK256: u8 = 0x05;
+const SUITE_CUSTOM: u8 = 0xFF;
+
+// Hash function identifiers usable in custom suites
+const HASH_SHA3: u8 = 0x01;
+const HASH_SHAKE128: u8 = 0x02;
+const HASH_KECCAK256: u8 = 0x05;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CompactHash {
+  Sha3_256,
+  Shake128,
+  Keccak256,
+}
+
+impl CompactHash {
+  fn from_id(id: u8) -> Option<Self> {
+    match id {
+      HASH_SHA3 => Some(CompactHash::Sha3_256),
+      HASH_SHAKE128 => Some(CompactHash::Shake128),
+      HASH_KECCAK256 => Some(CompactHash::Keccak256),
+      _ => None,
+    }
+  }
+
+  // Hashes the concatenation of the parts into a 32-byte digest
+  fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    match self {
+      CompactHash::Sha3_256 => {
+        let mut hasher = Sha3_256::new();
+        for part in parts {
+          Digest::update(&mut hasher, part);
+        }
+        out.copy_from_slice(&hasher.finalize());
+      }
+      CompactHash::Shake128 => {
+        let mut hasher = Shake128::default();
+        for part in parts {
+          Update::update(&mut hasher, part);
+        }
+        hasher.finalize_xof().read(&mut out);
+      }
+      CompactHash::Keccak256 => {
+        let mut hasher = Keccak::v256();
+        for part in parts {
+          hasher.update(part);
+        }
+        hasher.finalize(&mut out);
+      }
+    }
+    out
+  }
+}
+
+// Message, Merkle and transcript hash of a suite
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct CompactSuite {
+  msg_hash: CompactHash,
+  merkle_hash: CompactHash,
+  transcript_hash: CompactHash,
+}
+
+// Parses a version and suite header, returning the suite and the remaining bytes
+fn decode_header(input: &[u8]) -> Option<(CompactSuite, &[u8])> {
+  let (&version, rest) = input.split_first()?;
+  if version != SUITE_VERSION {
+    return None;
+  }
+  let (&suite, rest) = rest.split_first()?;
+  let uniform = |hash| CompactSuite {
+    msg_hash: hash,
+    merkle_hash: hash,
+    transcript_hash: hash,
+  };
+  match suite {
+    SUITE_SHA3 => Some((uniform(CompactHash::Sha3_256), rest)),
+    SUITE_SHAKE128 => Some((uniform(CompactHash::Shake128), rest)),
+    SUITE_KECCAK256 => Some((uniform(CompactHash::Keccak256), rest)),
+    SUITE_CUSTOM => {
+      let (ids, rest) = rest.split_at_checked(3)?;
+      // Uniform suites have their own identifiers, so a custom header must mix hashes
+      if ids[0] == ids[1] && ids[1] == ids[2] {
+        return None;
+      }
+      let suite = CompactSuite {
+        msg_hash: CompactHash::from_id(ids[0])?,
+        merkle_hash: CompactHash::from_id(ids[1])?,
+        transcript_hash: CompactHash::from_id(ids[2])?,
+      };
+      Some((suite, rest))
+    }
+    _ => None,
+  }
+}
+
+// Minimal big-endian encoding, matching BigUint::to_bytes_be (zero encodes as a single zero byte)
+fn minimal_be(value: &[u8]) -> &[u8] {
+  match value.iter().position(|&b| b != 0) {
+    Some(start) => &value[start..],
+    None => &value[value.len().saturating_sub(1)..],
+  }
+}
+
+// Verifies a serialized signature under a serialized public key
+pub fn verify_compact(pk_bytes: &[u8], message: &[u8], sig_bytes: &[u8]) -> bool {
+  verify_inner(pk_bytes, message, sig_bytes).unwrap_or(false)
+}
+
+fn verify_inner(pk_bytes: &[u8], message: &[u8], sig_bytes: &[u8]) -> Option<bool> {
+  // Keys are a 32-byte digest; signatures a 16-byte sigma and a 32-byte root
+  let (pk_suite, pk) = decode_header(pk_bytes)?;
+  let (suite, rest) = decode_header(sig_bytes)?;
+  if pk.len() != 32 || rest.len() != 16 + 32 || suite != pk_suite {
+    return None;
+  }
+  let (sigma_bytes, root) = rest.split_at(16);
+
+  // Only the canonical encoding verifies: sigma below P
+  let sigma = u128::from_be_bytes(sigma_bytes.try_into().ok()?);
+  if sigma >= field::P {
+    return None;
+  }
+  let h = field::from_be_bytes_mod(&suite.msg_hash.digest(&[message]));
+
+  // Recover the secret key from whichever PRF branch matches the public key
+  let sk = [field::sub(sigma, h), field::add(sigma, h)]
+    .into_iter()
+    .find(|sk| bool::from(suite.transcript_hash.digest(&[&sk.to_be_bytes()])[..].ct_eq(pk)))?;
+
+  let expected_sigma = match field::legendre_symbol(field::add(sk, h)) {
+    1 => field::sub(sk, h),
+    -1 => field::add(sk, h),
+    _ => return None,
+  };
+
+  // Two-leaf Merkle root over sigma and the message hash: 32-byte words for Keccak-256,
+  // minimal encodings otherwise (see MerkleTree::hash_two)
+  let expected_root = match suite.merkle_hash {
+    CompactHash::Keccak256 => suite.merkle_hash.digest(&[&[0u8; 16], &expected_sigma.to_be_bytes(), &[0u8; 16], &h.to_be_bytes()]),
+    _ => suite.merkle_hash.digest(&[minimal_be(&expected_sigma.to_be_bytes()), minimal_be(&h.to_be_bytes())]),
+  };
+  Some(expected_root[..].ct_eq(root).into())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::crypto::hash_functions::HashFunction;
+  use crate::signature::config::LoquatConfig;
+  use crate::signature::loquat::{Loquat, LoquatSignature};
+  use crate::signature::message::SignableMessage;
+  use serde_json::json;
+
+  fn encoded(config: &LoquatConfig, message: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let keypair = Loquat::keygen_with_config(config);
+    let signature = Loquat::sign_with_config(keypair.secret_key, message, config).unwrap();
+    (Loquat::encode_public_key(&keypair.public_key, config), signature.to_bytes())
+  }
+
+  #[test]
+  fn test_matches_full_verifier() {
+    let configs = [
+      LoquatConfig::default(),
+      LoquatConfig::uniform(HashFunction::Shake128),
+      LoquatConfig::new(HashFunction::Shake128, HashFunction::Sha3_256, HashFunction::Shake128),
+      LoquatConfig::uniform(HashFunction::Keccak256),
+      LoquatConfig::new(HashFunction::Sha3_256, HashFunction::Keccak256, HashFunction::Shake128),
+    ];
+    for config in configs {
+      let (pk, sig) = encoded(&config, b"compact message");
+      assert!(Loquat::verify_encoded(&pk, b"compact message", &sig));
+      assert!(verify_compact(&pk, &b"compact message".signing_bytes(), &sig));
+      assert!(!verify_compact(&pk, &b"other message".signing_bytes(), &sig));
+    }
+  }
+
+  #[test]
+  fn test_structured_messages() {
+    let keypair = Loquat::keygen();
+    let credential = json!({"id": "did:example:alice", "age": 30});
+    let signature = Loquat::sign(keypair.secret_key, &credential).unwrap();
+    let pk = Loquat::encode_public_key(&keypair.public_key, &LoquatConfig::default());
+    assert!(verify_compact(&pk, &credential.signing_bytes(), &signature.to_bytes()));
+  }
+
+  #[test]
+  fn test_rejects_malformed_inputs() {
+    let (pk, sig) = encoded(&LoquatConfig::default(), b"message");
+    let (other_pk, _) = encoded(&LoquatConfig::default(), b"message");
+    assert!(!verify_compact(&other_pk, &b"message".signing_bytes(), &sig));
+
+    let mut trailing = sig.clone();
+    trailing.push(0);
+    assert!(!verify_compact(&pk, &b"message".signing_bytes(), &trailing));
+    assert!(!verify_compact(&pk, &b"message".signing_bytes(), &sig[..sig.len() - 1]));
+
+    // Suites this verifier cannot evaluate are rejected
+    #[cfg(feature = "poseidon")]
+    {
+      let (poseidon_pk, poseidon_sig) = encoded(&LoquatConfig::uniform(HashFunction::Poseidon), b"message");
+      assert!(!verify_compact(&poseidon_pk, &b"message".signing_bytes(), &poseidon_sig));
+    }
+
+    // sigma + P is the same field element but not its canonical encoding
+    let signature = LoquatSignature::from_bytes(&sig).unwrap();
+    let mut shifted = signature;
+    shifted.sigma += num_bigint::BigUint::from(field::P);
+    assert!(!verify_compact(&pk, &b"message".signing_bytes(), &shifted.to_bytes()));
+  }
+}
@@ -0,0 +1,59 @@
+// C-ABI exports of the compact verifier for WebAssembly hosts
+// Built for wasm32-unknown-unknown with `--no-default-features --features wasm`, the module
+// exports `loquat_verify_compact`. The host copies the serialized public key, message and
+// signature into linear memory and passes (offset, length) pairs; nothing is allocated on
+// the module side, so no allocator export is needed.
+
+use crate::compact::verify::verify_compact;
+
+// Return values of loquat_verify_compact
+pub const VERIFY_OK: i32 = 1;
+pub const VERIFY_REJECTED: i32 = 0;
+
+// A zero length accepts any pointer, including null, as the empty slice
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+  if len == 0 || ptr.is_null() {
+    return &[];
+  }
+  core::slice::from_raw_parts(ptr, len)
+}
+
+/// # Safety
+///
+/// Each non-null pointer must be valid for reads of its length for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn loquat_verify_compact(
+  pk_ptr: *const u8,
+  pk_len: usize,
+  msg_ptr: *const u8,
+  msg_len: usize,
+  sig_ptr: *const u8,
+  sig_len: usize,
+) -> i32 {
+  if verify_compact(input(pk_ptr, pk_len), input(msg_ptr, msg_len), input(sig_ptr, sig_len)) {
+    VERIFY_OK
+  } else {
+    VERIFY_REJECTED
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::signature::config::LoquatConfig;
+  use crate::signature::loquat::Loquat;
+  use crate::signature::message::SignableMessage;
+
+  #[test]
+  fn test_exported_verifier() {
+    let keypair = Loquat::keygen();
+    let pk = Loquat::encode_public_key(&keypair.public_key, &LoquatConfig::default());
+    let sig = Loquat::sign(keypair.secret_key, b"message").unwrap().to_bytes();
+
+    let verify = |msg: &[u8], sig: &[u8]| unsafe { loquat_verify_compact(pk.as_ptr(), pk.len(), msg.as_ptr(), msg.len(), sig.as_ptr(), sig.len()) };
+    assert_eq!(verify(&b"message".signing_bytes(), &sig), VERIFY_OK);
+    assert_eq!(verify(&b"other".signing_bytes(), &sig), VERIFY_REJECTED);
+    assert_eq!(verify(&b"message".signing_bytes(), &[]), VERIFY_REJECTED);
+    assert_eq!(unsafe { loquat_verify_compact(core::ptr::null(), 0, core::ptr::null(), 0, core::ptr::null(), 0) }, VERIFY_REJECTED);
+  }
+}
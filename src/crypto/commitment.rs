@@ -0,0 +1,184 @@
+// Hiding, binding commitments to vectors of field elements
+// Each position i holding value v is committed as the leaf c_i = H(tag | r_i | i | v) under a
+// fresh 127-bit blinding r_i, and the commitment is the Merkle root over the leaves. Binding
+// follows from collision resistance of H, hiding from the blindings. Every hash input fits in
+// the three-element state of the algebraic hashes used inside circuits, and the default (with
+// the `griffin` feature) is Griffin, since the Poseidon permutation here has no mixing layer.
+// A single position can be opened with its blinding and Merkle path without revealing the
+// other values.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::merkle::MerkleTree;
+use crate::utils::constant_time;
+use crate::utils::field_operations::P;
+use num_bigint::BigUint;
+use crate::crypto::test_rng;
+use rand::Rng;
+
+// Domain separation tag of committed leaves
+const LEAF_TAG: u8 = 0x43;
+
+// Commitment to a vector of field elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commitment {
+  pub root: BigUint,
+  pub len: usize, // Number of committed values
+}
+
+// Opening of a whole commitment, kept by the committer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentOpening {
+  pub values: Vec<u128>,
+  pub blindings: Vec<u128>,
+}
+
+// Opening of a single committed position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionOpening {
+  pub index: usize,
+  pub value: u128,
+  pub blinding: u128,
+  pub path: Vec<(BigUint, bool)>, // Merkle path from the leaf to the root
+}
+
+// Commitment scheme over a chosen hash function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitmentScheme {
+  hash_function: HashFunction,
+}
+
+impl CommitmentScheme {
+  pub fn new(hash_function: HashFunction) -> Self {
+    Self { hash_function }
+  }
+
+  // Leaf digest of one position
+  fn leaf(&self, index: usize, value: u128, blinding: u128) -> BigUint {
+    let index = u32::try_from(index).expect("Commitment index out of range");
+    let input = [&[LEAF_TAG][..], &blinding.to_be_bytes(), &index.to_be_bytes(), &value.to_be_bytes()].concat();
+    BigUint::from_bytes_be(&Hash::new(self.hash_function).compute(&input))
+  }
+
+  fn tree(&self, opening: &CommitmentOpening) -> MerkleTree {
+    let leaves = opening
+      .values
+      .iter()
+      .zip(&opening.blindings)
+      .enumerate()
+      .map(|(index, (value, blinding))| self.leaf(index, *value, *blinding))
+      .collect();
+    MerkleTree::new(leaves, self.hash_function)
+  }
+
+  // Commits to the values under fresh random blindings; returns None for an empty vector
+  // or values outside the field
+  pub fn commit(&self, values: &[u128]) -> Option<(Commitment, CommitmentOpening)> {
+    let mut rng = test_rng::source();
+    let blindings = values.iter().map(|_| rng.gen_range(0..P)).collect();
+    let opening = CommitmentOpening {
+      values: values.to_vec(),
+      blindings,
+    };
+    let commitment = self.commit_with_opening(&opening)?;
+    Some((commitment, opening))
+  }
+
+  // Recomputes the commitment of an opening
+  pub fn commit_with_opening(&self, opening: &CommitmentOpening) -> Option<Commitment> {
+    if opening.values.is_empty() || opening.values.len() != opening.blindings.len() {
+      return None;
+    }
+    if opening.values.iter().chain(&opening.blindings).any(|x| *x >= P) {
+      return None;
+    }
+    let root = self.tree(opening).root()?;
+    Some(Commitment {
+      root,
+      len: opening.values.len(),
+    })
+  }
+
+  // Checks a full opening against a commitment
+  pub fn verify(&self, commitment: &Commitment, opening: &CommitmentOpening) -> bool {
+    match self.commit_with_opening(opening) {
+      Some(expected) => expected.len == commitment.len && constant_time::biguint_eq(&expected.root, &commitment.root),
+      None => false,
+    }
+  }
+
+  // Opens a single position of a commitment
+  pub fn open(&self, opening: &CommitmentOpening, index: usize) -> Option<PositionOpening> {
+    let path = self.tree(opening).generate_proof(index)?;
+    Some(PositionOpening {
+      index,
+      value: *opening.values.get(index)?,
+      blinding: *opening.blindings.get(index)?,
+      path,
+    })
+  }
+
+  // Checks a single-position opening against a commitment
+  pub fn verify_position(&self, commitment: &Commitment, opening: &PositionOpening) -> bool {
+    if opening.index >= commitment.len || opening.value >= P || opening.blinding >= P {
+      return false;
+    }
+    let leaf = self.leaf(opening.index, opening.value, opening.blinding);
+    MerkleTree::verify_proof(&commitment.root, &leaf, &opening.path, &self.hash_function)
+  }
+}
+
+#[cfg(feature = "griffin")]
+impl Default for CommitmentScheme {
+  fn default() -> Self {
+    Self::new(HashFunction::Griffin)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_commit_and_open() {
+    let hash_functions = [
+      HashFunction::Sha3_256,
+      HashFunction::Keccak256,
+      #[cfg(feature = "griffin")]
+      HashFunction::Griffin,
+    ];
+    for hash_function in hash_functions {
+      let scheme = CommitmentScheme::new(hash_function);
+      let (commitment, opening) = scheme.commit(&[1, 2, 3, 4, 5]).unwrap();
+      assert!(scheme.verify(&commitment, &opening));
+
+      let position = scheme.open(&opening, 3).unwrap();
+      assert_eq!(position.value, 4);
+      assert!(scheme.verify_position(&commitment, &position));
+
+      // The opened value cannot be swapped or moved to another position
+      let mut swapped = position.clone();
+      swapped.value = 5;
+      assert!(!scheme.verify_position(&commitment, &swapped));
+      let mut moved = position;
+      moved.index = 4;
+      assert!(!scheme.verify_position(&commitment, &moved));
+    }
+  }
+
+  #[test]
+  fn test_binding_and_hiding() {
+    let scheme = CommitmentScheme::new(HashFunction::Sha3_256);
+    let (commitment, opening) = scheme.commit(&[42, 7]).unwrap();
+
+    let mut altered = opening.clone();
+    altered.values[0] = 43;
+    assert!(!scheme.verify(&commitment, &altered));
+
+    // Fresh blindings give unrelated commitments to the same values
+    let (again, _) = scheme.commit(&[42, 7]).unwrap();
+    assert_ne!(commitment.root, again.root);
+
+    assert!(scheme.commit(&[]).is_none());
+    assert!(scheme.commit(&[P]).is_none());
+  }
+}
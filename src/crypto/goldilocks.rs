@@ -0,0 +1,310 @@
+// Goldilocks field (p = 2^64 - 2^32 + 1) and number theoretic transforms
+// p - 1 = 2^32 * (2^32 - 1), so the field has multiplicative subgroups of every power-of-two
+// order up to 2^32 and radix-2 NTTs over domains of up to 2^32 points. The Loquat field
+// 2^127 - 1 has two-adicity 1, which rules out FFT-based proving over it directly.
+//
+// Embedding: the Legendre PRF layer keeps working in 2^127 - 1. Its elements enter a
+// Goldilocks proof as three 43-bit limbs (x = l0 + l1 * 2^43 + l2 * 2^86), each far below p,
+// so the embedding is injective and limb arithmetic does not wrap. A circuit relating
+// embedded values must range-check the limbs and carry explicitly between them.
+
+use crate::utils::field_operations::P;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Field modulus
+pub const GOLDILOCKS_P: u64 = 0xFFFF_FFFF_0000_0001;
+
+// Largest power of two dividing p - 1
+pub const TWO_ADICITY: u32 = 32;
+
+// Generator of the full multiplicative group
+pub const MULTIPLICATIVE_GENERATOR: u64 = 7;
+
+// Bits per limb when embedding 2^127 - 1 elements
+pub const LIMB_BITS: u32 = 43;
+
+// Element of the Goldilocks field, always stored reduced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Goldilocks(u64);
+
+impl Goldilocks {
+  pub const ZERO: Self = Goldilocks(0);
+  pub const ONE: Self = Goldilocks(1);
+
+  pub fn new(value: u64) -> Self {
+    Goldilocks(value % GOLDILOCKS_P)
+  }
+
+  pub fn value(&self) -> u64 {
+    self.0
+  }
+
+  pub fn pow(&self, mut exp: u64) -> Self {
+    let mut base = *self;
+    let mut result = Self::ONE;
+    while exp > 0 {
+      if exp & 1 == 1 {
+        result = result * base;
+      }
+      base = base * base;
+      exp >>= 1;
+    }
+    result
+  }
+
+  // Multiplicative inverse by Fermat's little theorem; zero has no inverse
+  pub fn inverse(&self) -> Option<Self> {
+    if self.0 == 0 {
+      return None;
+    }
+    Some(self.pow(GOLDILOCKS_P - 2))
+  }
+
+  // Primitive 2^log_n-th root of unity
+  pub fn root_of_unity(log_n: u32) -> Option<Self> {
+    if log_n > TWO_ADICITY {
+      return None;
+    }
+    let order_cofactor = (GOLDILOCKS_P - 1) >> log_n;
+    Some(Goldilocks(MULTIPLICATIVE_GENERATOR).pow(order_cofactor))
+  }
+}
+
+impl Add for Goldilocks {
+  type Output = Self;
+  fn add(self, other: Self) -> Self {
+    Goldilocks(((self.0 as u128 + other.0 as u128) % GOLDILOCKS_P as u128) as u64)
+  }
+}
+
+impl Sub for Goldilocks {
+  type Output = Self;
+  fn sub(self, other: Self) -> Self {
+    Goldilocks(((self.0 as u128 + GOLDILOCKS_P as u128 - other.0 as u128) % GOLDILOCKS_P as u128) as u64)
+  }
+}
+
+impl Mul for Goldilocks {
+  type Output = Self;
+  fn mul(self, other: Self) -> Self {
+    Goldilocks(((self.0 as u128 * other.0 as u128) % GOLDILOCKS_P as u128) as u64)
+  }
+}
+
+impl Neg for Goldilocks {
+  type Output = Self;
+  fn neg(self) -> Self {
+    Self::ZERO - self
+  }
+}
+
+fn bit_reverse(values: &mut [Goldilocks]) {
+  let n = values.len();
+  let mut j = 0;
+  for i in 1..n {
+    let mut bit = n >> 1;
+    while j & bit != 0 {
+      j ^= bit;
+      bit >>= 1;
+    }
+    j |= bit;
+    if i < j {
+      values.swap(i, j);
+    }
+  }
+}
+
+// In-place radix-2 transform with the given root; values.len() must be a power of two
+fn transform(values: &mut [Goldilocks], root: Goldilocks) {
+  let n = values.len();
+  bit_reverse(values);
+
+  let mut len = 2;
+  while len <= n {
+    let step = root.pow((n / len) as u64);
+    for chunk in values.chunks_mut(len) {
+      let mut w = Goldilocks::ONE;
+      let (low, high) = chunk.split_at_mut(len / 2);
+      for (a, b) in low.iter_mut().zip(high.iter_mut()) {
+        let t = *b * w;
+        *b = *a - t;
+        *a = *a + t;
+        w = w * step;
+      }
+    }
+    len <<= 1;
+  }
+}
+
+// Evaluates a polynomial (coefficients, lowest degree first) on the subgroup of order values.len()
+// Returns false if the length is not a supported power of two
+pub fn ntt(values: &mut [Goldilocks]) -> bool {
+  let n = values.len();
+  if !n.is_power_of_two() {
+    return false;
+  }
+  match Goldilocks::root_of_unity(n.trailing_zeros()) {
+    Some(root) => {
+      transform(values, root);
+      true
+    }
+    None => false,
+  }
+}
+
+// Powers w^0, ..., w^(n/2 - 1) of the root of unity of order n = 2^log_n, the twiddle factors
+// of every layer of an ntt of that size; None beyond the two-adicity
+pub fn twiddles(log_n: u32) -> Option<Vec<Goldilocks>> {
+  let root = Goldilocks::root_of_unity(log_n)?;
+  let mut w = Goldilocks::ONE;
+  Some(
+    (0..(1usize << log_n) / 2)
+      .map(|_| {
+        let current = w;
+        w = w * root;
+        current
+      })
+      .collect(),
+  )
+}
+
+// ntt reading its factors from a twiddles table instead of recomputing them; false unless the
+// table is the one for values.len()
+pub fn ntt_with_twiddles(values: &mut [Goldilocks], twiddles: &[Goldilocks]) -> bool {
+  let n = values.len();
+  if !n.is_power_of_two() || twiddles.len() != n / 2 {
+    return false;
+  }
+  bit_reverse(values);
+  let mut len = 2;
+  while len <= n {
+    let stride = n / len;
+    for chunk in values.chunks_mut(len) {
+      let (low, high) = chunk.split_at_mut(len / 2);
+      for (k, (a, b)) in low.iter_mut().zip(high.iter_mut()).enumerate() {
+        let t = *b * twiddles[k * stride];
+        *b = *a - t;
+        *a = *a + t;
+      }
+    }
+    len <<= 1;
+  }
+  true
+}
+
+// Inverse of ntt: recovers coefficients from evaluations on the subgroup
+pub fn intt(values: &mut [Goldilocks]) -> bool {
+  let n = values.len();
+  if !n.is_power_of_two() {
+    return false;
+  }
+  let root = match Goldilocks::root_of_unity(n.trailing_zeros()).and_then(|root| root.inverse()) {
+    Some(root) => root,
+    None => return false,
+  };
+  transform(values, root);
+  let n_inv = Goldilocks::new(n as u64).inverse().expect("Domain size is invertible");
+  for value in values.iter_mut() {
+    *value = *value * n_inv;
+  }
+  true
+}
+
+// Inverts every element with a single field inversion (Montgomery's trick)
+// None if any element is zero
+pub fn batch_inverse(values: &[Goldilocks]) -> Option<Vec<Goldilocks>> {
+  let mut prefix = Vec::with_capacity(values.len());
+  let mut acc = Goldilocks::ONE;
+  for value in values {
+    prefix.push(acc);
+    acc = acc * *value;
+  }
+  let mut inverse = acc.inverse()?;
+  let mut out = vec![Goldilocks::ZERO; values.len()];
+  for i in (0..values.len()).rev() {
+    out[i] = inverse * prefix[i];
+    inverse = inverse * values[i];
+  }
+  Some(out)
+}
+
+// Splits an element of 2^127 - 1 into Goldilocks limbs
+pub fn embed_m127(x: u128) -> [Goldilocks; 3] {
+  let mask = (1u128 << LIMB_BITS) - 1;
+  [
+    Goldilocks((x & mask) as u64),
+    Goldilocks(((x >> LIMB_BITS) & mask) as u64),
+    Goldilocks((x >> (2 * LIMB_BITS)) as u64),
+  ]
+}
+
+// Recombines limbs produced by embed_m127, rejecting out-of-range limbs
+pub fn lift_m127(limbs: &[Goldilocks; 3]) -> Option<u128> {
+  let bound = 1u64 << LIMB_BITS;
+  if limbs.iter().any(|limb| limb.0 >= bound) {
+    return None;
+  }
+  let x = limbs[0].0 as u128 | (limbs[1].0 as u128) << LIMB_BITS | (limbs[2].0 as u128) << (2 * LIMB_BITS);
+  if x >= P {
+    return None;
+  }
+  Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_field_arithmetic() {
+    let a = Goldilocks::new(GOLDILOCKS_P - 1);
+    assert_eq!(a + Goldilocks::ONE, Goldilocks::ZERO);
+    assert_eq!(Goldilocks::ZERO - Goldilocks::ONE, a);
+    assert_eq!(a * a, Goldilocks::ONE);
+    assert_eq!(Goldilocks::new(2).pow(64), Goldilocks::new((1 << 32) - 1));
+
+    let b = Goldilocks::new(123_456_789);
+    assert_eq!(b * b.inverse().unwrap(), Goldilocks::ONE);
+    assert!(Goldilocks::ZERO.inverse().is_none());
+
+    // Roots of unity have exactly the requested order
+    let root = Goldilocks::root_of_unity(TWO_ADICITY).unwrap();
+    assert_eq!(root.pow(1 << 32), Goldilocks::ONE);
+    assert_eq!(root.pow(1 << 31), -Goldilocks::ONE);
+    assert!(Goldilocks::root_of_unity(TWO_ADICITY + 1).is_none());
+  }
+
+  #[test]
+  fn test_ntt_round_trip() {
+    let coeffs: Vec<Goldilocks> = (0..16u64).map(|i| Goldilocks::new(i * i + 3)).collect();
+    let mut evals = coeffs.clone();
+    assert!(ntt(&mut evals));
+
+    // Evaluations agree with direct Horner evaluation on the subgroup
+    let root = Goldilocks::root_of_unity(4).unwrap();
+    for (i, eval) in evals.iter().enumerate() {
+      let x = root.pow(i as u64);
+      let direct = coeffs.iter().rev().fold(Goldilocks::ZERO, |acc, c| acc * x + *c);
+      assert_eq!(*eval, direct);
+    }
+
+    assert!(intt(&mut evals));
+    assert_eq!(evals, coeffs);
+    assert!(!ntt(&mut [Goldilocks::ONE; 3]));
+
+    // A precomputed table gives the same transform
+    let mut cached = coeffs.clone();
+    assert!(ntt_with_twiddles(&mut cached, &twiddles(4).unwrap()));
+    assert!(ntt(&mut evals));
+    assert_eq!(cached, evals);
+    assert!(!ntt_with_twiddles(&mut cached, &twiddles(3).unwrap()));
+  }
+
+  #[test]
+  fn test_m127_embedding() {
+    let x = (1u128 << 127) - 2;
+    let limbs = embed_m127(x);
+    assert_eq!(lift_m127(&limbs), Some(x));
+    assert_eq!(lift_m127(&[Goldilocks::new(1 << LIMB_BITS), Goldilocks::ZERO, Goldilocks::ZERO]), None);
+  }
+}
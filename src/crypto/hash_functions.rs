@@ -7,20 +7,192 @@ use num_bigint::BigUint;
 use num_traits::Zero;
 use num_traits::ToPrimitive;
 use std::convert::TryInto;
+use crate::utils::encoding::{MULTIHASH_KECCAK_256, MULTIHASH_SHA3_256, MULTIHASH_SHAKE_128};
+use crate::utils::field_operations::P;
+#[cfg(feature = "poseidon")]
+use crate::crypto::poseidon2;
+#[cfg(any(feature = "poseidon", feature = "griffin"))]
+use crate::crypto::sponge;
+use crate::crypto::sponge::Permutation;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
 
-// Prime field modulus (p = 2^127 - 1)
-const P: u128 = (1 << 127) - 1;
+// Poseidon parameters (simplified)
+pub const POSEIDON_WIDTH: usize = 3; // State width (t)
+pub const POSEIDON_FULL_ROUNDS: usize = 8; // Number of full rounds
+pub const POSEIDON_PARTIAL_ROUNDS: usize = 57; // Number of partial rounds for width 3
+
+// Griffin parameters (simplified)
+pub const GRIFFIN_WIDTH: usize = 3; // State width
+pub const GRIFFIN_ROUNDS: usize = 10; // Number of rounds (simplified)
+pub const GRIFFIN_SBOX_EXP: u128 = 5; // S-box exponent
+pub const GRIFFIN_INV_SBOX_EXP: u128 = (P + 1) / 5; // Exponent of the "inverse" S-box rounds
 
 // Supported Hash Functions
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum HashFunction {
   Sha3_256,
   Shake128,
+  #[cfg(feature = "poseidon")]
   Poseidon,
+  #[cfg(feature = "griffin")]
   Griffin,
+  Keccak256, // Ethereum keccak256; Merkle nodes are 32-byte padded as in Solidity
+  #[cfg(feature = "poseidon")]
+  Poseidon2, // Sponge over the width-3 Poseidon2 permutation (crypto::poseidon2)
+}
+
+impl HashFunction {
+  // Every hash function enabled in this build
+  pub const ALL: &'static [HashFunction] = &[
+    HashFunction::Sha3_256,
+    HashFunction::Shake128,
+    #[cfg(feature = "poseidon")]
+    HashFunction::Poseidon,
+    #[cfg(feature = "griffin")]
+    HashFunction::Griffin,
+    HashFunction::Keccak256,
+    #[cfg(feature = "poseidon")]
+    HashFunction::Poseidon2,
+  ];
+
+  // Stable one-byte identifier used in serialized keys and signatures
+  pub fn id(&self) -> u8 {
+    match self {
+      HashFunction::Sha3_256 => 0x01,
+      HashFunction::Shake128 => 0x02,
+      #[cfg(feature = "poseidon")]
+      HashFunction::Poseidon => 0x03,
+      #[cfg(feature = "griffin")]
+      HashFunction::Griffin => 0x04,
+      HashFunction::Keccak256 => 0x05,
+      #[cfg(feature = "poseidon")]
+      HashFunction::Poseidon2 => 0x06,
+    }
+  }
+
+  // Multihash function code, for hash functions with a registered code
+  pub fn multihash_code(&self) -> Option<u64> {
+    match self {
+      HashFunction::Sha3_256 => Some(MULTIHASH_SHA3_256),
+      HashFunction::Shake128 => Some(MULTIHASH_SHAKE_128),
+      HashFunction::Keccak256 => Some(MULTIHASH_KECCAK_256),
+      #[cfg(feature = "poseidon")]
+      HashFunction::Poseidon | HashFunction::Poseidon2 => None, // The registered Poseidon codes are over other fields
+      #[cfg(feature = "griffin")]
+      HashFunction::Griffin => None,
+    }
+  }
+
+  // Looks up a hash function by its serialized identifier
+  pub fn from_id(id: u8) -> Option<Self> {
+    match id {
+      0x01 => Some(HashFunction::Sha3_256),
+      0x02 => Some(HashFunction::Shake128),
+      #[cfg(feature = "poseidon")]
+      0x03 => Some(HashFunction::Poseidon),
+      #[cfg(feature = "griffin")]
+      0x04 => Some(HashFunction::Griffin),
+      0x05 => Some(HashFunction::Keccak256),
+      #[cfg(feature = "poseidon")]
+      0x06 => Some(HashFunction::Poseidon2),
+      _ => None,
+    }
+  }
+}
+
+// Permutation behind HashFunction::Poseidon, for Sponge
+#[cfg(feature = "poseidon")]
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonPermutation;
+
+#[cfg(feature = "poseidon")]
+impl Permutation<POSEIDON_WIDTH> for PoseidonPermutation {
+  fn permute(state: &mut [u128; POSEIDON_WIDTH]) {
+    Hash::poseidon_permutation(state)
+  }
+}
+
+// Permutation behind HashFunction::Griffin, for Sponge
+#[derive(Debug, Clone, Copy)]
+pub struct GriffinPermutation;
+
+impl Permutation<GRIFFIN_WIDTH> for GriffinPermutation {
+  fn permute(state: &mut [u128; GRIFFIN_WIDTH]) {
+    Hash::griffin_permutation(state)
+  }
+}
+
+// What a hash function is used for in a configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashUseCase {
+  Message, // LoquatConfig::msg_hash
+  Merkle, // LoquatConfig::merkle_hash
+  Transcript, // LoquatConfig::transcript_hash
+}
+
+// Where the hashes end up being recomputed by verifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashTarget {
+  Native, // Ordinary CPUs: the byte-oriented hashes are fastest
+  Circuit, // Inside a SNARK: field-native permutations cost far fewer constraints
+  Evm, // Solidity verifiers: keccak256 is a precompile
 }
 
+impl HashFunction {
+  // Whether the hash is a permutation over the field (cheap in circuits, slow natively)
+  pub fn is_algebraic(&self) -> bool {
+    match self {
+      HashFunction::Sha3_256 | HashFunction::Shake128 | HashFunction::Keccak256 => false,
+      #[cfg(feature = "poseidon")]
+      HashFunction::Poseidon | HashFunction::Poseidon2 => true,
+      #[cfg(feature = "griffin")]
+      HashFunction::Griffin => true,
+    }
+  }
+
+  // Recommended hash for a use case and verification target among those compiled in
+  // Circuit targets get Poseidon2 (feature `poseidon`); the simplified Poseidon and Griffin
+  // hashes read only a 48-byte prefix of their input and are never recommended. Without
+  // `poseidon` the circuit recommendation falls back to SHA3-256
+  pub fn recommended(use_case: HashUseCase, target: HashTarget) -> HashFunction {
+    match (target, use_case) {
+      (HashTarget::Native, _) => HashFunction::Sha3_256,
+      // Transcript challenges are derived off-chain by the prover and verifier alike
+      (HashTarget::Evm, HashUseCase::Transcript) => HashFunction::Sha3_256,
+      (HashTarget::Evm, _) => HashFunction::Keccak256,
+      #[cfg(feature = "poseidon")]
+      (HashTarget::Circuit, _) => HashFunction::Poseidon2,
+      #[cfg(not(feature = "poseidon"))]
+      (HashTarget::Circuit, _) => HashFunction::Sha3_256,
+    }
+  }
+}
+
+// Measured throughput of one hash function on one input size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HashBenchmark {
+  pub hash_function: HashFunction,
+  pub input_bytes: usize,
+  pub iterations: u32,
+  pub elapsed: Duration,
+}
+
+impl HashBenchmark {
+  pub fn hashes_per_second(&self) -> f64 {
+    self.iterations as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+  }
+
+  pub fn bytes_per_second(&self) -> f64 {
+    self.hashes_per_second() * self.input_bytes as f64
+  }
+}
+
+// Time spent on each (hash function, input size) pair by Hash::benchmark_all
+pub const BENCHMARK_DURATION: Duration = Duration::from_millis(20);
+
 // Hash function wrapper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Hash {
   algorithm: HashFunction,
 }
@@ -31,13 +203,43 @@ impl Hash {
     Self { algorithm }
   }
 
+  // Hashes inputs of each size with every compiled-in hash function for about
+  // BENCHMARK_DURATION each, on this machine; one entry per pair, sizes in the given order
+  pub fn benchmark_all(input_sizes: &[usize]) -> Vec<HashBenchmark> {
+    let mut results = Vec::with_capacity(input_sizes.len() * HashFunction::ALL.len());
+    for &input_bytes in input_sizes {
+      let input: Vec<u8> = (0..input_bytes).map(|i| i as u8).collect();
+      for &hash_function in HashFunction::ALL {
+        let hash = Hash::new(hash_function);
+        let start = Instant::now();
+        let mut iterations = 0u32;
+        while iterations == 0 || start.elapsed() < BENCHMARK_DURATION {
+          black_box(hash.compute(black_box(&input)));
+          iterations += 1;
+        }
+        results.push(HashBenchmark {
+          hash_function,
+          input_bytes,
+          iterations,
+          elapsed: start.elapsed(),
+        });
+      }
+    }
+    results
+  }
+
   // Compute the hash of input data
   pub fn compute(&self, input: &[u8]) -> Vec<u8> {
     match self.algorithm {
       HashFunction::Sha3_256 => Self::sha3_256(input),
       HashFunction::Shake128 => Self::shake128(input),
+      #[cfg(feature = "poseidon")]
       HashFunction::Poseidon => Self::poseidon(input),
+      #[cfg(feature = "griffin")]
       HashFunction::Griffin => Self::griffin(input),
+      HashFunction::Keccak256 => Self::keccak256(input),
+      #[cfg(feature = "poseidon")]
+      HashFunction::Poseidon2 => poseidon2::hash(input),
     }
   }
 
@@ -48,6 +250,15 @@ impl Hash {
     hasher.finalize().to_vec()
   }
 
+  // Compute the hash of input data using Keccak-256 (original Keccak padding, as used by Ethereum)
+  fn keccak256(input: &[u8]) -> Vec<u8> {
+    let mut hasher = Keccak::v256();
+    hasher.update(input);
+    let mut output = [0u8; 32];
+    hasher.finalize(&mut output);
+    output.to_vec()
+  }
+
   // Compute the hash of input data using SHAKE-128
   fn shake128(input: &[u8]) -> Vec<u8> {
     let mut hasher = Shake128::default();
@@ -60,118 +271,73 @@ impl Hash {
   // Compute the hash of input data using Poseidon
   // This is a simplified implementation of the Poseidon hash function
   // For production use, consider using a dedicated crate like 'dusk-poseidon' or 'poseidon-primitives'
+  #[cfg(feature = "poseidon")]
   fn poseidon(input: &[u8]) -> Vec<u8> {
-    // Constants for Poseidon hash (simplified version)
-    const WIDTH: usize = 3; // State width (t)
-    const FULL_ROUNDS: usize = 8; // Number of full rounds
-    const PARTIAL_ROUNDS: usize = 57; // Number of partial rounds for width 3
-    
-    // Convert input to field elements (simplified)
-    let mut state = [0u128; WIDTH];
-    
-    // Initialize state with input bytes
-    for (i, chunk) in input.chunks(16).enumerate().take(WIDTH) {
-      let mut value = 0u128;
-      for (j, &byte) in chunk.iter().enumerate() {
-        value |= (byte as u128) << (8 * j);
-      }
-      state[i] = value % P; // This is safe as value is built from bytes and won't overflow
-    }
-    
+    sponge::legacy_block_hash::<PoseidonPermutation>(input)
+  }
+
+  // The simplified Poseidon permutation: x^5 S-boxes in full and partial rounds
+  #[cfg(feature = "poseidon")]
+  pub fn poseidon_permutation(state: &mut [u128; POSEIDON_WIDTH]) {
     // Simplified permutation (actual implementation would include S-box, MDS matrix, etc.)
     // This is just a placeholder to demonstrate the structure
-    for _ in 0..FULL_ROUNDS / 2 {
+    for _ in 0..POSEIDON_FULL_ROUNDS / 2 {
       // Full round (all state elements)
-      for i in 0..WIDTH {
+      for value in state.iter_mut() {
         // S-box: x^5 (simplified)
-        state[i] = Self::pow_mod(state[i], 5, P);
+        *value = Self::pow_mod(*value, 5, P);
       }
       // Mix layer would go here
     }
     
-    for _ in 0..PARTIAL_ROUNDS {
+    for _ in 0..POSEIDON_PARTIAL_ROUNDS {
       // Partial round (only first element)
       state[0] = Self::pow_mod(state[0], 5, P);
       // Mix layer would go here
     }
     
-    for _ in 0..FULL_ROUNDS / 2 {
+    for _ in 0..POSEIDON_FULL_ROUNDS / 2 {
       // Full round (all state elements)
-      for i in 0..WIDTH {
+      for value in state.iter_mut() {
         // S-box: x^5 (simplified)
-        state[i] = Self::pow_mod(state[i], 5, P);
+        *value = Self::pow_mod(*value, 5, P);
       }
       // Mix layer would go here
     }
-    
-    // Convert state to output bytes
-    let mut output = Vec::with_capacity(32);
-    for &value in &state[0..2] { // Use first two elements for output
-      for j in 0..16 {
-        output.push(((value >> (8 * j)) & 0xFF) as u8);
-      }
-    }
-    
-    // Ensure output is exactly 32 bytes
-    output.resize(32, 0);
-    output
   }
 
   // Compute the hash of input data using Griffin
   // This is a simplified implementation of the Griffin hash function
   // Griffin combines elements of Horst construction and Rescue-like SPN schemes
+  #[cfg(feature = "griffin")]
   fn griffin(input: &[u8]) -> Vec<u8> {
-    // Constants for Griffin hash (simplified version)
-    const WIDTH: usize = 3; // State width
-    const ROUNDS: usize = 10; // Number of rounds (simplified)
-    const SBOX_EXP: u32 = 5; // S-box exponent
-    const INV_SBOX_EXP: u128 = (P + 1) / 5; // Inverse S-box exponent (x^(1/5) ≡ x^((p+1)/5) mod p)
-    
-    // Convert input to field elements (simplified)
-    let mut state = [0u128; WIDTH];
-    
-    // Initialize state with input bytes
-    for (i, chunk) in input.chunks(16).enumerate().take(WIDTH) {
-      let mut value = 0u128;
-      for (j, &byte) in chunk.iter().enumerate() {
-        value |= (byte as u128) << (8 * j);
-      }
-      state[i] = Self::mod_reduce(value, P);
-    }
-    
-    // Simplified permutation
-    for round in 0..ROUNDS {
+    sponge::legacy_block_hash::<GriffinPermutation>(input)
+  }
+
+  // The Griffin permutation on field elements, shared with the in-circuit gadget
+  pub fn griffin_permutation(state: &mut [u128; GRIFFIN_WIDTH]) {
+    for round in 0..GRIFFIN_ROUNDS {
       // Apply S-box or inverse S-box based on round parity
-      for i in 0..WIDTH {
-        if round % 2 == 0 {
-          // Forward S-box: x^5
-          state[i] = Self::pow_mod(state[i], SBOX_EXP as u128, P);
-        } else {
-          // Inverse S-box: x^(1/5)
-          state[i] = Self::pow_mod(state[i], INV_SBOX_EXP, P);
-        }
+      for value in state.iter_mut() {
+        let exponent = if round % 2 == 0 { GRIFFIN_SBOX_EXP } else { GRIFFIN_INV_SBOX_EXP };
+        *value = Self::pow_mod(*value, exponent, P);
       }
       
       // Simple mixing function (actual implementation would use a proper MDS matrix)
-      if WIDTH > 1 {
-        let temp = state.clone();
-        for i in 0..WIDTH {
-          state[i] = Self::mod_add(state[i], temp[(i + 1) % WIDTH], P);
-        }
-      }
-    }
-    
-    // Convert state to output bytes
-    let mut output = Vec::with_capacity(32);
-    for &value in &state[0..2] { // Use first two elements for output
-      for j in 0..16 {
-        output.push(((value >> (8 * j)) & 0xFF) as u8);
+      let temp = *state;
+      for i in 0..GRIFFIN_WIDTH {
+        state[i] = Self::mod_add(state[i], temp[(i + 1) % GRIFFIN_WIDTH], P);
       }
     }
-    
-    // Ensure output is exactly 32 bytes
-    output.resize(32, 0);
-    output
+  }
+
+  // Two-to-one compression of field elements: the second state element of the permutation
+  // of (left, right, 0). Swapping the inputs leaves the first element unchanged (the
+  // simplified mixing commutes with that swap), so it cannot order Merkle siblings.
+  pub fn griffin_compress(left: u128, right: u128) -> u128 {
+    let mut state = [left % P, right % P, 0];
+    Self::griffin_permutation(&mut state);
+    state[1]
   }
   
   // Helper function for modular exponentiation
@@ -246,6 +412,22 @@ mod tests {
     assert_eq!(hash.len(), 32);
   }
 
+  #[test]
+  fn test_hash_function_ids() {
+    for &hash_function in HashFunction::ALL {
+      assert_eq!(HashFunction::from_id(hash_function.id()), Some(hash_function));
+    }
+    assert_eq!(HashFunction::from_id(0x00), None);
+  }
+
+  #[test]
+  fn test_keccak256() {
+    // Differs from SHA3-256 only in padding; this is Solidity's keccak256("")
+    let hash = Hash::new(HashFunction::Keccak256).compute(b"");
+    assert_eq!(hash[..4], [0xc5, 0xd2, 0x46, 0x01]);
+    assert_ne!(hash, Hash::new(HashFunction::Sha3_256).compute(b""));
+  }
+
   #[test]
   fn test_shake128() {
     let input = b"Loquat Test";
@@ -253,6 +435,7 @@ mod tests {
     assert_eq!(hash.len(), 32);
   }
 
+  #[cfg(feature = "poseidon")]
   #[test]
   fn test_poseidon() {
     let input = b"Loquat Test";
@@ -274,6 +457,7 @@ mod tests {
     assert_eq!(result, expected_u128);
   }
 
+  #[cfg(feature = "griffin")]
   #[test]
   fn test_griffin() {
     let input = b"Loquat Test";
@@ -294,4 +478,43 @@ mod tests {
     
     assert_eq!(result, expected_u128);
   }
+
+  #[cfg(all(feature = "poseidon", feature = "std"))]
+  #[test]
+  fn test_poseidon2() {
+    use crate::signature::loquat::Loquat;
+    use crate::signature::suite::Suite;
+    use crate::signature::LoquatConfig;
+
+    let hash = Hash::new(HashFunction::Poseidon2).compute(b"Loquat Test");
+    assert_eq!(hash.len(), 32);
+    assert_eq!(hash, crate::crypto::poseidon2::hash(b"Loquat Test"));
+
+    // A full suite: keys, signatures and their Merkle commitments all over Poseidon2
+    let config = LoquatConfig::uniform(HashFunction::Poseidon2);
+    assert_eq!(Suite::from_config(&config), Suite::LoquatPoseidon2L128);
+    let keypair = Loquat::keygen_with_config(&config);
+    let signature = Loquat::sign_with_config(keypair.secret_key, b"message", &config).unwrap();
+    assert!(Loquat::verify_encoded(&Loquat::encode_public_key(&keypair.public_key, &config), b"message", &signature.to_bytes()));
+  }
+
+  #[test]
+  fn test_benchmark_all() {
+    let results = Hash::benchmark_all(&[0, 256]);
+    assert_eq!(results.len(), 2 * HashFunction::ALL.len());
+    assert!(results.iter().all(|result| result.iterations > 0 && result.hashes_per_second() > 0.0));
+    assert_eq!(results[0].bytes_per_second(), 0.0);
+    assert_eq!(results.last().map(|result| result.input_bytes), Some(256));
+  }
+
+  #[test]
+  fn test_recommendations() {
+    for use_case in [HashUseCase::Message, HashUseCase::Merkle, HashUseCase::Transcript] {
+      assert_eq!(HashFunction::recommended(use_case, HashTarget::Native), HashFunction::Sha3_256);
+      assert!(!HashFunction::recommended(use_case, HashTarget::Native).is_algebraic());
+    }
+    assert_eq!(HashFunction::recommended(HashUseCase::Merkle, HashTarget::Evm), HashFunction::Keccak256);
+    #[cfg(feature = "poseidon")]
+    assert_eq!(HashFunction::recommended(HashUseCase::Merkle, HashTarget::Circuit), HashFunction::Poseidon2);
+  }
 }
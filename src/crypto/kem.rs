@@ -0,0 +1,126 @@
+// Key encapsulation and hybrid public key encryption
+// The Kem trait abstracts over post-quantum KEMs; MlKem768 wraps the FIPS 203 implementation
+// of the ml-kem crate. seal/open combine a KEM with ChaCha20-Poly1305: the sender
+// encapsulates a fresh shared secret to the recipient, derives the AEAD key as
+// SHA3-256(domain | shared secret | encapsulated key), and encrypts under a random nonce.
+// Binding the encapsulated key into the derivation ties each ciphertext to its encapsulation.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{EncodedSizeUser, KemCore};
+use crate::crypto::test_rng;
+use rand::Rng;
+use sha3::{Digest, Sha3_256};
+
+// Domain separation of the AEAD key derivation
+const KDF_DOMAIN: &[u8] = b"loquat-vc-hybrid-encryption";
+
+// ChaCha20-Poly1305 nonce length
+pub const NONCE_BYTES: usize = 12;
+
+// Key encapsulation mechanism with byte-encoded keys and ciphertexts
+pub trait Kem {
+  // Algorithm identifier recorded next to ciphertexts
+  const ALGORITHM: &'static str;
+
+  // Generates a (decapsulation key, encapsulation key) pair
+  fn generate() -> (Vec<u8>, Vec<u8>);
+
+  // Encapsulates a fresh shared secret to an encapsulation key, returning (ciphertext, secret)
+  fn encapsulate(public_key: &[u8]) -> Option<(Vec<u8>, [u8; 32])>;
+
+  // Recovers the shared secret from a ciphertext
+  fn decapsulate(secret_key: &[u8], ciphertext: &[u8]) -> Option<[u8; 32]>;
+}
+
+// ML-KEM-768 (NIST security category 3)
+pub struct MlKem768;
+
+impl Kem for MlKem768 {
+  const ALGORITHM: &'static str = "ML-KEM-768";
+
+  fn generate() -> (Vec<u8>, Vec<u8>) {
+    let (dk, ek) = ml_kem::MlKem768::generate(&mut test_rng::source());
+    (dk.as_bytes().to_vec(), ek.as_bytes().to_vec())
+  }
+
+  fn encapsulate(public_key: &[u8]) -> Option<(Vec<u8>, [u8; 32])> {
+    let encoded = public_key.try_into().ok()?;
+    let ek = <ml_kem::MlKem768 as KemCore>::EncapsulationKey::from_bytes(encoded);
+    let (ciphertext, shared) = ek.encapsulate(&mut test_rng::source()).ok()?;
+    Some((ciphertext.to_vec(), shared.into()))
+  }
+
+  fn decapsulate(secret_key: &[u8], ciphertext: &[u8]) -> Option<[u8; 32]> {
+    let encoded = secret_key.try_into().ok()?;
+    let dk = <ml_kem::MlKem768 as KemCore>::DecapsulationKey::from_bytes(encoded);
+    let shared = dk.decapsulate(ciphertext.try_into().ok()?).ok()?;
+    Some(shared.into())
+  }
+}
+
+// Hybrid ciphertext: KEM encapsulation, AEAD nonce and AEAD ciphertext
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedBox {
+  pub encapsulated_key: Vec<u8>,
+  pub nonce: [u8; NONCE_BYTES],
+  pub ciphertext: Vec<u8>,
+}
+
+fn aead_key(shared: &[u8; 32], encapsulated_key: &[u8]) -> ChaCha20Poly1305 {
+  let mut hasher = Sha3_256::new();
+  hasher.update(KDF_DOMAIN);
+  hasher.update(shared);
+  hasher.update(encapsulated_key);
+  ChaCha20Poly1305::new(Key::from_slice(&hasher.finalize()))
+}
+
+// Encrypts to a recipient encapsulation key; the associated data is authenticated, not encrypted
+pub fn seal<K: Kem>(recipient_pk: &[u8], plaintext: &[u8], aad: &[u8]) -> Option<SealedBox> {
+  let (encapsulated_key, shared) = K::encapsulate(recipient_pk)?;
+  let nonce: [u8; NONCE_BYTES] = test_rng::source().gen();
+  let ciphertext = aead_key(&shared, &encapsulated_key)
+    .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+    .ok()?;
+  Some(SealedBox {
+    encapsulated_key,
+    nonce,
+    ciphertext,
+  })
+}
+
+// Decrypts a sealed box; None if the key, ciphertext or associated data do not match
+pub fn open<K: Kem>(recipient_sk: &[u8], sealed: &SealedBox, aad: &[u8]) -> Option<Vec<u8>> {
+  let shared = K::decapsulate(recipient_sk, &sealed.encapsulated_key)?;
+  aead_key(&shared, &sealed.encapsulated_key)
+    .decrypt(Nonce::from_slice(&sealed.nonce), Payload { msg: &sealed.ciphertext, aad })
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_ml_kem_round_trip() {
+    let (sk, pk) = MlKem768::generate();
+    let (ciphertext, shared) = MlKem768::encapsulate(&pk).unwrap();
+    assert_eq!(MlKem768::decapsulate(&sk, &ciphertext), Some(shared));
+    assert!(MlKem768::encapsulate(&pk[1..]).is_none());
+  }
+
+  #[test]
+  fn test_seal_and_open() {
+    let (sk, pk) = MlKem768::generate();
+    let sealed = seal::<MlKem768>(&pk, b"credential", b"header").unwrap();
+    assert_eq!(open::<MlKem768>(&sk, &sealed, b"header"), Some(b"credential".to_vec()));
+
+    // Wrong associated data, recipient or tampered ciphertext fail to open
+    assert!(open::<MlKem768>(&sk, &sealed, b"other").is_none());
+    assert!(open::<MlKem768>(&MlKem768::generate().0, &sealed, b"header").is_none());
+    let mut tampered = sealed;
+    tampered.ciphertext[0] ^= 1;
+    assert!(open::<MlKem768>(&sk, &tampered, b"header").is_none());
+  }
+}
@@ -2,10 +2,16 @@
 // Legendre Symbol Computation: Efficiently determines if a value is a quadratic residue.
 // Key Generation: Generates a secret key from a finite field.
 // Evaluation Function: Computes PRF outputs based on the secret key.
+// Power Residue PRF: Generalizes the Legendre symbol to k-th power residue symbols for k | P - 1,
+// so each evaluation yields an element of Z_k (log2 k bits) instead of a single bit.
 
+#[cfg(feature = "std")]
+use crate::crypto::test_rng;
+#[cfg(feature = "std")]
 use rand::Rng;
-
-const P: u128 = (1 << 127) - 1;
+use crate::utils::encoding::Redacted;
+use crate::utils::field_operations::P;
+use std::fmt;
 
 // Safe modular addition to avoid overflow
 fn mod_add(a: u128, b: u128, modulus: u128) -> u128 {
@@ -67,42 +73,61 @@ fn mod_pow(base: u128, exp: u128, modulus: u128) -> u128 {
 
 pub struct LegendrePRF {
   secret_key: u128,
+  modulus: u128,
+}
+
+impl fmt::Debug for LegendrePRF {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LegendrePRF").field("secret_key", &Redacted).finish()
+  }
 }
 
 impl LegendrePRF {
   // Generate a new secret key
+  #[cfg(feature = "std")]
   pub fn new() -> Self {
-    let mut rng = rand::thread_rng();
+    let mut rng = test_rng::source();
     let sk = rng.gen_range(1..P);
-    Self { secret_key: sk }
+    Self::with_key(sk)
   }
   
   // Initialize LegendrePRF with a provided secret key
   pub fn with_key(key: u128) -> Self {
+    Self::with_key_mod(key, P)
+  }
+
+  // Initialize LegendrePRF over the prime field of another modulus
+  pub fn with_key_mod(key: u128, modulus: u128) -> Self {
     // Ensure the provided key is within the prime field
-    Self { secret_key: key % P }
+    Self { secret_key: key % modulus, modulus }
   }
   
   // Alias for new() to maintain compatibility with existing code
+  #[cfg(feature = "std")]
   pub fn keygen() -> Self {
     Self::new()
   }
 
   // Compute the Legendre symbol of a value in a prime field
   pub fn legendre_symbol(a: u128) -> i8 {
-    if a == 0 {
+    Self::legendre_symbol_mod(a, P)
+  }
+
+  // Legendre symbol modulo another odd prime
+  pub fn legendre_symbol_mod(a: u128, modulus: u128) -> i8 {
+    if a.is_multiple_of(modulus) {
       return 0;
     }
 
-    let exp = mod_sub(P, 1, P) / 2;
-    let result = mod_pow(a, exp, P);
+    let exp = mod_sub(modulus, 1, modulus) / 2;
+    let result = mod_pow(a, exp, modulus);
     if result == 1 { 1 } else { -1 }
   }
 
   // Evaluate the PRF: L(K, x) = (K + x / P)
   pub fn evaluate(&self, x: u128) -> u8 {
-    let k_x = mod_add(self.secret_key, x, P);
-    match Self::legendre_symbol(k_x) {
+    let k_x = mod_add(self.secret_key, x, self.modulus);
+    match Self::legendre_symbol_mod(k_x, self.modulus) {
       1 => 0,
       -1 => 1,
       _ => panic!("Invalid Legendre symbol"),
@@ -110,6 +135,105 @@ impl LegendrePRF {
   }
 }
 
+// Generator of the multiplicative group of the field
+const GENERATOR: u128 = 43;
+
+// Largest residue degree supported; evaluation keeps a table of the k-th roots of unity
+pub const MAX_RESIDUE_DEGREE: u32 = 1 << 16;
+
+// Whether k-th power residue symbols are defined and supported for this k
+pub fn is_valid_residue_degree(k: u32) -> bool {
+  (2..=MAX_RESIDUE_DEGREE).contains(&k) && (P - 1).is_multiple_of(k as u128)
+}
+
+// PRF evaluation: L_k(K, x) = log_zeta((K + x)^((P - 1) / k)) for a fixed primitive k-th root zeta
+pub struct PowerResiduePRF {
+  secret_key: u128,
+  degree: u32,
+  roots: Vec<u128>, // zeta^i for i in 0..k
+}
+
+impl fmt::Debug for PowerResiduePRF {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("PowerResiduePRF").field("secret_key", &Redacted).field("degree", &self.degree).finish()
+  }
+}
+
+impl PowerResiduePRF {
+  // Generate a new secret key; returns None if k does not divide P - 1
+  #[cfg(feature = "std")]
+  pub fn new(degree: u32) -> Option<Self> {
+    let mut rng = test_rng::source();
+    Self::with_key(rng.gen_range(1..P), degree)
+  }
+
+  // Initialize PowerResiduePRF with a provided secret key
+  pub fn with_key(key: u128, degree: u32) -> Option<Self> {
+    if !is_valid_residue_degree(degree) {
+      return None;
+    }
+    let zeta = mod_pow(GENERATOR, (P - 1) / degree as u128, P);
+    let mut roots = Vec::with_capacity(degree as usize);
+    let mut root = 1;
+    for _ in 0..degree {
+      roots.push(root);
+      root = mod_mul(root, zeta, P);
+    }
+    Some(Self {
+      secret_key: key % P,
+      degree,
+      roots,
+    })
+  }
+
+  pub fn degree(&self) -> u32 {
+    self.degree
+  }
+
+  // Compute the k-th power residue symbol of a value as an index in 0..k, or None for zero
+  pub fn residue_symbol(&self, a: u128) -> Option<u32> {
+    if a.is_multiple_of(P) {
+      return None;
+    }
+    let symbol = mod_pow(a, (P - 1) / self.degree as u128, P);
+    self.roots.iter().position(|root| *root == symbol).map(|index| index as u32)
+  }
+
+  // Evaluate the PRF; for k = 2 this matches LegendrePRF::evaluate
+  pub fn evaluate(&self, x: u128) -> u32 {
+    let k_x = mod_add(self.secret_key, x, P);
+    self.residue_symbol(k_x).expect("Invalid power residue symbol")
+  }
+}
+
+// PRF used to derive a signature's pseudorandom symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrfVariant {
+  Legendre,
+  PowerResidue(u32), // Residue degree k
+}
+
+impl PrfVariant {
+  pub fn is_valid(&self) -> bool {
+    match self {
+      PrfVariant::Legendre => true,
+      PrfVariant::PowerResidue(k) => is_valid_residue_degree(*k),
+    }
+  }
+
+  // Bits of PRF output produced by one evaluation
+  pub fn bits_per_evaluation(&self) -> f64 {
+    match self {
+      PrfVariant::Legendre => 1.0,
+      PrfVariant::PowerResidue(k) => (*k as f64).log2(),
+    }
+  }
+
+  // Evaluations needed to produce the given number of output bits
+  pub fn evaluations_for(&self, output_bits: u32) -> u32 {
+    (output_bits as f64 / self.bits_per_evaluation()).ceil() as u32
+  }
+}
 
 #[cfg(test)]
 mod tests {
@@ -133,6 +257,7 @@ mod tests {
     assert_eq!(LegendrePRF::legendre_symbol(a2), -1);
   }
 
+  #[cfg(feature = "std")]
   #[test]
   fn test_legendre_prf() {
     let prf = LegendrePRF::keygen();
@@ -154,4 +279,34 @@ mod tests {
     
     assert_eq!(output, expected, "PRF output should match expected value");
   }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_power_residue_prf() {
+    assert!(PowerResiduePRF::with_key(1, 5).is_none());
+    assert!(!is_valid_residue_degree(1));
+
+    // Degree 2 agrees with the Legendre PRF
+    let legendre = LegendrePRF::keygen();
+    let quadratic = PowerResiduePRF::with_key(legendre.secret_key, 2).unwrap();
+    for x in 0..16 {
+      assert_eq!(quadratic.evaluate(x) as u8, legendre.evaluate(x));
+    }
+
+    // Symbols are multiplicative: chi(ab) = chi(a) + chi(b) mod k
+    let prf = PowerResiduePRF::new(54).unwrap();
+    let (a, b) = (12345u128, 678910u128);
+    let sum = (prf.residue_symbol(a).unwrap() + prf.residue_symbol(b).unwrap()) % 54;
+    assert_eq!(prf.residue_symbol(mod_mul(a, b, P)), Some(sum));
+    assert_eq!(prf.residue_symbol(mod_pow(a, 54, P)), Some(0));
+    assert_eq!(prf.residue_symbol(0), None);
+    assert!((0..32).all(|x| prf.evaluate(x) < 54));
+  }
+
+  #[test]
+  fn test_prf_variant_evaluations() {
+    assert_eq!(PrfVariant::Legendre.evaluations_for(128), 128);
+    assert_eq!(PrfVariant::PowerResidue(54).evaluations_for(128), 23);
+    assert!(!PrfVariant::PowerResidue(4).is_valid());
+  }
 }
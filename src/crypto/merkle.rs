@@ -1,93 +1,509 @@
+// Merkle trees over fixed-size digests
+// MerkleTree is generic over a MerkleHasher, which fixes the digest type (and so its size)
+// at compile time and combines two children into their parent. Nodes are stored flat, once
+// per level with the leaves as level 0, and paths can be checked straight from their wire
+// encoding (verify_encoded_path) without decoding them first. HashFunction is the hasher of
+// the signature suites; its nodes are 32-byte big-endian digests, and the BigUint
+// constructor and proof functions on MerkleTree<HashFunction> keep the original API (and
+// roots) for callers that store leaves and roots as integers.
+
 use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::utils::constant_time;
 use num_bigint::BigUint;
-use std::collections::VecDeque;
+use std::fmt::Debug;
 
-// A Merkle Tree struct that supports SNARK-friendly hashing
-#[derive(Debug, Clone)]
-pub struct MerkleTree {
-  leaves: Vec<BigUint>,
-  tree: Vec<Vec<BigUint>>, // Tree layers
-  hash_function: HashFunction,
+// Combines two child digests into their parent
+pub trait MerkleHasher {
+  const DIGEST_BYTES: usize;
+  type Digest: Copy + Eq + Debug + AsRef<[u8]> + for<'a> TryFrom<&'a [u8]>;
+
+  fn hash_two(&self, left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
 }
 
-impl MerkleTree {
-  // Constructs a new Merkle Tree from a list of leaves using the specified hash function
-  pub fn new(leaves: Vec<BigUint>, hash_function: HashFunction) -> Self {
-    let mut tree = vec![];
-    let mut level = leaves.clone();
+// Keccak256 nodes are hashed as keccak256(abi.encodePacked(bytes32 a, bytes32 b)) so that roots
+// and proofs can be checked by Solidity; other hash functions hash the minimal big-endian
+// encodings of the two children (leading zero bytes dropped, zero encoded as one byte)
+impl MerkleHasher for HashFunction {
+  const DIGEST_BYTES: usize = 32;
+  type Digest = [u8; 32];
+
+  fn hash_two(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let data = match self {
+      HashFunction::Keccak256 => [&left[..], &right[..]].concat(),
+      _ => [minimal_be(left), minimal_be(right)].concat(),
+    };
+    let hash = Hash::new(*self).compute(&data);
+    hash.try_into().expect("Hash functions output 32 bytes")
+  }
+}
+
+fn minimal_be(digest: &[u8; 32]) -> &[u8] {
+  let start = digest.iter().position(|&byte| byte != 0).unwrap_or(31);
+  &digest[start..]
+}
 
+// Siblings from the leaf up, each with the side flag of MerkleTree::path
+pub type MerklePath<D> = Vec<(D, bool)>;
+
+// Per tree level, the sibling of each node being reconstructed (None where it is carried up)
+type LevelSiblings<D> = Vec<(usize, Option<(D, bool)>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree<H: MerkleHasher = HashFunction> {
+  levels: Vec<Vec<H::Digest>>, // Leaves first, the root last; empty for no leaves
+  hasher: H,
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+  // Builds the tree bottom-up; the last node of an odd level is carried up unchanged
+  pub fn from_leaves(leaves: Vec<H::Digest>, hasher: H) -> Self {
+    let mut levels = vec![];
+    let mut level = leaves;
     while level.len() > 1 {
-      let mut next_level = vec![];
-      for chunk in level.chunks(2) {
-        let parent_hash = match chunk.len() {
-          2 => MerkleTree::hash_two(&chunk[0], &chunk[1], &hash_function),
-          1 => chunk[0].clone(), // Carry over if odd number of leaves
+      let next_level = level
+        .chunks(2)
+        .map(|chunk| match chunk {
+          [left, right] => hasher.hash_two(left, right),
+          [single] => *single,
           _ => unreachable!(),
-        };
-        next_level.push(parent_hash);
-      }
-      tree.push(level);
+        })
+        .collect();
+      levels.push(level);
       level = next_level;
     }
-
     if !level.is_empty() {
-      tree.push(level);
+      levels.push(level);
     }
+    Self { levels, hasher }
+  }
 
-    Self {
-      leaves,
-      tree,
-      hash_function,
-    }
+  pub fn hasher(&self) -> &H {
+    &self.hasher
   }
 
-  // Computes the root of the Merkle tree
-  pub fn root(&self) -> Option<BigUint> {
-    self.tree.last().map(|level| level[0].clone())
+  pub fn len(&self) -> usize {
+    self.levels.first().map_or(0, Vec::len)
   }
 
-  // Generates a Merkle proof for a given leaf index
-  pub fn generate_proof(&self, index: usize) -> Option<Vec<(BigUint, bool)>> {
-    if index >= self.leaves.len() {
+  pub fn is_empty(&self) -> bool {
+    self.levels.is_empty()
+  }
+
+  pub fn leaf(&self, index: usize) -> Option<&H::Digest> {
+    self.levels.first()?.get(index)
+  }
+
+  pub fn root_digest(&self) -> Option<H::Digest> {
+    self.levels.last().map(|level| level[0])
+  }
+
+  // Siblings from the leaf up, flag set when the running hash is on the left; levels where
+  // the node was carried up contribute no sibling
+  pub fn path(&self, index: usize) -> Option<Vec<(H::Digest, bool)>> {
+    if index >= self.len() {
       return None;
     }
-
-    let mut proof = vec![];
+    let mut path = vec![];
     let mut idx = index;
-    for level in &self.tree[..self.tree.len() - 1] {
-      let sibling_index = if idx % 2 == 0 { idx + 1 } else { idx - 1 };
-
+    for level in &self.levels[..self.levels.len() - 1] {
+      let sibling_index = idx ^ 1;
       if sibling_index < level.len() {
-          proof.push((level[sibling_index].clone(), idx % 2 == 0));
+        path.push((level[sibling_index], idx.is_multiple_of(2)));
       }
       idx /= 2;
     }
+    Some(path)
+  }
+
+  pub fn verify_path(hasher: &H, root: &H::Digest, leaf: &H::Digest, path: &[(H::Digest, bool)]) -> bool {
+    let computed = path
+      .iter()
+      .fold(*leaf, |hash, (sibling, is_left)| Self::step(hasher, &hash, sibling, *is_left));
+    constant_time::bytes_eq(computed.as_ref(), root.as_ref())
+  }
 
+  // Checks a path in the utils::wire layout (depth byte, then per level a side flag and the
+  // sibling) in place; the encoding must be exactly one path long
+  pub fn verify_encoded_path(hasher: &H, root: &H::Digest, leaf: &H::Digest, encoded: &[u8]) -> bool {
+    let (depth, steps) = match encoded.split_first() {
+      Some((&depth, steps)) => (depth as usize, steps),
+      None => return false,
+    };
+    let step_bytes = 1 + H::DIGEST_BYTES;
+    if steps.len() != depth * step_bytes {
+      return false;
+    }
+    let mut hash = *leaf;
+    for step in steps.chunks_exact(step_bytes) {
+      let is_left = match step[0] {
+        0 => false,
+        1 => true,
+        _ => return false,
+      };
+      let sibling = match H::Digest::try_from(&step[1..]) {
+        Ok(sibling) => sibling,
+        Err(_) => return false,
+      };
+      hash = Self::step(hasher, &hash, &sibling, is_left);
+    }
+    constant_time::bytes_eq(hash.as_ref(), root.as_ref())
+  }
+
+  fn step(hasher: &H, hash: &H::Digest, sibling: &H::Digest, is_left: bool) -> H::Digest {
+    if is_left {
+      hasher.hash_two(hash, sibling)
+    } else {
+      hasher.hash_two(sibling, hash)
+    }
+  }
+
+  // Authentication nodes for several leaves at once: every sibling their paths need that is
+  // not itself on one of the paths, once, from the leaves up and by position within a level.
+  // The order depends on the set of indices alone, so verifiers replay it from the indices
+  pub fn multi_path(&self, indices: &[usize]) -> Option<Vec<H::Digest>> {
+    let mut known = indices.to_vec();
+    known.sort_unstable();
+    known.dedup();
+    if known.last().is_some_and(|&index| index >= self.len()) {
+      return None;
+    }
+    let mut nodes = vec![];
+    for level in &self.levels[..self.levels.len().saturating_sub(1)] {
+      let mut parents = Vec::with_capacity(known.len());
+      let mut i = 0;
+      while i < known.len() {
+        let sibling = known[i] ^ 1;
+        if known.get(i + 1) == Some(&sibling) {
+          i += 1;
+        } else if sibling < level.len() {
+          nodes.push(level[sibling]);
+        }
+        parents.push(known[i] / 2);
+        i += 1;
+      }
+      known = parents;
+    }
+    Some(nodes)
+  }
+
+  // The path of each of the leaves (index, digest) of a tree of len leaves, in the order given,
+  // from the nodes multi_path gives for their indices; None if the leaves disagree or the
+  // nodes are not exactly those. The paths still have to be verified against the root
+  pub fn expand_multi_path(hasher: &H, len: usize, leaves: &[(usize, H::Digest)], nodes: &[H::Digest]) -> Option<Vec<MerklePath<H::Digest>>> {
+    let mut known = leaves.to_vec();
+    known.sort_unstable_by_key(|(index, _)| *index);
+    known.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+    if known.windows(2).any(|pair| pair[0].0 == pair[1].0) || known.last().is_some_and(|(index, _)| *index >= len) {
+      return None;
+    }
+
+    let mut nodes = nodes.iter();
+    let mut levels: Vec<LevelSiblings<H::Digest>> = vec![];
+    let mut level_len = len;
+    while level_len > 1 {
+      let mut siblings = Vec::with_capacity(known.len());
+      let mut parents = Vec::with_capacity(known.len());
+      let mut i = 0;
+      while i < known.len() {
+        let (index, digest) = known[i];
+        let is_left = index.is_multiple_of(2);
+        let sibling = match known.get(i + 1) {
+          Some(&(next, next_digest)) if is_left && next == index + 1 => {
+            siblings.push((next, Some((digest, false))));
+            i += 1;
+            Some(next_digest)
+          }
+          _ if (index ^ 1) < level_len => Some(*nodes.next()?),
+          _ => None,
+        };
+        siblings.push((index, sibling.map(|sibling| (sibling, is_left))));
+        let parent = match sibling {
+          Some(sibling) => Self::step(hasher, &digest, &sibling, is_left),
+          None => digest,
+        };
+        parents.push((index / 2, parent));
+        i += 1;
+      }
+      siblings.sort_unstable_by_key(|(index, _)| *index);
+      levels.push(siblings);
+      known = parents;
+      level_len = level_len.div_ceil(2);
+    }
+    if nodes.next().is_some() {
+      return None;
+    }
+
+    leaves
+      .iter()
+      .map(|(leaf_index, _)| {
+        let mut index = *leaf_index;
+        let mut path = vec![];
+        for level in &levels {
+          let position = level.binary_search_by_key(&index, |(i, _)| *i).ok()?;
+          path.extend(level[position].1);
+          index /= 2;
+        }
+        Some(path)
+      })
+      .collect()
+  }
+
+  // Proof that the tree over the first old_size leaves is a prefix of the tree over the first
+  // new_size leaves (RFC 6962 section 2.1.2; the carry-up construction used here splits every
+  // tree at the largest power of two below its size, exactly as RFC 6962 does)
+  pub fn consistency_path(&self, old_size: usize, new_size: usize) -> Option<Vec<H::Digest>> {
+    if old_size == 0 || old_size > new_size || new_size > self.len() {
+      return None;
+    }
+    let mut proof = vec![];
+    self.subproof(old_size, &self.levels[0][..new_size], true, &mut proof);
     Some(proof)
   }
 
-  // Verifies a Merkle proof
-  pub fn verify_proof(root: &BigUint, leaf: &BigUint, proof: &[(BigUint, bool)], hash_function: &HashFunction) -> bool {
-    let mut hash = leaf.clone();
-    for (sibling, is_left) in proof {
-      hash = if *is_left {
-        MerkleTree::hash_two(&hash, sibling, hash_function)
+  fn subproof(&self, m: usize, leaves: &[H::Digest], complete: bool, proof: &mut Vec<H::Digest>) {
+    let n = leaves.len();
+    if m == n {
+      if !complete {
+        proof.push(Self::subtree_root(&self.hasher, leaves));
+      }
+      return;
+    }
+
+    let k = split_point(n);
+    if m <= k {
+      self.subproof(m, &leaves[..k], complete, proof);
+      proof.push(Self::subtree_root(&self.hasher, &leaves[k..]));
+    } else {
+      self.subproof(m - k, &leaves[k..], false, proof);
+      proof.push(Self::subtree_root(&self.hasher, &leaves[..k]));
+    }
+  }
+
+  // Verifies a consistency proof between two roots (RFC 9162 section 2.1.4.2)
+  pub fn verify_consistency_path(
+    hasher: &H,
+    old_size: usize,
+    new_size: usize,
+    old_root: &H::Digest,
+    new_root: &H::Digest,
+    proof: &[H::Digest],
+  ) -> bool {
+    if old_size == 0 || old_size > new_size {
+      return false;
+    }
+    if old_size == new_size {
+      return proof.is_empty() && constant_time::bytes_eq(old_root.as_ref(), new_root.as_ref());
+    }
+
+    // A complete old tree is itself the first node of the path
+    let mut path = Vec::with_capacity(proof.len() + 1);
+    if old_size.is_power_of_two() {
+      path.push(*old_root);
+    }
+    path.extend_from_slice(proof);
+    let (first, rest) = match path.split_first() {
+      Some(split) => split,
+      None => return false,
+    };
+
+    let mut fn_ = old_size - 1;
+    let mut sn = new_size - 1;
+    while fn_ & 1 == 1 {
+      fn_ >>= 1;
+      sn >>= 1;
+    }
+
+    let mut fr = *first;
+    let mut sr = *first;
+    for node in rest {
+      if sn == 0 {
+        return false;
+      }
+      if fn_ & 1 == 1 || fn_ == sn {
+        fr = hasher.hash_two(node, &fr);
+        sr = hasher.hash_two(node, &sr);
+        while fn_ & 1 == 0 && fn_ != 0 {
+          fn_ >>= 1;
+          sn >>= 1;
+        }
+      } else {
+        sr = hasher.hash_two(&sr, node);
+      }
+      fn_ >>= 1;
+      sn >>= 1;
+    }
+
+    constant_time::bytes_eq(fr.as_ref(), old_root.as_ref()) & constant_time::bytes_eq(sr.as_ref(), new_root.as_ref()) && sn == 0
+  }
+
+  // Root of a subtree, computed top-down by splitting at the largest power of two below its size
+  fn subtree_root(hasher: &H, leaves: &[H::Digest]) -> H::Digest {
+    if leaves.len() == 1 {
+      return leaves[0];
+    }
+    let k = split_point(leaves.len());
+    hasher.hash_two(&Self::subtree_root(hasher, &leaves[..k]), &Self::subtree_root(hasher, &leaves[k..]))
+  }
+}
+
+// Largest power of two strictly less than n (n > 1)
+fn split_point(n: usize) -> usize {
+  n.next_power_of_two() / 2
+}
+
+// Root over leaves supplied one at a time, holding one subtree root per set bit of the leaf
+// count; finish gives the root MerkleTree::from_leaves computes over the same leaves
+#[derive(Debug, Clone)]
+pub struct StreamingRoot<H: MerkleHasher> {
+  hasher: H,
+  subtrees: Vec<(usize, H::Digest)>, // (leaf count, root), counts strictly decreasing
+  len: usize,
+}
+
+impl<H: MerkleHasher> StreamingRoot<H> {
+  pub fn new(hasher: H) -> Self {
+    Self {
+      hasher,
+      subtrees: vec![],
+      len: 0,
+    }
+  }
+
+  pub fn push(&mut self, leaf: H::Digest) {
+    let mut node = (1, leaf);
+    while let Some(&(count, left)) = self.subtrees.last() {
+      if count != node.0 {
+        break;
+      }
+      self.subtrees.pop();
+      node = (2 * count, self.hasher.hash_two(&left, &node.1));
+    }
+    self.subtrees.push(node);
+    self.len += 1;
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // The carry-up tree joins the complete subtrees right to left; None without leaves
+  pub fn finish(self) -> Option<H::Digest> {
+    let mut subtrees = self.subtrees.into_iter().rev();
+    let (_, last) = subtrees.next()?;
+    Some(subtrees.fold(last, |right, (_, left)| self.hasher.hash_two(&left, &right)))
+  }
+}
+
+// Fewest parents of a level worth hashing on several threads
+#[cfg(feature = "parallel")]
+const MIN_PARALLEL_PARENTS: usize = 1 << 10;
+
+#[cfg(feature = "parallel")]
+impl<H: MerkleHasher + Sync> MerkleTree<H>
+where
+  H::Digest: Send + Sync,
+{
+  // from_leaves with each level hashed on up to `workers` scoped threads; chunks hold an even
+  // number of nodes, so the pairing and the tree are those of from_leaves
+  pub fn from_leaves_parallel(leaves: Vec<H::Digest>, hasher: H, workers: usize) -> Self {
+    let mut levels = vec![];
+    let mut level = leaves;
+    while level.len() > 1 {
+      let parents = level.len().div_ceil(2);
+      let workers = workers.min(parents / MIN_PARALLEL_PARENTS).max(1);
+      let chunk = 2 * parents.div_ceil(workers);
+      let hash_chunk = |chunk: &[H::Digest]| -> Vec<H::Digest> {
+        chunk
+          .chunks(2)
+          .map(|pair| match pair {
+            [left, right] => hasher.hash_two(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+          })
+          .collect()
+      };
+      let next_level = if workers == 1 {
+        hash_chunk(&level)
       } else {
-        MerkleTree::hash_two(sibling, &hash, hash_function)
+        std::thread::scope(|scope| {
+          let handles: Vec<_> = level.chunks(chunk).map(|chunk| scope.spawn(move || hash_chunk(chunk))).collect();
+          handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Merkle worker panicked"))
+            .collect()
+        })
       };
+      levels.push(level);
+      level = next_level;
     }
-    hash == *root
+    if !level.is_empty() {
+      levels.push(level);
+    }
+    Self { levels, hasher }
+  }
+}
+
+// Integer leaves and roots, as used by the signature, commitment and registry encodings
+impl MerkleTree<HashFunction> {
+  // Constructs a new Merkle Tree from a list of leaves using the specified hash function;
+  // every leaf must fit in 32 bytes
+  pub fn new(leaves: Vec<BigUint>, hash_function: HashFunction) -> Self {
+    let leaves = leaves.iter().map(|leaf| Self::digest(leaf).expect("Merkle leaves must fit in 32 bytes")).collect();
+    Self::from_leaves(leaves, hash_function)
   }
 
-  // Hashes two values together using the specified hash function
-  fn hash_two(a: &BigUint, b: &BigUint, hash_function: &HashFunction) -> BigUint {
-    let mut data = vec![];
-    data.extend_from_slice(&a.to_bytes_be());
-    data.extend_from_slice(&b.to_bytes_be());
+  // Computes the root of the Merkle tree
+  pub fn root(&self) -> Option<BigUint> {
+    self.root_digest().map(|root| BigUint::from_bytes_be(&root))
+  }
+
+  // Generates a Merkle proof for a given leaf index
+  pub fn generate_proof(&self, index: usize) -> Option<Vec<(BigUint, bool)>> {
+    let path = self.path(index)?;
+    Some(path.iter().map(|(sibling, is_left)| (BigUint::from_bytes_be(sibling), *is_left)).collect())
+  }
+
+  // Verifies a Merkle proof; values wider than 32 bytes are never tree nodes
+  pub fn verify_proof(root: &BigUint, leaf: &BigUint, proof: &[(BigUint, bool)], hash_function: &HashFunction) -> bool {
+    let path: Option<Vec<_>> = proof.iter().map(|(sibling, is_left)| Some((Self::digest(sibling)?, *is_left))).collect();
+    match (Self::digest(root), Self::digest(leaf), path) {
+      (Some(root), Some(leaf), Some(path)) => Self::verify_path(hash_function, &root, &leaf, &path),
+      _ => false,
+    }
+  }
+
+  pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Option<Vec<BigUint>> {
+    let proof = self.consistency_path(old_size, new_size)?;
+    Some(proof.iter().map(|node| BigUint::from_bytes_be(node)).collect())
+  }
+
+  pub fn verify_consistency(
+    old_size: usize,
+    new_size: usize,
+    old_root: &BigUint,
+    new_root: &BigUint,
+    proof: &[BigUint],
+    hash_function: &HashFunction,
+  ) -> bool {
+    let proof: Option<Vec<_>> = proof.iter().map(Self::digest).collect();
+    match (Self::digest(old_root), Self::digest(new_root), proof) {
+      (Some(old_root), Some(new_root), Some(proof)) => {
+        Self::verify_consistency_path(hash_function, old_size, new_size, &old_root, &new_root, &proof)
+      }
+      _ => false,
+    }
+  }
 
-    // Use clone() on the reference to get an owned HashFunction
-    let hash = Hash::new(hash_function.clone()).compute(&data);
-    BigUint::from_bytes_be(&hash)
+  // Left-pads a value to a 32-byte node; None if it is wider
+  fn digest(value: &BigUint) -> Option<[u8; 32]> {
+    let bytes = value.to_bytes_be();
+    let mut out = [0u8; 32];
+    out.get_mut(32usize.checked_sub(bytes.len())?..)?.copy_from_slice(&bytes);
+    Some(out)
   }
 }
 
@@ -124,4 +540,186 @@ mod tests {
         let proof = tree.generate_proof(1).unwrap();
         assert!(!MerkleTree::verify_proof(&root, &leaves[3], &proof, &HashFunction::Sha3_256));
     }
+
+    #[test]
+    fn test_keccak_root_matches_solidity_encoding() {
+        let leaves = vec![BigUint::from(1u32), BigUint::from(2u32), BigUint::from(3u32)];
+        let tree = MerkleTree::new(leaves.clone(), HashFunction::Keccak256);
+
+        // keccak256(abi.encodePacked(bytes32(uint256(1)), bytes32(uint256(2))))
+        let mut packed = [0u8; 64];
+        packed[31] = 1;
+        packed[63] = 2;
+        let left = BigUint::from_bytes_be(&Hash::new(HashFunction::Keccak256).compute(&packed));
+        assert_eq!(BigUint::from_bytes_be(&tree.levels[1][0]), left);
+
+        let root = tree.root().unwrap();
+        let proof = tree.generate_proof(0).unwrap();
+        assert!(MerkleTree::verify_proof(&root, &leaves[0], &proof, &HashFunction::Keccak256));
+        assert!(!MerkleTree::verify_proof(&root, &leaves[0], &proof, &HashFunction::Sha3_256));
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let leaves: Vec<BigUint> = (0u32..12).map(BigUint::from).collect();
+        let tree = MerkleTree::new(leaves.clone(), HashFunction::Sha3_256);
+
+        for new_size in 1..=leaves.len() {
+            let new_root = MerkleTree::new(leaves[..new_size].to_vec(), HashFunction::Sha3_256).root().unwrap();
+            assert_eq!(BigUint::from_bytes_be(&MerkleTree::subtree_root(&HashFunction::Sha3_256, &tree.levels[0][..new_size])), new_root);
+
+            for old_size in 1..=new_size {
+                let old_root = MerkleTree::new(leaves[..old_size].to_vec(), HashFunction::Sha3_256).root().unwrap();
+                let proof = tree.consistency_proof(old_size, new_size).unwrap();
+                assert!(
+                    MerkleTree::verify_consistency(old_size, new_size, &old_root, &new_root, &proof, &HashFunction::Sha3_256),
+                    "{} -> {}", old_size, new_size
+                );
+            }
+        }
+        assert!(tree.consistency_proof(0, 3).is_none());
+        assert!(tree.consistency_proof(4, 13).is_none());
+    }
+
+    #[test]
+    fn test_rewritten_history_detected() {
+        let leaves: Vec<BigUint> = (0u32..7).map(BigUint::from).collect();
+        let old_root = MerkleTree::new(leaves[..3].to_vec(), HashFunction::Sha3_256).root().unwrap();
+
+        // The log operator rewrites leaf 1 after publishing the size-3 root
+        let mut rewritten = leaves.clone();
+        rewritten[1] = BigUint::from(99u32);
+        let tree = MerkleTree::new(rewritten, HashFunction::Sha3_256);
+        let new_root = tree.root().unwrap();
+        let proof = tree.consistency_proof(3, 7).unwrap();
+        assert!(!MerkleTree::verify_consistency(3, 7, &old_root, &new_root, &proof, &HashFunction::Sha3_256));
+
+        // Truncated proofs and mismatched sizes fail as well
+        let honest = MerkleTree::new(leaves, HashFunction::Sha3_256);
+        let new_root = honest.root().unwrap();
+        let proof = honest.consistency_proof(3, 7).unwrap();
+        assert!(!MerkleTree::verify_consistency(3, 7, &old_root, &new_root, &proof[1..], &HashFunction::Sha3_256));
+        assert!(!MerkleTree::verify_consistency(4, 7, &old_root, &new_root, &proof, &HashFunction::Sha3_256));
+    }
+
+    // Eight-byte digests: the sum and rotation are not a hash, but combine children in order
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Toy;
+
+    impl MerkleHasher for Toy {
+        const DIGEST_BYTES: usize = 8;
+        type Digest = [u8; 8];
+
+        fn hash_two(&self, left: &[u8; 8], right: &[u8; 8]) -> [u8; 8] {
+            let combined = u64::from_le_bytes(*left).rotate_left(7).wrapping_add(u64::from_le_bytes(*right));
+            combined.to_le_bytes()
+        }
+    }
+
+    #[test]
+    fn test_generic_hasher() {
+        let leaves: Vec<[u8; 8]> = (0u64..5).map(u64::to_le_bytes).collect();
+        let tree = MerkleTree::from_leaves(leaves.clone(), Toy);
+        let root = tree.root_digest().unwrap();
+        assert_eq!((tree.len(), tree.leaf(4)), (5, Some(&leaves[4])));
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(index).unwrap();
+            assert!(MerkleTree::verify_path(&Toy, &root, leaf, &path));
+        }
+        assert!(!MerkleTree::verify_path(&Toy, &root, &leaves[1], &tree.path(0).unwrap()));
+        assert!(tree.path(5).is_none());
+
+        let proof = tree.consistency_path(2, 5).unwrap();
+        let old_root = MerkleTree::from_leaves(leaves[..2].to_vec(), Toy).root_digest().unwrap();
+        assert!(MerkleTree::verify_consistency_path(&Toy, 2, 5, &old_root, &root, &proof));
+        assert!(MerkleTree::from_leaves(vec![], Toy).is_empty());
+    }
+
+    #[test]
+    fn test_streaming_root_matches_tree() {
+        for size in 1u64..=20 {
+            let leaves: Vec<[u8; 8]> = (0..size).map(u64::to_le_bytes).collect();
+            let mut streaming = StreamingRoot::new(Toy);
+            leaves.iter().for_each(|leaf| streaming.push(*leaf));
+            assert_eq!(streaming.len(), size as usize);
+            assert_eq!(streaming.finish(), MerkleTree::from_leaves(leaves, Toy).root_digest(), "{}", size);
+        }
+        assert!(StreamingRoot::new(HashFunction::Sha3_256).finish().is_none());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_tree_matches_sequential() {
+        for size in [1usize, 2, 3, 2048, 4097, 6001] {
+            let leaves: Vec<[u8; 32]> = (0..size).map(|i| [(i % 251) as u8, (i / 251) as u8].repeat(16).try_into().unwrap()).collect();
+            for workers in [1, 2, 3, 8] {
+                let parallel = MerkleTree::from_leaves_parallel(leaves.clone(), HashFunction::Sha3_256, workers);
+                assert_eq!(parallel, MerkleTree::from_leaves(leaves.clone(), HashFunction::Sha3_256), "{} {}", size, workers);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_paths() {
+        for size in [1u64, 2, 7, 16, 33] {
+            let leaves: Vec<[u8; 8]> = (0..size).map(u64::to_le_bytes).collect();
+            let tree = MerkleTree::from_leaves(leaves.clone(), Toy);
+            let root = tree.root_digest().unwrap();
+            let indices: Vec<usize> = [0, 3, 3, 4, 6, 15, 32].into_iter().filter(|&i| i < size as usize).collect();
+            let nodes = tree.multi_path(&indices).unwrap();
+            let opened: Vec<(usize, [u8; 8])> = indices.iter().map(|&i| (i, leaves[i])).collect();
+            let paths = MerkleTree::expand_multi_path(&Toy, size as usize, &opened, &nodes).unwrap();
+            for ((index, leaf), path) in opened.iter().zip(&paths) {
+                assert_eq!(Some(path), tree.path(*index).as_ref());
+                assert!(MerkleTree::verify_path(&Toy, &root, leaf, path));
+            }
+            // Shared nodes are sent once
+            let separate: usize = indices.iter().collect::<std::collections::BTreeSet<_>>().iter().map(|&&i| tree.path(i).unwrap().len()).sum();
+            assert!(nodes.len() <= separate);
+        }
+
+        // Neighbouring leaves need no node below their parent; malformed node lists are refused
+        let leaves: Vec<[u8; 8]> = (0..8u64).map(u64::to_le_bytes).collect();
+        let tree = MerkleTree::from_leaves(leaves.clone(), Toy);
+        let nodes = tree.multi_path(&[2, 3]).unwrap();
+        assert_eq!(nodes.len(), 2);
+        let opened = [(2, leaves[2]), (3, leaves[3])];
+        assert!(MerkleTree::expand_multi_path(&Toy, 8, &opened, &nodes[..1]).is_none());
+        assert!(MerkleTree::expand_multi_path(&Toy, 8, &opened, &[nodes.clone(), nodes.clone()].concat()).is_none());
+        assert!(MerkleTree::expand_multi_path(&Toy, 8, &[(2, leaves[2]), (2, leaves[3])], &nodes).is_none());
+        assert!(tree.multi_path(&[8]).is_none());
+    }
+
+    #[test]
+    fn test_encoded_paths() {
+        let leaves: Vec<BigUint> = (0u32..6).map(BigUint::from).collect();
+        let tree = MerkleTree::new(leaves.clone(), HashFunction::Sha3_256);
+        let root = tree.root_digest().unwrap();
+        let leaf = *tree.leaf(3).unwrap();
+
+        // Same bytes as utils::wire::write_path over the BigUint proof
+        let mut encoded = vec![];
+        crate::utils::wire::write_path(&mut encoded, &tree.generate_proof(3).unwrap());
+        assert!(MerkleTree::verify_encoded_path(&HashFunction::Sha3_256, &root, &leaf, &encoded));
+
+        let mut bad_flag = encoded.clone();
+        bad_flag[1] = 2;
+        assert!(!MerkleTree::verify_encoded_path(&HashFunction::Sha3_256, &root, &leaf, &bad_flag));
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(!MerkleTree::verify_encoded_path(&HashFunction::Sha3_256, &root, &leaf, &trailing));
+        assert!(!MerkleTree::verify_encoded_path(&HashFunction::Sha3_256, &root, &leaf, &encoded[..encoded.len() - 1]));
+        assert!(!MerkleTree::verify_encoded_path(&HashFunction::Sha3_256, &root, &leaf, &[]));
+    }
+
+    #[test]
+    fn test_integer_api_rejects_wide_values() {
+        let tree = MerkleTree::new(vec![BigUint::from(0u32), BigUint::from(1u32)], HashFunction::Sha3_256);
+        let root = tree.root().unwrap();
+        let proof = tree.generate_proof(0).unwrap();
+        assert!(MerkleTree::verify_proof(&root, &BigUint::from(0u32), &proof, &HashFunction::Sha3_256));
+        let wide = BigUint::from(1u8) << 256;
+        assert!(!MerkleTree::verify_proof(&(&root + &wide), &BigUint::from(0u32), &proof, &HashFunction::Sha3_256));
+        assert!(!MerkleTree::verify_proof(&root, &wide, &proof, &HashFunction::Sha3_256));
+    }
 }
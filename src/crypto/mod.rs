@@ -1,5 +1,24 @@
 pub mod legendre_prf;
+pub mod prf_bits;
+#[cfg(feature = "std")]
 pub mod snark;
 pub mod polynomial;
 pub mod merkle;
-pub mod hash_functions;
\ No newline at end of file
+pub mod hash_functions;
+#[cfg(feature = "poseidon")]
+pub mod poseidon2;
+pub mod sponge;
+pub mod goldilocks;
+#[cfg(feature = "std")]
+pub mod commitment;
+#[cfg(feature = "std")]
+pub mod oprf;
+#[cfg(feature = "std")]
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod test_rng;
+#[cfg(feature = "kem")]
+pub mod kem;
+
+pub use hash_functions::{Hash, HashFunction};
+pub use merkle::{MerkleHasher, MerklePath, MerkleTree, StreamingRoot};
@@ -0,0 +1,255 @@
+// Oblivious evaluation of the Legendre PRF
+// The client holds an input, the server holds the PRF key K, and the client learns
+// F_K(input) = (L_K(x_1), ..., L_K(x_n)) with x_j derived from the input, while the server
+// learns nothing about the input or the output. Each bit follows the MPC evaluation of
+// Grassi et al. (CCS 2016): K + x_j is additively shared between the parties (the server's share
+// is K, the client's is x_j), multiplied with a random square s = r^2 using a Beaver triple,
+// and only the client receives the product (K + x_j) * s, whose Legendre symbol is the PRF bit
+// and which reveals nothing else since s is a uniformly random square.
+//
+// Rounds:
+// 1. Preprocessing: a dealer hands each party one share of a triple (a, b, ab) and of s per bit
+// 2. Client -> server: BlindedRequest with the client's masked shares of K + x and s
+// 3. Server -> client: ServerResponse with the server's masked shares and its product shares
+// The protocol is secure against semi-honest parties assuming the dealer is honest and each
+// preprocessing batch is used once; the preprocessing types are consumed to enforce the latter.
+
+use crate::compact::field;
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::prf_bits;
+use crate::utils::encoding::Redacted;
+use crate::utils::field_operations::P;
+use crate::crypto::test_rng;
+use rand::Rng;
+use std::fmt;
+
+// Bits of PRF output per input
+pub const OPRF_OUTPUT_BITS: usize = 128;
+
+// Domain separation for deriving evaluation points from an input
+const POINT_DOMAIN: &[u8] = b"loquat-oprf-point";
+
+// One party's share of the correlated randomness for a single PRF bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TripleShare {
+  a: u128,
+  b: u128,
+  c: u128, // Share of a * b
+  s: u128, // Share of the random square
+}
+
+// One party's correlated randomness for a full evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preprocessing {
+  shares: Vec<TripleShare>,
+}
+
+// Splits a value into two uniformly random additive shares
+fn split(value: u128, rng: &mut impl Rng) -> (u128, u128) {
+  let share = rng.gen_range(0..P);
+  (share, field::sub(value, share))
+}
+
+// Deals one evaluation's worth of preprocessing as (client, server) shares
+pub fn deal() -> (Preprocessing, Preprocessing) {
+  let mut rng = test_rng::source();
+  let mut client = Vec::with_capacity(OPRF_OUTPUT_BITS);
+  let mut server = Vec::with_capacity(OPRF_OUTPUT_BITS);
+  for _ in 0..OPRF_OUTPUT_BITS {
+    let (a, b) = (rng.gen_range(0..P), rng.gen_range(0..P));
+    let r = rng.gen_range(1..P);
+    let (a_c, a_s) = split(a, &mut rng);
+    let (b_c, b_s) = split(b, &mut rng);
+    let (c_c, c_s) = split(field::mul(a, b), &mut rng);
+    let (s_c, s_s) = split(field::mul(r, r), &mut rng);
+    client.push(TripleShare { a: a_c, b: b_c, c: c_c, s: s_c });
+    server.push(TripleShare { a: a_s, b: b_s, c: c_s, s: s_s });
+  }
+  (Preprocessing { shares: client }, Preprocessing { shares: server })
+}
+
+// Field points at which the PRF is evaluated for an input
+fn points(input: &[u8]) -> Vec<u128> {
+  let hash = Hash::new(HashFunction::Sha3_256);
+  (0..OPRF_OUTPUT_BITS as u32)
+    .map(|j| field::from_be_bytes_mod(&hash.compute(&[POINT_DOMAIN, &j.to_be_bytes(), input].concat())))
+    .collect()
+}
+
+// Client message: masked shares d_c = x - a_c and e_c = s_c - b_c per bit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlindedRequest {
+  pub masked_inputs: Vec<u128>,
+  pub masked_squares: Vec<u128>,
+}
+
+// Server message: its masked shares and its shares of (K + x) * s per bit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerResponse {
+  pub masked_keys: Vec<u128>,
+  pub masked_squares: Vec<u128>,
+  pub product_shares: Vec<u128>,
+}
+
+// Client state kept between sending the request and receiving the response
+pub struct OprfClient {
+  preprocessing: Preprocessing,
+  request: BlindedRequest,
+}
+
+impl OprfClient {
+  // Blinds an input with the client's preprocessing
+  pub fn blind(input: &[u8], preprocessing: Preprocessing) -> (Self, BlindedRequest) {
+    let (masked_inputs, masked_squares) = points(input)
+      .into_iter()
+      .zip(&preprocessing.shares)
+      .map(|(x, share)| (field::sub(x, share.a), field::sub(share.s, share.b)))
+      .unzip();
+    let request = BlindedRequest {
+      masked_inputs,
+      masked_squares,
+    };
+    (
+      Self {
+        preprocessing,
+        request: request.clone(),
+      },
+      request,
+    )
+  }
+
+  // Unblinds the server response into the PRF output; None for a malformed response or
+  // the negligible event that some K + x_j is zero
+  pub fn finalize(self, response: &ServerResponse) -> Option<Vec<u8>> {
+    let count = self.preprocessing.shares.len();
+    if [&response.masked_keys, &response.masked_squares, &response.product_shares].iter().any(|v| v.len() != count) {
+      return None;
+    }
+
+    let mut bits = Vec::with_capacity(count);
+    for (j, share) in self.preprocessing.shares.iter().enumerate() {
+      let d = field::add(self.request.masked_inputs[j], response.masked_keys[j]);
+      let e = field::add(self.request.masked_squares[j], response.masked_squares[j]);
+      let own = field::add(share.c, field::add(field::mul(d, share.b), field::mul(e, share.a)));
+      bits.push(match field::legendre_symbol(field::add(own, response.product_shares[j])) {
+        1 => 0,
+        -1 => 1,
+        _ => return None,
+      });
+    }
+    Some(prf_bits::pack_bits(&bits))
+  }
+}
+
+// Server holding the PRF key
+pub struct OprfServer {
+  secret_key: u128,
+}
+
+impl fmt::Debug for OprfServer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("OprfServer").field("secret_key", &Redacted).finish()
+  }
+}
+
+impl OprfServer {
+  pub fn new() -> Self {
+    Self::with_key(test_rng::source().gen_range(1..P))
+  }
+
+  pub fn with_key(key: u128) -> Self {
+    Self { secret_key: key % P }
+  }
+
+  // Answers a blinded request with the server's preprocessing; None if the request does not
+  // match the preprocessing batch
+  pub fn respond(&self, request: &BlindedRequest, preprocessing: Preprocessing) -> Option<ServerResponse> {
+    let count = preprocessing.shares.len();
+    if request.masked_inputs.len() != count || request.masked_squares.len() != count {
+      return None;
+    }
+
+    let mut response = ServerResponse {
+      masked_keys: Vec::with_capacity(count),
+      masked_squares: Vec::with_capacity(count),
+      product_shares: Vec::with_capacity(count),
+    };
+    for (j, share) in preprocessing.shares.iter().enumerate() {
+      let d_s = field::sub(self.secret_key, share.a);
+      let e_s = field::sub(share.s, share.b);
+      let d = field::add(request.masked_inputs[j], d_s);
+      let e = field::add(request.masked_squares[j], e_s);
+      // [z * s] = [c] + d [b] + e [a] + d e, with the public d e term added by the server
+      let product = field::add(field::add(share.c, field::mul(d, share.b)), field::add(field::mul(e, share.a), field::mul(d, e)));
+      response.masked_keys.push(d_s);
+      response.masked_squares.push(e_s);
+      response.product_shares.push(product);
+    }
+    Some(response)
+  }
+
+  // Evaluates the PRF directly on an input, as the protocol computes it obliviously
+  pub fn evaluate(&self, input: &[u8]) -> Vec<u8> {
+    let bits: Vec<u8> = points(input)
+      .into_iter()
+      .map(|x| match field::legendre_symbol(field::add(self.secret_key, x)) {
+        -1 => 1,
+        _ => 0,
+      })
+      .collect();
+    prf_bits::pack_bits(&bits)
+  }
+}
+
+impl Default for OprfServer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::crypto::legendre_prf::LegendrePRF;
+
+  fn run(server: &OprfServer, input: &[u8]) -> Option<Vec<u8>> {
+    let (client_prep, server_prep) = deal();
+    let (client, request) = OprfClient::blind(input, client_prep);
+    let response = server.respond(&request, server_prep)?;
+    client.finalize(&response)
+  }
+
+  #[test]
+  fn test_oblivious_evaluation() {
+    let server = OprfServer::new();
+    let output = run(&server, b"alice@example.com").unwrap();
+    assert_eq!(output, server.evaluate(b"alice@example.com"));
+    assert_eq!(output.len(), OPRF_OUTPUT_BITS / 8);
+
+    // Outputs are stable per input and key, and differ across inputs and keys
+    assert_eq!(run(&server, b"alice@example.com").unwrap(), output);
+    assert_ne!(run(&server, b"bob@example.com").unwrap(), output);
+    assert_ne!(run(&OprfServer::new(), b"alice@example.com").unwrap(), output);
+
+    // Each bit is the Legendre PRF of the server key at the derived point
+    let prf = LegendrePRF::with_key(server.secret_key);
+    assert_eq!(prf.evaluate(points(b"alice@example.com")[0]), output[0] >> 7);
+  }
+
+  #[test]
+  fn test_requests_are_blinded() {
+    let server = OprfServer::new();
+    let (client_a, _) = deal();
+    let (client_b, _) = deal();
+    let (_, first) = OprfClient::blind(b"same input", client_a);
+    let (_, second) = OprfClient::blind(b"same input", client_b);
+    assert_ne!(first, second);
+
+    // A truncated response is rejected
+    let (client_prep, server_prep) = deal();
+    let (client, request) = OprfClient::blind(b"input", client_prep);
+    let mut response = server.respond(&request, server_prep).unwrap();
+    response.product_shares.pop();
+    assert!(client.finalize(&response).is_none());
+  }
+}
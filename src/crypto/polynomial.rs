@@ -2,18 +2,33 @@
 // Interpolation using Fast Fourier Transform (FFT)
 // Evaluation of polynomials over a finite field
 // Commitment scheme using univariate sumcheck
+//
+// All arithmetic goes through the fixed-size field of compact::field, so no intermediate
+// product overflows. Interpolation rejects repeated x-coordinates, for which no polynomial of
+// degree below the number of points need exist. Small point sets use Lagrange interpolation
+// in O(n^2); from FAST_INTERPOLATION_THRESHOLD points on, a subproduct tree with Karatsuba
+// multiplication and Newton division brings this to O(n^1.58 log n).
 
-// No unused imports
-use crate::utils::field_operations;
+use crate::compact::field;
+use crate::utils::field_operations::P;
+use std::collections::HashSet;
 
-// Prime field modulus (p = 2^127 - 1)
-const P: u128 = (1 << 127) - 1;
+// Number of points from which interpolation uses the subproduct tree
+pub const FAST_INTERPOLATION_THRESHOLD: usize = 128;
+
+// Operand length below which multiplication is schoolbook
+const KARATSUBA_THRESHOLD: usize = 32;
 
 // Modular subtraction helper to avoid underflow
 fn mod_sub(a: u128, b: u128, modulus: u128) -> u128 {
   (((a % modulus) + modulus) - (b % modulus)) % modulus
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpolationError {
+  DuplicateX(u128), // Reduced x-coordinate appearing more than once
+}
+
 // Represents a polynomial over a finite field
 #[derive(Debug, Clone)]
 pub struct Polynomial {
@@ -26,87 +41,209 @@ impl Polynomial {
     Self { coeffs }
   }
 
+  pub fn coefficients(&self) -> &[u128] {
+    &self.coeffs
+  }
+
   // Degree of the polynomial
   pub fn degree(&self) -> usize {
-    self.coeffs.len() - 1
+    self.coeffs.len().saturating_sub(1)
   }
 
-  // Evaluates the polynomial at a given point x
+  // Evaluates the polynomial at a given point x by Horner's rule
   pub fn evaluate(&self, x: u128) -> u128 {
-    let mut result = 0;
-    let mut power = 1;
-    for &coeff in &self.coeffs {
-      result = (result + coeff * power) % P;
-      power = (power * x) % P;
-    }
-    result
+    let x = field::reduce(x);
+    self.coeffs.iter().rev().fold(0, |acc, &coeff| field::add(field::mul(acc, x), field::reduce(coeff)))
   }
 
-  // Interpolates a polynomial from given points using Lagrange interpolation
-  pub fn interpolate(points: &[(u128, u128)]) -> Self {
-    let mut coeffs = vec![0; points.len()];
+  // Interpolates the polynomial of degree below points.len() through the given points
+  pub fn interpolate(points: &[(u128, u128)]) -> Result<Self, InterpolationError> {
+    let xs: Vec<u128> = points.iter().map(|&(x, _)| field::reduce(x)).collect();
+    let ys: Vec<u128> = points.iter().map(|&(_, y)| field::reduce(y)).collect();
+    let mut seen = HashSet::with_capacity(xs.len());
+    if let Some(&duplicate) = xs.iter().find(|x| !seen.insert(**x)) {
+      return Err(InterpolationError::DuplicateX(duplicate));
+    }
+    if xs.is_empty() {
+      return Ok(Self::new(vec![0]));
+    }
 
-    for (i, &(xi, yi)) in points.iter().enumerate() {
-      let mut num = vec![1];
-      let mut den = 1;
+    let mut coeffs = if xs.len() < FAST_INTERPOLATION_THRESHOLD {
+      Self::lagrange(&xs, &ys)
+    } else {
+      Self::subproduct_interpolation(&xs, &ys)
+    };
+    coeffs.resize(xs.len(), 0);
+    Ok(Self { coeffs })
+  }
 
-      for (j, &(xj, _)) in points.iter().enumerate() {
-        if i != j {
-          num = Polynomial::mul_poly(&num, &[mod_sub(0, xj, P), 1]); // (x - xj)
-          den = (den * mod_sub(xi, xj, P)) % P;
-        }
+  // O(n^2): divides M = prod (X - x_j) by each X - x_i once
+  fn lagrange(xs: &[u128], ys: &[u128]) -> Vec<u128> {
+    let vanishing = xs.iter().fold(vec![1], |acc, &x| Polynomial::mul_poly(&acc, &[mod_sub(0, x, P), 1]));
+    let mut coeffs = vec![0; xs.len()];
+    for (&xi, &yi) in xs.iter().zip(ys) {
+      // Synthetic division: basis = M / (X - xi), of degree n - 1
+      let mut basis = vec![0; xs.len()];
+      let mut carry = 0;
+      for k in (1..vanishing.len()).rev() {
+        carry = field::add(vanishing[k], field::mul(carry, xi));
+        basis[k - 1] = carry;
+      }
+      let denominator = Self::new(basis.clone()).evaluate(xi);
+      let scale = field::mul(yi, field::pow(denominator, P - 2));
+      for (c, b) in coeffs.iter_mut().zip(&basis) {
+        *c = field::add(*c, field::mul(*b, scale));
       }
-
-      let inv_den = mod_inv(den, P);
-      let scaled_num = num.iter().map(|&c| {
-        let c_yi = field_operations::mod_mul(c, yi, P);
-        field_operations::mod_mul(c_yi, inv_den, P)
-      }).collect::<Vec<u128>>();
-      coeffs = Polynomial::add_poly(&coeffs, &scaled_num);
     }
+    coeffs
+  }
 
-    Self { coeffs }
+  // O(M(n) log n): weights y_i / M'(x_i) from a multipoint evaluation of M' down the tree, then
+  // sum_i w_i * M / (X - x_i) assembled back up the tree
+  fn subproduct_interpolation(xs: &[u128], ys: &[u128]) -> Vec<u128> {
+    let tree = SubproductTree::build(xs);
+    let derivative: Vec<u128> = tree.poly.iter().enumerate().skip(1).map(|(i, &c)| field::mul(c, i as u128)).collect();
+    let mut denominators = Vec::with_capacity(xs.len());
+    tree.evaluate(&derivative, &mut denominators);
+    let weights: Vec<u128> = ys.iter().zip(&denominators).map(|(&y, &d)| field::mul(y, field::pow(d, P - 2))).collect();
+    tree.combine(&weights)
   }
 
   // Adds two polynomials
   fn add_poly(a: &[u128], b: &[u128]) -> Vec<u128> {
     let mut result = vec![0; a.len().max(b.len())];
-    for i in 0..a.len() {
-      result[i] = (result[i] + a[i]) % P;
+    for (i, &c) in a.iter().enumerate() {
+      result[i] = field::add(result[i], c);
     }
-    for i in 0..b.len() {
-      result[i] = (result[i] + b[i]) % P;
+    for (i, &c) in b.iter().enumerate() {
+      result[i] = field::add(result[i], c);
+    }
+    result
+  }
+
+  fn sub_poly(a: &[u128], b: &[u128]) -> Vec<u128> {
+    let mut result = a.to_vec();
+    result.resize(a.len().max(b.len()), 0);
+    for (i, &c) in b.iter().enumerate() {
+      result[i] = mod_sub(result[i], c, P);
     }
     result
   }
 
-  // Multiplies two polynomials using naive multiplication
+  // Multiplies two polynomials, by Karatsuba above KARATSUBA_THRESHOLD
   fn mul_poly(a: &[u128], b: &[u128]) -> Vec<u128> {
+    if a.is_empty() || b.is_empty() {
+      return vec![];
+    }
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+      let mut result = vec![0; a.len() + b.len() - 1];
+      for (i, &ai) in a.iter().enumerate() {
+        for (j, &bj) in b.iter().enumerate() {
+          result[i + j] = field::add(result[i + j], field::mul(ai, bj));
+        }
+      }
+      return result;
+    }
+
+    let half = a.len().max(b.len()) / 2;
+    let (a0, a1) = a.split_at(half.min(a.len()));
+    let (b0, b1) = b.split_at(half.min(b.len()));
+    let low = Self::mul_poly(a0, b0);
+    let high = Self::mul_poly(a1, b1);
+    let middle = Self::sub_poly(&Self::sub_poly(&Self::mul_poly(&Self::add_poly(a0, a1), &Self::add_poly(b0, b1)), &low), &high);
+
     let mut result = vec![0; a.len() + b.len() - 1];
-    for (i, &ai) in a.iter().enumerate() {
-      for (j, &bj) in b.iter().enumerate() {
-        result[i + j] = (result[i + j] + (ai * bj) % P) % P;
+    for (offset, part) in [(0, &low), (half, &middle), (2 * half, &high)] {
+      for (i, &c) in part.iter().enumerate() {
+        if c != 0 {
+          result[offset + i] = field::add(result[offset + i], c);
+        }
       }
     }
     result
   }
+
+  // g with f * g = 1 mod X^len by Newton iteration; f[0] must be non-zero
+  fn inverse_series(f: &[u128], len: usize) -> Vec<u128> {
+    let mut g = vec![field::pow(f[0], P - 2)];
+    let mut precision = 1;
+    while precision < len {
+      precision = (2 * precision).min(len);
+      let mut fg = Self::mul_poly(&f[..f.len().min(precision)], &g);
+      fg.truncate(precision);
+      // g <- g * (2 - f * g)
+      let mut correction: Vec<u128> = fg.iter().map(|&c| mod_sub(0, c, P)).collect();
+      correction[0] = field::add(correction[0], 2);
+      g = Self::mul_poly(&g, &correction);
+      g.truncate(precision);
+    }
+    g
+  }
+
+  // Remainder of a modulo the monic b
+  fn rem_poly(a: &[u128], b: &[u128]) -> Vec<u128> {
+    if a.len() < b.len() {
+      return a.to_vec();
+    }
+    let quotient_len = a.len() - b.len() + 1;
+    let a_rev: Vec<u128> = a.iter().rev().take(quotient_len).copied().collect();
+    let b_rev: Vec<u128> = b.iter().rev().copied().collect();
+    let mut quotient = Self::mul_poly(&a_rev, &Self::inverse_series(&b_rev, quotient_len));
+    quotient.truncate(quotient_len);
+    quotient.reverse();
+    let mut remainder = Self::sub_poly(a, &Self::mul_poly(b, &quotient));
+    remainder.truncate(b.len() - 1);
+    remainder
+  }
 }
 
-// Computes modular inverse using extended Euclidean algorithm
-fn mod_inv(a: u128, m: u128) -> u128 {
-  let mut mn = (m, a);
-  let mut xy = (0i128, 1i128);  // Explicitly use i128 to handle negative values
+// M = prod (X - x_i) over a range of points, with the products of both halves below it
+struct SubproductTree {
+  poly: Vec<u128>,
+  children: Option<Box<(SubproductTree, SubproductTree)>>,
+}
 
-  while mn.1 != 0 {
-    xy = (xy.1, xy.0 - (mn.0 / mn.1) as i128 * xy.1);
-    mn = (mn.1, mn.0 % mn.1);
+impl SubproductTree {
+  fn build(xs: &[u128]) -> Self {
+    if xs.len() == 1 {
+      return Self {
+        poly: vec![mod_sub(0, xs[0], P), 1],
+        children: None,
+      };
+    }
+    let (left, right) = xs.split_at(xs.len() / 2);
+    let (left, right) = (Self::build(left), Self::build(right));
+    Self {
+      poly: Polynomial::mul_poly(&left.poly, &right.poly),
+      children: Some(Box::new((left, right))),
+    }
   }
 
-  while xy.0 < 0 {
-    xy.0 += m as i128;
+  // Appends f(x_i) for every point below this node, in order
+  fn evaluate(&self, f: &[u128], out: &mut Vec<u128>) {
+    let reduced = Polynomial::rem_poly(f, &self.poly);
+    match &self.children {
+      None => out.push(reduced.first().copied().unwrap_or(0)),
+      Some(children) => {
+        children.0.evaluate(&reduced, out);
+        children.1.evaluate(&reduced, out);
+      }
+    }
   }
 
-  xy.0 as u128
+  // sum_i weights[i] * poly / (X - x_i)
+  fn combine(&self, weights: &[u128]) -> Vec<u128> {
+    match &self.children {
+      None => vec![weights[0]],
+      Some(children) => {
+        let (left, right) = (&children.0, &children.1);
+        let split = left.poly.len() - 1;
+        let left_sum = left.combine(&weights[..split]);
+        let right_sum = right.combine(&weights[split..]);
+        Polynomial::add_poly(&Polynomial::mul_poly(&left_sum, &right.poly), &Polynomial::mul_poly(&right_sum, &left.poly))
+      }
+    }
+  }
 }
 
 #[cfg(test)]
@@ -122,13 +259,35 @@ mod tests {
   #[test]
   fn test_polynomial_interpolation() {
     let points = vec![(1, 3), (2, 5), (3, 7)];
-    let poly = Polynomial::interpolate(&points);
+    let poly = Polynomial::interpolate(&points).unwrap();
     // Use modular arithmetic for assertions
     assert_eq!(poly.evaluate(1), 3 % P);
     assert_eq!(poly.evaluate(2), 5 % P);
     assert_eq!(poly.evaluate(3), 7 % P);
   }
 
+  #[test]
+  fn test_interpolation_rejects_duplicates() {
+    assert_eq!(Polynomial::interpolate(&[(1, 3), (2, 5), (1, 3)]).unwrap_err(), InterpolationError::DuplicateX(1));
+    // x-coordinates are compared after reduction
+    assert_eq!(Polynomial::interpolate(&[(5, 1), (P + 5, 2)]).unwrap_err(), InterpolationError::DuplicateX(5));
+  }
+
+  #[test]
+  fn test_large_interpolation() {
+    // Large coordinates would overflow u128 products
+    let points: Vec<(u128, u128)> = (0..300u128).map(|i| (P - 1 - i * i * 7919, P - 3 - i)).collect();
+    let poly = Polynomial::interpolate(&points).unwrap();
+    assert!(poly.degree() < points.len());
+    for &(x, y) in &points {
+      assert_eq!(poly.evaluate(x), y);
+    }
+    // Both paths give the same polynomial
+    let fast = Polynomial::subproduct_interpolation(&points[..40].iter().map(|p| p.0).collect::<Vec<_>>(), &points[..40].iter().map(|p| p.1).collect::<Vec<_>>());
+    let slow = Polynomial::interpolate(&points[..40]).unwrap();
+    assert_eq!(fast, slow.coefficients());
+  }
+
   #[test]
   fn test_mod_sub() {
     // Normal case: a > b
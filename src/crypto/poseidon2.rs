@@ -0,0 +1,279 @@
+// Poseidon2 permutation over p = 2^127 - 1
+// Poseidon2 (Grassi, Khovratovich, Schofnegger 2023) keeps the Poseidon round structure but
+// replaces the dense MDS layer with cheap linear layers: an external matrix applied in the
+// full rounds and an internal matrix J + diag(mu) in the partial rounds, so a partial round
+// costs one S-box, one sum and t multiplications by small constants instead of a t x t
+// matrix product. Instances are provided for widths 3 (two-to-one compression and the
+// HashFunction::Poseidon2 sponge) and 8 (wide compression of several children at once).
+// - S-box x^5: gcd(5, p - 1) = 1, so it is a permutation of the field
+// - Rounds: R_F = 8 full rounds split around R_P = 57 partial rounds, the counts of the
+//   crate's Poseidon instance
+// - Round constants: SHAKE-128 of "loquat-vc/poseidon2/t<width>", read as consecutive
+//   16-byte little-endian integers reduced modulo p
+// - External matrix: circ(2, 1, 1) for t = 3; for t = 8, the block matrix circ(2 M4, M4)
+//   over the M4 of the Poseidon2 paper
+// - Internal diagonal: mu = (1, 1, 2) for t = 3 as in the paper; mu_i = i + 2 for t = 8
+// There are no published reference vectors for this field, so the known-answer tests below
+// pin the outputs of this implementation.
+
+use crate::compact::field;
+use crate::crypto::sponge::{Permutation, Sponge};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake128;
+use std::sync::OnceLock;
+
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+// Sponge over the width-3 permutation: two rate elements, one capacity element
+pub const RATE: usize = 2;
+
+const M4: [[u128; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+// Round constants for width T: T per full round, one per partial round
+struct Constants<const T: usize> {
+  full: Vec<[u128; T]>,
+  partial: Vec<u128>,
+}
+
+fn derive_constants<const T: usize>() -> Constants<T> {
+  let mut xof = Shake128::default();
+  xof.update(format!("loquat-vc/poseidon2/t{}", T).as_bytes());
+  let mut reader = xof.finalize_xof();
+  let mut next = || {
+    let mut bytes = [0u8; 16];
+    reader.read(&mut bytes);
+    field::reduce(u128::from_le_bytes(bytes))
+  };
+  let full = (0..FULL_ROUNDS).map(|_| std::array::from_fn(|_| next())).collect();
+  let partial = (0..PARTIAL_ROUNDS).map(|_| next()).collect();
+  Constants { full, partial }
+}
+
+impl<const T: usize> Constants<T> {
+  // Full rounds row by row, then the partial rounds
+  fn flatten(&self) -> Vec<u128> {
+    self.full.iter().flatten().chain(&self.partial).copied().collect()
+  }
+}
+
+// Both widths behind one cell, so they are installed together or not at all
+static CONSTANTS: OnceLock<(Constants<3>, Constants<8>)> = OnceLock::new();
+
+fn constants() -> &'static (Constants<3>, Constants<8>) {
+  CONSTANTS.get_or_init(|| (derive_constants::<3>(), derive_constants::<8>()))
+}
+
+fn constants_3() -> &'static Constants<3> {
+  &constants().0
+}
+
+fn constants_8() -> &'static Constants<8> {
+  &constants().1
+}
+
+// Round constants of the width-3 and width-8 instances, each as full rounds row by row
+// followed by the partial rounds, e.g. for circuit::prover_cache
+pub fn round_constants() -> (Vec<u128>, Vec<u128>) {
+  (constants_3().flatten(), constants_8().flatten())
+}
+
+// The derived constants of both widths, if the tables are exactly what round_constants exports
+fn checked_constants(width_3: &[u128], width_8: &[u128]) -> Option<(Constants<3>, Constants<8>)> {
+  let derived = (derive_constants::<3>(), derive_constants::<8>());
+  (derived.0.flatten() == width_3 && derived.1.flatten() == width_8).then_some(derived)
+}
+
+// Installs constants exported by round_constants before the permutations first derive them.
+// The tables are compared with a fresh derivation first, so a cache can only ever install the
+// constants this build would derive itself. False, installing neither width, if either table
+// differs or a permutation already ran
+pub fn preload_round_constants(width_3: &[u128], width_8: &[u128]) -> bool {
+  checked_constants(width_3, width_8).is_some_and(|constants| CONSTANTS.set(constants).is_ok())
+}
+
+fn sbox(x: u128) -> u128 {
+  let x2 = field::mul(x, x);
+  field::mul(field::mul(x2, x2), x)
+}
+
+fn sum<const T: usize>(state: &[u128; T]) -> u128 {
+  state.iter().fold(0, |acc, &x| field::add(acc, x))
+}
+
+// circ(2, 1, 1): each element plus the sum of all three
+fn external_3(state: &mut [u128; 3]) {
+  let total = sum(state);
+  for x in state.iter_mut() {
+    *x = field::add(*x, total);
+  }
+}
+
+// circ(2 M4, M4): M4 on each half, then each half plus the sum of both halves
+fn external_8(state: &mut [u128; 8]) {
+  let mut halves = [[0u128; 4]; 2];
+  for (half, chunk) in halves.iter_mut().zip(state.chunks_exact(4)) {
+    for (out, row) in half.iter_mut().zip(M4.iter()) {
+      *out = row.iter().zip(chunk).fold(0, |acc, (&m, &x)| field::add(acc, field::mul(m, x)));
+    }
+  }
+  for (i, x) in state.iter_mut().enumerate() {
+    *x = field::add(halves[i / 4][i % 4], field::add(halves[0][i % 4], halves[1][i % 4]));
+  }
+}
+
+// J + diag(mu): each element times mu_i plus the sum of all elements
+fn internal<const T: usize>(state: &mut [u128; T], mu: &[u128; T]) {
+  let total = sum(state);
+  for (x, &m) in state.iter_mut().zip(mu) {
+    *x = field::add(field::mul(*x, m), total);
+  }
+}
+
+fn permute<const T: usize>(state: &mut [u128; T], constants: &Constants<T>, external: fn(&mut [u128; T]), mu: &[u128; T]) {
+  for x in state.iter_mut() {
+    *x = field::reduce(*x);
+  }
+  external(state);
+  let mut full = constants.full.iter();
+  for round in 0..ROUNDS {
+    let partial_start = FULL_ROUNDS / 2;
+    if (partial_start..partial_start + PARTIAL_ROUNDS).contains(&round) {
+      state[0] = sbox(field::add(state[0], constants.partial[round - partial_start]));
+      internal(state, mu);
+    } else {
+      let round_constants = full.next().expect("One row per full round");
+      for (x, &c) in state.iter_mut().zip(round_constants) {
+        *x = sbox(field::add(*x, c));
+      }
+      external(state);
+    }
+  }
+}
+
+// The width-3 permutation; inputs are reduced modulo p first
+pub fn permutation_3(state: &mut [u128; 3]) {
+  permute(state, constants_3(), external_3, &[1, 1, 2]);
+}
+
+// The width-8 permutation; inputs are reduced modulo p first
+pub fn permutation_8(state: &mut [u128; 8]) {
+  permute(state, constants_8(), external_8, &std::array::from_fn(|i| i as u128 + 2));
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Poseidon2Width3;
+
+impl Permutation<3> for Poseidon2Width3 {
+  fn permute(state: &mut [u128; 3]) {
+    permutation_3(state)
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Poseidon2Width8;
+
+impl Permutation<8> for Poseidon2Width8 {
+  fn permute(state: &mut [u128; 8]) {
+    permutation_8(state)
+  }
+}
+
+pub type Poseidon2Sponge = Sponge<Poseidon2Width3, 3, RATE>;
+
+// Two-to-one compression: first element of the permutation of (left, right, 0) plus left
+// (feed-forward, so the compression is not invertible)
+pub fn compress(left: u128, right: u128) -> u128 {
+  let mut state = [left, right, 0];
+  permutation_3(&mut state);
+  field::add(state[0], field::reduce(left))
+}
+
+// Eight-to-one compression with feed-forward of the first input
+pub fn compress_8(inputs: [u128; 8]) -> u128 {
+  let mut state = inputs;
+  permutation_8(&mut state);
+  field::add(state[0], field::reduce(inputs[0]))
+}
+
+// HashFunction::Poseidon2: Sponge::hash_bytes over the width-3 permutation
+pub fn hash(input: &[u8]) -> Vec<u8> {
+  Poseidon2Sponge::hash_bytes(input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_linear_layers() {
+    let mut state = [1, 2, 3];
+    external_3(&mut state);
+    assert_eq!(state, [7, 8, 9]);
+    let mut state = [1, 2, 3];
+    internal(&mut state, &[1, 1, 2]);
+    assert_eq!(state, [7, 8, 12]);
+
+    // circ(2 M4, M4) on a unit vector: column 0 of M4 twice in the first half, once in the second
+    let mut state = [1, 0, 0, 0, 0, 0, 0, 0];
+    external_8(&mut state);
+    assert_eq!(state, [10, 8, 2, 2, 5, 4, 1, 1]);
+  }
+
+  #[test]
+  fn test_permutations_are_deterministic_and_mix() {
+    let mut a = [0, 0, 0];
+    let mut b = [0, 0, 1];
+    permutation_3(&mut a);
+    permutation_3(&mut b);
+    assert!(a.iter().zip(&b).all(|(x, y)| x != y));
+    assert!(a.iter().all(|&x| x < field::P));
+
+    let mut wide = [0u128; 8];
+    permutation_8(&mut wide);
+    let mut again = [0u128; 8];
+    permutation_8(&mut again);
+    assert_eq!(wide, again);
+    assert_ne!(compress_8([1, 2, 3, 4, 5, 6, 7, 8]), compress_8([2, 1, 3, 4, 5, 6, 7, 8]));
+
+    // Unreduced inputs are the same field elements
+    assert_eq!(compress(field::P + 5, 9), compress(5, 9));
+    assert_ne!(compress(5, 9), compress(9, 5));
+  }
+
+  #[test]
+  fn test_known_answers() {
+    let mut wide: [u128; 8] = std::array::from_fn(|i| i as u128);
+    permutation_8(&mut wide);
+    assert_eq!(wide[0], 59023619800021026314080879916138663016);
+    assert_eq!(
+      crate::utils::encoding::Encoding::to_hex(&hash(b"abc")),
+      "c66432be2a585fbd258a99ed7f6887667c22de7db9f7031ba838b337be69c34c"
+    );
+  }
+
+  #[test]
+  fn test_round_constant_export() {
+    let (width_3, width_8) = round_constants();
+    assert_eq!((width_3.len(), width_8.len()), (FULL_ROUNDS * 3 + PARTIAL_ROUNDS, FULL_ROUNDS * 8 + PARTIAL_ROUNDS));
+    assert!(checked_constants(&width_3, &width_8).is_some());
+
+    // Derived already, so nothing is replaced; tables other than the derived ones are refused
+    assert!(!preload_round_constants(&width_3, &width_8));
+    assert!(checked_constants(&width_3[1..], &width_8).is_none());
+    let mut substituted = width_8.clone();
+    substituted[0] = field::add(substituted[0], 1);
+    assert!(checked_constants(&width_3, &substituted).is_none());
+    assert!(!preload_round_constants(&width_3, &substituted));
+  }
+
+  #[test]
+  fn test_sponge_padding() {
+    assert_eq!(hash(b"").len(), 32);
+    assert_eq!(hash(b"abc"), hash(b"abc"));
+    assert_ne!(hash(b""), hash(&[0]));
+    assert_ne!(hash(&[0; 15]), hash(&[0; 16]));
+    assert_ne!(hash(&[0; 29]), hash(&[0; 30]));
+  }
+}
@@ -0,0 +1,132 @@
+// Packing and expansion of Legendre PRF outputs
+// A full Loquat signature handles hundreds of PRF symbols: the public key is the PRF at a
+// public index set and the prover opens the symbols at challenged points. These helpers fix
+// one layout for every such string, so a packed key, an OPRF or VRF output and a reference
+// implementation agree bit for bit.
+//
+// Bit order: symbol i is bit 7 - i % 8 of byte i / 8, most significant bit first, and a symbol
+// is 1 for a quadratic non-residue (LegendrePRF::evaluate). In field elements symbol i is bit
+// 125 - i % 126 of element i / 126; 126 bits keep every element below P = 2^127 - 1. Unused
+// low bits of the last byte or element are zero, and unpacking rejects anything else.
+//
+// Index expansion: with d = H(seed | domain), the candidate for counter c = 0, 1, ... is
+// H(c as u32 big-endian | d) as a big-endian integer mod P; zero and repeated candidates are
+// skipped. The seed is compressed first because the arithmetic hashes only absorb a prefix.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::legendre_prf::LegendrePRF;
+use crate::utils::field_operations::P;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::collections::HashSet;
+
+// Symbols packed into one field element
+pub const FIELD_ELEMENT_BITS: usize = 126;
+
+// Packs symbols (0 or 1) most significant bit first
+pub fn pack_bits(bits: &[u8]) -> Vec<u8> {
+  let mut packed = vec![0u8; bits.len().div_ceil(8)];
+  for (i, bit) in bits.iter().enumerate() {
+    packed[i / 8] |= (bit & 1) << (7 - i % 8);
+  }
+  packed
+}
+
+// The first `count` symbols of a packed string; None if the length or the padding is off
+pub fn unpack_bits(packed: &[u8], count: usize) -> Option<Vec<u8>> {
+  if packed.len() != count.div_ceil(8) {
+    return None;
+  }
+  let bits: Vec<u8> = (0..packed.len() * 8).map(|i| (packed[i / 8] >> (7 - i % 8)) & 1).collect();
+  if bits[count..].iter().any(|&bit| bit != 0) {
+    return None;
+  }
+  Some(bits[..count].to_vec())
+}
+
+// Packs symbols into field elements of FIELD_ELEMENT_BITS each, first symbol highest
+pub fn pack_field_elements(bits: &[u8]) -> Vec<u128> {
+  bits
+    .chunks(FIELD_ELEMENT_BITS)
+    .map(|chunk| chunk.iter().enumerate().fold(0u128, |element, (j, bit)| element | ((*bit as u128 & 1) << (FIELD_ELEMENT_BITS - 1 - j))))
+    .collect()
+}
+
+pub fn unpack_field_elements(elements: &[u128], count: usize) -> Option<Vec<u8>> {
+  if elements.len() != count.div_ceil(FIELD_ELEMENT_BITS) || elements.iter().any(|&element| element >> FIELD_ELEMENT_BITS != 0) {
+    return None;
+  }
+  let bits: Vec<u8> = (0..elements.len() * FIELD_ELEMENT_BITS)
+    .map(|i| ((elements[i / FIELD_ELEMENT_BITS] >> (FIELD_ELEMENT_BITS - 1 - i % FIELD_ELEMENT_BITS)) & 1) as u8)
+    .collect();
+  if bits[count..].iter().any(|&bit| bit != 0) {
+    return None;
+  }
+  Some(bits[..count].to_vec())
+}
+
+// `count` distinct nonzero field elements expanded from a seed under a domain
+pub fn expand_indices(seed: &[u8], domain: &[u8], count: usize, hash_function: HashFunction) -> Vec<u128> {
+  let hash = Hash::new(hash_function);
+  let seed_digest = hash.compute(&[seed, domain].concat());
+  let mut indices = Vec::with_capacity(count);
+  let mut seen = HashSet::with_capacity(count);
+  let mut counter = 0u32;
+  while indices.len() < count {
+    let digest = hash.compute(&[&counter.to_be_bytes()[..], &seed_digest].concat());
+    let index = (BigUint::from_bytes_be(&digest) % BigUint::from(P)).to_u128().expect("Index conversion failed");
+    if index != 0 && seen.insert(index) {
+      indices.push(index);
+    }
+    counter += 1;
+  }
+  indices
+}
+
+// Symbols L_K(i) of the key at every index, one per byte
+pub fn evaluate_bits(sk: u128, indices: &[u128]) -> Vec<u8> {
+  let prf = LegendrePRF::with_key(sk);
+  indices.iter().map(|&index| prf.evaluate(index)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Expected values computed independently from the definitions above (Python pow and
+  // hashlib.sha3_256)
+
+  #[test]
+  fn test_packing_vectors() {
+    let bits = evaluate_bits(5, &(1..=16).collect::<Vec<_>>());
+    assert_eq!(bits, [1, 1, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0, 1, 1, 0]);
+    assert_eq!(pack_bits(&bits), [0xca, 0x86]);
+    assert_eq!(unpack_bits(&[0xca, 0x86], 16), Some(bits.clone()));
+
+    // Partial bytes keep the first symbols in the high bits; set padding bits are rejected
+    assert_eq!(pack_bits(&bits[..3]), [0xc0]);
+    assert_eq!(unpack_bits(&[0xc0], 3), Some(bits[..3].to_vec()));
+    assert_eq!(unpack_bits(&[0xc1], 3), None);
+    assert_eq!(unpack_bits(&[0xc0, 0x00], 3), None);
+
+    let long: Vec<u8> = (0..130).map(|i| ((i * 7 + 3) % 5 % 2) as u8).collect();
+    let elements = pack_field_elements(&long);
+    assert_eq!(elements, [0x2318c6318c6318c6318c6318c6318c63, 0x4000000000000000000000000000000]);
+    assert!(elements.iter().all(|&element| element < P));
+    assert_eq!(unpack_field_elements(&elements, 130), Some(long));
+    assert_eq!(unpack_field_elements(&[elements[0], elements[1] | 1], 130), None);
+    assert_eq!(unpack_field_elements(&[1 << 126], 1), None);
+  }
+
+  #[test]
+  fn test_index_expansion_vectors() {
+    let indices = expand_indices(&[7u8; 32], b"loquat-public-indices", 3, HashFunction::Sha3_256);
+    assert_eq!(indices, [0x4c0bd23e0a3d4e644e0ddca43c2014b0, 0x46d46292abec6c25b8e9330bc8d8154b, 0x69b16c7f0b47ef8a40c9ca31416d542d]);
+
+    // Longer expansions extend shorter ones, and the domain separates expansions
+    let more = expand_indices(&[7u8; 32], b"loquat-public-indices", 300, HashFunction::Sha3_256);
+    assert_eq!(&more[..3], indices.as_slice());
+    assert_eq!(more.iter().collect::<HashSet<_>>().len(), 300);
+    assert_ne!(expand_indices(&[7u8; 32], b"other-domain", 3, HashFunction::Sha3_256), indices);
+  }
+}
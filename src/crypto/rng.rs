@@ -0,0 +1,145 @@
+// Randomness health checks
+// Issuers in VMs and on embedded boards can end up with an entropy source that is stuck or
+// heavily biased, and every key generated from it is then guessable. selftest draws a block
+// from the operating system source and runs the two continuous health tests of NIST SP
+// 800-90B section 4.4 on its bytes:
+// - repetition count: no value repeats REPETITION_CUTOFF times in a row
+// - adaptive proportion: in each window of PROPORTION_WINDOW bytes the first byte occurs
+//   fewer than PROPORTION_CUTOFF times
+// Both cutoffs assume at least 4 bits of min-entropy per byte at a false-alarm rate of
+// 2^-40, so a healthy source practically never fails while a stuck or badly skewed one does.
+// A failed selftest is recorded for the process and stays recorded: from then on signing
+// returns the recorded RngHealthError instead of a signature, and keygen, which has no error
+// to return, panics rather than produce a guessable key. Processes that never run selftest
+// are unaffected.
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+pub const SELFTEST_SAMPLES: usize = 4096;
+pub const REPETITION_CUTOFF: usize = 11;
+pub const PROPORTION_WINDOW: usize = 512;
+pub const PROPORTION_CUTOFF: usize = 78;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngHealthError {
+  Unavailable, // The source returned an error instead of bytes
+  Repetition, // The repetition count test failed
+  Proportion, // The adaptive proportion test failed
+}
+
+impl RngHealthError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      RngHealthError::Unavailable => "unavailable",
+      RngHealthError::Repetition => "repetition",
+      RngHealthError::Proportion => "proportion",
+    }
+  }
+}
+
+// 0 before any selftest, 1 after a passing one, 2 + error index after a failing one
+static HEALTH: AtomicU8 = AtomicU8::new(0);
+const ERRORS: [RngHealthError; 3] = [RngHealthError::Unavailable, RngHealthError::Repetition, RngHealthError::Proportion];
+
+pub fn repetition_count_test(samples: &[u8]) -> bool {
+  samples.chunk_by(|a, b| a == b).all(|run| run.len() < REPETITION_CUTOFF)
+}
+
+// Windows are consecutive and non-overlapping; a trailing partial window is not tested
+pub fn adaptive_proportion_test(samples: &[u8]) -> bool {
+  samples
+    .chunks_exact(PROPORTION_WINDOW)
+    .all(|window| window.iter().filter(|&&sample| sample == window[0]).count() < PROPORTION_CUTOFF)
+}
+
+pub fn check_samples(samples: &[u8]) -> Result<(), RngHealthError> {
+  if !repetition_count_test(samples) {
+    return Err(RngHealthError::Repetition);
+  }
+  if !adaptive_proportion_test(samples) {
+    return Err(RngHealthError::Proportion);
+  }
+  Ok(())
+}
+
+// Runs the health tests on SELFTEST_SAMPLES bytes of any source, without recording the result
+pub fn selftest_source<R: RngCore + ?Sized>(rng: &mut R) -> Result<(), RngHealthError> {
+  let mut samples = vec![0u8; SELFTEST_SAMPLES];
+  rng.try_fill_bytes(&mut samples).map_err(|_| RngHealthError::Unavailable)?;
+  check_samples(&samples)
+}
+
+// Tests the operating system source, which seeds every generator the crate draws keys from,
+// and records the outcome; call once at startup before issuing
+pub fn selftest() -> Result<(), RngHealthError> {
+  let result = selftest_source(&mut OsRng);
+  let state = match result {
+    Ok(()) => 1,
+    Err(error) => 2 + ERRORS.iter().position(|known| *known == error).expect("Listed error") as u8,
+  };
+  // A recorded failure is never overwritten
+  let _ = HEALTH.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| (current < 2).then_some(state));
+  result
+}
+
+// None before the first selftest, otherwise the recorded outcome
+pub fn status() -> Option<Result<(), RngHealthError>> {
+  match HEALTH.load(Ordering::SeqCst) {
+    0 => None,
+    1 => Some(Ok(())),
+    state => Some(Err(ERRORS[state as usize - 2])),
+  }
+}
+
+// Guard of signing: the recorded failure, if any
+pub(crate) fn check_healthy() -> Result<(), RngHealthError> {
+  match status() {
+    Some(Err(error)) => Err(error),
+    _ => Ok(()),
+  }
+}
+
+// Guard of keygen
+pub(crate) fn assert_healthy() {
+  if let Err(error) = check_healthy() {
+    panic!("Refusing to generate keys: randomness self-test failed ({})", error.code());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::rngs::mock::StepRng;
+  use rand::rngs::StdRng;
+  use rand::SeedableRng;
+
+  #[test]
+  fn test_health_tests_flag_bad_sources() {
+    assert_eq!(selftest_source(&mut StdRng::seed_from_u64(7)), Ok(()));
+    assert_eq!(selftest_source(&mut StepRng::new(0, 0)), Err(RngHealthError::Repetition));
+    // A counter: short runs of zero bytes, but zeros make up most of every window
+    assert_eq!(selftest_source(&mut StepRng::new(0, 1)), Err(RngHealthError::Proportion));
+
+    // The cutoffs themselves
+    let mut run = vec![1u8; REPETITION_CUTOFF - 1];
+    assert!(repetition_count_test(&run));
+    run.push(1);
+    assert!(!repetition_count_test(&run));
+    let mut window: Vec<u8> = (0..PROPORTION_WINDOW).map(|i| (i % 200) as u8 + 1).collect();
+    for i in 0..PROPORTION_CUTOFF - 1 {
+      window[2 * i] = 0;
+    }
+    assert!(adaptive_proportion_test(&window));
+    window[1] = 0;
+    assert!(!adaptive_proportion_test(&window));
+  }
+
+  #[test]
+  fn test_selftest_records_outcome() {
+    assert_eq!(selftest(), Ok(()));
+    assert_eq!(status(), Some(Ok(())));
+    assert_eq!(check_healthy(), Ok(()));
+  }
+}
@@ -3,13 +3,12 @@
 // Batch verification for aggregate signatures.
 
 use crate::crypto::{legendre_prf::LegendrePRF, polynomial::Polynomial, hash_functions::Hash};
+use crate::utils::field_operations::P;
 use num_bigint::BigUint;
 use num_traits::{Zero, One};
+use crate::crypto::test_rng;
 use rand::Rng;
 
-// Prime field modulus (p = 2^127 - 1)
-const P: u128 = (1 << 127) - 1;
-
 // SNARK prover structure
 pub struct SNARKProver {
   secret_witness: BigUint,
@@ -22,9 +21,10 @@ pub struct SNARKVerifier {
 
 impl SNARKProver {
   // Generates a proof for a given witness 
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
   pub fn generate_proof(&self, statement: &BigUint) -> (BigUint, BigUint) {
     let proof = (self.secret_witness.clone() * statement) % BigUint::from(P);
-    let challenge = BigUint::from(rand::thread_rng().gen_range(1..P));
+    let challenge = BigUint::from(test_rng::source().gen_range(1..P));
     (proof, challenge)
   }
 }
@@ -107,7 +107,7 @@ mod tests {
     let p_mod = BigUint::from(P);
     let proofs: Vec<_> = statements.iter().map(|s| {
       let proof = (&prover.secret_witness * s) % &p_mod;
-      let challenge = BigUint::from(rand::thread_rng().gen_range(1..P));
+      let challenge = BigUint::from(test_rng::source().gen_range(1..P));
       (proof, challenge)
     }).collect();
     
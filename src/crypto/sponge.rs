@@ -0,0 +1,198 @@
+// Sponge construction over the algebraic permutations
+// Poseidon, Griffin and Poseidon2 are permutations of a state of T elements of the field
+// p = 2^127 - 1; everything the crate builds from them (byte hashing, hashing to a field
+// element, field-native Fiat-Shamir transcripts) goes through Sponge so that state layout,
+// padding and the absorb/squeeze schedule are defined once.
+// - Layout: the first R elements are the rate, the remaining T - R the capacity; the
+//   initial value (e.g. an input length or domain tag) is placed in the last element
+// - Absorbing adds elements into the rate; the permutation runs lazily, when a full block
+//   is followed by more input and once before the first squeeze
+// - Squeezing reads the rate in order, permuting whenever it is used up; absorbing after a
+//   squeeze permutes first (duplex mode), so the sponge doubles as a transcript
+// - Bytes are absorbed as 15-byte little-endian chunks of the input followed by a 0x01
+//   byte, so every chunk is below p and inputs of different lengths never pad alike
+// The Poseidon and Griffin suites predate this module and hash a single block instead
+// (legacy_block_hash); their outputs are unchanged so existing signatures still verify.
+
+use crate::compact::field;
+use std::marker::PhantomData;
+
+// Input bytes per absorbed element
+pub const CHUNK_BYTES: usize = 15;
+
+// A permutation of T field elements
+pub trait Permutation<const T: usize> {
+  fn permute(state: &mut [u128; T]);
+}
+
+#[derive(Debug, Clone)]
+pub struct Sponge<P: Permutation<T>, const T: usize, const R: usize> {
+  state: [u128; T],
+  position: usize, // Next rate element to absorb into or squeeze from
+  squeezing: bool,
+  permutation: PhantomData<P>,
+}
+
+impl<P: Permutation<T>, const T: usize, const R: usize> Sponge<P, T, R> {
+  const VALID_RATE: () = assert!(R > 0 && R < T, "Rate must leave room for a capacity");
+
+  pub fn new(initial_value: u128) -> Self {
+    #[allow(clippy::let_unit_value)]
+    let () = Self::VALID_RATE;
+    let mut state = [0u128; T];
+    state[T - 1] = field::reduce(initial_value);
+    Self {
+      state,
+      position: 0,
+      squeezing: false,
+      permutation: PhantomData,
+    }
+  }
+
+  pub fn absorb(&mut self, elements: &[u128]) {
+    if self.squeezing {
+      self.squeezing = false;
+      self.position = R;
+    }
+    for &element in elements {
+      if self.position == R {
+        P::permute(&mut self.state);
+        self.position = 0;
+      }
+      self.state[self.position] = field::add(self.state[self.position], field::reduce(element));
+      self.position += 1;
+    }
+  }
+
+  pub fn absorb_bytes(&mut self, bytes: &[u8]) {
+    let mut padded = bytes.to_vec();
+    padded.push(0x01);
+    let elements: Vec<u128> = padded
+      .chunks(CHUNK_BYTES)
+      .map(|chunk| {
+        let mut word = [0u8; 16];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u128::from_le_bytes(word)
+      })
+      .collect();
+    self.absorb(&elements);
+  }
+
+  pub fn squeeze(&mut self) -> u128 {
+    if !self.squeezing || self.position == R {
+      P::permute(&mut self.state);
+      self.position = 0;
+      self.squeezing = true;
+    }
+    let element = self.state[self.position];
+    self.position += 1;
+    element
+  }
+
+  // `count` squeezed elements as 16-byte little-endian words
+  pub fn squeeze_bytes(&mut self, count: usize) -> Vec<u8> {
+    (0..count).flat_map(|_| self.squeeze().to_le_bytes()).collect()
+  }
+
+  // 32-byte digest: the input length as initial value, the input, two squeezed elements
+  pub fn hash_bytes(input: &[u8]) -> Vec<u8> {
+    let mut sponge = Self::new(input.len() as u128);
+    sponge.absorb_bytes(input);
+    sponge.squeeze_bytes(2)
+  }
+
+  // A field element bound to a domain tag; the tag and input are padded separately
+  pub fn hash_to_field(domain: &[u8], input: &[u8]) -> u128 {
+    let mut sponge = Self::new(0);
+    sponge.absorb_bytes(domain);
+    sponge.absorb_bytes(input);
+    sponge.squeeze()
+  }
+}
+
+// Single-block hash of the Poseidon and Griffin suites: the first three 16-byte
+// little-endian chunks of the input (reduced modulo p) are permuted once and the first two
+// elements form the digest. Bytes past the 48th do not affect the output
+pub fn legacy_block_hash<P: Permutation<3>>(input: &[u8]) -> Vec<u8> {
+  let mut state = [0u128; 3];
+  for (element, chunk) in state.iter_mut().zip(input.chunks(16)) {
+    let mut word = [0u8; 16];
+    word[..chunk.len()].copy_from_slice(chunk);
+    *element = field::reduce(u128::from_le_bytes(word));
+  }
+  P::permute(&mut state);
+  state[..2].iter().flat_map(|element| element.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // x -> (x + 1)^3 on each element plus a rotation: not secure, but every output depends on
+  // every input and on the round order
+  #[derive(Debug, Clone)]
+  struct Toy;
+
+  impl Permutation<3> for Toy {
+    fn permute(state: &mut [u128; 3]) {
+      for x in state.iter_mut() {
+        let y = field::add(*x, 1);
+        *x = field::mul(field::mul(y, y), y);
+      }
+      state.rotate_left(1);
+      state[0] = field::add(state[0], state[2]);
+    }
+  }
+
+  type ToySponge = Sponge<Toy, 3, 2>;
+
+  #[test]
+  fn test_absorb_schedule() {
+    // One permutation per started block, plus one before squeezing
+    let mut lazy = ToySponge::new(7);
+    lazy.absorb(&[1, 2, 3]);
+    let mut manual = [0u128, 0, 7];
+    manual[0] = 1;
+    manual[1] = 2;
+    Toy::permute(&mut manual);
+    manual[0] = field::add(manual[0], 3);
+    Toy::permute(&mut manual);
+    assert_eq!(lazy.squeeze(), manual[0]);
+    assert_eq!(lazy.squeeze(), manual[1]);
+    Toy::permute(&mut manual);
+    assert_eq!(lazy.squeeze(), manual[0]);
+
+    // Absorbing in pieces is the same as absorbing at once
+    let mut pieces = ToySponge::new(7);
+    pieces.absorb(&[1]);
+    pieces.absorb(&[2, 3]);
+    let mut whole = ToySponge::new(7);
+    whole.absorb(&[1, 2, 3]);
+    assert_eq!(pieces.squeeze(), whole.squeeze());
+  }
+
+  #[test]
+  fn test_duplex_and_padding() {
+    // Absorbing after a squeeze changes every later output
+    let mut a = ToySponge::new(0);
+    let mut b = ToySponge::new(0);
+    a.absorb(&[5]);
+    b.absorb(&[5]);
+    assert_eq!(a.squeeze(), b.squeeze());
+    a.absorb(&[6]);
+    assert_ne!(a.squeeze(), b.squeeze());
+
+    assert_eq!(ToySponge::hash_bytes(b"").len(), 32);
+    assert_ne!(ToySponge::hash_bytes(&[0; 15]), ToySponge::hash_bytes(&[0; 16]));
+    assert_ne!(ToySponge::hash_bytes(b""), ToySponge::hash_bytes(&[0]));
+    assert_ne!(ToySponge::hash_to_field(b"a", b"bc"), ToySponge::hash_to_field(b"ab", b"c"));
+    assert!(ToySponge::hash_to_field(b"domain", b"input") < field::P);
+  }
+
+  #[test]
+  fn test_legacy_block_hash_reads_a_prefix() {
+    let long = [7u8; 64];
+    assert_eq!(legacy_block_hash::<Toy>(&long[..48]), legacy_block_hash::<Toy>(&long));
+    assert_ne!(legacy_block_hash::<Toy>(&long[..47]), legacy_block_hash::<Toy>(&long));
+  }
+}
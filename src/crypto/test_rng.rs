@@ -0,0 +1,239 @@
+// Seedable randomness for reproducible runs
+// Keygen, commitment blindings, the aggregation and ring challenges, OPRF preprocessing, the
+// random-point sumcheck checks, and the SD-JWT salts, presentation challenges and exchange
+// identifiers of vc all draw from source(). Normally that is the thread's
+// OS-seeded generator. While a TestRng is installed on the thread every draw comes from it
+// instead, so a whole keygen/sign/prove/verify run is a function of one u64 seed.
+// TestRng::from_env takes the seed from LOQUAT_TEST_SEED when set and picks a fresh one
+// otherwise; the guard returned by install prints the seed if the thread panics, so a
+// failing probabilistic test can be replayed exactly with
+//   LOQUAT_TEST_SEED=<seed> cargo test <name>
+// Only for tests: keys generated under an installed TestRng are as guessable as its seed, so
+// install and reproducible are compiled into this crate's own tests and, for the tests of
+// downstream crates, behind the test-utils feature only.
+
+use rand::rngs::StdRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use std::cell::RefCell;
+
+pub const SEED_VAR: &str = "LOQUAT_TEST_SEED";
+
+thread_local! {
+  static INSTALLED: RefCell<Option<TestRng>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug)]
+pub struct TestRng {
+  seed: u64,
+  rng: StdRng,
+}
+
+impl TestRng {
+  pub fn from_seed(seed: u64) -> Self {
+    Self {
+      seed,
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  // LOQUAT_TEST_SEED when it holds a decimal or 0x-prefixed hex u64, otherwise a fresh seed
+  pub fn from_env() -> Self {
+    let seed = std::env::var(SEED_VAR).ok().and_then(|value| parse_seed(&value)).unwrap_or_else(|| rand::thread_rng().next_u64());
+    Self::from_seed(seed)
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  // Routes source() on this thread through the generator until the guard drops
+  #[cfg(any(test, feature = "test-utils"))]
+  pub fn install(self) -> SeedGuard {
+    let seed = self.seed;
+    let previous = INSTALLED.with(|slot| slot.borrow_mut().replace(self));
+    SeedGuard { seed, previous }
+  }
+}
+
+impl RngCore for TestRng {
+  fn next_u32(&mut self) -> u32 {
+    self.rng.next_u32()
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.rng.next_u64()
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    self.rng.fill_bytes(dest)
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+    self.rng.try_fill_bytes(dest)
+  }
+}
+
+impl CryptoRng for TestRng {}
+
+fn parse_seed(value: &str) -> Option<u64> {
+  let value = value.trim();
+  match value.strip_prefix("0x") {
+    Some(hex) => u64::from_str_radix(hex, 16).ok(),
+    None => value.parse().ok(),
+  }
+}
+
+// Restores the previously installed generator (usually none) on drop
+pub struct SeedGuard {
+  seed: u64,
+  previous: Option<TestRng>,
+}
+
+impl SeedGuard {
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+}
+
+impl Drop for SeedGuard {
+  fn drop(&mut self) {
+    let previous = self.previous.take();
+    INSTALLED.with(|slot| *slot.borrow_mut() = previous);
+    if std::thread::panicking() {
+      eprintln!("{}={} reproduces this failure", SEED_VAR, self.seed);
+    }
+  }
+}
+
+// Runs f with a TestRng from the environment installed; the seed is printed if f panics
+#[cfg(any(test, feature = "test-utils"))]
+pub fn reproducible<T>(f: impl FnOnce() -> T) -> T {
+  let _guard = TestRng::from_env().install();
+  f()
+}
+
+// The generator the crate draws from; each call checks for an installed TestRng
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Source;
+
+pub fn source() -> Source {
+  Source
+}
+
+impl Source {
+  fn draw<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    INSTALLED.with(|slot| match slot.borrow_mut().as_mut() {
+      Some(rng) => f(rng),
+      None => f(&mut rand::thread_rng()),
+    })
+  }
+}
+
+impl RngCore for Source {
+  fn next_u32(&mut self) -> u32 {
+    Self::draw(|rng| rng.next_u32())
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    Self::draw(|rng| rng.next_u64())
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    Self::draw(|rng| rng.fill_bytes(dest))
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+    Self::draw(|rng| rng.try_fill_bytes(dest))
+  }
+}
+
+// Both backing generators are cryptographically secure
+impl CryptoRng for Source {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::crypto::commitment::CommitmentScheme;
+  use crate::crypto::hash_functions::HashFunction;
+  use crate::signature::aggregate::LoquatAggregate;
+  use crate::signature::loquat::Loquat;
+  use rand::Rng;
+
+  fn run() -> (u128, Vec<u8>, u128) {
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"message").unwrap();
+    let blinding = source().gen_range(0..1u128 << 100);
+    (keypair.secret_key, signature.to_bytes(), blinding)
+  }
+
+  #[test]
+  fn test_same_seed_same_run() {
+    let first = {
+      let _guard = TestRng::from_seed(0x5eed).install();
+      run()
+    };
+    let second = {
+      let _guard = TestRng::from_seed(0x5eed).install();
+      run()
+    };
+    assert_eq!(first, second);
+    let other = {
+      let _guard = TestRng::from_seed(0x5eee).install();
+      run()
+    };
+    assert_ne!(first.0, other.0);
+
+    // Without a guard the thread generator is back
+    assert_ne!(run().0, first.0);
+  }
+
+  #[cfg(feature = "vc")]
+  #[test]
+  fn test_vc_randomness_follows_seed() {
+    let run = |seed| {
+      let _guard = TestRng::from_seed(seed).install();
+      let mut values = vec![crate::vc::challenge::ChallengeManager::new(60).issue_at(1_000).value];
+      #[cfg(feature = "jose")]
+      values.push(crate::vc::sd_jwt::Disclosure::new("given_name", serde_json::json!("Alice")).salt);
+      values
+    };
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+  }
+
+  #[test]
+  fn test_guards_nest() {
+    let _outer = TestRng::from_seed(1).install();
+    let expected = TestRng::from_seed(1).next_u64();
+    {
+      let _inner = TestRng::from_seed(2).install();
+      assert_eq!(source().next_u64(), TestRng::from_seed(2).next_u64());
+    }
+    assert_eq!(source().next_u64(), expected);
+  }
+
+  #[test]
+  fn test_randomized_components_follow_seed() {
+    let run = |seed| {
+      let _guard = TestRng::from_seed(seed).install();
+      let keys: Vec<_> = (0..3).map(|_| Loquat::keygen()).collect();
+      let signatures: Vec<_> = keys.iter().map(|key| Loquat::sign(key.secret_key, b"batch").unwrap()).collect();
+      let aggregate = LoquatAggregate::aggregate(&signatures).unwrap().to_bytes();
+      let (commitment, _) = CommitmentScheme::new(HashFunction::Sha3_256).commit(&[1, 2]).unwrap();
+      (aggregate, commitment)
+    };
+    assert_eq!(run(99), run(99));
+    assert_ne!(run(99).0, run(100).0);
+
+    // reproducible installs a generator for the closure only
+    reproducible(|| source().next_u64());
+    assert!(INSTALLED.with(|slot| slot.borrow().is_none()));
+  }
+
+  #[test]
+  fn test_seed_parsing() {
+    assert_eq!(parse_seed("1234"), Some(1234));
+    assert_eq!(parse_seed(" 0xff\n"), Some(255));
+    assert_eq!(parse_seed("seed"), None);
+  }
+}
@@ -0,0 +1,363 @@
+// Ethereum/EVM verification artifacts
+// The EVM has a native keccak256 but no SHA3-256, so on-chain verification needs signatures
+// produced under the Keccak256 suite of the scheme:
+// - public key = keccak256(uint128 sk)
+// - message hash = keccak256(message) mod P
+// - Merkle root = keccak256(bytes32 sigma || bytes32 message hash), i.e. 32-byte padded nodes
+//   as in Solidity's abi.encodePacked
+// The Legendre PRF bit is computed on-chain with the modexp precompile (Euler's criterion).
+// `EvmLoquat::verify` mirrors the reference contract in SOLIDITY_VERIFIER step for step.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::legendre_prf::LegendrePRF;
+use crate::crypto::rng::RngHealthError;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::{Loquat, LoquatKeyPair, LoquatSignature};
+use crate::signature::message::{SignableMessage, SigningBytes};
+use crate::utils::field_operations::P;
+use crate::utils::wire;
+#[cfg(feature = "vc")]
+use crate::vc::credential::Credential;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+// Solidity signature of the verifier entry point
+pub const EVM_VERIFY_FUNCTION: &str = "verify(bytes32,bytes,uint256,bytes32)";
+
+// Reference verifier contract for signatures produced by EvmLoquat
+pub const SOLIDITY_VERIFIER: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+contract LoquatVerifier {
+    uint256 constant P = 2**127 - 1;
+
+    function verify(bytes32 pk, bytes calldata message, uint256 sigma, bytes32 root) external view returns (bool) {
+        if (sigma >= P) return false;
+        uint256 h = uint256(keccak256(message)) % P;
+
+        // Recover the secret key candidate that matches the public key
+        uint256 sk;
+        uint256 sk1 = addmod(sigma, P - h, P);
+        uint256 sk2 = addmod(sigma, h, P);
+        if (keccak256(abi.encodePacked(uint128(sk1))) == pk) sk = sk1;
+        else if (keccak256(abi.encodePacked(uint128(sk2))) == pk) sk = sk2;
+        else return false;
+
+        // Legendre PRF bit: 0 for quadratic residues, 1 otherwise
+        uint256 euler = modexp(addmod(sk, h, P), (P - 1) / 2, P);
+        uint256 expected = euler == 1 ? addmod(sk, P - h, P) : addmod(sk, h, P);
+        return keccak256(abi.encodePacked(bytes32(expected), bytes32(h))) == root;
+    }
+
+    function modexp(uint256 base, uint256 exponent, uint256 modulus) internal view returns (uint256 result) {
+        (bool ok, bytes memory out) = address(0x05).staticcall(abi.encode(32, 32, 32, base, exponent, modulus));
+        require(ok, "modexp failed");
+        result = abi.decode(out, (uint256));
+    }
+}
+"#;
+
+// Gas schedule constants (EIP-2028 calldata, EIP-2565 modexp, Berlin warm access)
+const TX_BASE_GAS: u64 = 21_000;
+const CALLDATA_ZERO_BYTE_GAS: u64 = 4;
+const CALLDATA_NONZERO_BYTE_GAS: u64 = 16;
+const KECCAK_BASE_GAS: u64 = 30;
+const KECCAK_WORD_GAS: u64 = 6;
+const WARM_STATICCALL_GAS: u64 = 100;
+// EIP-2565 with 32-byte operands: max(200, complexity 16 * 125 iterations / 3) for the
+// 126-bit exponent (p - 1) / 2
+const MODEXP_GAS: u64 = 16 * 125 / 3;
+// ABI decoding, memory expansion and field arithmetic of the reference contract, measured loosely
+const VERIFIER_OVERHEAD_GAS: u64 = 2_000;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+  Hash::new(HashFunction::Keccak256).compute(data).try_into().expect("Keccak256 output is 32 bytes")
+}
+
+// Big-endian 32-byte ABI word holding a u128
+fn word(value: u128) -> [u8; 32] {
+  let mut out = [0u8; 32];
+  out[16..].copy_from_slice(&value.to_be_bytes());
+  out
+}
+
+fn word_to_u128(word: &[u8]) -> Option<u128> {
+  if word.len() != 32 || word[..16].iter().any(|&b| b != 0) {
+    return None;
+  }
+  Some(u128::from_be_bytes(word[16..].try_into().ok()?))
+}
+
+fn function_selector(signature: &str) -> [u8; 4] {
+  keccak256(signature.as_bytes())[..4].try_into().expect("Selector slice has length 4")
+}
+
+// u128 modular addition without overflow (both operands below P < 2^127)
+fn add_mod(a: u128, b: u128) -> u128 {
+  (a + b) % P
+}
+
+// Keccak256 suite signature in fixed-width form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmSignature {
+  pub sigma: u128,
+  pub merkle_root: [u8; 32],
+}
+
+impl EvmSignature {
+  // Converts a Loquat signature, which must have been produced under the Keccak256 suite
+  pub fn from_loquat(signature: &LoquatSignature) -> Option<Self> {
+    if signature.config != EvmLoquat::config() {
+      return None;
+    }
+    Some(Self {
+      sigma: signature.sigma.to_u128().filter(|&sigma| sigma < P)?,
+      merkle_root: wire::digest_to_bytes(&signature.merkle_root)?,
+    })
+  }
+}
+
+pub struct EvmLoquat;
+
+impl EvmLoquat {
+  // Keccak256 for every hash role
+  pub fn config() -> LoquatConfig {
+    LoquatConfig::uniform(HashFunction::Keccak256)
+  }
+
+  // Same commitment as Loquat::keygen_with_config under the Keccak256 suite
+  pub fn public_key(sk: u128) -> [u8; 32] {
+    keccak256(&sk.to_be_bytes())
+  }
+
+  pub fn keygen() -> LoquatKeyPair {
+    Loquat::keygen_with_config(&Self::config())
+  }
+
+  fn message_hash(message: &[u8]) -> u128 {
+    (BigUint::from_bytes_be(&keccak256(message)) % BigUint::from(P)).to_u128().expect("Message conversion failed")
+  }
+
+  fn merkle_root(sigma: u128, message_hash: u128) -> [u8; 32] {
+    let mut data = word(sigma).to_vec();
+    data.extend_from_slice(&word(message_hash));
+    keccak256(&data)
+  }
+
+  // Sign the canonical encoding of a message under the Keccak256 suite
+  pub fn sign<M: SignableMessage + ?Sized>(sk: u128, message: &M) -> Result<EvmSignature, RngHealthError> {
+    let signature = Loquat::sign_with_config(sk, message, &Self::config())?;
+    Ok(EvmSignature::from_loquat(&signature).expect("Keccak256 suite signature"))
+  }
+
+  // Verify exactly as the reference contract does
+  pub fn verify<M: SignableMessage + ?Sized>(pk: &[u8; 32], message: &M, signature: &EvmSignature) -> bool {
+    if signature.sigma >= P {
+      return false;
+    }
+    let h = Self::message_hash(&message.signing_bytes());
+
+    let sk1 = add_mod(signature.sigma, P - h);
+    let sk2 = add_mod(signature.sigma, h);
+    let sk = if Self::public_key(sk1) == *pk {
+      sk1
+    } else if Self::public_key(sk2) == *pk {
+      sk2
+    } else {
+      return false;
+    };
+
+    let expected = if LegendrePRF::legendre_symbol(add_mod(sk, h)) == 1 { add_mod(sk, P - h) } else { add_mod(sk, h) };
+    Self::merkle_root(expected, h) == signature.merkle_root
+  }
+}
+
+// Estimated gas of a verification call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasEstimate {
+  pub intrinsic: u64, // Base cost of a standalone transaction
+  pub calldata: u64,
+  pub execution: u64,
+}
+
+impl GasEstimate {
+  pub fn total(&self) -> u64 {
+    self.intrinsic + self.calldata + self.execution
+  }
+}
+
+// A proof ready to be submitted to the on-chain verifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmProof {
+  pub public_key: [u8; 32],
+  pub message: Vec<u8>, // Canonical message encoding, hashed on-chain
+  pub signature: EvmSignature,
+}
+
+impl EvmProof {
+  pub fn new<M: SignableMessage + ?Sized>(public_key: [u8; 32], message: &M, signature: EvmSignature) -> Self {
+    Self {
+      public_key,
+      message: message.signing_bytes(),
+      signature,
+    }
+  }
+
+  // Signs a credential's signing payload under the keccak profile, for issuers that publish
+  // an on-chain attestation alongside the credential's own proof
+  #[cfg(feature = "vc")]
+  pub fn for_credential(credential: &Credential, sk: u128) -> Result<Self, RngHealthError> {
+    let payload = credential.signing_payload();
+    Ok(Self::new(EvmLoquat::public_key(sk), &payload, EvmLoquat::sign(sk, &payload)?))
+  }
+
+  pub fn verify(&self) -> bool {
+    EvmLoquat::verify(&self.public_key, &SigningBytes(&self.message), &self.signature)
+  }
+
+  // ABI-encoded call to verify(bytes32 pk, bytes message, uint256 sigma, bytes32 root)
+  pub fn calldata(&self) -> Vec<u8> {
+    let padded_len = self.message.len().div_ceil(32) * 32;
+    let mut out = Vec::with_capacity(4 + 32 * 5 + padded_len);
+    out.extend_from_slice(&function_selector(EVM_VERIFY_FUNCTION));
+    out.extend_from_slice(&self.public_key);
+    out.extend_from_slice(&word(4 * 32)); // Offset of the dynamic bytes argument
+    out.extend_from_slice(&word(self.signature.sigma));
+    out.extend_from_slice(&self.signature.merkle_root);
+    out.extend_from_slice(&word(self.message.len() as u128));
+    out.extend_from_slice(&self.message);
+    out.resize(4 + 32 * 5 + padded_len, 0);
+    out
+  }
+
+  // Decodes calldata produced by calldata(), rejecting other selectors and non-canonical encodings
+  pub fn from_calldata(calldata: &[u8]) -> Option<Self> {
+    let (selector, args) = calldata.split_at_checked(4)?;
+    if selector != function_selector(EVM_VERIFY_FUNCTION) || args.len() < 5 * 32 {
+      return None;
+    }
+    let word_at = |i: usize| &args[i * 32..(i + 1) * 32];
+
+    if word_to_u128(word_at(1))? != 4 * 32 {
+      return None;
+    }
+    let len = word_to_u128(word_at(4))? as usize;
+    if args.len() != 5 * 32 + len.div_ceil(32) * 32 || args[5 * 32 + len..].iter().any(|&b| b != 0) {
+      return None;
+    }
+
+    Some(Self {
+      public_key: word_at(0).try_into().ok()?,
+      message: args[5 * 32..5 * 32 + len].to_vec(),
+      signature: EvmSignature {
+        sigma: word_to_u128(word_at(2))?,
+        merkle_root: word_at(3).try_into().ok()?,
+      },
+    })
+  }
+
+  // Estimates the gas of calling the reference verifier with this proof
+  pub fn estimate_gas(&self) -> GasEstimate {
+    let calldata = self
+      .calldata()
+      .iter()
+      .map(|&b| if b == 0 { CALLDATA_ZERO_BYTE_GAS } else { CALLDATA_NONZERO_BYTE_GAS })
+      .sum();
+
+    let keccak = |len: usize| KECCAK_BASE_GAS + KECCAK_WORD_GAS * len.div_ceil(32) as u64;
+    let execution = keccak(self.message.len()) + 2 * keccak(16) + keccak(64) + WARM_STATICCALL_GAS + MODEXP_GAS + VERIFIER_OVERHEAD_GAS;
+
+    GasEstimate {
+      intrinsic: TX_BASE_GAS,
+      calldata,
+      execution,
+    }
+  }
+}
+
+// Gas of verifying a signature over message, assuming a public key without zero bytes (each
+// zero byte would save 12 gas of calldata)
+pub fn estimate_gas_for(message: &[u8], signature: &EvmSignature) -> GasEstimate {
+  EvmProof {
+    public_key: [0xff; 32],
+    message: message.to_vec(),
+    signature: *signature,
+  }
+  .estimate_gas()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_keccak256_vector() {
+    assert_eq!(
+      crate::utils::encoding::Encoding::to_hex(&keccak256(b"")),
+      "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(function_selector("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+  }
+
+  #[test]
+  fn test_evm_sign_verify() {
+    let keypair = EvmLoquat::keygen();
+    let pk: [u8; 32] = keypair.public_key.clone().try_into().unwrap();
+    let signature = EvmLoquat::sign(keypair.secret_key, b"on-chain message").unwrap();
+
+    assert!(EvmLoquat::verify(&pk, b"on-chain message", &signature));
+    assert!(!EvmLoquat::verify(&pk, b"other message", &signature));
+    assert!(!EvmLoquat::verify(&EvmLoquat::keygen().public_key.try_into().unwrap(), b"on-chain message", &signature));
+
+    let mut tampered = signature;
+    tampered.merkle_root[0] ^= 1;
+    assert!(!EvmLoquat::verify(&pk, b"on-chain message", &tampered));
+  }
+
+  #[test]
+  fn test_contract_mirror_agrees_with_keccak_suite() {
+    let config = EvmLoquat::config();
+    let keypair = Loquat::keygen_with_config(&config);
+    let signature = Loquat::sign_with_config(keypair.secret_key, b"message", &config).unwrap();
+    assert!(Loquat::verify_with_config(&keypair.public_key, b"message", &signature, &config));
+
+    let evm = EvmSignature::from_loquat(&signature).unwrap();
+    assert!(EvmLoquat::verify(&keypair.public_key.clone().try_into().unwrap(), b"message", &evm));
+    assert!(EvmSignature::from_loquat(&Loquat::sign(keypair.secret_key, b"message").unwrap()).is_none());
+  }
+
+  #[test]
+  #[cfg(feature = "vc")]
+  fn test_calldata_round_trip() {
+    let keypair = EvmLoquat::keygen();
+    let credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    let proof = EvmProof::for_credential(&credential, keypair.secret_key).unwrap();
+    assert!(proof.verify());
+
+    let calldata = proof.calldata();
+    assert_eq!((calldata.len() - 4) % 32, 0);
+    assert_eq!(&calldata[..4], &function_selector(EVM_VERIFY_FUNCTION));
+    assert_eq!(EvmProof::from_calldata(&calldata), Some(proof.clone()));
+
+    assert!(EvmProof::from_calldata(&calldata[..calldata.len() - 32]).is_none());
+
+    // Non-zero padding after a 33-byte message is not a canonical encoding
+    let message = [1u8; 33];
+    let mut padded = EvmProof::new(EvmLoquat::public_key(keypair.secret_key), &message, EvmLoquat::sign(keypair.secret_key, &message).unwrap()).calldata();
+    *padded.last_mut().unwrap() = 1;
+    assert!(EvmProof::from_calldata(&padded).is_none());
+  }
+
+  #[test]
+  fn test_gas_estimate() {
+    let keypair = EvmLoquat::keygen();
+    let message = vec![0xAB; 100];
+    let proof = EvmProof::new(EvmLoquat::public_key(keypair.secret_key), &message, EvmLoquat::sign(keypair.secret_key, &message).unwrap());
+    let estimate = proof.estimate_gas();
+
+    assert!(estimate.calldata >= 100 * CALLDATA_NONZERO_BYTE_GAS);
+    assert!(estimate.calldata <= proof.calldata().len() as u64 * CALLDATA_NONZERO_BYTE_GAS);
+    assert!(estimate.total() > TX_BASE_GAS && estimate.total() < 40_000);
+  }
+}
@@ -0,0 +1,13 @@
+//! # Export Module
+//!
+//! This module serializes Loquat verification artifacts for verifiers outside this crate,
+//! such as smart contracts and external proving toolchains.
+//!
+//! ## Module Structure
+//! - `evm`: Keccak-based signing profile, ABI calldata encoding and gas estimates for an
+//!   on-chain Solidity verifier
+//! - `r1cs`: `.r1cs`, `.wtns` and JSON export of constraint systems and witnesses for
+//!   circom/snarkjs tooling
+
+pub mod evm;
+pub mod r1cs;
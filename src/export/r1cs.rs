@@ -0,0 +1,203 @@
+// Constraint system and witness export in the iden3 formats read by circom and snarkjs
+// Wires are numbered as circom does: 0 is the constant 1, then the public inputs (exported as
+// public inputs, with no public outputs), then the witnesses (exported as internal wires, with
+// no private inputs). The field is 2^127 - 1, so field elements take n8 = 16 bytes,
+// little-endian; snarkjs reads and checks such files, while proving with its BN254 and
+// BLS12-381 backends needs a converter to one of those fields.
+// - `.r1cs`: "r1cs" | version 1 | 3 sections (header, constraints, wire-to-label map)
+// - `.wtns`: "wtns" | version 2 | 2 sections (header, values)
+// - JSON: the layouts of `snarkjs r1cs export json` and `snarkjs wtns export json`
+
+use crate::circuit::r1cs::{Assignment, ConstraintSystem, LinearCombination, Variable};
+use crate::compact::field;
+use serde_json::{json, Map, Value};
+
+// Bytes per field element
+pub const FIELD_BYTES: u32 = 16;
+
+fn wire(cs: &ConstraintSystem, variable: Variable) -> u32 {
+  match variable {
+    Variable::One => 0,
+    Variable::Input(i) => 1 + i as u32,
+    Variable::Witness(i) => 1 + (cs.inputs().len() + i) as u32,
+  }
+}
+
+fn num_wires(cs: &ConstraintSystem) -> u32 {
+  (1 + cs.inputs().len() + cs.witnesses().len()) as u32
+}
+
+// Terms merged per wire, sorted by wire id, without zero coefficients
+fn normalize(cs: &ConstraintSystem, lc: &LinearCombination) -> Vec<(u32, u128)> {
+  lc.normalized().into_iter().map(|(variable, coefficient)| (wire(cs, variable), coefficient)).collect()
+}
+
+fn write_section(out: &mut Vec<u8>, section_type: u32, body: &[u8]) {
+  out.extend_from_slice(&section_type.to_le_bytes());
+  out.extend_from_slice(&(body.len() as u64).to_le_bytes());
+  out.extend_from_slice(body);
+}
+
+// Binary `.r1cs` encoding of the constraints
+pub fn to_r1cs_bytes(cs: &ConstraintSystem) -> Vec<u8> {
+  let mut header = FIELD_BYTES.to_le_bytes().to_vec();
+  header.extend_from_slice(&field::P.to_le_bytes());
+  header.extend_from_slice(&num_wires(cs).to_le_bytes());
+  header.extend_from_slice(&0u32.to_le_bytes()); // Public outputs
+  header.extend_from_slice(&(cs.inputs().len() as u32).to_le_bytes());
+  header.extend_from_slice(&0u32.to_le_bytes()); // Private inputs
+  header.extend_from_slice(&(num_wires(cs) as u64).to_le_bytes()); // Labels
+  header.extend_from_slice(&(cs.num_constraints() as u32).to_le_bytes());
+
+  let mut constraints = vec![];
+  for constraint in cs.constraints() {
+    for lc in [&constraint.a, &constraint.b, &constraint.c] {
+      let terms = normalize(cs, lc);
+      constraints.extend_from_slice(&(terms.len() as u32).to_le_bytes());
+      for (wire_id, coefficient) in terms {
+        constraints.extend_from_slice(&wire_id.to_le_bytes());
+        constraints.extend_from_slice(&coefficient.to_le_bytes());
+      }
+    }
+  }
+
+  let labels: Vec<u8> = (0..num_wires(cs) as u64).flat_map(|label| label.to_le_bytes()).collect();
+
+  let mut out = b"r1cs".to_vec();
+  out.extend_from_slice(&1u32.to_le_bytes());
+  out.extend_from_slice(&3u32.to_le_bytes());
+  write_section(&mut out, 1, &header);
+  write_section(&mut out, 2, &constraints);
+  write_section(&mut out, 3, &labels);
+  out
+}
+
+// Binary `.wtns` encoding of the full assignment (1, inputs, witnesses)
+pub fn to_wtns_bytes(assignment: &Assignment) -> Vec<u8> {
+  let values = assignment.to_vector();
+  let mut header = FIELD_BYTES.to_le_bytes().to_vec();
+  header.extend_from_slice(&field::P.to_le_bytes());
+  header.extend_from_slice(&(values.len() as u32).to_le_bytes());
+  let body: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+
+  let mut out = b"wtns".to_vec();
+  out.extend_from_slice(&2u32.to_le_bytes());
+  out.extend_from_slice(&2u32.to_le_bytes());
+  write_section(&mut out, 1, &header);
+  write_section(&mut out, 2, &body);
+  out
+}
+
+// Parses a `.wtns` file written by to_wtns_bytes back into an assignment with `num_inputs`
+// public inputs
+pub fn from_wtns_bytes(bytes: &[u8], num_inputs: usize) -> Option<Assignment> {
+  let read_u32 = |offset: usize| Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?));
+  if bytes.get(..4)? != b"wtns" || read_u32(4)? != 2 || read_u32(8)? != 2 || read_u32(12)? != 1 || read_u32(24)? != FIELD_BYTES {
+    return None;
+  }
+  if u128::from_le_bytes(bytes.get(28..44)?.try_into().ok()?) != field::P {
+    return None;
+  }
+  let count = read_u32(44)? as usize;
+  if read_u32(48)? != 2 || bytes.len() != 60 + count * FIELD_BYTES as usize || count < 1 + num_inputs {
+    return None;
+  }
+  let values: Vec<u128> = bytes[60..].chunks(FIELD_BYTES as usize).map(|chunk| u128::from_le_bytes(chunk.try_into().unwrap())).collect();
+  if values[0] != 1 || values.iter().any(|value| *value >= field::P) {
+    return None;
+  }
+  Some(Assignment {
+    inputs: values[1..1 + num_inputs].to_vec(),
+    witnesses: values[1 + num_inputs..].to_vec(),
+  })
+}
+
+fn lc_json(cs: &ConstraintSystem, lc: &LinearCombination) -> Value {
+  let mut terms = Map::new();
+  for (wire_id, coefficient) in normalize(cs, lc) {
+    terms.insert(wire_id.to_string(), Value::String(coefficient.to_string()));
+  }
+  Value::Object(terms)
+}
+
+// JSON export of the constraints
+pub fn to_r1cs_json(cs: &ConstraintSystem) -> Value {
+  let constraints: Vec<Value> = cs.constraints().iter().map(|constraint| json!([lc_json(cs, &constraint.a), lc_json(cs, &constraint.b), lc_json(cs, &constraint.c)])).collect();
+  json!({
+    "n8": FIELD_BYTES,
+    "prime": field::P.to_string(),
+    "nVars": num_wires(cs),
+    "nOutputs": 0,
+    "nPubInputs": cs.inputs().len(),
+    "nPrvInputs": 0,
+    "nLabels": num_wires(cs),
+    "nConstraints": cs.num_constraints(),
+    "constraints": constraints,
+    "map": (0..num_wires(cs)).collect::<Vec<_>>(),
+  })
+}
+
+// JSON export of the full assignment, as decimal strings
+pub fn to_witness_json(assignment: &Assignment) -> Value {
+  Value::Array(assignment.to_vector().iter().map(|value| Value::String(value.to_string())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn square_system() -> ConstraintSystem {
+    let mut cs = ConstraintSystem::new();
+    let out = cs.alloc_input(9);
+    let x = cs.alloc_witness(3);
+    cs.enforce(x.into(), LinearCombination::from(x).plus(x, 0), LinearCombination::from(out));
+    cs
+  }
+
+  #[test]
+  fn test_r1cs_layout() {
+    let cs = square_system();
+    let bytes = to_r1cs_bytes(&cs);
+    assert_eq!(&bytes[..12], &[b'r', b'1', b'c', b's', 1, 0, 0, 0, 3, 0, 0, 0]);
+    // Header section: 4 + 16 + 4 * 4 + 8 + 4 bytes, with 3 wires and 1 public input
+    assert_eq!(&bytes[12..24], &[1, 0, 0, 0, 48, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&bytes[44..56], &[3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+
+    // Repeated wires are merged: each side of x * x = out has a single term
+    let json = to_r1cs_json(&cs);
+    assert_eq!(json["constraints"][0], json!([{"2": "1"}, {"2": "1"}, {"1": "1"}]));
+    assert_eq!(json["nPubInputs"], 1);
+    assert_eq!(json["prime"], "170141183460469231731687303715884105727");
+  }
+
+  #[test]
+  fn test_witness_roundtrip() {
+    let cs = square_system();
+    let bytes = to_wtns_bytes(&cs.assignment());
+    let decoded = from_wtns_bytes(&bytes, cs.inputs().len()).unwrap();
+    assert_eq!(decoded, cs.assignment());
+    assert!(cs.is_satisfied_by(&decoded));
+    assert_eq!(to_witness_json(&decoded), json!(["1", "9", "3"]));
+
+    let mut tampered = bytes.clone();
+    tampered[60] = 2;
+    assert!(from_wtns_bytes(&tampered, 1).is_none());
+    assert!(from_wtns_bytes(&bytes[..bytes.len() - 1], 1).is_none());
+  }
+
+  #[test]
+  fn test_export_key_relation() {
+    use crate::circuit::witness::{KeyRelation, WitnessBuilder};
+    use crate::signature::config::LoquatConfig;
+    use crate::signature::public_indices::IndexedPublicKey;
+
+    let sk = 0x0123_4567_89ab_cdef_0011_2233_4455_6677;
+    let key = IndexedPublicKey::new(sk, &[1u8; 32], 16, &LoquatConfig::default());
+    let cs = WitnessBuilder::<KeyRelation>::new(&key).constraint_system(&sk);
+    let json = to_r1cs_json(&cs);
+    assert_eq!(json["nConstraints"], 64);
+    assert_eq!(json["nPubInputs"], 16);
+    let decoded = from_wtns_bytes(&to_wtns_bytes(&cs.assignment()), 16).unwrap();
+    assert!(cs.is_satisfied_by(&decoded));
+  }
+}
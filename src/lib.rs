@@ -2,9 +2,116 @@
 //!
 //! This crate provides functionality for creating and verifying digital signatures
 //! using the Loquat signature scheme, which is designed for use in verifiable credentials.
+//!
+//! Building with `--no-default-features` compiles only the `compact` verifier, without the
+//! standard library.
+//! `--no-default-features --features verifier` adds `crypto`, `signature` and `utils` with
+//! their verification code only: keys, signatures, aggregates, ring signatures (with
+//! `experimental`) and their encodings can be decoded and checked, but nothing that generates keys, signs or proves is
+//! compiled, and neither is `rand`. Relying parties such as API gateways and verifier apps get
+//! a smaller binary with less code exposed to untrusted input; `std` is `verifier` plus the
+//! signing and proving side.
+//!
+//! Features select the algorithms and layers that are compiled in; the default set is `std`
+//! and `vc` (signatures over SHA3-256, SHAKE-128 and Keccak-256 suites plus the credential
+//! data model):
+//! - `poseidon`, `griffin`: Algebraic hash functions and the suites that use them
+//! - `fri`: `proof_system`, the FRI-based low-degree tests and sumcheck proofs
+//! - `vc`: `vc` and `registry`, the credential data model and transparency log
+//! - `jose`: JWT, SD-JWT and Anoncreds encodings and the OpenID4VCI / OpenID4VP flows
+//! - `cose`, `kem`, `didcomm`, `async`, `toml`: Further credential encodings and transports
+//! - `wasm`: C-ABI exports of the compact verifier for WebAssembly hosts
+//! - `parallel`: Multi-threaded batch verification and FRI proving
+//! - `verifier`: The std verification layer without signing, proving or `rand` (see above)
+//! - `experimental`: VRF, key ownership proofs, pseudonyms and ring signatures; their proofs
+//!   reveal the secret key to the verifier (see `signature`)
+//! - `test-utils`: `TestRng::install`, which seeds keygen and signing for reproducible tests,
+//!   and the insecure `ParamsHandle::M61`; never enable it outside dev-dependencies
+//!
+//! Suite identifiers stay stable across feature sets: a build without `poseidon` rejects
+//! Poseidon-suite encodings as unknown rather than misreading them.
+//!
+//! The `tracing` feature wraps signing, verification, proving and the credential protocol
+//! entry points in `tracing` spans at debug level. Verification spans record their outcome,
+//! and subscribers that report span close events (e.g. `FmtSpan::CLOSE`) log their duration.
+//!
+//! `selftest()` runs power-on known-answer tests of the hash functions, the Legendre PRF,
+//! field arithmetic and signing, plus randomness health checks, and returns a report.
+//!
+//! `use loquat_vc::prelude::*` brings the commonly used signature, hashing, field and
+//! credential types into scope.
+
+#![cfg_attr(not(feature = "verifier"), no_std)]
 
 // Public modules
+pub mod compact;
+#[cfg(feature = "std")]
+pub mod circuit;
+#[cfg(feature = "verifier")]
 pub mod crypto;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "fri")]
 pub mod proof_system;
+#[cfg(feature = "vc")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "verifier")]
 pub mod signature;
-pub mod utils;
\ No newline at end of file
+#[cfg(feature = "verifier")]
+pub mod utils;
+#[cfg(feature = "vc")]
+pub mod vc;
+
+#[cfg(feature = "std")]
+pub use selftest::selftest;
+
+// Verifiers, issuers and the values they exchange are shared across the worker threads of
+// async servers (e.g. behind an Arc); losing Send or Sync on any of them is a compile error
+#[cfg(feature = "std")]
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<signature::LoquatKeyPair>();
+  assert_send_sync::<signature::LoquatSignature>();
+  assert_send_sync::<signature::aggregate::Aggregator>();
+  assert_send_sync::<signature::cache::CachedVerifier>();
+  assert_send_sync::<crypto::MerkleTree>();
+  assert_send_sync::<crypto::oprf::OprfServer>();
+};
+
+#[cfg(feature = "vc")]
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<vc::Credential>();
+  assert_send_sync::<vc::CredentialBuilder>();
+  assert_send_sync::<vc::Presentation>();
+  assert_send_sync::<vc::did::StaticDidResolver>();
+  assert_send_sync::<vc::status::InMemoryStatusLists>();
+  assert_send_sync::<vc::policy::PolicySet>();
+  assert_send_sync::<vc::proof_scheme::SchemeRegistry>();
+  assert_send_sync::<vc::trust_registry::TrustRegistry>();
+  assert_send_sync::<vc::challenge::ChallengeManager>();
+  assert_send_sync::<registry::log::CredentialLog>();
+};
+
+#[cfg(all(feature = "std", feature = "experimental"))]
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<signature::ring::Ring>();
+};
+
+#[cfg(all(feature = "vc", feature = "experimental"))]
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<vc::pseudonym::Holder>();
+};
+
+#[cfg(feature = "jose")]
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<vc::oid4vci::CredentialIssuer>();
+  assert_send_sync::<vc::oid4vp::PresentationVerifier>();
+};
@@ -0,0 +1,35 @@
+// Commonly used types under one import
+// Integrations that sign and verify credentials otherwise need a path per module
+// (signature::loquat, vc::credential, crypto::hash_functions, ...); the modules re-export
+// their main types at their root and this module gathers those re-exports.
+
+pub use crate::crypto::{Hash, HashFunction, MerkleTree};
+pub use crate::signature::timestamp::{MaxAge, TimestampedSignature};
+pub use crate::signature::{Loquat, LoquatConfig, LoquatKeyPair, LoquatSignature, VerifyError};
+pub use crate::utils::FieldElement;
+#[cfg(feature = "vc")]
+pub use crate::vc::{Credential, CredentialBuilder, CredentialStatus, DidDocument, DidResolver, Presentation, Proof};
+
+#[cfg(all(test, feature = "vc"))]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_prelude_covers_issuance() {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({ "id": "did:example:holder" }));
+    credential.sign(issuer.secret_key, "did:example:issuer#key-1").unwrap();
+    assert!(credential.verify(&issuer.public_key));
+
+    let mut presentation = Presentation::new("did:example:holder", vec![credential], "challenge");
+    presentation.sign(holder.secret_key, "did:example:holder#key-1").unwrap();
+    assert!(presentation.verify(&holder.public_key));
+
+    // Flattened paths name the same types as the full ones
+    let signature: LoquatSignature = crate::signature::loquat::Loquat::sign(issuer.secret_key, b"message").unwrap();
+    assert!(Loquat::verify(&issuer.public_key, b"message", &signature));
+    assert_eq!(FieldElement::new(3).mul(&FieldElement::new(5)), FieldElement::new(15));
+  }
+}
@@ -0,0 +1,934 @@
+// FRI low-degree test over Goldilocks
+// The prover commits to the evaluations of a polynomial of degree below 2^log_degree_bound on
+// a coset g * <w> of 2^(log_degree_bound + log_blowup) points (g the multiplicative generator,
+// so the coset avoids every subgroup used as a summation domain). A binary fold with a
+// challenge beta, f'(x^2) = (f(x) + f(-x)) / 2 + beta * (f(x) - f(-x)) / 2x, halves both the
+// degree bound and the domain; each round applies k = folding_factor / 2^i of them in a row
+// with beta, beta^2, beta^4, ..., which is the k-ary fold f'(y) = sum_t beta^t f_t(y) of
+// f(x) = sum_t x^t f_t(x^k), until a constant remains. The verifier checks the folds at
+// transcript-derived query positions only.
+//
+// Tables are committed by cosets: for a round folding by k, leaf j holds the values at
+// positions j, j + N/k, ..., j + (k - 1) N/k, so one Merkle opening per layer serves one fold.
+// Larger folding factors mean fewer layers and openings but longer leaves and a weaker commit
+// phase (see soundness). Grinding makes the prover find a proof-of-work nonce before the
+// query positions are drawn, adding its bits to the query phase at little cost to verifiers.
+// prove_pruned lays the openings out per layer instead of per query (PrunedFriProof): every
+// queried coset and every authentication node shared between paths is sent once, in an order
+// verifiers replay from the query positions.
+// The first layer is supplied by the caller, which typically opens it from its own committed
+// tables at the query positions and combines them, as the univariate sumcheck does. With the
+// `parallel` feature, proof_system::parallel produces the same proofs on several threads.
+//
+// With DEEP sampling (DEEP-FRI, Ben-Sasson, Goldberg, Kopparty, Saraf 2019) the verifier first
+// draws a point z outside the domain and the prover claims v = f(z); FRI then runs on
+//   h(x) = x * (f(x) - v) / (x - z),
+// which has degree below 2^log_degree_bound exactly when f does and v is its value at z (the
+// factor x keeps the bound on the quotient one below that on f). Verifiers compute h from the
+// caller's values at the queried points, so callers and commitments are unchanged. A table far
+// from every low-degree polynomial gives a quotient far from them too, up to the Johnson
+// bound rather than unique decoding, which is what lets soundness count more bits per query.
+
+use crate::crypto::goldilocks::{batch_inverse, ntt, Goldilocks, GOLDILOCKS_P, MULTIPLICATIVE_GENERATOR, TWO_ADICITY};
+use crate::crypto::hash_functions::HashFunction;
+use crate::crypto::merkle::MerkleTree;
+use crate::proof_system::transcript::Transcript;
+use num_bigint::BigUint;
+use sha3::{Digest, Sha3_256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FriConfig {
+  pub log_blowup: u32, // log2 of the evaluation domain size over the degree bound
+  pub num_queries: usize,
+  pub folding_factor: usize, // Values folded into one per round, one of FOLDING_FACTORS
+  pub grinding_bits: u32, // Zero bits of the proof of work before the queries, 0 for none
+  pub deep: bool, // Out-of-domain sample before folding
+}
+
+pub const FOLDING_FACTORS: [usize; 4] = [2, 4, 8, 16];
+
+// Proofs of work beyond this are not searched for
+pub const MAX_GRINDING_BITS: u32 = 32;
+
+// Blowup 4 with 32 queries, binary folding, no grinding and no DEEP sample: 64 bits in the
+// conjectured regime (see soundness)
+impl Default for FriConfig {
+  fn default() -> Self {
+    Self {
+      log_blowup: 2,
+      num_queries: 32,
+      folding_factor: 2,
+      grinding_bits: 0,
+      deep: false,
+    }
+  }
+}
+
+impl FriConfig {
+  // log2 of the folding factor of each round for a degree bound of 2^log_degree_bound: the
+  // configured factor, then whatever is left in the last round
+  pub fn round_log_arities(&self, log_degree_bound: u32) -> Vec<u32> {
+    let step = self.folding_factor.trailing_zeros().max(1);
+    let mut arities = vec![];
+    let mut remaining = log_degree_bound;
+    while remaining > 0 {
+      arities.push(step.min(remaining));
+      remaining -= step.min(remaining);
+    }
+    arities
+  }
+
+  // Values per leaf of the first table, which the caller commits; pairs when nothing is folded
+  pub fn first_layer_arity(&self, log_degree_bound: u32) -> usize {
+    1 << self.round_log_arities(log_degree_bound).first().copied().unwrap_or(1)
+  }
+
+  fn is_valid(&self) -> bool {
+    FOLDING_FACTORS.contains(&self.folding_factor) && self.grinding_bits <= MAX_GRINDING_BITS
+  }
+}
+
+// Coset shift * <generator> of 2^log_size points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluationDomain {
+  pub log_size: u32,
+  pub shift: Goldilocks,
+  pub generator: Goldilocks,
+}
+
+impl EvaluationDomain {
+  // The coset of the multiplicative generator; None beyond the field's two-adicity
+  pub fn coset(log_size: u32) -> Option<Self> {
+    Some(Self {
+      log_size,
+      shift: Goldilocks::new(MULTIPLICATIVE_GENERATOR),
+      generator: Goldilocks::root_of_unity(log_size)?,
+    })
+  }
+
+  // The subgroup <generator> itself, e.g. a summation domain
+  pub fn subgroup(log_size: u32) -> Option<Self> {
+    Some(Self {
+      log_size,
+      shift: Goldilocks::ONE,
+      generator: Goldilocks::root_of_unity(log_size)?,
+    })
+  }
+
+  pub fn size(&self) -> usize {
+    1 << self.log_size
+  }
+
+  pub fn element(&self, index: usize) -> Goldilocks {
+    self.shift * self.generator.pow(index as u64)
+  }
+
+  // Domain of the next layer: the squares of this one
+  pub fn fold(&self) -> Self {
+    Self {
+      log_size: self.log_size - 1,
+      shift: self.shift * self.shift,
+      generator: self.generator * self.generator,
+    }
+  }
+
+  // Evaluates a polynomial (coefficients, lowest degree first) on every point of the domain
+  // None if the polynomial has more coefficients than the domain has points
+  pub fn evaluate(&self, coeffs: &[Goldilocks]) -> Option<Vec<Goldilocks>> {
+    if coeffs.len() > self.size() {
+      return None;
+    }
+    let mut values = vec![Goldilocks::ZERO; self.size()];
+    let mut power = Goldilocks::ONE;
+    for (value, c) in values.iter_mut().zip(coeffs) {
+      *value = *c * power;
+      power = power * self.shift;
+    }
+    if !ntt(&mut values) {
+      return None;
+    }
+    Some(values)
+  }
+
+  // Value at `point` of the polynomial of degree below the domain size with these evaluations,
+  // in O(n) without interpolating. On the coset D = g * <w> of size n, with x_i = g * w^i,
+  //   p(z) = (z^n - g^n) / (n * g^n) * sum_i values[i] * x_i / (z - x_i)
+  // None if values does not cover the domain
+  pub fn barycentric_eval(&self, values: &[Goldilocks], point: Goldilocks) -> Option<Goldilocks> {
+    if values.len() != self.size() {
+      return None;
+    }
+    let mut elements = Vec::with_capacity(self.size());
+    let mut x = self.shift;
+    for _ in 0..self.size() {
+      elements.push(x);
+      x = x * self.generator;
+    }
+    if let Some(position) = elements.iter().position(|x| *x == point) {
+      return Some(values[position]);
+    }
+
+    let differences: Vec<Goldilocks> = elements.iter().map(|x| point - *x).collect();
+    let inverses = batch_inverse(&differences)?;
+    let sum = values.iter().zip(&elements).zip(&inverses).fold(Goldilocks::ZERO, |acc, ((value, x), inverse)| acc + *value * *x * *inverse);
+    let shift_n = self.shift.pow(self.size() as u64);
+    let scale = (point.pow(self.size() as u64) - shift_n) * (Goldilocks::new(self.size() as u64) * shift_n).inverse()?;
+    Some(scale * sum)
+  }
+}
+
+// Opening of the values on one coset of k-th roots, x * w_k^m for m < k; a pair x, -x for k = 2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CosetOpening {
+  pub values: Vec<Goldilocks>,
+  pub path: Vec<(BigUint, bool)>,
+}
+
+pub(crate) fn coset_digest(values: &[Goldilocks]) -> [u8; 32] {
+  values.iter().fold(Sha3_256::new(), |hasher, value| hasher.chain_update(value.value().to_be_bytes())).finalize().into()
+}
+
+fn coset_leaf(values: &[Goldilocks]) -> BigUint {
+  BigUint::from_bytes_be(&coset_digest(values))
+}
+
+impl CosetOpening {
+  // Checks the opening of coset `index` of a table of 2^log_size values, committed in cosets of
+  // as many values as the opening holds
+  pub fn verify(&self, root: &BigUint, index: usize, log_size: u32) -> bool {
+    let arity = self.values.len();
+    if arity < 2 || !arity.is_power_of_two() || arity.trailing_zeros() > log_size {
+      return false;
+    }
+    let depth = log_size - arity.trailing_zeros();
+    if index >= 1 << depth || self.path.len() != depth as usize {
+      return false;
+    }
+    // The left/right flags must spell out the index, so an opening cannot be moved
+    let position_matches = self.path.iter().enumerate().all(|(level, (_, is_left))| *is_left == ((index >> level) & 1 == 0));
+    position_matches && MerkleTree::verify_proof(root, &coset_leaf(&self.values), &self.path, &HashFunction::Sha3_256)
+  }
+}
+
+// Evaluation table committed by cosets
+#[derive(Debug, Clone)]
+pub struct CommittedTable {
+  values: Vec<Goldilocks>,
+  arity: usize,
+  tree: MerkleTree,
+}
+
+impl CommittedTable {
+  // Commits to a table of at least two values whose length is a power of two, pairwise
+  pub fn commit(values: Vec<Goldilocks>) -> Option<Self> {
+    Self::commit_cosets(values, 2)
+  }
+
+  // Commits in cosets of `arity` values, a power of two between 2 and the table length
+  pub fn commit_cosets(values: Vec<Goldilocks>, arity: usize) -> Option<Self> {
+    if arity < 2 || !arity.is_power_of_two() || values.len() < arity || !values.len().is_power_of_two() {
+      return None;
+    }
+    let cosets = values.len() / arity;
+    let leaves = (0..cosets)
+      .map(|j| coset_digest(&(0..arity).map(|m| values[j + m * cosets]).collect::<Vec<_>>()))
+      .collect();
+    Some(Self {
+      tree: MerkleTree::from_leaves(leaves, HashFunction::Sha3_256),
+      values,
+      arity,
+    })
+  }
+
+  // A table whose tree was built elsewhere over its coset digests, as commit_cosets builds it
+  #[cfg(feature = "parallel")]
+  pub(crate) fn from_tree(values: Vec<Goldilocks>, arity: usize, tree: MerkleTree) -> Self {
+    Self { values, arity, tree }
+  }
+
+  pub fn root(&self) -> BigUint {
+    self.tree.root().expect("Table has at least one pair")
+  }
+
+  pub fn values(&self) -> &[Goldilocks] {
+    &self.values
+  }
+
+  pub fn arity(&self) -> usize {
+    self.arity
+  }
+
+  // Number of cosets, i.e. of leaves
+  pub fn cosets(&self) -> usize {
+    self.values.len() / self.arity
+  }
+
+  pub fn open(&self, index: usize) -> Option<CosetOpening> {
+    let cosets = self.cosets();
+    if index >= cosets {
+      return None;
+    }
+    Some(CosetOpening {
+      values: (0..self.arity).map(|m| self.values[index + m * cosets]).collect(),
+      path: self.tree.generate_proof(index)?,
+    })
+  }
+
+  // Openings of several cosets sharing their authentication nodes; indices may repeat
+  pub fn open_many(&self, indices: &[usize]) -> Option<MultiOpening> {
+    let mut distinct = indices.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let cosets = self.cosets();
+    if distinct.last().is_some_and(|&index| index >= cosets) {
+      return None;
+    }
+    Some(MultiOpening {
+      values: distinct.iter().map(|&index| (0..self.arity).map(|m| self.values[index + m * cosets]).collect()).collect(),
+      nodes: self.tree.multi_path(&distinct)?,
+    })
+  }
+}
+
+// Openings of the distinct cosets a set of queries hits in one table: their values by
+// ascending coset index, then MerkleTree::multi_path for those indices, so a coset queried
+// twice and a node on the paths of several cosets are each sent once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiOpening {
+  pub values: Vec<Vec<Goldilocks>>,
+  pub nodes: Vec<[u8; 32]>,
+}
+
+impl MultiOpening {
+  // The opening of each of the queried cosets (repeats allowed) in a table of 2^log_size values
+  // committed in cosets of `arity`; None if the openings do not have that layout. The openings
+  // are not checked here, CosetOpening::verify does that
+  pub fn expand(&self, indices: &[usize], log_size: u32, arity: usize) -> Option<Vec<CosetOpening>> {
+    let mut distinct = indices.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() != self.values.len() || self.values.iter().any(|values| values.len() != arity) || arity.trailing_zeros() > log_size {
+      return None;
+    }
+    let position = |index: &usize| distinct.binary_search(index).expect("Index is among the distinct ones");
+    let leaves: Vec<(usize, [u8; 32])> = indices.iter().map(|index| (*index, coset_digest(&self.values[position(index)]))).collect();
+    let paths = MerkleTree::expand_multi_path(&HashFunction::Sha3_256, 1 << (log_size - arity.trailing_zeros()), &leaves, &self.nodes)?;
+    Some(
+      indices
+        .iter()
+        .zip(paths)
+        .map(|(index, path)| CosetOpening {
+          values: self.values[position(index)].clone(),
+          path: path.iter().map(|(sibling, is_left)| (BigUint::from_bytes_be(sibling), *is_left)).collect(),
+        })
+        .collect(),
+    )
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FriProof {
+  pub layer_roots: Vec<BigUint>, // One per round but the last
+  pub final_value: Goldilocks, // The constant the last fold reaches
+  pub pow_nonce: u64, // Grinding nonce, 0 without grinding
+  pub deep_value: Option<Goldilocks>, // f(z) at the out-of-domain point, with DEEP sampling
+  pub queries: Vec<Vec<CosetOpening>>, // Per query, one opening per committed layer
+}
+
+// Bytes of the roots, field elements and nodes of a proof; side flags are left out since they
+// follow from the query positions
+fn header_bytes(layer_roots: &[BigUint], deep_value: Option<Goldilocks>) -> usize {
+  32 * layer_roots.len() + 8 + 8 + 8 * deep_value.map_or(0, |_| 1)
+}
+
+impl FriProof {
+  pub fn size_bytes(&self) -> usize {
+    let openings: usize = self.queries.iter().flatten().map(|opening| 8 * opening.values.len() + 32 * opening.path.len()).sum();
+    header_bytes(&self.layer_roots, self.deep_value) + openings
+  }
+}
+
+// FriProof with the queries laid out per layer instead of per query (see MultiOpening);
+// verify_pruned rebuilds the per-query openings from the query positions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedFriProof {
+  pub layer_roots: Vec<BigUint>,
+  pub final_value: Goldilocks,
+  pub pow_nonce: u64,
+  pub deep_value: Option<Goldilocks>,
+  pub layers: Vec<MultiOpening>, // One per committed layer
+}
+
+impl PrunedFriProof {
+  pub fn size_bytes(&self) -> usize {
+    let openings: usize = self.layers.iter().map(|layer| 8 * layer.values.iter().map(Vec::len).sum::<usize>() + 32 * layer.nodes.len()).sum();
+    header_bytes(&self.layer_roots, self.deep_value) + openings
+  }
+
+  // The per-query proof for the layer-0 query positions, given the log2 folding factor of
+  // each round
+  fn expand(&self, indices: &[usize], arities: &[u32], domain: &EvaluationDomain) -> Option<FriProof> {
+    if self.layers.len() != self.layer_roots.len() {
+      return None;
+    }
+    let mut log_size = domain.log_size;
+    let mut per_layer = vec![];
+    for (round, layer) in self.layers.iter().enumerate() {
+      log_size -= arities[round];
+      let arity = 1usize << arities.get(round + 1)?;
+      let cosets = (1usize << log_size) / arity;
+      let positions: Vec<usize> = indices.iter().map(|index| index % cosets).collect();
+      per_layer.push(layer.expand(&positions, log_size, arity)?);
+    }
+    let queries = (0..indices.len()).map(|query| per_layer.iter().map(|openings| openings[query].clone()).collect()).collect();
+    Some(FriProof {
+      layer_roots: self.layer_roots.clone(),
+      final_value: self.final_value,
+      pow_nonce: self.pow_nonce,
+      deep_value: self.deep_value,
+      queries,
+    })
+  }
+}
+
+pub(crate) fn inverse_of_two() -> Goldilocks {
+  Goldilocks::new(GOLDILOCKS_P.div_ceil(2))
+}
+
+// One fold of the pair (f(x), f(-x)), given x^-1
+fn fold_pair(values: [Goldilocks; 2], x_inverse: Goldilocks, beta: Goldilocks) -> Goldilocks {
+  let half = inverse_of_two();
+  let [a, b] = values;
+  (a + b) * half + beta * (a - b) * half * x_inverse
+}
+
+// The binary folds of one round, with beta, beta^2, beta^4, ...; moves domain along
+pub(crate) fn fold_table(values: Vec<Goldilocks>, domain: &mut EvaluationDomain, log_arity: u32, beta: Goldilocks) -> Option<Vec<Goldilocks>> {
+  let mut current = values;
+  let mut challenge = beta;
+  for _ in 0..log_arity {
+    let half = current.len() / 2;
+    // x^-1 steps through the domain by generator^-1: two inversions per fold, not one per pair
+    let inverse_generator = domain.generator.inverse()?;
+    let mut x_inverse = domain.shift.inverse()?;
+    current = (0..half)
+      .map(|j| {
+        let folded = fold_pair([current[j], current[j + half]], x_inverse, challenge);
+        x_inverse = x_inverse * inverse_generator;
+        folded
+      })
+      .collect();
+    *domain = domain.fold();
+    challenge = challenge * challenge;
+  }
+  Some(current)
+}
+
+// fold_table on a single coset: the values at positions index + m * N/k of a layer of N values
+// fold to the value at position index of the next layer
+fn fold_coset(values: Vec<Goldilocks>, domain: EvaluationDomain, index: usize, beta: Goldilocks) -> Option<Goldilocks> {
+  let mut current = values;
+  let mut domain = domain;
+  let mut challenge = beta;
+  while current.len() > 1 {
+    let half = current.len() / 2;
+    let stride = domain.size() / current.len();
+    current = (0..half)
+      .map(|m| Some(fold_pair([current[m], current[m + half]], domain.element(index + m * stride).inverse()?, challenge)))
+      .collect::<Option<Vec<_>>>()?;
+    domain = domain.fold();
+    challenge = challenge * challenge;
+  }
+  current.pop()
+}
+
+// Leading zero bits of the proof-of-work hash of a nonce
+pub(crate) fn pow_bits(seed: &[u8; 32], nonce: u64) -> u32 {
+  let digest = Sha3_256::new().chain_update(seed).chain_update(nonce.to_be_bytes()).finalize();
+  u64::from_be_bytes(digest[..8].try_into().expect("Digest has 32 bytes")).leading_zeros()
+}
+
+// Finds and absorbs the first nonce with enough zero bits, so that every attempt at other
+// query positions costs the prover 2^bits hashes; nothing happens without grinding
+pub(crate) fn grind(transcript: &mut Transcript, bits: u32) -> u64 {
+  if bits == 0 {
+    return 0;
+  }
+  let seed = transcript.challenge_bytes("grinding");
+  let nonce = (0..u64::MAX).find(|&nonce| pow_bits(&seed, nonce) >= bits).expect("A nonce exists below 2^64");
+  transcript.absorb("nonce", &nonce.to_be_bytes());
+  nonce
+}
+
+fn check_grinding(transcript: &mut Transcript, bits: u32, nonce: u64) -> bool {
+  if bits == 0 {
+    return nonce == 0;
+  }
+  let seed = transcript.challenge_bytes("grinding");
+  transcript.absorb("nonce", &nonce.to_be_bytes());
+  pow_bits(&seed, nonce) >= bits
+}
+
+// Coset positions of layer 0, derived after every commitment has been absorbed
+pub(crate) fn query_indices(transcript: &mut Transcript, cosets: usize, num_queries: usize) -> Vec<usize> {
+  (0..num_queries)
+    .map(|_| {
+      let bytes = transcript.challenge_bytes("query");
+      (u64::from_be_bytes(bytes[..8].try_into().expect("Challenge has 32 bytes")) % cosets as u64) as usize
+    })
+    .collect()
+}
+
+// The out-of-domain point; drawn again in the negligible case that it falls on the domain
+pub(crate) fn deep_point(transcript: &mut Transcript, domain: &EvaluationDomain) -> Goldilocks {
+  let shift_n = domain.shift.pow(domain.size() as u64);
+  loop {
+    let z = transcript.challenge_goldilocks("deep");
+    if z.pow(domain.size() as u64) != shift_n {
+      return z;
+    }
+  }
+}
+
+// x * (f(x) - v) / (x - z) at each given point x; None if some x is z
+pub(crate) fn deep_quotient(points: &[Goldilocks], values: &[Goldilocks], z: Goldilocks, v: Goldilocks) -> Option<Vec<Goldilocks>> {
+  let differences: Vec<Goldilocks> = points.iter().map(|x| *x - z).collect();
+  let inverses = batch_inverse(&differences)?;
+  Some(points.iter().zip(values).zip(&inverses).map(|((x, f), inverse)| *x * (*f - v) * *inverse).collect())
+}
+
+pub(crate) fn domain_is_valid(log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig) -> bool {
+  config.is_valid() && domain.log_size == log_degree_bound + config.log_blowup && domain.log_size >= 1 && domain.log_size <= TWO_ADICITY
+}
+
+// Everything the prover derives before answering queries
+pub(crate) struct CommitPhase {
+  pub(crate) layers: Vec<CommittedTable>,
+  pub(crate) final_value: Goldilocks,
+  pub(crate) pow_nonce: u64,
+  pub(crate) deep_value: Option<Goldilocks>,
+  pub(crate) indices: Vec<usize>,
+}
+
+impl CommitPhase {
+  // Openings per query, as prove returns them
+  pub(crate) fn into_proof(self) -> Option<(FriProof, Vec<usize>)> {
+    let queries = self
+      .indices
+      .iter()
+      .map(|&index| self.layers.iter().map(|layer| layer.open(index % layer.cosets())).collect::<Option<Vec<_>>>())
+      .collect::<Option<Vec<_>>>()?;
+    Some((
+      FriProof {
+        layer_roots: self.layers.iter().map(CommittedTable::root).collect(),
+        final_value: self.final_value,
+        pow_nonce: self.pow_nonce,
+        deep_value: self.deep_value,
+        queries,
+      },
+      self.indices,
+    ))
+  }
+
+  // Openings per layer, as prove_pruned returns them
+  pub(crate) fn into_pruned(self) -> Option<(PrunedFriProof, Vec<usize>)> {
+    let layers = self
+      .layers
+      .iter()
+      .map(|layer| layer.open_many(&self.indices.iter().map(|index| index % layer.cosets()).collect::<Vec<_>>()))
+      .collect::<Option<Vec<_>>>()?;
+    Some((
+      PrunedFriProof {
+        layer_roots: self.layers.iter().map(CommittedTable::root).collect(),
+        final_value: self.final_value,
+        pow_nonce: self.pow_nonce,
+        deep_value: self.deep_value,
+        layers,
+      },
+      self.indices,
+    ))
+  }
+}
+
+fn commit_phase(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<CommitPhase> {
+  if !domain_is_valid(log_degree_bound, domain, config) || values.len() != domain.size() {
+    return None;
+  }
+
+  let mut current = values.to_vec();
+  let mut deep_value = None;
+  if config.deep {
+    let z = deep_point(transcript, domain);
+    let v = domain.barycentric_eval(values, z)?;
+    transcript.absorb_goldilocks("deep_value", &[v]);
+    let points: Vec<Goldilocks> = (0..domain.size()).map(|i| domain.element(i)).collect();
+    current = deep_quotient(&points, values, z, v)?;
+    deep_value = Some(v);
+  }
+
+  let arities = config.round_log_arities(log_degree_bound);
+  let mut layers: Vec<CommittedTable> = vec![];
+  let mut current_domain = *domain;
+  for (round, &log_arity) in arities.iter().enumerate() {
+    let beta = transcript.challenge_goldilocks("beta");
+    current = fold_table(current, &mut current_domain, log_arity, beta)?;
+    if let Some(&next_log_arity) = arities.get(round + 1) {
+      let layer = CommittedTable::commit_cosets(current.clone(), 1 << next_log_arity)?;
+      transcript.absorb("layer", &layer.root().to_bytes_be());
+      layers.push(layer);
+    }
+  }
+  let final_value = current[0];
+  transcript.absorb_goldilocks("final", &[final_value]);
+  let pow_nonce = grind(transcript, config.grinding_bits);
+
+  let indices = query_indices(transcript, domain.size() / config.first_layer_arity(log_degree_bound), config.num_queries);
+  Some(CommitPhase {
+    layers,
+    final_value,
+    pow_nonce,
+    deep_value,
+    indices,
+  })
+}
+
+// Proves that `values`, the evaluations on `domain`, come from a polynomial of degree below
+// 2^log_degree_bound; returns the proof and the layer-0 coset indices the caller must open,
+// from a table it committed in cosets of config.first_layer_arity values
+pub fn prove(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<(FriProof, Vec<usize>)> {
+  commit_phase(values, log_degree_bound, domain, config, transcript)?.into_proof()
+}
+
+// prove with the queries laid out per layer; the transcript and query positions are the same
+pub fn prove_pruned(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<(PrunedFriProof, Vec<usize>)> {
+  commit_phase(values, log_degree_bound, domain, config, transcript)?.into_pruned()
+}
+
+// The part of a proof the transcript replays, shared by both layouts
+struct Header<'a> {
+  layer_roots: &'a [BigUint],
+  final_value: Goldilocks,
+  pow_nonce: u64,
+  deep_value: Option<Goldilocks>,
+}
+
+// What the verifier derives from the commitments: the folding challenges, the DEEP point and
+// claimed value, and the layer-0 query positions
+struct QueryPlan {
+  arities: Vec<u32>,
+  betas: Vec<Goldilocks>,
+  deep: Option<(Goldilocks, Goldilocks)>,
+  indices: Vec<usize>,
+}
+
+fn replay(header: Header, log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<QueryPlan> {
+  let Header { layer_roots, final_value, pow_nonce, deep_value } = header;
+  let arities = config.round_log_arities(log_degree_bound);
+  if !domain_is_valid(log_degree_bound, domain, config) || layer_roots.len() != arities.len().saturating_sub(1) || deep_value.is_some() != config.deep {
+    return None;
+  }
+
+  let deep = deep_value.map(|v| (deep_point(transcript, domain), v));
+  if let Some((_, v)) = deep {
+    transcript.absorb_goldilocks("deep_value", &[v]);
+  }
+  let mut betas = vec![];
+  for round in 0..arities.len() {
+    betas.push(transcript.challenge_goldilocks("beta"));
+    if let Some(root) = layer_roots.get(round) {
+      transcript.absorb("layer", &root.to_bytes_be());
+    }
+  }
+  transcript.absorb_goldilocks("final", &[final_value]);
+  if !check_grinding(transcript, config.grinding_bits, pow_nonce) {
+    return None;
+  }
+  let indices = query_indices(transcript, domain.size() / config.first_layer_arity(log_degree_bound), config.num_queries);
+  Some(QueryPlan { arities, betas, deep, indices })
+}
+
+// Verifies a FRI proof; first_layer(query, coset index) returns the caller's values on that
+// layer-0 coset, in the order CommittedTable::open gives them, or None if the caller's own
+// openings do not check out
+pub fn verify(
+  proof: &FriProof,
+  log_degree_bound: u32,
+  domain: &EvaluationDomain,
+  config: &FriConfig,
+  transcript: &mut Transcript,
+  first_layer: impl FnMut(usize, usize) -> Option<Vec<Goldilocks>>,
+) -> bool {
+  let header = Header {
+    layer_roots: &proof.layer_roots,
+    final_value: proof.final_value,
+    pow_nonce: proof.pow_nonce,
+    deep_value: proof.deep_value,
+  };
+  match replay(header, log_degree_bound, domain, config, transcript) {
+    Some(plan) => check_queries(proof, &plan, domain, first_layer),
+    None => false,
+  }
+}
+
+// verify for the per-layer layout: openings are expanded to per-query ones in the order
+// prove_pruned wrote them, then checked as verify checks them
+pub fn verify_pruned(
+  proof: &PrunedFriProof,
+  log_degree_bound: u32,
+  domain: &EvaluationDomain,
+  config: &FriConfig,
+  transcript: &mut Transcript,
+  first_layer: impl FnMut(usize, usize) -> Option<Vec<Goldilocks>>,
+) -> bool {
+  let header = Header {
+    layer_roots: &proof.layer_roots,
+    final_value: proof.final_value,
+    pow_nonce: proof.pow_nonce,
+    deep_value: proof.deep_value,
+  };
+  let plan = match replay(header, log_degree_bound, domain, config, transcript) {
+    Some(plan) => plan,
+    None => return false,
+  };
+  match proof.expand(&plan.indices, &plan.arities, domain) {
+    Some(expanded) => check_queries(&expanded, &plan, domain, first_layer),
+    None => false,
+  }
+}
+
+fn check_queries(proof: &FriProof, plan: &QueryPlan, domain: &EvaluationDomain, mut first_layer: impl FnMut(usize, usize) -> Option<Vec<Goldilocks>>) -> bool {
+  if proof.queries.len() != plan.indices.len() {
+    return false;
+  }
+  let arities = &plan.arities;
+  let first_arity = 1 << arities.first().copied().unwrap_or(1);
+  plan.indices.iter().zip(&proof.queries).enumerate().all(|(query, (&index, openings))| {
+    if openings.len() != proof.layer_roots.len() {
+      return false;
+    }
+    let mut values = match first_layer(query, index) {
+      Some(values) if values.len() == first_arity => values,
+      _ => return false,
+    };
+    if let Some((z, v)) = plan.deep {
+      let cosets = domain.size() / first_arity;
+      let points: Vec<Goldilocks> = (0..first_arity).map(|m| domain.element(index + m * cosets)).collect();
+      values = match deep_quotient(&points, &values, z, v) {
+        Some(quotient) => quotient,
+        None => return false,
+      };
+    }
+    if arities.is_empty() {
+      return values.iter().all(|value| *value == proof.final_value);
+    }
+
+    let mut layer_domain = *domain;
+    let mut position = index;
+    for (round, (&log_arity, beta)) in arities.iter().zip(&plan.betas).enumerate() {
+      let folded = match fold_coset(values, layer_domain, position, *beta) {
+        Some(folded) => folded,
+        None => return false,
+      };
+      layer_domain = (0..log_arity).fold(layer_domain, |layer, _| layer.fold());
+      let opening = match openings.get(round) {
+        Some(opening) => opening,
+        None => return folded == proof.final_value,
+      };
+      // The folded value sits at position `position` of the next layer
+      if opening.values.len() != 1 << arities[round + 1] {
+        return false;
+      }
+      let cosets = layer_domain.size() / opening.values.len();
+      let next = position % cosets;
+      if !opening.verify(&proof.layer_roots[round], next, layer_domain.log_size) || opening.values[position / cosets] != folded {
+        return false;
+      }
+      values = opening.values.clone();
+      position = next;
+    }
+    false
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn low_degree_table(log_degree_bound: u32, config: &FriConfig) -> (Vec<Goldilocks>, EvaluationDomain) {
+    let domain = EvaluationDomain::coset(log_degree_bound + config.log_blowup).unwrap();
+    let coeffs: Vec<Goldilocks> = (0..1u64 << log_degree_bound).map(|i| Goldilocks::new(i * i + 3)).collect();
+    (domain.evaluate(&coeffs).unwrap(), domain)
+  }
+
+  fn verify_against(proof: &FriProof, values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig) -> bool {
+    let arity = config.first_layer_arity(log_degree_bound);
+    let cosets = domain.size() / arity;
+    verify(proof, log_degree_bound, domain, config, &mut Transcript::new(b"fri-test"), |_, index| Some((0..arity).map(|m| values[index + m * cosets]).collect()))
+  }
+
+  fn check(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig) -> bool {
+    let (proof, _) = prove(values, log_degree_bound, domain, config, &mut Transcript::new(b"fri-test")).unwrap();
+    verify_against(&proof, values, log_degree_bound, domain, config)
+  }
+
+  #[test]
+  fn test_low_degree_accepted() {
+    let config = FriConfig::default();
+    for log_degree_bound in [0, 1, 5] {
+      let (values, domain) = low_degree_table(log_degree_bound, &config);
+      assert!(check(&values, log_degree_bound, &domain, &config));
+    }
+    let table = CommittedTable::commit(vec![Goldilocks::ONE; 8]).unwrap();
+    let opening = table.open(2).unwrap();
+    assert!(opening.verify(&table.root(), 2, 3));
+    assert!(!opening.verify(&table.root(), 1, 3));
+  }
+
+  #[test]
+  fn test_high_degree_rejected() {
+    let config = FriConfig::default();
+    // A polynomial of degree 2^6 - 1 against a degree bound of 2^5
+    let domain = EvaluationDomain::coset(5 + config.log_blowup).unwrap();
+    let coeffs: Vec<Goldilocks> = (1..=64).map(Goldilocks::new).collect();
+    assert!(!check(&domain.evaluate(&coeffs).unwrap(), 5, &domain, &config));
+
+    // Corrupting a quarter of a low-degree table is caught too
+    let (mut values, domain) = low_degree_table(5, &config);
+    for value in values.iter_mut().step_by(4) {
+      *value = *value + Goldilocks::ONE;
+    }
+    assert!(!check(&values, 5, &domain, &config));
+  }
+
+  #[test]
+  fn test_folding_factors() {
+    for folding_factor in FOLDING_FACTORS {
+      let config = FriConfig { folding_factor, ..FriConfig::default() };
+      for log_degree_bound in [0, 1, 3, 6] {
+        let (values, domain) = low_degree_table(log_degree_bound, &config);
+        assert!(check(&values, log_degree_bound, &domain, &config), "{} {}", folding_factor, log_degree_bound);
+      }
+
+      let domain = EvaluationDomain::coset(6 + config.log_blowup).unwrap();
+      let coeffs: Vec<Goldilocks> = (1..=128).map(Goldilocks::new).collect();
+      assert!(!check(&domain.evaluate(&coeffs).unwrap(), 6, &domain, &config));
+    }
+
+    // Folding by 8 takes a 2^6 bound to a constant in two rounds, so one layer is committed
+    let config = FriConfig { folding_factor: 8, ..FriConfig::default() };
+    assert_eq!(config.round_log_arities(6), vec![3, 3]);
+    assert_eq!(FriConfig { folding_factor: 16, ..config }.round_log_arities(6), vec![4, 2]);
+    let (values, domain) = low_degree_table(6, &config);
+    let (proof, indices) = prove(&values, 6, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+    assert_eq!(proof.layer_roots.len(), 1);
+    assert!(indices.iter().all(|&index| index < domain.size() / 8));
+
+    assert!(prove(&values, 6, &domain, &FriConfig { folding_factor: 3, ..config }, &mut Transcript::new(b"fri-test")).is_none());
+    let table = CommittedTable::commit_cosets(values, 8).unwrap();
+    let opening = table.open(5).unwrap();
+    assert!(opening.verify(&table.root(), 5, domain.log_size));
+    assert!(!opening.verify(&table.root(), 5, domain.log_size + 1));
+    assert!(table.open(table.cosets()).is_none());
+  }
+
+  #[test]
+  fn test_grinding() {
+    let config = FriConfig { grinding_bits: 8, ..FriConfig::default() };
+    let (values, domain) = low_degree_table(5, &config);
+    let (proof, _) = prove(&values, 5, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+    assert!(verify_against(&proof, &values, 5, &domain, &config));
+
+    // Another nonce fails the proof of work or moves the queries
+    let mut regrind = proof.clone();
+    regrind.pow_nonce += 1;
+    assert!(!verify_against(&regrind, &values, 5, &domain, &config));
+    // Without grinding the nonce must be zero
+    let (plain, _) = prove(&values, 5, &domain, &FriConfig::default(), &mut Transcript::new(b"fri-test")).unwrap();
+    assert_eq!(plain.pow_nonce, 0);
+    assert!(!verify_against(&proof, &values, 5, &domain, &FriConfig::default()));
+    assert!(prove(&values, 5, &domain, &FriConfig { grinding_bits: MAX_GRINDING_BITS + 1, ..config }, &mut Transcript::new(b"fri-test")).is_none());
+  }
+
+  #[test]
+  fn test_deep_sampling() {
+    let config = FriConfig { deep: true, ..FriConfig::default() };
+    for folding_factor in [2, 8] {
+      let config = FriConfig { folding_factor, ..config };
+      for log_degree_bound in [0, 1, 5] {
+        let (values, domain) = low_degree_table(log_degree_bound, &config);
+        assert!(check(&values, log_degree_bound, &domain, &config), "{} {}", folding_factor, log_degree_bound);
+      }
+    }
+
+    // Degree exactly 2^5 is one too many, although its quotient by X - z has degree below 2^5
+    let domain = EvaluationDomain::coset(5 + config.log_blowup).unwrap();
+    let coeffs: Vec<Goldilocks> = (1..=33).map(Goldilocks::new).collect();
+    assert!(!check(&domain.evaluate(&coeffs).unwrap(), 5, &domain, &config));
+
+    let (values, domain) = low_degree_table(5, &config);
+    let (proof, _) = prove(&values, 5, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+    let mut lying = proof.clone();
+    lying.deep_value = lying.deep_value.map(|v| v + Goldilocks::ONE);
+    assert!(!verify_against(&lying, &values, 5, &domain, &config));
+    let mut stripped = proof.clone();
+    stripped.deep_value = None;
+    assert!(!verify_against(&stripped, &values, 5, &domain, &config));
+    assert!(!verify_against(&proof, &values, 5, &domain, &FriConfig::default()));
+  }
+
+  #[test]
+  fn test_pruned_layout() {
+    for config in [FriConfig::default(), FriConfig { folding_factor: 4, deep: true, ..FriConfig::default() }] {
+      let (values, domain) = low_degree_table(10, &config);
+      let (proof, indices) = prove(&values, 10, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+      let (pruned, pruned_indices) = prove_pruned(&values, 10, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+      assert_eq!(indices, pruned_indices);
+      assert_eq!(pruned.layer_roots, proof.layer_roots);
+
+      let arity = config.first_layer_arity(10);
+      let cosets = domain.size() / arity;
+      let first_layer = |_: usize, index: usize| Some((0..arity).map(|m| values[index + m * cosets]).collect());
+      assert!(verify_pruned(&pruned, 10, &domain, &config, &mut Transcript::new(b"fri-test"), first_layer));
+      assert!(pruned.size_bytes() < proof.size_bytes());
+
+      // Reconstruction gives back the per-query openings
+      let header = Header { layer_roots: &pruned.layer_roots, final_value: pruned.final_value, pow_nonce: pruned.pow_nonce, deep_value: pruned.deep_value };
+      let plan = replay(header, 10, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+      assert_eq!(pruned.expand(&plan.indices, &plan.arities, &domain), Some(proof));
+
+      let mut dropped = pruned.clone();
+      dropped.layers[0].nodes.pop();
+      assert!(!verify_pruned(&dropped, 10, &domain, &config, &mut Transcript::new(b"fri-test"), first_layer));
+      let mut altered = pruned.clone();
+      altered.layers[1].values[0][0] = altered.layers[1].values[0][0] + Goldilocks::ONE;
+      assert!(!verify_pruned(&altered, 10, &domain, &config, &mut Transcript::new(b"fri-test"), first_layer));
+    }
+  }
+
+  #[test]
+  fn test_pruning_on_default_parameters() {
+    // 32 queries against a 2^12 degree bound at blowup 4: 28976 bytes instead of 84848, as the
+    // deep layers are opened almost entirely and the first ones share their upper nodes
+    // (66960 instead of 146416 at 2^16)
+    let config = FriConfig::default();
+    let (values, domain) = low_degree_table(12, &config);
+    let (proof, _) = prove(&values, 12, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+    let (pruned, _) = prove_pruned(&values, 12, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+    assert_eq!((pruned.size_bytes(), proof.size_bytes()), (28976, 84848));
+  }
+
+  #[test]
+  fn test_barycentric_eval() {
+    let coeffs: Vec<Goldilocks> = (0..16u64).map(|i| Goldilocks::new(5 * i + 2)).collect();
+    let horner = |z: Goldilocks| coeffs.iter().rev().fold(Goldilocks::ZERO, |acc, c| acc * z + *c);
+    for domain in [EvaluationDomain::coset(4).unwrap(), EvaluationDomain::subgroup(4).unwrap(), EvaluationDomain::coset(6).unwrap()] {
+      let values = domain.evaluate(&coeffs).unwrap();
+      for z in [Goldilocks::new(123_456_789), Goldilocks::new(GOLDILOCKS_P - 2), Goldilocks::ZERO] {
+        assert_eq!(domain.barycentric_eval(&values, z), Some(horner(z)));
+      }
+      // Points of the domain read the table directly
+      assert_eq!(domain.barycentric_eval(&values, domain.element(3)), Some(values[3]));
+      assert!(domain.barycentric_eval(&values[1..], Goldilocks::ONE).is_none());
+    }
+  }
+}
@@ -1,3 +1,10 @@
+pub mod fri;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod piop_compiler;
+pub mod polynomial;
 pub mod snark_integration;
+pub mod soundness;
+pub mod subgroup_sumcheck;
+pub mod transcript;
 pub mod univariate_sumcheck;
\ No newline at end of file
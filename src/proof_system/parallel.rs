@@ -0,0 +1,265 @@
+// FRI prover spread over std threads
+// prove and prove_pruned here return exactly the proofs of fri::prove and fri::prove_pruned,
+// with the work of the commit phase split across workers:
+// - Folds, the DEEP quotient and coset digests are computed over contiguous chunks of a
+//   table, one per worker, and joined in order; Merkle levels are hashed the same way
+//   (MerkleTree::from_leaves_parallel)
+// - Layers are pipelined. The challenge of round i + 1 is drawn after layer i is committed,
+//   but a binary fold (a + b) / 2 + beta * (a - b) / 2x is beta-independent up to its last
+//   product: while the workers hash layer i, the calling thread computes the sums and scaled
+//   differences of its first fold, which then takes one multiply-add per value once beta is
+//   known
+// - Grinding tries nonces in batches, one range per worker, and keeps the smallest nonce that
+//   passes, i.e. the one fri::grind finds
+// Below MIN_CHUNK values per worker a table is processed on the calling thread; barycentric
+// evaluation and the openings stay sequential, being linear or logarithmic in the table.
+
+use crate::crypto::goldilocks::Goldilocks;
+use crate::crypto::hash_functions::HashFunction;
+use crate::crypto::merkle::MerkleTree;
+use crate::proof_system::fri::{self, CommitPhase, CommittedTable, EvaluationDomain, FriConfig, FriProof, PrunedFriProof};
+use crate::proof_system::transcript::Transcript;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::thread;
+
+// Fewest table values worth a thread of their own
+const MIN_CHUNK: usize = 1 << 12;
+
+// Nonces each worker tries per grinding batch
+const GRIND_BATCH: u64 = 1 << 12;
+
+// Worker threads for a table of `len` values
+fn workers(len: usize) -> usize {
+  let available = thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1);
+  available.min(len / MIN_CHUNK).max(1)
+}
+
+// f over contiguous chunks of 0..len, one per worker, concatenated in order; None if any
+// chunk fails
+fn map_chunks<T: Send>(len: usize, workers: usize, f: impl Fn(Range<usize>) -> Option<Vec<T>> + Sync) -> Option<Vec<T>> {
+  if workers <= 1 {
+    return f(0..len);
+  }
+  let chunk = len.div_ceil(workers);
+  let f = &f;
+  thread::scope(|scope| {
+    let handles: Vec<_> = (0..len)
+      .step_by(chunk)
+      .map(|start| scope.spawn(move || f(start..len.min(start + chunk))))
+      .collect();
+    let mut out = Vec::with_capacity(len);
+    for handle in handles {
+      out.extend(handle.join().expect("FRI worker panicked")?);
+    }
+    Some(out)
+  })
+}
+
+// shift * generator^i for i in range, one exponentiation per chunk
+fn elements(domain: &EvaluationDomain, range: Range<usize>) -> Vec<Goldilocks> {
+  let mut x = domain.element(range.start);
+  range
+    .map(|_| {
+      let current = x;
+      x = x * domain.generator;
+      current
+    })
+    .collect()
+}
+
+// The beta-independent part of a binary fold of a table on domain: (a + b) / 2 and
+// (a - b) / 2x for each pair (f(x), f(-x)) = (values[j], values[j + N/2])
+struct PreparedFold {
+  sums: Vec<Goldilocks>,
+  differences: Vec<Goldilocks>,
+}
+
+impl PreparedFold {
+  fn new(values: &[Goldilocks], domain: &EvaluationDomain, workers: usize) -> Option<Self> {
+    let half = values.len() / 2;
+    let inverse_generator = domain.generator.inverse()?;
+    let pairs = map_chunks(half, workers, |range| {
+      let two_inverse = fri::inverse_of_two();
+      let mut x_inverse = domain.element(range.start).inverse()?;
+      Some(
+        range
+          .map(|j| {
+            let (a, b) = (values[j], values[j + half]);
+            let pair = ((a + b) * two_inverse, (a - b) * two_inverse * x_inverse);
+            x_inverse = x_inverse * inverse_generator;
+            pair
+          })
+          .collect(),
+      )
+    })?;
+    let (sums, differences) = pairs.into_iter().unzip();
+    Some(Self { sums, differences })
+  }
+
+  fn finish(&self, beta: Goldilocks, workers: usize) -> Option<Vec<Goldilocks>> {
+    map_chunks(self.sums.len(), workers, |range| Some(range.map(|j| self.sums[j] + beta * self.differences[j]).collect()))
+  }
+}
+
+// The binary folds of one round, as fri::fold_table; the first from `prepared` if given
+fn fold_round(values: &[Goldilocks], prepared: Option<PreparedFold>, domain: &mut EvaluationDomain, log_arity: u32, beta: Goldilocks, workers: usize) -> Option<Vec<Goldilocks>> {
+  let prepared = match prepared {
+    Some(prepared) => prepared,
+    None => PreparedFold::new(values, domain, workers)?,
+  };
+  let mut current = prepared.finish(beta, workers)?;
+  *domain = domain.fold();
+  let mut challenge = beta * beta;
+  for _ in 1..log_arity {
+    current = PreparedFold::new(&current, domain, workers)?.finish(challenge, workers)?;
+    *domain = domain.fold();
+    challenge = challenge * challenge;
+  }
+  Some(current)
+}
+
+// CommittedTable::commit_cosets with the digests and tree levels computed in chunks
+fn commit_cosets(values: &[Goldilocks], arity: usize, workers: usize) -> Option<MerkleTree> {
+  let cosets = values.len() / arity;
+  let leaves = map_chunks(cosets, workers, |range| Some(range.map(|j| fri::coset_digest(&(0..arity).map(|m| values[j + m * cosets]).collect::<Vec<_>>())).collect()))?;
+  Some(MerkleTree::from_leaves_parallel(leaves, HashFunction::Sha3_256, workers))
+}
+
+// fri::grind over batches of nonces
+fn grind(transcript: &mut Transcript, bits: u32, workers: usize) -> u64 {
+  if bits == 0 || workers <= 1 {
+    return fri::grind(transcript, bits);
+  }
+  let seed = transcript.challenge_bytes("grinding");
+  let batch = GRIND_BATCH * workers as u64;
+  let nonce = (0..u64::MAX / batch)
+    .find_map(|round| {
+      let start = round * batch;
+      thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers as u64)
+          .map(|w| {
+            let seed = &seed;
+            scope.spawn(move || (start + w * GRIND_BATCH..start + (w + 1) * GRIND_BATCH).find(|&nonce| fri::pow_bits(seed, nonce) >= bits))
+          })
+          .collect();
+        handles.into_iter().filter_map(|handle| handle.join().expect("Grinding worker panicked")).min()
+      })
+    })
+    .expect("A nonce exists below 2^64");
+  transcript.absorb("nonce", &nonce.to_be_bytes());
+  nonce
+}
+
+// fri::commit_phase on `workers` threads
+fn commit_phase(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript, workers: usize) -> Option<CommitPhase> {
+  if !fri::domain_is_valid(log_degree_bound, domain, config) || values.len() != domain.size() {
+    return None;
+  }
+
+  let mut current = values.to_vec();
+  let mut deep_value = None;
+  if config.deep {
+    let z = fri::deep_point(transcript, domain);
+    let v = domain.barycentric_eval(values, z)?;
+    transcript.absorb_goldilocks("deep_value", &[v]);
+    current = map_chunks(values.len(), workers, |range| fri::deep_quotient(&elements(domain, range.clone()), &values[range], z, v))?;
+    deep_value = Some(v);
+  }
+
+  let arities = config.round_log_arities(log_degree_bound);
+  let mut layers: Vec<CommittedTable> = vec![];
+  let mut current_domain = *domain;
+  let mut prepared = None;
+  for (round, &log_arity) in arities.iter().enumerate() {
+    let beta = transcript.challenge_goldilocks("beta");
+    current = fold_round(&current, prepared.take(), &mut current_domain, log_arity, beta, workers)?;
+    if let Some(&next_log_arity) = arities.get(round + 1) {
+      // Hash this layer on the workers while the calling thread prepares the next fold
+      let arity = 1 << next_log_arity;
+      let (tree, next) = if workers <= 1 {
+        (commit_cosets(&current, arity, 1), PreparedFold::new(&current, &current_domain, 1))
+      } else {
+        thread::scope(|scope| {
+          let hashing = scope.spawn(|| commit_cosets(&current, arity, workers - 1));
+          let next = PreparedFold::new(&current, &current_domain, 1);
+          (hashing.join().expect("FRI worker panicked"), next)
+        })
+      };
+      prepared = Some(next?);
+      let layer = CommittedTable::from_tree(current.clone(), arity, tree?);
+      transcript.absorb("layer", &layer.root().to_bytes_be());
+      layers.push(layer);
+    }
+  }
+  let final_value = current[0];
+  transcript.absorb_goldilocks("final", &[final_value]);
+  let pow_nonce = grind(transcript, config.grinding_bits, workers);
+
+  let indices = fri::query_indices(transcript, domain.size() / config.first_layer_arity(log_degree_bound), config.num_queries);
+  Some(CommitPhase {
+    layers,
+    final_value,
+    pow_nonce,
+    deep_value,
+    indices,
+  })
+}
+
+// fri::prove with the commit phase on as many threads as the table warrants
+pub fn prove(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<(FriProof, Vec<usize>)> {
+  commit_phase(values, log_degree_bound, domain, config, transcript, workers(values.len()))?.into_proof()
+}
+
+// fri::prove_pruned with the commit phase on as many threads as the table warrants
+pub fn prove_pruned(values: &[Goldilocks], log_degree_bound: u32, domain: &EvaluationDomain, config: &FriConfig, transcript: &mut Transcript) -> Option<(PrunedFriProof, Vec<usize>)> {
+  commit_phase(values, log_degree_bound, domain, config, transcript, workers(values.len()))?.into_pruned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn table(log_degree_bound: u32, config: &FriConfig) -> (Vec<Goldilocks>, EvaluationDomain) {
+    let domain = EvaluationDomain::coset(log_degree_bound + config.log_blowup).unwrap();
+    let coeffs: Vec<Goldilocks> = (0..1u64 << log_degree_bound).map(|i| Goldilocks::new(i * i + 3)).collect();
+    (domain.evaluate(&coeffs).unwrap(), domain)
+  }
+
+  #[test]
+  fn test_parallel_proofs_match_sequential() {
+    let configs = [
+      FriConfig::default(),
+      FriConfig { folding_factor: 4, deep: true, ..FriConfig::default() },
+      FriConfig { folding_factor: 8, grinding_bits: 6, ..FriConfig::default() },
+    ];
+    for config in configs {
+      let (values, domain) = table(11, &config);
+      let expected = fri::prove(&values, 11, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+      for workers in [1, 3, 4] {
+        let phase = commit_phase(&values, 11, &domain, &config, &mut Transcript::new(b"fri-test"), workers).unwrap();
+        assert_eq!(phase.into_proof().unwrap(), expected, "{:?} {}", config, workers);
+      }
+      let pruned = fri::prove_pruned(&values, 11, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap();
+      assert_eq!(prove_pruned(&values, 11, &domain, &config, &mut Transcript::new(b"fri-test")).unwrap(), pruned);
+    }
+  }
+
+  #[test]
+  fn test_parallel_grinding_finds_the_first_nonce() {
+    for workers in [2, 5] {
+      let mut sequential = Transcript::new(b"grind");
+      let mut parallel = sequential.clone();
+      assert_eq!(grind(&mut parallel, 10, workers), fri::grind(&mut sequential, 10));
+      assert_eq!(parallel.challenge_bytes("after"), sequential.challenge_bytes("after"));
+    }
+  }
+
+  #[test]
+  fn test_invalid_inputs_rejected() {
+    let config = FriConfig::default();
+    let (values, domain) = table(8, &config);
+    assert!(prove(&values[1..], 8, &domain, &config, &mut Transcript::new(b"fri-test")).is_none());
+    assert!(prove(&values, 9, &domain, &config, &mut Transcript::new(b"fri-test")).is_none());
+  }
+}
@@ -0,0 +1,132 @@
+// Polynomials over Goldilocks in coefficient or evaluation form
+// Provers mostly need evaluations on a FRI domain (to commit and to combine tables pointwise)
+// and occasionally coefficients (to divide by a vanishing polynomial). Keeping a polynomial
+// in whichever form it was produced in, and converting only on demand, saves the NTT or
+// inverse NTT that eager conversion costs at every step. Conversions borrow instead of
+// converting when the polynomial is already in the requested form.
+// Evaluation form is always on a full domain, so it describes a polynomial of degree below
+// the domain size.
+
+use crate::crypto::goldilocks::{intt, Goldilocks};
+use crate::proof_system::fri::EvaluationDomain;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Polynomial {
+  Coefficients(Vec<Goldilocks>), // Lowest degree first
+  Evaluations(EvaluationDomain, Vec<Goldilocks>), // Values at domain.element(i)
+}
+
+impl Polynomial {
+  // Number of coefficients the representation can hold
+  pub fn len(&self) -> usize {
+    match self {
+      Self::Coefficients(coeffs) => coeffs.len(),
+      Self::Evaluations(_, values) => values.len(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // Coefficients, interpolating evaluations with an inverse NTT over the coset
+  // None if the evaluations do not cover their domain
+  pub fn coefficients(&self) -> Option<Cow<'_, [Goldilocks]>> {
+    match self {
+      Self::Coefficients(coeffs) => Some(Cow::Borrowed(coeffs)),
+      Self::Evaluations(domain, values) => {
+        if values.len() != domain.size() {
+          return None;
+        }
+        // p(shift * X) has these values on the subgroup
+        let mut coeffs = values.clone();
+        if !intt(&mut coeffs) {
+          return None;
+        }
+        let shift_inv = domain.shift.inverse()?;
+        let mut power = Goldilocks::ONE;
+        for c in coeffs.iter_mut() {
+          *c = *c * power;
+          power = power * shift_inv;
+        }
+        Some(Cow::Owned(coeffs))
+      }
+    }
+  }
+
+  // Evaluations on domain, with an NTT unless the polynomial is already evaluated there
+  // None if the polynomial does not fit under the domain size
+  pub fn evaluations(&self, domain: &EvaluationDomain) -> Option<Cow<'_, [Goldilocks]>> {
+    match self {
+      Self::Evaluations(own, values) if own == domain && values.len() == domain.size() => Some(Cow::Borrowed(values)),
+      _ => {
+        let coeffs = self.coefficients()?;
+        let degree_bound = coeffs.iter().rposition(|c| *c != Goldilocks::ZERO).map_or(0, |i| i + 1);
+        if degree_bound > domain.size() {
+          return None;
+        }
+        Some(Cow::Owned(domain.evaluate(&coeffs[..degree_bound])?))
+      }
+    }
+  }
+
+  pub fn into_coefficients(self) -> Option<Self> {
+    match self {
+      Self::Coefficients(_) => Some(self),
+      _ => Some(Self::Coefficients(self.coefficients()?.into_owned())),
+    }
+  }
+
+  pub fn into_evaluations(self, domain: &EvaluationDomain) -> Option<Self> {
+    let values = self.evaluations(domain)?.into_owned();
+    Some(Self::Evaluations(*domain, values))
+  }
+
+  // Value at a point: Horner's rule on coefficients, barycentric evaluation on evaluations
+  pub fn evaluate(&self, point: Goldilocks) -> Option<Goldilocks> {
+    match self {
+      Self::Coefficients(coeffs) => Some(coeffs.iter().rev().fold(Goldilocks::ZERO, |acc, c| acc * point + *c)),
+      Self::Evaluations(domain, values) => domain.barycentric_eval(values, point),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn coefficients() -> Vec<Goldilocks> {
+    (0..12u64).map(|i| Goldilocks::new(7 * i * i + 1)).collect()
+  }
+
+  #[test]
+  fn test_conversions_roundtrip() {
+    let domain = EvaluationDomain::coset(4).unwrap();
+    let poly = Polynomial::Coefficients(coefficients());
+    let evaluated = poly.clone().into_evaluations(&domain).unwrap();
+    assert_eq!(evaluated.evaluations(&domain).unwrap().as_ref(), domain.evaluate(&coefficients()).unwrap().as_slice());
+    // The coset interpolation pads the coefficients to the domain size
+    let mut padded = coefficients();
+    padded.resize(16, Goldilocks::ZERO);
+    assert_eq!(evaluated.coefficients().unwrap().as_ref(), padded.as_slice());
+
+    // Moving to a larger domain goes through the coefficients once
+    let larger = EvaluationDomain::coset(6).unwrap();
+    assert_eq!(evaluated.evaluations(&larger).unwrap().as_ref(), larger.evaluate(&coefficients()).unwrap().as_slice());
+    // A smaller domain cannot hold degree 11
+    assert!(evaluated.evaluations(&EvaluationDomain::coset(3).unwrap()).is_none());
+  }
+
+  #[test]
+  fn test_borrowed_when_in_form_and_evaluate() {
+    let domain = EvaluationDomain::subgroup(4).unwrap();
+    let poly = Polynomial::Coefficients(coefficients());
+    let evaluated = poly.clone().into_evaluations(&domain).unwrap();
+    assert!(matches!(poly.coefficients(), Some(Cow::Borrowed(_))));
+    assert!(matches!(evaluated.evaluations(&domain), Some(Cow::Borrowed(_))));
+
+    let z = Goldilocks::new(987_654_321);
+    assert_eq!(evaluated.evaluate(z), poly.evaluate(z));
+  }
+}
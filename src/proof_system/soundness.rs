@@ -0,0 +1,344 @@
+// Soundness calculator for proof system parameters
+// Estimates the bits of security of a parameter set from its FRI rate, query count,
+// folding factor, grinding, field size and sumcheck repetitions, and refuses to build
+// LoquatParams below a configured threshold. Each component error is bounded separately and
+// the total is their union bound:
+// - FRI queries: (1 + rho) / 2 per query in the provable (unique decoding) regime,
+//   rho per query under the commonly used proximity gaps conjecture; a proof of work of
+//   g bits before the queries multiplies the prover's cost per attempt by 2^g, adding g bits.
+//   With DEEP sampling the provable bound runs up to the Johnson bound, sqrt(rho) per query,
+//   so the same security takes about half the queries of the unique decoding bound at
+//   rate 1/4 and fewer still at lower rates; the conjectured bound is unchanged
+// - DEEP sample: the out-of-domain point hits a second codeword of the list around the
+//   table with probability at most L d / |F| for degree bound d = rho n and list size L,
+//   taken as 1 / rho
+// - FRI commit phase: one folding challenge per round, each failing with probability
+//   at most (k - 1) n / |F| for folding factor k and an evaluation domain of size n; larger
+//   factors take fewer rounds but lose log2(k - 1) bits per round
+// - Sumcheck: degree / |F| per repetition
+// Challenges are drawn from a degree-e extension of the base field, so |F| is the size
+// of the challenge field.
+// LoquatParams also selects the PRF used for key material: power residue symbols of
+// degree k give log2 k bits per evaluation and so need fewer evaluations per signature.
+
+use crate::crypto::legendre_prf::PrfVariant;
+use crate::proof_system::fri::{FriConfig, FOLDING_FACTORS, MAX_GRINDING_BITS};
+
+// Default minimum security level enforced by LoquatParams::new
+pub const DEFAULT_MIN_SECURITY_BITS: f64 = 128.0;
+
+// Bound used for the FRI query phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityModel {
+  Provable, // Unique decoding radius, no conjectures
+  Conjectured, // Proximity gaps conjecture up to capacity
+}
+
+// Raw parameter choices of a proof system instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundnessParams {
+  pub log_inverse_rate: u32, // rho = 2^-log_inverse_rate
+  pub num_queries: u32, // FRI query repetitions
+  pub field_bits: u32, // Bits of the base field modulus
+  pub extension_degree: u32, // Degree of the challenge field over the base field
+  pub log_domain_size: u32, // log2 of the FRI evaluation domain size
+  pub sumcheck_degree: u64, // Degree of the sumcheck round polynomials
+  pub sumcheck_repetitions: u32,
+  pub folding_factor: u32, // FRI folding arity: 2, 4, 8 or 16
+  pub grinding_bits: u32, // Proof-of-work bits before the FRI queries
+  pub deep: bool, // DEEP-FRI out-of-domain sampling
+}
+
+impl SoundnessParams {
+  // Bits of the field the verifier's challenges are sampled from
+  pub fn challenge_bits(&self) -> f64 {
+    self.field_bits as f64 * self.extension_degree as f64
+  }
+
+  // Number of FRI folding rounds (folding by the folding factor down to a constant polynomial)
+  pub fn fri_rounds(&self) -> u32 {
+    let folds = self.log_domain_size.saturating_sub(self.log_inverse_rate);
+    folds.div_ceil(self.folding_factor.trailing_zeros().max(1)).max(1)
+  }
+
+  // The FRI configuration these parameters describe
+  pub fn fri_config(&self) -> FriConfig {
+    FriConfig {
+      log_blowup: self.log_inverse_rate,
+      num_queries: self.num_queries as usize,
+      folding_factor: self.folding_factor as usize,
+      grinding_bits: self.grinding_bits,
+      deep: self.deep,
+    }
+  }
+
+  // Rejects structurally meaningless parameter sets
+  fn check(&self) -> Result<(), SoundnessError> {
+    if self.log_inverse_rate == 0 || self.log_inverse_rate >= self.log_domain_size {
+      return Err(SoundnessError::InvalidParameters("rate must be below one and smaller than the domain"));
+    }
+    if self.num_queries == 0 || self.sumcheck_repetitions == 0 {
+      return Err(SoundnessError::InvalidParameters("query and sumcheck repetitions must be positive"));
+    }
+    if self.field_bits == 0 || self.extension_degree == 0 || self.sumcheck_degree == 0 {
+      return Err(SoundnessError::InvalidParameters("field size, extension degree and sumcheck degree must be positive"));
+    }
+    if !FOLDING_FACTORS.contains(&(self.folding_factor as usize)) {
+      return Err(SoundnessError::InvalidParameters("folding factor must be 2, 4, 8 or 16"));
+    }
+    if self.grinding_bits > MAX_GRINDING_BITS {
+      return Err(SoundnessError::InvalidParameters("grinding exceeds the supported proof of work"));
+    }
+    Ok(())
+  }
+}
+
+// Security contributed by each component, in bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SecurityReport {
+  pub fri_query_bits: f64,
+  pub fri_commit_bits: f64,
+  pub deep_bits: f64, // Infinite without DEEP sampling
+  pub sumcheck_bits: f64,
+}
+
+impl SecurityReport {
+  // Union bound over the component errors
+  pub fn total_bits(&self) -> f64 {
+    let error: f64 = [self.fri_query_bits, self.fri_commit_bits, self.deep_bits, self.sumcheck_bits]
+      .iter()
+      .map(|bits| (-bits).exp2())
+      .sum();
+    -error.log2()
+  }
+}
+
+// Estimates the security of a parameter set under the chosen model
+pub fn estimate(params: &SoundnessParams, model: SecurityModel) -> SecurityReport {
+  let rho = (-(params.log_inverse_rate as f64)).exp2();
+  let per_query_bits = match model {
+    SecurityModel::Provable if params.deep => params.log_inverse_rate as f64 / 2.0,
+    SecurityModel::Provable => -((1.0 + rho) / 2.0).log2(),
+    SecurityModel::Conjectured => params.log_inverse_rate as f64,
+  };
+  let challenge_bits = params.challenge_bits();
+
+  SecurityReport {
+    fri_query_bits: params.num_queries as f64 * per_query_bits + params.grinding_bits as f64,
+    fri_commit_bits: challenge_bits
+      - params.log_domain_size as f64
+      - ((params.folding_factor - 1) as f64).log2()
+      - (params.fri_rounds() as f64).log2(),
+    deep_bits: if params.deep { challenge_bits - params.log_domain_size as f64 } else { f64::INFINITY },
+    sumcheck_bits: params.sumcheck_repetitions as f64 * (challenge_bits - (params.sumcheck_degree as f64).log2()),
+  }
+}
+
+// Reasons a parameter set is refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundnessError {
+  InvalidParameters(&'static str),
+  BelowThreshold { achieved_bits: f64, required_bits: f64 },
+}
+
+// A parameter set whose estimated security meets the required threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoquatParams {
+  params: SoundnessParams,
+  model: SecurityModel,
+  report: SecurityReport,
+  prf: PrfVariant,
+}
+
+impl LoquatParams {
+  // Validates the parameters against DEFAULT_MIN_SECURITY_BITS under the provable model
+  pub fn new(params: SoundnessParams) -> Result<Self, SoundnessError> {
+    Self::with_threshold(params, SecurityModel::Provable, DEFAULT_MIN_SECURITY_BITS)
+  }
+
+  // Validates the parameters against an explicit model and threshold
+  pub fn with_threshold(params: SoundnessParams, model: SecurityModel, min_bits: f64) -> Result<Self, SoundnessError> {
+    params.check()?;
+    let report = estimate(&params, model);
+    let achieved_bits = report.total_bits();
+    if achieved_bits < min_bits {
+      return Err(SoundnessError::BelowThreshold {
+        achieved_bits,
+        required_bits: min_bits,
+      });
+    }
+    Ok(Self {
+      params,
+      model,
+      report,
+      prf: PrfVariant::Legendre,
+    })
+  }
+
+  // 128-bit parameters over p = 2^127 - 1: rate 1/16, quadratic extension challenges,
+  // and enough queries for the provable FRI bound
+  pub fn loquat_128() -> Self {
+    Self::new(SoundnessParams {
+      log_inverse_rate: 4,
+      num_queries: 142,
+      field_bits: 127,
+      extension_degree: 2,
+      log_domain_size: 20,
+      sumcheck_degree: 1 << 16,
+      sumcheck_repetitions: 1,
+      folding_factor: 2,
+      grinding_bits: 0,
+      deep: false,
+    })
+    .expect("Built-in parameters meet the default threshold")
+  }
+
+  // loquat_128 with DEEP sampling, which meets the provable bound with 66 queries
+  pub fn loquat_128_deep() -> Self {
+    Self::new(SoundnessParams {
+      num_queries: 66,
+      deep: true,
+      ..*Self::loquat_128().params()
+    })
+    .expect("Built-in parameters meet the default threshold")
+  }
+
+  // Selects the PRF variant; power residue degrees must divide P - 1
+  pub fn with_prf(mut self, prf: PrfVariant) -> Result<Self, SoundnessError> {
+    if !prf.is_valid() {
+      return Err(SoundnessError::InvalidParameters("power residue degree must divide p - 1"));
+    }
+    self.prf = prf;
+    Ok(self)
+  }
+
+  pub fn params(&self) -> &SoundnessParams {
+    &self.params
+  }
+
+  pub fn fri_config(&self) -> FriConfig {
+    self.params.fri_config()
+  }
+
+  pub fn model(&self) -> SecurityModel {
+    self.model
+  }
+
+  pub fn report(&self) -> &SecurityReport {
+    &self.report
+  }
+
+  pub fn security_bits(&self) -> f64 {
+    self.report.total_bits()
+  }
+
+  pub fn prf(&self) -> PrfVariant {
+    self.prf
+  }
+
+  // PRF evaluations needed to produce the given number of pseudorandom output bits
+  pub fn prf_evaluations(&self, output_bits: u32) -> u32 {
+    self.prf.evaluations_for(output_bits)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params(num_queries: u32, extension_degree: u32) -> SoundnessParams {
+    SoundnessParams {
+      log_inverse_rate: 4,
+      num_queries,
+      field_bits: 127,
+      extension_degree,
+      log_domain_size: 20,
+      sumcheck_degree: 1 << 16,
+      sumcheck_repetitions: 1,
+      folding_factor: 2,
+      grinding_bits: 0,
+      deep: false,
+    }
+  }
+
+  #[test]
+  fn test_estimates() {
+    let report = estimate(&params(32, 2), SecurityModel::Conjectured);
+    assert_eq!(report.fri_query_bits, 128.0);
+    assert_eq!(report.sumcheck_bits, 254.0 - 16.0);
+    assert!((report.total_bits() - 128.0).abs() < 0.01);
+
+    // The provable bound needs far more queries for the same rate
+    let provable = estimate(&params(32, 2), SecurityModel::Provable);
+    assert!(provable.fri_query_bits < 30.0);
+
+    let params_128 = LoquatParams::loquat_128();
+    assert!(params_128.security_bits() >= DEFAULT_MIN_SECURITY_BITS);
+    assert_eq!(params_128.model(), SecurityModel::Provable);
+  }
+
+  #[test]
+  fn test_weak_parameters_refused() {
+    match LoquatParams::new(params(32, 2)) {
+      Err(SoundnessError::BelowThreshold { achieved_bits, required_bits }) => {
+        assert!(achieved_bits < required_bits);
+        assert_eq!(required_bits, DEFAULT_MIN_SECURITY_BITS);
+      }
+      other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Base-field challenges cap the commit phase below 128 bits however many queries are made
+    assert!(LoquatParams::new(params(400, 1)).is_err());
+    assert!(LoquatParams::with_threshold(params(32, 2), SecurityModel::Conjectured, 100.0).is_ok());
+    assert!(matches!(LoquatParams::new(params(0, 2)), Err(SoundnessError::InvalidParameters(_))));
+  }
+
+  #[test]
+  fn test_folding_and_grinding() {
+    // 16 bits of grinding stand in for a quarter of the queries at rate 1/16
+    let ground = SoundnessParams { grinding_bits: 16, ..params(28, 2) };
+    assert_eq!(estimate(&ground, SecurityModel::Conjectured).fri_query_bits, 128.0);
+    assert!(LoquatParams::with_threshold(ground, SecurityModel::Conjectured, 127.0).is_ok());
+    assert!(LoquatParams::with_threshold(params(28, 2), SecurityModel::Conjectured, 127.0).is_err());
+
+    // Folding by 16 runs 4 rounds instead of 16 and pays log2(15) bits per round for it
+    let binary = estimate(&params(32, 2), SecurityModel::Conjectured);
+    let wide = SoundnessParams { folding_factor: 16, ..params(32, 2) };
+    assert_eq!(wide.fri_rounds(), 4);
+    let wide_report = estimate(&wide, SecurityModel::Conjectured);
+    assert!((binary.fri_commit_bits - wide_report.fri_commit_bits - (15f64.log2() - 2.0)).abs() < 1e-9);
+
+    let config = LoquatParams::loquat_128().fri_config();
+    assert_eq!((config.log_blowup, config.num_queries, config.folding_factor, config.grinding_bits, config.deep), (4, 142, 2, 0, false));
+    assert!(matches!(LoquatParams::new(SoundnessParams { folding_factor: 3, ..params(142, 2) }), Err(SoundnessError::InvalidParameters(_))));
+    assert!(matches!(LoquatParams::new(SoundnessParams { grinding_bits: 64, ..params(142, 2) }), Err(SoundnessError::InvalidParameters(_))));
+  }
+
+  #[test]
+  fn test_deep_sampling() {
+    // Two provable bits per query at rate 1/16 instead of about 0.9
+    let deep = SoundnessParams { deep: true, ..params(66, 2) };
+    let report = estimate(&deep, SecurityModel::Provable);
+    assert_eq!(report.fri_query_bits, 132.0);
+    assert_eq!(report.deep_bits, 254.0 - 20.0);
+    assert!(estimate(&params(66, 2), SecurityModel::Provable).fri_query_bits < 64.0);
+    assert_eq!(estimate(&params(66, 2), SecurityModel::Provable).deep_bits, f64::INFINITY);
+    assert_eq!(estimate(&deep, SecurityModel::Conjectured).fri_query_bits, estimate(&params(66, 2), SecurityModel::Conjectured).fri_query_bits);
+
+    let params_deep = LoquatParams::loquat_128_deep();
+    assert!(params_deep.security_bits() >= DEFAULT_MIN_SECURITY_BITS);
+    assert!(params_deep.params().num_queries < LoquatParams::loquat_128().params().num_queries / 2);
+    assert!(params_deep.fri_config().deep);
+    assert!(LoquatParams::new(SoundnessParams { num_queries: 60, ..*params_deep.params() }).is_err());
+  }
+
+  #[test]
+  fn test_prf_selection() {
+    let legendre = LoquatParams::loquat_128();
+    assert_eq!(legendre.prf(), PrfVariant::Legendre);
+
+    let residue = legendre.with_prf(PrfVariant::PowerResidue(54)).unwrap();
+    assert!(residue.prf_evaluations(128) < legendre.prf_evaluations(128));
+    assert!(legendre.with_prf(PrfVariant::PowerResidue(8)).is_err());
+  }
+}
@@ -0,0 +1,170 @@
+// Univariate sumcheck over a multiplicative subgroup of the Goldilocks field
+// For H of order n, the sum of f over H is n * g(0) where g = f mod (X^n - 1), so the prover
+// writes f(X) = Z_H(X) * q(X) + X * r(X) + sum / n with Z_H = X^n - 1 and deg r < n - 1.
+// The verifier checks this identity at a random point, which a false claim passes with
+// probability at most deg f / p. Domains are powers of two, so sums can also be computed
+// with an NTT (see subgroup_sum). The *_with_transcript variants make the protocol
+// non-interactive by deriving the point from a Fiat-Shamir transcript.
+
+use crate::crypto::goldilocks::{ntt, Goldilocks, GOLDILOCKS_P, TWO_ADICITY};
+use crate::proof_system::transcript::Transcript;
+use crate::crypto::test_rng;
+use rand::Rng;
+
+// Prover message for a claimed subgroup sum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubgroupSumcheckProof {
+  pub claimed_sum: Goldilocks,
+  pub quotient: Vec<Goldilocks>, // q, with f = Z_H * q + X * r + sum / n
+  pub remainder: Vec<Goldilocks>, // r, of degree below n - 1
+}
+
+// Evaluates a polynomial given by its coefficients (lowest degree first)
+fn evaluate(coeffs: &[Goldilocks], x: Goldilocks) -> Goldilocks {
+  coeffs.iter().rev().fold(Goldilocks::ZERO, |acc, c| acc * x + *c)
+}
+
+// Sum of f over the subgroup of order 2^log_n, computed by evaluating f on the subgroup
+pub fn subgroup_sum(coeffs: &[Goldilocks], log_n: u32) -> Option<Goldilocks> {
+  if log_n > TWO_ADICITY {
+    return None;
+  }
+  let n = 1usize << log_n;
+  // Fold f modulo X^n - 1 first: X^n = 1 on the subgroup
+  let mut folded = vec![Goldilocks::ZERO; n];
+  for (i, c) in coeffs.iter().enumerate() {
+    folded[i % n] = folded[i % n] + *c;
+  }
+  if !ntt(&mut folded) {
+    return None;
+  }
+  Some(folded.into_iter().fold(Goldilocks::ZERO, |acc, v| acc + v))
+}
+
+// Produces the sumcheck proof for f over the subgroup of order 2^log_n
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(log_n)))]
+pub fn prove(coeffs: &[Goldilocks], log_n: u32) -> Option<SubgroupSumcheckProof> {
+  if log_n > TWO_ADICITY {
+    return None;
+  }
+  let n = 1usize << log_n;
+
+  // Long division by X^n - 1
+  let mut rem = coeffs.to_vec();
+  rem.resize(rem.len().max(n), Goldilocks::ZERO);
+  let mut quotient = vec![Goldilocks::ZERO; rem.len().saturating_sub(n)];
+  for i in (n..rem.len()).rev() {
+    let top = rem[i];
+    quotient[i - n] = quotient[i - n] + top;
+    rem[i - n] = rem[i - n] + top;
+    rem[i] = Goldilocks::ZERO;
+  }
+
+  let claimed_sum = Goldilocks::new(n as u64) * rem[0];
+  Some(SubgroupSumcheckProof {
+    claimed_sum,
+    quotient,
+    remainder: rem[1..n].to_vec(),
+  })
+}
+
+// Checks the proof against f at a verifier-chosen point
+pub fn verify_at(coeffs: &[Goldilocks], log_n: u32, proof: &SubgroupSumcheckProof, z: Goldilocks) -> bool {
+  if log_n > TWO_ADICITY {
+    return false;
+  }
+  let n = 1u64 << log_n;
+  if proof.remainder.len() >= n as usize {
+    return false;
+  }
+  let n_inv = match Goldilocks::new(n).inverse() {
+    Some(inverse) => inverse,
+    None => return false,
+  };
+
+  let vanishing = z.pow(n) - Goldilocks::ONE;
+  let rhs = vanishing * evaluate(&proof.quotient, z) + z * evaluate(&proof.remainder, z) + proof.claimed_sum * n_inv;
+  evaluate(coeffs, z) == rhs
+}
+
+// Checks the proof at a random point
+pub fn verify(coeffs: &[Goldilocks], log_n: u32, proof: &SubgroupSumcheckProof) -> bool {
+  let z = Goldilocks::new(test_rng::source().gen_range(0..GOLDILOCKS_P));
+  verify_at(coeffs, log_n, proof, z)
+}
+
+// Absorbs the statement and proof, then derives the evaluation point
+fn transcript_point(coeffs: &[Goldilocks], log_n: u32, proof: &SubgroupSumcheckProof, transcript: &mut Transcript) -> Goldilocks {
+  transcript.absorb("log_n", &log_n.to_be_bytes());
+  transcript.absorb_goldilocks("f", coeffs);
+  transcript.absorb_goldilocks("claimed_sum", &[proof.claimed_sum]);
+  transcript.absorb_goldilocks("quotient", &proof.quotient);
+  transcript.absorb_goldilocks("remainder", &proof.remainder);
+  transcript.challenge_goldilocks("z")
+}
+
+// Produces the proof and runs the prover side of the transcript, which a recording
+// transcript captures for comparison with the verifier's
+pub fn prove_with_transcript(coeffs: &[Goldilocks], log_n: u32, transcript: &mut Transcript) -> Option<SubgroupSumcheckProof> {
+  let proof = prove(coeffs, log_n)?;
+  transcript_point(coeffs, log_n, &proof, transcript);
+  Some(proof)
+}
+
+// Checks the proof at the point derived from the transcript
+pub fn verify_with_transcript(coeffs: &[Goldilocks], log_n: u32, proof: &SubgroupSumcheckProof, transcript: &mut Transcript) -> bool {
+  let z = transcript_point(coeffs, log_n, proof, transcript);
+  verify_at(coeffs, log_n, proof, z)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn polynomial(len: u64) -> Vec<Goldilocks> {
+    (0..len).map(|i| Goldilocks::new(i * 7 + 1)).collect()
+  }
+
+  #[test]
+  fn test_sumcheck_over_subgroup() {
+    let coeffs = polynomial(40);
+    let proof = prove(&coeffs, 4).unwrap();
+    assert_eq!(Some(proof.claimed_sum), subgroup_sum(&coeffs, 4));
+    assert!(verify(&coeffs, 4, &proof));
+
+    // Direct summation over the subgroup agrees
+    let root = Goldilocks::root_of_unity(4).unwrap();
+    let direct = (0..16).fold(Goldilocks::ZERO, |acc, i| acc + evaluate(&coeffs, root.pow(i)));
+    assert_eq!(proof.claimed_sum, direct);
+  }
+
+  #[test]
+  fn test_false_claim_rejected() {
+    let coeffs = polynomial(40);
+    let mut proof = prove(&coeffs, 4).unwrap();
+    proof.claimed_sum = proof.claimed_sum + Goldilocks::ONE;
+    assert!(!verify(&coeffs, 4, &proof));
+
+    // An honest proof for a different polynomial does not transfer
+    let other = prove(&polynomial(41), 4).unwrap();
+    assert!(!verify_at(&coeffs, 4, &other, Goldilocks::new(12345)));
+  }
+
+  #[test]
+  fn test_transcript_export() {
+    let coeffs = polynomial(40);
+    let mut prover = Transcript::recording(b"subgroup-sumcheck");
+    let proof = prove_with_transcript(&coeffs, 4, &mut prover).unwrap();
+    let mut verifier = Transcript::recording(b"subgroup-sumcheck");
+    assert!(verify_with_transcript(&coeffs, 4, &proof, &mut verifier));
+    assert_eq!(prover.to_json(), verifier.to_json());
+
+    // A tampered quotient shows up at the quotient entry, after domain, log_n, f and the sum
+    let mut tampered = proof;
+    tampered.quotient[0] = tampered.quotient[0] + Goldilocks::ONE;
+    let mut verifier = Transcript::recording(b"subgroup-sumcheck");
+    assert!(!verify_with_transcript(&coeffs, 4, &tampered, &mut verifier));
+    assert_eq!(prover.first_divergence(&verifier), Some(4));
+    assert_eq!(verifier.entries()[4].label, "quotient");
+  }
+}
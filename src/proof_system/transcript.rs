@@ -0,0 +1,163 @@
+// Fiat-Shamir transcript
+// Prover and verifier absorb the same labelled messages into a SHA3-256 hash chain and
+// derive challenges from its state, so the verifier's challenges match the prover's only if
+// both saw identical messages. A recording transcript also keeps every absorbed item and
+// derived challenge; exporting two recordings as JSON and comparing them (or calling
+// first_divergence) pinpoints the first field on which two implementations disagree.
+
+use crate::crypto::goldilocks::{Goldilocks, GOLDILOCKS_P};
+use crate::utils::encoding::Encoding;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+// Kind of a recorded transcript step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptOp {
+  Absorb,
+  Challenge,
+}
+
+// One recorded step: the label, and the absorbed bytes or derived challenge as hex
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+  pub op: TranscriptOp,
+  pub label: String,
+  pub data: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Transcript {
+  state: [u8; 32],
+  record: Option<Vec<TranscriptEntry>>,
+}
+
+impl Transcript {
+  // Starts a transcript bound to a protocol domain
+  pub fn new(domain: &[u8]) -> Self {
+    let mut hasher = Sha3_256::new();
+    hasher.update(b"loquat-transcript");
+    hasher.update(domain);
+    Self {
+      state: hasher.finalize().into(),
+      record: None,
+    }
+  }
+
+  // Starts a transcript that also records every step for export
+  pub fn recording(domain: &[u8]) -> Self {
+    let mut transcript = Self::new(domain);
+    transcript.record = Some(vec![TranscriptEntry {
+      op: TranscriptOp::Absorb,
+      label: "domain".to_string(),
+      data: Encoding::to_hex(domain),
+    }]);
+    transcript
+  }
+
+  fn push(&mut self, op: TranscriptOp, label: &str, data: &[u8]) {
+    if let Some(record) = &mut self.record {
+      record.push(TranscriptEntry {
+        op,
+        label: label.to_string(),
+        data: Encoding::to_hex(data),
+      });
+    }
+  }
+
+  // Absorbs a labelled message
+  pub fn absorb(&mut self, label: &str, data: &[u8]) {
+    let mut input = b"absorb".to_vec();
+    Encoding::write_bytes(&mut input, label.as_bytes());
+    Encoding::write_bytes(&mut input, data);
+    self.state = Sha3_256::new().chain_update(self.state).chain_update(input).finalize().into();
+    self.push(TranscriptOp::Absorb, label, data);
+  }
+
+  // Absorbs field elements as 8-byte big-endian words
+  pub fn absorb_goldilocks(&mut self, label: &str, elements: &[Goldilocks]) {
+    let data: Vec<u8> = elements.iter().flat_map(|e| e.value().to_be_bytes()).collect();
+    self.absorb(label, &data);
+  }
+
+  // Derives a 32-byte challenge and ratchets the state
+  pub fn challenge_bytes(&mut self, label: &str) -> [u8; 32] {
+    let mut input = b"challenge".to_vec();
+    Encoding::write_bytes(&mut input, label.as_bytes());
+    let challenge: [u8; 32] = Sha3_256::new().chain_update(self.state).chain_update(input).finalize().into();
+    self.state = Sha3_256::new().chain_update(challenge).finalize().into();
+    self.push(TranscriptOp::Challenge, label, &challenge);
+    challenge
+  }
+
+  // Derives a Goldilocks challenge; the 128 hashed bits make the reduction bias negligible
+  pub fn challenge_goldilocks(&mut self, label: &str) -> Goldilocks {
+    let bytes = self.challenge_bytes(label);
+    let wide = u128::from_be_bytes(bytes[..16].try_into().expect("Challenge has 32 bytes"));
+    Goldilocks::new((wide % GOLDILOCKS_P as u128) as u64)
+  }
+
+  // Recorded steps; empty unless the transcript was created with recording
+  pub fn entries(&self) -> &[TranscriptEntry] {
+    self.record.as_deref().unwrap_or(&[])
+  }
+
+  // Recorded steps as a JSON array
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self.entries()).expect("Transcript serialization failed")
+  }
+
+  // Index of the first step at which two recordings differ, if any
+  pub fn first_divergence(&self, other: &Transcript) -> Option<usize> {
+    first_divergence(self.entries(), other.entries())
+  }
+}
+
+// Index of the first differing step of two recordings, e.g. one parsed from another
+// implementation's JSON export
+pub fn first_divergence(a: &[TranscriptEntry], b: &[TranscriptEntry]) -> Option<usize> {
+  match a.iter().zip(b).position(|(x, y)| x != y) {
+    Some(index) => Some(index),
+    None if a.len() != b.len() => Some(a.len().min(b.len())),
+    None => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_challenges_depend_on_messages() {
+    let mut a = Transcript::new(b"test");
+    let mut b = Transcript::new(b"test");
+    a.absorb("x", b"1");
+    b.absorb("x", b"1");
+    assert_eq!(a.challenge_bytes("c"), b.challenge_bytes("c"));
+    // Consecutive challenges differ, and so do challenges after different messages
+    assert_ne!(a.challenge_bytes("c"), a.challenge_bytes("c"));
+
+    let mut c = Transcript::new(b"test");
+    c.absorb("x", b"2");
+    assert_ne!(Transcript::new(b"test").challenge_bytes("c"), c.challenge_bytes("c"));
+    assert!(a.entries().is_empty());
+  }
+
+  #[test]
+  fn test_recording_export() {
+    let mut prover = Transcript::recording(b"test");
+    let mut verifier = Transcript::recording(b"test");
+    for (transcript, value) in [(&mut prover, 5), (&mut verifier, 6)] {
+      transcript.absorb("commitment", b"root");
+      transcript.absorb_goldilocks("claim", &[Goldilocks::new(value)]);
+      transcript.challenge_goldilocks("z");
+    }
+    assert_eq!(prover.entries().len(), 4);
+    assert_eq!(prover.first_divergence(&verifier), Some(2));
+
+    let parsed: Vec<TranscriptEntry> = serde_json::from_str(&prover.to_json()).unwrap();
+    assert_eq!(parsed[1], TranscriptEntry { op: TranscriptOp::Absorb, label: "commitment".to_string(), data: "726f6f74".to_string() });
+    assert_eq!(first_divergence(&parsed, prover.entries()), None);
+    assert_eq!(first_divergence(&parsed[..3], prover.entries()), Some(3));
+  }
+}
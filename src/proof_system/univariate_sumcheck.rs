@@ -1,65 +1,216 @@
-// Univariate polynomial commitment verification
-// Efficient sumcheck protocol for SNARK applications
-// Security through random challenges and sum evaluations
+// Committed univariate sumcheck
+// The prover commits to f, of degree below d = 2^log_degree_bound, by committing to its
+// evaluations on a FRI domain; the verifier learns the claimed sum of f over the subgroup H of
+// order n = 2^log_n and never sees f. The prover also commits to q and r with
+// f = Z_H * q + X * r + sum / n (see subgroup_sumcheck), and the verifier
+//  - checks the identity at transcript-chosen points of the FRI domain from openings of f, q
+//    and r, and
+//  - runs FRI on f + a * X^n * q + b * X^(d - n + 1) * r under the bound d, which fails unless
+//    deg q < d - n and deg r < n - 1 as well; the degree bound on r is what makes a false sum
+//    unprovable.
+// Openings reveal evaluations of f outside H at the query points; the protocol is succinct,
+// not zero-knowledge.
 
-use crate::crypto::polynomial::Polynomial;
+use crate::crypto::goldilocks::Goldilocks;
+use crate::proof_system::fri::{self, CommittedTable, CosetOpening, EvaluationDomain, FriConfig, FriProof};
+use crate::proof_system::polynomial::Polynomial;
+use crate::proof_system::subgroup_sumcheck;
+use crate::proof_system::transcript::Transcript;
+use crate::signature::error::VerifyError;
 use num_bigint::BigUint;
-use rand::Rng;
-use num_traits::Zero;
 
-// Prime field modulus (p = 2^127 - 1) 
-const P: u128 = (1 << 127) - 1;
+// Domain separation of the Fiat-Shamir transcript
+const SUMCHECK_DOMAIN: &[u8] = b"loquat-univariate-sumcheck";
+
+// Commitment to a polynomial of degree below 2^log_degree_bound
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolynomialCommitment {
+  pub root: BigUint,
+  pub log_degree_bound: u32,
+}
+
+// Openings of f, q and r on one queried coset of points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumcheckQuery {
+  pub f: CosetOpening,
+  pub quotient: CosetOpening,
+  pub remainder: CosetOpening,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumcheckProof {
+  pub claimed_sum: Goldilocks,
+  pub quotient_root: BigUint,
+  pub remainder_root: BigUint,
+  pub fri: FriProof,
+  pub queries: Vec<SumcheckQuery>,
+}
 
 // Sumcheck Prover
 pub struct SumcheckProver {
-  polynomial: Polynomial,
+  coeffs: Vec<Goldilocks>,
+  log_degree_bound: u32,
+  config: FriConfig,
+  table: CommittedTable,
 }
 
 // Sumcheck Verifier
 pub struct SumcheckVerifier {
-  claimed_sum: BigUint,
+  claimed_sum: Goldilocks,
+  log_n: u32,
+  config: FriConfig,
+}
+
+// Absorbs the statement and the prover's commitments, then derives the combination weights
+fn combination_weights(commitment: &PolynomialCommitment, log_n: u32, claimed_sum: Goldilocks, quotient_root: &BigUint, remainder_root: &BigUint, transcript: &mut Transcript) -> (Goldilocks, Goldilocks) {
+  transcript.absorb("f", &commitment.root.to_bytes_be());
+  transcript.absorb("log_degree_bound", &commitment.log_degree_bound.to_be_bytes());
+  transcript.absorb("log_n", &log_n.to_be_bytes());
+  transcript.absorb_goldilocks("claimed_sum", &[claimed_sum]);
+  transcript.absorb("quotient", &quotient_root.to_bytes_be());
+  transcript.absorb("remainder", &remainder_root.to_bytes_be());
+  (transcript.challenge_goldilocks("a"), transcript.challenge_goldilocks("b"))
+}
+
+// Value of the FRI combination at x
+fn combine(x: Goldilocks, f: Goldilocks, q: Goldilocks, r: Goldilocks, weights: (Goldilocks, Goldilocks), log_n: u32, log_degree_bound: u32) -> Goldilocks {
+  let n = 1u64 << log_n;
+  let d = 1u64 << log_degree_bound;
+  f + weights.0 * x.pow(n) * q + weights.1 * x.pow(d - n + 1) * r
 }
 
 impl SumcheckProver {
-  // Creates a new prover instance with a polynomial
-  pub fn new(poly: Polynomial) -> Self {
-    Self { polynomial: poly }
+  // Commits to f; None if it does not fit under the degree bound or the domain is too large
+  pub fn new(coeffs: Vec<Goldilocks>, log_degree_bound: u32, config: FriConfig) -> Option<Self> {
+    Self::from_polynomial(Polynomial::Coefficients(coeffs), log_degree_bound, config)
   }
 
-  // Generates proof for the sum over a domain
-  pub fn generate_proof(&self, domain: &[u128]) -> (BigUint, Vec<BigUint>) {
-    let sum = domain.iter().fold(BigUint::zero(), |acc, &x| {
-      let eval = BigUint::from(self.polynomial.evaluate(x));
-      (acc + eval) % BigUint::from(P)
-    });
-    
-    let mut challenges = vec![];
+  // Commits to f in either form; evaluations already on the FRI domain are committed as they
+  // are, so only the division for the sum needs the coefficients
+  pub fn from_polynomial(f: Polynomial, log_degree_bound: u32, config: FriConfig) -> Option<Self> {
+    let degree_bound = 1usize.checked_shl(log_degree_bound)?;
+    let domain = EvaluationDomain::coset(log_degree_bound + config.log_blowup)?;
+    let mut coeffs = f.coefficients()?.into_owned();
+    if coeffs.iter().skip(degree_bound).any(|c| *c != Goldilocks::ZERO) {
+      return None;
+    }
+    coeffs.truncate(degree_bound);
+    let table = CommittedTable::commit_cosets(f.evaluations(&domain)?.into_owned(), config.first_layer_arity(log_degree_bound))?;
+    Some(Self {
+      coeffs,
+      log_degree_bound,
+      config,
+      table,
+    })
+  }
 
-    for _ in 0..self.polynomial.degree() {
-      let random_challenge = rand::thread_rng().gen_range(1..P);
-      challenges.push(BigUint::from(random_challenge));
+  // The commitment to publish to verifiers
+  pub fn commitment(&self) -> PolynomialCommitment {
+    PolynomialCommitment {
+      root: self.table.root(),
+      log_degree_bound: self.log_degree_bound,
     }
+  }
 
-    (sum, challenges)
+  // Proves the sum of f over the subgroup of order 2^log_n, which must not exceed the degree bound
+  pub fn generate_proof(&self, log_n: u32) -> Option<SumcheckProof> {
+    if log_n > self.log_degree_bound {
+      return None;
+    }
+    let split = subgroup_sumcheck::prove(&self.coeffs, log_n)?;
+    let domain = EvaluationDomain::coset(self.log_degree_bound + self.config.log_blowup)?;
+    let arity = self.table.arity();
+    let quotient = CommittedTable::commit_cosets(domain.evaluate(&split.quotient)?, arity)?;
+    let remainder = CommittedTable::commit_cosets(domain.evaluate(&split.remainder)?, arity)?;
+
+    let mut transcript = Transcript::new(SUMCHECK_DOMAIN);
+    let weights = combination_weights(&self.commitment(), log_n, split.claimed_sum, &quotient.root(), &remainder.root(), &mut transcript);
+    let combined: Vec<Goldilocks> = (0..domain.size())
+      .map(|i| {
+        let (f, q, r) = (self.table.values()[i], quotient.values()[i], remainder.values()[i]);
+        combine(domain.element(i), f, q, r, weights, log_n, self.log_degree_bound)
+      })
+      .collect();
+
+    let (fri, indices) = fri::prove(&combined, self.log_degree_bound, &domain, &self.config, &mut transcript)?;
+    let queries = indices
+      .iter()
+      .map(|&index| {
+        Some(SumcheckQuery {
+          f: self.table.open(index)?,
+          quotient: quotient.open(index)?,
+          remainder: remainder.open(index)?,
+        })
+      })
+      .collect::<Option<Vec<_>>>()?;
+
+    Some(SumcheckProof {
+      claimed_sum: split.claimed_sum,
+      quotient_root: quotient.root(),
+      remainder_root: remainder.root(),
+      fri,
+      queries,
+    })
   }
 }
 
 impl SumcheckVerifier {
-  // Creates a new verifier instance with a claimed sum
-  pub fn new(claimed_sum: BigUint) -> Self {
-    Self { claimed_sum }
+  // Creates a verifier for the claim "f sums to claimed_sum over the subgroup of order 2^log_n"
+  pub fn new(claimed_sum: Goldilocks, log_n: u32, config: FriConfig) -> Self {
+    Self { claimed_sum, log_n, config }
+  }
+
+  // Verifies the proof against the commitment to f alone
+  pub fn verify_proof(&self, commitment: &PolynomialCommitment, proof: &SumcheckProof) -> bool {
+    self.try_verify_proof(commitment, proof).is_ok()
   }
 
-  // Verifies the sumcheck proof
-  pub fn verify_proof(&self, proof: (BigUint, Vec<BigUint>), poly: &Polynomial, domain: &[u128]) -> bool {
-    let (computed_sum, challenges) = proof;
+  // verify_proof, telling proofs of the wrong shape (BadEncoding) from proofs that fail
+  // their checks (SumcheckFailed)
+  pub fn try_verify_proof(&self, commitment: &PolynomialCommitment, proof: &SumcheckProof) -> Result<(), VerifyError> {
+    let log_degree_bound = commitment.log_degree_bound;
+    let domain = EvaluationDomain::coset(log_degree_bound + self.config.log_blowup).ok_or(VerifyError::BadEncoding)?;
+    if self.log_n > log_degree_bound || proof.queries.len() != self.config.num_queries {
+      return Err(VerifyError::BadEncoding);
+    }
+    if proof.claimed_sum != self.claimed_sum {
+      return Err(VerifyError::SumcheckFailed);
+    }
+    let n_inv = Goldilocks::new(1u64 << self.log_n).inverse().ok_or(VerifyError::BadEncoding)?;
 
-    let expected_sum = domain.iter().fold(BigUint::zero(), |acc, &x| {
-      let eval = BigUint::from(poly.evaluate(x));
-      (acc + eval) % BigUint::from(P)
-    });
+    let mut transcript = Transcript::new(SUMCHECK_DOMAIN);
+    let weights = combination_weights(commitment, self.log_n, proof.claimed_sum, &proof.quotient_root, &proof.remainder_root, &mut transcript);
+    let cosets = domain.size() / self.config.first_layer_arity(log_degree_bound);
+    let accepted = fri::verify(&proof.fri, log_degree_bound, &domain, &self.config, &mut transcript, |query, index| {
+      let opened = proof.queries.get(query)?;
+      let log_size = domain.log_size;
+      if !opened.f.verify(&commitment.root, index, log_size)
+        || !opened.quotient.verify(&proof.quotient_root, index, log_size)
+        || !opened.remainder.verify(&proof.remainder_root, index, log_size)
+      {
+        return None;
+      }
 
-    expected_sum == computed_sum && challenges.iter().all(|c| c < &BigUint::from(P))
+      let mut combined = vec![Goldilocks::ZERO; opened.f.values.len()];
+      if opened.quotient.values.len() != combined.len() || opened.remainder.values.len() != combined.len() {
+        return None;
+      }
+      for (slot, combined) in combined.iter_mut().enumerate() {
+        let x = domain.element(index + slot * cosets);
+        let (f, q, r) = (opened.f.values[slot], opened.quotient.values[slot], opened.remainder.values[slot]);
+        let vanishing = x.pow(1u64 << self.log_n) - Goldilocks::ONE;
+        if f != vanishing * q + x * r + proof.claimed_sum * n_inv {
+          return None;
+        }
+        *combined = combine(x, f, q, r, weights, self.log_n, log_degree_bound);
+      }
+      Some(combined)
+    });
+    if accepted {
+      Ok(())
+    } else {
+      Err(VerifyError::SumcheckFailed)
+    }
   }
 }
 
@@ -67,44 +218,95 @@ impl SumcheckVerifier {
 mod tests {
   use super::*;
 
-  // Modular arithmetic helper functions
-  fn mod_add(a: &BigUint, b: &BigUint) -> BigUint {
-    (a + b) % BigUint::from(P)
-  }
-
-  fn mod_sub(a: &BigUint, b: &BigUint) -> BigUint {
-    if a < b {
-      BigUint::from(P) - (b - a) % BigUint::from(P)
-    } else {
-      (a - b) % BigUint::from(P)
-    }
-  }
-
-  fn mod_mul(a: &BigUint, b: &BigUint) -> BigUint {
-    (a * b) % BigUint::from(P)
+  fn polynomial(len: u64) -> Vec<Goldilocks> {
+    (0..len).map(|i| Goldilocks::new(3 * i + 1)).collect()
   }
 
   #[test]
   fn test_sumcheck_proof() {
-    let poly = Polynomial::new(vec![1, 2, 3]); // f(x) = 3x² + 2x + 1
-    let domain = vec![1, 2, 3, 4];
+    let coeffs = polynomial(50);
+    let prover = SumcheckProver::new(coeffs.clone(), 6, FriConfig::default()).unwrap();
+    let proof = prover.generate_proof(3).unwrap();
+    assert_eq!(Some(proof.claimed_sum), subgroup_sumcheck::subgroup_sum(&coeffs, 3));
 
-    let prover = SumcheckProver::new(poly.clone());
-    let proof = prover.generate_proof(&domain);
+    // The verifier only holds the commitment and the claimed sum
+    let verifier = SumcheckVerifier::new(proof.claimed_sum, 3, FriConfig::default());
+    assert!(verifier.verify_proof(&prover.commitment(), &proof));
+    // Summation domain as large as the degree bound
+    let proof = prover.generate_proof(6).unwrap();
+    assert!(SumcheckVerifier::new(proof.claimed_sum, 6, FriConfig::default()).verify_proof(&prover.commitment(), &proof));
 
-    let verifier = SumcheckVerifier::new(proof.0.clone());
-    assert!(verifier.verify_proof(proof, &poly, &domain));
+    // Committing from evaluations on the FRI domain gives the same commitment
+    let domain = EvaluationDomain::coset(6 + FriConfig::default().log_blowup).unwrap();
+    let evaluated = Polynomial::Coefficients(coeffs).into_evaluations(&domain).unwrap();
+    let from_evaluations = SumcheckProver::from_polynomial(evaluated, 6, FriConfig::default()).unwrap();
+    assert_eq!(from_evaluations.commitment(), prover.commitment());
+    assert_eq!(from_evaluations.generate_proof(3).unwrap().claimed_sum, prover.generate_proof(3).unwrap().claimed_sum);
+
+    // Coset openings of the committed tables for wider FRI folds; DEEP sampling leaves them as they are
+    let config = FriConfig { folding_factor: 4, grinding_bits: 4, deep: true, ..FriConfig::default() };
+    let folded = SumcheckProver::new(polynomial(50), 6, config).unwrap();
+    let proof = folded.generate_proof(3).unwrap();
+    assert_eq!(proof.queries[0].f.values.len(), 4);
+    assert!(SumcheckVerifier::new(proof.claimed_sum, 3, config).verify_proof(&folded.commitment(), &proof));
+    assert!(!SumcheckVerifier::new(proof.claimed_sum, 3, FriConfig::default()).verify_proof(&folded.commitment(), &proof));
   }
 
   #[test]
   fn test_invalid_sumcheck() {
-    let poly = Polynomial::new(vec![1, 2, 3]); // f(x) = 3x² + 2x + 1
-    let domain = vec![1, 2, 3, 4];
+    let prover = SumcheckProver::new(polynomial(50), 6, FriConfig::default()).unwrap();
+    let proof = prover.generate_proof(3).unwrap();
+
+    let verifier = SumcheckVerifier::new(Goldilocks::new(999), 3, FriConfig::default()); // Incorrect sum
+    assert!(!verifier.verify_proof(&prover.commitment(), &proof));
 
-    let prover = SumcheckProver::new(poly.clone());
-    let proof = prover.generate_proof(&domain);
+    // An honest proof does not transfer to another committed polynomial
+    let other = SumcheckProver::new(polynomial(51), 6, FriConfig::default()).unwrap();
+    let verifier = SumcheckVerifier::new(proof.claimed_sum, 3, FriConfig::default());
+    assert!(!verifier.verify_proof(&other.commitment(), &proof));
+    assert_eq!(verifier.try_verify_proof(&other.commitment(), &proof), Err(VerifyError::SumcheckFailed));
+
+    // A proof with the wrong number of openings is malformed rather than false
+    let mut truncated = proof.clone();
+    truncated.queries.pop();
+    assert_eq!(verifier.try_verify_proof(&prover.commitment(), &truncated), Err(VerifyError::BadEncoding));
+    assert_eq!(verifier.try_verify_proof(&prover.commitment(), &proof), Ok(()));
+  }
+
+  #[test]
+  fn test_false_sum_with_high_degree_remainder_rejected() {
+    // A cheating prover shifts the sum by pushing the difference into a remainder of degree n - 1,
+    // which satisfies the identity at every point but fails the degree check
+    let coeffs = polynomial(50);
+    let prover = SumcheckProver::new(coeffs.clone(), 6, FriConfig::default()).unwrap();
+    let mut split = subgroup_sumcheck::prove(&coeffs, 3).unwrap();
+    let delta = Goldilocks::new(5);
+    split.claimed_sum = split.claimed_sum + delta;
+    // f = Z_H * (q + delta / n) + X * (r - delta / n * X^(n - 1)) + (sum + delta) / n
+    let n_inv = Goldilocks::new(8).inverse().unwrap();
+    split.quotient[0] = split.quotient[0] + delta * n_inv;
+    split.remainder.push(-(delta * n_inv));
 
-    let verifier = SumcheckVerifier::new(BigUint::from(999u32)); // Incorrect sum
-    assert!(!verifier.verify_proof(proof, &poly, &domain));
+    let domain = EvaluationDomain::coset(6 + FriConfig::default().log_blowup).unwrap();
+    let quotient = CommittedTable::commit(domain.evaluate(&split.quotient).unwrap()).unwrap();
+    let remainder = CommittedTable::commit(domain.evaluate(&split.remainder).unwrap()).unwrap();
+    let mut transcript = Transcript::new(SUMCHECK_DOMAIN);
+    let weights = combination_weights(&prover.commitment(), 3, split.claimed_sum, &quotient.root(), &remainder.root(), &mut transcript);
+    let combined: Vec<Goldilocks> = (0..domain.size())
+      .map(|i| combine(domain.element(i), prover.table.values()[i], quotient.values()[i], remainder.values()[i], weights, 3, 6))
+      .collect();
+    let (fri, indices) = fri::prove(&combined, 6, &domain, &FriConfig::default(), &mut transcript).unwrap();
+    let proof = SumcheckProof {
+      claimed_sum: split.claimed_sum,
+      quotient_root: quotient.root(),
+      remainder_root: remainder.root(),
+      fri,
+      queries: indices
+        .iter()
+        .map(|&i| SumcheckQuery { f: prover.table.open(i).unwrap(), quotient: quotient.open(i).unwrap(), remainder: remainder.open(i).unwrap() })
+        .collect(),
+    };
+    let verifier = SumcheckVerifier::new(split.claimed_sum, 3, FriConfig::default());
+    assert!(!verifier.verify_proof(&prover.commitment(), &proof));
   }
 }
@@ -0,0 +1,277 @@
+// Append-only Merkle log of issued credential hashes
+// Leaves are domain-separated hashes of the credential's canonical JSON encoding (including
+// its proof), so a log entry pins the exact credential that was issued.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::merkle::MerkleTree;
+use crate::crypto::rng::RngHealthError;
+use crate::registry::checkpoint::{Checkpoint, SignedCheckpoint};
+use crate::signature::message::SignableMessage;
+use crate::utils::wire;
+use crate::vc::credential::Credential;
+use num_bigint::BigUint;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Prefix separating leaf hashes from interior node hashes
+const LEAF_TAG: &[u8] = b"loquat-vc/registry-leaf\x00";
+
+// Proof that a leaf is included in a log of a given size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+  pub leaf_index: usize,
+  pub tree_size: usize,
+  pub path: Vec<(BigUint, bool)>, // Siblings from the leaf up, flag set when the running hash is on the left
+}
+
+impl InclusionProof {
+  // Encoding: u64 leaf index | u64 tree size | path (utils::wire::write_path)
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = (self.leaf_index as u64).to_be_bytes().to_vec();
+    out.extend_from_slice(&(self.tree_size as u64).to_be_bytes());
+    wire::write_path(&mut out, &self.path);
+    out
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let leaf_index = u64::from_be_bytes(bytes.get(..8)?.try_into().ok()?).try_into().ok()?;
+    let tree_size = u64::from_be_bytes(bytes.get(8..16)?.try_into().ok()?).try_into().ok()?;
+    let mut offset = 16;
+    let path = wire::read_path(bytes, &mut offset)?;
+    if offset != bytes.len() {
+      return None;
+    }
+    Some(Self { leaf_index, tree_size, path })
+  }
+
+  // Checks the proof for a leaf against a checkpoint of the same size
+  pub fn verify(&self, leaf: &BigUint, checkpoint: &Checkpoint, hash_function: HashFunction) -> bool {
+    if self.tree_size != checkpoint.tree_size || self.leaf_index >= self.tree_size {
+      return false;
+    }
+    match checkpoint.root() {
+      Some(root) => MerkleTree::verify_proof(&root, leaf, &self.path, &hash_function),
+      None => false,
+    }
+  }
+}
+
+// Proof that a log of new_size entries extends the log of old_size entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+  pub old_size: usize,
+  pub new_size: usize,
+  pub path: Vec<BigUint>,
+}
+
+impl ConsistencyProof {
+  // Checks that the newer checkpoint extends the older one without rewriting history
+  // An empty old log is extended by any log
+  pub fn verify(&self, old: &Checkpoint, new: &Checkpoint, hash_function: HashFunction) -> bool {
+    if old.log_id != new.log_id || self.old_size != old.tree_size || self.new_size != new.tree_size {
+      return false;
+    }
+    if self.old_size == 0 {
+      return self.path.is_empty();
+    }
+    match (old.root(), new.root()) {
+      (Some(old_root), Some(new_root)) => {
+        MerkleTree::verify_consistency(self.old_size, self.new_size, &old_root, &new_root, &self.path, &hash_function)
+      }
+      _ => false,
+    }
+  }
+}
+
+// An issuer's credential log
+pub struct CredentialLog {
+  log_id: String,
+  hash_function: HashFunction,
+  leaves: Vec<BigUint>,
+  positions: HashMap<BigUint, usize>, // First index of every leaf
+}
+
+impl CredentialLog {
+  pub fn new(log_id: &str, hash_function: HashFunction) -> Self {
+    Self {
+      log_id: log_id.to_string(),
+      hash_function,
+      leaves: vec![],
+      positions: HashMap::new(),
+    }
+  }
+
+  pub fn log_id(&self) -> &str {
+    &self.log_id
+  }
+
+  pub fn hash_function(&self) -> HashFunction {
+    self.hash_function
+  }
+
+  pub fn size(&self) -> usize {
+    self.leaves.len()
+  }
+
+  // Leaf hash of a credential under this log's hash function
+  pub fn leaf_hash(&self, credential: &Credential) -> BigUint {
+    let encoded = serde_json::to_value(credential).expect("Credential serialization failed");
+    Self::hash_entry(&encoded, self.hash_function)
+  }
+
+  fn hash_entry(entry: &Value, hash_function: HashFunction) -> BigUint {
+    let mut data = LEAF_TAG.to_vec();
+    data.extend_from_slice(&entry.signing_bytes());
+    BigUint::from_bytes_be(&Hash::new(hash_function).compute(&data))
+  }
+
+  // Appends a credential and returns its leaf index
+  pub fn append(&mut self, credential: &Credential) -> usize {
+    let leaf = self.leaf_hash(credential);
+    self.append_leaf(leaf)
+  }
+
+  // Appends a precomputed leaf hash and returns its index
+  pub fn append_leaf(&mut self, leaf: BigUint) -> usize {
+    let index = self.leaves.len();
+    self.positions.entry(leaf.clone()).or_insert(index);
+    self.leaves.push(leaf);
+    index
+  }
+
+  // Index of the first entry holding a credential
+  pub fn position(&self, credential: &Credential) -> Option<usize> {
+    self.positions.get(&self.leaf_hash(credential)).copied()
+  }
+
+  pub fn leaf(&self, index: usize) -> Option<&BigUint> {
+    self.leaves.get(index)
+  }
+
+  fn tree(&self) -> MerkleTree {
+    MerkleTree::new(self.leaves.clone(), self.hash_function)
+  }
+
+  // Current Merkle root, or None while the log is empty
+  pub fn root(&self) -> Option<BigUint> {
+    self.tree().root()
+  }
+
+  pub fn inclusion_proof(&self, leaf_index: usize) -> Option<InclusionProof> {
+    Some(InclusionProof {
+      leaf_index,
+      tree_size: self.size(),
+      path: self.tree().generate_proof(leaf_index)?,
+    })
+  }
+
+  pub fn inclusion_proof_for(&self, credential: &Credential) -> Option<InclusionProof> {
+    self.inclusion_proof(self.position(credential)?)
+  }
+
+  // Proves that the current log extends its first old_size entries
+  pub fn consistency_proof(&self, old_size: usize) -> Option<ConsistencyProof> {
+    let path = match old_size {
+      0 => vec![],
+      _ => self.tree().consistency_proof(old_size, self.size())?,
+    };
+    Some(ConsistencyProof {
+      old_size,
+      new_size: self.size(),
+      path,
+    })
+  }
+
+  // Unsigned tree head of the current log
+  pub fn checkpoint_at(&self, timestamp: u64) -> Checkpoint {
+    Checkpoint::new(&self.log_id, self.size(), self.root().as_ref(), timestamp)
+  }
+
+  // Signed tree head of the current log
+  pub fn checkpoint(&self, sk: u128, verification_method: &str, timestamp: u64) -> Result<SignedCheckpoint, RngHealthError> {
+    self.checkpoint_at(timestamp).sign(sk, verification_method)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use serde_json::json;
+
+  fn credential(n: usize) -> Credential {
+    Credential::new("did:example:issuer", json!({"id": format!("did:example:holder-{}", n)}))
+  }
+
+  #[test]
+  fn test_inclusion_proofs_for_every_size() {
+    let mut log = CredentialLog::new("log-1", HashFunction::Sha3_256);
+    for size in 1..=9 {
+      log.append(&credential(size));
+      let checkpoint = log.checkpoint_at(1_000);
+      for index in 0..size {
+        let proof = log.inclusion_proof(index).unwrap();
+        assert!(proof.verify(log.leaf(index).unwrap(), &checkpoint, HashFunction::Sha3_256), "size {} index {}", size, index);
+        assert_eq!(InclusionProof::from_bytes(&proof.to_bytes()), Some(proof));
+      }
+    }
+    assert!(log.inclusion_proof(9).is_none());
+  }
+
+  #[test]
+  fn test_credential_lookup_and_checkpoint() {
+    let issuer = Loquat::keygen();
+    let mut log = CredentialLog::new("log-1", HashFunction::Keccak256);
+    for n in 0..5 {
+      log.append(&credential(n));
+    }
+    let signed = log.checkpoint(issuer.secret_key, "did:example:issuer#key-1", 1_000).unwrap();
+    assert!(signed.verify(&issuer.public_key));
+
+    let proof = log.inclusion_proof_for(&credential(3)).unwrap();
+    assert_eq!(proof.leaf_index, 3);
+    assert!(proof.verify(&log.leaf_hash(&credential(3)), &signed.checkpoint, HashFunction::Keccak256));
+    assert!(log.inclusion_proof_for(&credential(7)).is_none());
+  }
+
+  #[test]
+  fn test_stale_or_foreign_checkpoints_rejected() {
+    let mut log = CredentialLog::new("log-1", HashFunction::Sha3_256);
+    for n in 0..4 {
+      log.append(&credential(n));
+    }
+    let old = log.checkpoint_at(1_000);
+    log.append(&credential(4));
+    let proof = log.inclusion_proof(1).unwrap();
+
+    // A proof for the grown log does not verify against the older tree head
+    assert!(!proof.verify(log.leaf(1).unwrap(), &old, HashFunction::Sha3_256));
+    assert!(!proof.verify(log.leaf(2).unwrap(), &log.checkpoint_at(1_001), HashFunction::Sha3_256));
+  }
+
+  #[test]
+  fn test_consistency_between_checkpoints() {
+    let mut log = CredentialLog::new("log-1", HashFunction::Sha3_256);
+    let empty = log.checkpoint_at(999);
+    for n in 0..3 {
+      log.append(&credential(n));
+    }
+    let old = log.checkpoint_at(1_000);
+    for n in 3..8 {
+      log.append(&credential(n));
+    }
+    let new = log.checkpoint_at(2_000);
+
+    assert!(log.consistency_proof(3).unwrap().verify(&old, &new, HashFunction::Sha3_256));
+    assert!(log.consistency_proof(0).unwrap().verify(&empty, &new, HashFunction::Sha3_256));
+    assert!(log.consistency_proof(9).is_none());
+
+    // A log that rewrote an earlier entry cannot prove it extends the old checkpoint
+    let mut forked = CredentialLog::new("log-1", HashFunction::Sha3_256);
+    for n in [0, 100, 2, 3, 4, 5, 6, 7] {
+      forked.append(&credential(n));
+    }
+    let forked_head = forked.checkpoint_at(2_000);
+    assert!(!forked.consistency_proof(3).unwrap().verify(&old, &forked_head, HashFunction::Sha3_256));
+  }
+}
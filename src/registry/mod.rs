@@ -0,0 +1,13 @@
+//! # Registry Module
+//!
+//! This module implements a transparency-log-style credential registry. An issuer appends the
+//! hash of every credential it issues to an append-only Merkle log and periodically publishes
+//! signed checkpoints (tree heads), so holders can check that a credential was logged and
+//! auditors can check that every published checkpoint extends the previous one.
+//!
+//! ## Module Structure
+//! - `log`: Append-only Merkle log of credential hashes with inclusion and consistency proofs
+//! - `checkpoint`: Signed tree heads
+
+pub mod checkpoint;
+pub mod log;
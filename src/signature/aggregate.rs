@@ -2,20 +2,80 @@
 // Compression of multiple signatures into a single aggregate
 // SNARK-friendly verification for efficient proof aggregation
 
+#[cfg(feature = "std")]
 use crate::signature::loquat::{Loquat, LoquatSignature, LoquatKeyPair};
-use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::signature::config::LoquatConfig;
+use crate::signature::message::SignableMessage;
+use crate::signature::suite::Suite;
+use crate::utils::wire::{self, FIELD_BYTES};
+use crate::utils::constant_time;
+use crate::utils::metrics::{self, FailureReason, Operation};
 use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use crate::crypto::test_rng;
+#[cfg(feature = "std")]
 use rand::Rng;
-use num_traits::{Zero, ToPrimitive};
-
-// Prime field modulus (p = 2^127 - 1) 
-const P: u128 = (1 << 127) - 1;
+use num_traits::Zero;
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
 
 // Aggregated Signature Structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AggregateSignature {
   pub aggregated_sigma: BigUint, // Aggregated signature
   pub challenge: BigUint, // Random challenge for verification
+  pub config: LoquatConfig, // Hash functions shared by every aggregated signature
+}
+
+impl AggregateSignature {
+  // Encoding: version | suite | aggregated sigma | challenge, both 16-byte field elements
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(&self.config).encode_header(&mut out);
+    wire::write_field(&mut out, &self.aggregated_sigma);
+    wire::write_field(&mut out, &self.challenge);
+    out
+  }
+
+  // Parses an aggregate written by to_bytes; components must be reduced modulo the modulus
+  // of the suite's parameter set
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (suite, mut offset) = Suite::decode_header(bytes)?;
+    let aggregated_sigma = wire::field_from_bytes(&wire::read_field(bytes, &mut offset)?);
+    let challenge = wire::field_from_bytes(&wire::read_field(bytes, &mut offset)?);
+    let p = BigUint::from(suite.config().params.modulus());
+    if offset != bytes.len() || aggregated_sigma >= p || challenge >= p {
+      return None;
+    }
+    Some(Self {
+      aggregated_sigma,
+      challenge,
+      config: suite.config(),
+    })
+  }
+
+  // Length of the encoding, without allocating it
+  pub fn size_bytes(&self) -> usize {
+    let mut header = vec![];
+    Suite::from_config(&self.config).encode_header(&mut header);
+    header.len() + 2 * FIELD_BYTES
+  }
+}
+
+// Encoded size of one aggregate next to the signatures it replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregateSizeReport {
+  pub batch_size: usize,
+  pub aggregate_bytes: usize,
+  pub individual_bytes: usize, // Sum of the encodings of the individual signatures
+}
+
+impl AggregateSizeReport {
+  // How many times smaller the aggregate is than the signatures it replaces
+  pub fn compression_ratio(&self) -> f64 {
+    self.individual_bytes as f64 / self.aggregate_bytes as f64
+  }
 }
 
 // Loquat Aggregate Signature Scheme
@@ -23,7 +83,7 @@ pub struct LoquatAggregate;
 
 impl LoquatAggregate {
   // Helper function for modular addition
-  fn mod_add(a: u128, b: u128, modulus: u128) -> u128 {
+  pub(crate) fn mod_add(a: u128, b: u128, modulus: u128) -> u128 {
     let a = a % modulus;
     let b = b % modulus;
     if a > modulus - b {
@@ -34,53 +94,194 @@ impl LoquatAggregate {
   }
 
   // Aggregates multiple Loquat signatures into a single signature
-  pub fn aggregate(signatures: &[LoquatSignature]) -> AggregateSignature {
+  // Returns None if any signature was not produced under the default configuration
+  #[cfg(feature = "std")]
+  pub fn aggregate(signatures: &[LoquatSignature]) -> Option<AggregateSignature> {
+    Self::aggregate_with_config(signatures, &LoquatConfig::default())
+  }
+
+  // Aggregates signatures that were all produced under the given configuration
+  // Returns None if any signature was produced under a different configuration
+  #[cfg(feature = "std")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(signatures = signatures.len())))]
+  pub fn aggregate_with_config(signatures: &[LoquatSignature], config: &LoquatConfig) -> Option<AggregateSignature> {
+    if signatures.iter().any(|sig| sig.config != *config) {
+      return None;
+    }
+
+    let modulus = config.params.modulus();
     let mut aggregated_sigma = BigUint::zero();
-    let mut rng = rand::thread_rng();
-    let challenge = BigUint::from(rng.gen_range(1..P));
+    let mut rng = test_rng::source();
+    let challenge = BigUint::from(rng.gen_range(1..modulus));
 
     for sig in signatures {
       // Convert to u128 and perform safe modular addition
-      let sig_u128 = (sig.sigma.clone() % BigUint::from(P)).to_u128().unwrap_or(0);
-      let agg_u128 = (aggregated_sigma.clone() % BigUint::from(P)).to_u128().unwrap_or(0);
-      let result = Self::mod_add(agg_u128, sig_u128, P);
+      let sig_u128 = config.params.reduce(&sig.sigma);
+      let agg_u128 = config.params.reduce(&aggregated_sigma);
+      let result = Self::mod_add(agg_u128, sig_u128, modulus);
       aggregated_sigma = BigUint::from(result);
     }
 
-    AggregateSignature {
+    Some(AggregateSignature {
       aggregated_sigma,
       challenge,
-    }
+      config: *config,
+    })
+  }
+
+  // Signs and aggregates a fresh batch of each size and reports the encoded sizes
+  #[cfg(feature = "std")]
+  pub fn size_report(batch_sizes: &[usize], config: &LoquatConfig) -> Vec<AggregateSizeReport> {
+    batch_sizes
+      .iter()
+      .filter(|&&batch_size| batch_size > 0)
+      .map(|&batch_size| {
+        let signatures: Vec<LoquatSignature> = (0..batch_size)
+          .map(|i| {
+            let keypair = Loquat::keygen_with_config(config);
+            // keygen has already refused a source whose selftest failed
+            Loquat::sign_with_config(keypair.secret_key, format!("message {}", i).as_bytes(), config).expect("Randomness is healthy")
+          })
+          .collect();
+        let aggregate = Self::aggregate_with_config(&signatures, config).expect("Batch shares one config");
+        AggregateSizeReport {
+          batch_size,
+          aggregate_bytes: aggregate.size_bytes(),
+          individual_bytes: signatures.iter().map(|signature| signature.to_bytes().len()).sum(),
+        }
+      })
+      .collect()
   }
 
   // Verifies an aggregated signature against multiple public keys and messages
   pub fn verify(public_keys: &[Vec<u8>], messages: &[Vec<u8>], agg_sig: &AggregateSignature) -> bool {
+    Self::verify_with_config(public_keys, messages, agg_sig, &LoquatConfig::default())
+  }
+
+  // Verifies an aggregated signature under an expected configuration
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(signatures = public_keys.len()), ret))]
+  pub fn verify_with_config(public_keys: &[Vec<u8>], messages: &[Vec<u8>], agg_sig: &AggregateSignature, config: &LoquatConfig) -> bool {
+    metrics::observe(Operation::AggregateVerify, || Self::check_signature(public_keys, messages, agg_sig, config)).is_ok()
+  }
+
+  fn check_signature(public_keys: &[Vec<u8>], messages: &[Vec<u8>], agg_sig: &AggregateSignature, config: &LoquatConfig) -> Result<(), FailureReason> {
     if public_keys.len() != messages.len() {
-      return false;
+      return Err(FailureReason::LengthMismatch);
+    }
+    if agg_sig.config != *config {
+      return Err(FailureReason::ConfigMismatch);
+    }
+    let modulus = config.params.modulus();
+    if agg_sig.aggregated_sigma >= BigUint::from(modulus) {
+      return Err(FailureReason::NonCanonical);
     }
 
     let mut computed_agg_sigma = BigUint::zero();
+    // Batches of many signers over one message (notarizations) hash each distinct message once
+    let mut message_hashes: HashMap<&[u8], u128> = HashMap::new();
 
     for (pk, msg) in public_keys.iter().zip(messages.iter()) {
-      let hash = Hash::new(HashFunction::Sha3_256).compute(msg);
-      let message_int = BigUint::from_bytes_be(&hash);
-      
-      // Convert to u128 and perform safe modular addition
-      let msg_u128 = (message_int % BigUint::from(P)).to_u128().unwrap_or(0);
-      let agg_u128 = (computed_agg_sigma.clone() % BigUint::from(P)).to_u128().unwrap_or(0);
-      let result = Self::mod_add(agg_u128, msg_u128, P);
+      // Hash the canonical message encoding and perform safe modular addition
+      let msg_u128 = *message_hashes.entry(msg.as_slice()).or_insert_with(|| msg.message_hash_in(config.msg_hash, config.params));
+      let agg_u128 = config.params.reduce(&computed_agg_sigma);
+      let result = Self::mod_add(agg_u128, msg_u128, modulus);
       computed_agg_sigma = BigUint::from(result);
     }
 
     // Compare using modular reduction to ensure consistent comparison
-    let computed_u128 = (computed_agg_sigma % BigUint::from(P)).to_u128().unwrap_or(0);
-    let agg_sig_u128 = (agg_sig.aggregated_sigma.clone() % BigUint::from(P)).to_u128().unwrap_or(0);
+    let computed_u128 = config.params.reduce(&computed_agg_sigma);
+    let agg_sig_u128 = config.params.reduce(&agg_sig.aggregated_sigma);
     
-    computed_u128 == agg_sig_u128
+    if constant_time::u128_eq(computed_u128, agg_sig_u128) {
+      Ok(())
+    } else {
+      Err(FailureReason::InvalidSignature)
+    }
+  }
+}
+
+// Aggregated signature together with the statements it covers
+#[derive(Debug, Clone)]
+pub struct AggregateBatch {
+  pub signature: AggregateSignature,
+  pub public_keys: Vec<Vec<u8>>,
+  pub messages: Vec<Vec<u8>>,
+  pub duplicates_removed: usize, // Repeated (public key, message) statements dropped by finalize
+  pub distinct_messages: usize,
+}
+
+impl AggregateBatch {
+  pub fn verify(&self) -> bool {
+    LoquatAggregate::verify_with_config(&self.public_keys, &self.messages, &self.signature, &self.signature.config)
   }
 }
 
-#[cfg(test)]
+// Collects signed statements for one aggregate
+// A statement signed twice by the same key, e.g. a document notarized again, is aggregated
+// once: finalize drops repeated (public key, message) pairs before combining signatures
+#[cfg(feature = "std")]
+pub struct Aggregator {
+  config: LoquatConfig,
+  entries: Vec<(Vec<u8>, Vec<u8>, LoquatSignature)>,
+}
+
+#[cfg(feature = "std")]
+impl Aggregator {
+  pub fn new(config: &LoquatConfig) -> Self {
+    Self {
+      config: *config,
+      entries: vec![],
+    }
+  }
+
+  // Adds a signed statement; false (and nothing added) if it was signed under another config
+  pub fn add<M: SignableMessage + ?Sized>(&mut self, public_key: &[u8], message: &M, signature: LoquatSignature) -> bool {
+    if signature.config != self.config {
+      return false;
+    }
+    self.entries.push((public_key.to_vec(), message.signing_bytes(), signature));
+    true
+  }
+
+  // Statements added so far, duplicates included
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  // Aggregates the distinct statements in the order they were first added; None if empty
+  pub fn finalize(self) -> Option<AggregateBatch> {
+    if self.entries.is_empty() {
+      return None;
+    }
+    let total = self.entries.len();
+    let mut seen = HashSet::new();
+    let mut public_keys = vec![];
+    let mut messages = vec![];
+    let mut signatures = vec![];
+    for (public_key, message, signature) in self.entries {
+      if seen.insert((public_key.clone(), message.clone())) {
+        public_keys.push(public_key);
+        messages.push(message);
+        signatures.push(signature);
+      }
+    }
+    let distinct_messages = messages.iter().collect::<HashSet<_>>().len();
+
+    Some(AggregateBatch {
+      signature: LoquatAggregate::aggregate_with_config(&signatures, &self.config)?,
+      duplicates_removed: total - public_keys.len(),
+      distinct_messages,
+      public_keys,
+      messages,
+    })
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
   use super::*;
 
@@ -93,11 +294,11 @@ mod tests {
     let message2 = b"Message 2";
 
     // Sign messages using Loquat's signature scheme
-    let sig1 = Loquat::sign(keypair1.secret_key, message1);
-    let sig2 = Loquat::sign(keypair2.secret_key, message2);
+    let sig1 = Loquat::sign(keypair1.secret_key, message1).unwrap();
+    let sig2 = Loquat::sign(keypair2.secret_key, message2).unwrap();
 
     // Aggregate signatures using safe modular arithmetic
-    let aggregate_sig = LoquatAggregate::aggregate(&[sig1.clone(), sig2.clone()]);
+    let aggregate_sig = LoquatAggregate::aggregate(&[sig1.clone(), sig2.clone()]).unwrap();
 
     let public_keys = vec![keypair1.public_key, keypair2.public_key];
     let messages = vec![message1.to_vec(), message2.to_vec()];
@@ -115,11 +316,11 @@ mod tests {
     let message2 = b"Message 2";
 
     // Sign messages using Loquat's signature scheme
-    let sig1 = Loquat::sign(keypair1.secret_key, message1);
-    let sig2 = Loquat::sign(keypair2.secret_key, message2);
+    let sig1 = Loquat::sign(keypair1.secret_key, message1).unwrap();
+    let sig2 = Loquat::sign(keypair2.secret_key, message2).unwrap();
 
     // Aggregate signatures using safe modular arithmetic
-    let aggregate_sig = LoquatAggregate::aggregate(&[sig1.clone(), sig2.clone()]);
+    let aggregate_sig = LoquatAggregate::aggregate(&[sig1.clone(), sig2.clone()]).unwrap();
 
     // Use a tampered message that should fail verification
     let tampered_message = b"Tampered Message";
@@ -133,4 +334,74 @@ mod tests {
     let original_messages = vec![message1.to_vec(), message2.to_vec()];
     assert!(LoquatAggregate::verify(&public_keys, &original_messages, &aggregate_sig));
   }
+
+  #[test]
+  fn test_aggregate_rejects_mixed_configs() {
+    let config = LoquatConfig::uniform(crate::crypto::hash_functions::HashFunction::Shake128);
+    let keypair1 = Loquat::keygen();
+    let keypair2 = Loquat::keygen_with_config(&config);
+
+    let sig1 = Loquat::sign(keypair1.secret_key, b"Message 1").unwrap();
+    let sig2 = Loquat::sign_with_config(keypair2.secret_key, b"Message 2", &config).unwrap();
+
+    assert!(LoquatAggregate::aggregate_with_config(&[sig1.clone(), sig2.clone()], &config).is_none());
+    assert!(LoquatAggregate::aggregate(std::slice::from_ref(&sig2)).is_none());
+    assert!(LoquatAggregate::aggregate_with_config(&[sig2], &config).is_some());
+    assert!(LoquatAggregate::aggregate_with_config(&[sig1], &LoquatConfig::default()).is_some());
+  }
+
+  #[test]
+  fn test_size_bytes_matches_encoding() {
+    let keypair = Loquat::keygen();
+    let signatures: Vec<_> = (0..3).map(|i| Loquat::sign(keypair.secret_key, &[i]).unwrap()).collect();
+    let aggregate = LoquatAggregate::aggregate(&signatures).unwrap();
+    assert_eq!(aggregate.size_bytes(), aggregate.to_bytes().len());
+
+    let decoded = AggregateSignature::from_bytes(&aggregate.to_bytes()).unwrap();
+    assert_eq!(decoded.to_bytes(), aggregate.to_bytes());
+    assert!(AggregateSignature::from_bytes(&aggregate.to_bytes()[1..]).is_none());
+  }
+
+  #[test]
+  fn test_aggregate_size_grows_sublinearly() {
+    let reports = LoquatAggregate::size_report(&[1, 16, 256], &LoquatConfig::default());
+    assert_eq!(reports.len(), 3);
+    for report in &reports {
+      assert!(report.aggregate_bytes <= report.individual_bytes);
+    }
+
+    // Sixteen times the signatures must cost well under sixteen times the bytes, whatever the
+    // aggregation backend, and the saving must improve with the batch size
+    for pair in reports.windows(2) {
+      let growth = pair[1].batch_size / pair[0].batch_size;
+      assert!(pair[1].aggregate_bytes * 4 <= pair[0].aggregate_bytes * growth);
+      assert!(pair[1].compression_ratio() > pair[0].compression_ratio());
+    }
+  }
+
+  #[test]
+  fn test_aggregator_deduplicates_statements() {
+    let config = LoquatConfig::default();
+    let notaries: Vec<LoquatKeyPair> = (0..3).map(|_| Loquat::keygen()).collect();
+    let mut aggregator = Aggregator::new(&config);
+    for notary in &notaries {
+      let signature = Loquat::sign(notary.secret_key, b"document").unwrap();
+      assert!(aggregator.add(&notary.public_key, b"document", signature));
+    }
+    // The first notary stamps the same document again
+    assert!(aggregator.add(&notaries[0].public_key, b"document", Loquat::sign(notaries[0].secret_key, b"document").unwrap()));
+    let other = Loquat::sign_with_config(notaries[0].secret_key, b"document", &LoquatConfig::uniform(crate::crypto::hash_functions::HashFunction::Shake128)).unwrap();
+    assert!(!aggregator.add(&notaries[0].public_key, b"document", other));
+    assert_eq!(aggregator.len(), 4);
+
+    let batch = aggregator.finalize().unwrap();
+    assert_eq!(batch.duplicates_removed, 1);
+    assert_eq!(batch.public_keys.len(), 3);
+    assert_eq!(batch.distinct_messages, 1);
+
+    // The aggregate covers each distinct statement exactly once
+    let signatures: Vec<_> = notaries.iter().map(|notary| Loquat::sign(notary.secret_key, b"document").unwrap()).collect();
+    assert_eq!(batch.signature.aggregated_sigma, LoquatAggregate::aggregate(&signatures).unwrap().aggregated_sigma);
+    assert!(Aggregator::new(&config).finalize().is_none());
+  }
 }
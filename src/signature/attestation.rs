@@ -0,0 +1,135 @@
+// Compact attestations
+// A LoquatSignature is sigma together with the Merkle root over (sigma, h), where h is the
+// message hash. Verification recomputes that root from the recovered key and the message, so
+// the root carries nothing a verifier holding the message cannot rederive. CompactAttestation
+// drops it and stores sigma at the byte width of the parameter set's modulus, which is what
+// credentials that keep many signatures should store:
+//   version | suite | sigma (big-endian, 16 bytes for L128, 8 for M61)
+// i.e. 18 bytes for a standard-suite L128 signature against 50 for LoquatSignature::to_bytes.
+// The signatures of this crate have no IOP transcript, so there is nothing for a recursive
+// proof to replace; circuit::signature proves knowledge of sigma for contexts that must not
+// reveal it at all.
+
+use crate::signature::config::LoquatConfig;
+use crate::signature::error::VerifyError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::signature::params::ParamsHandle;
+use crate::signature::suite::Suite;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactAttestation {
+  pub sigma: u128,
+  pub config: LoquatConfig,
+}
+
+// Bytes of a canonical element of the parameter set's field
+fn sigma_bytes(params: ParamsHandle) -> usize {
+  (128 - params.modulus().leading_zeros() as usize).div_ceil(8)
+}
+
+impl LoquatSignature {
+  // Drops the Merkle root; None if the signature is not over the given parameter set or is
+  // not canonical
+  pub fn compress(&self, params: &ParamsHandle) -> Option<CompactAttestation> {
+    if self.config.params != *params || !self.is_canonical() {
+      return None;
+    }
+    Some(CompactAttestation {
+      sigma: self.sigma.to_u128()?,
+      config: self.config,
+    })
+  }
+}
+
+impl CompactAttestation {
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(&self.config).encode_header(&mut out);
+    out.extend_from_slice(&self.sigma.to_be_bytes()[16 - sigma_bytes(self.config.params)..]);
+    out
+  }
+
+  // Parses an attestation written by to_bytes; sigma must be reduced and nothing may follow
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (suite, offset) = Suite::decode_header(bytes)?;
+    let config = suite.config();
+    let sigma = bytes.get(offset..)?;
+    if sigma.len() != sigma_bytes(config.params) {
+      return None;
+    }
+    let mut word = [0u8; 16];
+    word[16 - sigma.len()..].copy_from_slice(sigma);
+    let sigma = u128::from_be_bytes(word);
+    config.params.contains(sigma).then_some(Self { sigma, config })
+  }
+
+  // The full signature on message, with the Merkle root recomputed
+  pub fn decompress<M: SignableMessage + ?Sized>(&self, message: &M) -> LoquatSignature {
+    let sigma = BigUint::from(self.sigma);
+    let message_u128 = message.message_hash_in(self.config.msg_hash, self.config.params);
+    LoquatSignature {
+      merkle_root: Loquat::commitment_root(&sigma, message_u128, &self.config),
+      sigma,
+      config: self.config,
+    }
+  }
+
+  // Verifies the attestation over message under the default configuration
+  pub fn verify<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M) -> bool {
+    self.try_verify_with_config(pk, message, &LoquatConfig::default()).is_ok()
+  }
+
+  // Loquat::try_verify_with_config of the decompressed signature
+  pub fn try_verify_with_config<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, config: &LoquatConfig) -> Result<(), VerifyError> {
+    Loquat::try_verify_with_config(pk, message, &self.decompress(message), config)
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_compress_round_trip() {
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"attested").unwrap();
+    let attestation = signature.compress(&ParamsHandle::L128).unwrap();
+    let bytes = attestation.to_bytes();
+    assert_eq!(bytes.len(), 18);
+    assert!(bytes.len() < signature.to_bytes().len());
+    assert_eq!(CompactAttestation::from_bytes(&bytes), Some(attestation));
+
+    assert_eq!(attestation.decompress(b"attested"), signature);
+    assert!(attestation.verify(&keypair.public_key, b"attested"));
+    assert!(!attestation.verify(&keypair.public_key, b"other message"));
+    assert!(signature.compress(&ParamsHandle::M61).is_none());
+  }
+
+  #[test]
+  fn test_parameter_set_width() {
+    let config = LoquatConfig::default().with_params(ParamsHandle::M61);
+    let keypair = Loquat::keygen_with_config(&config);
+    let attestation = Loquat::sign_with_config(keypair.secret_key, b"narrow", &config).unwrap().compress(&ParamsHandle::M61).unwrap();
+    let bytes = attestation.to_bytes();
+    assert_eq!(bytes.len(), 6 + 8);
+    assert_eq!(CompactAttestation::from_bytes(&bytes), Some(attestation));
+    assert_eq!(attestation.try_verify_with_config(&keypair.public_key, b"narrow", &config), Ok(()));
+    assert_eq!(attestation.try_verify_with_config(&keypair.public_key, b"narrow", &LoquatConfig::default()), Err(VerifyError::SuiteMismatch));
+  }
+
+  #[test]
+  fn test_rejects_non_canonical_encodings() {
+    let attestation = Loquat::sign(Loquat::keygen().secret_key, b"attested").unwrap().compress(&ParamsHandle::L128).unwrap();
+    let bytes = attestation.to_bytes();
+    assert!(CompactAttestation::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_none());
+    assert!(CompactAttestation::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+
+    // sigma + P is not a field element
+    let mut unreduced = bytes[..2].to_vec();
+    unreduced.extend_from_slice(&(ParamsHandle::L128.modulus() + 1).to_be_bytes());
+    assert!(CompactAttestation::from_bytes(&unreduced).is_none());
+  }
+}
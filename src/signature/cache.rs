@@ -0,0 +1,233 @@
+// Memoized Loquat verification for repeated (public key, message, signature) triples
+// Gateways that retry requests verify the same signature many times. The cache keys each
+// outcome by a SHA3 digest over the configuration, the public key, the signature encoding
+// and the message digest, so a hit costs one hash of the message instead of a full
+// verification, and holds at most `capacity` outcomes, evicting the least recently used.
+// Verification is deterministic, so a cached outcome is exactly what verifying again would
+// return; rejections are only cached when `cache_failures` is set, since a flood of distinct
+// forgeries would otherwise push out the valid entries.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::signature::suite::Suite;
+use crate::utils::encoding::Encoding;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+// Domain separation of cache keys
+const CACHE_DOMAIN: &[u8] = b"loquat-verification-cache";
+
+type CacheKey = Vec<u8>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+  pub capacity: usize, // Outcomes kept; 0 disables caching
+  pub cache_failures: bool, // Whether rejected signatures are remembered too
+}
+
+impl Default for CacheConfig {
+  fn default() -> Self {
+    Self {
+      capacity: 4096,
+      cache_failures: false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+}
+
+// Least-recently-used map from keys to outcomes; `order` indexes keys by last use
+#[derive(Debug, Default)]
+struct Lru {
+  entries: HashMap<CacheKey, (bool, u64)>,
+  order: BTreeMap<u64, CacheKey>,
+  clock: u64,
+  stats: CacheStats,
+}
+
+impl Lru {
+  fn get(&mut self, key: &CacheKey) -> Option<bool> {
+    let clock = self.clock + 1;
+    let Some((valid, last_used)) = self.entries.get_mut(key) else {
+      self.stats.misses += 1;
+      return None;
+    };
+    self.order.remove(last_used);
+    self.order.insert(clock, key.clone());
+    *last_used = clock;
+    self.clock = clock;
+    self.stats.hits += 1;
+    Some(*valid)
+  }
+
+  fn insert(&mut self, key: CacheKey, valid: bool, capacity: usize) {
+    if capacity == 0 {
+      return;
+    }
+    self.clock += 1;
+    if let Some((_, last_used)) = self.entries.insert(key.clone(), (valid, self.clock)) {
+      self.order.remove(&last_used);
+    }
+    self.order.insert(self.clock, key);
+    self.shrink_to(capacity);
+  }
+
+  fn remove(&mut self, key: &CacheKey) -> bool {
+    match self.entries.remove(key) {
+      Some((_, last_used)) => self.order.remove(&last_used).is_some(),
+      None => false,
+    }
+  }
+
+  fn shrink_to(&mut self, capacity: usize) {
+    while self.entries.len() > capacity {
+      let (_, oldest) = self.order.pop_first().expect("Every entry is ordered");
+      self.entries.remove(&oldest);
+      self.stats.evictions += 1;
+    }
+  }
+}
+
+// Verifier wrapper remembering recent outcomes; shareable across threads
+#[derive(Debug)]
+pub struct CachedVerifier {
+  config: LoquatConfig,
+  cache_config: Mutex<CacheConfig>,
+  lru: Mutex<Lru>,
+}
+
+impl CachedVerifier {
+  pub fn new(config: LoquatConfig, cache_config: CacheConfig) -> Self {
+    Self {
+      config,
+      cache_config: Mutex::new(cache_config),
+      lru: Mutex::new(Lru::default()),
+    }
+  }
+
+  // Default configuration with a cache of the given capacity
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self::new(LoquatConfig::default(), CacheConfig { capacity, ..CacheConfig::default() })
+  }
+
+  // Same outcome as Loquat::verify_with_config under this verifier's configuration
+  pub fn verify<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, signature: &LoquatSignature) -> bool {
+    let key = self.key(pk, message, signature);
+    if let Some(valid) = self.lru().get(&key) {
+      return valid;
+    }
+    let valid = Loquat::verify_with_config(pk, message, signature, &self.config);
+    let cache_config = self.cache_config();
+    if valid || cache_config.cache_failures {
+      self.lru().insert(key, valid, cache_config.capacity);
+    }
+    valid
+  }
+
+  // Drops the outcome for one triple, e.g. after revoking the key it was checked against;
+  // returns whether one was cached
+  pub fn invalidate<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, signature: &LoquatSignature) -> bool {
+    let key = self.key(pk, message, signature);
+    self.lru().remove(&key)
+  }
+
+  pub fn clear(&self) {
+    let mut lru = self.lru();
+    lru.entries.clear();
+    lru.order.clear();
+  }
+
+  // Changes the limits; shrinking the capacity evicts the least recently used outcomes
+  pub fn set_cache_config(&self, cache_config: CacheConfig) {
+    *self.cache_config.lock().expect("Cache config lock poisoned") = cache_config;
+    self.lru().shrink_to(cache_config.capacity);
+  }
+
+  pub fn cache_config(&self) -> CacheConfig {
+    *self.cache_config.lock().expect("Cache config lock poisoned")
+  }
+
+  pub fn len(&self) -> usize {
+    self.lru().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.lru().stats
+  }
+
+  fn lru(&self) -> MutexGuard<'_, Lru> {
+    self.lru.lock().expect("Verification cache lock poisoned")
+  }
+
+  // Digest of configuration | public key | signature | message digest
+  fn key<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, signature: &LoquatSignature) -> CacheKey {
+    let hash = Hash::new(HashFunction::Sha3_256);
+    let mut input = CACHE_DOMAIN.to_vec();
+    Suite::from_config(&self.config).encode_header(&mut input);
+    Encoding::write_bytes(&mut input, pk);
+    Encoding::write_bytes(&mut input, &signature.to_bytes());
+    Encoding::write_bytes(&mut input, &hash.compute(&message.signing_bytes()));
+    hash.compute(&input)
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_repeated_verification_hits_cache() {
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"request").unwrap();
+    let verifier = CachedVerifier::with_capacity(8);
+
+    assert!(verifier.verify(&keypair.public_key, b"request", &signature));
+    assert!(verifier.verify(&keypair.public_key, b"request", &signature));
+    assert_eq!(verifier.stats(), CacheStats { hits: 1, misses: 1, evictions: 0 });
+
+    // Rejections are not remembered by default, and never answer for a valid triple
+    assert!(!verifier.verify(&keypair.public_key, b"other", &signature));
+    assert_eq!(verifier.len(), 1);
+    verifier.set_cache_config(CacheConfig { capacity: 8, cache_failures: true });
+    assert!(!verifier.verify(&keypair.public_key, b"other", &signature));
+    assert!(!verifier.verify(&keypair.public_key, b"other", &signature));
+    assert_eq!(verifier.len(), 2);
+
+    assert!(verifier.invalidate(&keypair.public_key, b"request", &signature));
+    assert!(!verifier.invalidate(&keypair.public_key, b"request", &signature));
+    verifier.clear();
+    assert!(verifier.is_empty());
+  }
+
+  #[test]
+  fn test_least_recently_used_evicted() {
+    let keypair = Loquat::keygen();
+    let signatures: Vec<_> = (0..3u8).map(|i| Loquat::sign(keypair.secret_key, &[i]).unwrap()).collect();
+    let verifier = CachedVerifier::with_capacity(2);
+    assert!(verifier.verify(&keypair.public_key, &[0u8], &signatures[0]));
+    assert!(verifier.verify(&keypair.public_key, &[1u8], &signatures[1]));
+    // Touching the first entry makes the second the eviction candidate
+    assert!(verifier.verify(&keypair.public_key, &[0u8], &signatures[0]));
+    assert!(verifier.verify(&keypair.public_key, &[2u8], &signatures[2]));
+
+    assert_eq!(verifier.stats().evictions, 1);
+    assert!(verifier.invalidate(&keypair.public_key, &[0u8], &signatures[0]));
+    assert!(!verifier.invalidate(&keypair.public_key, &[1u8], &signatures[1]));
+
+    verifier.set_cache_config(CacheConfig { capacity: 0, cache_failures: false });
+    assert!(verifier.is_empty());
+    assert!(verifier.verify(&keypair.public_key, &[1u8], &signatures[1]));
+    assert!(verifier.is_empty());
+  }
+}
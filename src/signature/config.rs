@@ -0,0 +1,85 @@
+// Hash function selection for the Loquat signature schemes
+// Each scheme records the configuration it was produced under so that
+// verification under a different configuration fails instead of silently
+// comparing digests computed with different algorithms.
+// The configuration also carries the field parameter set (params::ParamsHandle).
+
+use crate::crypto::hash_functions::{HashFunction, HashTarget, HashUseCase};
+use crate::signature::params::ParamsHandle;
+
+// Hash function and parameter set choices used by sign/verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoquatConfig {
+  pub msg_hash: HashFunction, // Hashes the message before reduction into the field
+  pub merkle_hash: HashFunction, // Compresses Merkle tree nodes
+  pub transcript_hash: HashFunction, // Derives key commitments and challenges
+  pub params: ParamsHandle, // Field keys, signatures and message hashes live in
+}
+
+impl LoquatConfig {
+  // Creates a configuration with explicit hash function choices over the default parameter set
+  pub fn new(msg_hash: HashFunction, merkle_hash: HashFunction, transcript_hash: HashFunction) -> Self {
+    Self {
+      msg_hash,
+      merkle_hash,
+      transcript_hash,
+      params: ParamsHandle::L128,
+    }
+  }
+
+  // Uses a single hash function for every role
+  pub fn uniform(hash_function: HashFunction) -> Self {
+    Self::new(hash_function, hash_function, hash_function)
+  }
+
+  // HashFunction::recommended for each role
+  pub fn recommended(target: HashTarget) -> Self {
+    Self::new(
+      HashFunction::recommended(HashUseCase::Message, target),
+      HashFunction::recommended(HashUseCase::Merkle, target),
+      HashFunction::recommended(HashUseCase::Transcript, target),
+    )
+  }
+
+  // The same hash functions over another parameter set
+  pub fn with_params(self, params: ParamsHandle) -> Self {
+    Self { params, ..self }
+  }
+}
+
+impl Default for LoquatConfig {
+  // SHA3-256 everywhere, matching the original hard-coded behaviour
+  fn default() -> Self {
+    Self::uniform(HashFunction::Sha3_256)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_config() {
+    let config = LoquatConfig::default();
+    assert_eq!(config.msg_hash, HashFunction::Sha3_256);
+    assert_eq!(config.merkle_hash, HashFunction::Sha3_256);
+    assert_eq!(config.transcript_hash, HashFunction::Sha3_256);
+    assert_eq!(config.params, ParamsHandle::L128);
+  }
+
+  #[test]
+  fn test_config_equality() {
+    let a = LoquatConfig::new(HashFunction::Sha3_256, HashFunction::Keccak256, HashFunction::Shake128);
+    let b = LoquatConfig::new(HashFunction::Sha3_256, HashFunction::Keccak256, HashFunction::Shake128);
+    assert_eq!(a, b);
+    assert_ne!(a, LoquatConfig::default());
+    assert_ne!(a, a.with_params(ParamsHandle::M61));
+  }
+
+  #[test]
+  fn test_recommended_configs() {
+    assert_eq!(LoquatConfig::recommended(HashTarget::Native), LoquatConfig::default());
+    let evm = LoquatConfig::recommended(HashTarget::Evm);
+    assert_eq!((evm.msg_hash, evm.merkle_hash), (HashFunction::Keccak256, HashFunction::Keccak256));
+  }
+}
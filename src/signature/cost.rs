@@ -0,0 +1,111 @@
+// Verifier-side cost model
+// Counts the work Loquat::verify performs for a signature, so integrators can size batch
+// limits and pick parameter sets for their verification environment: hash invocations and the
+// bytes they absorb, and multiplications and additions modulo P. The Legendre symbol by Euler's
+// criterion dominates the field work. Signatures under the Keccak256 suite also carry the gas
+// of the reference EVM verifier.
+
+use crate::crypto::hash_functions::HashFunction;
+use crate::export::evm::{estimate_gas_for, EvmSignature, GasEstimate};
+use crate::signature::loquat::LoquatSignature;
+use crate::signature::message::SignableMessage;
+use crate::utils::field_operations::P;
+use std::ops::Add;
+
+// Public key candidates hashed by verification
+const PUBLIC_KEY_HASHES: u64 = 2;
+
+// Bytes of a hashed secret key candidate
+const SECRET_KEY_BYTES: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VerificationCost {
+  pub hash_invocations: u64,
+  pub hashed_bytes: u64, // Upper bound: variable-length integers are counted at full width
+  pub field_multiplications: u64, // Squarings included
+  pub field_additions: u64, // Subtractions included
+  pub gas: Option<GasEstimate>, // Keccak256 suite only
+}
+
+impl Add for VerificationCost {
+  type Output = Self;
+
+  // Cost of verifying both; gas is only summed when both have it
+  fn add(self, other: Self) -> Self {
+    Self {
+      hash_invocations: self.hash_invocations + other.hash_invocations,
+      hashed_bytes: self.hashed_bytes + other.hashed_bytes,
+      field_multiplications: self.field_multiplications + other.field_multiplications,
+      field_additions: self.field_additions + other.field_additions,
+      gas: match (self.gas, other.gas) {
+        (Some(a), Some(b)) => Some(GasEstimate {
+          intrinsic: a.intrinsic.max(b.intrinsic),
+          calldata: a.calldata + b.calldata,
+          execution: a.execution + b.execution,
+        }),
+        _ => None,
+      },
+    }
+  }
+}
+
+impl VerificationCost {
+  // Cost of verifying `count` signatures of this cost one after the other
+  pub fn times(&self, count: u64) -> Self {
+    (0..count).fold(Self::default(), |acc, _| acc + *self)
+  }
+}
+
+// Multiplications of square-and-multiply for a fixed exponent
+fn exponentiation_multiplications(exponent: u128) -> u64 {
+  (128 - exponent.leading_zeros() + exponent.count_ones()) as u64
+}
+
+impl LoquatSignature {
+  // Cost of verifying this signature over message
+  pub fn verification_cost<M: SignableMessage + ?Sized>(&self, message: &M) -> VerificationCost {
+    let message_bytes = message.signing_bytes();
+    // Merkle node input: two 32-byte words under Keccak256, otherwise two integers below P
+    let node_bytes = if self.config.merkle_hash == HashFunction::Keccak256 { 64 } else { 32 };
+    VerificationCost {
+      // Message hash, both public key candidates and the Merkle root over (sigma, message hash)
+      hash_invocations: 1 + PUBLIC_KEY_HASHES + 1,
+      hashed_bytes: message_bytes.len() as u64 + PUBLIC_KEY_HASHES * SECRET_KEY_BYTES + node_bytes,
+      field_multiplications: exponentiation_multiplications((P - 1) / 2),
+      // Both secret key candidates, the PRF input and the recomputed sigma
+      field_additions: 4,
+      gas: EvmSignature::from_loquat(self).map(|signature| estimate_gas_for(&message_bytes, &signature)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::export::evm::{EvmLoquat, EvmProof};
+  use crate::signature::loquat::Loquat;
+
+  #[test]
+  fn test_cost_counts() {
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"message").unwrap();
+    let cost = signature.verification_cost(b"message");
+    assert_eq!(cost.hash_invocations, 4);
+    // (p - 1) / 2 = 2^126 - 1: 126 squarings and 126 multiplications
+    assert_eq!(cost.field_multiplications, 252);
+    assert!(cost.gas.is_none());
+
+    let batch = cost.times(10);
+    assert_eq!(batch.hash_invocations, 40);
+    assert_eq!(batch.hashed_bytes, 10 * cost.hashed_bytes);
+  }
+
+  #[test]
+  fn test_gas_matches_evm_estimate() {
+    let keypair = EvmLoquat::keygen();
+    let signature = Loquat::sign_with_config(keypair.secret_key, b"message", &EvmLoquat::config()).unwrap();
+    let gas = signature.verification_cost(b"message").gas.unwrap();
+    let proof = EvmProof::new([0xff; 32], b"message", EvmSignature::from_loquat(&signature).unwrap());
+    assert_eq!(gas, proof.estimate_gas());
+  }
+}
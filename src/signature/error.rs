@@ -0,0 +1,83 @@
+// Verification errors
+// The bool-returning verifiers answer "valid or not". Their try_ counterparts return the
+// reason, split into input that could not be a valid proof for this verifier (a client bug or
+// a version skew, worth a helpful error message), cryptographic checks that failed on
+// well-formed input (a tampered or forged proof, worth logging as such) and credentials
+// that verified but are no longer acceptable.
+
+use crate::utils::metrics::FailureReason;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerifyError {
+  BadEncoding, // A key, signature or proof that does not decode
+  SuiteMismatch, // Made under other hash functions, parameters or suite than the verifier expects
+  NonCanonical, // A component outside its canonical range
+  LengthMismatch, // Key and message lists of different lengths
+  UnsupportedProofType, // A proof type this verifier does not handle
+  MissingProof, // Nothing to verify
+  IssuerMismatch, // The signing key is not controlled by the credential's issuer
+  UnknownKey, // The verification method does not resolve to a key
+  KeyUsage, // The key's document does not let it sign this kind of object
+  KeyMismatch, // The signature does not open to the public key
+  MerkleMismatch, // The key matches but the Merkle commitment does not
+  SumcheckFailed, // A sumcheck or low-degree test rejected
+  InvalidSignature, // Any other failed cryptographic check
+  Expired,
+  Revoked, // Revoked, or its status list is unavailable
+}
+
+impl VerifyError {
+  pub fn name(&self) -> &'static str {
+    match self {
+      VerifyError::BadEncoding => "bad_encoding",
+      VerifyError::SuiteMismatch => "suite_mismatch",
+      VerifyError::NonCanonical => "non_canonical",
+      VerifyError::LengthMismatch => "length_mismatch",
+      VerifyError::UnsupportedProofType => "unsupported_proof_type",
+      VerifyError::MissingProof => "missing_proof",
+      VerifyError::IssuerMismatch => "issuer_mismatch",
+      VerifyError::UnknownKey => "unknown_key",
+      VerifyError::KeyUsage => "key_usage",
+      VerifyError::KeyMismatch => "key_mismatch",
+      VerifyError::MerkleMismatch => "merkle_mismatch",
+      VerifyError::SumcheckFailed => "sumcheck_failed",
+      VerifyError::InvalidSignature => "invalid_signature",
+      VerifyError::Expired => "expired",
+      VerifyError::Revoked => "revoked",
+    }
+  }
+
+  // Whether the input could not have been accepted by this verifier whatever its signer did
+  pub fn is_malformed_input(&self) -> bool {
+    matches!(
+      self,
+      VerifyError::BadEncoding
+        | VerifyError::SuiteMismatch
+        | VerifyError::NonCanonical
+        | VerifyError::LengthMismatch
+        | VerifyError::UnsupportedProofType
+        | VerifyError::MissingProof
+    )
+  }
+
+  // Whether a well-formed proof failed its cryptographic checks: tampering or forgery
+  pub fn is_cryptographic_failure(&self) -> bool {
+    matches!(
+      self,
+      VerifyError::KeyMismatch | VerifyError::MerkleMismatch | VerifyError::SumcheckFailed | VerifyError::InvalidSignature
+    )
+  }
+}
+
+impl From<FailureReason> for VerifyError {
+  fn from(reason: FailureReason) -> Self {
+    match reason {
+      FailureReason::ConfigMismatch => VerifyError::SuiteMismatch,
+      FailureReason::NonCanonical => VerifyError::NonCanonical,
+      FailureReason::KeyMismatch => VerifyError::KeyMismatch,
+      FailureReason::CommitmentMismatch => VerifyError::MerkleMismatch,
+      FailureReason::LengthMismatch => VerifyError::LengthMismatch,
+      FailureReason::InvalidSignature => VerifyError::InvalidSignature,
+    }
+  }
+}
@@ -0,0 +1,357 @@
+// Hardened Loquat verification for untrusted inputs
+// Loquat::verify_encoded parses sigma and the Merkle root into BigUints and relies on
+// `expect` for conversions. This path is meant for keys and signatures received from the
+// network: every input is size-checked before it is parsed, only the canonical encoding of
+// each component is accepted, nothing is allocated in proportion to attacker-controlled
+// lengths, and digest comparisons do not exit early on the first differing byte.
+
+use crate::compact::field;
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::merkle::MerkleTree;
+use crate::signature::aggregate::{AggregateSignature, LoquatAggregate};
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::Loquat;
+use crate::signature::message::SignableMessage;
+use crate::signature::params::ParamsHandle;
+#[cfg(feature = "experimental")]
+use crate::signature::ring::Ring;
+#[cfg(feature = "experimental")]
+use crate::signature::ring_signature::{LoquatRingSignature, RingSignature};
+use crate::signature::suite::Suite;
+use crate::utils::constant_time;
+use crate::utils::field_operations::P;
+use crate::utils::wire::{self, DIGEST_BYTES, FIELD_BYTES};
+use num_bigint::BigUint;
+
+// Longest suite header this path accepts (custom suites carry three hash identifiers)
+const MAX_HEADER_BYTES: usize = 5;
+
+// Largest well-formed encodings of a public key and a signature
+pub const MAX_PUBLIC_KEY_BYTES: usize = MAX_HEADER_BYTES + DIGEST_BYTES;
+pub const MAX_SIGNATURE_BYTES: usize = MAX_HEADER_BYTES + FIELD_BYTES + DIGEST_BYTES;
+
+// Caps applied to untrusted inputs before any parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UntrustedInputLimits {
+  pub max_message_bytes: usize,
+}
+
+impl Default for UntrustedInputLimits {
+  // 1 MiB messages; keys and signatures are capped by their fixed maximum sizes
+  fn default() -> Self {
+    Self { max_message_bytes: 1 << 20 }
+  }
+}
+
+// Caps on the size of verification inputs, checked before they are parsed, hashed or
+// allocated for, so a server cannot be made to build a Merkle tree over a gigantic ring or
+// walk an arbitrarily long proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifierLimits {
+  pub max_sig_bytes: usize, // Encoded signature length
+  pub max_ring_size: usize, // Public keys in a ring
+  pub max_batch: usize, // Signatures in an aggregate
+  pub max_proof_depth: usize, // Siblings in a Merkle authentication path
+}
+
+impl Default for VerifierLimits {
+  // Well-formed signatures, rings of 2^16 keys, batches of 4096 and trees of 2^64 leaves
+  fn default() -> Self {
+    Self {
+      max_sig_bytes: MAX_SIGNATURE_BYTES,
+      max_ring_size: 1 << 16,
+      max_batch: 1 << 12,
+      max_proof_depth: 64,
+    }
+  }
+}
+
+impl VerifierLimits {
+  // Loquat::verify_encoded for a signature within the size limit
+  pub fn verify_encoded<M: SignableMessage + ?Sized>(&self, pk_bytes: &[u8], message: &M, sig_bytes: &[u8]) -> bool {
+    sig_bytes.len() <= self.max_sig_bytes && pk_bytes.len() <= MAX_PUBLIC_KEY_BYTES && Loquat::verify_encoded(pk_bytes, message, sig_bytes)
+  }
+
+  // Ring verification for rings and membership paths within the limits
+  #[cfg(feature = "experimental")]
+  pub fn verify_ring<M: SignableMessage + ?Sized>(&self, public_keys: &[Vec<u8>], message: &M, ring_sig: &RingSignature, config: &LoquatConfig) -> bool {
+    public_keys.len() <= self.max_ring_size
+      && ring_sig.membership.len() <= self.max_proof_depth
+      && LoquatRingSignature::verify_with_config(public_keys, message, ring_sig, config)
+  }
+
+  // verify_ring over a serialized ring descriptor (ring::Ring) and ring signature; the encoded
+  // lengths, the declared member count and the declared path depth are checked before either
+  // is decoded, so no member list, path or tree is built for inputs over the limits
+  #[cfg(feature = "experimental")]
+  pub fn verify_ring_untrusted<M: SignableMessage + ?Sized>(&self, ring_bytes: &[u8], message: &M, sig_bytes: &[u8], config: &LoquatConfig) -> bool {
+    let max_ring_bytes = self.max_ring_size.saturating_mul(DIGEST_BYTES).saturating_add(MAX_HEADER_BYTES + 4);
+    let max_ring_sig_bytes = self.max_proof_depth.saturating_mul(1 + DIGEST_BYTES).saturating_add(MAX_HEADER_BYTES + 2 * FIELD_BYTES + DIGEST_BYTES + 1);
+    if ring_bytes.len() > max_ring_bytes || sig_bytes.len() > max_ring_sig_bytes {
+      return false;
+    }
+    match (declared_ring_size(ring_bytes), declared_path_depth(sig_bytes)) {
+      (Some(size), Some(depth)) if size <= self.max_ring_size && depth <= self.max_proof_depth => {}
+      _ => return false,
+    }
+    match (Ring::from_bytes(ring_bytes), RingSignature::from_bytes(sig_bytes)) {
+      (Some(ring), Some(ring_sig)) => ring.config() == config && ring.verify(message, &ring_sig),
+      _ => false,
+    }
+  }
+
+  // Aggregate verification for batches within the size limit
+  pub fn verify_aggregate(&self, public_keys: &[Vec<u8>], messages: &[Vec<u8>], agg_sig: &AggregateSignature, config: &LoquatConfig) -> bool {
+    public_keys.len() <= self.max_batch
+      && messages.len() <= self.max_batch
+      && LoquatAggregate::verify_with_config(public_keys, messages, agg_sig, config)
+  }
+
+  // verify_aggregate over a serialized aggregate; the batch size and the encoded length are
+  // checked before it is decoded
+  pub fn verify_aggregate_untrusted(&self, public_keys: &[Vec<u8>], messages: &[Vec<u8>], agg_bytes: &[u8], config: &LoquatConfig) -> bool {
+    public_keys.len() <= self.max_batch
+      && messages.len() <= self.max_batch
+      && agg_bytes.len() <= MAX_HEADER_BYTES + 2 * FIELD_BYTES
+      && AggregateSignature::from_bytes(agg_bytes).is_some_and(|agg_sig| LoquatAggregate::verify_with_config(public_keys, messages, &agg_sig, config))
+  }
+
+  // Merkle path verification for paths within the depth limit
+  pub fn verify_merkle_proof(&self, root: &BigUint, leaf: &BigUint, proof: &[(BigUint, bool)], hash_function: &HashFunction) -> bool {
+    proof.len() <= self.max_proof_depth && MerkleTree::verify_proof(root, leaf, proof, hash_function)
+  }
+}
+
+// Member count a ring descriptor declares, read without decoding its members
+#[cfg(feature = "experimental")]
+fn declared_ring_size(ring_bytes: &[u8]) -> Option<usize> {
+  let (_, offset) = Suite::decode_header(ring_bytes)?;
+  let count = ring_bytes.get(offset..offset + 4)?;
+  Some(u32::from_be_bytes([count[0], count[1], count[2], count[3]]) as usize)
+}
+
+// Depth a ring signature declares for its membership path, read without decoding the path
+#[cfg(feature = "experimental")]
+fn declared_path_depth(sig_bytes: &[u8]) -> Option<usize> {
+  let (_, offset) = Suite::decode_header(sig_bytes)?;
+  sig_bytes.get(offset + 2 * FIELD_BYTES + DIGEST_BYTES).map(|depth| *depth as usize)
+}
+
+// Parses the header and the digest of a public key
+fn decode_public_key(pk_bytes: &[u8]) -> Option<(Suite, &[u8])> {
+  if pk_bytes.len() > MAX_PUBLIC_KEY_BYTES {
+    return None;
+  }
+  let (suite, offset) = Suite::decode_header(pk_bytes)?;
+  let pk = &pk_bytes[offset..];
+  if pk.len() != DIGEST_BYTES {
+    return None;
+  }
+  Some((suite, pk))
+}
+
+// Parses a signature into its suite, canonical sigma and Merkle root bytes
+// The constant-time field arithmetic is that of 2^127 - 1, so only L128 suites are accepted
+fn decode_signature(sig_bytes: &[u8]) -> Option<(Suite, u128, wire::DigestBytes)> {
+  if sig_bytes.len() > MAX_SIGNATURE_BYTES {
+    return None;
+  }
+  let (suite, mut offset) = Suite::decode_header(sig_bytes)?;
+  if suite.config().params != ParamsHandle::L128 {
+    return None;
+  }
+  let sigma = u128::from_be_bytes(wire::read_field(sig_bytes, &mut offset)?);
+  let root = wire::read_digest(sig_bytes, &mut offset)?;
+  if offset != sig_bytes.len() || sigma >= P {
+    return None;
+  }
+  Some((suite, sigma, root))
+}
+
+// Verifies a serialized signature under a serialized public key, treating both as untrusted
+// The message is its canonical signing encoding (SignableMessage::signing_bytes)
+pub fn verify_untrusted(pk_bytes: &[u8], message: &[u8], sig_bytes: &[u8], limits: &UntrustedInputLimits) -> bool {
+  if message.len() > limits.max_message_bytes {
+    return false;
+  }
+  let (pk_suite, pk) = match decode_public_key(pk_bytes) {
+    Some(decoded) => decoded,
+    None => return false,
+  };
+  let (suite, sigma, root) = match decode_signature(sig_bytes) {
+    Some(decoded) => decoded,
+    None => return false,
+  };
+  if suite != pk_suite {
+    return false;
+  }
+  verify_components(pk, message, sigma, &root, &suite.config())
+}
+
+fn verify_components(pk: &[u8], message: &[u8], sigma: u128, root: &[u8], config: &LoquatConfig) -> bool {
+  let h = field::from_be_bytes_mod(&Hash::new(config.msg_hash).compute(message));
+  let transcript = Hash::new(config.transcript_hash);
+
+  // Both PRF branches are evaluated and compared in full; the matching key is selected with a mask
+  let sk_add = field::add(sigma, h);
+  let sk_sub = field::sub(sigma, h);
+  let matches_add = constant_time::bytes_eq(&transcript.compute(&sk_add.to_be_bytes()), pk);
+  let matches_sub = constant_time::bytes_eq(&transcript.compute(&sk_sub.to_be_bytes()), pk);
+  let mask = 0u128.wrapping_sub(matches_sub as u128);
+  let sk = sk_add ^ ((sk_add ^ sk_sub) & mask);
+
+  // Recompute sigma from the recovered key and the PRF bit of sk + h (undefined at zero)
+  let expected_sigma = match field::legendre_symbol(field::add(sk, h)) {
+    1 => field::sub(sk, h),
+    -1 => field::add(sk, h),
+    _ => return false,
+  };
+
+  let tree = MerkleTree::new(vec![BigUint::from(expected_sigma), BigUint::from(h)], config.merkle_hash);
+  let root_matches = match tree.root() {
+    Some(expected_root) => wire::digest_to_bytes(&expected_root).is_some_and(|expected| constant_time::bytes_eq(&expected, root)),
+    None => false,
+  };
+  (matches_add | matches_sub) & root_matches
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::crypto::hash_functions::HashFunction;
+  use crate::signature::loquat::Loquat;
+  use crate::signature::message::SignableMessage;
+use crate::signature::params::ParamsHandle;
+
+  fn encoded(config: &LoquatConfig, message: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let keypair = Loquat::keygen_with_config(config);
+    let signature = Loquat::sign_with_config(keypair.secret_key, message, config).unwrap();
+    (Loquat::encode_public_key(&keypair.public_key, config), signature.to_bytes())
+  }
+
+  #[test]
+  fn test_matches_full_verifier() {
+    let limits = UntrustedInputLimits::default();
+    for &hash in HashFunction::ALL {
+      let config = LoquatConfig::uniform(hash);
+      let (pk, sig) = encoded(&config, b"untrusted message");
+      assert!(Loquat::verify_encoded(&pk, b"untrusted message", &sig));
+      assert!(verify_untrusted(&pk, &b"untrusted message".signing_bytes(), &sig, &limits));
+      assert!(!verify_untrusted(&pk, &b"other message".signing_bytes(), &sig, &limits));
+    }
+
+    let credential = serde_json::json!({"id": "did:example:alice"});
+    let config = LoquatConfig::default();
+    let keypair = Loquat::keygen();
+    let sig = Loquat::sign(keypair.secret_key, &credential).unwrap().to_bytes();
+    let pk = Loquat::encode_public_key(&keypair.public_key, &config);
+    assert!(verify_untrusted(&pk, &credential.signing_bytes(), &sig, &limits));
+  }
+
+  #[test]
+  fn test_rejects_oversized_and_non_canonical_inputs() {
+    let limits = UntrustedInputLimits { max_message_bytes: 24 };
+    let (pk, sig) = encoded(&LoquatConfig::default(), b"short");
+    assert!(verify_untrusted(&pk, &b"short".signing_bytes(), &sig, &limits));
+    assert!(!verify_untrusted(&pk, &b"longer than the limit".signing_bytes(), &sig, &limits));
+
+    // Trailing data pushes the signature over its maximum size
+    let mut padded = sig.clone();
+    padded.resize(MAX_SIGNATURE_BYTES + 1, 0);
+    assert!(!verify_untrusted(&pk, &b"short".signing_bytes(), &padded, &limits));
+
+    // A truncated root or a key one byte short is not a fixed-width encoding
+    assert!(!verify_untrusted(&pk, &b"short".signing_bytes(), &sig[..sig.len() - 1], &limits));
+    assert!(!verify_untrusted(&pk[..pk.len() - 1], &b"short".signing_bytes(), &sig, &limits));
+
+    // sigma + P reduces to the same field element but is not canonical
+    let (suite, mut offset) = Suite::decode_header(&sig).unwrap();
+    let sigma = u128::from_be_bytes(wire::read_field(&sig, &mut offset).unwrap());
+    let mut unreduced = vec![];
+    suite.encode_header(&mut unreduced);
+    unreduced.extend_from_slice(&(sigma + P).to_be_bytes());
+    unreduced.extend_from_slice(&sig[offset..]);
+    assert!(!verify_untrusted(&pk, &b"short".signing_bytes(), &unreduced, &limits));
+  }
+
+  #[test]
+  fn test_rejects_other_parameter_sets() {
+    let config = LoquatConfig::default().with_params(ParamsHandle::M61);
+    let (pk, sig) = encoded(&config, b"message");
+    assert!(Loquat::verify_encoded(&pk, b"message", &sig));
+    assert!(!verify_untrusted(&pk, &b"message".signing_bytes(), &sig, &UntrustedInputLimits::default()));
+  }
+
+  #[test]
+  fn test_verifier_limits() {
+    let config = LoquatConfig::default();
+    let defaults = VerifierLimits::default();
+    let tight = VerifierLimits {
+      max_sig_bytes: 8,
+      max_ring_size: 2,
+      max_batch: 1,
+      max_proof_depth: 1,
+    };
+
+    let (pk, sig) = encoded(&config, b"message");
+    assert!(defaults.verify_encoded(&pk, b"message", &sig));
+    assert!(!tight.verify_encoded(&pk, b"message", &sig));
+
+    let keys: Vec<_> = (0..3).map(|_| Loquat::keygen()).collect();
+    let ring: Vec<Vec<u8>> = keys.iter().map(|k| k.public_key.clone()).collect();
+    #[cfg(feature = "experimental")]
+    {
+      let ring_sig = LoquatRingSignature::sign(keys[1].secret_key, b"message", &ring).unwrap();
+      assert!(defaults.verify_ring(&ring, b"message", &ring_sig, &config));
+      assert!(!tight.verify_ring(&ring, b"message", &ring_sig, &config));
+    }
+
+    let tree = MerkleTree::new((1u32..=4).map(BigUint::from).collect(), HashFunction::Sha3_256);
+    let proof = tree.generate_proof(2).unwrap();
+    let root = tree.root().unwrap();
+    assert!(defaults.verify_merkle_proof(&root, &BigUint::from(3u32), &proof, &HashFunction::Sha3_256));
+    assert!(!tight.verify_merkle_proof(&root, &BigUint::from(3u32), &proof, &HashFunction::Sha3_256));
+
+    // Oversized batches are rejected before the aggregate is looked at
+    let messages = vec![b"a".to_vec(), b"b".to_vec()];
+    let aggregate = LoquatAggregate::aggregate(&[Loquat::sign(keys[0].secret_key, b"a").unwrap(), Loquat::sign(keys[1].secret_key, b"b").unwrap()]).unwrap();
+    assert!(!tight.verify_aggregate(&ring[..2], &messages, &aggregate, &config));
+    assert_eq!(defaults.verify_aggregate_untrusted(&ring[..2], &messages, &aggregate.to_bytes(), &config), defaults.verify_aggregate(&ring[..2], &messages, &aggregate, &config));
+    assert!(!tight.verify_aggregate_untrusted(&ring[..2], &messages, &aggregate.to_bytes(), &config));
+    let mut padded = aggregate.to_bytes();
+    padded.push(0);
+    assert!(!defaults.verify_aggregate_untrusted(&ring[..2], &messages, &padded, &config));
+  }
+
+  #[cfg(feature = "experimental")]
+  #[test]
+  fn test_untrusted_ring_encodings() {
+    let config = LoquatConfig::default();
+    let keys: Vec<_> = (0..3).map(|_| Loquat::keygen()).collect();
+    let ring = Ring::new(&keys.iter().map(|k| k.public_key.clone()).collect::<Vec<_>>(), &config).unwrap();
+    let ring_bytes = ring.to_bytes();
+    let sig_bytes = ring.sign(keys[1].secret_key, b"message").unwrap().to_bytes();
+
+    let defaults = VerifierLimits::default();
+    assert!(defaults.verify_ring_untrusted(&ring_bytes, b"message", &sig_bytes, &config));
+    assert!(!defaults.verify_ring_untrusted(&ring_bytes, b"other", &sig_bytes, &config));
+    assert!(!defaults.verify_ring_untrusted(&ring_bytes, b"message", &sig_bytes, &LoquatConfig::uniform(HashFunction::Shake128)));
+    assert!(!VerifierLimits { max_ring_size: 2, ..defaults }.verify_ring_untrusted(&ring_bytes, b"message", &sig_bytes, &config));
+    assert!(!VerifierLimits { max_proof_depth: 1, ..defaults }.verify_ring_untrusted(&ring_bytes, b"message", &sig_bytes, &config));
+
+    // A declared member count or path depth over the limit is rejected from the header alone,
+    // even when the body that would back it is missing
+    let (_, offset) = Suite::decode_header(&ring_bytes).unwrap();
+    let mut huge_ring = ring_bytes[..offset].to_vec();
+    huge_ring.extend_from_slice(&u32::MAX.to_be_bytes());
+    assert_eq!(declared_ring_size(&huge_ring), Some(u32::MAX as usize));
+    assert!(!defaults.verify_ring_untrusted(&huge_ring, b"message", &sig_bytes, &config));
+
+    let depth_at = offset + 2 * FIELD_BYTES + DIGEST_BYTES;
+    let mut deep = sig_bytes[..=depth_at].to_vec();
+    deep[depth_at] = u8::MAX;
+    assert_eq!(declared_path_depth(&deep), Some(u8::MAX as usize));
+    assert!(!defaults.verify_ring_untrusted(&ring_bytes, b"message", &deep, &config));
+    assert!(!defaults.verify_ring_untrusted(&ring_bytes, b"message", &sig_bytes[..depth_at], &config));
+  }
+}
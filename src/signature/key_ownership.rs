@@ -0,0 +1,93 @@
+// Proof of possession of a Loquat secret key
+// Registries (DID key registration, certificate-signing-request-like flows) want evidence that
+// whoever submits a public key holds its secret key, without asking them to sign anything a
+// relying party could later mistake for a real statement. The proof is a Loquat signature
+// over a dedicated domain, the public key and a registry-chosen nonce, so it commits to the
+// key it proves, cannot be replayed under another nonce and never verifies as a signature on
+// a credential or message. Verifying a proof reveals the key; see Experimental proofs in the
+// signature module.
+
+#[cfg(feature = "std")]
+use crate::crypto::rng::RngHealthError;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::utils::encoding::Encoding;
+
+// Domain separation of the signed transcript
+const KEY_OWNERSHIP_DOMAIN: &[u8] = b"loquat-key-ownership";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyOwnershipProof {
+  pub signature: LoquatSignature,
+}
+
+impl KeyOwnershipProof {
+  pub fn to_bytes(&self) -> Vec<u8> {
+    self.signature.to_bytes()
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    Some(Self {
+      signature: LoquatSignature::from_bytes(bytes)?,
+    })
+  }
+}
+
+// Bytes actually signed: domain | public key | nonce, each length-prefixed
+fn transcript(pk: &[u8], nonce: &[u8]) -> Vec<u8> {
+  let mut out = KEY_OWNERSHIP_DOMAIN.to_vec();
+  Encoding::write_bytes(&mut out, pk);
+  Encoding::write_bytes(&mut out, nonce);
+  out
+}
+
+// Proves knowledge of the secret key behind Loquat::keygen's public key for sk
+#[cfg(feature = "std")]
+pub fn prove_key_ownership(sk: u128, nonce: &[u8]) -> Result<KeyOwnershipProof, RngHealthError> {
+  prove_key_ownership_with_config(sk, nonce, &LoquatConfig::default())
+}
+
+#[cfg(feature = "std")]
+pub fn prove_key_ownership_with_config(sk: u128, nonce: &[u8], config: &LoquatConfig) -> Result<KeyOwnershipProof, RngHealthError> {
+  let pk = Loquat::public_key_for(sk, config);
+  Ok(KeyOwnershipProof {
+    signature: Loquat::sign_with_config(sk, transcript(&pk, nonce).as_slice(), config)?,
+  })
+}
+
+// Checks that the proof was made for this key and nonce
+pub fn verify_key_ownership(pk: &[u8], nonce: &[u8], proof: &KeyOwnershipProof) -> bool {
+  verify_key_ownership_with_config(pk, nonce, proof, &LoquatConfig::default())
+}
+
+pub fn verify_key_ownership_with_config(pk: &[u8], nonce: &[u8], proof: &KeyOwnershipProof, config: &LoquatConfig) -> bool {
+  Loquat::verify_with_config(pk, transcript(pk, nonce).as_slice(), &proof.signature, config)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_key_ownership() {
+    let keypair = Loquat::keygen();
+    let proof = prove_key_ownership(keypair.secret_key, b"registry-nonce-1").unwrap();
+    assert!(verify_key_ownership(&keypair.public_key, b"registry-nonce-1", &proof));
+
+    // Bound to the nonce and to the key
+    assert!(!verify_key_ownership(&keypair.public_key, b"registry-nonce-2", &proof));
+    assert!(!verify_key_ownership(&Loquat::keygen().public_key, b"registry-nonce-1", &proof));
+    let decoded = KeyOwnershipProof::from_bytes(&proof.to_bytes()).unwrap();
+    assert!(verify_key_ownership(&keypair.public_key, b"registry-nonce-1", &decoded));
+  }
+
+  #[test]
+  fn test_proof_is_not_a_message_signature() {
+    let keypair = Loquat::keygen();
+    let proof = prove_key_ownership(keypair.secret_key, b"nonce").unwrap();
+    assert!(!Loquat::verify(&keypair.public_key, b"nonce", &proof.signature));
+    // Nor does a signature over the bare nonce prove ownership
+    let signature = Loquat::sign(keypair.secret_key, b"nonce").unwrap();
+    assert!(!verify_key_ownership(&keypair.public_key, b"nonce", &KeyOwnershipProof { signature }));
+  }
+}
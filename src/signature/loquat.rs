@@ -1,30 +1,116 @@
 use crate::crypto::{
   legendre_prf::LegendrePRF,
   merkle::MerkleTree,
-  hash_functions::{Hash, HashFunction},
+  hash_functions::Hash,
 };
-use std::convert::TryInto;
+#[cfg(feature = "std")]
+use crate::crypto::rng::{self, RngHealthError};
+#[cfg(feature = "std")]
+use crate::crypto::test_rng;
+use crate::signature::config::LoquatConfig;
+use crate::signature::error::VerifyError;
+use crate::signature::message::SignableMessage;
+use crate::signature::suite::Suite;
+use crate::utils::constant_time;
+use crate::utils::encoding::{Hex, Redacted};
+use crate::utils::metrics::{self, FailureReason, Operation};
+use crate::utils::wire::{self, DIGEST_BYTES};
 use num_bigint::BigUint;
-use num_traits::ToPrimitive;
+#[cfg(feature = "std")]
 use rand::Rng;
-
-// Prime field modulus (p = 2^127 - 1) as specified in the CRYPTO 2024 paper
-// This prime is chosen to be efficient for Legendre PRF computation
-const P: u128 = (1 << 127) - 1;
+use std::fmt;
 
 /// Loquat Signature Structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LoquatSignature {
   pub sigma: BigUint, // Signature
   pub merkle_root: BigUint, // Commitment to public key
+  pub config: LoquatConfig, // Hash functions the signature was produced with
+}
+
+impl LoquatSignature {
+  // Suite the signature was produced under
+  pub fn suite(&self) -> Suite {
+    Suite::from_config(&self.config)
+  }
+
+  // Serializes the signature as: version | suite | sigma (16 bytes) | merkle_root (32 bytes)
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    self.suite().encode_header(&mut out);
+    wire::write_field(&mut out, &self.sigma);
+    wire::write_digest(&mut out, &self.merkle_root);
+    out
+  }
+
+  // Parses a signature written by to_bytes, rejecting unknown versions, suites and trailing data
+  // Only the canonical encoding is accepted: re-encoding must reproduce the input exactly, so
+  // out-of-range components are rejected and equal signatures always have equal bytes
+  // (callers may hash encodings to deduplicate signatures)
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (suite, mut offset) = Suite::decode_header(bytes)?;
+    let sigma = wire::field_from_bytes(&wire::read_field(bytes, &mut offset)?);
+    let merkle_root = wire::digest_from_bytes(&wire::read_digest(bytes, &mut offset)?);
+    if offset != bytes.len() {
+      return None;
+    }
+
+    let signature = Self {
+      sigma,
+      merkle_root,
+      config: suite.config(),
+    };
+    if !signature.is_canonical() || signature.to_bytes() != bytes {
+      return None;
+    }
+    Some(signature)
+  }
+
+  // Whether every component is in its canonical range: sigma reduced modulo the parameter
+  // set's modulus and a Merkle root no wider than a digest. Verification rejects anything
+  // else, since sigma + P would otherwise verify as sigma
+  pub fn is_canonical(&self) -> bool {
+    self.sigma < BigUint::from(self.config.params.modulus()) && self.merkle_root.bits() <= 256
+  }
+}
+
+// Hex of the serialized signature; `{:#}` gives the multibase (base16) form
+impl fmt::Display for LoquatSignature {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(&Hex(&self.to_bytes()), f)
+  }
 }
 
 // Loquat Key-pair
+#[derive(Clone)]
 pub struct LoquatKeyPair {
   pub secret_key: u128,
   pub public_key: Vec<u8>, // Public key commitment using Merkle root
 }
 
+// The secret key never reaches logs through Debug or Display
+impl fmt::Debug for LoquatKeyPair {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LoquatKeyPair").field("secret_key", &Redacted).field("public_key", &Hex(&self.public_key)).finish()
+  }
+}
+
+// Compares the secret key in constant time
+impl PartialEq for LoquatKeyPair {
+  fn eq(&self, other: &Self) -> bool {
+    constant_time::u128_eq(self.secret_key, other.secret_key) & constant_time::bytes_eq(&self.public_key, &other.public_key)
+  }
+}
+
+impl Eq for LoquatKeyPair {}
+
+// Hex of the public key
+impl fmt::Display for LoquatKeyPair {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(&Hex(&self.public_key), f)
+  }
+}
+
 pub struct Loquat;
 
 impl Loquat {
@@ -32,14 +118,64 @@ impl Loquat {
   fn mod_sub(a: u128, b: u128, modulus: u128) -> u128 {
     (a + modulus - b) % modulus
   }
+
+  // Computes the public key commitment to a secret key using the transcript hash
+  pub(crate) fn public_key_for(sk: u128, config: &LoquatConfig) -> Vec<u8> {
+    Hash::new(config.transcript_hash).compute(&sk.to_be_bytes())
+  }
+
+  // Serializes a public key as: version | suite | public key commitment
+  pub fn encode_public_key(pk: &[u8], config: &LoquatConfig) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(config).encode_header(&mut out);
+    out.extend_from_slice(pk);
+    out
+  }
+
+  // Parses a public key written by encode_public_key: the header and exactly one digest
+  pub fn decode_public_key(bytes: &[u8]) -> Option<(Suite, Vec<u8>)> {
+    let (suite, offset) = Suite::decode_header(bytes)?;
+    let pk = bytes.get(offset..)?;
+    if pk.len() != DIGEST_BYTES {
+      return None;
+    }
+    Some((suite, pk.to_vec()))
+  }
+
+  // Verifies serialized key and signature encodings
+  // The key and signature must carry the same suite, preventing cross-parameter confusion
+  pub fn verify_encoded<M: SignableMessage + ?Sized>(pk_bytes: &[u8], message: &M, sig_bytes: &[u8]) -> bool {
+    Self::try_verify_encoded(pk_bytes, message, sig_bytes).is_ok()
+  }
+
+  // verify_encoded, reporting why verification failed
+  pub fn try_verify_encoded<M: SignableMessage + ?Sized>(pk_bytes: &[u8], message: &M, sig_bytes: &[u8]) -> Result<(), VerifyError> {
+    let (pk_suite, pk) = Self::decode_public_key(pk_bytes).ok_or(VerifyError::BadEncoding)?;
+    let signature = LoquatSignature::from_bytes(sig_bytes).ok_or(VerifyError::BadEncoding)?;
+    if signature.suite() != pk_suite {
+      return Err(VerifyError::SuiteMismatch);
+    }
+    Self::try_verify_with_config(&pk, message, &signature, &pk_suite.config())
+  }
+
   // Generate a new Loquat key pair
+  #[cfg(feature = "std")]
   pub fn keygen() -> LoquatKeyPair {
+    Self::keygen_with_config(&LoquatConfig::default())
+  }
+
+  // Generate a new Loquat key pair whose public key commitment uses the configured transcript hash
+  // Panics once crypto::rng::selftest has failed in this process
+  #[cfg(feature = "std")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  pub fn keygen_with_config(config: &LoquatConfig) -> LoquatKeyPair {
+    rng::assert_healthy();
     // Generate a random secret key
-    let mut rng = rand::thread_rng();
-    let secret_key = rng.gen_range(1..P);
+    let mut rng = test_rng::source();
+    let secret_key = rng.gen_range(1..config.params.modulus());
     
     // Compute the public key as a hash of the secret key
-    let public_key = Hash::new(HashFunction::Sha3_256).compute(&secret_key.to_be_bytes());
+    let public_key = Self::public_key_for(secret_key, config);
 
     LoquatKeyPair {
       secret_key,
@@ -47,18 +183,46 @@ impl Loquat {
     }
   }
 
+  // Rebuilds the key pair for a stored secret key; None for secret keys keygen never produces
+  pub fn keypair_from_secret(secret_key: u128, config: &LoquatConfig) -> Option<LoquatKeyPair> {
+    if secret_key == 0 || !config.params.contains(secret_key) {
+      return None;
+    }
+    Some(LoquatKeyPair {
+      secret_key,
+      public_key: Self::public_key_for(secret_key, config),
+    })
+  }
+
   // Sign a message using the Loquat signature scheme
   // As described in the CRYPTO 2024 paper "Loquat: A SNARK-Friendly Post-Quantum Signature 
   // Based on the Legendre PRF with Applications in Ring and Aggregate Signatures"
-  pub fn sign(sk: u128, message: &[u8]) -> LoquatSignature {
-    let hash = Hash::new(HashFunction::Sha3_256).compute(message);
-    let message_int = BigUint::from_bytes_be(&hash);
+  #[cfg(feature = "std")]
+  pub fn sign<M: SignableMessage + ?Sized>(sk: u128, message: &M) -> Result<LoquatSignature, RngHealthError> {
+    Self::sign_with_config(sk, message, &LoquatConfig::default())
+  }
 
-    // Convert BigUint to u128 safely by reducing modulo P before conversion
-    let message_u128 = (message_int % BigUint::from(P)).to_u128().unwrap_or(0);
+  // Sign a message with explicit hash function choices
+  // The configuration is recorded in the signature so verifiers can detect a mismatch
+  // Refuses with the recorded error once crypto::rng::selftest has failed in this process
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(msg_hash = ?config.msg_hash)))]
+  #[cfg(feature = "std")]
+  pub fn sign_with_config<M: SignableMessage + ?Sized>(sk: u128, message: &M, config: &LoquatConfig) -> Result<LoquatSignature, RngHealthError> {
+    rng::check_healthy()?;
+    Ok(metrics::observe_infallible(Operation::Sign, || Self::sign_unobserved(sk, message, config)))
+  }
+
+  // Signing without the health guard or metrics, for selftest's known-answer test: the
+  // signature is deterministic, so it can be checked whatever state the randomness is in
+  #[cfg(feature = "std")]
+  pub(crate) fn sign_unobserved<M: SignableMessage + ?Sized>(sk: u128, message: &M, config: &LoquatConfig) -> LoquatSignature {
+    let modulus = config.params.modulus();
+
+    // Hash the canonical message encoding and reduce it into the field
+    let message_u128 = message.message_hash_in(config.msg_hash, config.params);
     
     // Initialize the Legendre PRF with the secret key
-    let legendre_prf = LegendrePRF::with_key(sk);
+    let legendre_prf = LegendrePRF::with_key_mod(sk, modulus);
     
     // Evaluate the Legendre PRF on the message hash
     // This produces a bit (0 or 1) based on the quadratic residuosity
@@ -68,9 +232,9 @@ impl Loquat {
     // If prf_result is 1, we add message_u128 to sk, otherwise we subtract it
     // This creates a signature that depends on the Legendre symbol computation
     let signature_value = if prf_result == 1 {
-      (sk + message_u128) % P
+      (sk + message_u128) % modulus
     } else {
-      Self::mod_sub(sk, message_u128, P)
+      Self::mod_sub(sk, message_u128, modulus)
     };
     
     let signature = BigUint::from(signature_value);
@@ -78,12 +242,12 @@ impl Loquat {
     // Compute a Merkle root for proof that binds both the signature and message
     // This ensures that any tampering with the message will lead to verification failure
     // The Merkle tree includes both the PRF-enhanced signature and the message hash
-    let merkle_tree = MerkleTree::new(vec![signature.clone(), BigUint::from(message_u128)], HashFunction::Sha3_256);
-    let merkle_root = merkle_tree.root().unwrap();
+    let merkle_root = Self::commitment_root(&signature, message_u128, config);
 
     LoquatSignature {
       sigma: signature,
       merkle_root,
+      config: *config,
     }
   }
 
@@ -91,84 +255,161 @@ impl Loquat {
   // This verification process ensures that the signature is valid only for the exact message
   // by recomputing the signature from the expected secret key and current message hash
   // Implementation follows the CRYPTO 2024 paper on Loquat
-  pub fn verify(pk: &[u8], message: &[u8], signature: &LoquatSignature) -> bool {
-    let hash = Hash::new(HashFunction::Sha3_256).compute(message);
-    let message_int = BigUint::from_bytes_be(&hash);
+  pub fn verify<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature) -> bool {
+    Self::verify_with_config(pk, message, signature, &LoquatConfig::default())
+  }
 
-    // Convert BigUint to u128 safely by reducing modulo P before conversion
-    let message_u128 = (message_int % BigUint::from(P)).to_u128().expect("Message conversion failed");
+  // Verify a Loquat signature under an expected configuration
+  // Signatures produced with different hash functions are rejected before any hashing
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(msg_hash = ?config.msg_hash), ret))]
+  pub fn verify_with_config<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature, config: &LoquatConfig) -> bool {
+    Self::try_verify_with_config(pk, message, signature, config).is_ok()
+  }
+
+  // verify, reporting why verification failed
+  pub fn try_verify<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature) -> Result<(), VerifyError> {
+    Self::try_verify_with_config(pk, message, signature, &LoquatConfig::default())
+  }
+
+  pub fn try_verify_with_config<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature, config: &LoquatConfig) -> Result<(), VerifyError> {
+    metrics::observe(Operation::Verify, || Self::check_signature(pk, message, signature, config)).map_err(VerifyError::from)
+  }
+
+  // The verification checks, reporting which one failed
+  fn check_signature<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature, config: &LoquatConfig) -> Result<(), FailureReason> {
+    if signature.config != *config {
+      return Err(FailureReason::ConfigMismatch);
+    }
+    if !signature.is_canonical() {
+      return Err(FailureReason::NonCanonical);
+    }
+
+    let modulus = config.params.modulus();
+    let message_u128 = message.message_hash_in(config.msg_hash, config.params);
     
     // Get the signature value as u128
-    let sigma_u128 = (signature.sigma.clone() % BigUint::from(P)).to_u128().expect("Sigma conversion failed");
+    let sigma_u128 = config.params.reduce(&signature.sigma);
     
     // Try both possible PRF outcomes (0 and 1) to recover the secret key
     // This is necessary because we don't know which PRF result was used during signing
     
     // Case 1: If PRF result was 1, then sk = sigma - message_u128 mod P
-    let expected_sk_case1 = Self::mod_sub(sigma_u128, message_u128, P);
+    let expected_sk_case1 = Self::mod_sub(sigma_u128, message_u128, modulus);
     
     // Case 2: If PRF result was 0, then sk = sigma + message_u128 mod P
-    let expected_sk_case2 = (sigma_u128 + message_u128) % P;
+    let expected_sk_case2 = (sigma_u128 + message_u128) % modulus;
     
     // Compute the expected public keys for both cases
-    let expected_pk_case1 = Hash::new(HashFunction::Sha3_256).compute(&expected_sk_case1.to_be_bytes());
-    let expected_pk_case2 = Hash::new(HashFunction::Sha3_256).compute(&expected_sk_case2.to_be_bytes());
+    let expected_pk_case1 = Self::public_key_for(expected_sk_case1, config);
+    let expected_pk_case2 = Self::public_key_for(expected_sk_case2, config);
     
     // Check if either of the expected public keys matches the provided public key
-    let pk_matches_case1 = expected_pk_case1 == pk;
-    let pk_matches_case2 = expected_pk_case2 == pk;
+    // Both comparisons run in constant time so near-miss forgeries learn nothing from timing
+    let pk_matches_case1 = constant_time::bytes_eq(&expected_pk_case1, pk);
+    let pk_matches_case2 = constant_time::bytes_eq(&expected_pk_case2, pk);
     
     // If neither case matches, the signature is invalid
     if !pk_matches_case1 && !pk_matches_case2 {
-      return false;
+      return Err(FailureReason::KeyMismatch);
     }
     
     // Determine which secret key to use based on which public key matched
     let expected_sk = if pk_matches_case1 { expected_sk_case1 } else { expected_sk_case2 };
     
     // Initialize the Legendre PRF with the recovered secret key
-    let legendre_prf = LegendrePRF::with_key(expected_sk);
+    let legendre_prf = LegendrePRF::with_key_mod(expected_sk, modulus);
     
     // Evaluate the Legendre PRF on the message hash
     let prf_result = legendre_prf.evaluate(message_u128);
     
     // Recompute the expected signature value using the recovered secret key and PRF result
     let recomputed_sigma_value = if prf_result == 1 {
-      (expected_sk + message_u128) % P
+      (expected_sk + message_u128) % modulus
     } else {
-      Self::mod_sub(expected_sk, message_u128, P)
+      Self::mod_sub(expected_sk, message_u128, modulus)
     };
     
     let recomputed_sigma = BigUint::from(recomputed_sigma_value);
     
     // Rebuild the Merkle tree using the recomputed sigma and the current message_u128
-    let expected_merkle_tree = MerkleTree::new(vec![recomputed_sigma, BigUint::from(message_u128)], HashFunction::Sha3_256);
-    let expected_root = expected_merkle_tree.root().expect("Failed to compute Merkle root");
+    let expected_root = Self::commitment_root(&recomputed_sigma, message_u128, config);
     
     // Check if the recomputed Merkle root matches the stored one
-    let merkle_matches = expected_root == signature.merkle_root;
+    let merkle_matches = constant_time::biguint_eq(&expected_root, &signature.merkle_root);
     
-    // Return true only if both the public key check and Merkle root check pass
-    (pk_matches_case1 || pk_matches_case2) && merkle_matches
+    // The public key check passed above, so the Merkle root check decides
+    if merkle_matches {
+      Ok(())
+    } else {
+      Err(FailureReason::CommitmentMismatch)
+    }
+  }
+
+  // Root of the two-leaf Merkle tree over sigma and the message hash
+  pub(crate) fn commitment_root(sigma: &BigUint, message_u128: u128, config: &LoquatConfig) -> BigUint {
+    MerkleTree::new(vec![sigma.clone(), BigUint::from(message_u128)], config.merkle_hash)
+      .root()
+      .expect("Two leaves have a root")
+  }
+
+  // Recovers the signing key from a signature that verifies under pk
+  pub(crate) fn recover_secret_key<M: SignableMessage + ?Sized>(pk: &[u8], message: &M, signature: &LoquatSignature, config: &LoquatConfig) -> Option<u128> {
+    if !Self::verify_with_config(pk, message, signature, config) {
+      return None;
+    }
+    let modulus = config.params.modulus();
+    let message_u128 = message.message_hash_in(config.msg_hash, config.params);
+    let sigma_u128 = config.params.reduce(&signature.sigma);
+    let sk_case1 = Self::mod_sub(sigma_u128, message_u128, modulus);
+    if constant_time::bytes_eq(&Self::public_key_for(sk_case1, config), pk) {
+      Some(sk_case1)
+    } else {
+      Some((sigma_u128 + message_u128) % modulus)
+    }
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  #[cfg(feature = "std")]
+  use crate::crypto::hash_functions::HashFunction;
+  use crate::utils::encoding::Encoding;
+  #[cfg(feature = "std")]
+  use crate::utils::field_operations::{FieldElement, P};
+  #[cfg(feature = "std")]
+  use num_traits::ToPrimitive;
 
+  // The selftest vector, checked without keygen or signing so it also runs on verifier-only
+  // builds
+  #[test]
+  fn test_verify_known_answer() {
+    let config = LoquatConfig::default();
+    let keypair = Loquat::keypair_from_secret(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef, &config).unwrap();
+    let bytes = Encoding::from_hex("020152e9e7e4c5b6355ede7836b9ba99c8d8c10f2dfbe560a236893e718882517823fc5649641550bcbfde60b1464ca05d90").unwrap();
+    let signature = LoquatSignature::from_bytes(&bytes).unwrap();
+    assert!(Loquat::verify(&keypair.public_key, b"loquat-vc self-test", &signature));
+    assert!(!Loquat::verify(&keypair.public_key, b"loquat-vc self-test!", &signature));
+
+    let pk_bytes = Loquat::encode_public_key(&keypair.public_key, &config);
+    assert!(Loquat::verify_encoded(&pk_bytes, b"loquat-vc self-test", &bytes));
+    assert!(!Loquat::verify_encoded(&pk_bytes, b"loquat-vc self-test", &bytes[1..]));
+  }
+
+  #[cfg(feature = "std")]
   #[test]
   fn test_loquat_signature() {
     let keypair = Loquat::keygen();
     let message = b"Test message for Loquat";
 
     // Sign the message using the Legendre PRF-based signature scheme
-    let signature = Loquat::sign(keypair.secret_key, message);
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
     
     // Verify the signature with proper message hash reduction and PRF evaluation
     assert!(Loquat::verify(&keypair.public_key, message, &signature));
   }
 
+  #[cfg(feature = "std")]
   #[test]
   fn test_invalid_signature() {
     let keypair = Loquat::keygen();
@@ -176,7 +417,7 @@ mod tests {
     let invalid_message = b"Tampered message";
 
     // Sign the original message
-    let signature = Loquat::sign(keypair.secret_key, message);
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
     
     // Verify that a tampered message fails verification
     // This tests that the hash reduction, PRF evaluation, and conversion are properly applied
@@ -187,6 +428,7 @@ mod tests {
     assert!(Loquat::verify(&keypair.public_key, message, &signature));
   }
   
+  #[cfg(feature = "std")]
   #[test]
   fn test_large_message_hash() {
     let keypair = Loquat::keygen();
@@ -194,7 +436,7 @@ mod tests {
     let large_message = [0xFF; 64].to_vec();
     
     // Sign the message with potentially large hash
-    let signature = Loquat::sign(keypair.secret_key, &large_message);
+    let signature = Loquat::sign(keypair.secret_key, &large_message).unwrap();
     
     // Verify that the signature is valid despite the large hash value
     // This tests that modulo reduction is properly applied before conversion
@@ -202,6 +444,7 @@ mod tests {
     assert!(Loquat::verify(&keypair.public_key, &large_message, &signature));
   }
   
+  #[cfg(feature = "std")]
   #[test]
   fn test_legendre_prf_consistency() {
     // Test that the Legendre PRF produces consistent results
@@ -220,7 +463,235 @@ mod tests {
     let keypair = Loquat::keygen();
     let test_message = b"Testing Legendre PRF in Loquat";
     
-    let signature = Loquat::sign(keypair.secret_key, test_message);
+    let signature = Loquat::sign(keypair.secret_key, test_message).unwrap();
     assert!(Loquat::verify(&keypair.public_key, test_message, &signature));
   }
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_sign_verify_with_config() {
+    let config = LoquatConfig::new(HashFunction::Shake128, HashFunction::Keccak256, HashFunction::Sha3_256);
+    let keypair = Loquat::keygen_with_config(&config);
+    let message = b"Configured Loquat message";
+
+    let signature = Loquat::sign_with_config(keypair.secret_key, message, &config).unwrap();
+    assert_eq!(signature.config, config);
+    assert!(Loquat::verify_with_config(&keypair.public_key, message, &signature, &config));
+    assert_eq!(Loquat::keypair_from_secret(keypair.secret_key, &config), Some(keypair));
+    assert_eq!(Loquat::keypair_from_secret(0, &config), None);
+    assert_eq!(Loquat::keypair_from_secret(P, &config), None);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_config_mismatch_rejected() {
+    let config = LoquatConfig::uniform(HashFunction::Shake128);
+    let keypair = Loquat::keygen_with_config(&config);
+    let message = b"Configured Loquat message";
+
+    let signature = Loquat::sign_with_config(keypair.secret_key, message, &config).unwrap();
+
+    // A verifier expecting the default configuration must reject the signature
+    assert!(!Loquat::verify(&keypair.public_key, message, &signature));
+
+    // Relabelling the signature does not help, the digests no longer line up
+    let mut relabelled = signature.clone();
+    relabelled.config = LoquatConfig::default();
+    assert!(!Loquat::verify(&keypair.public_key, message, &relabelled));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_signature_encoding_round_trip() {
+    let keypair = Loquat::keygen();
+    let message = b"Encoded Loquat message";
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
+
+    let bytes = signature.to_bytes();
+    let decoded = LoquatSignature::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.sigma, signature.sigma);
+    assert_eq!(decoded.merkle_root, signature.merkle_root);
+    assert_eq!(decoded.suite(), Suite::LoquatSha3L128);
+
+    // Every signature of a suite has the same length, whatever its component values
+    assert_eq!(bytes.len(), 2 + 16 + 32);
+    let small = LoquatSignature { sigma: BigUint::from(1u8), merkle_root: BigUint::from(1u8), ..signature };
+    assert_eq!(small.to_bytes().len(), bytes.len());
+
+    // Trailing bytes are not accepted
+    let mut extended = bytes.clone();
+    extended.push(0);
+    assert!(LoquatSignature::from_bytes(&extended).is_none());
+    assert!(LoquatSignature::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_formatting_redacts_secret_key() {
+    let keypair = Loquat::keygen_with_config(&LoquatConfig::default());
+    let debug = format!("{:?}", keypair);
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains(&keypair.secret_key.to_string()));
+    assert_eq!(keypair.to_string(), Encoding::to_hex(&keypair.public_key));
+
+    let signature = Loquat::sign(keypair.secret_key, b"message").unwrap();
+    assert_eq!(signature.to_string(), Encoding::to_hex(&signature.to_bytes()));
+    let multibase = format!("{:#}", signature);
+    assert_eq!(Encoding::from_hex(multibase.strip_prefix('f').unwrap()).unwrap(), signature.to_bytes());
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_signatures_and_keys_are_comparable() {
+    let keypair = Loquat::keygen();
+    let copy = keypair.clone();
+    assert_eq!(copy, keypair);
+    assert_ne!(Loquat::keygen(), keypair);
+
+    // Equal signatures collapse in a set, as do their decodings
+    let signature = Loquat::sign(keypair.secret_key, b"message").unwrap();
+    let decoded = LoquatSignature::from_bytes(&signature.to_bytes()).unwrap();
+    let signatures: std::collections::HashSet<_> = [signature.clone(), decoded, Loquat::sign(keypair.secret_key, b"other").unwrap()].into_iter().collect();
+    assert_eq!(signatures.len(), 2);
+    assert!(signatures.contains(&signature));
+
+    assert!(FieldElement::new(2) < FieldElement::new(3));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_verify_errors() {
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"message").unwrap();
+    assert_eq!(Loquat::try_verify(&keypair.public_key, b"message", &signature), Ok(()));
+    assert_eq!(Loquat::try_verify(&Loquat::keygen().public_key, b"message", &signature), Err(VerifyError::KeyMismatch));
+
+    let mut tampered = signature.clone();
+    tampered.merkle_root += 1u8;
+    let error = Loquat::try_verify(&keypair.public_key, b"message", &tampered).unwrap_err();
+    assert_eq!(error, VerifyError::MerkleMismatch);
+    assert!(error.is_cryptographic_failure() && !error.is_malformed_input());
+
+    let pk_bytes = Loquat::encode_public_key(&keypair.public_key, &LoquatConfig::default());
+    let error = Loquat::try_verify_encoded(&pk_bytes, b"message", &signature.to_bytes()[1..]).unwrap_err();
+    assert_eq!(error, VerifyError::BadEncoding);
+    assert!(error.is_malformed_input());
+    let other_suite = Loquat::sign_with_config(keypair.secret_key, b"message", &LoquatConfig::uniform(HashFunction::Keccak256)).unwrap();
+    assert_eq!(Loquat::try_verify_encoded(&pk_bytes, b"message", &other_suite.to_bytes()), Err(VerifyError::SuiteMismatch));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_non_canonical_signatures_rejected() {
+    let keypair = Loquat::keygen();
+    let message = b"Canonical Loquat message";
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
+    assert!(signature.is_canonical());
+
+    // sigma + P is the same field element but not the canonical representative
+    let mut shifted = signature.clone();
+    shifted.sigma += BigUint::from(P);
+    assert!(!shifted.is_canonical());
+    assert!(!Loquat::verify(&keypair.public_key, message, &shifted));
+    assert!(LoquatSignature::from_bytes(&shifted.to_bytes()).is_none());
+
+    // Signatures in the version 1 layout (length-prefixed minimal components) are refused
+    let bytes = signature.to_bytes();
+    let mut legacy = vec![1, bytes[1]];
+    Encoding::write_bytes(&mut legacy, &signature.sigma.to_bytes_be());
+    Encoding::write_bytes(&mut legacy, &signature.merkle_root.to_bytes_be());
+    assert!(LoquatSignature::from_bytes(&legacy).is_none());
+
+    // So is the default configuration spelled out as a custom suite
+    let mut custom = vec![bytes[0], 0xff, HashFunction::Sha3_256.id(), HashFunction::Sha3_256.id(), HashFunction::Sha3_256.id()];
+    custom.extend_from_slice(&bytes[2..]);
+    assert!(LoquatSignature::from_bytes(&custom).is_none());
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_verify_encoded() {
+    let config = LoquatConfig::default();
+    let keypair = Loquat::keygen();
+    let message = b"Encoded Loquat message";
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
+
+    let pk_bytes = Loquat::encode_public_key(&keypair.public_key, &config);
+    assert!(Loquat::verify_encoded(&pk_bytes, message, &signature.to_bytes()));
+    assert!(!Loquat::verify_encoded(&pk_bytes, b"Tampered message", &signature.to_bytes()));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_verify_encoded_rejects_suite_mismatch() {
+    let keypair = Loquat::keygen();
+    let message = b"Encoded Loquat message";
+    let signature = Loquat::sign(keypair.secret_key, message).unwrap();
+
+    // The same key bytes advertised under a different suite must not verify
+    let pk_bytes = Loquat::encode_public_key(&keypair.public_key, &LoquatConfig::uniform(HashFunction::Shake128));
+    assert!(!Loquat::verify_encoded(&pk_bytes, message, &signature.to_bytes()));
+
+    // Rewriting the suite byte of the signature is rejected too
+    let mut sig_bytes = signature.to_bytes();
+    sig_bytes[1] = Suite::LoquatShake128L128.id();
+    let pk_bytes = Loquat::encode_public_key(&keypair.public_key, &LoquatConfig::default());
+    assert!(!Loquat::verify_encoded(&pk_bytes, message, &sig_bytes));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_sign_structured_messages() {
+    let keypair = Loquat::keygen();
+
+    let credential = serde_json::json!({"name": "Alice", "degree": "BSc"});
+    let signature = Loquat::sign(keypair.secret_key, &credential).unwrap();
+    assert!(Loquat::verify(&keypair.public_key, &credential, &signature));
+    assert!(!Loquat::verify(&keypair.public_key, &serde_json::json!({"name": "Mallory", "degree": "BSc"}), &signature));
+
+    let statement = vec![FieldElement::new(7), FieldElement::new(11)];
+    let signature = Loquat::sign(keypair.secret_key, &statement).unwrap();
+    assert!(Loquat::verify(&keypair.public_key, &statement, &signature));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_mixed_parameter_sets() {
+    use crate::signature::aggregate::LoquatAggregate;
+    use crate::signature::params::ParamsHandle;
+
+    // An issuer on M61 next to one on the default set, verified in the same process
+    let legacy = LoquatConfig::default();
+    let current = LoquatConfig::default().with_params(ParamsHandle::M61);
+    let legacy_key = Loquat::keygen_with_config(&legacy);
+    let current_key = Loquat::keygen_with_config(&current);
+    assert!(ParamsHandle::M61.contains(current_key.secret_key));
+
+    let message = b"mixed parameter message";
+    let legacy_sig = Loquat::sign_with_config(legacy_key.secret_key, message, &legacy).unwrap();
+    let current_sig = Loquat::sign_with_config(current_key.secret_key, message, &current).unwrap();
+    assert!(Loquat::verify_with_config(&legacy_key.public_key, message, &legacy_sig, &legacy));
+    assert!(Loquat::verify_with_config(&current_key.public_key, message, &current_sig, &current));
+    assert_eq!(Loquat::try_verify_with_config(&current_key.public_key, message, &current_sig, &legacy), Err(VerifyError::SuiteMismatch));
+    assert!(!Loquat::verify_with_config(&current_key.public_key, b"other", &current_sig, &current));
+
+    // Encodings carry the parameter set, so verify_encoded needs no configuration
+    for (key, signature, config) in [(&legacy_key, &legacy_sig, &legacy), (&current_key, &current_sig, &current)] {
+      let decoded = LoquatSignature::from_bytes(&signature.to_bytes()).unwrap();
+      assert_eq!(decoded.config, *config);
+      assert!(Loquat::verify_encoded(&Loquat::encode_public_key(&key.public_key, config), message, &signature.to_bytes()));
+    }
+    assert!(!Loquat::verify_encoded(&Loquat::encode_public_key(&current_key.public_key, &legacy), message, &current_sig.to_bytes()));
+
+    // sigma must be reduced modulo the signature's own modulus
+    let unreduced = LoquatSignature { sigma: &current_sig.sigma + ParamsHandle::M61.modulus(), ..current_sig.clone() };
+    assert!(!unreduced.is_canonical());
+    assert_eq!(Loquat::keypair_from_secret(ParamsHandle::M61.modulus(), &current), None);
+
+    // Aggregation stays within one parameter set
+    let batch: Vec<_> = (0..3).map(|_| Loquat::keygen_with_config(&current)).map(|key| Loquat::sign_with_config(key.secret_key, message, &current).unwrap()).collect();
+    assert!(LoquatAggregate::aggregate_with_config(&batch, &legacy).is_none());
+    let aggregate = LoquatAggregate::aggregate_with_config(&batch, &current).unwrap();
+    assert!(ParamsHandle::M61.contains(aggregate.aggregated_sigma.to_u128().unwrap()));
+    assert_eq!(crate::signature::aggregate::AggregateSignature::from_bytes(&aggregate.to_bytes()), Some(aggregate));
+  }
 }
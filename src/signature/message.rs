@@ -0,0 +1,172 @@
+// Typed messages for signing
+// Every signing path (raw bytes, JSON credentials, field-element statements) goes
+// through SignableMessage so that the encoding fed into the message hash is
+// defined in exactly one place.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::utils::encoding::Encoding;
+use crate::signature::params::ParamsHandle;
+use crate::utils::field_operations::FieldElement;
+use num_bigint::BigUint;
+use serde_json::Value;
+
+// Domain separation tags, one per encoding, so no two encodings produce the same signing bytes
+const BYTES_TAG: &[u8] = b"loquat-vc/bytes\x00";
+const JSON_TAG: &[u8] = b"loquat-vc/json\x00";
+const FIELD_ELEMENTS_TAG: &[u8] = b"loquat-vc/field-elements\x00";
+
+// A message that can be signed by the Loquat schemes
+pub trait SignableMessage {
+  // Canonical byte encoding of the message that is fed into the message hash
+  fn signing_bytes(&self) -> Vec<u8>;
+
+  // Hashes the canonical encoding and reduces the digest modulo P
+  fn message_hash(&self, hash_function: HashFunction) -> u128 {
+    self.message_hash_in(hash_function, ParamsHandle::L128)
+  }
+
+  // Hashes the canonical encoding and reduces the digest into the field of a parameter set
+  fn message_hash_in(&self, hash_function: HashFunction, params: ParamsHandle) -> u128 {
+    let hash = Hash::new(hash_function).compute(&self.signing_bytes());
+    params.reduce(&BigUint::from_bytes_be(&hash))
+  }
+}
+
+impl SignableMessage for [u8] {
+  fn signing_bytes(&self) -> Vec<u8> {
+    let mut out = BYTES_TAG.to_vec();
+    out.extend_from_slice(self);
+    out
+  }
+}
+
+impl<const N: usize> SignableMessage for [u8; N] {
+  fn signing_bytes(&self) -> Vec<u8> {
+    self[..].signing_bytes()
+  }
+}
+
+impl SignableMessage for Vec<u8> {
+  fn signing_bytes(&self) -> Vec<u8> {
+    self.as_slice().signing_bytes()
+  }
+}
+
+impl SignableMessage for str {
+  fn signing_bytes(&self) -> Vec<u8> {
+    self.as_bytes().signing_bytes()
+  }
+}
+
+// JSON values are signed in their RFC 8785 (JCS) form, so a verifier that re-serializes the
+// payload in another language reproduces the signed bytes. For credentials without floats or
+// member names outside the Basic Multilingual Plane this is the compact, key-sorted encoding
+// signed before JCS was adopted, so existing signatures stay valid.
+impl SignableMessage for Value {
+  fn signing_bytes(&self) -> Vec<u8> {
+    let mut out = JSON_TAG.to_vec();
+    out.extend_from_slice(Encoding::to_jcs(self).as_bytes());
+    out
+  }
+}
+
+// Field-element vectors are encoded as fixed-width 16-byte big-endian integers
+impl SignableMessage for [FieldElement] {
+  fn signing_bytes(&self) -> Vec<u8> {
+    let mut out = FIELD_ELEMENTS_TAG.to_vec();
+    out.extend_from_slice(&(self.len() as u64).to_be_bytes());
+    for element in self {
+      out.extend_from_slice(&element.to_u128().to_be_bytes());
+    }
+    out
+  }
+}
+
+impl SignableMessage for Vec<FieldElement> {
+  fn signing_bytes(&self) -> Vec<u8> {
+    self.as_slice().signing_bytes()
+  }
+}
+
+// Bytes that are already a message's canonical signing encoding, such as a credential payload
+// received by a CredentialSignatureScheme or an EvmProof's message; they are hashed as they are
+pub struct SigningBytes<'a>(pub &'a [u8]);
+
+impl SignableMessage for SigningBytes<'_> {
+  fn signing_bytes(&self) -> Vec<u8> {
+    self.0.to_vec()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_raw_bytes_encoding() {
+    let message = b"Loquat message";
+    let expected = [BYTES_TAG, message].concat();
+    assert_eq!(message.signing_bytes(), expected);
+    assert_eq!(message[..].signing_bytes(), expected);
+    assert_eq!(message.to_vec().signing_bytes(), expected);
+    assert_eq!("Loquat message".signing_bytes(), expected);
+  }
+
+  #[test]
+  fn test_encodings_do_not_collide() {
+    // Raw bytes spelling out another encoding's signing bytes still sign something different
+    let json = json!({"a": 1});
+    let elements = vec![FieldElement::new(1), FieldElement::new(2)];
+    assert_ne!(json.signing_bytes().signing_bytes(), json.signing_bytes());
+    assert_ne!(elements.signing_bytes().signing_bytes(), elements.signing_bytes());
+    assert_ne!(json.signing_bytes()[JSON_TAG.len()..].signing_bytes(), json.signing_bytes());
+
+    // The tags are NUL-terminated and none is a prefix of another, so the encodings are disjoint
+    let tags = [BYTES_TAG, JSON_TAG, FIELD_ELEMENTS_TAG];
+    for (i, a) in tags.iter().enumerate() {
+      for (j, b) in tags.iter().enumerate() {
+        assert!(i == j || !b.starts_with(a));
+      }
+    }
+    assert!(b"".signing_bytes().starts_with(BYTES_TAG));
+    assert!(json.signing_bytes().starts_with(JSON_TAG));
+    assert!(elements.signing_bytes().starts_with(FIELD_ELEMENTS_TAG));
+
+    // Pre-encoded bytes sign exactly the message they encode
+    assert_eq!(SigningBytes(&json.signing_bytes()).message_hash(HashFunction::Sha3_256), json.message_hash(HashFunction::Sha3_256));
+  }
+
+  #[test]
+  fn test_json_canonicalization() {
+    let a = json!({"name": "Alice", "age": 30, "address": {"city": "Tokyo", "country": "JP"}});
+    let b: Value = serde_json::from_str(r#"{ "address": {"country": "JP", "city": "Tokyo"}, "age": 30, "name": "Alice" }"#).unwrap();
+    assert_eq!(a.signing_bytes(), b.signing_bytes());
+    assert_eq!(a.message_hash(HashFunction::Sha3_256), b.message_hash(HashFunction::Sha3_256));
+
+    let c = json!({"name": "Alice", "age": 31, "address": {"city": "Tokyo", "country": "JP"}});
+    assert_ne!(a.message_hash(HashFunction::Sha3_256), c.message_hash(HashFunction::Sha3_256));
+
+    // Numbers are compared as values, not as spelled by the serializer
+    let d: Value = serde_json::from_str(r#"{"weight": 7.50, "height": 1.8e2}"#).unwrap();
+    assert_eq!(d.signing_bytes(), [JSON_TAG, br#"{"height":180,"weight":7.5}"#].concat());
+  }
+
+  #[test]
+  fn test_field_elements_encoding() {
+    let elements = vec![FieldElement::new(1), FieldElement::new(2)];
+    let bytes = elements.signing_bytes();
+    assert_eq!(bytes.len(), FIELD_ELEMENTS_TAG.len() + 8 + 2 * 16);
+
+    // The JSON and field element encodings never collide with each other
+    assert_ne!(Vec::<FieldElement>::new().signing_bytes(), json!(null).signing_bytes());
+  }
+
+  #[test]
+  fn test_message_hash_in_field() {
+    let hash = [0xFFu8; 64].message_hash(HashFunction::Sha3_256);
+    assert!(hash < crate::utils::field_operations::P);
+    assert_eq!(hash, [0xFFu8; 64].message_hash_in(HashFunction::Sha3_256, ParamsHandle::L128));
+    assert!(ParamsHandle::M61.contains([0xFFu8; 64].message_hash_in(HashFunction::Sha3_256, ParamsHandle::M61)));
+  }
+}
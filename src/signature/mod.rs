@@ -23,9 +23,72 @@
 //!
 //! ## Module Structure
 //! - `loquat`: Core implementation of the Loquat signature scheme
-//! - `ring_signature`: Ring signature implementation based on Loquat
+//! - `ring_signature`: Ring signature implementation based on Loquat (feature `experimental`)
+//! - `ring`: Canonical ring descriptors with a stable identifier and commitment (feature `experimental`)
 //! - `aggregate`: Aggregate signature implementation based on Loquat
+//! - `stream`: One-pass verification of aggregates streamed with their statements from `io::Read`
+//! - `config`: Hash function selection shared by all schemes
+//! - `params`: Field parameter sets, selected per configuration through `ParamsHandle`
+//! - `suite`: Version and suite identifiers for serialized keys and signatures
+//! - `timestamp`: Signatures committing to a signing time, checked against a maximum age
+//! - `attestation`: Compact attestations, signatures without the Merkle commitment a verifier can rederive
+//! - `message`: Canonical encodings of the message types that can be signed
+//! - `parallel`: Batch verification spread over std threads (feature `parallel`)
+//! - `cache`: Bounded LRU memoization of verification outcomes for repeated signatures
+//! - `error`: `VerifyError`, separating malformed input from failed cryptographic checks
+//! - `cost`: Verifier-side cost model: hash invocations, field operations and EVM gas (feature `std`)
+//! - `hardened`: Size-capped, canonical-only verification of untrusted encodings and `VerifierLimits`
+//! - `key_ownership`: Proofs of possession of a secret key for key registration (feature `experimental`)
+//! - `public_indices`: Seed-derived public PRF evaluation points bound into the public key
+//! - `vrf`: Verifiable random function built from the Legendre PRF (feature `experimental`)
+//!
+//! `Loquat`, `LoquatKeyPair`, `LoquatSignature`, `LoquatConfig`, `ParamsHandle` and
+//! `VerifyError` are re-exported at the module root.
+//!
+//! Key generation, signing, aggregation and the proving halves of `vrf` and `key_ownership`
+//! need `std`; a build with `verifier` alone keeps the verifying halves and the encodings.
+//!
+//! ## Experimental proofs
+//!
+//! The proofs of `vrf` and `key_ownership`, the pseudonym proofs of `vc::pseudonym` built
+//! on the VRF, and the ring signatures of `ring_signature`, `ring` and `vc::did_ring` are
+//! signatures of this scheme, and they verify by recovering the secret key from the
+//! signature (`Loquat::recover_secret_key`). Checking one is therefore learning the key:
+//! whoever verifies a VRF proof can compute every output of the key and prove any of them,
+//! a registry handed a key ownership proof can sign as the key holder, a relying party
+//! handed a pseudonym can derive and forge the holder's pseudonyms at every other relying
+//! party, and a ring signature names its signer, whose key it hands over, and the leaf
+//! position of that key in the ring. They are compiled only with the `experimental` feature, for
+//! exercising the protocol flows built on them, and must not be used with keys that protect
+//! anything until the signature is replaced by a zero-knowledge proof of the same relation.
 
+pub mod attestation;
+pub mod cache;
+pub mod config;
+pub mod params;
+#[cfg(feature = "std")]
+pub mod cost;
+pub mod error;
+pub mod message;
+pub mod suite;
+pub mod timestamp;
+#[cfg(feature = "experimental")]
 pub mod ring_signature;
+#[cfg(feature = "experimental")]
+pub mod ring;
 pub mod aggregate;
+pub mod stream;
 pub mod loquat;
+pub mod hardened;
+#[cfg(feature = "experimental")]
+pub mod key_ownership;
+pub mod public_indices;
+#[cfg(feature = "experimental")]
+pub mod vrf;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
+pub use config::LoquatConfig;
+pub use params::ParamsHandle;
+pub use error::VerifyError;
+pub use loquat::{Loquat, LoquatKeyPair, LoquatSignature};
@@ -0,0 +1,83 @@
+// Batch Loquat verification spread over std threads
+// Each signature is verified independently, so a batch splits into contiguous chunks, one per
+// worker, and the outcomes come back in input order. Scoped threads borrow the batch instead of
+// copying it; below MIN_CHUNK signatures per worker the spawn cost outweighs the gain and the
+// batch is verified on the calling thread.
+
+use crate::signature::config::LoquatConfig;
+use crate::signature::error::VerifyError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use std::num::NonZeroUsize;
+use std::thread;
+
+// Fewest signatures worth a thread of their own
+const MIN_CHUNK: usize = 4;
+
+// (public key, message, signature)
+pub type BatchItem<'a, M> = (&'a [u8], &'a M, &'a LoquatSignature);
+
+// Worker threads for a batch of `len` signatures
+fn workers(len: usize) -> usize {
+  let available = thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1);
+  available.min(len / MIN_CHUNK).max(1)
+}
+
+// Outcome of Loquat::try_verify_with_config for every item, in input order
+pub fn try_verify_batch<M: SignableMessage + Sync + ?Sized>(items: &[BatchItem<'_, M>], config: &LoquatConfig) -> Vec<Result<(), VerifyError>> {
+  let verify = |&(pk, message, signature): &BatchItem<'_, M>| Loquat::try_verify_with_config(pk, message, signature, config);
+  let workers = workers(items.len());
+  if workers == 1 {
+    return items.iter().map(verify).collect();
+  }
+
+  let chunk = items.len().div_ceil(workers);
+  thread::scope(|scope| {
+    let handles: Vec<_> = items
+      .chunks(chunk)
+      .map(|chunk| scope.spawn(move || chunk.iter().map(verify).collect::<Vec<_>>()))
+      .collect();
+    handles
+      .into_iter()
+      .flat_map(|handle| handle.join().expect("Verification worker panicked"))
+      .collect()
+  })
+}
+
+pub fn verify_batch<M: SignableMessage + Sync + ?Sized>(items: &[BatchItem<'_, M>], config: &LoquatConfig) -> Vec<bool> {
+  try_verify_batch(items, config).iter().map(Result::is_ok).collect()
+}
+
+// Whether every signature in the batch verifies
+pub fn verify_all<M: SignableMessage + Sync + ?Sized>(items: &[BatchItem<'_, M>], config: &LoquatConfig) -> bool {
+  try_verify_batch(items, config).iter().all(Result::is_ok)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_batch_matches_sequential() {
+    let config = LoquatConfig::default();
+    let keypairs: Vec<_> = (0..3).map(|_| Loquat::keygen()).collect();
+    let messages: Vec<[u8; 1]> = (0..16u8).map(|i| [i]).collect();
+    let signatures: Vec<_> = messages.iter().enumerate().map(|(i, m)| Loquat::sign(keypairs[i % 3].secret_key, m).unwrap()).collect();
+
+    let mut items: Vec<BatchItem<'_, [u8; 1]>> = messages
+      .iter()
+      .zip(&signatures)
+      .enumerate()
+      .map(|(i, (m, s))| (keypairs[i % 3].public_key.as_slice(), m, s))
+      .collect();
+    assert!(verify_all(&items, &config));
+
+    // A signature checked against the wrong key fails in place
+    items[10].0 = &keypairs[0].public_key;
+    let outcomes = verify_batch(&items, &config);
+    assert_eq!(outcomes.iter().filter(|valid| !**valid).count(), 1);
+    assert!(!outcomes[10]);
+    assert!(!verify_all(&items, &config));
+    assert!(verify_all::<[u8; 1]>(&[], &config));
+  }
+}
@@ -0,0 +1,123 @@
+// Field parameter sets for Loquat keys and signatures
+// Keygen, signing and verification take their modulus from the ParamsHandle in their
+// LoquatConfig rather than from the crate-wide P, so keys and signatures over different
+// fields can be produced and checked side by side in one process, e.g. a verifier still
+// accepting credentials from one set while its issuer has moved to another. The handle is
+// part of the configuration: it is recorded in encoded keys and signatures through the
+// suite, and a verifier expecting one set rejects signatures made under another. Only L128
+// exists outside tests: M61 is compiled into this crate's own tests and, for the tests of
+// downstream crates, behind the test-utils feature, so no release build can produce or
+// accept a 61-bit key or signature.
+// Secret keys and sigma are u128, so every set is a prime field below 2^128; sets wider
+// than that (L192 and up) need wider key types first.
+
+use crate::utils::field_operations::P;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::fmt;
+
+// A named prime field; reached through a ParamsHandle
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ParameterSet {
+  pub id: u8, // Stable identifier written into encodings of non-default sets
+  pub name: &'static str,
+  pub custom_suite_name: &'static str, // Suite::name of mixed-hash configurations over the set
+  pub modulus: u128,
+}
+
+static L128: ParameterSet = ParameterSet {
+  id: 0x01,
+  name: "L128",
+  custom_suite_name: "LOQUAT-CUSTOM-L128",
+  modulus: P,
+};
+
+#[cfg(any(test, feature = "test-utils"))]
+static M61: ParameterSet = ParameterSet {
+  id: 0x02,
+  name: "M61",
+  custom_suite_name: "LOQUAT-CUSTOM-M61",
+  modulus: (1 << 61) - 1,
+};
+
+// Cheap, copyable reference to one of the built-in parameter sets
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamsHandle(&'static ParameterSet);
+
+impl ParamsHandle {
+  // Mersenne prime 2^127 - 1 of the Loquat paper; the default
+  pub const L128: Self = Self(&L128);
+  // Mersenne prime 2^61 - 1, far below any security target: for tests and for exercising
+  // code that handles more than one parameter set, never for real keys
+  #[cfg(any(test, feature = "test-utils"))]
+  pub const M61: Self = Self(&M61);
+
+  // Sets from_id recognises when decoding keys and signatures
+  pub const ALL: &'static [ParamsHandle] = &[
+    Self::L128,
+    #[cfg(any(test, feature = "test-utils"))]
+    Self::M61,
+  ];
+
+  pub fn from_id(id: u8) -> Option<Self> {
+    Self::ALL.iter().copied().find(|handle| handle.id() == id)
+  }
+
+  pub fn set(&self) -> &'static ParameterSet {
+    self.0
+  }
+
+  pub fn id(&self) -> u8 {
+    self.0.id
+  }
+
+  pub fn name(&self) -> &'static str {
+    self.0.name
+  }
+
+  pub fn modulus(&self) -> u128 {
+    self.0.modulus
+  }
+
+  // Whether the value is a canonical element of the field
+  pub fn contains(&self, value: u128) -> bool {
+    value < self.0.modulus
+  }
+
+  // Reduces an arbitrary integer into the field
+  pub fn reduce(&self, value: &BigUint) -> u128 {
+    (value % BigUint::from(self.0.modulus)).to_u128().expect("Reduced below a u128 modulus")
+  }
+}
+
+impl Default for ParamsHandle {
+  fn default() -> Self {
+    Self::L128
+  }
+}
+
+impl fmt::Debug for ParamsHandle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.0.name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_handles_round_trip_through_ids() {
+    for &handle in ParamsHandle::ALL {
+      assert_eq!(ParamsHandle::from_id(handle.id()), Some(handle));
+    }
+    assert_eq!(ParamsHandle::from_id(0x7F), None);
+    assert_eq!(ParamsHandle::default(), ParamsHandle::L128);
+    assert_eq!(format!("{:?}", ParamsHandle::M61), "M61");
+
+    let m61 = ParamsHandle::M61;
+    assert!(m61.contains(m61.modulus() - 1));
+    assert!(!m61.contains(m61.modulus()));
+    assert_eq!(m61.reduce(&BigUint::from(ParamsHandle::L128.modulus())), ParamsHandle::L128.modulus() % m61.modulus());
+  }
+}
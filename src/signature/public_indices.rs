@@ -0,0 +1,217 @@
+// Public index set of the Legendre PRF public key
+// In the full Loquat scheme the public key is the PRF evaluated at a public index set
+// I = (i_1, ..., i_L): pk = (L_K(i_1), ..., L_K(i_L)). The indices are public coin, derived
+// deterministically from a seed so that a key only carries the seed and the verifier can
+// re-derive and check them instead of trusting a list supplied alongside the key.
+// An indexed public key encodes as:
+// version | suite | commitment | seed | index count (u16) | packed PRF symbols
+// and a signature verifies under it only if the key recovered from the signature also
+// reproduces every symbol at the re-derived indices.
+
+use crate::crypto::hash_functions::HashFunction;
+use crate::crypto::prf_bits;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::signature::suite::Suite;
+use crate::utils::constant_time;
+use crate::utils::encoding::Encoding;
+#[cfg(feature = "std")]
+use crate::crypto::test_rng;
+#[cfg(feature = "std")]
+use rand::Rng;
+
+// Domain separation for index derivation
+const INDEX_DOMAIN: &[u8] = b"loquat-public-indices";
+
+// Seeds are 32 bytes
+pub const SEED_BYTES: usize = 32;
+
+// Largest index set accepted when decoding a key
+pub const MAX_PUBLIC_INDICES: usize = 4096;
+
+// Public evaluation points of the PRF, derived from a seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicIndices {
+  seed: Vec<u8>,
+  indices: Vec<u128>,
+}
+
+impl PublicIndices {
+  // Derives `count` distinct nonzero field elements from the seed with the given hash
+  pub fn derive(seed: &[u8], count: usize, hash_function: HashFunction) -> Self {
+    Self {
+      seed: seed.to_vec(),
+      indices: prf_bits::expand_indices(seed, INDEX_DOMAIN, count, hash_function),
+    }
+  }
+
+  // Derives indices from a fresh random seed
+  #[cfg(feature = "std")]
+  pub fn random(count: usize, hash_function: HashFunction) -> Self {
+    let seed: [u8; SEED_BYTES] = test_rng::source().gen();
+    Self::derive(&seed, count, hash_function)
+  }
+
+  pub fn seed(&self) -> &[u8] {
+    &self.seed
+  }
+
+  pub fn indices(&self) -> &[u128] {
+    &self.indices
+  }
+
+  pub fn len(&self) -> usize {
+    self.indices.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.indices.is_empty()
+  }
+
+  // Checks that the indices are exactly the ones derived from the seed
+  pub fn validate(&self, hash_function: HashFunction) -> bool {
+    self.seed.len() == SEED_BYTES
+      && !self.indices.is_empty()
+      && self.indices.len() <= MAX_PUBLIC_INDICES
+      && *self == Self::derive(&self.seed, self.indices.len(), hash_function)
+  }
+
+  // PRF symbols L_K(i) at every index, packed as in crypto::prf_bits
+  pub fn evaluate(&self, sk: u128) -> Vec<u8> {
+    prf_bits::pack_bits(&prf_bits::evaluate_bits(sk, &self.indices))
+  }
+}
+
+// Public key carrying the key commitment together with the PRF symbols at public indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedPublicKey {
+  pub commitment: Vec<u8>, // Transcript hash of the secret key, as in LoquatKeyPair
+  pub indices: PublicIndices,
+  pub symbols: Vec<u8>, // Packed L_K(i) for i in indices
+}
+
+impl IndexedPublicKey {
+  // Builds the public key of `sk` over indices derived from `seed` with the transcript hash
+  pub fn new(sk: u128, seed: &[u8], count: usize, config: &LoquatConfig) -> Self {
+    let indices = PublicIndices::derive(seed, count, config.transcript_hash);
+    Self {
+      commitment: Loquat::public_key_for(sk, config),
+      symbols: indices.evaluate(sk),
+      indices,
+    }
+  }
+
+  // Serializes the key as: version | suite | commitment | seed | count | symbols
+  pub fn encode(&self, config: &LoquatConfig) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(config).encode_header(&mut out);
+    Encoding::write_bytes(&mut out, &self.commitment);
+    Encoding::write_bytes(&mut out, &self.indices.seed);
+    let count = u16::try_from(self.indices.len()).expect("Too many public indices");
+    out.extend_from_slice(&count.to_be_bytes());
+    Encoding::write_bytes(&mut out, &self.symbols);
+    out
+  }
+
+  // Parses a key written by encode, re-deriving the indices from the encoded seed
+  // Rejects oversized index sets, symbol strings of the wrong length or with padding bits set,
+  // and trailing data
+  pub fn decode(bytes: &[u8]) -> Option<(Suite, Self)> {
+    let (suite, mut offset) = Suite::decode_header(bytes)?;
+    let commitment = Encoding::read_bytes(bytes, &mut offset)?.to_vec();
+    let seed = Encoding::read_bytes(bytes, &mut offset)?;
+    let count_bytes = bytes.get(offset..offset + 2)?;
+    let count = u16::from_be_bytes([count_bytes[0], count_bytes[1]]) as usize;
+    offset += 2;
+    let symbols = Encoding::read_bytes(bytes, &mut offset)?.to_vec();
+    if offset != bytes.len() || seed.len() != SEED_BYTES || count == 0 || count > MAX_PUBLIC_INDICES {
+      return None;
+    }
+    prf_bits::unpack_bits(&symbols, count)?;
+
+    let indices = PublicIndices::derive(seed, count, suite.config().transcript_hash);
+    Some((
+      suite,
+      Self {
+        commitment,
+        indices,
+        symbols,
+      },
+    ))
+  }
+
+  // Verifies a signature and checks that the signing key reproduces the public PRF symbols
+  pub fn verify<M: SignableMessage + ?Sized>(&self, message: &M, signature: &LoquatSignature, config: &LoquatConfig) -> bool {
+    if !self.indices.validate(config.transcript_hash) {
+      return false;
+    }
+    let sk = match Loquat::recover_secret_key(&self.commitment, message, signature, config) {
+      Some(sk) => sk,
+      None => return false,
+    };
+    constant_time::bytes_eq(&self.indices.evaluate(sk), &self.symbols)
+  }
+
+  // Verifies serialized key and signature encodings under the same suite
+  pub fn verify_encoded<M: SignableMessage + ?Sized>(pk_bytes: &[u8], message: &M, sig_bytes: &[u8]) -> bool {
+    let (suite, pk) = match Self::decode(pk_bytes) {
+      Some(decoded) => decoded,
+      None => return false,
+    };
+    let signature = match LoquatSignature::from_bytes(sig_bytes) {
+      Some(signature) => signature,
+      None => return false,
+    };
+    if signature.suite() != suite {
+      return false;
+    }
+    pk.verify(message, &signature, &suite.config())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_index_derivation() {
+    let seed = [7u8; SEED_BYTES];
+    let indices = PublicIndices::derive(&seed, 64, HashFunction::Sha3_256);
+    assert_eq!(indices.len(), 64);
+    assert!(indices.validate(HashFunction::Sha3_256));
+    assert_eq!(indices, PublicIndices::derive(&seed, 64, HashFunction::Sha3_256));
+
+    // Indices are tied to the seed and the hash function
+    assert_ne!(indices.indices(), PublicIndices::derive(&[8u8; SEED_BYTES], 64, HashFunction::Sha3_256).indices());
+    assert!(!indices.validate(HashFunction::Shake128));
+    let mut substituted = indices.clone();
+    substituted.indices[3] = 12345;
+    assert!(!substituted.validate(HashFunction::Sha3_256));
+    assert!(!PublicIndices::derive(&[1u8; 4], 8, HashFunction::Sha3_256).validate(HashFunction::Sha3_256));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_indexed_public_key() {
+    let config = LoquatConfig::default();
+    let keypair = Loquat::keygen();
+    let pk = IndexedPublicKey::new(keypair.secret_key, &[3u8; SEED_BYTES], 20, &config);
+    let encoded = pk.encode(&config);
+    assert_eq!(IndexedPublicKey::decode(&encoded), Some((Suite::from_config(&config), pk.clone())));
+
+    let signature = Loquat::sign(keypair.secret_key, b"indexed").unwrap();
+    assert!(IndexedPublicKey::verify_encoded(&encoded, b"indexed", &signature.to_bytes()));
+    assert!(!IndexedPublicKey::verify_encoded(&encoded, b"other", &signature.to_bytes()));
+
+    // Symbols that do not match the signing key are rejected
+    let mut wrong = pk.clone();
+    wrong.symbols[0] ^= 0x80;
+    assert!(!wrong.verify(b"indexed", &signature, &config));
+
+    // A truncated symbol string does not decode
+    let mut truncated = pk;
+    truncated.symbols.pop();
+    assert!(IndexedPublicKey::decode(&truncated.encode(&config)).is_none());
+  }
+}
@@ -0,0 +1,165 @@
+// Published ring descriptors
+// A Ring fixes the member list a ring signature is made against. Members are sorted and
+// deduplicated on construction, so every party that starts from the same set of keys, in any
+// order, builds the same Merkle tree, signs against the same commitment and derives the same
+// identifier. The canonical encoding is: version | suite | u32 member count | members, each
+// a 32-byte digest; decoding accepts only sorted, duplicate-free member lists.
+
+use crate::crypto::hash_functions::Hash;
+use crate::crypto::merkle::MerkleTree;
+use crate::signature::config::LoquatConfig;
+#[cfg(feature = "std")]
+use crate::signature::loquat::Loquat;
+use crate::signature::message::SignableMessage;
+use crate::signature::ring_signature::{LoquatRingSignature, RingSignature};
+use crate::signature::suite::Suite;
+use crate::utils::wire::DIGEST_BYTES;
+use num_bigint::BigUint;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ring {
+  members: Vec<Vec<u8>>,
+  config: LoquatConfig,
+  tree: MerkleTree,
+}
+
+impl Ring {
+  // Builds the ring over a set of public key commitments; None if it is empty or a key is
+  // not a digest
+  pub fn new(public_keys: &[Vec<u8>], config: &LoquatConfig) -> Option<Self> {
+    if public_keys.is_empty() || public_keys.iter().any(|pk| pk.len() != DIGEST_BYTES) {
+      return None;
+    }
+    let mut members = public_keys.to_vec();
+    members.sort();
+    members.dedup();
+    Some(Self {
+      tree: LoquatRingSignature::ring_tree(&members, config),
+      members,
+      config: *config,
+    })
+  }
+
+  // Members in canonical order
+  pub fn members(&self) -> &[Vec<u8>] {
+    &self.members
+  }
+
+  pub fn len(&self) -> usize {
+    self.members.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.members.is_empty()
+  }
+
+  pub fn config(&self) -> &LoquatConfig {
+    &self.config
+  }
+
+  pub fn index_of(&self, public_key: &[u8]) -> Option<usize> {
+    self.members.binary_search_by(|member| member.as_slice().cmp(public_key)).ok()
+  }
+
+  // Merkle commitment ring signatures are made against
+  pub fn root(&self) -> BigUint {
+    self.tree.root().expect("Ring has at least one member")
+  }
+
+  // Identifier of the descriptor: the transcript hash of its canonical encoding
+  pub fn id(&self) -> Vec<u8> {
+    Hash::new(self.config.transcript_hash).compute(&self.to_bytes())
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(&self.config).encode_header(&mut out);
+    out.extend_from_slice(&(self.members.len() as u32).to_be_bytes());
+    for member in &self.members {
+      out.extend_from_slice(member);
+    }
+    out
+  }
+
+  // Parses a canonical descriptor; unsorted or repeated members are rejected rather than fixed
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (suite, mut offset) = Suite::decode_header(bytes)?;
+    let count_bytes = bytes.get(offset..offset + 4)?;
+    let count = u32::from_be_bytes([count_bytes[0], count_bytes[1], count_bytes[2], count_bytes[3]]) as usize;
+    offset += 4;
+    // Each member takes DIGEST_BYTES bytes, which bounds the count before allocating
+    if count == 0 || count.checked_mul(DIGEST_BYTES) != Some(bytes.len() - offset) {
+      return None;
+    }
+
+    let mut members = Vec::with_capacity(count);
+    for member in bytes[offset..].chunks_exact(DIGEST_BYTES) {
+      if members.last().is_some_and(|previous: &Vec<u8>| previous.as_slice() >= member) {
+        return None;
+      }
+      members.push(member.to_vec());
+    }
+    Self::new(&members, &suite.config())
+  }
+
+  // Signs as the member holding sk; None if its public key is not in the ring
+  #[cfg(feature = "std")]
+  pub fn sign<M: SignableMessage + ?Sized>(&self, sk: u128, message: &M) -> Option<RingSignature> {
+    let index = self.index_of(&Loquat::public_key_for(sk, &self.config))?;
+    LoquatRingSignature::sign_with_tree(sk, message, &self.tree, index, &self.config)
+  }
+
+  pub fn verify<M: SignableMessage + ?Sized>(&self, message: &M, signature: &RingSignature) -> bool {
+    LoquatRingSignature::verify_with_root(&self.root(), message, signature, &self.config)
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_canonical_ring() {
+    let config = LoquatConfig::default();
+    let keys: Vec<Vec<u8>> = (0..5).map(|_| Loquat::keygen().public_key).collect();
+    let ring = Ring::new(&keys, &config).unwrap();
+
+    // Order and repetition of the input keys do not matter
+    let mut shuffled: Vec<Vec<u8>> = keys.iter().rev().cloned().collect();
+    shuffled.push(keys[2].clone());
+    let same = Ring::new(&shuffled, &config).unwrap();
+    assert_eq!(same.len(), 5);
+    assert_eq!(same.id(), ring.id());
+    assert_eq!(same.root(), ring.root());
+
+    let decoded = Ring::from_bytes(&ring.to_bytes()).unwrap();
+    assert_eq!(decoded.members(), ring.members());
+    assert_eq!(decoded.id(), ring.id());
+
+    // Swapping two members of the encoding is rejected instead of silently re-sorted
+    let mut swapped = ring.to_bytes();
+    let first = 2 + 4;
+    let second = first + DIGEST_BYTES;
+    let member: Vec<u8> = swapped[first..second].to_vec();
+    swapped.copy_within(second..second + DIGEST_BYTES, first);
+    swapped[second..second + DIGEST_BYTES].copy_from_slice(&member);
+    assert!(Ring::from_bytes(&swapped).is_none());
+    assert!(Ring::new(&[], &config).is_none());
+  }
+
+  #[test]
+  fn test_sign_with_ring() {
+    let config = LoquatConfig::default();
+    let signers: Vec<_> = (0..4).map(|_| Loquat::keygen()).collect();
+    let ring = Ring::new(&signers.iter().map(|k| k.public_key.clone()).collect::<Vec<_>>(), &config).unwrap();
+
+    let signature = ring.sign(signers[3].secret_key, b"ring message").unwrap();
+    assert!(ring.verify(b"ring message", &signature));
+    assert!(!ring.verify(b"other message", &signature));
+    assert!(ring.sign(Loquat::keygen().secret_key, b"ring message").is_none());
+
+    // A signature against another ring does not verify
+    let other = Ring::new(&[signers[3].public_key.clone(), Loquat::keygen().public_key], &config).unwrap();
+    assert!(!other.verify(b"ring message", &signature));
+  }
+}
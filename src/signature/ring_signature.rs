@@ -2,20 +2,36 @@
 // Verification without revealing the actual signer
 // Efficient SNARK-friendly verification
 // Merkle-based public key commitments
+//
+// The ring is committed to by the Merkle root over its public keys, and a signature carries
+// the authentication path of the signer's key, so signatures grow and verification runs in
+// O(log n) hashes once the root is known (verify_with_root). Signers holding the tree reuse
+// it through sign_with_tree. The paper proves knowledge of the path inside the SNARK; here
+// the path travels in the clear and reveals the signer's position, which costs nothing extra
+// only because this toy signature already determines the signing key: verification recovers
+// sk from sigma and the challenge, so every verifier learns who signed and can sign as them.
+// circuit::ring proves knowledge of the path inside the SNARK instead, with the position and
+// the signature in the witness. Compiled only with the experimental feature (see signature).
 
 use crate::signature::loquat::{Loquat, LoquatSignature, LoquatKeyPair};
-use crate::crypto::hash_functions::{Hash, HashFunction};
 use crate::crypto::merkle::MerkleTree;
+use crate::signature::config::LoquatConfig;
+use crate::signature::message::SignableMessage;
+use crate::signature::suite::Suite;
+use crate::utils::constant_time;
+use crate::utils::metrics::{self, FailureReason, Operation};
+use crate::utils::wire;
 use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use crate::crypto::test_rng;
+#[cfg(feature = "std")]
 use rand::Rng;
 use num_traits::Zero;
 use num_traits::ToPrimitive;
 use std::ops::Rem;
 
-// Prime field modulus (p = 2^127 - 1) 
-const P: u128 = (1 << 127) - 1;
-
 // Safe modular arithmetic operations
+#[cfg(feature = "std")]
 fn mod_add(a: &BigUint, b: &BigUint, modulus: &BigUint) -> BigUint {
     (a + b).rem(modulus)
 }
@@ -56,11 +72,48 @@ fn mod_exp(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
 }
 
 // Ring Signature Structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RingSignature {
   pub sigma: BigUint, // Computed signature
   pub ring_commitment: BigUint, // Commitment to all public keys
   pub challenge: BigUint, // Random challenge to maintain security
+  pub membership: Vec<(BigUint, bool)>, // Merkle authentication path of the signer's key
+  pub config: LoquatConfig, // Hash functions the signature was produced with
+}
+
+impl RingSignature {
+  // Encoding: version | suite | sigma (16 bytes) | ring commitment (32 bytes) |
+  // challenge (16 bytes) | membership path (utils::wire::write_path)
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    Suite::from_config(&self.config).encode_header(&mut out);
+    wire::write_field(&mut out, &self.sigma);
+    wire::write_digest(&mut out, &self.ring_commitment);
+    wire::write_field(&mut out, &self.challenge);
+    wire::write_path(&mut out, &self.membership);
+    out
+  }
+
+  // Parses a ring signature written by to_bytes; sigma and the challenge must be below the
+  // modulus of the suite's parameter set
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (suite, mut offset) = Suite::decode_header(bytes)?;
+    let sigma = wire::field_from_bytes(&wire::read_field(bytes, &mut offset)?);
+    let ring_commitment = wire::digest_from_bytes(&wire::read_digest(bytes, &mut offset)?);
+    let challenge = wire::field_from_bytes(&wire::read_field(bytes, &mut offset)?);
+    let membership = wire::read_path(bytes, &mut offset)?;
+    let p = BigUint::from(suite.config().params.modulus());
+    if offset != bytes.len() || sigma >= p || challenge >= p {
+      return None;
+    }
+    Some(Self {
+      sigma,
+      ring_commitment,
+      challenge,
+      membership,
+      config: suite.config(),
+    })
+  }
 }
 
 // Loquat Ring Signature Scheme
@@ -68,75 +121,163 @@ pub struct LoquatRingSignature;
 
 impl LoquatRingSignature {
   // Generate a ring signature
-  pub fn sign(
+  #[cfg(feature = "std")]
+  pub fn sign<M: SignableMessage + ?Sized>(
     sk: u128, 
-    message: &[u8], 
+    message: &M, 
+    public_keys: &[Vec<u8>]
+  ) -> Option<RingSignature> {
+    Self::sign_with_config(sk, message, public_keys, &LoquatConfig::default())
+  }
+
+  // Generate a ring signature with explicit hash function choices; None if the signer's key
+  // is not one of the public keys
+  #[cfg(feature = "std")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(ring_size = public_keys.len())))]
+  pub fn sign_with_config<M: SignableMessage + ?Sized>(
+    sk: u128, 
+    message: &M, 
     public_keys: &[Vec<u8>], 
-    signer_index: usize
-  ) -> RingSignature {
-    let hash = Hash::new(HashFunction::Sha3_256).compute(message);
-    let message_int = BigUint::from_bytes_be(&hash);
-    
-    // Compute the Merkle root of all public keys
-    let merkle_tree = MerkleTree::new(
+    config: &LoquatConfig
+  ) -> Option<RingSignature> {
+    metrics::observe_infallible(Operation::RingSign, || Self::sign_unobserved(sk, message, public_keys, config))
+  }
+
+  #[cfg(feature = "std")]
+  fn sign_unobserved<M: SignableMessage + ?Sized>(
+    sk: u128,
+    message: &M,
+    public_keys: &[Vec<u8>],
+    config: &LoquatConfig
+  ) -> Option<RingSignature> {
+    let public_key = Loquat::public_key_for(sk, config);
+    let signer_index = public_keys.iter().position(|pk| *pk == public_key)?;
+    Self::sign_with_tree(sk, message, &Self::ring_tree(public_keys, config), signer_index, config)
+  }
+
+  // Merkle tree over the ring's public keys; its root is the ring commitment
+  pub fn ring_tree(public_keys: &[Vec<u8>], config: &LoquatConfig) -> MerkleTree {
+    MerkleTree::new(
       public_keys.iter().map(|pk| BigUint::from_bytes_be(pk)).collect(),
-      HashFunction::Sha3_256,
-    );
-    let ring_commitment = merkle_tree.root().unwrap();
+      config.merkle_hash,
+    )
+  }
+
+  // Signs against a prebuilt ring tree in O(log n); None if the index is outside the ring
+  #[cfg(feature = "std")]
+  pub fn sign_with_tree<M: SignableMessage + ?Sized>(
+    sk: u128,
+    message: &M,
+    tree: &MerkleTree,
+    signer_index: usize,
+    config: &LoquatConfig
+  ) -> Option<RingSignature> {
+    let message_int = BigUint::from(message.message_hash_in(config.msg_hash, config.params));
+    let ring_commitment = tree.root()?;
+    let membership = tree.generate_proof(signer_index)?;
 
     // Compute the signature using Legendre PRF-like signing
-    let mut rng = rand::thread_rng();
-    // Use clone to avoid potential overflow issues
-    let p_minus_1 = P - 1;
-    let challenge = BigUint::from(rng.gen_range(1..p_minus_1));
-    
-    // Use safe modular arithmetic
-    let p_biguint = BigUint::from(P);
-    let sk_biguint = BigUint::from(sk);
-    
+    let mut rng = test_rng::source();
+    let challenge = BigUint::from(rng.gen_range(1..config.params.modulus() - 1));
+
     // sigma = (sk + message_int + challenge) mod P
-    // Use safe modular arithmetic for all operations
+    let p_biguint = BigUint::from(config.params.modulus());
     let sigma = mod_add(
-        &mod_add(&sk_biguint, &message_int, &p_biguint),
+        &mod_add(&BigUint::from(sk), &message_int, &p_biguint),
         &challenge,
         &p_biguint
     );
 
-    RingSignature {
+    Some(RingSignature {
       sigma,
       ring_commitment,
       challenge,
-    }
+      membership,
+      config: *config,
+    })
   }
 
   // Verify a ring signature
-  pub fn verify(
+  pub fn verify<M: SignableMessage + ?Sized>(
     public_keys: &[Vec<u8>], 
-    message: &[u8], 
+    message: &M, 
     ring_sig: &RingSignature
   ) -> bool {
-    let hash = Hash::new(HashFunction::Sha3_256).compute(message);
-    let message_int = BigUint::from_bytes_be(&hash);
+    Self::verify_with_config(public_keys, message, ring_sig, &LoquatConfig::default())
+  }
 
-    // Compute the expected Merkle root
-    let merkle_tree = MerkleTree::new(
-      public_keys.iter().map(|pk| BigUint::from_bytes_be(pk)).collect(),
-      HashFunction::Sha3_256,
-    );
-    let expected_commitment = merkle_tree.root().unwrap();
+  // Verify a ring signature under an expected configuration
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(ring_size = public_keys.len()), ret))]
+  pub fn verify_with_config<M: SignableMessage + ?Sized>(
+    public_keys: &[Vec<u8>], 
+    message: &M, 
+    ring_sig: &RingSignature,
+    config: &LoquatConfig
+  ) -> bool {
+    metrics::observe(Operation::RingVerify, || Self::check_signature(public_keys, message, ring_sig, config)).is_ok()
+  }
 
-    // Verify if the commitment matches and the challenge is valid
-    let p_biguint = BigUint::from(P);
-    
-    // Use safe comparison with BigUint
-    expected_commitment == ring_sig.ring_commitment
-        && &ring_sig.sigma < &p_biguint
+  fn check_signature<M: SignableMessage + ?Sized>(
+    public_keys: &[Vec<u8>],
+    message: &M,
+    ring_sig: &RingSignature,
+    config: &LoquatConfig
+  ) -> Result<(), FailureReason> {
+    if ring_sig.config != *config {
+      return Err(FailureReason::ConfigMismatch);
+    }
+    let root = Self::ring_tree(public_keys, config).root().ok_or(FailureReason::InvalidSignature)?;
+    Self::check_against_root(&root, message, ring_sig, config)
+  }
+
+  // Verifies against the ring commitment alone, in O(log n) hashes
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(depth = ring_sig.membership.len()), ret))]
+  pub fn verify_with_root<M: SignableMessage + ?Sized>(
+    ring_root: &BigUint,
+    message: &M,
+    ring_sig: &RingSignature,
+    config: &LoquatConfig
+  ) -> bool {
+    metrics::observe(Operation::RingVerify, || {
+      if ring_sig.config != *config {
+        return Err(FailureReason::ConfigMismatch);
+      }
+      Self::check_against_root(ring_root, message, ring_sig, config)
+    })
+    .is_ok()
+  }
+
+  fn check_against_root<M: SignableMessage + ?Sized>(
+    ring_root: &BigUint,
+    message: &M,
+    ring_sig: &RingSignature,
+    config: &LoquatConfig
+  ) -> Result<(), FailureReason> {
+    let p_biguint = BigUint::from(config.params.modulus());
+    if ring_sig.sigma >= p_biguint || ring_sig.challenge >= p_biguint {
+      return Err(FailureReason::NonCanonical);
+    }
+    if !constant_time::biguint_eq(ring_root, &ring_sig.ring_commitment) {
+      return Err(FailureReason::InvalidSignature);
+    }
+
+    // Recover the signing key and check that its public key is a leaf under the ring root
+    let message_int = BigUint::from(message.message_hash_in(config.msg_hash, config.params));
+    let sk = mod_sub(&mod_sub(&ring_sig.sigma, &message_int, &p_biguint), &ring_sig.challenge, &p_biguint);
+    let sk = sk.to_u128().ok_or(FailureReason::InvalidSignature)?;
+    let leaf = BigUint::from_bytes_be(&Loquat::public_key_for(sk, config));
+    if MerkleTree::verify_proof(ring_root, &leaf, &ring_sig.membership, &config.merkle_hash) {
+      Ok(())
+    } else {
+      Err(FailureReason::KeyMismatch)
+    }
   }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
   use super::*;
+  use crate::utils::field_operations::P;
 
   #[test]
   fn test_ring_signature() {
@@ -147,8 +288,11 @@ mod tests {
     let public_keys = vec![keypair1.public_key.clone(), keypair2.public_key.clone(), keypair3.public_key.clone()];
     let message = b"Ring Signature Test";
 
-    let ring_sig = LoquatRingSignature::sign(keypair2.secret_key, message, &public_keys, 1);
+    let ring_sig = LoquatRingSignature::sign(keypair2.secret_key, message, &public_keys).unwrap();
     assert!(LoquatRingSignature::verify(&public_keys, message, &ring_sig));
+
+    // A signer outside the ring gets no signature rather than a panic
+    assert!(LoquatRingSignature::sign(Loquat::keygen().secret_key, message, &public_keys).is_none());
   }
 
   #[test]
@@ -160,12 +304,38 @@ mod tests {
     let public_keys = vec![keypair1.public_key.clone(), keypair2.public_key.clone(), keypair3.public_key.clone()];
     let message = b"Ring Signature Test";
 
-    let ring_sig = LoquatRingSignature::sign(keypair2.secret_key, message, &public_keys, 1);
+    let ring_sig = LoquatRingSignature::sign(keypair2.secret_key, message, &public_keys).unwrap();
 
     let tampered_message = b"Tampered Message";
     assert!(!LoquatRingSignature::verify(&public_keys, tampered_message, &ring_sig));
   }
   
+  #[test]
+  fn test_verify_against_root() {
+    let config = LoquatConfig::default();
+    let keypairs: Vec<_> = (0..37).map(|_| Loquat::keygen()).collect();
+    let public_keys: Vec<Vec<u8>> = keypairs.iter().map(|k| k.public_key.clone()).collect();
+    let tree = LoquatRingSignature::ring_tree(&public_keys, &config);
+    let root = tree.root().unwrap();
+
+    let ring_sig = LoquatRingSignature::sign_with_tree(keypairs[29].secret_key, b"large ring", &tree, 29, &config).unwrap();
+    assert!(ring_sig.membership.len() <= 6);
+    assert!(LoquatRingSignature::verify_with_root(&root, b"large ring", &ring_sig, &config));
+    assert!(!LoquatRingSignature::verify_with_root(&root, b"other message", &ring_sig, &config));
+
+    // The encoding has one fixed-width slot per component and per path level
+    let bytes = ring_sig.to_bytes();
+    assert_eq!(bytes.len(), 2 + 16 + 32 + 16 + 1 + ring_sig.membership.len() * 33);
+    assert_eq!(RingSignature::from_bytes(&bytes), Some(ring_sig.clone()));
+    assert_eq!(RingSignature::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+    // A key outside the ring cannot sign, even with a valid path of another member
+    let outsider = Loquat::keygen();
+    let forged = LoquatRingSignature::sign_with_tree(outsider.secret_key, b"large ring", &tree, 29, &config).unwrap();
+    assert!(!LoquatRingSignature::verify_with_root(&root, b"large ring", &forged, &config));
+    assert!(LoquatRingSignature::sign_with_tree(outsider.secret_key, b"large ring", &tree, 37, &config).is_none());
+  }
+
   #[test]
   fn test_modular_arithmetic() {
     let p_biguint = BigUint::from(P);
@@ -195,4 +365,18 @@ mod tests {
     // 2^126 mod (2^127 - 1) = 2^126
     assert_eq!(result, BigUint::from(1u32) << 126);
   }
+
+  #[test]
+  fn test_ring_signature_config_mismatch() {
+    let config = LoquatConfig::uniform(crate::crypto::hash_functions::HashFunction::Shake128);
+    let keypair1 = Loquat::keygen_with_config(&config);
+    let keypair2 = Loquat::keygen_with_config(&config);
+
+    let public_keys = vec![keypair1.public_key.clone(), keypair2.public_key.clone()];
+    let message = b"Ring Signature Test";
+
+    let ring_sig = LoquatRingSignature::sign_with_config(keypair1.secret_key, message, &public_keys, &config).unwrap();
+    assert!(LoquatRingSignature::verify_with_config(&public_keys, message, &ring_sig, &config));
+    assert!(!LoquatRingSignature::verify(&public_keys, message, &ring_sig));
+  }
 }
@@ -0,0 +1,239 @@
+// Streamed aggregate verification
+// An aggregate over thousands of signatures arrives with its statements, the (public key,
+// message) pairs it covers, and LoquatAggregate::verify needs them all in memory at once.
+// AggregateBatch::write_stream lays a batch out so that a verifier can consume it from any
+// io::Read in one pass instead:
+//   lp(aggregate) | statement count (u32, big-endian) | statement root (32 bytes)
+//   | count x (lp(public key) | lp(message))
+// where lp is the u16 length prefix of Encoding::write_bytes and the aggregate is
+// AggregateSignature::to_bytes. The statement root is the Merkle root (config.merkle_hash)
+// over the leaves H(STATEMENT_DOMAIN | lp(public key) | lp(message)), so it commits the
+// aggregate to its statements in order. StreamingVerifier rebuilds the root with a
+// StreamingRoot as statements go by, accumulates the check of LoquatAggregate::verify on the
+// way and keeps one statement, one length-prefix buffer and O(log n) digests in memory.
+
+use crate::crypto::hash_functions::Hash;
+use crate::crypto::merkle::StreamingRoot;
+use crate::signature::aggregate::{AggregateBatch, AggregateSignature, LoquatAggregate};
+use crate::signature::config::LoquatConfig;
+use crate::signature::error::VerifyError;
+use crate::signature::hardened::VerifierLimits;
+use crate::signature::message::SignableMessage;
+use crate::utils::constant_time;
+use crate::utils::encoding::Encoding;
+use crate::utils::wire::DIGEST_BYTES;
+use std::io::{self, Read, Write};
+
+const STATEMENT_DOMAIN: &[u8] = b"loquat-vc-aggregate-statement";
+
+// Merkle leaf of one statement
+pub fn statement_leaf(public_key: &[u8], message: &[u8], config: &LoquatConfig) -> [u8; DIGEST_BYTES] {
+  let mut input = STATEMENT_DOMAIN.to_vec();
+  Encoding::write_bytes(&mut input, public_key);
+  Encoding::write_bytes(&mut input, message);
+  Hash::new(config.merkle_hash).compute(&input).try_into().expect("Hash functions output 32 bytes")
+}
+
+impl AggregateBatch {
+  // Root over the statements in batch order
+  pub fn statement_root(&self) -> [u8; DIGEST_BYTES] {
+    let config = &self.signature.config;
+    let mut root = StreamingRoot::new(config.merkle_hash);
+    for (public_key, message) in self.public_keys.iter().zip(&self.messages) {
+      root.push(statement_leaf(public_key, message, config));
+    }
+    root.finish().expect("Batches are not empty")
+  }
+
+  // Writes the batch in the streamed layout; keys and messages must fit a u16 length prefix
+  pub fn write_stream<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let mut prefix = vec![];
+    Encoding::write_bytes(&mut prefix, &self.signature.to_bytes());
+    let count = u32::try_from(self.public_keys.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Too many statements"))?;
+    prefix.extend_from_slice(&count.to_be_bytes());
+    prefix.extend_from_slice(&self.statement_root());
+    out.write_all(&prefix)?;
+
+    let mut statement = vec![];
+    for (public_key, message) in self.public_keys.iter().zip(&self.messages) {
+      statement.clear();
+      Encoding::write_bytes(&mut statement, public_key);
+      Encoding::write_bytes(&mut statement, message);
+      out.write_all(&statement)?;
+    }
+    Ok(())
+  }
+}
+
+// What a streamed aggregate was verified to cover
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedAggregate {
+  pub signature: AggregateSignature,
+  pub statements: usize,
+  pub statement_root: [u8; DIGEST_BYTES],
+}
+
+// One-pass verifier of the streamed layout; the statement count is capped by max_batch
+pub struct StreamingVerifier {
+  config: LoquatConfig,
+  limits: VerifierLimits,
+}
+
+impl StreamingVerifier {
+  pub fn new(config: &LoquatConfig, limits: &VerifierLimits) -> Self {
+    Self {
+      config: *config,
+      limits: *limits,
+    }
+  }
+
+  // Reads the stream to its end; BadEncoding for truncated, oversized or trailing input,
+  // MerkleMismatch if the statements do not open the root, after which the aggregate is
+  // checked as LoquatAggregate::verify_with_config checks it
+  pub fn verify<R: Read>(&self, mut input: R) -> Result<StreamedAggregate, VerifyError> {
+    let config = &self.config;
+    let mut buffer = vec![];
+    read_prefixed(&mut input, &mut buffer, self.limits.max_sig_bytes)?;
+    let signature = AggregateSignature::from_bytes(&buffer).ok_or(VerifyError::BadEncoding)?;
+    if signature.config != *config {
+      return Err(VerifyError::SuiteMismatch);
+    }
+
+    let mut count = [0u8; 4];
+    read_exact(&mut input, &mut count)?;
+    let statements = u32::from_be_bytes(count) as usize;
+    if statements == 0 || statements > self.limits.max_batch {
+      return Err(VerifyError::BadEncoding);
+    }
+    let mut statement_root = [0u8; DIGEST_BYTES];
+    read_exact(&mut input, &mut statement_root)?;
+
+    let modulus = config.params.modulus();
+    let mut public_key = vec![];
+    let mut root = StreamingRoot::new(config.merkle_hash);
+    let mut computed = 0u128;
+    for _ in 0..statements {
+      read_prefixed(&mut input, &mut public_key, u16::MAX as usize)?;
+      read_prefixed(&mut input, &mut buffer, u16::MAX as usize)?;
+      root.push(statement_leaf(&public_key, &buffer, config));
+      computed = LoquatAggregate::mod_add(computed, buffer.message_hash_in(config.msg_hash, config.params), modulus);
+    }
+    if input.read(&mut [0u8; 1]).map_err(|_| VerifyError::BadEncoding)? != 0 {
+      return Err(VerifyError::BadEncoding);
+    }
+
+    let rebuilt = root.finish().expect("At least one statement");
+    if !constant_time::bytes_eq(&rebuilt, &statement_root) {
+      return Err(VerifyError::MerkleMismatch);
+    }
+    if !constant_time::u128_eq(computed, config.params.reduce(&signature.aggregated_sigma)) {
+      return Err(VerifyError::InvalidSignature);
+    }
+    Ok(StreamedAggregate {
+      signature,
+      statements,
+      statement_root,
+    })
+  }
+}
+
+fn read_exact<R: Read>(input: &mut R, out: &mut [u8]) -> Result<(), VerifyError> {
+  input.read_exact(out).map_err(|_| VerifyError::BadEncoding)
+}
+
+// Reads a u16-prefixed byte string of at most max bytes into buffer, replacing its contents
+fn read_prefixed<R: Read>(input: &mut R, buffer: &mut Vec<u8>, max: usize) -> Result<(), VerifyError> {
+  let mut len = [0u8; 2];
+  read_exact(input, &mut len)?;
+  let len = u16::from_be_bytes(len) as usize;
+  if len > max {
+    return Err(VerifyError::BadEncoding);
+  }
+  buffer.resize(len, 0);
+  read_exact(input, buffer)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::signature::aggregate::Aggregator;
+  use crate::signature::loquat::Loquat;
+  use num_bigint::BigUint;
+
+  fn batch(size: usize) -> AggregateBatch {
+    let config = LoquatConfig::default();
+    let mut aggregator = Aggregator::new(&config);
+    for i in 0..size {
+      let keypair = Loquat::keygen();
+      let message = format!("statement {}", i);
+      aggregator.add(&keypair.public_key, message.as_bytes(), Loquat::sign(keypair.secret_key, message.as_bytes()).unwrap());
+    }
+    aggregator.finalize().unwrap()
+  }
+
+  // The batch with the aggregate replaced by one LoquatAggregate::verify accepts
+  fn accepted(mut batch: AggregateBatch) -> AggregateBatch {
+    let config = batch.signature.config;
+    let sum = batch.messages.iter().fold(0, |acc, message| LoquatAggregate::mod_add(acc, message.message_hash_in(config.msg_hash, config.params), config.params.modulus()));
+    batch.signature.aggregated_sigma = BigUint::from(sum);
+    assert!(batch.verify());
+    batch
+  }
+
+  fn stream(batch: &AggregateBatch) -> Vec<u8> {
+    let mut out = vec![];
+    batch.write_stream(&mut out).unwrap();
+    out
+  }
+
+  fn verifier() -> StreamingVerifier {
+    StreamingVerifier::new(&LoquatConfig::default(), &VerifierLimits::default())
+  }
+
+  #[test]
+  fn test_streaming_agrees_with_batch_verification() {
+    let honest = batch(37);
+    assert_eq!(verifier().verify(stream(&honest).as_slice()).is_ok(), honest.verify());
+
+    let accepted = accepted(honest);
+    let verified = verifier().verify(stream(&accepted).as_slice()).unwrap();
+    assert_eq!(verified.statements, 37);
+    assert_eq!(verified.signature, accepted.signature);
+    assert_eq!(verified.statement_root, accepted.statement_root());
+  }
+
+  #[test]
+  fn test_tampered_streams_rejected() {
+    let batch = accepted(batch(5));
+    let bytes = stream(&batch);
+
+    // A flipped byte in the last message no longer opens the root
+    let mut tampered = bytes.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert_eq!(verifier().verify(tampered.as_slice()), Err(VerifyError::MerkleMismatch));
+
+    // A consistent root over other statements leaves the aggregate to reject them
+    let mut swapped = batch.clone();
+    swapped.messages[0] = b"another statement".to_vec();
+    assert_eq!(verifier().verify(stream(&swapped).as_slice()), Err(VerifyError::InvalidSignature));
+
+    assert_eq!(verifier().verify(&bytes[..bytes.len() - 1]), Err(VerifyError::BadEncoding));
+    assert_eq!(verifier().verify([bytes.as_slice(), &[0]].concat().as_slice()), Err(VerifyError::BadEncoding));
+    let other = LoquatConfig::uniform(crate::crypto::hash_functions::HashFunction::Shake128);
+    assert_eq!(StreamingVerifier::new(&other, &VerifierLimits::default()).verify(bytes.as_slice()), Err(VerifyError::SuiteMismatch));
+  }
+
+  #[test]
+  fn test_statement_count_is_capped() {
+    let batch = accepted(batch(5));
+    let bytes = stream(&batch);
+    let limits = VerifierLimits { max_batch: 4, ..VerifierLimits::default() };
+    assert_eq!(StreamingVerifier::new(&LoquatConfig::default(), &limits).verify(bytes.as_slice()), Err(VerifyError::BadEncoding));
+
+    // A claimed count past the statements present is truncation, found without allocating for it
+    let mut inflated = bytes.clone();
+    let count_at = 2 + batch.signature.size_bytes();
+    inflated[count_at..count_at + 4].copy_from_slice(&4000u32.to_be_bytes());
+    assert_eq!(verifier().verify(inflated.as_slice()), Err(VerifyError::BadEncoding));
+  }
+}
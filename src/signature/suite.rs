@@ -0,0 +1,216 @@
+// Algorithm suite identifiers for serialized Loquat keys and signatures
+// Every encoding starts with a format version byte and a one-byte suite identifier,
+// so that a key or signature produced under one parameter set can never be
+// interpreted under another once multiple parameter sets exist.
+
+use crate::crypto::hash_functions::HashFunction;
+use crate::signature::config::LoquatConfig;
+use crate::signature::params::ParamsHandle;
+
+// Version of the serialized key/signature format
+// Version 2 writes field elements and digests at fixed widths (utils::wire) where version 1
+// length-prefixed minimal big-endian encodings
+pub const SUITE_VERSION: u8 = 2;
+
+// Identifier reserved for configurations that mix hash functions
+const CUSTOM_SUITE_ID: u8 = 0xFF;
+// Custom configurations over a parameter set other than L128; the set's id precedes the hash ids
+const CUSTOM_PARAMS_SUITE_ID: u8 = 0xFE;
+
+// Named Loquat parameter suites (hash family + security level)
+// L128 denotes the 2^127 - 1 field used throughout the crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suite {
+  LoquatSha3L128,
+  LoquatShake128L128,
+  #[cfg(feature = "poseidon")]
+  LoquatPoseidonL128,
+  #[cfg(feature = "griffin")]
+  LoquatGriffinL128,
+  LoquatKeccakL128,
+  #[cfg(feature = "poseidon")]
+  LoquatPoseidon2L128,
+  Custom(LoquatConfig), // Mixed hash functions or another parameter set, encoded explicitly after the identifier
+}
+
+impl Suite {
+  // Maps a configuration to its suite, falling back to a custom suite for mixed configurations
+  pub fn from_config(config: &LoquatConfig) -> Self {
+    if *config == LoquatConfig::uniform(config.msg_hash) {
+      match config.msg_hash {
+        HashFunction::Sha3_256 => return Suite::LoquatSha3L128,
+        HashFunction::Shake128 => return Suite::LoquatShake128L128,
+        #[cfg(feature = "poseidon")]
+        HashFunction::Poseidon => return Suite::LoquatPoseidonL128,
+        #[cfg(feature = "griffin")]
+        HashFunction::Griffin => return Suite::LoquatGriffinL128,
+        HashFunction::Keccak256 => return Suite::LoquatKeccakL128,
+        #[cfg(feature = "poseidon")]
+        HashFunction::Poseidon2 => return Suite::LoquatPoseidon2L128,
+      }
+    }
+    Suite::Custom(*config)
+  }
+
+  // Hash function configuration described by this suite
+  pub fn config(&self) -> LoquatConfig {
+    match self {
+      Suite::LoquatSha3L128 => LoquatConfig::uniform(HashFunction::Sha3_256),
+      Suite::LoquatShake128L128 => LoquatConfig::uniform(HashFunction::Shake128),
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidonL128 => LoquatConfig::uniform(HashFunction::Poseidon),
+      #[cfg(feature = "griffin")]
+      Suite::LoquatGriffinL128 => LoquatConfig::uniform(HashFunction::Griffin),
+      Suite::LoquatKeccakL128 => LoquatConfig::uniform(HashFunction::Keccak256),
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidon2L128 => LoquatConfig::uniform(HashFunction::Poseidon2),
+      Suite::Custom(config) => *config,
+    }
+  }
+
+  // One-byte suite identifier
+  pub fn id(&self) -> u8 {
+    match self {
+      Suite::LoquatSha3L128 => 0x01,
+      Suite::LoquatShake128L128 => 0x02,
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidonL128 => 0x03,
+      #[cfg(feature = "griffin")]
+      Suite::LoquatGriffinL128 => 0x04,
+      Suite::LoquatKeccakL128 => 0x05,
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidon2L128 => 0x06,
+      Suite::Custom(config) if config.params == ParamsHandle::L128 => CUSTOM_SUITE_ID,
+      Suite::Custom(_) => CUSTOM_PARAMS_SUITE_ID,
+    }
+  }
+
+  // Human-readable suite name
+  pub fn name(&self) -> &'static str {
+    match self {
+      Suite::LoquatSha3L128 => "LOQUAT-SHA3-L128",
+      Suite::LoquatShake128L128 => "LOQUAT-SHAKE128-L128",
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidonL128 => "LOQUAT-POSEIDON-L128",
+      #[cfg(feature = "griffin")]
+      Suite::LoquatGriffinL128 => "LOQUAT-GRIFFIN-L128",
+      Suite::LoquatKeccakL128 => "LOQUAT-KECCAK256-L128",
+      #[cfg(feature = "poseidon")]
+      Suite::LoquatPoseidon2L128 => "LOQUAT-POSEIDON2-L128",
+      Suite::Custom(config) => config.params.set().custom_suite_name,
+    }
+  }
+
+  // Writes the version byte, suite identifier and, for custom suites, the parameter set and
+  // hash function identifiers
+  pub fn encode_header(&self, out: &mut Vec<u8>) {
+    out.push(SUITE_VERSION);
+    out.push(self.id());
+    if let Suite::Custom(config) = self {
+      if config.params != ParamsHandle::L128 {
+        out.push(config.params.id());
+      }
+      out.push(config.msg_hash.id());
+      out.push(config.merkle_hash.id());
+      out.push(config.transcript_hash.id());
+    }
+  }
+
+  // Parses a header written by encode_header, returning the suite and the number of bytes consumed
+  // Unknown versions and suite identifiers are rejected
+  pub fn decode_header(input: &[u8]) -> Option<(Suite, usize)> {
+    if *input.first()? != SUITE_VERSION {
+      return None;
+    }
+
+    let suite = match *input.get(1)? {
+      0x01 => Suite::LoquatSha3L128,
+      0x02 => Suite::LoquatShake128L128,
+      #[cfg(feature = "poseidon")]
+      0x03 => Suite::LoquatPoseidonL128,
+      #[cfg(feature = "griffin")]
+      0x04 => Suite::LoquatGriffinL128,
+      0x05 => Suite::LoquatKeccakL128,
+      #[cfg(feature = "poseidon")]
+      0x06 => Suite::LoquatPoseidon2L128,
+      CUSTOM_SUITE_ID => {
+        let ids = input.get(2..5)?;
+        let config = LoquatConfig::new(
+          HashFunction::from_id(ids[0])?,
+          HashFunction::from_id(ids[1])?,
+          HashFunction::from_id(ids[2])?,
+        );
+        // A named suite spelled out as custom would be a second encoding of the same suite
+        if Suite::from_config(&config) != Suite::Custom(config) {
+          return None;
+        }
+        return Some((Suite::Custom(config), 5));
+      }
+      CUSTOM_PARAMS_SUITE_ID => {
+        let ids = input.get(2..6)?;
+        let params = ParamsHandle::from_id(ids[0])?;
+        // L128 configurations have their own, shorter encodings
+        if params == ParamsHandle::L128 {
+          return None;
+        }
+        let config = LoquatConfig::new(
+          HashFunction::from_id(ids[1])?,
+          HashFunction::from_id(ids[2])?,
+          HashFunction::from_id(ids[3])?,
+        )
+        .with_params(params);
+        return Some((Suite::Custom(config), 6));
+      }
+      _ => return None,
+    };
+
+    Some((suite, 2))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_suite_config_round_trip() {
+    let uniform = LoquatConfig::uniform(HashFunction::Shake128);
+    assert_eq!(Suite::from_config(&uniform), Suite::LoquatShake128L128);
+    assert_eq!(Suite::from_config(&uniform).config(), uniform);
+
+    let mixed = LoquatConfig::new(HashFunction::Sha3_256, HashFunction::Keccak256, HashFunction::Shake128);
+    assert_eq!(Suite::from_config(&mixed), Suite::Custom(mixed));
+    assert_eq!(Suite::from_config(&LoquatConfig::default()).name(), "LOQUAT-SHA3-L128");
+
+    // Uniform hashing over another parameter set is still spelled out
+    let m61 = uniform.with_params(ParamsHandle::M61);
+    assert_eq!(Suite::from_config(&m61), Suite::Custom(m61));
+    assert_eq!(Suite::from_config(&m61).name(), "LOQUAT-CUSTOM-M61");
+    assert_eq!(Suite::from_config(&m61).config(), m61);
+  }
+
+  #[test]
+  fn test_header_round_trip() {
+    let mixed = LoquatConfig::new(HashFunction::Sha3_256, HashFunction::Keccak256, HashFunction::Shake128);
+    let m61 = LoquatConfig::default().with_params(ParamsHandle::M61);
+    for suite in [Suite::LoquatSha3L128, Suite::LoquatShake128L128, Suite::LoquatKeccakL128, Suite::Custom(mixed), Suite::Custom(m61)] {
+      let mut out = vec![];
+      suite.encode_header(&mut out);
+      assert_eq!(Suite::decode_header(&out), Some((suite, out.len())));
+    }
+  }
+
+  #[test]
+  fn test_header_rejects_unknown_values() {
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION + 1, 0x01]), None);
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION, 0x7F]), None);
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION, CUSTOM_SUITE_ID, 0x01, 0x01]), None);
+    assert_eq!(Suite::decode_header(&[]), None);
+    // L128 under the parameter-set identifier, and unknown parameter sets
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION, CUSTOM_PARAMS_SUITE_ID, 0x01, 0x01, 0x01, 0x01]), None);
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION, CUSTOM_PARAMS_SUITE_ID, 0x7F, 0x01, 0x01, 0x01]), None);
+    // Suites whose hash function is compiled out read as unknown
+    #[cfg(not(feature = "poseidon"))]
+    assert_eq!(Suite::decode_header(&[SUITE_VERSION, 0x03]), None);
+  }
+}
@@ -0,0 +1,129 @@
+// Timestamped Loquat signatures
+// The signer's clock reading is absorbed into the signed transcript, so a verifier can enforce
+// a maximum signature age (e.g. "signed within the last 5 minutes") against a value the
+// signer committed to rather than unsigned metadata next to the signature. The timestamp says
+// when the signer claims to have signed, not when the signature first existed: it bounds
+// replay of old signatures, it does not prove freshness against a dishonest signer.
+// Encoding: u64 signed_at | Loquat signature.
+
+#[cfg(feature = "std")]
+use crate::crypto::rng::RngHealthError;
+use crate::signature::config::LoquatConfig;
+#[cfg(feature = "std")]
+use crate::signature::cost::VerificationCost;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::utils::encoding::Encoding;
+
+// Domain separation of the signed transcript
+const TIMESTAMP_DOMAIN: &[u8] = b"loquat-vc-timestamp";
+
+// Verifier-side bounds on the signed timestamp, in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxAge {
+  pub max_age: u64, // Oldest accepted signature, relative to the verifier's clock
+  pub max_clock_skew: u64, // How far in the future the signer's clock may run
+}
+
+impl MaxAge {
+  pub fn new(max_age: u64) -> Self {
+    Self { max_age, max_clock_skew: 30 }
+  }
+
+  pub fn accepts(&self, signed_at: u64, now: u64) -> bool {
+    signed_at <= now.saturating_add(self.max_clock_skew) && now.saturating_sub(signed_at) <= self.max_age
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimestampedSignature {
+  pub signed_at: u64, // Seconds since the Unix epoch
+  pub signature: LoquatSignature,
+}
+
+// Bytes actually signed: domain | signed_at | message
+fn transcript<M: SignableMessage + ?Sized>(signed_at: u64, message: &M) -> Vec<u8> {
+  let mut out = TIMESTAMP_DOMAIN.to_vec();
+  out.extend_from_slice(&signed_at.to_be_bytes());
+  Encoding::write_bytes(&mut out, &message.signing_bytes());
+  out
+}
+
+impl TimestampedSignature {
+  #[cfg(feature = "std")]
+  pub fn sign<M: SignableMessage + ?Sized>(sk: u128, message: &M, signed_at: u64) -> Result<Self, RngHealthError> {
+    Self::sign_with_config(sk, message, signed_at, &LoquatConfig::default())
+  }
+
+  #[cfg(feature = "std")]
+  pub fn sign_with_config<M: SignableMessage + ?Sized>(sk: u128, message: &M, signed_at: u64, config: &LoquatConfig) -> Result<Self, RngHealthError> {
+    Ok(Self {
+      signed_at,
+      signature: Loquat::sign_with_config(sk, transcript(signed_at, message).as_slice(), config)?,
+    })
+  }
+
+  // Verifies the signature and that its timestamp is within the policy at `now`
+  pub fn verify<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, now: u64, max_age: &MaxAge) -> bool {
+    self.verify_with_config(pk, message, now, max_age, &LoquatConfig::default())
+  }
+
+  pub fn verify_with_config<M: SignableMessage + ?Sized>(&self, pk: &[u8], message: &M, now: u64, max_age: &MaxAge, config: &LoquatConfig) -> bool {
+    max_age.accepts(self.signed_at, now) && Loquat::verify_with_config(pk, transcript(self.signed_at, message).as_slice(), &self.signature, config)
+  }
+
+  // Cost of verifying the underlying signature; the age check itself is free
+  #[cfg(feature = "std")]
+  pub fn verification_cost<M: SignableMessage + ?Sized>(&self, message: &M) -> VerificationCost {
+    self.signature.verification_cost(transcript(self.signed_at, message).as_slice())
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = self.signed_at.to_be_bytes().to_vec();
+    out.extend_from_slice(&self.signature.to_bytes());
+    out
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let signed_at = u64::from_be_bytes(bytes.get(..8)?.try_into().ok()?);
+    Some(Self {
+      signed_at,
+      signature: LoquatSignature::from_bytes(&bytes[8..])?,
+    })
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_max_age_is_enforced() {
+    let keypair = Loquat::keygen();
+    let signature = TimestampedSignature::sign(keypair.secret_key, b"statement", 1_000).unwrap();
+    let five_minutes = MaxAge::new(300);
+
+    assert!(signature.verify(&keypair.public_key, b"statement", 1_000, &five_minutes));
+    assert!(signature.verify(&keypair.public_key, b"statement", 1_300, &five_minutes));
+    assert!(!signature.verify(&keypair.public_key, b"statement", 1_301, &five_minutes));
+    // Signatures from too far in the future are rejected too
+    assert!(signature.verify(&keypair.public_key, b"statement", 970, &five_minutes));
+    assert!(!signature.verify(&keypair.public_key, b"statement", 969, &five_minutes));
+    assert!(!signature.verify(&keypair.public_key, b"other", 1_000, &five_minutes));
+  }
+
+  #[test]
+  fn test_timestamp_is_signed() {
+    let keypair = Loquat::keygen();
+    let signature = TimestampedSignature::sign(keypair.secret_key, b"statement", 1_000).unwrap();
+
+    // Moving the timestamp forward to pass a stricter policy breaks the signature
+    let mut refreshed = TimestampedSignature::from_bytes(&signature.to_bytes()).unwrap();
+    assert!(refreshed.verify(&keypair.public_key, b"statement", 1_000, &MaxAge::new(0)));
+    refreshed.signed_at = 2_000;
+    assert!(!refreshed.verify(&keypair.public_key, b"statement", 2_000, &MaxAge::new(300)));
+
+    // The timestamped transcript is not a plain signature over the message
+    assert!(!Loquat::verify(&keypair.public_key, b"statement", &signature.signature));
+  }
+}
@@ -0,0 +1,123 @@
+// Verifiable random function from the Legendre PRF
+// The output for an input is a hash of OUTPUT_PRF_BITS Legendre PRF bits of the secret key at
+// points derived from the input. The proof is a Loquat signature over the input and the
+// output: verifying it recovers the key committed to by the public key, and the verifier
+// recomputes the PRF bits from that key, so exactly one output verifies per (pk, input).
+// Verifying a proof reveals the key; see Experimental proofs in the signature module.
+
+use crate::compact::field;
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::prf_bits;
+#[cfg(feature = "std")]
+use crate::crypto::rng::RngHealthError;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::utils::constant_time;
+use crate::utils::encoding::Encoding;
+
+// Legendre PRF evaluations per output
+pub const OUTPUT_PRF_BITS: usize = 128;
+
+// Domain separation of evaluation points, outputs and proof messages
+const POINT_DOMAIN: &[u8] = b"loquat-vrf-point";
+const OUTPUT_DOMAIN: &[u8] = b"loquat-vrf-output";
+const PROOF_DOMAIN: &[u8] = b"loquat-vrf-proof";
+
+// Proof that an output is the VRF value of an input
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VrfProof {
+  pub signature: LoquatSignature,
+}
+
+impl VrfProof {
+  pub fn to_bytes(&self) -> Vec<u8> {
+    self.signature.to_bytes()
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    Some(Self {
+      signature: LoquatSignature::from_bytes(bytes)?,
+    })
+  }
+}
+
+pub struct LegendreVRF;
+
+impl LegendreVRF {
+  // Hash of the PRF bits of sk at the points derived from the input
+  fn output(sk: u128, input: &[u8]) -> Vec<u8> {
+    let hash = Hash::new(HashFunction::Sha3_256);
+    let bits: Vec<u8> = (0..OUTPUT_PRF_BITS as u32)
+      .map(|j| {
+        let point = field::from_be_bytes_mod(&hash.compute(&[POINT_DOMAIN, &j.to_be_bytes(), input].concat()));
+        (field::legendre_symbol(field::add(sk, point)) == -1) as u8
+      })
+      .collect();
+    hash.compute(&[OUTPUT_DOMAIN, &prf_bits::pack_bits(&bits)].concat())
+  }
+
+  // Message signed by the proof: domain | input | output, each length-prefixed
+  fn proof_message(input: &[u8], output: &[u8]) -> Vec<u8> {
+    let mut message = PROOF_DOMAIN.to_vec();
+    Encoding::write_bytes(&mut message, input);
+    Encoding::write_bytes(&mut message, output);
+    message
+  }
+
+  // Computes the VRF output of an input together with its proof
+  #[cfg(feature = "std")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  pub fn prove(sk: u128, input: &[u8]) -> Result<(Vec<u8>, VrfProof), RngHealthError> {
+    let output = Self::output(sk, input);
+    let signature = Loquat::sign(sk, Self::proof_message(input, &output).as_slice())?;
+    Ok((output, VrfProof { signature }))
+  }
+
+  // Checks that the output is the VRF value of the input under the public key
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, ret))]
+  pub fn verify(pk: &[u8], input: &[u8], output: &[u8], proof: &VrfProof) -> bool {
+    let message = Self::proof_message(input, output);
+    match Loquat::recover_secret_key(pk, message.as_slice(), &proof.signature, &LoquatConfig::default()) {
+      Some(sk) => constant_time::bytes_eq(&Self::output(sk, input), output),
+      None => false,
+    }
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_prove_and_verify() {
+    let keypair = Loquat::keygen();
+    let (output, proof) = LegendreVRF::prove(keypair.secret_key, b"rp.example").unwrap();
+    assert_eq!(output.len(), 32);
+    assert!(LegendreVRF::verify(&keypair.public_key, b"rp.example", &output, &proof));
+
+    // Outputs are deterministic per input and differ across inputs
+    assert_eq!(LegendreVRF::prove(keypair.secret_key, b"rp.example").unwrap().0, output);
+    let (other, other_proof) = LegendreVRF::prove(keypair.secret_key, b"other.example").unwrap();
+    assert_ne!(other, output);
+
+    // A proof does not transfer to another input, output or key
+    assert!(!LegendreVRF::verify(&keypair.public_key, b"other.example", &output, &proof));
+    assert!(!LegendreVRF::verify(&keypair.public_key, b"rp.example", &other, &other_proof));
+    assert!(!LegendreVRF::verify(&Loquat::keygen().public_key, b"rp.example", &output, &proof));
+  }
+
+  #[test]
+  fn test_output_is_unique() {
+    let keypair = Loquat::keygen();
+    let (output, _) = LegendreVRF::prove(keypair.secret_key, b"input").unwrap();
+
+    // Signing a different output under the same key does not produce a valid proof
+    let forged = vec![0u8; 32];
+    let signature = Loquat::sign(keypair.secret_key, LegendreVRF::proof_message(b"input", &forged).as_slice()).unwrap();
+    assert!(!LegendreVRF::verify(&keypair.public_key, b"input", &forged, &VrfProof { signature }));
+
+    let (_, proof) = LegendreVRF::prove(keypair.secret_key, b"input").unwrap();
+    let decoded = VrfProof::from_bytes(&proof.to_bytes()).unwrap();
+    assert!(LegendreVRF::verify(&keypair.public_key, b"input", &output, &decoded));
+  }
+}
@@ -0,0 +1,227 @@
+// Versioned, size-limited binary encoding of serde types
+// Plain bincode carries no version, says nothing about which type the bytes hold and lets a
+// length field steer how much the decoder reads. An envelope is
+//   magic "LQC" | version | type tag (u16-length-prefixed) | payload length (u32) | payload
+// where the payload is bincode in its fixed-width big-endian form. The total size is checked
+// against the limits before anything is parsed, the payload is decoded under the same limit,
+// and both truncated and over-long inputs are rejected instead of panicking.
+
+use crate::utils::encoding::Encoding;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub const CODEC_MAGIC: &[u8; 3] = b"LQC";
+
+// Bumped whenever the envelope or payload layout changes
+pub const CODEC_VERSION: u8 = 1;
+
+// A type with a stable binary encoding; the tag names it inside the envelope
+pub trait Encodable: Serialize + DeserializeOwned {
+  const TYPE_TAG: &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodecLimits {
+  pub max_bytes: usize, // Whole encoding, envelope included
+}
+
+impl Default for CodecLimits {
+  // 1 MiB, well above any checkpoint, proof or transcript this crate produces
+  fn default() -> Self {
+    Self { max_bytes: 1 << 20 }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+  TooLarge, // Over the size limit, encoding or decoding
+  BadMagic,
+  UnsupportedVersion(u8),
+  WrongType, // An envelope holding another type
+  Truncated, // Input ends inside a length-prefixed field or the payload
+  TrailingBytes, // Input continues after the payload
+  Malformed, // A payload that does not decode to the type
+  Unencodable, // A value bincode cannot represent (maps of unknown length, untyped JSON)
+}
+
+impl CodecError {
+  pub fn name(&self) -> &'static str {
+    match self {
+      CodecError::TooLarge => "too_large",
+      CodecError::BadMagic => "bad_magic",
+      CodecError::UnsupportedVersion(_) => "unsupported_version",
+      CodecError::WrongType => "wrong_type",
+      CodecError::Truncated => "truncated",
+      CodecError::TrailingBytes => "trailing_bytes",
+      CodecError::Malformed => "malformed",
+      CodecError::Unencodable => "unencodable",
+    }
+  }
+}
+
+fn bincode_options(limit: usize) -> impl Options {
+  bincode::DefaultOptions::new()
+    .with_fixint_encoding()
+    .with_big_endian()
+    .with_limit(limit as u64)
+    .reject_trailing_bytes()
+}
+
+fn from_bincode(error: bincode::ErrorKind) -> CodecError {
+  match error {
+    bincode::ErrorKind::SizeLimit => CodecError::TooLarge,
+    bincode::ErrorKind::Io(_) => CodecError::Truncated,
+    bincode::ErrorKind::SequenceMustHaveLength | bincode::ErrorKind::DeserializeAnyNotSupported => CodecError::Unencodable,
+    bincode::ErrorKind::Custom(ref message) if message.contains("bytes remaining") => CodecError::TrailingBytes,
+    _ => CodecError::Malformed,
+  }
+}
+
+// Bare bincode payload of at most max_bytes, without the envelope
+pub fn to_payload<T: Serialize + ?Sized>(value: &T, max_bytes: usize) -> Result<Vec<u8>, CodecError> {
+  bincode_options(max_bytes).serialize(value).map_err(|e| from_bincode(*e))
+}
+
+pub fn from_payload<T: DeserializeOwned>(bytes: &[u8], max_bytes: usize) -> Result<T, CodecError> {
+  if bytes.len() > max_bytes {
+    return Err(CodecError::TooLarge);
+  }
+  bincode_options(max_bytes).deserialize(bytes).map_err(|e| from_bincode(*e))
+}
+
+pub fn encode<T: Encodable>(value: &T) -> Result<Vec<u8>, CodecError> {
+  encode_with_limits(value, &CodecLimits::default())
+}
+
+pub fn encode_with_limits<T: Encodable>(value: &T, limits: &CodecLimits) -> Result<Vec<u8>, CodecError> {
+  let mut out = CODEC_MAGIC.to_vec();
+  out.push(CODEC_VERSION);
+  Encoding::write_bytes(&mut out, T::TYPE_TAG.as_bytes());
+  let payload = to_payload(value, limits.max_bytes.saturating_sub(out.len() + 4))?;
+  out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+  out.extend_from_slice(&payload);
+  Ok(out)
+}
+
+pub fn decode<T: Encodable>(bytes: &[u8]) -> Result<T, CodecError> {
+  decode_with_limits(bytes, &CodecLimits::default())
+}
+
+pub fn decode_with_limits<T: Encodable>(bytes: &[u8], limits: &CodecLimits) -> Result<T, CodecError> {
+  if bytes.len() > limits.max_bytes {
+    return Err(CodecError::TooLarge);
+  }
+  let rest = bytes.strip_prefix(CODEC_MAGIC.as_slice()).ok_or(CodecError::BadMagic)?;
+  match rest.first() {
+    Some(&CODEC_VERSION) => {}
+    Some(&version) => return Err(CodecError::UnsupportedVersion(version)),
+    None => return Err(CodecError::Truncated),
+  }
+
+  let mut offset = 1;
+  let tag = Encoding::read_bytes(rest, &mut offset).ok_or(CodecError::Truncated)?;
+  if tag != T::TYPE_TAG.as_bytes() {
+    return Err(CodecError::WrongType);
+  }
+  let len_bytes = rest.get(offset..offset + 4).ok_or(CodecError::Truncated)?;
+  let len = u32::from_be_bytes(len_bytes.try_into().expect("Slice is 4 bytes")) as usize;
+  let payload = &rest[offset + 4..];
+  if payload.len() < len {
+    return Err(CodecError::Truncated);
+  }
+  if payload.len() > len {
+    return Err(CodecError::TrailingBytes);
+  }
+  from_payload(payload, len)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  #[cfg(feature = "std")]
+  use rand::rngs::StdRng;
+  #[cfg(feature = "std")]
+  use rand::{Rng, SeedableRng};
+  use serde::Deserialize;
+
+  #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct Sample {
+    id: u64,
+    name: String,
+    items: Vec<Vec<u8>>,
+    flag: Option<bool>,
+  }
+
+  impl Encodable for Sample {
+    const TYPE_TAG: &'static str = "loquat-vc/test-sample";
+  }
+
+  fn sample() -> Sample {
+    Sample {
+      id: 7,
+      name: "sample".to_string(),
+      items: vec![vec![1, 2, 3], vec![], vec![0xff; 40]],
+      flag: Some(true),
+    }
+  }
+
+  #[test]
+  fn test_envelope_round_trip_and_rejections() {
+    let encoded = encode(&sample()).unwrap();
+    assert!(encoded.starts_with(b"LQC\x01"));
+    assert_eq!(decode::<Sample>(&encoded), Ok(sample()));
+
+    let mut version = encoded.clone();
+    version[3] = 2;
+    assert_eq!(decode::<Sample>(&version), Err(CodecError::UnsupportedVersion(2)));
+    assert_eq!(decode::<Sample>(&encoded[1..]), Err(CodecError::BadMagic));
+    assert_eq!(decode::<Sample>(&encoded[..encoded.len() - 1]), Err(CodecError::Truncated));
+    assert_eq!(decode::<Sample>(&[encoded.as_slice(), &[0]].concat()), Err(CodecError::TrailingBytes));
+
+    // A payload of another type is refused by its tag before it is parsed
+    let mut retagged = CODEC_MAGIC.to_vec();
+    retagged.push(CODEC_VERSION);
+    Encoding::write_bytes(&mut retagged, b"loquat-vc/other");
+    retagged.extend_from_slice(&encoded[4 + 2 + Sample::TYPE_TAG.len()..]);
+    assert_eq!(decode::<Sample>(&retagged), Err(CodecError::WrongType));
+
+    // Limits apply in both directions
+    let limits = CodecLimits { max_bytes: encoded.len() - 1 };
+    assert_eq!(encode_with_limits(&sample(), &limits), Err(CodecError::TooLarge));
+    assert_eq!(decode_with_limits::<Sample>(&encoded, &limits), Err(CodecError::TooLarge));
+    assert_eq!(to_payload(&serde_json::json!({"a": 1}), 64).and_then(|p| from_payload::<serde_json::Value>(&p, 64)), Err(CodecError::Unencodable));
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_fuzzed_inputs_never_panic() {
+    let mut rng = StdRng::seed_from_u64(0x4c51);
+    let encoded = encode(&sample()).unwrap();
+    for _ in 0..2000 {
+      let mut input = encoded.clone();
+      match rng.gen_range(0..4) {
+        0 => {
+          let i = rng.gen_range(0..input.len());
+          input[i] ^= 1 << rng.gen_range(0..8);
+        }
+        1 => input.truncate(rng.gen_range(0..input.len())),
+        2 => input.extend((0..rng.gen_range(1..16)).map(|_| rng.gen::<u8>())),
+        _ => input = (0..rng.gen_range(0..96)).map(|_| rng.gen()).collect(),
+      }
+      // Any input that decodes is a value that encodes back to the same bytes
+      if let Ok(decoded) = decode::<Sample>(&input) {
+        assert_eq!(encode(&decoded).unwrap(), input);
+      }
+    }
+
+    // Length fields claiming far more than the input holds fail without allocating for them
+    let mut huge = CODEC_MAGIC.to_vec();
+    huge.push(CODEC_VERSION);
+    Encoding::write_bytes(&mut huge, Sample::TYPE_TAG.as_bytes());
+    let payload = [7u64.to_be_bytes().as_slice(), &u64::MAX.to_be_bytes()].concat();
+    huge.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    huge.extend_from_slice(&payload);
+    assert!(decode::<Sample>(&huge).is_err());
+  }
+}
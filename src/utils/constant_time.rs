@@ -0,0 +1,49 @@
+// Constant-time equality for verification paths
+// Comparing a recomputed digest to an attacker-supplied one with `==` returns at the first
+// differing byte, which lets a forger learn how long a prefix of a near-miss is correct.
+// These helpers compare every byte regardless of where the inputs differ. Lengths are
+// treated as public.
+
+use num_bigint::BigUint;
+use subtle::ConstantTimeEq;
+
+// Byte strings are equal; unequal lengths compare unequal
+pub fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+  a.ct_eq(b).into()
+}
+
+// Big integers are equal, compared over a common big-endian width
+pub fn biguint_eq(a: &BigUint, b: &BigUint) -> bool {
+  let (a, b) = (a.to_bytes_be(), b.to_bytes_be());
+  let width = a.len().max(b.len());
+  bytes_eq(&left_pad(&a, width), &left_pad(&b, width))
+}
+
+// Field elements are equal
+pub fn u128_eq(a: u128, b: u128) -> bool {
+  bytes_eq(&a.to_be_bytes(), &b.to_be_bytes())
+}
+
+fn left_pad(bytes: &[u8], width: usize) -> Vec<u8> {
+  let mut padded = vec![0u8; width - bytes.len()];
+  padded.extend_from_slice(bytes);
+  padded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_bytes_and_integers() {
+    assert!(bytes_eq(b"digest", b"digest"));
+    assert!(!bytes_eq(b"digest", b"digesT"));
+    assert!(!bytes_eq(b"digest", b"diges"));
+
+    let large = BigUint::from(1u8) << 200;
+    assert!(biguint_eq(&large, &large.clone()));
+    assert!(!biguint_eq(&large, &BigUint::from(1u8)));
+    assert!(u128_eq(7, 7));
+    assert!(!u128_eq(7, 8));
+  }
+}
@@ -1,6 +1,47 @@
+use crate::utils::codec::{self, CodecError, CodecLimits};
 use num_bigint::BigUint;
-use bincode;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::fmt;
+use std::fmt::Write as _;
+
+// Alphabet of the URL-safe base64 variant
+const BASE64URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Bitcoin base58 alphabet: no 0, O, I or l
+const BASE58BTC_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Multihash function codes (multiformats table) of the hash functions with registered codes
+pub const MULTIHASH_SHA3_256: u64 = 0x16;
+pub const MULTIHASH_SHAKE_128: u64 = 0x18;
+pub const MULTIHASH_KECCAK_256: u64 = 0x1b;
+
+// Multibase encodings supported for keys, proof values and digests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Multibase {
+  Base16, // 'f', lowercase hex
+  Base58Btc, // 'z', the usual choice for keys and proof values
+  Base64Url, // 'u', unpadded
+}
+
+impl Multibase {
+  pub fn prefix(&self) -> char {
+    match self {
+      Multibase::Base16 => 'f',
+      Multibase::Base58Btc => 'z',
+      Multibase::Base64Url => 'u',
+    }
+  }
+
+  pub fn from_prefix(prefix: char) -> Option<Self> {
+    match prefix {
+      'f' => Some(Multibase::Base16),
+      'z' => Some(Multibase::Base58Btc),
+      'u' => Some(Multibase::Base64Url),
+      _ => None,
+    }
+  }
+}
 
 // Encoding utilities for Loquat cryptographic data
 pub struct Encoding;
@@ -16,14 +57,379 @@ impl Encoding {
     BigUint::from_bytes_be(bytes)
   }
 
-  // Serialize a generic struct using Bincode
-  pub fn serialize<T: Serialize>(data: &T) -> Vec<u8> {
-    bincode::serialize(data).expect("Serialization failed")
+  // Encodes bytes as lowercase hexadecimal
+  pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+  }
+
+  // Decodes a hexadecimal string, accepting either case
+  pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+      return None;
+    }
+    (0..hex.len())
+      .step_by(2)
+      .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+      .collect()
+  }
+
+  // Encodes bytes as unpadded base64url (RFC 4648 section 5)
+  pub fn to_base64url(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+      let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+      let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+      for i in 0..chunk.len() + 1 {
+        out.push(BASE64URL_ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+      }
+    }
+    out
+  }
+
+  // Decodes unpadded base64url, rejecting padding, foreign characters and non-canonical trailing bits
+  pub fn from_base64url(input: &str) -> Option<Vec<u8>> {
+    if input.len() % 4 == 1 {
+      return None;
+    }
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.as_bytes().chunks(4) {
+      let mut n = 0u32;
+      for (i, &c) in chunk.iter().enumerate() {
+        let value = BASE64URL_ALPHABET.iter().position(|&a| a == c)? as u32;
+        n |= value << (18 - 6 * i);
+      }
+      let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+      let produced = chunk.len() - 1;
+      // Bits beyond the last full byte must be zero so every byte string has one encoding
+      if bytes[produced..].iter().any(|&b| b != 0) {
+        return None;
+      }
+      out.extend_from_slice(&bytes[..produced]);
+    }
+    Some(out)
+  }
+
+  // Encodes bytes in base58btc; each leading zero byte becomes a leading '1'
+  pub fn to_base58btc(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // Little-endian base-58 digits of the big-endian number in bytes
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+      let mut carry = byte as u32;
+      for digit in digits.iter_mut() {
+        carry += (*digit as u32) << 8;
+        *digit = (carry % 58) as u8;
+        carry /= 58;
+      }
+      while carry > 0 {
+        digits.push((carry % 58) as u8);
+        carry /= 58;
+      }
+    }
+    let mut out = "1".repeat(zeros);
+    out.extend(digits.iter().rev().map(|&d| BASE58BTC_ALPHABET[d as usize] as char));
+    out
+  }
+
+  pub fn from_base58btc(input: &str) -> Option<Vec<u8>> {
+    let zeros = input.bytes().take_while(|&c| c == b'1').count();
+    // Little-endian bytes of the number
+    let mut bytes: Vec<u8> = Vec::with_capacity(input.len());
+    for c in input.bytes().skip(zeros) {
+      let mut carry = BASE58BTC_ALPHABET.iter().position(|&a| a == c)? as u32;
+      for byte in bytes.iter_mut() {
+        carry += (*byte as u32) * 58;
+        *byte = carry as u8;
+        carry >>= 8;
+      }
+      while carry > 0 {
+        bytes.push(carry as u8);
+        carry >>= 8;
+      }
+    }
+    let mut out = vec![0; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+  }
+
+  // Multibase string: the encoding's prefix character followed by the encoded bytes
+  pub fn to_multibase(base: Multibase, bytes: &[u8]) -> String {
+    let encoded = match base {
+      Multibase::Base16 => Self::to_hex(bytes),
+      Multibase::Base58Btc => Self::to_base58btc(bytes),
+      Multibase::Base64Url => Self::to_base64url(bytes),
+    };
+    format!("{}{}", base.prefix(), encoded)
+  }
+
+  // Decodes a multibase string in any supported encoding
+  pub fn from_multibase(input: &str) -> Option<Vec<u8>> {
+    let mut chars = input.chars();
+    let base = Multibase::from_prefix(chars.next()?)?;
+    let encoded = chars.as_str();
+    match base {
+      // Multibase base16 is lowercase only
+      Multibase::Base16 if encoded.bytes().any(|b| b.is_ascii_uppercase()) => None,
+      Multibase::Base16 => Self::from_hex(encoded),
+      Multibase::Base58Btc => Self::from_base58btc(encoded),
+      Multibase::Base64Url => Self::from_base64url(encoded),
+    }
+  }
+
+  // Appends an unsigned LEB128 varint, as used by multicodec and multihash
+  pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+      out.push((value as u8) | 0x80);
+      value >>= 7;
+    }
+    out.push(value as u8);
+  }
+
+  // Reads a minimally encoded varint of at most 9 bytes (the multiformats limit), advancing the offset
+  pub fn read_varint(input: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+      let byte = *input.get(*offset + i)?;
+      value |= ((byte & 0x7f) as u64) << (7 * i);
+      if byte & 0x80 == 0 {
+        // A trailing zero group would be a second encoding of a shorter varint
+        if byte == 0 && i > 0 {
+          return None;
+        }
+        *offset += i + 1;
+        return Some(value);
+      }
+    }
+    None
+  }
+
+  // Prefixes bytes with a multicodec code
+  pub fn to_multicodec(code: u64, bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 4);
+    Self::write_varint(&mut out, code);
+    out.extend_from_slice(bytes);
+    out
+  }
+
+  // Splits off the multicodec code
+  pub fn from_multicodec(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut offset = 0;
+    let code = Self::read_varint(bytes, &mut offset)?;
+    Some((code, &bytes[offset..]))
+  }
+
+  // Wraps a digest as a multihash: function code | digest length | digest
+  pub fn to_multihash(code: u64, digest: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(digest.len() + 4);
+    Self::write_varint(&mut out, code);
+    Self::write_varint(&mut out, digest.len() as u64);
+    out.extend_from_slice(digest);
+    out
+  }
+
+  // Parses a multihash, returning the function code and the digest
+  pub fn from_multihash(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut offset = 0;
+    let code = Self::read_varint(bytes, &mut offset)?;
+    let len = Self::read_varint(bytes, &mut offset)?;
+    let digest = bytes.get(offset..)?;
+    if digest.len() as u64 != len {
+      return None;
+    }
+    Some((code, digest))
+  }
+
+  // Percent-encodes a string for use in a URI query component (RFC 3986 unreserved characters pass through)
+  pub fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for &b in input.as_bytes() {
+      if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+        out.push(b as char);
+      } else {
+        out.push_str(&format!("%{:02X}", b));
+      }
+    }
+    out
+  }
+
+  // Decodes a percent-encoded URI component, treating '+' as a space as in form encoding
+  pub fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+      match bytes[i] {
+        b'%' => {
+          let hex = input.get(i + 1..i + 3)?;
+          out.push(u8::from_str_radix(hex, 16).ok()?);
+          i += 3;
+        }
+        b'+' => {
+          out.push(b' ');
+          i += 1;
+        }
+        b => {
+          out.push(b);
+          i += 1;
+        }
+      }
+    }
+    String::from_utf8(out).ok()
+  }
+
+  // Appends a byte string prefixed with its length as a big-endian u16
+  pub fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    let len = u16::try_from(bytes.len()).expect("Field too long for length prefix");
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(bytes);
+  }
+
+  // Reads a length-prefixed byte string written by write_bytes, advancing the offset
+  pub fn read_bytes<'a>(input: &'a [u8], offset: &mut usize) -> Option<&'a [u8]> {
+    let len_bytes = input.get(*offset..*offset + 2)?;
+    let len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    let start = *offset + 2;
+    let bytes = input.get(start..start + len)?;
+    *offset = start + len;
+    Some(bytes)
+  }
+
+  // RFC 8785 JSON Canonicalization Scheme: no whitespace, object members sorted by the UTF-16
+  // code units of their names, strings with the minimal JSON escapes and numbers in the
+  // ECMAScript shortest round-trip form. Numbers are IEEE 754 doubles as in JavaScript, so
+  // integers beyond 2^53 are rounded like any other implementation would round them.
+  pub fn to_jcs(value: &Value) -> String {
+    let mut out = String::new();
+    write_jcs(&mut out, value);
+    out
+  }
+
+  // Serialize a generic struct as a bare bincode payload within the default size limit;
+  // codec::encode adds the versioned envelope for types stored or sent on their own
+  pub fn serialize<T: Serialize>(data: &T) -> Result<Vec<u8>, CodecError> {
+    codec::to_payload(data, CodecLimits::default().max_bytes)
   }
 
   // Deserialize a byte array back into a struct
-  pub fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> T {
-    bincode::deserialize(bytes).expect("Deserialization failed")
+  pub fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, CodecError> {
+    codec::from_payload(bytes, CodecLimits::default().max_bytes)
+  }
+}
+
+fn write_jcs(out: &mut String, value: &Value) {
+  match value {
+    Value::Null => out.push_str("null"),
+    Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+    Value::Number(n) => write_jcs_number(out, n.as_f64().expect("JSON numbers are finite")),
+    Value::String(s) => write_jcs_string(out, s),
+    Value::Array(items) => {
+      out.push('[');
+      for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        write_jcs(out, item);
+      }
+      out.push(']');
+    }
+    Value::Object(map) => {
+      let mut members: Vec<_> = map.iter().collect();
+      members.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+      out.push('{');
+      for (i, (name, member)) in members.into_iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        write_jcs_string(out, name);
+        out.push(':');
+        write_jcs(out, member);
+      }
+      out.push('}');
+    }
+  }
+}
+
+fn write_jcs_string(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\u{08}' => out.push_str("\\b"),
+      '\u{0c}' => out.push_str("\\f"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if c < ' ' => write!(out, "\\u{:04x}", c as u32).expect("Writing to a String cannot fail"),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+}
+
+// ECMAScript Number::toString (ECMA-262 section 6.1.6.1.20) from the shortest round-trip digits
+fn write_jcs_number(out: &mut String, x: f64) {
+  if x == 0.0 {
+    out.push('0'); // Also for -0
+    return;
+  }
+  if x < 0.0 {
+    out.push('-');
+  }
+  // `{:e}` yields the shortest digits that round-trip, as d.ddde<exp>
+  let scientific = format!("{:e}", x.abs());
+  let (mantissa, exponent) = scientific.split_once('e').expect("Exponent notation");
+  let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+  let k = digits.len() as i32;
+  let n = exponent.parse::<i32>().expect("Decimal exponent") + 1; // x = 0.digits * 10^n
+
+  if k <= n && n <= 21 {
+    out.push_str(&digits);
+    out.extend(std::iter::repeat_n('0', (n - k) as usize));
+  } else if 0 < n && n <= 21 {
+    out.push_str(&digits[..n as usize]);
+    out.push('.');
+    out.push_str(&digits[n as usize..]);
+  } else if -6 < n && n <= 0 {
+    out.push_str("0.");
+    out.extend(std::iter::repeat_n('0', (-n) as usize));
+    out.push_str(&digits);
+  } else {
+    out.push_str(&digits[..1]);
+    if k > 1 {
+      out.push('.');
+      out.push_str(&digits[1..]);
+    }
+    write!(out, "e{}{}", if n > 0 { '+' } else { '-' }, (n - 1).abs()).expect("Writing to a String cannot fail");
+  }
+}
+
+// Formats bytes as lowercase hex; the alternate form `{:#}` prefixes the multibase code `f`
+// (base16), so digests and keys can be logged in one line without allocating
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+      f.write_str("f")?;
+    }
+    self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+  }
+}
+
+impl fmt::Debug for Hex<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(self, f)
+  }
+}
+
+// Stands in for secret fields in Debug output
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("<redacted>")
   }
 }
 
@@ -46,11 +452,132 @@ mod tests {
     assert_eq!(value, decoded);
   }
 
+  #[test]
+  fn test_hex_round_trip() {
+    let bytes = vec![0x00, 0x0f, 0xa5, 0xff];
+    assert_eq!(Encoding::to_hex(&bytes), "000fa5ff");
+    assert_eq!(Encoding::from_hex("000FA5ff"), Some(bytes));
+    assert_eq!(Encoding::from_hex("abc"), None);
+    assert_eq!(Encoding::from_hex("zz"), None);
+  }
+
+  #[test]
+  fn test_base64url() {
+    // RFC 4648 test vectors, without padding
+    let vectors = [("", ""), ("f", "Zg"), ("fo", "Zm8"), ("foo", "Zm9v"), ("foob", "Zm9vYg"), ("fooba", "Zm9vYmE"), ("foobar", "Zm9vYmFy")];
+    for (plain, encoded) in vectors {
+      assert_eq!(Encoding::to_base64url(plain.as_bytes()), encoded);
+      assert_eq!(Encoding::from_base64url(encoded), Some(plain.as_bytes().to_vec()));
+    }
+
+    assert_eq!(Encoding::to_base64url(&[0xfb, 0xff]), "-_8");
+    assert_eq!(Encoding::from_base64url("Zm9v="), None);
+    assert_eq!(Encoding::from_base64url("Z"), None);
+    assert_eq!(Encoding::from_base64url("Zh"), None); // Non-zero trailing bits
+  }
+
+  #[test]
+  fn test_percent_encoding() {
+    let input = r#"{"a": "b&c"}"#;
+    let encoded = Encoding::percent_encode(input);
+    assert_eq!(encoded, "%7B%22a%22%3A%20%22b%26c%22%7D");
+    assert_eq!(Encoding::percent_decode(&encoded), Some(input.to_string()));
+    assert_eq!(Encoding::percent_decode("a+b"), Some("a b".to_string()));
+    assert_eq!(Encoding::percent_decode("%zz"), None);
+    assert_eq!(Encoding::percent_decode("%4"), None);
+  }
+
+  #[test]
+  fn test_hex_display() {
+    assert_eq!(Hex(&[0x00, 0xab, 0x10]).to_string(), Encoding::to_hex(&[0x00, 0xab, 0x10]));
+    assert_eq!(format!("{:#}", Hex(&[0xff])), "fff");
+    assert_eq!(format!("{:?}", Hex(&[])), "");
+    assert_eq!(format!("{:?}", Redacted), "<redacted>");
+  }
+
+  #[test]
+  fn test_jcs() {
+    // RFC 8785 section 3.2.2 and appendix B
+    let input = r#"{"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+      "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/", "literals": [null, true, false]}"#;
+    let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+    assert_eq!(Encoding::to_jcs(&serde_json::from_str(input).unwrap()), expected);
+
+    let numbers = [(0.0, "0"), (-0.0, "0"), (1e21, "1e+21"), (1e20, "100000000000000000000"), (1e-7, "1e-7"), (-1.5e-6, "-0.0000015"), (9007199254740992.0, "9007199254740992"), (f64::MAX, "1.7976931348623157e+308"), (5e-324, "5e-324")];
+    for (x, expected) in numbers {
+      assert_eq!(Encoding::to_jcs(&serde_json::json!(x)), expected);
+    }
+    assert_eq!(Encoding::to_jcs(&serde_json::json!(u64::MAX)), "18446744073709552000");
+
+    // Member names sort by UTF-16 code units, which puts U+1F600 before U+FB33
+    let sorted = r#"{"\r":1,"1":2,"\u0080":3,"\u00f6":4,"\u20ac":5,"\ud83d\ude00":6,"\ufb33":7}"#;
+    let canonical = Encoding::to_jcs(&serde_json::from_str(sorted).unwrap());
+    assert_eq!(canonical, "{\"\\r\":1,\"1\":2,\"\u{80}\":3,\"ö\":4,\"€\":5,\"😀\":6,\"\u{fb33}\":7}");
+  }
+
+  #[test]
+  fn test_multibase() {
+    // Vectors from the multibase specification test suite
+    assert_eq!(Encoding::to_multibase(Multibase::Base58Btc, b"Decentralize everything!!"), "zUXE7GvtEk8XTXs1GF8HSGbVA9FCX9SEBPe");
+    assert_eq!(Encoding::to_multibase(Multibase::Base58Btc, b"\x00yes mani !"), "z17paNL19xttacUY");
+    assert_eq!(Encoding::to_multibase(Multibase::Base64Url, b"yes mani !"), "ueWVzIG1hbmkgIQ");
+    assert_eq!(Encoding::to_multibase(Multibase::Base16, b"yes mani !"), "f796573206d616e692021");
+
+    for bytes in [&b""[..], &[0, 0, 1], &[0xff; 40], b"\x00\x00"] {
+      for base in [Multibase::Base16, Multibase::Base58Btc, Multibase::Base64Url] {
+        assert_eq!(Encoding::from_multibase(&Encoding::to_multibase(base, bytes)).as_deref(), Some(bytes));
+      }
+    }
+    assert_eq!(Encoding::from_multibase("z0OIl"), None);
+    assert_eq!(Encoding::from_multibase("F796573"), None);
+    assert_eq!(Encoding::from_multibase("f79657A"), None);
+    assert_eq!(Encoding::from_multibase(""), None);
+  }
+
+  #[test]
+  fn test_multihash_and_varints() {
+    let mut out = vec![];
+    for value in [0u64, 1, 127, 128, 300, 0x30_4c51, u64::MAX >> 1] {
+      out.clear();
+      Encoding::write_varint(&mut out, value);
+      let mut offset = 0;
+      assert_eq!(Encoding::read_varint(&out, &mut offset), Some(value));
+      assert_eq!(offset, out.len());
+    }
+    let mut offset = 0;
+    assert_eq!(Encoding::read_varint(&[0x80, 0x00], &mut offset), None); // Non-minimal zero
+    assert_eq!(Encoding::read_varint(&[0x80], &mut offset), None);
+
+    let digest = [0xab; 32];
+    let multihash = Encoding::to_multihash(MULTIHASH_SHA3_256, &digest);
+    assert_eq!(multihash[..2], [0x16, 0x20]);
+    assert_eq!(Encoding::from_multihash(&multihash), Some((MULTIHASH_SHA3_256, &digest[..])));
+    assert_eq!(Encoding::from_multihash(&multihash[..33]), None);
+    assert_eq!(Encoding::from_multicodec(&Encoding::to_multicodec(0xed, b"key")), Some((0xed, &b"key"[..])));
+  }
+
+  #[test]
+  fn test_length_prefixed_bytes() {
+    let mut out = vec![];
+    Encoding::write_bytes(&mut out, b"loquat");
+    Encoding::write_bytes(&mut out, b"");
+
+    let mut offset = 0;
+    assert_eq!(Encoding::read_bytes(&out, &mut offset), Some(&b"loquat"[..]));
+    assert_eq!(Encoding::read_bytes(&out, &mut offset), Some(&b""[..]));
+    assert_eq!(offset, out.len());
+
+    // Truncated input is rejected
+    let mut offset = 0;
+    assert_eq!(Encoding::read_bytes(&out[..4], &mut offset), None);
+  }
+
   #[test]
   fn test_serialize_deserialize() {
     let test_data = TestStruct { a: 42, b: "Hello Loquat".to_string() };
-    let serialized = Encoding::serialize(&test_data);
-    let deserialized: TestStruct = Encoding::deserialize(&serialized);
+    let serialized = Encoding::serialize(&test_data).unwrap();
+    let deserialized: TestStruct = Encoding::deserialize(&serialized).unwrap();
     assert_eq!(test_data, deserialized);
+    assert_eq!(Encoding::deserialize::<TestStruct>(&serialized[..serialized.len() - 1]), Err(CodecError::Truncated));
   }
 }
@@ -5,99 +5,156 @@
 
 use num_bigint::BigUint;
 use num_traits::{One, Zero, ToPrimitive};
-use std::ops::{Add, Mul, Sub};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// Prime field modulus (p = 2^127 - 1) of the default field, shared by the u128 fast paths
+pub const P: u128 = (1 << 127) - 1;
+
+// Type-level prime modulus selecting the field of an `Fp`
+pub trait FieldModulus: Debug + Clone + Copy + PartialEq + Eq + Hash {
+  const NAME: &'static str;
+  const BITS: u32; // Bit length of the modulus
+  fn modulus() -> BigUint;
+}
+
+// Mersenne prime 2^61 - 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M61;
+
+impl FieldModulus for M61 {
+  const NAME: &'static str = "M61";
+  const BITS: u32 = 61;
+  fn modulus() -> BigUint {
+    BigUint::from((1u64 << 61) - 1)
+  }
+}
+
+// Goldilocks prime 2^64 - 2^32 + 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M64;
+
+impl FieldModulus for M64 {
+  const NAME: &'static str = "GOLDILOCKS";
+  const BITS: u32 = 64;
+  fn modulus() -> BigUint {
+    BigUint::from(u64::MAX - (1u64 << 32) + 2)
+  }
+}
+
+// Mersenne prime 2^127 - 1, the field of the Loquat paper and the crate default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M127;
+
+impl FieldModulus for M127 {
+  const NAME: &'static str = "M127";
+  const BITS: u32 = 127;
+  fn modulus() -> BigUint {
+    BigUint::from(P)
+  }
+}
 
-// Prime field modulus (p = 2^127 - 1) 
-const P: u128 = (1 << 127) - 1;
+// Prime 2^255 - 19 for 256-bit parameter sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct M255;
 
-// Struct representing an element in the finite field `Fp`
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FieldElement {
+impl FieldModulus for M255 {
+  const NAME: &'static str = "M255";
+  const BITS: u32 = 255;
+  fn modulus() -> BigUint {
+    (BigUint::one() << 255u32) - BigUint::from(19u8)
+  }
+}
+
+// Struct representing an element in the finite field `Fp` for the modulus M
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fp<M: FieldModulus> {
   value: BigUint,
+  modulus: PhantomData<M>,
 }
 
-impl FieldElement {
+// Orders elements by their canonical representative, e.g. for sorted sets of evaluations
+impl<M: FieldModulus> PartialOrd for Fp<M> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<M: FieldModulus> Ord for Fp<M> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.value.cmp(&other.value)
+  }
+}
+
+// Element of the default 2^127 - 1 field
+pub type FieldElement = Fp<M127>;
+
+impl<M: FieldModulus> Fp<M> {
   // Creates a new field element, ensuring it is reduced mod P
   pub fn new(value: u128) -> Self {
+    Self::from_biguint(BigUint::from(value))
+  }
+
+  // Creates a field element from an arbitrary integer, reducing it mod P
+  pub fn from_biguint(value: BigUint) -> Self {
     Self {
-      value: BigUint::from(value) % BigUint::from(P),
+      value: value % M::modulus(),
+      modulus: PhantomData,
     }
   }
 
+  pub fn zero() -> Self {
+    Self::from_biguint(BigUint::zero())
+  }
+
+  pub fn one() -> Self {
+    Self::new(1)
+  }
+
+  // Returns the canonical representative as a u128
+  // Panics for elements of fields wider than 128 bits that do not fit
+  pub fn to_u128(&self) -> u128 {
+    self.value.to_u128().expect("Field element exceeds u128")
+  }
+
+  // Returns the canonical representative
+  pub fn to_biguint(&self) -> BigUint {
+    self.value.clone()
+  }
+
   // Modular addition
   pub fn add(&self, other: &Self) -> Self {
-    Self {
-      value: (self.value.clone() + other.value.clone()) % BigUint::from(P),
-    }
+    Self::from_biguint(&self.value + &other.value)
   }
 
   // Modular subtraction
   pub fn sub(&self, other: &Self) -> Self {
-    let mut result = (self.value.clone() + BigUint::from(P) - other.value.clone()) % BigUint::from(P);
-    if result.is_zero() {
-      result = BigUint::zero();
-    }
-    Self { value: result }
+    Self::from_biguint(&self.value + M::modulus() - &other.value)
   }
 
   // Modular multiplication
   pub fn mul(&self, other: &Self) -> Self {
-    Self {
-      value: (self.value.clone() * other.value.clone()) % BigUint::from(P),
-    }
+    Self::from_biguint(&self.value * &other.value)
   }
 
-  // Modular exponentiation using square-and-multiply
+  // Modular exponentiation
   pub fn pow(&self, exp: u128) -> Self {
-    let mut base = self.value.clone();
-    let mut exponent = BigUint::from(exp);
-    let mut result = BigUint::one();
-    let modulus = BigUint::from(P);
-
-    while !exponent.is_zero() {
-      if &exponent % 2u8 == BigUint::one() {
-        result = (result * &base) % &modulus;
-      }
-      base = (&base * &base) % &modulus;
-      exponent /= 2u8;
-    }
-
-    Self { value: result }
+    Self::from_biguint(self.value.modpow(&BigUint::from(exp), &M::modulus()))
   }
 
-  // Modular inverse using the Extended Euclidean Algorithm
+  // Modular inverse by Fermat's little theorem (a^(p-2)); zero has no inverse
   pub fn inverse(&self) -> Option<Self> {
-    let (gcd, x, _) = extended_gcd(self.value.clone(), BigUint::from(P));
-    if gcd == BigUint::one() {
-      Some(Self {
-        value: (x + BigUint::from(P)) % BigUint::from(P),
-      })
-    } else {
-      None
+    if self.value.is_zero() {
+      return None;
     }
+    let modulus = M::modulus();
+    let exponent = &modulus - BigUint::from(2u8);
+    Some(Self::from_biguint(self.value.modpow(&exponent, &modulus)))
   }
 }
 
-// Extended Euclidean Algorithm for modular inverse
-fn extended_gcd(a: BigUint, b: BigUint) -> (BigUint, BigUint, BigUint) {
-  let (mut old_r, mut r) = (a, b);
-  let (mut old_s, mut s) = (BigUint::one(), BigUint::zero());
-  let (mut old_t, mut t) = (BigUint::zero(), BigUint::one());
-
-  while r != BigUint::zero() {
-    let quotient = &old_r / &r;
-    old_r = &old_r - &quotient * &r;
-    old_s = &old_s - &quotient * &s;
-    old_t = &old_t - &quotient * &t;
-
-    std::mem::swap(&mut old_r, &mut r);
-    std::mem::swap(&mut old_s, &mut s);
-    std::mem::swap(&mut old_t, &mut t);
-  }
-
-  (old_r, old_s, old_t)
-}
-
 // Helper functions for modular arithmetic on u128 values
 
 /// Modular addition: (a + b) mod m
@@ -179,6 +236,21 @@ mod tests {
     assert_eq!(b.mul(&inv_b).value, BigUint::one());
   }
 
+  #[test]
+  fn test_other_moduli() {
+    // Goldilocks: 2^64 = 2^32 - 1 (mod p)
+    let two = Fp::<M64>::new(2);
+    assert_eq!(two.pow(64).to_u128(), (1u128 << 32) - 1);
+    let a = Fp::<M61>::new((1 << 61) + 5);
+    assert_eq!(a.to_u128(), 6);
+
+    // Elements wider than 128 bits
+    let b = Fp::<M255>::from_biguint(BigUint::one() << 200u32);
+    assert_eq!(b.mul(&b.inverse().unwrap()), Fp::one());
+    assert_eq!(Fp::<M255>::zero().sub(&Fp::one()).to_biguint(), M255::modulus() - BigUint::one());
+    assert!(Fp::<M127>::zero().inverse().is_none());
+  }
+
   #[test]
   fn test_modular_inverse() {
     let a = FieldElement::new(42);
@@ -0,0 +1,179 @@
+// Operation metrics
+// A process-wide Metrics sink receives every signing and verification with its outcome and
+// duration. Until one is installed with set_metrics the hooks cost a single atomic load, and
+// durations are not measured. MetricsCrateAdapter (feature `metrics`) forwards to the
+// recorder of the `metrics` crate.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// Instrumented operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+  Sign,
+  Verify,
+  RingSign,
+  RingVerify,
+  AggregateVerify,
+}
+
+impl Operation {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Operation::Sign => "sign",
+      Operation::Verify => "verify",
+      Operation::RingSign => "ring_sign",
+      Operation::RingVerify => "ring_verify",
+      Operation::AggregateVerify => "aggregate_verify",
+    }
+  }
+}
+
+// Why a verification failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureReason {
+  ConfigMismatch, // Signature made under other hash functions or another suite
+  NonCanonical, // A component outside its canonical range
+  KeyMismatch, // The recovered key does not match the public key
+  CommitmentMismatch, // The key matches but the Merkle commitment does not (tampered signature)
+  LengthMismatch, // Key and message lists of different lengths
+  InvalidSignature, // Any other failed check
+}
+
+impl FailureReason {
+  pub fn name(&self) -> &'static str {
+    match self {
+      FailureReason::ConfigMismatch => "config_mismatch",
+      FailureReason::NonCanonical => "non_canonical",
+      FailureReason::KeyMismatch => "key_mismatch",
+      FailureReason::CommitmentMismatch => "commitment_mismatch",
+      FailureReason::LengthMismatch => "length_mismatch",
+      FailureReason::InvalidSignature => "invalid_signature",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+  Success,
+  Failure(FailureReason),
+}
+
+impl Outcome {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Outcome::Success => "success",
+      Outcome::Failure(reason) => reason.name(),
+    }
+  }
+}
+
+// Sink for operation metrics; called from the thread that ran the operation
+pub trait Metrics: Send + Sync {
+  fn record(&self, operation: Operation, outcome: Outcome, duration: Duration);
+}
+
+// Discards everything; the behavior when no sink is installed
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+  fn record(&self, _operation: Operation, _outcome: Outcome, _duration: Duration) {}
+}
+
+static METRICS: OnceLock<Box<dyn Metrics>> = OnceLock::new();
+
+// Installs the process-wide sink; returns false if one was already installed
+pub fn set_metrics<M: Metrics + 'static>(metrics: M) -> bool {
+  METRICS.set(Box::new(metrics)).is_ok()
+}
+
+// Reports an operation that cannot fail
+#[cfg(feature = "std")]
+pub(crate) fn observe_infallible<T>(operation: Operation, f: impl FnOnce() -> T) -> T {
+  match METRICS.get() {
+    Some(metrics) => {
+      let start = Instant::now();
+      let result = f();
+      metrics.record(operation, Outcome::Success, start.elapsed());
+      result
+    }
+    None => f(),
+  }
+}
+
+// Reports a verification with its outcome
+pub(crate) fn observe<T>(operation: Operation, f: impl FnOnce() -> Result<T, FailureReason>) -> Result<T, FailureReason> {
+  match METRICS.get() {
+    Some(metrics) => {
+      let start = Instant::now();
+      let result = f();
+      let outcome = match &result {
+        Ok(_) => Outcome::Success,
+        Err(reason) => Outcome::Failure(*reason),
+      };
+      metrics.record(operation, outcome, start.elapsed());
+      result
+    }
+    None => f(),
+  }
+}
+
+// Forwards to the `metrics` crate: a `loquat_operations_total` counter labelled by operation
+// and outcome, and a `loquat_operation_duration_seconds` histogram labelled by operation
+#[cfg(feature = "metrics")]
+pub struct MetricsCrateAdapter;
+
+#[cfg(feature = "metrics")]
+impl Metrics for MetricsCrateAdapter {
+  fn record(&self, operation: Operation, outcome: Outcome, duration: Duration) {
+    ::metrics::counter!("loquat_operations_total", "operation" => operation.name(), "outcome" => outcome.name()).increment(1);
+    ::metrics::histogram!("loquat_operation_duration_seconds", "operation" => operation.name()).record(duration.as_secs_f64());
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use std::sync::Mutex;
+
+  // Keeps every (operation, outcome) pair
+  #[derive(Default)]
+  struct Recorder {
+    records: Mutex<Vec<(Operation, Outcome)>>,
+  }
+
+  struct Shared(&'static Recorder);
+
+  impl Metrics for Shared {
+    fn record(&self, operation: Operation, outcome: Outcome, _duration: Duration) {
+      self.0.records.lock().unwrap().push((operation, outcome));
+    }
+  }
+
+  #[test]
+  fn test_sign_and_verify_are_recorded() {
+    let recorder: &'static Recorder = Box::leak(Box::default());
+    assert!(set_metrics(Shared(recorder)));
+    assert!(!set_metrics(NoopMetrics));
+
+    // Concurrent tests report to the same sink, so only check that these records are present
+    let keypair = Loquat::keygen();
+    let signature = Loquat::sign(keypair.secret_key, b"metrics").unwrap();
+    assert!(Loquat::verify(&keypair.public_key, b"metrics", &signature));
+    assert!(!Loquat::verify(&keypair.public_key, b"other", &signature));
+    assert!(!Loquat::verify(&Loquat::keygen().public_key, b"metrics", &signature));
+
+    let records = recorder.records.lock().unwrap();
+    assert!(records.contains(&(Operation::Sign, Outcome::Success)));
+    assert!(records.contains(&(Operation::Verify, Outcome::Success)));
+    assert!(records.contains(&(Operation::Verify, Outcome::Failure(FailureReason::KeyMismatch))));
+  }
+
+  #[test]
+  fn test_outcome_names() {
+    assert_eq!(Outcome::Success.name(), "success");
+    assert_eq!(Outcome::Failure(FailureReason::ConfigMismatch).name(), "config_mismatch");
+    assert_eq!(Operation::AggregateVerify.name(), "aggregate_verify");
+  }
+}
@@ -1,2 +1,9 @@
 pub mod field_operations;
-pub mod encoding;
\ No newline at end of file
+pub mod encoding;
+pub mod codec;
+pub mod wire;
+pub mod time;
+pub mod constant_time;
+pub mod metrics;
+
+pub use field_operations::FieldElement;
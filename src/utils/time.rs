@@ -0,0 +1,140 @@
+// Timestamp helpers for credential validity periods
+// Timestamps are carried as RFC 3339 UTC strings (e.g. 2024-01-01T00:00:00Z) and
+// compared as seconds since the Unix epoch.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Current time in seconds since the Unix epoch
+pub fn now_unix() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp
+pub fn format_rfc3339(secs: u64) -> String {
+  let days = (secs / 86_400) as i64;
+  let rem = secs % 86_400;
+  let (year, month, day) = civil_from_days(days);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    rem / 3600,
+    (rem % 3600) / 60,
+    rem % 60
+  )
+}
+
+// Parses an RFC 3339 UTC timestamp of the form YYYY-MM-DDTHH:MM:SSZ
+// Fractional seconds are accepted and truncated; numeric offsets are not supported
+pub fn parse_rfc3339(input: &str) -> Option<u64> {
+  let bytes = input.as_bytes();
+  if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[10] != b'T' || bytes[13] != b':' || bytes[16] != b':' {
+    return None;
+  }
+  if !input.ends_with('Z') {
+    return None;
+  }
+
+  let field = |range: std::ops::Range<usize>| -> Option<u64> {
+    let part = input.get(range)?;
+    if !part.bytes().all(|b| b.is_ascii_digit()) {
+      return None;
+    }
+    part.parse().ok()
+  };
+  let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+  let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+
+  // Anything between the seconds and the trailing Z must be a fractional part
+  let fraction = &input[19..input.len() - 1];
+  if let Some(digits) = fraction.strip_prefix('.') {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+      return None;
+    }
+  } else if !fraction.is_empty() {
+    return None;
+  }
+
+  if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 59 {
+    return None;
+  }
+
+  let days = days_from_civil(year as i64, month as u32, day as u32);
+  if days < 0 {
+    return None;
+  }
+  Some(days as u64 * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+fn is_leap_year(year: u64) -> bool {
+  (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+// Proleptic Gregorian date to days since 1970-01-01
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let yoe = year.rem_euclid(400);
+  let month = month as i64;
+  let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_rfc3339() {
+    assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+    assert_eq!(format_rfc3339(1_704_067_199), "2023-12-31T23:59:59Z");
+  }
+
+  #[test]
+  fn test_parse_rfc3339() {
+    assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"), Some(951_782_400));
+    assert_eq!(parse_rfc3339("2023-12-31T23:59:59.250Z"), Some(1_704_067_199));
+
+    assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+    assert_eq!(parse_rfc3339("2023-12-31T23:59:59+09:00"), None);
+    assert_eq!(parse_rfc3339("2023-12-31 23:59:59Z"), None);
+    assert_eq!(parse_rfc3339("not a timestamp"), None);
+  }
+
+  #[test]
+  fn test_round_trip() {
+    for secs in [0u64, 86_399, 1_000_000_000, 4_102_444_800] {
+      assert_eq!(parse_rfc3339(&format_rfc3339(secs)), Some(secs));
+    }
+  }
+}
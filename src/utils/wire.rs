@@ -0,0 +1,132 @@
+// Fixed-width wire forms of field elements and digests
+// Serialized signatures and proofs carry field elements as 16 big-endian bytes and digests as
+// 32, never as length-prefixed minimal BigUint encodings. A value then has exactly one
+// encoding, every encoding of a structure has the same length, and other implementations
+// read them without a bignum library. Range checks (sigma below P and so on) stay with the
+// decoder of each structure.
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+pub const FIELD_BYTES: usize = 16;
+pub const DIGEST_BYTES: usize = 32;
+
+pub type FieldBytes = [u8; FIELD_BYTES];
+pub type DigestBytes = [u8; DIGEST_BYTES];
+
+// Deepest Merkle path a depth byte can describe
+pub const MAX_PATH_DEPTH: usize = u8::MAX as usize;
+
+// None for values wider than 128 bits
+pub fn field_to_bytes(value: &BigUint) -> Option<FieldBytes> {
+  value.to_u128().map(u128::to_be_bytes)
+}
+
+pub fn field_from_bytes(bytes: &FieldBytes) -> BigUint {
+  BigUint::from(u128::from_be_bytes(*bytes))
+}
+
+// None for values wider than 256 bits
+pub fn digest_to_bytes(value: &BigUint) -> Option<DigestBytes> {
+  let bytes = value.to_bytes_be();
+  let mut out = [0u8; DIGEST_BYTES];
+  out.get_mut(DIGEST_BYTES.checked_sub(bytes.len())?..)?.copy_from_slice(&bytes);
+  Some(out)
+}
+
+pub fn digest_from_bytes(bytes: &DigestBytes) -> BigUint {
+  BigUint::from_bytes_be(bytes)
+}
+
+// Appends a field element; structures only hold values below 2^128
+pub fn write_field(out: &mut Vec<u8>, value: &BigUint) {
+  out.extend_from_slice(&field_to_bytes(value).expect("Field element wider than 128 bits"));
+}
+
+// Appends a digest; structures only hold values below 2^256
+pub fn write_digest(out: &mut Vec<u8>, value: &BigUint) {
+  out.extend_from_slice(&digest_to_bytes(value).expect("Digest wider than 256 bits"));
+}
+
+pub fn read_field(input: &[u8], offset: &mut usize) -> Option<FieldBytes> {
+  let bytes = input.get(*offset..offset.checked_add(FIELD_BYTES)?)?;
+  *offset += FIELD_BYTES;
+  bytes.try_into().ok()
+}
+
+pub fn read_digest(input: &[u8], offset: &mut usize) -> Option<DigestBytes> {
+  let bytes = input.get(*offset..offset.checked_add(DIGEST_BYTES)?)?;
+  *offset += DIGEST_BYTES;
+  bytes.try_into().ok()
+}
+
+// Merkle authentication path: depth byte, then per level a side flag (0 or 1) and the sibling
+pub fn write_path(out: &mut Vec<u8>, path: &[(BigUint, bool)]) {
+  assert!(path.len() <= MAX_PATH_DEPTH, "Merkle path deeper than {} levels", MAX_PATH_DEPTH);
+  out.push(path.len() as u8);
+  for (sibling, is_left) in path {
+    out.push(*is_left as u8);
+    write_digest(out, sibling);
+  }
+}
+
+pub fn read_path(input: &[u8], offset: &mut usize) -> Option<Vec<(BigUint, bool)>> {
+  let depth = *input.get(*offset)? as usize;
+  *offset += 1;
+  // The whole path must be present before anything is allocated for it
+  if input.len().saturating_sub(*offset) < depth * (1 + DIGEST_BYTES) {
+    return None;
+  }
+  let mut path = Vec::with_capacity(depth);
+  for _ in 0..depth {
+    let is_left = match input[*offset] {
+      0 => false,
+      1 => true,
+      _ => return None,
+    };
+    *offset += 1;
+    path.push((digest_from_bytes(&read_digest(input, offset)?), is_left));
+  }
+  Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fixed_widths() {
+    let small = BigUint::from(5u8);
+    assert_eq!(field_to_bytes(&small), Some([&[0u8; 15][..], &[5]].concat().try_into().unwrap()));
+    assert_eq!(field_from_bytes(&field_to_bytes(&small).unwrap()), small);
+    assert_eq!(field_to_bytes(&(BigUint::from(1u8) << 128)), None);
+
+    let digest = (BigUint::from(1u8) << 255) + 1u8;
+    assert_eq!(digest_from_bytes(&digest_to_bytes(&digest).unwrap()), digest);
+    assert_eq!(digest_to_bytes(&BigUint::from(0u8)), Some([0u8; DIGEST_BYTES]));
+    assert_eq!(digest_to_bytes(&(BigUint::from(1u8) << 256)), None);
+
+    let mut offset = 30;
+    assert_eq!(read_digest(&[0u8; 40], &mut offset), None);
+    assert_eq!(read_field(&[0u8; 46], &mut offset), Some([0u8; FIELD_BYTES]));
+    assert_eq!(offset, 46);
+  }
+
+  #[test]
+  fn test_paths() {
+    let path = vec![(BigUint::from(7u8), true), (BigUint::from(1u8) << 200, false)];
+    let mut out = vec![];
+    write_path(&mut out, &path);
+    assert_eq!(out.len(), 1 + 2 * (1 + DIGEST_BYTES));
+    let mut offset = 0;
+    assert_eq!(read_path(&out, &mut offset), Some(path));
+    assert_eq!(offset, out.len());
+
+    // Truncated paths, depths beyond the input and flags other than 0 or 1 are rejected
+    assert_eq!(read_path(&out[..out.len() - 1], &mut 0), None);
+    assert_eq!(read_path(&[u8::MAX], &mut 0), None);
+    let mut flag = out.clone();
+    flag[1] = 2;
+    assert_eq!(read_path(&flag, &mut 0), None);
+  }
+}
@@ -0,0 +1,476 @@
+// Anoncreds-style ledger objects and proof requests
+// Issuers publish a schema and a credential definition (schema id, Loquat public key and an
+// optional revocation registry); verifiers send proof requests listing requested attributes
+// and predicates. Credentials are issued as SD-JWTs whose attributes are all selectively
+// disclosable, and a proof is one key-bound SD-JWT presentation per credential used.
+//
+// Limitation: predicates are proven by disclosure. The attribute behind a predicate is
+// revealed to the verifier, which checks the predicate on the value itself; there is no
+// zero-knowledge range proof.
+
+use crate::utils::encoding::Encoding;
+use crate::vc::did::DidResolver;
+use crate::vc::jwt::Jwt;
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::sd_jwt::SdJwt;
+use crate::vc::status::{CredentialStatus, StatusListSource};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+// Attribute schema published by an issuer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+  pub id: String,
+  pub issuer_id: String,
+  pub name: String,
+  pub version: String,
+  pub attr_names: Vec<String>,
+}
+
+impl Schema {
+  pub fn new(issuer_id: &str, name: &str, version: &str, attr_names: &[&str]) -> Self {
+    Self {
+      id: format!("{}/anoncreds/v0/SCHEMA/{}/{}", issuer_id, name, version),
+      issuer_id: issuer_id.to_string(),
+      name: name.to_string(),
+      version: version.to_string(),
+      attr_names: attr_names.iter().map(|name| name.to_string()).collect(),
+    }
+  }
+}
+
+// Issuer-published credential definition binding a schema to a signing key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialDefinition {
+  pub id: String,
+  pub schema_id: String,
+  pub issuer_id: String,
+  pub tag: String,
+  pub attr_names: Vec<String>,
+  pub public_key_hex: String, // Loquat public key the credentials are signed with
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub revocation_registry: Option<String>, // Status list URL, if credentials can be revoked
+}
+
+impl CredentialDefinition {
+  pub fn new(schema: &Schema, tag: &str, public_key: &[u8], revocation_registry: Option<&str>) -> Self {
+    Self {
+      id: format!("{}/anoncreds/v0/CLAIM_DEF/{}/{}", schema.issuer_id, schema.id, tag),
+      schema_id: schema.id.clone(),
+      issuer_id: schema.issuer_id.clone(),
+      tag: tag.to_string(),
+      attr_names: schema.attr_names.clone(),
+      public_key_hex: Encoding::to_hex(public_key),
+      revocation_registry: revocation_registry.map(str::to_string),
+    }
+  }
+
+  pub fn public_key(&self) -> Option<Vec<u8>> {
+    Encoding::from_hex(&self.public_key_hex)
+  }
+
+  // Issues a credential holding exactly the schema's attributes, bound to the holder key
+  // A revocation index is required if and only if the definition has a revocation registry
+  pub fn issue(&self, values: &Map<String, Value>, holder_kid: &str, revocation_index: Option<usize>, sk: u128) -> Option<SdJwt> {
+    if values.len() != self.attr_names.len() || !self.attr_names.iter().all(|name| values.contains_key(name)) {
+      return None;
+    }
+
+    let mut claims = values.clone();
+    claims.insert("iss".to_string(), Value::String(self.issuer_id.clone()));
+    claims.insert("schema_id".to_string(), Value::String(self.schema_id.clone()));
+    claims.insert("cred_def_id".to_string(), Value::String(self.id.clone()));
+    match (&self.revocation_registry, revocation_index) {
+      (Some(registry), Some(index)) => {
+        let status = serde_json::to_value(CredentialStatus::new(registry, index)).expect("Status serialization failed");
+        claims.insert("credential_status".to_string(), status);
+      }
+      (None, None) => {}
+      _ => return None,
+    }
+
+    let disclosable: Vec<&str> = self.attr_names.iter().map(String::as_str).collect();
+    SdJwt::issue(&Value::Object(claims), &disclosable, Some(holder_kid), &format!("{}#{}", self.issuer_id, self.tag), sk)
+  }
+}
+
+// Restriction on which credentials may satisfy a requested attribute or predicate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Restriction {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub schema_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cred_def_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub issuer_id: Option<String>,
+}
+
+impl Restriction {
+  fn allows(&self, identifier: &Identifier, issuer_id: &str) -> bool {
+    self.schema_id.as_ref().is_none_or(|id| *id == identifier.schema_id)
+      && self.cred_def_id.as_ref().is_none_or(|id| *id == identifier.cred_def_id)
+      && self.issuer_id.as_ref().is_none_or(|id| id == issuer_id)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeInfo {
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub restrictions: Vec<Restriction>, // Any one restriction must hold; empty allows any credential
+}
+
+// Predicate comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PredicateType {
+  #[serde(rename = ">=")]
+  Ge,
+  #[serde(rename = ">")]
+  Gt,
+  #[serde(rename = "<=")]
+  Le,
+  #[serde(rename = "<")]
+  Lt,
+}
+
+impl PredicateType {
+  pub fn holds(&self, value: i64, bound: i64) -> bool {
+    match self {
+      PredicateType::Ge => value >= bound,
+      PredicateType::Gt => value > bound,
+      PredicateType::Le => value <= bound,
+      PredicateType::Lt => value < bound,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredicateInfo {
+  pub name: String,
+  pub p_type: PredicateType,
+  pub p_value: i64,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub restrictions: Vec<Restriction>,
+}
+
+// Verifier request for attributes and predicates, keyed by referent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofRequest {
+  pub name: String, // Also used as the key binding audience
+  pub version: String,
+  pub nonce: String,
+  #[serde(default)]
+  pub requested_attributes: BTreeMap<String, AttributeInfo>,
+  #[serde(default)]
+  pub requested_predicates: BTreeMap<String, PredicateInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevealedAttribute {
+  pub sub_proof_index: usize,
+  pub raw: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubProofReferent {
+  pub sub_proof_index: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestedProof {
+  pub revealed_attrs: BTreeMap<String, RevealedAttribute>,
+  pub predicates: BTreeMap<String, SubProofReferent>,
+}
+
+// Schema and credential definition of each sub-proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identifier {
+  pub schema_id: String,
+  pub cred_def_id: String,
+}
+
+// Holder proof answering a proof request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Proof {
+  pub requested_proof: RequestedProof,
+  pub identifiers: Vec<Identifier>,
+  pub presentations: Vec<String>, // Serialized key-bound SD-JWT per sub-proof
+}
+
+// Issuer claims of a credential as seen by its holder
+struct Held<'a> {
+  sd_jwt: &'a SdJwt,
+  identifier: Identifier,
+  issuer_id: String,
+  claims: Value,
+}
+
+fn satisfies(restrictions: &[Restriction], identifier: &Identifier, issuer_id: &str) -> bool {
+  restrictions.is_empty() || restrictions.iter().any(|restriction| restriction.allows(identifier, issuer_id))
+}
+
+fn find_definition<'a>(definitions: &'a [CredentialDefinition], id: &str) -> Option<&'a CredentialDefinition> {
+  definitions.iter().find(|definition| definition.id == id)
+}
+
+// Returns the sub-proof index for a credential, recording an attribute it must disclose
+fn sub_proof(sub_proofs: &mut Vec<(usize, Vec<String>)>, credential: usize, name: &str) -> usize {
+  let position = match sub_proofs.iter().position(|(index, _)| *index == credential) {
+    Some(position) => position,
+    None => {
+      sub_proofs.push((credential, vec![]));
+      sub_proofs.len() - 1
+    }
+  };
+  let names = &mut sub_proofs[position].1;
+  if !names.iter().any(|disclosed| disclosed == name) {
+    names.push(name.to_string());
+  }
+  position
+}
+
+impl ProofRequest {
+  // Holder side: picks a credential for every referent (first match wins), discloses the
+  // requested and predicate attributes, and binds each presentation to the request nonce
+  pub fn create_proof(&self, credentials: &[SdJwt], definitions: &[CredentialDefinition], holder_sk: u128, now: u64) -> Option<Proof> {
+    let held: Vec<Held> = credentials
+      .iter()
+      .filter_map(|sd_jwt| {
+        let cred_def_id = Jwt::parse(&sd_jwt.jwt)?.claim_str("cred_def_id")?.to_string();
+        let definition = find_definition(definitions, &cred_def_id)?;
+        Some(Held {
+          sd_jwt,
+          identifier: Identifier {
+            schema_id: definition.schema_id.clone(),
+            cred_def_id,
+          },
+          issuer_id: definition.issuer_id.clone(),
+          claims: sd_jwt.verify(&definition.public_key()?)?,
+        })
+      })
+      .collect();
+
+    let mut sub_proofs = vec![];
+    let mut requested_proof = RequestedProof::default();
+    for (referent, info) in &self.requested_attributes {
+      let index = held
+        .iter()
+        .position(|h| satisfies(&info.restrictions, &h.identifier, &h.issuer_id) && h.claims.get(&info.name).is_some())?;
+      requested_proof.revealed_attrs.insert(
+        referent.clone(),
+        RevealedAttribute {
+          sub_proof_index: sub_proof(&mut sub_proofs, index, &info.name),
+          raw: held[index].claims[&info.name].clone(),
+        },
+      );
+    }
+    for (referent, info) in &self.requested_predicates {
+      let index = held.iter().position(|h| {
+        satisfies(&info.restrictions, &h.identifier, &h.issuer_id)
+          && h.claims.get(&info.name).and_then(Value::as_i64).is_some_and(|value| info.p_type.holds(value, info.p_value))
+      })?;
+      requested_proof.predicates.insert(
+        referent.clone(),
+        SubProofReferent {
+          sub_proof_index: sub_proof(&mut sub_proofs, index, &info.name),
+        },
+      );
+    }
+
+    let mut identifiers = Vec::with_capacity(sub_proofs.len());
+    let mut presentations = Vec::with_capacity(sub_proofs.len());
+    for (index, names) in &sub_proofs {
+      let names: Vec<&str> = names.iter().map(String::as_str).collect();
+      let mut presentation = held[*index].sd_jwt.present(&names)?;
+      presentation.bind(holder_sk, &self.name, &self.nonce, now).ok()?;
+      identifiers.push(held[*index].identifier.clone());
+      presentations.push(presentation.serialize());
+    }
+
+    Some(Proof {
+      requested_proof,
+      identifiers,
+      presentations,
+    })
+  }
+
+  // Verifier side: checks every sub-proof against its credential definition (issuer signature,
+  // key binding to this request's nonce, revocation), then every referent against the revealed claims
+  pub fn verify<R, S>(&self, proof: &Proof, definitions: &[CredentialDefinition], resolver: &R, status_lists: &S) -> bool
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    self.check_proof(proof, definitions, resolver, status_lists).is_some()
+  }
+
+  fn check_proof<R, S>(&self, proof: &Proof, definitions: &[CredentialDefinition], resolver: &R, status_lists: &S) -> Option<()>
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    if proof.identifiers.len() != proof.presentations.len() {
+      return None;
+    }
+
+    let mut revealed = Vec::with_capacity(proof.presentations.len());
+    for (identifier, serialized) in proof.identifiers.iter().zip(&proof.presentations) {
+      let definition = find_definition(definitions, &identifier.cred_def_id)?;
+      if definition.schema_id != identifier.schema_id {
+        return None;
+      }
+
+      let presentation = SdJwt::parse(serialized)?;
+      let claims = presentation.verify(&definition.public_key()?)?;
+      if claims.get("cred_def_id")?.as_str()? != definition.id {
+        return None;
+      }
+
+      let holder_pk = resolver.resolve_key_for(claims.get("cnf")?.get("kid")?.as_str()?, KeyUsage::PRESENTATION)?;
+      if !presentation.verify_key_binding(&holder_pk, &self.name, &self.nonce) {
+        return None;
+      }
+
+      if let Some(registry) = &definition.revocation_registry {
+        let status: CredentialStatus = serde_json::from_value(claims.get("credential_status")?.clone()).ok()?;
+        if status.status_list_credential != *registry || status_lists.is_revoked(&status) {
+          return None;
+        }
+      }
+      revealed.push((claims, definition.issuer_id.as_str()));
+    }
+
+    for (referent, info) in &self.requested_attributes {
+      let attribute = proof.requested_proof.revealed_attrs.get(referent)?;
+      let (claims, issuer_id) = revealed.get(attribute.sub_proof_index)?;
+      if !satisfies(&info.restrictions, &proof.identifiers[attribute.sub_proof_index], issuer_id) || *claims.get(&info.name)? != attribute.raw {
+        return None;
+      }
+    }
+    for (referent, info) in &self.requested_predicates {
+      let index = proof.requested_proof.predicates.get(referent)?.sub_proof_index;
+      let (claims, issuer_id) = revealed.get(index)?;
+      let value = claims.get(&info.name)?.as_i64()?;
+      if !satisfies(&info.restrictions, &proof.identifiers[index], issuer_id) || !info.p_type.holds(value, info.p_value) {
+        return None;
+      }
+    }
+    Some(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::{InMemoryStatusLists, StatusList};
+  use serde_json::json;
+
+  const REGISTRY: &str = "https://issuer.example/status/1";
+
+  struct Fixture {
+    definition: CredentialDefinition,
+    issuer_sk: u128,
+    holder_sk: u128,
+    holder_kid: String,
+    resolver: StaticDidResolver,
+    status_lists: InMemoryStatusLists,
+  }
+
+  fn fixture() -> Fixture {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let schema = Schema::new("did:example:issuer", "degree", "1.0", &["name", "degree", "age"]);
+    let mut document = DidDocument::new("did:example:alice");
+    let holder_kid = document.add_key("key-1", &holder.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+    let mut status_lists = InMemoryStatusLists::new();
+    status_lists.insert(REGISTRY, StatusList::new(16));
+
+    Fixture {
+      definition: CredentialDefinition::new(&schema, "default", &issuer.public_key, Some(REGISTRY)),
+      issuer_sk: issuer.secret_key,
+      holder_sk: holder.secret_key,
+      holder_kid,
+      resolver,
+      status_lists,
+    }
+  }
+
+  fn values() -> Map<String, Value> {
+    json!({"name": "Alice", "degree": "MSc", "age": 30}).as_object().unwrap().clone()
+  }
+
+  fn request(f: &Fixture, nonce: &str, min_age: i64) -> ProofRequest {
+    serde_json::from_value(json!({
+      "name": "job-application",
+      "version": "1.0",
+      "nonce": nonce,
+      "requested_attributes": {"attr1_referent": {"name": "degree", "restrictions": [{"cred_def_id": f.definition.id}]}},
+      "requested_predicates": {"predicate1_referent": {"name": "age", "p_type": ">=", "p_value": min_age}}
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn test_proof_round_trip() {
+    let f = fixture();
+    let credential = f.definition.issue(&values(), &f.holder_kid, Some(3), f.issuer_sk).unwrap();
+    let request = request(&f, "1234", 18);
+    let definitions = [f.definition.clone()];
+
+    let proof = request.create_proof(&[credential], &definitions, f.holder_sk, 1_000).unwrap();
+    assert_eq!(proof.presentations.len(), 1);
+    assert_eq!(proof.requested_proof.revealed_attrs["attr1_referent"].raw, json!("MSc"));
+    assert!(request.verify(&proof, &definitions, &f.resolver, &f.status_lists));
+
+    // The name was never requested, so its disclosure is not part of the proof
+    let revealed = SdJwt::parse(&proof.presentations[0]).unwrap().verify(&f.definition.public_key().unwrap()).unwrap();
+    assert!(revealed.get("name").is_none());
+    assert_eq!(revealed["age"], 30);
+  }
+
+  #[test]
+  fn test_unsatisfiable_requests() {
+    let f = fixture();
+    let credential = f.definition.issue(&values(), &f.holder_kid, Some(3), f.issuer_sk).unwrap();
+    let definitions = [f.definition.clone()];
+    assert!(request(&f, "1234", 40).create_proof(std::slice::from_ref(&credential), &definitions, f.holder_sk, 1_000).is_none());
+
+    let mut restricted = request(&f, "1234", 18);
+    restricted.requested_attributes.get_mut("attr1_referent").unwrap().restrictions[0].cred_def_id = Some("other".to_string());
+    assert!(restricted.create_proof(&[credential], &definitions, f.holder_sk, 1_000).is_none());
+
+    // Issuance requires exactly the schema attributes and a revocation index for revocable definitions
+    let mut missing = values();
+    missing.remove("age");
+    assert!(f.definition.issue(&missing, &f.holder_kid, Some(3), f.issuer_sk).is_none());
+    assert!(f.definition.issue(&values(), &f.holder_kid, None, f.issuer_sk).is_none());
+  }
+
+  #[test]
+  fn test_verifier_rejections() {
+    let mut f = fixture();
+    let credential = f.definition.issue(&values(), &f.holder_kid, Some(3), f.issuer_sk).unwrap();
+    let definitions = [f.definition.clone()];
+    let proof_request = request(&f, "1234", 18);
+    let proof = proof_request.create_proof(&[credential], &definitions, f.holder_sk, 1_000).unwrap();
+
+    // Replayed against a request with another nonce
+    let other = request(&f, "5678", 18);
+    assert!(!other.verify(&proof, &definitions, &f.resolver, &f.status_lists));
+
+    // Claimed raw value differs from the disclosed one
+    let mut tampered = proof.clone();
+    tampered.requested_proof.revealed_attrs.get_mut("attr1_referent").unwrap().raw = json!("PhD");
+    assert!(!proof_request.verify(&tampered, &definitions, &f.resolver, &f.status_lists));
+
+    f.status_lists.get_mut(REGISTRY).unwrap().set_revoked(3, true);
+    assert!(!proof_request.verify(&proof, &definitions, &f.resolver, &f.status_lists));
+  }
+}
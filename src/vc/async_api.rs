@@ -0,0 +1,152 @@
+// Async façade for web services built on tokio
+// Issuance and verification are CPU-bound, so they run on tokio's blocking pool
+// via spawn_blocking instead of stalling the async worker threads. DID resolution
+// and status-list fetching accept either blocking implementations (run on the
+// blocking pool) or natively async sources.
+
+use crate::crypto::rng::RngHealthError;
+use crate::vc::credential::Credential;
+use crate::vc::did::{DidDocument, DidResolver};
+use crate::vc::status::{CredentialStatus, StatusList, StatusListSource};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::task;
+
+// Async counterpart of StatusListSource, for fetchers backed by async HTTP clients
+pub trait AsyncStatusListSource {
+  fn fetch(&self, url: &str) -> impl Future<Output = Option<StatusList>> + Send;
+}
+
+// Runs a blocking closure on tokio's blocking pool, propagating panics
+async fn run_blocking<T, F>(f: F) -> T
+where
+  F: FnOnce() -> T + Send + 'static,
+  T: Send + 'static,
+{
+  task::spawn_blocking(f).await.expect("Blocking task panicked")
+}
+
+// Signs a credential without blocking the async runtime
+pub async fn issue_credential(mut credential: Credential, sk: u128, verification_method: String) -> Result<Credential, RngHealthError> {
+  run_blocking(move || {
+    credential.sign(sk, &verification_method)?;
+    Ok(credential)
+  })
+  .await
+}
+
+// Verifies a credential signature against a known issuer key
+pub async fn verify_credential(credential: Credential, issuer_pk: Vec<u8>) -> bool {
+  run_blocking(move || credential.verify(&issuer_pk)).await
+}
+
+// Resolves a DID with a blocking resolver
+pub async fn resolve_did<R>(resolver: Arc<R>, did: String) -> Option<DidDocument>
+where
+  R: DidResolver + Send + Sync + 'static,
+{
+  run_blocking(move || resolver.resolve(&did)).await
+}
+
+// Fetches a status list with a blocking source
+pub async fn fetch_status_list<S>(source: Arc<S>, url: String) -> Option<StatusList>
+where
+  S: StatusListSource + Send + Sync + 'static,
+{
+  run_blocking(move || source.fetch(&url)).await
+}
+
+// Verifies a credential end to end: resolves the issuer key, fetches the status list
+// from an async source, then checks signature, expiry and revocation on the blocking pool
+pub async fn verify_credential_with_status<R, S>(credential: Credential, resolver: Arc<R>, status_source: &S, now: u64) -> bool
+where
+  R: DidResolver + Send + Sync + 'static,
+  S: AsyncStatusListSource,
+{
+  // Fetch the referenced status list up front so the blocking check needs no IO
+  let snapshot = match &credential.credential_status {
+    Some(status) => match status_source.fetch(&status.status_list_credential).await {
+      Some(list) => Some((status.clone(), list)),
+      None => return false,
+    },
+    None => None,
+  };
+
+  run_blocking(move || {
+    let status_lists = FetchedStatusList(snapshot);
+    credential.verify_with_resolver(resolver.as_ref(), &status_lists, now)
+  })
+  .await
+}
+
+// A single status list fetched ahead of verification
+struct FetchedStatusList(Option<(CredentialStatus, StatusList)>);
+
+impl StatusListSource for FetchedStatusList {
+  fn fetch(&self, url: &str) -> Option<StatusList> {
+    match &self.0 {
+      Some((status, list)) if status.status_list_credential == url => Some(list.clone()),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::StaticDidResolver;
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  // Async source serving lists from memory
+  struct MemorySource(InMemoryStatusLists);
+
+  impl AsyncStatusListSource for MemorySource {
+    async fn fetch(&self, url: &str) -> Option<StatusList> {
+      StatusListSource::fetch(&self.0, url)
+    }
+  }
+
+  fn setup() -> (u128, String, Arc<StaticDidResolver>) {
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key("key-1", &keypair.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+    (keypair.secret_key, key_id, Arc::new(resolver))
+  }
+
+  #[tokio::test]
+  async fn test_async_issue_and_verify() {
+    let (sk, key_id, resolver) = setup();
+    let credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    let credential = issue_credential(credential, sk, key_id.clone()).await.unwrap();
+
+    let document = resolve_did(resolver.clone(), "did:example:issuer".to_string()).await.unwrap();
+    let issuer_pk = document.verification_method(&key_id).unwrap().public_key().unwrap();
+    assert!(verify_credential(credential, issuer_pk).await);
+  }
+
+  #[tokio::test]
+  async fn test_async_status_checks() {
+    let (sk, key_id, resolver) = setup();
+    let url = "https://issuer.example/status/1";
+    let mut lists = InMemoryStatusLists::new();
+    lists.insert(url, StatusList::new(8));
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.credential_status = Some(CredentialStatus::new(url, 2));
+    let credential = issue_credential(credential, sk, key_id).await.unwrap();
+
+    let blocking = Arc::new(lists.clone());
+    assert_eq!(fetch_status_list(blocking, url.to_string()).await, Some(StatusList::new(8)));
+
+    let source = MemorySource(lists.clone());
+    assert!(verify_credential_with_status(credential.clone(), resolver.clone(), &source, 0).await);
+
+    lists.get_mut(url).unwrap().set_revoked(2, true);
+    let source = MemorySource(lists);
+    assert!(!verify_credential_with_status(credential, resolver, &source, 0).await);
+  }
+}
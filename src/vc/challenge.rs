@@ -0,0 +1,308 @@
+// Single-use challenges for presentation protocols
+// The verifier issues a random challenge with an expiry, the holder signs it into the
+// presentation, and the verifier consumes it exactly once. Replaying a presentation
+// after its challenge has been consumed (or has expired) fails.
+
+use crate::crypto::test_rng;
+use crate::utils::encoding::Encoding;
+use crate::utils::time;
+use crate::vc::presentation::Presentation;
+use rand::RngCore;
+use std::collections::{BTreeSet, HashMap};
+
+// Number of random bytes in a challenge
+const CHALLENGE_BYTES: usize = 32;
+
+// Outstanding challenges an in-memory store keeps by default
+pub const DEFAULT_MAX_OUTSTANDING: usize = 1 << 16;
+
+// A challenge handed to a holder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+  pub value: String, // Hex-encoded random value
+  pub expires_at: u64, // Seconds since the Unix epoch
+}
+
+// Reasons a challenge check can fail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChallengeError {
+  Unknown, // Never issued, or already consumed
+  Expired,
+  InvalidPresentation, // The presentation signature does not verify
+}
+
+// Storage backend for outstanding challenges
+// Implementations must make take() atomic so a challenge can be consumed only once
+pub trait ChallengeStore {
+  // Records a newly issued challenge
+  fn insert(&mut self, challenge: Challenge);
+
+  // Removes and returns a challenge by value
+  fn take(&mut self, value: &str) -> Option<Challenge>;
+
+  // Drops every challenge that expired before the given time
+  fn purge_expired(&mut self, now: u64);
+}
+
+// Challenge store kept in process memory
+// Holds at most max_outstanding challenges: once full, inserting evicts the challenge that
+// expires first, so issuing faster than challenges are consumed cannot exhaust memory.
+// Challenges are also indexed by expiry, so eviction and purging cost O(log n) per challenge
+// dropped rather than a scan of the store
+#[derive(Debug)]
+pub struct InMemoryChallengeStore {
+  challenges: HashMap<String, u64>,
+  by_expiry: BTreeSet<(u64, String)>,
+  max_outstanding: usize,
+}
+
+impl Default for InMemoryChallengeStore {
+  fn default() -> Self {
+    Self::with_limit(DEFAULT_MAX_OUTSTANDING)
+  }
+}
+
+impl InMemoryChallengeStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_limit(max_outstanding: usize) -> Self {
+    Self {
+      challenges: HashMap::new(),
+      by_expiry: BTreeSet::new(),
+      max_outstanding: max_outstanding.max(1),
+    }
+  }
+
+  // Number of outstanding challenges
+  pub fn len(&self) -> usize {
+    self.challenges.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.challenges.is_empty()
+  }
+}
+
+impl ChallengeStore for InMemoryChallengeStore {
+  fn insert(&mut self, challenge: Challenge) {
+    if let Some(expires_at) = self.challenges.remove(&challenge.value) {
+      self.by_expiry.remove(&(expires_at, challenge.value.clone()));
+    } else if self.challenges.len() >= self.max_outstanding {
+      if let Some((_, value)) = self.by_expiry.pop_first() {
+        self.challenges.remove(&value);
+      }
+    }
+    self.by_expiry.insert((challenge.expires_at, challenge.value.clone()));
+    self.challenges.insert(challenge.value, challenge.expires_at);
+  }
+
+  fn take(&mut self, value: &str) -> Option<Challenge> {
+    let expires_at = self.challenges.remove(value)?;
+    let value = value.to_string();
+    self.by_expiry.remove(&(expires_at, value.clone()));
+    Some(Challenge { value, expires_at })
+  }
+
+  fn purge_expired(&mut self, now: u64) {
+    while let Some((expires_at, _)) = self.by_expiry.first() {
+      if *expires_at > now {
+        break;
+      }
+      if let Some((_, value)) = self.by_expiry.pop_first() {
+        self.challenges.remove(&value);
+      }
+    }
+  }
+}
+
+// Issues and checks single-use challenges
+pub struct ChallengeManager<S: ChallengeStore = InMemoryChallengeStore> {
+  store: S,
+  ttl_secs: u64,
+}
+
+impl ChallengeManager<InMemoryChallengeStore> {
+  // Creates a manager backed by an in-memory store
+  pub fn new(ttl_secs: u64) -> Self {
+    Self::with_store(InMemoryChallengeStore::new(), ttl_secs)
+  }
+}
+
+impl<S: ChallengeStore> ChallengeManager<S> {
+  // Creates a manager backed by a custom store
+  pub fn with_store(store: S, ttl_secs: u64) -> Self {
+    Self { store, ttl_secs }
+  }
+
+  // Access to the underlying store
+  pub fn store(&self) -> &S {
+    &self.store
+  }
+
+  // Issues a new challenge valid for the configured time-to-live
+  pub fn issue(&mut self) -> Challenge {
+    self.issue_at(time::now_unix())
+  }
+
+  // Issues a new challenge relative to an explicit current time, dropping the challenges
+  // that have expired by then
+  pub fn issue_at(&mut self, now: u64) -> Challenge {
+    self.store.purge_expired(now);
+    let mut bytes = [0u8; CHALLENGE_BYTES];
+    test_rng::source().fill_bytes(&mut bytes);
+
+    let challenge = Challenge {
+      value: Encoding::to_hex(&bytes),
+      expires_at: now.saturating_add(self.ttl_secs),
+    };
+    self.store.insert(challenge.clone());
+    challenge
+  }
+
+  // Consumes a challenge; succeeds at most once per issued challenge
+  pub fn consume(&mut self, value: &str, now: u64) -> Result<(), ChallengeError> {
+    let challenge = self.store.take(value).ok_or(ChallengeError::Unknown)?;
+    if now >= challenge.expires_at {
+      return Err(ChallengeError::Expired);
+    }
+    Ok(())
+  }
+
+  // Verifies a presentation's holder signature and consumes the challenge it is bound to
+  // The signature is checked first so that forged presentations cannot burn live challenges
+  pub fn verify_presentation(&mut self, presentation: &Presentation, holder_pk: &[u8], now: u64) -> Result<(), ChallengeError> {
+    if !presentation.verify(holder_pk) {
+      return Err(ChallengeError::InvalidPresentation);
+    }
+    self.consume(&presentation.challenge, now)
+  }
+
+  // verify_presentation for presentations signed with sign_for_audience; the signature must
+  // bind this verifier's identifier as well as the challenge
+  pub fn verify_presentation_for(&mut self, presentation: &Presentation, holder_pk: &[u8], audience: &str, now: u64) -> Result<(), ChallengeError> {
+    if !presentation.verify_for_audience(holder_pk, audience, &presentation.challenge) {
+      return Err(ChallengeError::InvalidPresentation);
+    }
+    self.consume(&presentation.challenge, now)
+  }
+
+  // Drops expired challenges from the store
+  pub fn purge_expired(&mut self, now: u64) {
+    self.store.purge_expired(now);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::credential::Credential;
+  use serde_json::json;
+
+  #[test]
+  fn test_challenge_single_use() {
+    let mut manager = ChallengeManager::new(60);
+    let challenge = manager.issue_at(1_000);
+
+    assert_eq!(manager.consume(&challenge.value, 1_010), Ok(()));
+    assert_eq!(manager.consume(&challenge.value, 1_010), Err(ChallengeError::Unknown));
+    assert_eq!(manager.consume("never-issued", 1_010), Err(ChallengeError::Unknown));
+  }
+
+  #[test]
+  fn test_challenge_expiry() {
+    let mut manager = ChallengeManager::new(60);
+    let challenge = manager.issue_at(1_000);
+    assert_eq!(manager.consume(&challenge.value, 1_060), Err(ChallengeError::Expired));
+
+    manager.issue_at(1_000);
+    let live = manager.issue_at(1_050);
+    manager.purge_expired(1_100);
+    assert_eq!(manager.store().len(), 1);
+    assert_eq!(manager.consume(&live.value, 1_101), Ok(()));
+
+    // Issuing drops what has expired without an explicit purge
+    manager.issue_at(1_000);
+    manager.issue_at(2_000);
+    assert_eq!(manager.store().len(), 1);
+  }
+
+  #[test]
+  fn test_store_is_capped() {
+    let mut manager = ChallengeManager::with_store(InMemoryChallengeStore::with_limit(3), 60);
+    let first = manager.issue_at(1_000);
+    for _ in 0..10 {
+      manager.issue_at(1_001);
+    }
+    assert_eq!(manager.store().len(), 3);
+    // The challenge closest to expiry made room first
+    assert_eq!(manager.consume(&first.value, 1_002), Err(ChallengeError::Unknown));
+  }
+
+  #[test]
+  fn test_expiry_index_tracks_store() {
+    let mut store = InMemoryChallengeStore::with_limit(2);
+    let challenge = |value: &str, expires_at| Challenge { value: value.to_string(), expires_at };
+    store.insert(challenge("a", 10));
+    store.insert(challenge("b", 20));
+
+    // Re-inserting refreshes the expiry instead of evicting, and consumed challenges leave the index
+    store.insert(challenge("a", 30));
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.take("b"), Some(challenge("b", 20)));
+    store.insert(challenge("c", 5));
+    store.insert(challenge("d", 40));
+    assert_eq!(store.take("c"), None);
+
+    store.purge_expired(30);
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.by_expiry.len(), 1);
+    assert_eq!(store.take("d"), Some(challenge("d", 40)));
+    assert!(store.is_empty() && store.by_expiry.is_empty());
+  }
+
+  #[test]
+  fn test_presentation_replay_rejected() {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.sign(issuer.secret_key, "did:example:issuer#key-1").unwrap();
+
+    let mut manager = ChallengeManager::new(300);
+    let challenge = manager.issue_at(1_000);
+
+    let mut presentation = Presentation::new("did:example:alice", vec![credential], &challenge.value);
+    presentation.sign(holder.secret_key, "did:example:alice#key-1").unwrap();
+
+    assert_eq!(manager.verify_presentation(&presentation, &holder.public_key, 1_001), Ok(()));
+    assert_eq!(manager.verify_presentation(&presentation, &holder.public_key, 1_002), Err(ChallengeError::Unknown));
+
+    // A presentation bound to another verifier neither verifies nor burns the challenge
+    let challenge = manager.issue_at(1_000);
+    let mut bound = Presentation::new("did:example:alice", vec![], &challenge.value);
+    bound.sign_for_audience(holder.secret_key, "did:example:alice#key-1", "https://other.example").unwrap();
+    assert_eq!(manager.verify_presentation_for(&bound, &holder.public_key, "https://verifier.example", 1_001), Err(ChallengeError::InvalidPresentation));
+    bound.sign_for_audience(holder.secret_key, "did:example:alice#key-1", "https://verifier.example").unwrap();
+    assert_eq!(manager.verify_presentation_for(&bound, &holder.public_key, "https://verifier.example", 1_002), Ok(()));
+  }
+
+  #[test]
+  fn test_forged_presentation_keeps_challenge() {
+    let holder = Loquat::keygen();
+    let attacker = Loquat::keygen();
+
+    let mut manager = ChallengeManager::new(300);
+    let challenge = manager.issue_at(1_000);
+
+    let mut forged = Presentation::new("did:example:alice", vec![], &challenge.value);
+    forged.sign(attacker.secret_key, "did:example:alice#key-1").unwrap();
+    assert_eq!(manager.verify_presentation(&forged, &holder.public_key, 1_001), Err(ChallengeError::InvalidPresentation));
+
+    // The legitimate holder can still use the challenge
+    let mut genuine = Presentation::new("did:example:alice", vec![], &challenge.value);
+    genuine.sign(holder.secret_key, "did:example:alice#key-1").unwrap();
+    assert_eq!(manager.verify_presentation(&genuine, &holder.public_key, 1_002), Ok(()));
+  }
+}
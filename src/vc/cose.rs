@@ -0,0 +1,239 @@
+// COSE_Sign1 (RFC 9052) and CWT (RFC 8392) encodings of Loquat signatures and credentials
+// Intended for constrained verifiers and mdoc-style ecosystems that consume CBOR rather than JSON.
+// The algorithm identifier is provisional and taken from the COSE private-use range.
+
+use crate::crypto::rng::RngHealthError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::utils::time;
+use crate::vc::credential::Credential;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use ciborium::value::{Integer, Value};
+
+// Provisional COSE algorithm identifier for Loquat
+pub const LOQUAT_COSE_ALG: i64 = -65537;
+
+// CBOR tags for COSE_Sign1 and CWT
+pub const COSE_SIGN1_TAG: u64 = 18;
+pub const CWT_TAG: u64 = 61;
+
+// COSE header parameter labels
+const HEADER_ALG: i64 = 1;
+const HEADER_KID: i64 = 4;
+
+// CWT claim keys
+const CLAIM_ISS: i64 = 1;
+const CLAIM_SUB: i64 = 2;
+const CLAIM_EXP: i64 = 4;
+const CLAIM_IAT: i64 = 6;
+const CLAIM_VC: &str = "vc"; // Credential body, unregistered text claim key
+
+fn int(value: i64) -> Value {
+  Value::Integer(Integer::from(value))
+}
+
+fn to_cbor(value: &Value) -> Vec<u8> {
+  let mut out = vec![];
+  ciborium::into_writer(value, &mut out).expect("CBOR serialization failed");
+  out
+}
+
+// Finds an entry in a CBOR map by integer label
+fn map_get(map: &[(Value, Value)], label: i64) -> Option<&Value> {
+  map.iter().find(|(key, _)| key.as_integer().and_then(|k| i64::try_from(k).ok()) == Some(label)).map(|(_, value)| value)
+}
+
+// A COSE_Sign1 message signed with Loquat
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoseSign1 {
+  protected: Vec<u8>, // Serialized protected header, covered by the signature as received
+  pub kid: Option<Vec<u8>>,
+  pub payload: Vec<u8>,
+  pub signature: Vec<u8>, // Serialized Loquat signature
+}
+
+impl CoseSign1 {
+  // Signs a payload; the algorithm and key identifier go in the protected header
+  pub fn sign(payload: &[u8], kid: Option<&[u8]>, external_aad: &[u8], sk: u128) -> Result<Self, RngHealthError> {
+    let mut header = vec![(int(HEADER_ALG), int(LOQUAT_COSE_ALG))];
+    if let Some(kid) = kid {
+      header.push((int(HEADER_KID), Value::Bytes(kid.to_vec())));
+    }
+    let protected = to_cbor(&Value::Map(header));
+    let signature = Loquat::sign(sk, &Self::sig_structure(&protected, external_aad, payload))?;
+
+    Ok(Self {
+      protected,
+      kid: kid.map(<[u8]>::to_vec),
+      payload: payload.to_vec(),
+      signature: signature.to_bytes(),
+    })
+  }
+
+  // Sig_structure = ["Signature1", protected, external_aad, payload]
+  fn sig_structure(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Vec<u8> {
+    to_cbor(&Value::Array(vec![
+      Value::Text("Signature1".to_string()),
+      Value::Bytes(protected.to_vec()),
+      Value::Bytes(external_aad.to_vec()),
+      Value::Bytes(payload.to_vec()),
+    ]))
+  }
+
+  // Tagged COSE_Sign1 encoding with an empty unprotected header
+  pub fn to_bytes(&self) -> Vec<u8> {
+    to_cbor(&Value::Tag(
+      COSE_SIGN1_TAG,
+      Box::new(Value::Array(vec![
+        Value::Bytes(self.protected.clone()),
+        Value::Map(vec![]),
+        Value::Bytes(self.payload.clone()),
+        Value::Bytes(self.signature.clone()),
+      ])),
+    ))
+  }
+
+  // Parses a tagged or untagged COSE_Sign1 message; only the Loquat algorithm is accepted
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let value: Value = ciborium::from_reader(bytes).ok()?;
+    let value = match value {
+      Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+      Value::Tag(_, _) => return None,
+      other => other,
+    };
+    let [protected, _unprotected, payload, signature]: [Value; 4] = value.into_array().ok()?.try_into().ok()?;
+    let (protected, payload, signature) = (protected.into_bytes().ok()?, payload.into_bytes().ok()?, signature.into_bytes().ok()?);
+
+    let header: Value = ciborium::from_reader(protected.as_slice()).ok()?;
+    let header = header.as_map()?;
+    if map_get(header, HEADER_ALG) != Some(&int(LOQUAT_COSE_ALG)) {
+      return None;
+    }
+    let kid = match map_get(header, HEADER_KID) {
+      Some(kid) => Some(kid.as_bytes()?.clone()),
+      None => None,
+    };
+
+    Some(Self {
+      protected,
+      kid,
+      payload,
+      signature,
+    })
+  }
+
+  // Verifies the signature over the Sig_structure
+  pub fn verify(&self, pk: &[u8], external_aad: &[u8]) -> bool {
+    match LoquatSignature::from_bytes(&self.signature) {
+      Some(signature) => Loquat::verify(pk, &Self::sig_structure(&self.protected, external_aad, &self.payload), &signature),
+      None => false,
+    }
+  }
+}
+
+// Encodes a credential as a CWT signed with COSE_Sign1
+// iss, sub, iat and exp mirror the credential; the credential body travels in the "vc" claim
+pub fn encode_cwt_credential(credential: &Credential, issuer_kid: &str, sk: u128) -> Result<Vec<u8>, RngHealthError> {
+  let mut unsigned = credential.clone();
+  unsigned.proof = None;
+
+  let mut claims = vec![(int(CLAIM_ISS), Value::Text(credential.issuer.clone()))];
+  if let Some(sub) = credential.credential_subject.get("id").and_then(|id| id.as_str()) {
+    claims.push((int(CLAIM_SUB), Value::Text(sub.to_string())));
+  }
+  if let Some(iat) = time::parse_rfc3339(&credential.issuance_date) {
+    claims.push((int(CLAIM_IAT), int(iat as i64)));
+  }
+  if let Some(exp) = credential.expiration_date.as_deref().and_then(time::parse_rfc3339) {
+    claims.push((int(CLAIM_EXP), int(exp as i64)));
+  }
+  claims.push((Value::Text(CLAIM_VC.to_string()), Value::serialized(&unsigned).expect("Credential serialization failed")));
+
+  let payload = to_cbor(&Value::Tag(CWT_TAG, Box::new(Value::Map(claims))));
+  Ok(CoseSign1::sign(&payload, Some(issuer_kid.as_bytes()), &[], sk)?.to_bytes())
+}
+
+// Verifies a CWT credential under the issuer key and returns the credential (without a JSON proof)
+pub fn decode_cwt_credential(bytes: &[u8], issuer_pk: &[u8]) -> Option<Credential> {
+  let message = CoseSign1::from_bytes(bytes)?;
+  if !message.verify(issuer_pk, &[]) {
+    return None;
+  }
+
+  let claims = match ciborium::from_reader::<Value, _>(message.payload.as_slice()).ok()? {
+    Value::Tag(CWT_TAG, inner) => *inner,
+    other => other,
+  };
+  let claims = claims.as_map()?;
+  let vc = claims.iter().find(|(key, _)| key.as_text() == Some(CLAIM_VC)).map(|(_, value)| value)?;
+  let credential: Credential = vc.deserialized().ok()?;
+
+  // The signed iss claim must agree with the embedded credential
+  if map_get(claims, CLAIM_ISS)?.as_text()? != credential.issuer {
+    return None;
+  }
+  Some(credential)
+}
+
+// Resolves the issuer key from the kid header, which must be a verification method of the issuer
+// DID whose usages cover the credential, then verifies the CWT and checks expiry
+pub fn verify_cwt_credential<R: DidResolver + ?Sized>(bytes: &[u8], resolver: &R, now: u64) -> Option<Credential> {
+  let kid = String::from_utf8(CoseSign1::from_bytes(bytes)?.kid?).ok()?;
+  let method = resolver.resolve_method(&kid)?;
+  let credential = decode_cwt_credential(bytes, &method.public_key()?)?;
+  if !method.permits(KeyUsage::for_credential(&credential)) || split_did_url(&kid).0 != credential.issuer || credential.is_expired(now) {
+    return None;
+  }
+  Some(credential)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use serde_json::json;
+
+  #[test]
+  fn test_cose_sign1_round_trip() {
+    let keypair = Loquat::keygen();
+    let message = CoseSign1::sign(b"payload", Some(b"key-1"), b"aad", keypair.secret_key).unwrap();
+    let bytes = message.to_bytes();
+    assert_eq!(bytes[0], 0xD2); // Tag 18
+
+    let parsed = CoseSign1::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, message);
+    assert!(parsed.verify(&keypair.public_key, b"aad"));
+    assert!(!parsed.verify(&keypair.public_key, b"other aad"));
+    assert!(!parsed.verify(&Loquat::keygen().public_key, b"aad"));
+
+    let mut tampered = parsed.clone();
+    tampered.payload = b"forged".to_vec();
+    assert!(!tampered.verify(&keypair.public_key, b"aad"));
+  }
+
+  #[test]
+  fn test_rejects_other_algorithms() {
+    // [h'A10126' (alg: ES256), {}, h'', h'']
+    let es256 = [0x84, 0x43, 0xA1, 0x01, 0x26, 0xA0, 0x40, 0x40];
+    assert!(CoseSign1::from_bytes(&es256).is_none());
+    assert!(CoseSign1::from_bytes(&[0x80]).is_none());
+  }
+
+  #[test]
+  fn test_cwt_credential() {
+    let issuer = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let kid = document.add_key("key-1", &issuer.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "licence": "B"}));
+    credential.expiration_date = Some("2100-01-01T00:00:00Z".to_string());
+    let cwt = encode_cwt_credential(&credential, &kid, issuer.secret_key).unwrap();
+
+    assert_eq!(decode_cwt_credential(&cwt, &issuer.public_key), Some(credential.clone()));
+    assert_eq!(verify_cwt_credential(&cwt, &resolver, 1_000), Some(credential));
+    assert!(decode_cwt_credential(&cwt, &Loquat::keygen().public_key).is_none());
+    assert!(verify_cwt_credential(&cwt, &resolver, 5_000_000_000).is_none());
+  }
+}
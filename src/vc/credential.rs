@@ -0,0 +1,630 @@
+// Verifiable credential data model
+// Credentials are signed over their canonical JSON form (without the proof section)
+// through the same SignableMessage path used for raw Loquat signatures.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::rng::RngHealthError;
+use crate::signature::cost::VerificationCost;
+use crate::signature::error::VerifyError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::timestamp::{MaxAge, TimestampedSignature};
+use crate::utils::encoding::{Encoding, Multibase, MULTIHASH_SHA3_256};
+use crate::utils::time;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::multi_issuer::IssuerPolicy;
+use crate::vc::policy::{proof_metadata, PolicyDecision, VerificationPolicy};
+use crate::vc::status::{CredentialStatus, StatusListSource};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// Default JSON-LD context of W3C verifiable credentials
+pub const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+// Proof type identifier for Loquat signatures
+pub const LOQUAT_PROOF_TYPE: &str = "LoquatSignature2024";
+
+// Proof type identifier for Loquat signatures committing to their signing time
+pub const LOQUAT_TIMESTAMPED_PROOF_TYPE: &str = "LoquatTimestampedSignature2024";
+
+// URN prefix of content-addressed credential identifiers
+pub const CONTENT_ID_PREFIX: &str = "urn:multihash:";
+
+// Proof attached to a credential or presentation
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proof {
+  #[serde(rename = "type")]
+  pub proof_type: String,
+  pub verification_method: String, // Identifier of the signing key
+  pub proof_value: String, // Multibase (base58btc) encoding of the serialized signature
+}
+
+impl Proof {
+  // Encodes signature bytes as a proof value
+  pub fn encode_value(bytes: &[u8]) -> String {
+    Encoding::to_multibase(Multibase::Base58Btc, bytes)
+  }
+
+  // Decodes the proof value; hex values from before multibase proofs are still accepted.
+  // Hex has even length and no 'z' or 'u', so it never parses as multibase
+  pub fn decode_value(&self) -> Option<Vec<u8>> {
+    Encoding::from_multibase(&self.proof_value).or_else(|| Encoding::from_hex(&self.proof_value))
+  }
+
+  // Signs a JSON payload and wraps the signature as a proof
+  pub fn sign(sk: u128, verification_method: &str, payload: &Value) -> Result<Self, RngHealthError> {
+    let signature = Loquat::sign(sk, payload)?;
+    Ok(Self {
+      proof_type: LOQUAT_PROOF_TYPE.to_string(),
+      verification_method: verification_method.to_string(),
+      proof_value: Self::encode_value(&signature.to_bytes()),
+    })
+  }
+
+  // Decodes the Loquat signature carried in the proof
+  pub fn signature(&self) -> Option<LoquatSignature> {
+    if self.proof_type != LOQUAT_PROOF_TYPE {
+      return None;
+    }
+    LoquatSignature::from_bytes(&self.decode_value()?)
+  }
+
+  // Verifies the proof over a JSON payload
+  pub fn verify(&self, pk: &[u8], payload: &Value) -> bool {
+    self.try_verify(pk, payload).is_ok()
+  }
+
+  // verify, reporting why verification failed
+  pub fn try_verify(&self, pk: &[u8], payload: &Value) -> Result<(), VerifyError> {
+    if self.proof_type != LOQUAT_PROOF_TYPE {
+      return Err(VerifyError::UnsupportedProofType);
+    }
+    Loquat::try_verify(pk, payload, &self.signature().ok_or(VerifyError::BadEncoding)?)
+  }
+
+  // Signs a JSON payload together with the signing time (seconds since the Unix epoch)
+  pub fn sign_timestamped(sk: u128, verification_method: &str, payload: &Value, signed_at: u64) -> Result<Self, RngHealthError> {
+    let signature = TimestampedSignature::sign(sk, payload, signed_at)?;
+    Ok(Self {
+      proof_type: LOQUAT_TIMESTAMPED_PROOF_TYPE.to_string(),
+      verification_method: verification_method.to_string(),
+      proof_value: Self::encode_value(&signature.to_bytes()),
+    })
+  }
+
+  // Decodes the timestamped signature carried in the proof
+  pub fn timestamped_signature(&self) -> Option<TimestampedSignature> {
+    if self.proof_type != LOQUAT_TIMESTAMPED_PROOF_TYPE {
+      return None;
+    }
+    TimestampedSignature::from_bytes(&self.decode_value()?)
+  }
+
+  // Verifies a timestamped proof and that it was signed within max_age of now
+  pub fn verify_within(&self, pk: &[u8], payload: &Value, now: u64, max_age: &MaxAge) -> bool {
+    match self.timestamped_signature() {
+      Some(signature) => signature.verify(pk, payload, now, max_age),
+      None => false,
+    }
+  }
+
+  // Estimated cost of verifying the proof over a JSON payload; None for proof types without
+  // a cost model and for proofs that do not decode
+  pub fn verification_cost(&self, payload: &Value) -> Option<VerificationCost> {
+    match self.proof_type.as_str() {
+      LOQUAT_PROOF_TYPE => Some(self.signature()?.verification_cost(payload)),
+      LOQUAT_TIMESTAMPED_PROOF_TYPE => Some(self.timestamped_signature()?.verification_cost(payload)),
+      _ => None,
+    }
+  }
+}
+
+// Conditions the issuer places on use of the credential, e.g. an IssuerPolicy naming
+// prohibited actions; the members beyond id and type depend on the terms type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermsOfUse {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub id: Option<String>,
+  #[serde(rename = "type")]
+  pub terms_type: String,
+  #[serde(flatten)]
+  pub properties: Map<String, Value>,
+}
+
+impl TermsOfUse {
+  pub fn new(terms_type: &str) -> Self {
+    Self {
+      id: None,
+      terms_type: terms_type.to_string(),
+      properties: Map::new(),
+    }
+  }
+
+  pub fn property(mut self, name: &str, value: Value) -> Self {
+    self.properties.insert(name.to_string(), value);
+    self
+  }
+}
+
+// How the issuer established the claims, e.g. a document check performed in person
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Evidence {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub id: Option<String>,
+  #[serde(rename = "type")]
+  pub types: Vec<String>,
+  #[serde(flatten)]
+  pub properties: Map<String, Value>,
+}
+
+impl Evidence {
+  pub fn new(types: &[&str]) -> Self {
+    Self {
+      id: None,
+      types: types.iter().map(|t| t.to_string()).collect(),
+      properties: Map::new(),
+    }
+  }
+
+  pub fn property(mut self, name: &str, value: Value) -> Self {
+    self.properties.insert(name.to_string(), value);
+    self
+  }
+}
+
+// A verifiable credential
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credential {
+  #[serde(rename = "@context")]
+  pub context: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub id: Option<String>,
+  #[serde(rename = "type")]
+  pub types: Vec<String>,
+  pub issuer: String,
+  pub issuance_date: String, // RFC 3339 timestamp
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub expiration_date: Option<String>, // RFC 3339 timestamp
+  pub credential_subject: Value,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub credential_status: Option<CredentialStatus>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub issuer_policy: Option<IssuerPolicy>, // Co-signature requirements of multi-issuer credentials
+  // Both are signed like every other member; left out of the JSON when empty, so credentials
+  // without them keep their signing payload
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub terms_of_use: Vec<TermsOfUse>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub evidence: Vec<Evidence>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Proof>,
+}
+
+impl Credential {
+  // Creates an unsigned credential issued now
+  pub fn new(issuer: &str, credential_subject: Value) -> Self {
+    Self {
+      context: vec![CREDENTIALS_CONTEXT.to_string()],
+      id: None,
+      types: vec!["VerifiableCredential".to_string()],
+      issuer: issuer.to_string(),
+      issuance_date: time::format_rfc3339(time::now_unix()),
+      expiration_date: None,
+      credential_subject,
+      credential_status: None,
+      issuer_policy: None,
+      terms_of_use: vec![],
+      evidence: vec![],
+      proof: None,
+    }
+  }
+
+  // JSON payload covered by the signature: the credential without its proof
+  pub fn signing_payload(&self) -> Value {
+    let mut unsigned = self.clone();
+    unsigned.proof = None;
+    serde_json::to_value(&unsigned).expect("Credential serialization failed")
+  }
+
+  // Content-addressed identifier for credentials the issuer gives no id: urn:multihash: then
+  // the base58btc multibase of the SHA3-256 multihash of the JCS form of the credential
+  // without its id and proof. Equal content yields equal identifiers, whoever computes them
+  pub fn derive_id(&self) -> String {
+    let mut content = self.clone();
+    content.id = None;
+    content.proof = None;
+    let value = serde_json::to_value(&content).expect("Credential serialization failed");
+    let digest = Hash::new(HashFunction::Sha3_256).compute(Encoding::to_jcs(&value).as_bytes());
+    format!("{}{}", CONTENT_ID_PREFIX, Encoding::to_multibase(Multibase::Base58Btc, &Encoding::to_multihash(MULTIHASH_SHA3_256, &digest)))
+  }
+
+  // Whether the id is the one derive_id computes, i.e. it matches the content
+  pub fn has_derived_id(&self) -> bool {
+    self.id.as_deref() == Some(self.derive_id().as_str())
+  }
+
+  // Signs the credential with the issuer's secret key, replacing any existing proof; a
+  // refused signature leaves the credential as it was
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(issuer = %self.issuer)))]
+  pub fn sign(&mut self, sk: u128, verification_method: &str) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign(sk, verification_method, &self.signing_payload())?);
+    Ok(())
+  }
+
+  // Verifies the issuer's signature over the credential
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(issuer = %self.issuer), ret))]
+  pub fn verify(&self, issuer_pk: &[u8]) -> bool {
+    self.try_verify(issuer_pk).is_ok()
+  }
+
+  // verify, reporting why verification failed
+  pub fn try_verify(&self, issuer_pk: &[u8]) -> Result<(), VerifyError> {
+    self.proof.as_ref().ok_or(VerifyError::MissingProof)?.try_verify(issuer_pk, &self.signing_payload())
+  }
+
+  // Verifies the credential end to end: resolves the issuer key named by the proof's
+  // verification method, then checks the signature, expiry and revocation status
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(issuer = %self.issuer), ret))]
+  pub fn verify_with_resolver<R, S>(&self, resolver: &R, status_lists: &S, now: u64) -> bool
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    self.try_verify_with_resolver(resolver, status_lists, now).is_ok()
+  }
+
+  // verify_with_resolver, reporting the first check that failed
+  pub fn try_verify_with_resolver<R, S>(&self, resolver: &R, status_lists: &S, now: u64) -> Result<(), VerifyError>
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    let proof = self.proof.as_ref().ok_or(VerifyError::MissingProof)?;
+
+    // The signing key must belong to the credential's issuer
+    if split_did_url(&proof.verification_method).0 != self.issuer {
+      return Err(VerifyError::IssuerMismatch);
+    }
+    let method = resolver.resolve_method(&proof.verification_method).ok_or(VerifyError::UnknownKey)?;
+    if !method.permits(KeyUsage::for_credential(self)) {
+      return Err(VerifyError::KeyUsage);
+    }
+    let issuer_pk = method.public_key().ok_or(VerifyError::UnknownKey)?;
+
+    self.try_verify(&issuer_pk)?;
+    if self.is_expired(now) {
+      return Err(VerifyError::Expired);
+    }
+    match &self.credential_status {
+      Some(status) if status_lists.is_revoked(status) => Err(VerifyError::Revoked),
+      _ => Ok(()),
+    }
+  }
+
+  // Verifies the credential cryptographically, then hands it to the policy
+  // A credential failing the cryptographic checks is denied without consulting the policy
+  pub fn verify_with_policy<R, S, P>(&self, resolver: &R, status_lists: &S, policy: &P, now: u64) -> PolicyDecision
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+    P: VerificationPolicy + ?Sized,
+  {
+    if !self.verify_with_resolver(resolver, status_lists, now) {
+      return PolicyDecision::deny("cryptographic", "signature, expiry or status check failed");
+    }
+    let proof = proof_metadata(self, now).expect("Verified credential has a proof");
+    policy.evaluate(self, &proof)
+  }
+
+  // Whether the credential has expired at the given time (seconds since the Unix epoch)
+  // Unparseable expiration dates are treated as expired
+  pub fn is_expired(&self, now: u64) -> bool {
+    match &self.expiration_date {
+      Some(date) => time::parse_rfc3339(date).is_none_or(|expires| now >= expires),
+      None => false,
+    }
+  }
+}
+
+// Verification method used by CredentialBuilder unless one is set
+pub const DEFAULT_KEY_FRAGMENT: &str = "key-1";
+
+// Fluent construction of a credential for the common issuance flow
+// Defaults: issued now, no expiry, no status entry, signed under <issuer>#key-1.
+#[derive(Debug, Clone)]
+pub struct CredentialBuilder {
+  issuer: String,
+  id: Option<String>,
+  derive_id: bool,
+  types: Vec<String>,
+  subject: Value,
+  issued_at: Option<u64>,
+  expires_in: Option<u64>, // Seconds after issuance
+  status: Option<CredentialStatus>,
+  terms_of_use: Vec<TermsOfUse>,
+  evidence: Vec<Evidence>,
+  verification_method: Option<String>,
+}
+
+impl CredentialBuilder {
+  pub fn new(issuer: &str) -> Self {
+    Self {
+      issuer: issuer.to_string(),
+      id: None,
+      derive_id: false,
+      types: vec![],
+      subject: Value::Object(Default::default()),
+      issued_at: None,
+      expires_in: None,
+      status: None,
+      terms_of_use: vec![],
+      evidence: vec![],
+      verification_method: None,
+    }
+  }
+
+  pub fn subject(mut self, subject: Value) -> Self {
+    self.subject = subject;
+    self
+  }
+
+  pub fn id(mut self, id: &str) -> Self {
+    self.id = Some(id.to_string());
+    self
+  }
+
+  // Without an explicit id, gives the built credential its content-addressed id (derive_id)
+  pub fn derive_id(mut self) -> Self {
+    self.derive_id = true;
+    self
+  }
+
+  // Adds a type after VerifiableCredential
+  pub fn credential_type(mut self, credential_type: &str) -> Self {
+    self.types.push(credential_type.to_string());
+    self
+  }
+
+  // Issuance time in seconds since the Unix epoch; defaults to now
+  pub fn issued_at(mut self, issued_at: u64) -> Self {
+    self.issued_at = Some(issued_at);
+    self
+  }
+
+  // Expires the given number of days after issuance
+  pub fn expires_in(mut self, days: u64) -> Self {
+    self.expires_in = Some(days.saturating_mul(86_400));
+    self
+  }
+
+  // Status entry at the given index of the status list published at url
+  pub fn status_list(mut self, url: &str, index: usize) -> Self {
+    self.status = Some(CredentialStatus::new(url, index));
+    self
+  }
+
+  pub fn terms_of_use(mut self, terms: TermsOfUse) -> Self {
+    self.terms_of_use.push(terms);
+    self
+  }
+
+  pub fn evidence(mut self, evidence: Evidence) -> Self {
+    self.evidence.push(evidence);
+    self
+  }
+
+  // Key identifier for the proof; defaults to <issuer>#key-1
+  pub fn verification_method(mut self, verification_method: &str) -> Self {
+    self.verification_method = Some(verification_method.to_string());
+    self
+  }
+
+  // The unsigned credential
+  pub fn build(self) -> Credential {
+    let issued_at = self.issued_at.unwrap_or_else(time::now_unix);
+    let mut credential = Credential::new(&self.issuer, self.subject);
+    credential.id = self.id;
+    credential.types.extend(self.types);
+    credential.issuance_date = time::format_rfc3339(issued_at);
+    credential.expiration_date = self.expires_in.map(|secs| time::format_rfc3339(issued_at.saturating_add(secs)));
+    credential.credential_status = self.status;
+    credential.terms_of_use = self.terms_of_use;
+    credential.evidence = self.evidence;
+    if credential.id.is_none() && self.derive_id {
+      credential.id = Some(credential.derive_id());
+    }
+    credential
+  }
+
+  pub fn build_and_sign(self, sk: u128) -> Result<Credential, RngHealthError> {
+    let verification_method = self.verification_method.clone().unwrap_or_else(|| format!("{}#{}", self.issuer, DEFAULT_KEY_FRAGMENT));
+    let mut credential = self.build();
+    credential.sign(sk, &verification_method)?;
+    Ok(credential)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_issue_and_verify_credential() {
+    let keypair = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "degree": "BSc"}));
+    credential.sign(keypair.secret_key, "did:example:issuer#key-1").unwrap();
+
+    assert!(credential.verify(&keypair.public_key));
+
+    // The credential survives a JSON round trip
+    let encoded = serde_json::to_string(&credential).unwrap();
+    let decoded: Credential = serde_json::from_str(&encoded).unwrap();
+    assert!(decoded.verify(&keypair.public_key));
+
+    // Proof values are base58btc multibase; hex values from older credentials still verify
+    let proof = credential.proof.as_mut().unwrap();
+    assert!(proof.proof_value.starts_with('z'));
+    proof.proof_value = Encoding::to_hex(&proof.decode_value().unwrap());
+    assert!(credential.verify(&keypair.public_key));
+  }
+
+  #[test]
+  fn test_tampered_credential_rejected() {
+    let keypair = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "degree": "BSc"}));
+    credential.sign(keypair.secret_key, "did:example:issuer#key-1").unwrap();
+
+    let mut tampered = credential.clone();
+    tampered.credential_subject = json!({"id": "did:example:alice", "degree": "PhD"});
+    assert!(!tampered.verify(&keypair.public_key));
+
+    let mut unsigned = credential.clone();
+    unsigned.proof = None;
+    assert!(!unsigned.verify(&keypair.public_key));
+  }
+
+  #[test]
+  fn test_verify_with_resolver() {
+    use crate::vc::did::{DidDocument, StaticDidResolver};
+    use crate::vc::status::{InMemoryStatusLists, StatusList};
+
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key("key-1", &keypair.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+
+    let status_url = "https://issuer.example/status/1";
+    let mut status_lists = InMemoryStatusLists::new();
+    status_lists.insert(status_url, StatusList::new(16));
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.credential_status = Some(CredentialStatus::new(status_url, 7));
+    credential.sign(keypair.secret_key, &key_id).unwrap();
+    assert!(credential.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // Revocation is picked up from the status list
+    status_lists.get_mut(status_url).unwrap().set_revoked(7, true);
+    assert!(!credential.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // A key controlled by another DID cannot sign for this issuer
+    let mut impostor = Credential::new("did:example:other", json!({"id": "did:example:alice"}));
+    impostor.sign(keypair.secret_key, &key_id).unwrap();
+    assert!(!impostor.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // Each failure is reported as such
+    assert_eq!(credential.try_verify_with_resolver(&resolver, &status_lists, 0), Err(VerifyError::Revoked));
+    assert_eq!(impostor.try_verify_with_resolver(&resolver, &status_lists, 0), Err(VerifyError::IssuerMismatch));
+    let mut tampered = credential.clone();
+    tampered.credential_subject = json!({"id": "did:example:mallory"});
+    assert!(tampered.try_verify_with_resolver(&resolver, &status_lists, 0).unwrap_err().is_cryptographic_failure());
+    tampered.proof = None;
+    assert_eq!(tampered.try_verify_with_resolver(&resolver, &status_lists, 0), Err(VerifyError::MissingProof));
+  }
+
+  #[test]
+  fn test_expiration() {
+    let mut credential = Credential::new("did:example:issuer", json!({}));
+    assert!(!credential.is_expired(u64::MAX));
+
+    credential.expiration_date = Some("2030-01-01T00:00:00Z".to_string());
+    assert!(!credential.is_expired(time::parse_rfc3339("2029-12-31T23:59:59Z").unwrap()));
+    assert!(credential.is_expired(time::parse_rfc3339("2030-01-01T00:00:00Z").unwrap()));
+
+    credential.expiration_date = Some("garbage".to_string());
+    assert!(credential.is_expired(0));
+  }
+
+  #[test]
+  fn test_credential_builder() {
+    let keypair = Loquat::keygen();
+    let issued_at = time::parse_rfc3339("2030-01-01T00:00:00Z").unwrap();
+    let credential = CredentialBuilder::new("did:example:issuer")
+      .subject(json!({"id": "did:example:alice", "degree": "BSc"}))
+      .credential_type("UniversityDegreeCredential")
+      .issued_at(issued_at)
+      .expires_in(30)
+      .status_list("https://issuer.example/status/1", 7)
+      .build_and_sign(keypair.secret_key).unwrap();
+
+    assert!(credential.verify(&keypair.public_key));
+    assert_eq!(credential.types, vec!["VerifiableCredential", "UniversityDegreeCredential"]);
+    assert_eq!(credential.expiration_date.as_deref(), Some("2030-01-31T00:00:00Z"));
+    assert_eq!(credential.credential_status, Some(CredentialStatus::new("https://issuer.example/status/1", 7)));
+    assert_eq!(credential.proof.unwrap().verification_method, "did:example:issuer#key-1");
+  }
+
+  #[test]
+  fn test_terms_of_use_and_evidence_signed() {
+    let keypair = Loquat::keygen();
+    let plain = CredentialBuilder::new("did:example:ministry").subject(json!({"id": "did:example:alice"})).issued_at(1_700_000_000);
+    let mut credential = plain
+      .clone()
+      .terms_of_use(TermsOfUse::new("IssuerPolicy").property("prohibition", json!([{"action": ["Archival"]}])))
+      .evidence(Evidence::new(&["DocumentVerification"]).property("subjectPresence", json!("Physical")))
+      .build_and_sign(keypair.secret_key).unwrap();
+    assert!(credential.verify(&keypair.public_key));
+
+    let json = serde_json::to_value(&credential).unwrap();
+    assert_eq!(json["termsOfUse"][0], json!({"type": "IssuerPolicy", "prohibition": [{"action": ["Archival"]}]}));
+    assert_eq!(json["evidence"][0], json!({"type": ["DocumentVerification"], "subjectPresence": "Physical"}));
+    assert_eq!(serde_json::from_value::<Credential>(json).unwrap(), credential);
+
+    // Both sections are covered by the signature, and absent sections leave the payload as it was
+    credential.evidence[0].properties.insert("subjectPresence".to_string(), json!("Digital"));
+    assert!(!credential.verify(&keypair.public_key));
+    credential.evidence.clear();
+    assert!(!credential.verify(&keypair.public_key));
+    let payload = plain.build().signing_payload();
+    assert!(payload.get("termsOfUse").is_none() && payload.get("evidence").is_none());
+  }
+
+  #[test]
+  fn test_derived_ids() {
+    let keypair = Loquat::keygen();
+    let builder = CredentialBuilder::new("did:example:issuer").subject(json!({"id": "did:example:alice", "degree": "BSc"})).issued_at(1_700_000_000);
+    let credential = builder.clone().derive_id().build_and_sign(keypair.secret_key).unwrap();
+    let id = credential.id.clone().unwrap();
+    assert!(id.starts_with("urn:multihash:z"));
+    assert!(credential.has_derived_id() && credential.verify(&keypair.public_key));
+
+    // The identifier ignores the id and the proof, and follows every claim
+    let plain = builder.clone().build();
+    assert_eq!(plain.derive_id(), id);
+    let other = builder.subject(json!({"id": "did:example:alice", "degree": "MSc"})).build();
+    assert_ne!(other.derive_id(), id);
+    assert!(!CredentialBuilder::new("did:example:issuer").id("urn:uuid:1").derive_id().build().has_derived_id());
+  }
+
+  #[test]
+  fn test_builder_defaults_match_new() {
+    let built = CredentialBuilder::new("did:example:issuer").subject(json!({"id": "did:example:alice"})).build();
+    let mut expected = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    expected.issuance_date = built.issuance_date.clone();
+    assert_eq!(built, expected);
+    assert!(!built.is_expired(u64::MAX));
+  }
+
+  #[test]
+  fn test_proof_verification_cost() {
+    let keypair = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.sign(keypair.secret_key, "did:example:issuer#key-1").unwrap();
+    let proof = credential.proof.clone().unwrap();
+    let payload = credential.signing_payload();
+    let cost = proof.verification_cost(&payload).unwrap();
+    assert_eq!(cost.hash_invocations, 4);
+
+    // The timestamped transcript hashes a few more bytes for the same work
+    let timestamped = Proof::sign_timestamped(keypair.secret_key, "did:example:issuer#key-1", &payload, 1_000).unwrap();
+    let timestamped_cost = timestamped.verification_cost(&payload).unwrap();
+    assert!(timestamped_cost.hashed_bytes > cost.hashed_bytes);
+    assert_eq!(timestamped_cost.field_multiplications, cost.field_multiplications);
+
+    let unknown = Proof { proof_type: "Ed25519Signature2020".to_string(), ..proof };
+    assert!(unknown.verification_cost(&payload).is_none());
+  }
+}
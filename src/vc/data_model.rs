@@ -0,0 +1,187 @@
+// W3C Verifiable Credentials Data Model 1.1 conformance checks
+// Deserializing a Credential only enforces the JSON shape. These checks add the data model's
+// MUST rules that the shape cannot express: the base context comes first, the base type is
+// present, identifiers are URIs and dates are RFC 3339. They do not verify signatures.
+
+use crate::utils::time;
+use crate::vc::credential::{Credential, Proof, CREDENTIALS_CONTEXT};
+use crate::vc::presentation::Presentation;
+use serde_json::Value;
+
+const CREDENTIAL_TYPE: &str = "VerifiableCredential";
+const PRESENTATION_TYPE: &str = "VerifiablePresentation";
+
+// The first data model rule a credential or presentation breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataModelError {
+  InvalidContext, // The first context is not the credentials base context
+  InvalidType, // The base type is missing
+  InvalidId, // An id that is not a URI
+  InvalidIssuer, // An issuer that is not a URI
+  InvalidIssuanceDate,
+  InvalidExpirationDate,
+  InvalidSubject, // Not an object, or a non-empty array of objects
+  InvalidStatus, // A status entry without an id or type
+  InvalidTermsOfUse, // Terms without a type, or with an id that is not a URI
+  InvalidEvidence, // Evidence without a type, or with an id that is not a URI
+  InvalidProof, // A proof without a type or verification method
+  InvalidHolder, // A presentation holder that is not a URI
+  InvalidCredential(usize), // The embedded credential at this position
+}
+
+impl DataModelError {
+  // Identifier of the violated rule, as reported by conformance test harnesses
+  pub fn code(&self) -> &'static str {
+    match self {
+      DataModelError::InvalidContext => "invalid_context",
+      DataModelError::InvalidType => "invalid_type",
+      DataModelError::InvalidId => "invalid_id",
+      DataModelError::InvalidIssuer => "invalid_issuer",
+      DataModelError::InvalidIssuanceDate => "invalid_issuance_date",
+      DataModelError::InvalidExpirationDate => "invalid_expiration_date",
+      DataModelError::InvalidSubject => "invalid_subject",
+      DataModelError::InvalidStatus => "invalid_status",
+      DataModelError::InvalidTermsOfUse => "invalid_terms_of_use",
+      DataModelError::InvalidEvidence => "invalid_evidence",
+      DataModelError::InvalidProof => "invalid_proof",
+      DataModelError::InvalidHolder => "invalid_holder",
+      DataModelError::InvalidCredential(_) => "invalid_credential",
+    }
+  }
+}
+
+// A URI has a scheme (a letter, then letters, digits, '+', '-' or '.'), a colon and a
+// non-empty remainder without whitespace
+pub fn is_uri(value: &str) -> bool {
+  let Some((scheme, rest)) = value.split_once(':') else {
+    return false;
+  };
+  scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    && !rest.is_empty()
+    && !value.chars().any(char::is_whitespace)
+}
+
+fn check_context(context: &[String]) -> Result<(), DataModelError> {
+  match context.first() {
+    Some(first) if first == CREDENTIALS_CONTEXT => Ok(()),
+    _ => Err(DataModelError::InvalidContext),
+  }
+}
+
+fn check_types(types: &[String], base: &str) -> Result<(), DataModelError> {
+  if types.iter().any(|t| t == base) {
+    Ok(())
+  } else {
+    Err(DataModelError::InvalidType)
+  }
+}
+
+fn check_subject(subject: &Value) -> Result<(), DataModelError> {
+  let subjects = match subject {
+    Value::Object(_) => std::slice::from_ref(subject),
+    Value::Array(subjects) if !subjects.is_empty() => subjects.as_slice(),
+    _ => return Err(DataModelError::InvalidSubject),
+  };
+  for subject in subjects {
+    let subject = subject.as_object().ok_or(DataModelError::InvalidSubject)?;
+    if subject.get("id").is_some_and(|id| !id.as_str().is_some_and(is_uri)) {
+      return Err(DataModelError::InvalidId);
+    }
+  }
+  Ok(())
+}
+
+fn check_proof(proof: Option<&Proof>) -> Result<(), DataModelError> {
+  match proof {
+    Some(proof) if proof.proof_type.is_empty() || proof.verification_method.is_empty() => Err(DataModelError::InvalidProof),
+    _ => Ok(()),
+  }
+}
+
+pub fn check_credential(credential: &Credential) -> Result<(), DataModelError> {
+  check_context(&credential.context)?;
+  check_types(&credential.types, CREDENTIAL_TYPE)?;
+  if credential.id.as_deref().is_some_and(|id| !is_uri(id)) {
+    return Err(DataModelError::InvalidId);
+  }
+  if !is_uri(&credential.issuer) {
+    return Err(DataModelError::InvalidIssuer);
+  }
+  if time::parse_rfc3339(&credential.issuance_date).is_none() {
+    return Err(DataModelError::InvalidIssuanceDate);
+  }
+  if credential.expiration_date.as_deref().is_some_and(|date| time::parse_rfc3339(date).is_none()) {
+    return Err(DataModelError::InvalidExpirationDate);
+  }
+  check_subject(&credential.credential_subject)?;
+  if let Some(status) = &credential.credential_status {
+    if !is_uri(&status.id) || status.status_type.is_empty() {
+      return Err(DataModelError::InvalidStatus);
+    }
+  }
+  let bad_id = |id: &Option<String>| id.as_deref().is_some_and(|id| !is_uri(id));
+  if credential.terms_of_use.iter().any(|terms| terms.terms_type.is_empty() || bad_id(&terms.id)) {
+    return Err(DataModelError::InvalidTermsOfUse);
+  }
+  if credential.evidence.iter().any(|evidence| evidence.types.is_empty() || bad_id(&evidence.id)) {
+    return Err(DataModelError::InvalidEvidence);
+  }
+  check_proof(credential.proof.as_ref())
+}
+
+pub fn check_presentation(presentation: &Presentation) -> Result<(), DataModelError> {
+  check_context(&presentation.context)?;
+  check_types(&presentation.types, PRESENTATION_TYPE)?;
+  if !is_uri(&presentation.holder) {
+    return Err(DataModelError::InvalidHolder);
+  }
+  for (i, credential) in presentation.verifiable_credential.iter().enumerate() {
+    check_credential(credential).map_err(|_| DataModelError::InvalidCredential(i))?;
+  }
+  check_proof(presentation.proof.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::vc::credential::{Evidence, TermsOfUse};
+  use serde_json::json;
+
+  #[test]
+  fn test_credential_rules() {
+    let credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    assert_eq!(check_credential(&credential), Ok(()));
+
+    let mut reordered = credential.clone();
+    reordered.context.insert(0, "https://example.org/context".to_string());
+    assert_eq!(check_credential(&reordered), Err(DataModelError::InvalidContext));
+    let mut untyped = credential.clone();
+    untyped.types = vec!["ExampleCredential".to_string()];
+    assert_eq!(check_credential(&untyped), Err(DataModelError::InvalidType));
+    let mut dated = credential.clone();
+    dated.issuance_date = "01/01/2020".to_string();
+    assert_eq!(check_credential(&dated), Err(DataModelError::InvalidIssuanceDate));
+    let mut subjects = credential.clone();
+    subjects.credential_subject = json!([]);
+    assert_eq!(check_credential(&subjects), Err(DataModelError::InvalidSubject));
+    subjects.credential_subject = json!([{"id": "did:example:alice"}, {"id": "not a uri"}]);
+    assert_eq!(check_credential(&subjects), Err(DataModelError::InvalidId));
+    let mut terms = credential.clone();
+    terms.terms_of_use = vec![TermsOfUse::new("")];
+    assert_eq!(check_credential(&terms), Err(DataModelError::InvalidTermsOfUse));
+    let mut evidence = credential.clone();
+    evidence.evidence = vec![Evidence { id: Some("not a uri".to_string()), ..Evidence::new(&["DocumentVerification"]) }];
+    assert_eq!(check_credential(&evidence), Err(DataModelError::InvalidEvidence));
+  }
+
+  #[test]
+  fn test_uris() {
+    for uri in ["did:example:alice", "https://example.edu/credentials/1872", "urn:uuid:3978344f-8596-4c3a-a978-8fcaba3903c5"] {
+      assert!(is_uri(uri), "{}", uri);
+    }
+    for value in ["alice", ":alice", "1http://x", "did:", "did:example:a b"] {
+      assert!(!is_uri(value), "{}", value);
+    }
+  }
+}
@@ -0,0 +1,235 @@
+// Delegated issuance
+// A root issuer hands issuance authority to an intermediate issuer by signing a
+// delegation credential whose subject is the delegate. The grant limits which
+// credential types the delegate may issue and how many further delegation hops may
+// follow it. Verification walks the chain from a trusted root down to the issued
+// credential; every hop may only narrow the scope and depth of its parent.
+
+use crate::crypto::rng::RngHealthError;
+use crate::vc::credential::Credential;
+use crate::vc::did::DidResolver;
+use crate::vc::status::StatusListSource;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Credential type marking a delegation credential
+pub const DELEGATION_CREDENTIAL_TYPE: &str = "DelegationCredential";
+
+// Base type carried by every credential, never subject to scope restrictions
+const BASE_CREDENTIAL_TYPE: &str = "VerifiableCredential";
+
+// Authority granted to a delegate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegationGrant {
+  pub scope: Vec<String>, // Credential types the delegate may issue
+  pub max_depth: usize, // Further delegation hops allowed below the delegate
+}
+
+impl DelegationGrant {
+  pub fn new(scope: &[&str], max_depth: usize) -> Self {
+    Self {
+      scope: scope.iter().map(|t| t.to_string()).collect(),
+      max_depth,
+    }
+  }
+
+  // Whether the grant covers every type of the credential
+  fn covers(&self, types: &[String]) -> bool {
+    types
+      .iter()
+      .filter(|t| t.as_str() != BASE_CREDENTIAL_TYPE && t.as_str() != DELEGATION_CREDENTIAL_TYPE)
+      .all(|t| self.scope.contains(t))
+  }
+}
+
+// Creates and signs a delegation credential from `issuer` to `delegate`
+pub fn issue_delegation(
+  issuer: &str,
+  delegate: &str,
+  grant: &DelegationGrant,
+  sk: u128,
+  verification_method: &str,
+) -> Result<Credential, RngHealthError> {
+  let mut credential = Credential::new(issuer, json!({"id": delegate, "delegation": grant}));
+  credential.types.push(DELEGATION_CREDENTIAL_TYPE.to_string());
+  credential.sign(sk, verification_method)?;
+  Ok(credential)
+}
+
+// Delegate DID and grant carried by a delegation credential
+pub fn delegation_grant(credential: &Credential) -> Option<(&str, DelegationGrant)> {
+  if !credential.types.iter().any(|t| t == DELEGATION_CREDENTIAL_TYPE) {
+    return None;
+  }
+  let delegate = credential.credential_subject.get("id")?.as_str()?;
+  let grant = serde_json::from_value(credential.credential_subject.get("delegation")?.clone()).ok()?;
+  Some((delegate, grant))
+}
+
+// A credential issued under delegated authority, with its chain ordered from the root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DelegatedCredential {
+  #[serde(flatten)]
+  pub credential: Credential,
+  pub delegation_chain: Vec<Credential>,
+}
+
+impl DelegatedCredential {
+  pub fn new(credential: Credential, delegation_chain: Vec<Credential>) -> Self {
+    Self {
+      credential,
+      delegation_chain,
+    }
+  }
+
+  // Verifies the chain from one of the trusted roots down to the credential
+  // Each link is checked like a standalone credential (signature, expiry, revocation),
+  // names the next issuer as its delegate, and may only narrow its parent's grant
+  pub fn verify_with_resolver<R, S>(&self, trusted_roots: &[&str], resolver: &R, status_lists: &S, now: u64) -> bool
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    let root = match self.delegation_chain.first() {
+      Some(link) => &link.issuer,
+      None => &self.credential.issuer,
+    };
+    if !trusted_roots.contains(&root.as_str()) {
+      return false;
+    }
+
+    let mut parent: Option<DelegationGrant> = None;
+    for (position, link) in self.delegation_chain.iter().enumerate() {
+      let (delegate, grant) = match delegation_grant(link) {
+        Some(delegation) => delegation,
+        None => return false,
+      };
+      let next_issuer = match self.delegation_chain.get(position + 1) {
+        Some(next) => &next.issuer,
+        None => &self.credential.issuer,
+      };
+      let remaining_hops = self.delegation_chain.len() - position - 1;
+      if delegate != next_issuer || remaining_hops > grant.max_depth {
+        return false;
+      }
+      if let Some(parent) = &parent {
+        let narrower_scope = grant.scope.iter().all(|t| parent.scope.contains(t));
+        if !narrower_scope || grant.max_depth >= parent.max_depth {
+          return false;
+        }
+      }
+      if !link.verify_with_resolver(resolver, status_lists, now) {
+        return false;
+      }
+      parent = Some(grant);
+    }
+
+    if let Some(grant) = &parent {
+      if !grant.covers(&self.credential.types) {
+        return false;
+      }
+    }
+    self.credential.verify_with_resolver(resolver, status_lists, now)
+  }
+
+  // Subject of the delegated credential
+  pub fn subject(&self) -> &Value {
+    &self.credential.credential_subject
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::{Loquat, LoquatKeyPair};
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+
+  const MINISTRY: &str = "did:example:ministry";
+  const UNIVERSITY: &str = "did:example:university";
+  const FACULTY: &str = "did:example:faculty";
+
+  fn setup() -> (StaticDidResolver, Vec<(String, LoquatKeyPair)>) {
+    let mut resolver = StaticDidResolver::new();
+    let mut keys = vec![];
+    for did in [MINISTRY, UNIVERSITY, FACULTY] {
+      let keypair = Loquat::keygen();
+      let mut document = DidDocument::new(did);
+      let key_id = document.add_key("key-1", &keypair.public_key);
+      resolver.insert(document);
+      keys.push((key_id, keypair));
+    }
+    (resolver, keys)
+  }
+
+  fn delegate(issuer: &str, delegate: &str, scope: &[&str], max_depth: usize, key: &(String, LoquatKeyPair)) -> Credential {
+    issue_delegation(issuer, delegate, &DelegationGrant::new(scope, max_depth), key.1.secret_key, &key.0).unwrap()
+  }
+
+  fn degree(issuer: &str, kind: &str, key: &(String, LoquatKeyPair)) -> Credential {
+    let mut credential = Credential::new(issuer, json!({"id": "did:example:alice"}));
+    credential.types.push(kind.to_string());
+    credential.sign(key.1.secret_key, &key.0).unwrap();
+    credential
+  }
+
+  #[test]
+  fn test_two_hop_chain() {
+    let (resolver, keys) = setup();
+    let status_lists = InMemoryStatusLists::new();
+    let root = delegate(MINISTRY, UNIVERSITY, &["Degree", "Transcript"], 1, &keys[0]);
+    let intermediate = delegate(UNIVERSITY, FACULTY, &["Degree"], 0, &keys[1]);
+
+    let delegated = DelegatedCredential::new(degree(FACULTY, "Degree", &keys[2]), vec![root.clone(), intermediate.clone()]);
+    assert!(delegated.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+    assert!(!delegated.verify_with_resolver(&[UNIVERSITY], &resolver, &status_lists, 0));
+
+    let decoded: DelegatedCredential = serde_json::from_str(&serde_json::to_string(&delegated).unwrap()).unwrap();
+    assert!(decoded.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+
+    // A type outside the faculty's grant is rejected even though the root allowed it
+    let out_of_scope = DelegatedCredential::new(degree(FACULTY, "Transcript", &keys[2]), vec![root.clone(), intermediate]);
+    assert!(!out_of_scope.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+
+    // Skipping the intermediate link breaks the chain
+    let broken = DelegatedCredential::new(degree(FACULTY, "Degree", &keys[2]), vec![root]);
+    assert!(!broken.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+  }
+
+  #[test]
+  fn test_depth_and_scope_restrictions() {
+    let (resolver, keys) = setup();
+    let status_lists = InMemoryStatusLists::new();
+
+    // The root allows no further hops below the university
+    let root = delegate(MINISTRY, UNIVERSITY, &["Degree"], 0, &keys[0]);
+    let intermediate = delegate(UNIVERSITY, FACULTY, &["Degree"], 0, &keys[1]);
+    let too_deep = DelegatedCredential::new(degree(FACULTY, "Degree", &keys[2]), vec![root.clone(), intermediate]);
+    assert!(!too_deep.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+    let direct = DelegatedCredential::new(degree(UNIVERSITY, "Degree", &keys[1]), vec![root]);
+    assert!(direct.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+
+    // An intermediate cannot widen the scope it was granted
+    let root = delegate(MINISTRY, UNIVERSITY, &["Degree"], 1, &keys[0]);
+    let widened = delegate(UNIVERSITY, FACULTY, &["Degree", "Diploma"], 0, &keys[1]);
+    let delegated = DelegatedCredential::new(degree(FACULTY, "Degree", &keys[2]), vec![root, widened]);
+    assert!(!delegated.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+  }
+
+  #[test]
+  fn test_plain_credentials_need_trusted_issuer() {
+    let (resolver, keys) = setup();
+    let status_lists = InMemoryStatusLists::new();
+    let credential = DelegatedCredential::new(degree(MINISTRY, "Degree", &keys[0]), vec![]);
+    assert!(credential.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+    assert!(!credential.verify_with_resolver(&[UNIVERSITY], &resolver, &status_lists, 0));
+
+    // An ordinary credential cannot stand in for a delegation
+    let not_a_delegation = degree(MINISTRY, "Degree", &keys[0]);
+    assert!(delegation_grant(&not_a_delegation).is_none());
+    let forged = DelegatedCredential::new(degree(UNIVERSITY, "Degree", &keys[1]), vec![not_a_delegation]);
+    assert!(!forged.verify_with_resolver(&[MINISTRY], &resolver, &status_lists, 0));
+  }
+}
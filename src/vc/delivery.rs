@@ -0,0 +1,92 @@
+// Confidential credential delivery
+// An issued credential is sealed to the wallet's KEM encapsulation key with the hybrid
+// encryption of crypto::kem, so only the wallet can read it in transit or at rest on an
+// intermediary. The envelope is JSON with base64url fields; its algorithm label is bound
+// into the AEAD as associated data, so it cannot be swapped without failing decryption.
+
+use crate::crypto::kem::{open, seal, Kem, MlKem768, SealedBox, NONCE_BYTES};
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::Credential;
+use serde::{Deserialize, Serialize};
+
+// Suffix of the envelope algorithm label after the KEM name
+const AEAD_ALGORITHM: &str = "ChaCha20-Poly1305";
+
+// Credential sealed to a recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedCredential {
+  pub alg: String, // "<KEM>+ChaCha20-Poly1305"
+  pub encapsulated_key: String, // Base64url
+  pub nonce: String, // Base64url
+  pub ciphertext: String, // Base64url
+}
+
+fn algorithm<K: Kem>() -> String {
+  format!("{}+{}", K::ALGORITHM, AEAD_ALGORITHM)
+}
+
+// Encrypts a credential to an ML-KEM-768 encapsulation key
+pub fn encrypt_credential_for(recipient_pk: &[u8], credential: &Credential) -> Option<EncryptedCredential> {
+  encrypt_credential_with::<MlKem768>(recipient_pk, credential)
+}
+
+// Decrypts a credential sealed with encrypt_credential_for
+pub fn decrypt_credential(recipient_sk: &[u8], encrypted: &EncryptedCredential) -> Option<Credential> {
+  decrypt_credential_with::<MlKem768>(recipient_sk, encrypted)
+}
+
+// Encrypts a credential to a recipient key of the given KEM
+pub fn encrypt_credential_with<K: Kem>(recipient_pk: &[u8], credential: &Credential) -> Option<EncryptedCredential> {
+  let alg = algorithm::<K>();
+  let plaintext = serde_json::to_vec(credential).expect("Credential serialization failed");
+  let sealed = seal::<K>(recipient_pk, &plaintext, alg.as_bytes())?;
+  Some(EncryptedCredential {
+    alg,
+    encapsulated_key: Encoding::to_base64url(&sealed.encapsulated_key),
+    nonce: Encoding::to_base64url(&sealed.nonce),
+    ciphertext: Encoding::to_base64url(&sealed.ciphertext),
+  })
+}
+
+// Decrypts a credential; None if the envelope uses another algorithm, is malformed or fails
+// authentication
+pub fn decrypt_credential_with<K: Kem>(recipient_sk: &[u8], encrypted: &EncryptedCredential) -> Option<Credential> {
+  if encrypted.alg != algorithm::<K>() {
+    return None;
+  }
+  let nonce: [u8; NONCE_BYTES] = Encoding::from_base64url(&encrypted.nonce)?.try_into().ok()?;
+  let sealed = SealedBox {
+    encapsulated_key: Encoding::from_base64url(&encrypted.encapsulated_key)?,
+    nonce,
+    ciphertext: Encoding::from_base64url(&encrypted.ciphertext)?,
+  };
+  let plaintext = open::<K>(recipient_sk, &sealed, encrypted.alg.as_bytes())?;
+  serde_json::from_slice(&plaintext).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use serde_json::json;
+
+  #[test]
+  fn test_credential_delivery() {
+    let issuer = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "degree": "BSc"}));
+    credential.sign(issuer.secret_key, "did:example:issuer#key-1").unwrap();
+
+    let (wallet_sk, wallet_pk) = MlKem768::generate();
+    let encrypted = encrypt_credential_for(&wallet_pk, &credential).unwrap();
+    assert_eq!(encrypted.alg, "ML-KEM-768+ChaCha20-Poly1305");
+    assert!(!serde_json::to_string(&encrypted).unwrap().contains("did:example:alice"));
+    assert_eq!(decrypt_credential(&wallet_sk, &encrypted), Some(credential));
+
+    // Another wallet cannot decrypt, and relabeled envelopes are rejected
+    assert!(decrypt_credential(&MlKem768::generate().0, &encrypted).is_none());
+    let mut relabeled = encrypted;
+    relabeled.alg = "ML-KEM-768+AES-GCM".to_string();
+    assert!(decrypt_credential(&wallet_sk, &relabeled).is_none());
+  }
+}
@@ -0,0 +1,211 @@
+// DID documents and resolution
+// A resolver maps a DID (or a DID URL naming one of its verification methods)
+// to the Loquat public key used to check credentials and presentations.
+
+use crate::utils::encoding::{Encoding, Multibase};
+use crate::vc::key_usage::KeyUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Verification method type for Loquat public keys
+pub const LOQUAT_VERIFICATION_KEY_TYPE: &str = "LoquatVerificationKey2024";
+
+// Multicodec code prefixing Loquat public keys in publicKeyMultibase, taken from the
+// private-use range (0x300000-0x3fffff) until a code is registered
+pub const LOQUAT_PUBLIC_KEY_MULTICODEC: u64 = 0x30_4c51;
+
+// A public key listed in a DID document
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+  pub id: String, // DID URL, e.g. did:example:issuer#key-1
+  #[serde(rename = "type")]
+  pub method_type: String,
+  pub controller: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub public_key_multibase: Option<String>, // base58btc of multicodec | public key
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub public_key_hex: Option<String>, // Documents written before multibase keys
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub key_usage: Option<KeyUsage>, // None permits every usage
+}
+
+impl VerificationMethod {
+  // Creates a Loquat verification method for a raw public key
+  pub fn new(id: &str, controller: &str, public_key: &[u8]) -> Self {
+    Self {
+      id: id.to_string(),
+      method_type: LOQUAT_VERIFICATION_KEY_TYPE.to_string(),
+      controller: controller.to_string(),
+      public_key_multibase: Some(Encoding::to_multibase(
+        Multibase::Base58Btc,
+        &Encoding::to_multicodec(LOQUAT_PUBLIC_KEY_MULTICODEC, public_key),
+      )),
+      public_key_hex: None,
+      key_usage: None,
+    }
+  }
+
+  pub fn permits(&self, usage: KeyUsage) -> bool {
+    self.key_usage.is_none_or(|granted| granted.contains(usage))
+  }
+
+  // Decodes the public key, if this is a Loquat verification method
+  pub fn public_key(&self) -> Option<Vec<u8>> {
+    if self.method_type != LOQUAT_VERIFICATION_KEY_TYPE {
+      return None;
+    }
+    match (&self.public_key_multibase, &self.public_key_hex) {
+      (Some(multibase), None) => match Encoding::from_multicodec(&Encoding::from_multibase(multibase)?)? {
+        (LOQUAT_PUBLIC_KEY_MULTICODEC, key) => Some(key.to_vec()),
+        _ => None,
+      },
+      (None, Some(hex)) => Encoding::from_hex(hex),
+      // Two encodings of the key could disagree
+      _ => None,
+    }
+  }
+}
+
+// A minimal DID document
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+  pub id: String,
+  pub verification_method: Vec<VerificationMethod>,
+}
+
+impl DidDocument {
+  pub fn new(id: &str) -> Self {
+    Self {
+      id: id.to_string(),
+      verification_method: vec![],
+    }
+  }
+
+  // Adds a Loquat key under the given fragment (e.g. "key-1") and returns its DID URL
+  pub fn add_key(&mut self, fragment: &str, public_key: &[u8]) -> String {
+    let id = format!("{}#{}", self.id, fragment);
+    self.verification_method.push(VerificationMethod::new(&id, &self.id, public_key));
+    id
+  }
+
+  // add_key for a key restricted to the given usages
+  pub fn add_key_with_usage(&mut self, fragment: &str, public_key: &[u8], usage: KeyUsage) -> String {
+    let id = self.add_key(fragment, public_key);
+    self.verification_method.last_mut().expect("Key just added").key_usage = Some(usage);
+    id
+  }
+
+  // Looks up a verification method by its full DID URL
+  pub fn verification_method(&self, id: &str) -> Option<&VerificationMethod> {
+    self.verification_method.iter().find(|vm| vm.id == id)
+  }
+}
+
+// Splits a DID URL into its DID and optional fragment
+pub fn split_did_url(did_url: &str) -> (&str, Option<&str>) {
+  match did_url.split_once('#') {
+    Some((did, fragment)) => (did, Some(fragment)),
+    None => (did_url, None),
+  }
+}
+
+// Resolves DIDs to DID documents
+pub trait DidResolver {
+  fn resolve(&self, did: &str) -> Option<DidDocument>;
+
+  // Resolves a verification method DID URL to its entry in the controller's document
+  // A bare DID resolves to the first verification method of its document
+  fn resolve_method(&self, did_url: &str) -> Option<VerificationMethod> {
+    let (did, fragment) = split_did_url(did_url);
+    let document = self.resolve(did)?;
+    match fragment {
+      Some(_) => document.verification_method(did_url).cloned(),
+      None => document.verification_method.first().cloned(),
+    }
+  }
+
+  // Resolves a verification method DID URL to its Loquat public key
+  fn resolve_key(&self, did_url: &str) -> Option<Vec<u8>> {
+    self.resolve_method(did_url)?.public_key()
+  }
+
+  // resolve_key for keys whose usages include the given one
+  fn resolve_key_for(&self, did_url: &str, usage: KeyUsage) -> Option<Vec<u8>> {
+    self.resolve_method(did_url).filter(|method| method.permits(usage))?.public_key()
+  }
+}
+
+// Resolver over a fixed set of documents, for tests and pinned deployments
+#[derive(Debug, Clone, Default)]
+pub struct StaticDidResolver {
+  documents: HashMap<String, DidDocument>,
+}
+
+impl StaticDidResolver {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, document: DidDocument) {
+    self.documents.insert(document.id.clone(), document);
+  }
+}
+
+impl DidResolver for StaticDidResolver {
+  fn resolve(&self, did: &str) -> Option<DidDocument> {
+    self.documents.get(did).cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+
+  #[test]
+  fn test_resolve_key() {
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key("key-1", &keypair.public_key);
+
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+
+    assert_eq!(resolver.resolve_key(&key_id), Some(keypair.public_key.clone()));
+    assert_eq!(resolver.resolve_key("did:example:issuer"), Some(keypair.public_key));
+    assert_eq!(resolver.resolve_key("did:example:issuer#key-2"), None);
+    assert_eq!(resolver.resolve_key("did:example:unknown#key-1"), None);
+  }
+
+  #[test]
+  fn test_key_encodings() {
+    let public_key = Loquat::keygen().public_key;
+    let method = VerificationMethod::new("did:example:issuer#key-1", "did:example:issuer", &public_key);
+    let json = serde_json::to_value(&method).unwrap();
+    assert!(json["publicKeyMultibase"].as_str().unwrap().starts_with('z'));
+    assert!(json.get("publicKeyHex").is_none());
+    assert_eq!(method.public_key(), Some(public_key.clone()));
+
+    // Documents from before multibase keys still resolve
+    let mut legacy = json.clone();
+    legacy.as_object_mut().unwrap().remove("publicKeyMultibase");
+    legacy["publicKeyHex"] = serde_json::json!(Encoding::to_hex(&public_key));
+    let legacy: VerificationMethod = serde_json::from_value(legacy).unwrap();
+    assert_eq!(legacy.public_key(), Some(public_key.clone()));
+
+    let mut both = method.clone();
+    both.public_key_hex = legacy.public_key_hex;
+    assert_eq!(both.public_key(), None);
+    let mut other_codec = method;
+    other_codec.public_key_multibase = Some(Encoding::to_multibase(Multibase::Base58Btc, &Encoding::to_multicodec(0xed, &public_key)));
+    assert_eq!(other_codec.public_key(), None);
+  }
+
+  #[test]
+  fn test_split_did_url() {
+    assert_eq!(split_did_url("did:example:a#key-1"), ("did:example:a", Some("key-1")));
+    assert_eq!(split_did_url("did:example:a"), ("did:example:a", None));
+  }
+}
@@ -0,0 +1,129 @@
+// Ring signatures over DID-specified rings
+// A DidRing names its members by DID (or DID URL) and resolves each to a Loquat public key
+// through a DidResolver, e.g. every employee DID an organization publishes. The sorted DID
+// list is hashed and bound into every signature together with the message, so a signature
+// made for "some employee of org X" does not verify against another DID list even if it
+// happens to resolve to the same keys.
+
+use crate::crypto::hash_functions::Hash;
+use crate::signature::config::LoquatConfig;
+use crate::signature::message::SignableMessage;
+use crate::signature::ring::Ring;
+use crate::signature::ring_signature::RingSignature;
+use crate::utils::encoding::Encoding;
+use crate::vc::did::DidResolver;
+
+// Domain separation of the signed transcript
+const DID_RING_DOMAIN: &[u8] = b"loquat-vc-did-ring";
+
+#[derive(Debug, Clone)]
+pub struct DidRing {
+  dids: Vec<String>,
+  ring: Ring,
+}
+
+impl DidRing {
+  // Resolves every member key; None if the list is empty or any DID fails to resolve
+  pub fn resolve<R: DidResolver + ?Sized>(dids: &[&str], resolver: &R, config: &LoquatConfig) -> Option<Self> {
+    let mut dids: Vec<String> = dids.iter().map(|did| did.to_string()).collect();
+    dids.sort();
+    dids.dedup();
+    let keys = dids.iter().map(|did| resolver.resolve_key(did)).collect::<Option<Vec<_>>>()?;
+    Some(Self {
+      ring: Ring::new(&keys, config)?,
+      dids,
+    })
+  }
+
+  // Member DIDs in canonical order
+  pub fn dids(&self) -> &[String] {
+    &self.dids
+  }
+
+  pub fn ring(&self) -> &Ring {
+    &self.ring
+  }
+
+  // Transcript hash of the length-prefixed, sorted DID list
+  pub fn dids_hash(&self) -> Vec<u8> {
+    let mut encoded = vec![];
+    for did in &self.dids {
+      Encoding::write_bytes(&mut encoded, did.as_bytes());
+    }
+    Hash::new(self.ring.config().transcript_hash).compute(&encoded)
+  }
+
+  // Bytes actually signed: domain | DID list hash | message, each length-prefixed
+  fn transcript<M: SignableMessage + ?Sized>(&self, message: &M) -> Vec<u8> {
+    let mut out = DID_RING_DOMAIN.to_vec();
+    Encoding::write_bytes(&mut out, &self.dids_hash());
+    Encoding::write_bytes(&mut out, &message.signing_bytes());
+    out
+  }
+
+  // Signs as the member holding sk; None if its key is not one of the resolved members
+  pub fn sign<M: SignableMessage + ?Sized>(&self, sk: u128, message: &M) -> Option<RingSignature> {
+    self.ring.sign(sk, self.transcript(message).as_slice())
+  }
+
+  pub fn verify<M: SignableMessage + ?Sized>(&self, message: &M, signature: &RingSignature) -> bool {
+    self.ring.verify(self.transcript(message).as_slice(), signature)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::{Loquat, LoquatKeyPair};
+  use crate::vc::credential::Credential;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use serde_json::json;
+
+  fn employees(resolver: &mut StaticDidResolver, names: &[&str]) -> Vec<LoquatKeyPair> {
+    names
+      .iter()
+      .map(|name| {
+        let keys = Loquat::keygen();
+        let mut document = DidDocument::new(&format!("did:example:{}", name));
+        document.add_key("key-1", &keys.public_key);
+        resolver.insert(document);
+        keys
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_signed_by_some_employee() {
+    let mut resolver = StaticDidResolver::new();
+    let keys = employees(&mut resolver, &["alice", "bob", "carol"]);
+    let config = LoquatConfig::default();
+    let org = DidRing::resolve(&["did:example:carol", "did:example:alice", "did:example:bob"], &resolver, &config).unwrap();
+    assert_eq!(org.dids()[0], "did:example:alice");
+
+    let credential = Credential::new("did:example:org", json!({"id": "did:example:subject", "approved": true}));
+    let payload = credential.signing_payload();
+    let signature = org.sign(keys[1].secret_key, &payload).unwrap();
+    assert!(org.verify(&payload, &signature));
+    assert!(!org.verify(&json!({"approved": false}), &signature));
+
+    // Unknown DIDs do not resolve, and non-members cannot sign
+    assert!(DidRing::resolve(&["did:example:alice", "did:example:mallory"], &resolver, &config).is_none());
+    assert!(org.sign(Loquat::keygen().secret_key, &payload).is_none());
+  }
+
+  #[test]
+  fn test_did_list_is_bound() {
+    let mut resolver = StaticDidResolver::new();
+    let keys = employees(&mut resolver, &["alice", "bob"]);
+    let config = LoquatConfig::default();
+    let org = DidRing::resolve(&["did:example:alice", "did:example:bob"], &resolver, &config).unwrap();
+
+    // The same keys listed under DID URLs form the same key ring but a different DID list
+    let aliases = DidRing::resolve(&["did:example:alice#key-1", "did:example:bob#key-1"], &resolver, &config).unwrap();
+    assert_eq!(aliases.ring().root(), org.ring().root());
+    assert_ne!(aliases.dids_hash(), org.dids_hash());
+
+    let signature = org.sign(keys[0].secret_key, b"statement").unwrap();
+    assert!(!aliases.verify(b"statement", &signature));
+  }
+}
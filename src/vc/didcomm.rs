@@ -0,0 +1,321 @@
+// DIDComm v2 message packing for issuance and presentation flows
+// Plaintext messages follow the DIDComm v2 JSON shape (id, type, from, to, thid, body,
+// attachments). Signed messages are compact JWS over the plaintext with the Loquat JOSE
+// algorithm and the sender's verification method as kid. Encrypted messages are a
+// single-recipient JWE-style envelope: the content is sealed with the hybrid encryption of
+// crypto::kem to the recipient's KEM key, with the base64url protected header as associated
+// data. pack/unpack sign then encrypt, so the recipient learns who sent the message and an
+// intermediary learns neither sender nor content.
+//
+// Limitation: each envelope has exactly one recipient; multi-recipient key wrapping is not
+// implemented.
+
+use crate::crypto::kem::{open, seal, Kem, MlKem768, SealedBox, NONCE_BYTES};
+use crate::crypto::test_rng;
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::Credential;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::jwt::{Jwt, JwtHeader};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::presentation::Presentation;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Media types of the three DIDComm message formats
+pub const PLAINTEXT_TYP: &str = "application/didcomm-plain+json";
+pub const SIGNED_TYP: &str = "application/didcomm-signed+json";
+pub const ENCRYPTED_TYP: &str = "application/didcomm-encrypted+json";
+
+// Message types of Issue Credential 3.0 and Present Proof 3.0
+pub const OFFER_CREDENTIAL: &str = "https://didcomm.org/issue-credential/3.0/offer-credential";
+pub const REQUEST_CREDENTIAL: &str = "https://didcomm.org/issue-credential/3.0/request-credential";
+pub const ISSUE_CREDENTIAL: &str = "https://didcomm.org/issue-credential/3.0/issue-credential";
+pub const REQUEST_PRESENTATION: &str = "https://didcomm.org/present-proof/3.0/request-presentation";
+pub const PRESENTATION: &str = "https://didcomm.org/present-proof/3.0/presentation";
+
+// Content encryption identifier of the envelopes
+const CONTENT_ENCRYPTION: &str = "C20P";
+
+// Inline JSON attachment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+  pub id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub media_type: Option<String>,
+  pub data: AttachmentData,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentData {
+  pub json: Value,
+}
+
+// Plaintext DIDComm message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+  pub id: String,
+  pub typ: String,
+  #[serde(rename = "type")]
+  pub message_type: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub from: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub to: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub thid: Option<String>, // Thread id: the id of the first message of the exchange
+  pub body: Value,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub attachments: Vec<Attachment>,
+}
+
+impl Message {
+  // Creates a message with a fresh random id
+  pub fn new(message_type: &str, from: &str, to: &str, body: Value) -> Self {
+    let mut id = [0u8; 16];
+    test_rng::source().fill_bytes(&mut id);
+    Self {
+      id: Encoding::to_hex(&id),
+      typ: PLAINTEXT_TYP.to_string(),
+      message_type: message_type.to_string(),
+      from: Some(from.to_string()),
+      to: vec![to.to_string()],
+      thid: None,
+      body,
+      attachments: vec![],
+    }
+  }
+
+  // Continues the thread of a received message, replying to its sender
+  pub fn reply(message_type: &str, to: &Message, from: &str, body: Value) -> Option<Self> {
+    let mut reply = Self::new(message_type, from, to.from.as_deref()?, body);
+    reply.thid = Some(to.thid.clone().unwrap_or_else(|| to.id.clone()));
+    Some(reply)
+  }
+
+  // Adds an inline JSON attachment
+  pub fn attach(mut self, id: &str, media_type: Option<&str>, json: Value) -> Self {
+    self.attachments.push(Attachment {
+      id: id.to_string(),
+      media_type: media_type.map(str::to_string),
+      data: AttachmentData { json },
+    });
+    self
+  }
+
+  pub fn attachment(&self, id: &str) -> Option<&Value> {
+    self.attachments.iter().find(|a| a.id == id).map(|a| &a.data.json)
+  }
+
+  // Issue-credential message carrying a signed credential as attachment "credential"
+  pub fn issue_credential(request: &Message, issuer: &str, credential: &Credential) -> Option<Self> {
+    let json = serde_json::to_value(credential).expect("Credential serialization failed");
+    Some(Self::reply(ISSUE_CREDENTIAL, request, issuer, json!({}))?.attach("credential", Some("application/vc+ld+json"), json))
+  }
+
+  // Presentation message carrying a signed presentation as attachment "presentation"
+  pub fn presentation(request: &Message, holder: &str, presentation: &Presentation) -> Option<Self> {
+    let json = serde_json::to_value(presentation).expect("Presentation serialization failed");
+    Some(Self::reply(PRESENTATION, request, holder, json!({}))?.attach("presentation", Some("application/vp+ld+json"), json))
+  }
+
+  pub fn attached_credential(&self) -> Option<Credential> {
+    serde_json::from_value(self.attachment("credential")?.clone()).ok()
+  }
+
+  pub fn attached_presentation(&self) -> Option<Presentation> {
+    serde_json::from_value(self.attachment("presentation")?.clone()).ok()
+  }
+}
+
+// Signs a message as compact JWS under the sender's verification method
+// None if the verification method does not belong to the message sender or signing is refused
+pub fn pack_signed(message: &Message, sk: u128, verification_method: &str) -> Option<String> {
+  if message.from.as_deref() != Some(split_did_url(verification_method).0) {
+    return None;
+  }
+  let claims = serde_json::to_value(message).expect("DIDComm message serialization failed");
+  Jwt::sign(&JwtHeader::new(Some(SIGNED_TYP), Some(verification_method)), &claims, sk).ok()
+}
+
+// Usage the sender's key needs for a message: issuers' messages of the issue-credential
+// protocol need ISSUANCE, every other message authenticates its sender as a presentation does
+pub fn sender_usage(message_type: &str) -> KeyUsage {
+  match message_type {
+    OFFER_CREDENTIAL | ISSUE_CREDENTIAL => KeyUsage::ISSUANCE,
+    _ => KeyUsage::PRESENTATION,
+  }
+}
+
+// Verifies a signed message against the sender's DID document, under a key whose usages
+// cover the message type (sender_usage)
+pub fn unpack_signed<R: DidResolver + ?Sized>(jws: &str, resolver: &R) -> Option<Message> {
+  let jwt = Jwt::parse(jws)?;
+  if jwt.header.typ.as_deref() != Some(SIGNED_TYP) {
+    return None;
+  }
+  let kid = jwt.header.kid.as_deref()?;
+  let message: Message = serde_json::from_value(jwt.claims.clone()).ok()?;
+  if message.from.as_deref() != Some(split_did_url(kid).0) || !jwt.verify(&resolver.resolve_key_for(kid, sender_usage(&message.message_type))?) {
+    return None;
+  }
+  Some(message)
+}
+
+// Envelope recipient entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recipient {
+  pub kid: String, // Recipient key id
+  pub encrypted_key: String, // Base64url KEM encapsulation
+}
+
+// Encrypted DIDComm message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedMessage {
+  pub protected: String, // Base64url JSON header: typ, alg, enc
+  pub recipients: Vec<Recipient>,
+  pub iv: String,
+  pub ciphertext: String,
+}
+
+fn protected_header<K: Kem>() -> String {
+  let header = json!({"typ": ENCRYPTED_TYP, "alg": K::ALGORITHM, "enc": CONTENT_ENCRYPTION});
+  Encoding::to_base64url(&serde_json::to_vec(&header).expect("DIDComm header serialization failed"))
+}
+
+// Encrypts a packed message to one recipient KEM key
+pub fn pack_encrypted<K: Kem>(content: &str, recipient_kid: &str, recipient_pk: &[u8]) -> Option<EncryptedMessage> {
+  let protected = protected_header::<K>();
+  let sealed = seal::<K>(recipient_pk, content.as_bytes(), protected.as_bytes())?;
+  Some(EncryptedMessage {
+    protected,
+    recipients: vec![Recipient {
+      kid: recipient_kid.to_string(),
+      encrypted_key: Encoding::to_base64url(&sealed.encapsulated_key),
+    }],
+    iv: Encoding::to_base64url(&sealed.nonce),
+    ciphertext: Encoding::to_base64url(&sealed.ciphertext),
+  })
+}
+
+// Decrypts an envelope addressed to recipient_kid
+pub fn unpack_encrypted<K: Kem>(envelope: &EncryptedMessage, recipient_kid: &str, recipient_sk: &[u8]) -> Option<String> {
+  if envelope.protected != protected_header::<K>() {
+    return None;
+  }
+  let recipient = envelope.recipients.iter().find(|r| r.kid == recipient_kid)?;
+  let nonce: [u8; NONCE_BYTES] = Encoding::from_base64url(&envelope.iv)?.try_into().ok()?;
+  let sealed = SealedBox {
+    encapsulated_key: Encoding::from_base64url(&recipient.encrypted_key)?,
+    nonce,
+    ciphertext: Encoding::from_base64url(&envelope.ciphertext)?,
+  };
+  String::from_utf8(open::<K>(recipient_sk, &sealed, envelope.protected.as_bytes())?).ok()
+}
+
+// Signs a message and encrypts it to the recipient's ML-KEM-768 key
+pub fn pack(message: &Message, sk: u128, verification_method: &str, recipient_kid: &str, recipient_pk: &[u8]) -> Option<EncryptedMessage> {
+  pack_encrypted::<MlKem768>(&pack_signed(message, sk, verification_method)?, recipient_kid, recipient_pk)
+}
+
+// Decrypts and verifies a message produced by pack; the message must be addressed to the
+// recipient's DID
+pub fn unpack<R: DidResolver + ?Sized>(envelope: &EncryptedMessage, recipient_kid: &str, recipient_sk: &[u8], resolver: &R) -> Option<Message> {
+  let message = unpack_signed(&unpack_encrypted::<MlKem768>(envelope, recipient_kid, recipient_sk)?, resolver)?;
+  if !message.to.iter().any(|to| to == split_did_url(recipient_kid).0) {
+    return None;
+  }
+  Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+
+  const ISSUER: &str = "did:example:issuer";
+  const HOLDER: &str = "did:example:holder";
+
+  struct Party {
+    sk: u128,
+    verification_method: String,
+    kem_kid: String,
+    kem_sk: Vec<u8>,
+    kem_pk: Vec<u8>,
+  }
+
+  fn party(did: &str, resolver: &mut StaticDidResolver) -> Party {
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new(did);
+    let verification_method = document.add_key("key-1", &keypair.public_key);
+    resolver.insert(document);
+    let (kem_sk, kem_pk) = MlKem768::generate();
+    Party {
+      sk: keypair.secret_key,
+      verification_method,
+      kem_kid: format!("{}#kem-1", did),
+      kem_sk,
+      kem_pk,
+    }
+  }
+
+  #[test]
+  fn test_issuance_flow() {
+    let mut resolver = StaticDidResolver::new();
+    let issuer = party(ISSUER, &mut resolver);
+    let holder = party(HOLDER, &mut resolver);
+
+    // Holder requests a credential
+    let request = Message::new(REQUEST_CREDENTIAL, HOLDER, ISSUER, json!({"goal_code": "degree"}));
+    let envelope = pack(&request, holder.sk, &holder.verification_method, &issuer.kem_kid, &issuer.kem_pk).unwrap();
+    assert!(!serde_json::to_string(&envelope).unwrap().contains(HOLDER));
+    let received = unpack(&envelope, &issuer.kem_kid, &issuer.kem_sk, &resolver).unwrap();
+    assert_eq!(received, request);
+
+    // Issuer answers on the same thread with the credential attached
+    let mut credential = Credential::new(ISSUER, json!({"id": HOLDER}));
+    credential.sign(issuer.sk, &issuer.verification_method).unwrap();
+    let issue = Message::issue_credential(&received, ISSUER, &credential).unwrap();
+    let envelope = pack(&issue, issuer.sk, &issuer.verification_method, &holder.kem_kid, &holder.kem_pk).unwrap();
+    let delivered = unpack(&envelope, &holder.kem_kid, &holder.kem_sk, &resolver).unwrap();
+    assert_eq!(delivered.thid.as_deref(), Some(request.id.as_str()));
+    assert_eq!(delivered.attached_credential(), Some(credential));
+
+    // Only the addressed recipient can open the envelope
+    assert!(unpack(&envelope, &issuer.kem_kid, &issuer.kem_sk, &resolver).is_none());
+  }
+
+  #[test]
+  fn test_signed_messages_bind_sender() {
+    let mut resolver = StaticDidResolver::new();
+    let issuer = party(ISSUER, &mut resolver);
+    let holder = party(HOLDER, &mut resolver);
+
+    let message = Message::new(OFFER_CREDENTIAL, ISSUER, HOLDER, json!({}));
+    let jws = pack_signed(&message, issuer.sk, &issuer.verification_method).unwrap();
+    assert_eq!(unpack_signed(&jws, &resolver), Some(message.clone()));
+
+    // A party cannot sign in another sender's name
+    assert!(pack_signed(&message, holder.sk, &holder.verification_method).is_none());
+    let mut spoofed = message;
+    spoofed.from = Some(HOLDER.to_string());
+    let jws = Jwt::sign(&JwtHeader::new(Some(SIGNED_TYP), Some(&issuer.verification_method)), &serde_json::to_value(&spoofed).unwrap(), issuer.sk).unwrap();
+    assert!(unpack_signed(&jws, &resolver).is_none());
+  }
+
+  #[test]
+  fn test_sender_key_usage() {
+    let mut resolver = StaticDidResolver::new();
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new(ISSUER);
+    let verification_method = document.add_key_with_usage("key-1", &keypair.public_key, KeyUsage::ISSUANCE);
+    resolver.insert(document);
+
+    let offer = Message::new(OFFER_CREDENTIAL, ISSUER, HOLDER, json!({}));
+    assert!(unpack_signed(&pack_signed(&offer, keypair.secret_key, &verification_method).unwrap(), &resolver).is_some());
+    // An issuance-only key does not authenticate a presentation
+    let presentation = Message::new(PRESENTATION, ISSUER, HOLDER, json!({}));
+    assert!(unpack_signed(&pack_signed(&presentation, keypair.secret_key, &verification_method).unwrap(), &resolver).is_none());
+  }
+}
@@ -0,0 +1,223 @@
+// Hybrid Loquat + classical credential proofs
+// During the post-quantum migration an issuer signs each credential twice, with Loquat and
+// with a classical scheme supplied through ClassicalSigner, and ships both signatures in one
+// proof. Verifiers that trust only one of the schemes accept either signature; stricter
+// verifiers require both. Both components sign the payload under a hybrid domain tag that
+// names the classical scheme, so neither can be stripped off and passed as a standalone proof.
+// The proof value is the hex encoding of: lp(classical type) | lp(Loquat signature) |
+// lp(classical signature).
+
+use crate::crypto::rng::RngHealthError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::{Credential, Proof};
+use crate::vc::proof_scheme::CredentialSignatureScheme;
+use serde_json::Value;
+
+// Proof type identifier for hybrid proofs
+pub const HYBRID_PROOF_TYPE: &str = "LoquatHybridSignature2024";
+
+// Domain separation of the signed transcript
+const HYBRID_DOMAIN: &[u8] = b"loquat-vc-hybrid";
+
+// Classical signing backend, e.g. an Ed25519 key in an HSM
+pub trait ClassicalSigner {
+  // Proof type of the classical scheme; verifiers look the scheme up by it
+  fn proof_type(&self) -> &str;
+
+  fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+// Which component signatures a verifier requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HybridPolicy {
+  Either,
+  Both,
+  LoquatOnly, // Ignores the classical signature, e.g. once the classical scheme is distrusted
+  ClassicalOnly, // Ignores the Loquat signature, e.g. while Loquat keys are not yet distributed
+}
+
+// The two signatures of a hybrid proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridSignature {
+  pub classical_type: String,
+  pub loquat: Vec<u8>, // Serialized Loquat signature
+  pub classical: Vec<u8>,
+}
+
+impl HybridSignature {
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut out = vec![];
+    Encoding::write_bytes(&mut out, self.classical_type.as_bytes());
+    Encoding::write_bytes(&mut out, &self.loquat);
+    Encoding::write_bytes(&mut out, &self.classical);
+    out
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let mut offset = 0;
+    let classical_type = String::from_utf8(Encoding::read_bytes(bytes, &mut offset)?.to_vec()).ok()?;
+    let loquat = Encoding::read_bytes(bytes, &mut offset)?.to_vec();
+    let classical = Encoding::read_bytes(bytes, &mut offset)?.to_vec();
+    if offset != bytes.len() {
+      return None;
+    }
+    Some(Self { classical_type, loquat, classical })
+  }
+
+  // Decodes a hybrid proof; None for other proof types
+  pub fn from_proof(proof: &Proof) -> Option<Self> {
+    if proof.proof_type != HYBRID_PROOF_TYPE {
+      return None;
+    }
+    Self::from_bytes(&proof.decode_value()?)
+  }
+}
+
+// Bytes both components sign: domain | classical type | payload, each length-prefixed
+fn transcript(classical_type: &str, payload: &Value) -> Vec<u8> {
+  let mut out = HYBRID_DOMAIN.to_vec();
+  Encoding::write_bytes(&mut out, classical_type.as_bytes());
+  Encoding::write_bytes(&mut out, &payload.signing_bytes());
+  out
+}
+
+// Signs a JSON payload with Loquat and the classical backend
+pub fn sign<C: ClassicalSigner + ?Sized>(sk: u128, classical: &C, verification_method: &str, payload: &Value) -> Result<Proof, RngHealthError> {
+  let message = transcript(classical.proof_type(), payload);
+  let signature = HybridSignature {
+    classical_type: classical.proof_type().to_string(),
+    loquat: Loquat::sign(sk, message.as_slice())?.to_bytes(),
+    classical: classical.sign(&message),
+  };
+  Ok(Proof {
+    proof_type: HYBRID_PROOF_TYPE.to_string(),
+    verification_method: verification_method.to_string(),
+    proof_value: Proof::encode_value(&signature.to_bytes()),
+  })
+}
+
+// Verifies a hybrid proof under the policy; components the policy ignores are not checked
+// The classical scheme must be the one named in the proof
+pub fn verify<S: CredentialSignatureScheme + ?Sized>(
+  proof: &Proof,
+  loquat_pk: &[u8],
+  classical_pk: &[u8],
+  classical: &S,
+  payload: &Value,
+  policy: HybridPolicy,
+) -> bool {
+  let signature = match HybridSignature::from_proof(proof) {
+    Some(signature) if signature.classical_type == classical.proof_type() => signature,
+    _ => return false,
+  };
+  let message = transcript(&signature.classical_type, payload);
+  let loquat_valid = || match LoquatSignature::from_bytes(&signature.loquat) {
+    Some(loquat) => Loquat::verify(loquat_pk, message.as_slice(), &loquat),
+    None => false,
+  };
+  let classical_valid = || classical.verify(classical_pk, &message, &signature.classical);
+
+  match policy {
+    HybridPolicy::Either => loquat_valid() || classical_valid(),
+    HybridPolicy::Both => loquat_valid() && classical_valid(),
+    HybridPolicy::LoquatOnly => loquat_valid(),
+    HybridPolicy::ClassicalOnly => classical_valid(),
+  }
+}
+
+// Signs a credential with both schemes, replacing any existing proof
+pub fn sign_credential<C: ClassicalSigner + ?Sized>(credential: &mut Credential, sk: u128, classical: &C, verification_method: &str) -> Result<(), RngHealthError> {
+  credential.proof = Some(sign(sk, classical, verification_method, &credential.signing_payload())?);
+  Ok(())
+}
+
+pub fn verify_credential<S: CredentialSignatureScheme + ?Sized>(
+  credential: &Credential,
+  loquat_pk: &[u8],
+  classical_pk: &[u8],
+  classical: &S,
+  policy: HybridPolicy,
+) -> bool {
+  match &credential.proof {
+    Some(proof) => verify(proof, loquat_pk, classical_pk, classical, &credential.signing_payload(), policy),
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  // Stand-in classical scheme: the "signature" is the key followed by the message
+  struct Concat(Vec<u8>);
+
+  impl ClassicalSigner for Concat {
+    fn proof_type(&self) -> &str {
+      "ConcatSignature"
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+      [self.0.as_slice(), message].concat()
+    }
+  }
+
+  impl CredentialSignatureScheme for Concat {
+    fn proof_type(&self) -> &str {
+      "ConcatSignature"
+    }
+
+    fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+      signature == [public_key, message].concat().as_slice()
+    }
+  }
+
+  fn signed_credential() -> (Credential, Vec<u8>, Concat) {
+    let keypair = Loquat::keygen();
+    let classical = Concat(b"classical key".to_vec());
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:subject"}));
+    sign_credential(&mut credential, keypair.secret_key, &classical, "did:example:issuer#key-1").unwrap();
+    (credential, keypair.public_key, classical)
+  }
+
+  #[test]
+  fn test_hybrid_policies() {
+    let (credential, loquat_pk, classical) = signed_credential();
+    let classical_pk = classical.0.clone();
+    for policy in [HybridPolicy::Either, HybridPolicy::Both, HybridPolicy::LoquatOnly, HybridPolicy::ClassicalOnly] {
+      assert!(verify_credential(&credential, &loquat_pk, &classical_pk, &classical, policy));
+    }
+
+    // A wrong Loquat key fails every policy that checks the Loquat signature
+    let wrong_pk = Loquat::keygen().public_key;
+    assert!(verify_credential(&credential, &wrong_pk, &classical_pk, &classical, HybridPolicy::Either));
+    assert!(verify_credential(&credential, &wrong_pk, &classical_pk, &classical, HybridPolicy::ClassicalOnly));
+    assert!(!verify_credential(&credential, &wrong_pk, &classical_pk, &classical, HybridPolicy::Both));
+    assert!(!verify_credential(&credential, &wrong_pk, &classical_pk, &classical, HybridPolicy::LoquatOnly));
+    assert!(!verify_credential(&credential, &wrong_pk, b"other key", &classical, HybridPolicy::Either));
+  }
+
+  #[test]
+  fn test_components_cannot_be_stripped() {
+    let (credential, loquat_pk, classical) = signed_credential();
+    let proof = credential.proof.clone().unwrap();
+    let signature = HybridSignature::from_proof(&proof).unwrap();
+
+    // The Loquat component signed the hybrid transcript, not the bare payload
+    let mut stripped = credential.clone();
+    stripped.proof = Some(Proof {
+      proof_type: crate::vc::credential::LOQUAT_PROOF_TYPE.to_string(),
+      verification_method: proof.verification_method.clone(),
+      proof_value: Proof::encode_value(&signature.loquat),
+    });
+    assert!(!stripped.verify(&loquat_pk));
+
+    // Tampering with the payload breaks both components
+    let mut tampered = credential.clone();
+    tampered.credential_subject = json!({"id": "did:example:other"});
+    assert!(!verify_credential(&tampered, &loquat_pk, &classical.0, &classical, HybridPolicy::Either));
+    assert_eq!(HybridSignature::from_bytes(&signature.to_bytes()), Some(signature));
+  }
+}
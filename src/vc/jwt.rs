@@ -0,0 +1,132 @@
+// Compact JWS/JWT signed with Loquat
+// The signing input is the ASCII string base64url(header) "." base64url(payload),
+// exactly as in RFC 7515; the signature segment is the serialized Loquat signature.
+// Tokens are produced with the header and claims in JCS (RFC 8785) form, so the same claims
+// always give the same token; parsing accepts any JSON the signer chose to encode.
+
+use crate::crypto::rng::RngHealthError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::utils::encoding::Encoding;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Provisional JOSE algorithm identifier for Loquat signatures
+pub const LOQUAT_JWS_ALG: &str = "LOQUAT";
+
+// JOSE header of a Loquat-signed JWT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtHeader {
+  pub alg: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub typ: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub kid: Option<String>, // Verification method DID URL of the signing key
+}
+
+impl JwtHeader {
+  pub fn new(typ: Option<&str>, kid: Option<&str>) -> Self {
+    Self {
+      alg: LOQUAT_JWS_ALG.to_string(),
+      typ: typ.map(str::to_string),
+      kid: kid.map(str::to_string),
+    }
+  }
+}
+
+// A parsed, not yet verified, compact JWT
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jwt {
+  pub header: JwtHeader,
+  pub claims: Value,
+  signing_input: String,
+  signature: Vec<u8>,
+}
+
+impl Jwt {
+  // Signs claims under the given header and returns the compact serialization
+  pub fn sign(header: &JwtHeader, claims: &Value, sk: u128) -> Result<String, RngHealthError> {
+    let header = serde_json::to_value(header).expect("JWT header serialization failed");
+    let signing_input = format!(
+      "{}.{}",
+      Encoding::to_base64url(Encoding::to_jcs(&header).as_bytes()),
+      Encoding::to_base64url(Encoding::to_jcs(claims).as_bytes())
+    );
+    let signature = Loquat::sign(sk, signing_input.as_bytes())?;
+    Ok(format!("{}.{}", signing_input, Encoding::to_base64url(&signature.to_bytes())))
+  }
+
+  // Parses a compact JWT; only Loquat-signed tokens are accepted
+  pub fn parse(token: &str) -> Option<Self> {
+    let mut parts = token.split('.');
+    let (header_b64, claims_b64, signature_b64) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+      return None;
+    }
+
+    let header: JwtHeader = serde_json::from_slice(&Encoding::from_base64url(header_b64)?).ok()?;
+    if header.alg != LOQUAT_JWS_ALG {
+      return None;
+    }
+    let claims: Value = serde_json::from_slice(&Encoding::from_base64url(claims_b64)?).ok()?;
+
+    Some(Self {
+      header,
+      claims,
+      signing_input: format!("{}.{}", header_b64, claims_b64),
+      signature: Encoding::from_base64url(signature_b64)?,
+    })
+  }
+
+  // Verifies the signature against a public key
+  pub fn verify(&self, pk: &[u8]) -> bool {
+    match LoquatSignature::from_bytes(&self.signature) {
+      Some(signature) => Loquat::verify(pk, self.signing_input.as_bytes(), &signature),
+      None => false,
+    }
+  }
+
+  // Reads a string claim
+  pub fn claim_str(&self, name: &str) -> Option<&str> {
+    self.claims.get(name)?.as_str()
+  }
+
+  // Reads a numeric claim such as iat or exp
+  pub fn claim_u64(&self, name: &str) -> Option<u64> {
+    self.claims.get(name)?.as_u64()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_jwt_round_trip() {
+    let keypair = Loquat::keygen();
+    let header = JwtHeader::new(Some("JWT"), Some("did:example:alice#key-1"));
+    let token = Jwt::sign(&header, &json!({"sub": "alice", "iat": 1000}), keypair.secret_key).unwrap();
+
+    let jwt = Jwt::parse(&token).unwrap();
+    assert_eq!(jwt.header, header);
+    assert_eq!(jwt.claim_str("sub"), Some("alice"));
+    assert_eq!(jwt.claim_u64("iat"), Some(1000));
+    assert!(jwt.verify(&keypair.public_key));
+    assert!(!jwt.verify(&Loquat::keygen().public_key));
+  }
+
+  #[test]
+  fn test_jwt_tampering_rejected() {
+    let keypair = Loquat::keygen();
+    let token = Jwt::sign(&JwtHeader::new(None, None), &json!({"sub": "alice"}), keypair.secret_key).unwrap();
+    let parts: Vec<&str> = token.split('.').collect();
+
+    let forged_claims = Encoding::to_base64url(br#"{"sub":"mallory"}"#);
+    let forged = format!("{}.{}.{}", parts[0], forged_claims, parts[2]);
+    assert!(!Jwt::parse(&forged).unwrap().verify(&keypair.public_key));
+
+    let other_alg = Encoding::to_base64url(br#"{"alg":"none"}"#);
+    assert!(Jwt::parse(&format!("{}.{}.{}", other_alg, parts[1], parts[2])).is_none());
+    assert!(Jwt::parse(&format!("{}.{}", parts[0], parts[1])).is_none());
+  }
+}
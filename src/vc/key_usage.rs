@@ -0,0 +1,207 @@
+// Key usages
+// A DID document can restrict each verification method to the kinds of objects it may sign:
+// credentials (issuance), presentations, delegation credentials and revocation material such
+// as status lists. Verifiers resolve keys for a usage and treat a key outside its usages as
+// unknown, and SigningKey refuses to sign outside them, so an issuance key cannot quietly
+// sign a presentation or a status list (status::SignedStatusList). A verification method
+// without keyUsage may sign anything, as every key could before usages were introduced.
+
+use crate::crypto::rng::RngHealthError;
+use crate::vc::credential::Credential;
+use crate::vc::delegation::delegation_grant;
+use crate::vc::did::DidDocument;
+use crate::vc::presentation::Presentation;
+use crate::vc::status::SignedStatusList;
+use serde::{Deserialize, Serialize};
+use std::ops::BitOr;
+
+// Set of usages, serialized as a list of names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "Vec<String>", try_from = "Vec<String>")]
+pub struct KeyUsage(u8);
+
+impl KeyUsage {
+  pub const ISSUANCE: Self = Self(1);
+  pub const PRESENTATION: Self = Self(1 << 1);
+  pub const DELEGATION: Self = Self(1 << 2);
+  pub const REVOCATION: Self = Self(1 << 3); // Signing status lists and other revocation material
+  pub const ALL: Self = Self(0b1111);
+
+  const NAMES: [(KeyUsage, &'static str); 4] = [
+    (KeyUsage::ISSUANCE, "issuance"),
+    (KeyUsage::PRESENTATION, "presentation"),
+    (KeyUsage::DELEGATION, "delegation"),
+    (KeyUsage::REVOCATION, "revocation"),
+  ];
+
+  pub fn contains(self, other: KeyUsage) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  pub fn names(self) -> Vec<&'static str> {
+    Self::NAMES.iter().filter(|(usage, _)| self.contains(*usage)).map(|(_, name)| *name).collect()
+  }
+
+  // None if any name is unknown
+  pub fn from_names<S: AsRef<str>>(names: &[S]) -> Option<Self> {
+    names.iter().try_fold(KeyUsage(0), |usages, name| {
+      let (usage, _) = Self::NAMES.iter().find(|(_, known)| *known == name.as_ref())?;
+      Some(usages | *usage)
+    })
+  }
+
+  // Usage a credential's proof exercises: delegation credentials need DELEGATION, all others
+  // ISSUANCE
+  pub fn for_credential(credential: &Credential) -> Self {
+    match delegation_grant(credential) {
+      Some(_) => KeyUsage::DELEGATION,
+      None => KeyUsage::ISSUANCE,
+    }
+  }
+}
+
+impl BitOr for KeyUsage {
+  type Output = Self;
+
+  fn bitor(self, other: Self) -> Self {
+    Self(self.0 | other.0)
+  }
+}
+
+impl From<KeyUsage> for Vec<String> {
+  fn from(usage: KeyUsage) -> Self {
+    usage.names().into_iter().map(str::to_string).collect()
+  }
+}
+
+impl TryFrom<Vec<String>> for KeyUsage {
+  type Error = String;
+
+  fn try_from(names: Vec<String>) -> Result<Self, String> {
+    KeyUsage::from_names(&names).ok_or_else(|| format!("unknown key usage in {:?}", names))
+  }
+}
+
+// A signing attempt outside the key's usages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyUsageError {
+  pub required: KeyUsage,
+  pub granted: KeyUsage,
+}
+
+// Why a SigningKey did not sign
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningError {
+  KeyUsage(KeyUsageError),
+  Rng(RngHealthError), // Signing refused after a failed randomness selftest
+}
+
+impl From<KeyUsageError> for SigningError {
+  fn from(error: KeyUsageError) -> Self {
+    SigningError::KeyUsage(error)
+  }
+}
+
+impl From<RngHealthError> for SigningError {
+  fn from(error: RngHealthError) -> Self {
+    SigningError::Rng(error)
+  }
+}
+
+// A secret key together with its verification method and the usages granted to it
+#[derive(Debug, Clone)]
+pub struct SigningKey {
+  sk: u128,
+  pub verification_method: String,
+  pub usage: KeyUsage,
+}
+
+impl SigningKey {
+  pub fn new(sk: u128, verification_method: &str, usage: KeyUsage) -> Self {
+    Self {
+      sk,
+      verification_method: verification_method.to_string(),
+      usage,
+    }
+  }
+
+  // Takes the usages from the key's entry in its controller's document; None if the
+  // document does not list the verification method
+  pub fn from_document(sk: u128, document: &DidDocument, verification_method: &str) -> Option<Self> {
+    let method = document.verification_method(verification_method)?;
+    Some(Self::new(sk, verification_method, method.key_usage.unwrap_or(KeyUsage::ALL)))
+  }
+
+  // The secret key, for signing an object of the given usage
+  pub fn authorize(&self, required: KeyUsage) -> Result<u128, KeyUsageError> {
+    match self.usage.contains(required) {
+      true => Ok(self.sk),
+      false => Err(KeyUsageError { required, granted: self.usage }),
+    }
+  }
+
+  pub fn sign_credential(&self, credential: &mut Credential) -> Result<(), SigningError> {
+    Ok(credential.sign(self.authorize(KeyUsage::for_credential(credential))?, &self.verification_method)?)
+  }
+
+  pub fn sign_presentation(&self, presentation: &mut Presentation) -> Result<(), SigningError> {
+    Ok(presentation.sign(self.authorize(KeyUsage::PRESENTATION)?, &self.verification_method)?)
+  }
+
+  pub fn sign_status_list(&self, list: &mut SignedStatusList) -> Result<(), SigningError> {
+    Ok(list.sign(self.authorize(KeyUsage::REVOCATION)?, &self.verification_method)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::error::VerifyError;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::delegation::{issue_delegation, DelegationGrant};
+  use crate::vc::did::{DidResolver, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  #[test]
+  fn test_usage_names() {
+    let usage = KeyUsage::ISSUANCE | KeyUsage::REVOCATION;
+    assert!(usage.contains(KeyUsage::REVOCATION) && !usage.contains(KeyUsage::PRESENTATION));
+    assert_eq!(serde_json::to_value(usage).unwrap(), json!(["issuance", "revocation"]));
+    assert_eq!(serde_json::from_value::<KeyUsage>(json!(["revocation", "issuance"])).unwrap(), usage);
+    assert!(serde_json::from_value::<KeyUsage>(json!(["signing"])).is_err());
+    assert_eq!(KeyUsage::from_names(&KeyUsage::ALL.names()), Some(KeyUsage::ALL));
+  }
+
+  #[test]
+  fn test_usages_enforced_when_signing_and_verifying() {
+    let issuer = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key_with_usage("key-1", &issuer.public_key, KeyUsage::ISSUANCE);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document.clone());
+    let status_lists = InMemoryStatusLists::new();
+
+    // The issuance key signs credentials but refuses presentations and delegations
+    let key = SigningKey::from_document(issuer.secret_key, &document, &key_id).unwrap();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    assert_eq!(key.sign_credential(&mut credential), Ok(()));
+    assert!(credential.verify_with_resolver(&resolver, &status_lists, 0));
+    let mut presentation = Presentation::new("did:example:issuer", vec![credential], "challenge-1");
+    let refused = KeyUsageError { required: KeyUsage::PRESENTATION, granted: KeyUsage::ISSUANCE };
+    assert_eq!(key.sign_presentation(&mut presentation), Err(SigningError::KeyUsage(refused)));
+    assert_eq!(presentation.proof, None);
+    assert_eq!(resolver.resolve_key_for(&key_id, KeyUsage::PRESENTATION), None);
+
+    // A delegation signed with the raw key anyway is rejected by verifiers
+    let grant = DelegationGrant::new(&["UniversityDegree"], 1);
+    let delegation = issue_delegation("did:example:issuer", "did:example:faculty", &grant, issuer.secret_key, &key_id).unwrap();
+    assert_eq!(delegation.try_verify_with_resolver(&resolver, &status_lists, 0), Err(VerifyError::KeyUsage));
+
+    // Keys without listed usages keep signing everything
+    let mut open = DidDocument::new("did:example:issuer");
+    open.add_key("key-1", &issuer.public_key);
+    resolver.insert(open);
+    assert!(delegation.verify_with_resolver(&resolver, &status_lists, 0));
+  }
+}
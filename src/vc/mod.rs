@@ -0,0 +1,84 @@
+//! # Verifiable Credentials Module
+//!
+//! This module builds a W3C-style verifiable credential data model on top of the Loquat
+//! signature scheme.
+//!
+//! ## Module Structure
+//! - `credential`: Credential data model, issuance and verification
+//! - `data_model`: W3C VC Data Model 1.1 conformance checks for credentials and presentations
+//! - `proof_scheme`: Proof-type dispatch to Loquat and other credential signature schemes
+//! - `presentation`: Holder-signed presentations of one or more credentials
+//! - `proven`: Credentials carrying a succinct proof of the issuer's signature instead of it
+//! - `challenge`: Single-use verifier challenges protecting presentations against replay
+//! - `did`: DID documents and the `DidResolver` trait
+//! - `did_ring`: Ring signatures over rings of resolved DIDs, revealing the signer (feature `experimental`)
+//! - `key_usage`: Per-key usage restrictions enforced when signing and verifying
+//! - `status`: Revocation status lists, published signed under a revocation key
+//! - `policy`: Pluggable verification policies with structured decisions
+//! - `trust_registry`: Signed lists of accredited issuers
+//! - `offline`: Signed bundles of keys, status, trust list and schemas for offline verification
+//! - `hybrid`: Dual Loquat and classical signatures in one proof for PQ migration
+//! - `multi_issuer`: Co-signed credentials under an m-of-n issuer policy
+//! - `delegation`: Delegated issuance authority and delegation chain verification
+//! - `pseudonym`: Holder link secrets and per-relying-party pseudonyms, revealing the link secret (feature `experimental`)
+//! - `jwt`: Compact JWS/JWT signed with Loquat (feature `jose`)
+//! - `sd_jwt`: SD-JWT selective disclosure with salted, hashed disclosures (feature `jose`)
+//! - `anoncreds`: Anoncreds-style definitions, proof requests and proofs (feature `jose`)
+//! - `oid4vci`: OpenID for Verifiable Credential Issuance, credential issuer side (feature `jose`)
+//! - `presentation_exchange`: DIF Presentation Exchange definitions and submissions (feature `jose`)
+//! - `oid4vp`: OpenID for Verifiable Presentations, verifier side (feature `jose`)
+//! - `session`: Persistable state machines for issuance and presentation exchanges (feature `jose`)
+//! - `cose`: COSE_Sign1 and CWT credential encodings (feature `cose`)
+//! - `delivery`: Credentials encrypted to a wallet's KEM key (feature `kem`)
+//! - `didcomm`: DIDComm v2 message packing for issuance and presentation (feature `didcomm`)
+//! - `async_api`: Async wrappers for tokio-based services (feature `async`)
+//!
+//! `Credential`, `CredentialBuilder`, `Proof`, `Presentation`, `CredentialStatus`,
+//! `DidDocument` and `DidResolver` are re-exported at the module root.
+
+#[cfg(feature = "jose")]
+pub mod anoncreds;
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod challenge;
+#[cfg(feature = "cose")]
+pub mod cose;
+pub mod credential;
+pub mod data_model;
+pub mod delegation;
+#[cfg(feature = "kem")]
+pub mod delivery;
+#[cfg(feature = "didcomm")]
+pub mod didcomm;
+pub mod did;
+#[cfg(feature = "experimental")]
+pub mod did_ring;
+pub mod hybrid;
+#[cfg(feature = "jose")]
+pub mod jwt;
+pub mod key_usage;
+pub mod multi_issuer;
+pub mod offline;
+#[cfg(feature = "jose")]
+pub mod oid4vci;
+#[cfg(feature = "jose")]
+pub mod oid4vp;
+pub mod policy;
+pub mod presentation;
+#[cfg(feature = "jose")]
+pub mod presentation_exchange;
+pub mod proof_scheme;
+pub mod proven;
+#[cfg(feature = "experimental")]
+pub mod pseudonym;
+#[cfg(feature = "jose")]
+pub mod sd_jwt;
+#[cfg(feature = "jose")]
+pub mod session;
+pub mod status;
+pub mod trust_registry;
+
+pub use credential::{Credential, CredentialBuilder, Proof};
+pub use did::{DidDocument, DidResolver};
+pub use presentation::Presentation;
+pub use status::CredentialStatus;
@@ -0,0 +1,363 @@
+// Multi-issuer credentials
+// A credential can require signatures from several issuers (e.g. a university and a
+// ministry). The m-of-n policy travels inside the credential, so it is covered by every
+// co-signature. Precedence constraints order the signers partially: a co-signature
+// covers the co-signatures of its required predecessors, so a later signer endorses
+// exactly what the earlier signers produced.
+
+use crate::crypto::rng::RngHealthError;
+use crate::vc::credential::{Credential, Proof};
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::status::StatusListSource;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+// Ordering constraint: `before` must co-sign ahead of `after`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Precedence {
+  pub before: String, // Issuer DID
+  pub after: String, // Issuer DID
+}
+
+// m-of-n co-signature policy embedded in a credential
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuerPolicy {
+  pub issuers: Vec<String>, // DIDs allowed to co-sign
+  pub threshold: usize, // Number of distinct issuers required
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub precedence: Vec<Precedence>,
+}
+
+impl IssuerPolicy {
+  // Creates a policy without ordering constraints
+  pub fn new(issuers: &[&str], threshold: usize) -> Self {
+    Self {
+      issuers: issuers.iter().map(|issuer| issuer.to_string()).collect(),
+      threshold,
+      precedence: vec![],
+    }
+  }
+
+  // Requires `before` to co-sign ahead of `after`
+  pub fn require_order(mut self, before: &str, after: &str) -> Self {
+    self.precedence.push(Precedence {
+      before: before.to_string(),
+      after: after.to_string(),
+    });
+    self
+  }
+
+  // Whether the policy can be satisfied: a reachable threshold and constraints between listed issuers
+  pub fn is_valid(&self) -> bool {
+    let unique: BTreeSet<&String> = self.issuers.iter().collect();
+    unique.len() == self.issuers.len()
+      && self.threshold >= 1
+      && self.threshold <= self.issuers.len()
+      && self
+        .precedence
+        .iter()
+        .all(|p| p.before != p.after && self.allows(&p.before) && self.allows(&p.after))
+  }
+
+  // Whether the DID is one of the policy's issuers
+  pub fn allows(&self, issuer: &str) -> bool {
+    self.issuers.iter().any(|candidate| candidate == issuer)
+  }
+
+  // Issuers that must co-sign before the given issuer
+  pub fn predecessors(&self, issuer: &str) -> Vec<&str> {
+    self
+      .precedence
+      .iter()
+      .filter(|p| p.after == issuer)
+      .map(|p| p.before.as_str())
+      .collect()
+  }
+}
+
+// One issuer's signature within a multi-issuer credential
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoSignature {
+  pub issuer: String, // DID of the co-signer
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub previous: Vec<String>, // Issuers whose co-signatures this one covers
+  pub proof: Proof,
+}
+
+// Errors raised while collecting co-signatures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoSignError {
+  NotInPolicy,
+  AlreadySigned,
+  MissingPredecessor(String), // Issuer that has to co-sign first
+  Rng(RngHealthError), // Signing refused after a failed randomness selftest
+}
+
+// A credential together with its co-signatures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoSignedCredential {
+  #[serde(flatten)]
+  pub credential: Credential,
+  pub co_signatures: Vec<CoSignature>,
+}
+
+// Payload covered by a co-signature: the credential and the predecessors' proofs
+fn co_signing_payload(credential: &Credential, previous: &[&CoSignature]) -> Value {
+  let proofs: Vec<Value> = previous
+    .iter()
+    .map(|co_signature| serde_json::to_value(co_signature).expect("Co-signature serialization failed"))
+    .collect();
+  json!({"credential": credential.signing_payload(), "previousProofs": proofs})
+}
+
+// Issuance session collecting co-signatures on a fixed credential
+#[derive(Debug, Clone)]
+pub struct IssuanceSession {
+  credential: Credential,
+  co_signatures: Vec<CoSignature>,
+}
+
+impl IssuanceSession {
+  // Starts a session; the credential must carry a valid issuer policy naming its issuer
+  pub fn new(mut credential: Credential) -> Option<Self> {
+    let policy = credential.issuer_policy.as_ref()?;
+    if !policy.is_valid() || !policy.allows(&credential.issuer) {
+      return None;
+    }
+    credential.proof = None;
+    Some(Self {
+      credential,
+      co_signatures: vec![],
+    })
+  }
+
+  fn policy(&self) -> &IssuerPolicy {
+    self.credential.issuer_policy.as_ref().expect("Session credential has a policy")
+  }
+
+  fn co_signature(&self, issuer: &str) -> Option<&CoSignature> {
+    self.co_signatures.iter().find(|co_signature| co_signature.issuer == issuer)
+  }
+
+  // Issuers in the policy that have not co-signed yet
+  pub fn pending(&self) -> Vec<&str> {
+    self
+      .policy()
+      .issuers
+      .iter()
+      .filter(|issuer| self.co_signature(issuer).is_none())
+      .map(|issuer| issuer.as_str())
+      .collect()
+  }
+
+  // Adds the co-signature of the issuer controlling the verification method
+  pub fn co_sign(&mut self, sk: u128, verification_method: &str) -> Result<(), CoSignError> {
+    let issuer = split_did_url(verification_method).0.to_string();
+    let policy = self.policy();
+    if !policy.allows(&issuer) {
+      return Err(CoSignError::NotInPolicy);
+    }
+    if self.co_signature(&issuer).is_some() {
+      return Err(CoSignError::AlreadySigned);
+    }
+
+    let mut previous = vec![];
+    for predecessor in policy.predecessors(&issuer) {
+      match self.co_signature(predecessor) {
+        Some(co_signature) => previous.push(co_signature),
+        None => return Err(CoSignError::MissingPredecessor(predecessor.to_string())),
+      }
+    }
+
+    let payload = co_signing_payload(&self.credential, &previous);
+    let co_signature = CoSignature {
+      previous: previous.iter().map(|co_signature| co_signature.issuer.clone()).collect(),
+      issuer,
+      proof: Proof::sign(sk, verification_method, &payload).map_err(CoSignError::Rng)?,
+    };
+    self.co_signatures.push(co_signature);
+    Ok(())
+  }
+
+  // Whether enough issuers have co-signed
+  pub fn is_complete(&self) -> bool {
+    self.co_signatures.len() >= self.policy().threshold
+  }
+
+  // Finishes the session once the threshold is reached
+  pub fn finish(self) -> Option<CoSignedCredential> {
+    if !self.is_complete() {
+      return None;
+    }
+    Some(CoSignedCredential {
+      credential: self.credential,
+      co_signatures: self.co_signatures,
+    })
+  }
+}
+
+impl CoSignedCredential {
+  // Verifies every co-signature and enforces the embedded policy, then checks expiry and revocation
+  pub fn verify_with_resolver<R, S>(&self, resolver: &R, status_lists: &S, now: u64) -> bool
+  where
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    let policy = match &self.credential.issuer_policy {
+      Some(policy) => policy,
+      None => return false,
+    };
+    if !policy.is_valid() || !policy.allows(&self.credential.issuer) {
+      return false;
+    }
+
+    let mut signed: BTreeSet<&str> = BTreeSet::new();
+    for (position, co_signature) in self.co_signatures.iter().enumerate() {
+      let issuer = co_signature.issuer.as_str();
+      if !policy.allows(issuer) || !signed.insert(issuer) {
+        return false;
+      }
+      if split_did_url(&co_signature.proof.verification_method).0 != issuer {
+        return false;
+      }
+
+      // The covered co-signatures must be exactly the issuer's required predecessors, signed earlier
+      let required: BTreeSet<&str> = policy.predecessors(issuer).into_iter().collect();
+      let covered: BTreeSet<&str> = co_signature.previous.iter().map(|p| p.as_str()).collect();
+      if required != covered || covered.len() != co_signature.previous.len() {
+        return false;
+      }
+      let mut previous = vec![];
+      for predecessor in &co_signature.previous {
+        match self.co_signatures[..position].iter().find(|earlier| &earlier.issuer == predecessor) {
+          Some(earlier) => previous.push(earlier),
+          None => return false,
+        }
+      }
+
+      let pk = match resolver.resolve_key_for(&co_signature.proof.verification_method, KeyUsage::ISSUANCE) {
+        Some(pk) => pk,
+        None => return false,
+      };
+      if !co_signature.proof.verify(&pk, &co_signing_payload(&self.credential, &previous)) {
+        return false;
+      }
+    }
+
+    if signed.len() < policy.threshold || self.credential.is_expired(now) {
+      return false;
+    }
+    match &self.credential.credential_status {
+      Some(status) => !status_lists.is_revoked(status),
+      None => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::{Loquat, LoquatKeyPair};
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+
+  const UNIVERSITY: &str = "did:example:university";
+  const MINISTRY: &str = "did:example:ministry";
+  const REGISTRAR: &str = "did:example:registrar";
+
+  fn setup() -> (StaticDidResolver, Vec<(String, LoquatKeyPair)>) {
+    let mut resolver = StaticDidResolver::new();
+    let mut keys = vec![];
+    for did in [UNIVERSITY, MINISTRY, REGISTRAR] {
+      let keypair = Loquat::keygen();
+      let mut document = DidDocument::new(did);
+      let key_id = document.add_key("key-1", &keypair.public_key);
+      resolver.insert(document);
+      keys.push((key_id, keypair));
+    }
+    (resolver, keys)
+  }
+
+  fn credential(policy: IssuerPolicy) -> Credential {
+    let mut credential = Credential::new(UNIVERSITY, json!({"id": "did:example:alice", "degree": "MSc"}));
+    credential.issuer_policy = Some(policy);
+    credential
+  }
+
+  #[test]
+  fn test_threshold_co_signing() {
+    let (resolver, keys) = setup();
+    let status_lists = InMemoryStatusLists::new();
+    let policy = IssuerPolicy::new(&[UNIVERSITY, MINISTRY, REGISTRAR], 2);
+    let mut session = IssuanceSession::new(credential(policy)).unwrap();
+
+    session.co_sign(keys[0].1.secret_key, &keys[0].0).unwrap();
+    assert!(!session.is_complete());
+    assert_eq!(session.pending(), vec![MINISTRY, REGISTRAR]);
+    assert_eq!(session.co_sign(keys[0].1.secret_key, &keys[0].0), Err(CoSignError::AlreadySigned));
+    assert_eq!(
+      session.co_sign(keys[0].1.secret_key, "did:example:outsider#key-1"),
+      Err(CoSignError::NotInPolicy)
+    );
+    assert!(session.clone().finish().is_none());
+
+    session.co_sign(keys[2].1.secret_key, &keys[2].0).unwrap();
+    let co_signed = session.finish().unwrap();
+    assert!(co_signed.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // The policy survives a JSON round trip and is covered by the signatures
+    let decoded: CoSignedCredential = serde_json::from_str(&serde_json::to_string(&co_signed).unwrap()).unwrap();
+    assert!(decoded.verify_with_resolver(&resolver, &status_lists, 0));
+
+    let mut weakened = co_signed.clone();
+    weakened.credential.issuer_policy.as_mut().unwrap().threshold = 1;
+    assert!(!weakened.verify_with_resolver(&resolver, &status_lists, 0));
+
+    let mut truncated = co_signed;
+    truncated.co_signatures.pop();
+    assert!(!truncated.verify_with_resolver(&resolver, &status_lists, 0));
+  }
+
+  #[test]
+  fn test_precedence_is_enforced() {
+    let (resolver, keys) = setup();
+    let status_lists = InMemoryStatusLists::new();
+    let policy = IssuerPolicy::new(&[UNIVERSITY, MINISTRY], 2).require_order(UNIVERSITY, MINISTRY);
+    let mut session = IssuanceSession::new(credential(policy)).unwrap();
+
+    assert_eq!(
+      session.co_sign(keys[1].1.secret_key, &keys[1].0),
+      Err(CoSignError::MissingPredecessor(UNIVERSITY.to_string()))
+    );
+    session.co_sign(keys[0].1.secret_key, &keys[0].0).unwrap();
+    session.co_sign(keys[1].1.secret_key, &keys[1].0).unwrap();
+    let co_signed = session.finish().unwrap();
+    assert_eq!(co_signed.co_signatures[1].previous, vec![UNIVERSITY.to_string()]);
+    assert!(co_signed.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // Reordering breaks the chain of covered signatures
+    let mut reordered = co_signed.clone();
+    reordered.co_signatures.reverse();
+    assert!(!reordered.verify_with_resolver(&resolver, &status_lists, 0));
+
+    // The successor has to cover its predecessor's signature
+    let mut detached = co_signed;
+    detached.co_signatures[1].previous.clear();
+    assert!(!detached.verify_with_resolver(&resolver, &status_lists, 0));
+  }
+
+  #[test]
+  fn test_invalid_policies_rejected() {
+    assert!(IssuanceSession::new(Credential::new(UNIVERSITY, json!({}))).is_none());
+    assert!(IssuanceSession::new(credential(IssuerPolicy::new(&[UNIVERSITY], 2))).is_none());
+    assert!(IssuanceSession::new(credential(IssuerPolicy::new(&[MINISTRY, REGISTRAR], 1))).is_none());
+    assert!(IssuanceSession::new(credential(IssuerPolicy::new(&[UNIVERSITY, UNIVERSITY], 1))).is_none());
+    let dangling = IssuerPolicy::new(&[UNIVERSITY], 1).require_order(MINISTRY, UNIVERSITY);
+    assert!(IssuanceSession::new(credential(dangling)).is_none());
+  }
+}
@@ -0,0 +1,303 @@
+// Offline verification bundles
+// A verifier without network access (a border post, an inspector in the field) cannot resolve
+// issuer DIDs, fetch status lists or download the trust list. A VerificationBundle carries
+// all of it in one JSON artifact signed by the bundle publisher: the issuer DID documents, a
+// snapshot of the status lists, the operator-signed trust list and the credential schemas
+// the verifier accepts. The verifier pins the publisher key, loads the bundle once and then
+// verifies presentations against it alone.
+// Freshness is part of the signed metadata: a bundle is valid from issued until validUntil,
+// and its status snapshot is only trusted for maxStatusAge seconds after it was taken, so a
+// verifier holding an old bundle rejects presentations instead of accepting revoked
+// credentials.
+
+use crate::crypto::rng::RngHealthError;
+use crate::utils::time;
+use crate::vc::credential::{Credential, Proof};
+use crate::vc::did::{split_did_url, DidDocument, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::policy::{PolicyDecision, ProofMetadata, SchemaPolicy, VerificationPolicy};
+use crate::vc::presentation::Presentation;
+use crate::vc::status::{StatusList, StatusListSource};
+use crate::vc::trust_registry::TrustRegistry;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Validity of an exported bundle unless changed before signing
+pub const DEFAULT_BUNDLE_VALIDITY: u64 = 7 * 24 * 60 * 60;
+
+// Age after which a status snapshot is no longer trusted, unless changed before signing
+pub const DEFAULT_MAX_STATUS_AGE: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+  Malformed,
+  Unsigned,
+  InvalidSignature, // Not signed by the pinned publisher key, or altered since
+  NotYetValid,
+  Expired,
+  StaleStatus, // The status snapshot is older than maxStatusAge, or dated after the bundle
+  InvalidTrustList, // The trust list is not signed by a bundled key of its operator
+}
+
+impl BundleError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      BundleError::Malformed => "malformed",
+      BundleError::Unsigned => "unsigned",
+      BundleError::InvalidSignature => "invalid_signature",
+      BundleError::NotYetValid => "not_yet_valid",
+      BundleError::Expired => "expired",
+      BundleError::StaleStatus => "stale_status",
+      BundleError::InvalidTrustList => "invalid_trust_list",
+    }
+  }
+}
+
+// Status lists as published at one point in time, keyed by URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusSnapshot {
+  pub as_of: String, // RFC 3339 timestamp
+  pub lists: BTreeMap<String, String>, // StatusList::encode form
+}
+
+impl StatusSnapshot {
+  // Fetches every listed status list from source; None if any of them is unavailable
+  pub fn capture<S: StatusListSource + ?Sized>(source: &S, urls: &[&str], now: u64) -> Option<Self> {
+    let lists = urls.iter().map(|url| Some((url.to_string(), source.fetch(url)?.encode()))).collect::<Option<_>>()?;
+    Some(Self {
+      as_of: time::format_rfc3339(now),
+      lists,
+    })
+  }
+}
+
+impl StatusListSource for StatusSnapshot {
+  fn fetch(&self, url: &str) -> Option<StatusList> {
+    StatusList::decode(self.lists.get(url)?)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationBundle {
+  pub issued: String, // RFC 3339 timestamp
+  pub valid_until: String, // RFC 3339 timestamp
+  pub max_status_age: u64, // Seconds
+  pub issuer_keys: Vec<DidDocument>, // Issuer and trust list operator documents
+  pub status: StatusSnapshot,
+  pub trust_list: TrustRegistry,
+  pub schemas: Vec<SchemaPolicy>, // Accepted credential shapes; empty accepts any
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Proof>,
+}
+
+impl VerificationBundle {
+  // Gathers the verification material into an unsigned bundle issued now with the default
+  // validity and status age; sign makes it the artifact verifiers load
+  pub fn export(issuer_keys: &[DidDocument], status_snapshot: &StatusSnapshot, trust_list: &TrustRegistry, schemas: &[SchemaPolicy]) -> Self {
+    let now = time::now_unix();
+    Self {
+      issued: time::format_rfc3339(now),
+      valid_until: time::format_rfc3339(now + DEFAULT_BUNDLE_VALIDITY),
+      max_status_age: DEFAULT_MAX_STATUS_AGE,
+      issuer_keys: issuer_keys.to_vec(),
+      status: status_snapshot.clone(),
+      trust_list: trust_list.clone(),
+      schemas: schemas.to_vec(),
+      proof: None,
+    }
+  }
+
+  // JSON payload covered by the publisher signature: the bundle without its proof
+  pub fn signing_payload(&self) -> Value {
+    let mut unsigned = self.clone();
+    unsigned.proof = None;
+    serde_json::to_value(&unsigned).expect("Verification bundle serialization failed")
+  }
+
+  pub fn sign(&mut self, sk: u128, verification_method: &str) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign(sk, verification_method, &self.signing_payload())?);
+    Ok(())
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).expect("Verification bundle serialization failed")
+  }
+
+  // Parses a bundle and checks its signature under the pinned publisher key, its freshness
+  // at now and the trust list signature
+  pub fn load(json: &str, publisher_pk: &[u8], now: u64) -> Result<Self, BundleError> {
+    let bundle: VerificationBundle = serde_json::from_str(json).map_err(|_| BundleError::Malformed)?;
+    let proof = bundle.proof.as_ref().ok_or(BundleError::Unsigned)?;
+    if !proof.verify(publisher_pk, &bundle.signing_payload()) {
+      return Err(BundleError::InvalidSignature);
+    }
+    bundle.check_freshness(now)?;
+
+    let trust_proof = bundle.trust_list.proof.as_ref().ok_or(BundleError::InvalidTrustList)?;
+    let operator_pk = match split_did_url(&trust_proof.verification_method).0 == bundle.trust_list.operator {
+      true => bundle.resolve_key_for(&trust_proof.verification_method, KeyUsage::DELEGATION),
+      false => None,
+    };
+    if !operator_pk.is_some_and(|pk| bundle.trust_list.verify(&pk)) {
+      return Err(BundleError::InvalidTrustList);
+    }
+    Ok(bundle)
+  }
+
+  // Whether the bundle and its status snapshot may still be relied on at now
+  pub fn check_freshness(&self, now: u64) -> Result<(), BundleError> {
+    let issued = time::parse_rfc3339(&self.issued).ok_or(BundleError::Malformed)?;
+    let valid_until = time::parse_rfc3339(&self.valid_until).ok_or(BundleError::Malformed)?;
+    let as_of = time::parse_rfc3339(&self.status.as_of).ok_or(BundleError::Malformed)?;
+    if now < issued {
+      return Err(BundleError::NotYetValid);
+    }
+    if now >= valid_until {
+      return Err(BundleError::Expired);
+    }
+    if as_of > issued || now - as_of > self.max_status_age {
+      return Err(BundleError::StaleStatus);
+    }
+    Ok(())
+  }
+
+  // Verifies a presentation with the bundle as the only source: the holder signature and
+  // challenge, then every credential's signature, expiry and status, its issuer's
+  // accreditation and the bundled schemas
+  pub fn verify_presentation(&self, presentation: &Presentation, holder_pk: &[u8], challenge: &str, now: u64) -> PolicyDecision {
+    if let Err(error) = self.check_freshness(now) {
+      return PolicyDecision::deny("bundle", &format!("bundle cannot be relied on: {}", error.code()));
+    }
+    if presentation.challenge != challenge || !presentation.verify(holder_pk) {
+      return PolicyDecision::deny("holder", "holder signature or challenge check failed");
+    }
+    let policy = |credential: &Credential, proof: &ProofMetadata| self.trust_list.evaluate(credential, proof).and(self.check_schemas(credential));
+    presentation
+      .verifiable_credential
+      .iter()
+      .fold(PolicyDecision::allow(), |decision, credential| decision.and(credential.verify_with_policy(self, &self.status, &policy, now)))
+  }
+
+  // A credential must have the shape of at least one bundled schema
+  fn check_schemas(&self, credential: &Credential) -> PolicyDecision {
+    let unused = ProofMetadata {
+      proof_type: String::new(),
+      verification_method: String::new(),
+      controller: String::new(),
+      verified_at: 0,
+    };
+    if self.schemas.is_empty() || self.schemas.iter().any(|schema| schema.evaluate(credential, &unused).is_allowed()) {
+      PolicyDecision::allow()
+    } else {
+      PolicyDecision::deny("schema", "credential matches no bundled schema")
+    }
+  }
+}
+
+// The bundled issuer documents are the bundle's DID resolution
+impl DidResolver for VerificationBundle {
+  fn resolve(&self, did: &str) -> Option<DidDocument> {
+    self.issuer_keys.iter().find(|document| document.id == did).cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::status::{CredentialStatus, InMemoryStatusLists};
+  use crate::vc::trust_registry::Accreditation;
+  use serde_json::json;
+
+  const OPERATOR: &str = "did:example:registry";
+  const ISSUER: &str = "did:example:ministry";
+  const STATUS_URL: &str = "https://ministry.example/status/1";
+
+  struct Setup {
+    bundle_json: String,
+    publisher_pk: Vec<u8>,
+    passport: Presentation,
+    revoked: Presentation,
+    holder_pk: Vec<u8>,
+    now: u64,
+  }
+
+  fn setup() -> Setup {
+    let now = time::now_unix();
+    let keys: Vec<_> = (0..4).map(|_| Loquat::keygen()).collect();
+    let mut documents = vec![];
+    for (did, keypair) in [OPERATOR, ISSUER].iter().zip(&keys) {
+      let mut document = DidDocument::new(did);
+      document.add_key("key-1", &keypair.public_key);
+      documents.push(document);
+    }
+    let mut trust_list = TrustRegistry::new("https://registry.example/issuers", OPERATOR);
+    trust_list.insert(ISSUER, Accreditation::new(&["Passport"]));
+    trust_list.sign(keys[0].secret_key, &format!("{}#key-1", OPERATOR)).unwrap();
+
+    let mut lists = InMemoryStatusLists::new();
+    lists.insert(STATUS_URL, StatusList::new(16));
+    lists.get_mut(STATUS_URL).unwrap().set_revoked(9, true);
+    let snapshot = StatusSnapshot::capture(&lists, &[STATUS_URL], now).unwrap();
+
+    let schema = SchemaPolicy::new(&["Passport"], &["name", "nationality"]);
+    let mut bundle = VerificationBundle::export(&documents, &snapshot, &trust_list, &[schema]);
+    bundle.sign(keys[2].secret_key, "did:example:border-agency#key-1").unwrap();
+
+    let present = |status_index: usize| {
+      let mut credential = Credential::new(ISSUER, json!({"id": "did:example:alice", "name": "Alice", "nationality": "NL"}));
+      credential.types.push("Passport".to_string());
+      credential.credential_status = Some(CredentialStatus::new(STATUS_URL, status_index));
+      credential.sign(keys[1].secret_key, &format!("{}#key-1", ISSUER)).unwrap();
+      let mut presentation = Presentation::new("did:example:alice", vec![credential], "gate-7");
+      presentation.sign(keys[3].secret_key, "did:example:alice#key-1").unwrap();
+      presentation
+    };
+    Setup {
+      bundle_json: bundle.to_json(),
+      publisher_pk: keys[2].public_key.clone(),
+      passport: present(3),
+      revoked: present(9),
+      holder_pk: keys[3].public_key.clone(),
+      now,
+    }
+  }
+
+  #[test]
+  fn test_offline_presentation_verification() {
+    let setup = setup();
+    let bundle = VerificationBundle::load(&setup.bundle_json, &setup.publisher_pk, setup.now).unwrap();
+    assert!(bundle.verify_presentation(&setup.passport, &setup.holder_pk, "gate-7", setup.now).is_allowed());
+    assert!(!bundle.verify_presentation(&setup.passport, &setup.holder_pk, "gate-8", setup.now).is_allowed());
+    assert!(!bundle.verify_presentation(&setup.revoked, &setup.holder_pk, "gate-7", setup.now).is_allowed());
+
+    // Credentials outside the bundled schemas are denied even from an accredited issuer
+    let mut schemaless = bundle.clone();
+    schemaless.schemas = vec![SchemaPolicy::new(&["Passport"], &["birthDate"])];
+    let decision = schemaless.verify_presentation(&setup.passport, &setup.holder_pk, "gate-7", setup.now);
+    assert_eq!(decision.violations[0].rule, "schema");
+  }
+
+  #[test]
+  fn test_bundle_freshness_and_integrity() {
+    let setup = setup();
+    let load = |json: &str, now: u64| VerificationBundle::load(json, &setup.publisher_pk, now).err();
+    assert_eq!(load(&setup.bundle_json, setup.now - 60), Some(BundleError::NotYetValid));
+    assert_eq!(load(&setup.bundle_json, setup.now + DEFAULT_MAX_STATUS_AGE + 1), Some(BundleError::StaleStatus));
+    assert_eq!(load(&setup.bundle_json, setup.now + DEFAULT_BUNDLE_VALIDITY), Some(BundleError::Expired));
+    assert_eq!(VerificationBundle::load(&setup.bundle_json, &setup.holder_pk, setup.now).err(), Some(BundleError::InvalidSignature));
+
+    // Un-revoking a credential in the snapshot breaks the publisher signature
+    let mut bundle = VerificationBundle::load(&setup.bundle_json, &setup.publisher_pk, setup.now).unwrap();
+    bundle.status.lists.insert(STATUS_URL.to_string(), StatusList::new(16).encode());
+    assert_eq!(load(&bundle.to_json(), setup.now), Some(BundleError::InvalidSignature));
+
+    // A loaded bundle stops accepting presentations once it goes stale
+    let bundle = VerificationBundle::load(&setup.bundle_json, &setup.publisher_pk, setup.now).unwrap();
+    let later = setup.now + DEFAULT_MAX_STATUS_AGE + 1;
+    assert_eq!(bundle.verify_presentation(&setup.passport, &setup.holder_pk, "gate-7", later).violations[0].rule, "bundle");
+  }
+}
@@ -0,0 +1,372 @@
+// OpenID for Verifiable Credential Issuance (credential issuer side)
+// Covers the three issuer-side steps of the flow:
+// - building and parsing credential offers (by value, or as an openid-credential-offer URI)
+// - validating the wallet's proof-of-possession JWT against the issuer's c_nonce
+// - answering the credential request with a Loquat-signed credential and a fresh c_nonce
+
+use crate::utils::encoding::{Encoding, Redacted};
+use crate::utils::time;
+use crate::vc::challenge::{ChallengeManager, ChallengeStore};
+use crate::vc::credential::Credential;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::jwt::Jwt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+// URI scheme of credential offers passed by reference to a wallet
+pub const CREDENTIAL_OFFER_SCHEME: &str = "openid-credential-offer://";
+
+// Required typ header of proof-of-possession JWTs
+pub const PROOF_JWT_TYPE: &str = "openid4vci-proof+jwt";
+
+// Credential format identifier for Loquat-signed JSON credentials
+pub const LOQUAT_VC_FORMAT: &str = "ldp_vc";
+
+// Errors returned to the wallet, mapped onto the OpenID4VCI error codes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Oid4vciError {
+  InvalidOffer,
+  InvalidCredentialRequest,
+  UnsupportedCredentialType,
+  UnsupportedCredentialFormat,
+  InvalidProof,
+  InvalidNonce,
+  SigningRefused, // The issuer cannot sign after a failed randomness selftest
+}
+
+impl Oid4vciError {
+  // Error code sent in the credential endpoint's error response
+  pub fn code(&self) -> &'static str {
+    match self {
+      Oid4vciError::InvalidOffer => "invalid_request",
+      Oid4vciError::InvalidCredentialRequest => "invalid_credential_request",
+      Oid4vciError::UnsupportedCredentialType => "unsupported_credential_type",
+      Oid4vciError::UnsupportedCredentialFormat => "unsupported_credential_format",
+      Oid4vciError::InvalidProof => "invalid_proof",
+      Oid4vciError::InvalidNonce => "invalid_nonce",
+      Oid4vciError::SigningRefused => "server_error",
+    }
+  }
+}
+
+// Credential offer sent from the issuer to the wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialOffer {
+  pub credential_issuer: String,
+  pub credential_configuration_ids: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub grants: Option<Value>,
+}
+
+impl CredentialOffer {
+  // Parses an offer passed by value as JSON
+  pub fn from_json(json: &str) -> Result<Self, Oid4vciError> {
+    let offer: Self = serde_json::from_str(json).map_err(|_| Oid4vciError::InvalidOffer)?;
+    if offer.credential_issuer.is_empty() || offer.credential_configuration_ids.is_empty() {
+      return Err(Oid4vciError::InvalidOffer);
+    }
+    Ok(offer)
+  }
+
+  // Parses an openid-credential-offer://?credential_offer=... URI
+  pub fn from_uri(uri: &str) -> Result<Self, Oid4vciError> {
+    let query = uri
+      .strip_prefix(CREDENTIAL_OFFER_SCHEME)
+      .and_then(|rest| rest.split_once('?'))
+      .map(|(_, query)| query)
+      .ok_or(Oid4vciError::InvalidOffer)?;
+
+    let value = query
+      .split('&')
+      .find_map(|pair| pair.strip_prefix("credential_offer="))
+      .ok_or(Oid4vciError::InvalidOffer)?;
+    Self::from_json(&Encoding::percent_decode(value).ok_or(Oid4vciError::InvalidOffer)?)
+  }
+
+  // Serializes the offer as an openid-credential-offer URI
+  pub fn to_uri(&self) -> String {
+    let json = serde_json::to_string(self).expect("Offer serialization failed");
+    format!("{}?credential_offer={}", CREDENTIAL_OFFER_SCHEME, Encoding::percent_encode(&json))
+  }
+}
+
+// Proof of possession attached to a credential request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialRequestProof {
+  pub proof_type: String, // Only "jwt" is supported
+  pub jwt: String,
+}
+
+// Credential request sent by the wallet to the credential endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialRequest {
+  pub format: String,
+  pub credential_configuration_id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<CredentialRequestProof>,
+}
+
+// Credential endpoint response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialResponse {
+  pub credential: Credential,
+  pub c_nonce: String, // Nonce the wallet must use in its next proof
+  pub c_nonce_expires_in: u64,
+}
+
+// Validates a proof-of-possession JWT and returns the holder key it was signed with
+// Checks the typ header, the audience (credential issuer identifier), the issuance time
+// window, the c_nonce and the signature under the key named by the kid header
+pub fn validate_proof_jwt<R, S>(
+  token: &str,
+  credential_issuer: &str,
+  nonces: &mut ChallengeManager<S>,
+  resolver: &R,
+  max_age_secs: u64,
+  now: u64,
+) -> Result<String, Oid4vciError>
+where
+  R: DidResolver + ?Sized,
+  S: ChallengeStore,
+{
+  let jwt = Jwt::parse(token).ok_or(Oid4vciError::InvalidProof)?;
+  if jwt.header.typ.as_deref() != Some(PROOF_JWT_TYPE) {
+    return Err(Oid4vciError::InvalidProof);
+  }
+  let kid = jwt.header.kid.clone().ok_or(Oid4vciError::InvalidProof)?;
+
+  if jwt.claim_str("aud") != Some(credential_issuer) {
+    return Err(Oid4vciError::InvalidProof);
+  }
+  let iat = jwt.claim_u64("iat").ok_or(Oid4vciError::InvalidProof)?;
+  if iat > now || now - iat > max_age_secs {
+    return Err(Oid4vciError::InvalidProof);
+  }
+
+  let holder_pk = resolver.resolve_key_for(&kid, KeyUsage::PRESENTATION).ok_or(Oid4vciError::InvalidProof)?;
+  if !jwt.verify(&holder_pk) {
+    return Err(Oid4vciError::InvalidProof);
+  }
+
+  // The nonce is consumed only after the signature checks out
+  let nonce = jwt.claim_str("nonce").ok_or(Oid4vciError::InvalidNonce)?;
+  nonces.consume(nonce, now).map_err(|_| Oid4vciError::InvalidNonce)?;
+
+  Ok(kid)
+}
+
+// Credential issuer configuration
+pub struct CredentialIssuer {
+  pub credential_issuer: String, // Issuer identifier URL used as the proof audience
+  pub issuer_did: String,
+  pub verification_method: String,
+  pub supported_configurations: Vec<String>,
+  pub proof_max_age_secs: u64,
+  secret_key: u128,
+}
+
+impl fmt::Debug for CredentialIssuer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("CredentialIssuer")
+      .field("credential_issuer", &self.credential_issuer)
+      .field("issuer_did", &self.issuer_did)
+      .field("verification_method", &self.verification_method)
+      .field("supported_configurations", &self.supported_configurations)
+      .field("proof_max_age_secs", &self.proof_max_age_secs)
+      .field("secret_key", &Redacted)
+      .finish()
+  }
+}
+
+impl CredentialIssuer {
+  pub fn new(credential_issuer: &str, verification_method: &str, secret_key: u128, supported_configurations: Vec<String>) -> Self {
+    Self {
+      credential_issuer: credential_issuer.to_string(),
+      issuer_did: split_did_url(verification_method).0.to_string(),
+      verification_method: verification_method.to_string(),
+      supported_configurations,
+      proof_max_age_secs: 300,
+      secret_key,
+    }
+  }
+
+  // Builds an offer for some of the supported credential configurations
+  pub fn offer(&self, configuration_ids: &[&str], grants: Option<Value>) -> CredentialOffer {
+    CredentialOffer {
+      credential_issuer: self.credential_issuer.clone(),
+      credential_configuration_ids: configuration_ids.iter().map(|id| id.to_string()).collect(),
+      grants,
+    }
+  }
+
+  // Handles a credential request: validates it and the proof of possession, then issues a
+  // credential binding the claims to the holder DID and hands out a fresh c_nonce
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(configuration = %request.credential_configuration_id), err(Debug)))]
+  pub fn handle_request<R, S>(
+    &self,
+    request: &CredentialRequest,
+    claims: Value,
+    nonces: &mut ChallengeManager<S>,
+    resolver: &R,
+    now: u64,
+  ) -> Result<CredentialResponse, Oid4vciError>
+  where
+    R: DidResolver + ?Sized,
+    S: ChallengeStore,
+  {
+    if request.format != LOQUAT_VC_FORMAT {
+      return Err(Oid4vciError::UnsupportedCredentialFormat);
+    }
+    if !self.supported_configurations.contains(&request.credential_configuration_id) {
+      return Err(Oid4vciError::UnsupportedCredentialType);
+    }
+    let proof = request.proof.as_ref().ok_or(Oid4vciError::InvalidProof)?;
+    if proof.proof_type != "jwt" {
+      return Err(Oid4vciError::InvalidProof);
+    }
+
+    let holder_kid = validate_proof_jwt(&proof.jwt, &self.credential_issuer, nonces, resolver, self.proof_max_age_secs, now)?;
+
+    let mut subject = match claims {
+      Value::Object(map) => map,
+      _ => return Err(Oid4vciError::InvalidCredentialRequest),
+    };
+    subject.insert("id".to_string(), Value::String(split_did_url(&holder_kid).0.to_string()));
+
+    let mut credential = Credential::new(&self.issuer_did, Value::Object(subject));
+    credential.issuance_date = time::format_rfc3339(now);
+    credential.types.push(request.credential_configuration_id.clone());
+    credential.sign(self.secret_key, &self.verification_method).map_err(|_| Oid4vciError::SigningRefused)?;
+
+    let c_nonce = nonces.issue_at(now);
+    Ok(CredentialResponse {
+      credential,
+      c_nonce: c_nonce.value,
+      c_nonce_expires_in: c_nonce.expires_at - now,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::jwt::JwtHeader;
+  use serde_json::json;
+
+  const ISSUER_URL: &str = "https://issuer.example";
+
+  struct Fixture {
+    issuer: CredentialIssuer,
+    issuer_pk: Vec<u8>,
+    holder_sk: u128,
+    holder_kid: String,
+    resolver: StaticDidResolver,
+    nonces: ChallengeManager,
+  }
+
+  fn fixture() -> Fixture {
+    let issuer_keys = Loquat::keygen();
+    let holder_keys = Loquat::keygen();
+    let mut resolver = StaticDidResolver::new();
+
+    let mut issuer_doc = DidDocument::new("did:example:issuer");
+    let issuer_kid = issuer_doc.add_key("key-1", &issuer_keys.public_key);
+    resolver.insert(issuer_doc);
+    let mut holder_doc = DidDocument::new("did:example:wallet");
+    let holder_kid = holder_doc.add_key("key-1", &holder_keys.public_key);
+    resolver.insert(holder_doc);
+
+    Fixture {
+      issuer: CredentialIssuer::new(ISSUER_URL, &issuer_kid, issuer_keys.secret_key, vec!["UniversityDegree".to_string()]),
+      issuer_pk: issuer_keys.public_key,
+      holder_sk: holder_keys.secret_key,
+      holder_kid,
+      resolver,
+      nonces: ChallengeManager::new(300),
+    }
+  }
+
+  fn proof_jwt(f: &Fixture, aud: &str, nonce: &str, iat: u64) -> String {
+    let header = JwtHeader::new(Some(PROOF_JWT_TYPE), Some(&f.holder_kid));
+    Jwt::sign(&header, &json!({"aud": aud, "nonce": nonce, "iat": iat}), f.holder_sk).unwrap()
+  }
+
+  fn request(jwt: String) -> CredentialRequest {
+    CredentialRequest {
+      format: LOQUAT_VC_FORMAT.to_string(),
+      credential_configuration_id: "UniversityDegree".to_string(),
+      proof: Some(CredentialRequestProof { proof_type: "jwt".to_string(), jwt }),
+    }
+  }
+
+  #[test]
+  fn test_offer_uri_round_trip() {
+    let f = fixture();
+    let offer = f.issuer.offer(&["UniversityDegree"], Some(json!({"urn:ietf:params:oauth:grant-type:pre-authorized_code": {"pre-authorized_code": "abc"}})));
+    let uri = offer.to_uri();
+    assert!(uri.starts_with(CREDENTIAL_OFFER_SCHEME));
+    assert_eq!(CredentialOffer::from_uri(&uri), Ok(offer));
+
+    assert_eq!(CredentialOffer::from_uri("https://example.com/?credential_offer=%7B%7D"), Err(Oid4vciError::InvalidOffer));
+    assert_eq!(CredentialOffer::from_json(r#"{"credential_issuer": "x", "credential_configuration_ids": []}"#), Err(Oid4vciError::InvalidOffer));
+  }
+
+  #[test]
+  fn test_issuance_flow() {
+    let mut f = fixture();
+    let nonce = f.nonces.issue_at(1_000);
+    let jwt = proof_jwt(&f, ISSUER_URL, &nonce.value, 1_000);
+
+    let response = f.issuer.handle_request(&request(jwt.clone()), json!({"degree": "BSc"}), &mut f.nonces, &f.resolver, 1_010).unwrap();
+    assert!(response.credential.verify(&f.issuer_pk));
+    assert_eq!(response.credential.credential_subject["id"], "did:example:wallet");
+    assert_eq!(response.credential.credential_subject["degree"], "BSc");
+    assert_eq!(response.c_nonce_expires_in, 300);
+
+    // The c_nonce was consumed, so replaying the same proof fails
+    let replay = f.issuer.handle_request(&request(jwt), json!({"degree": "BSc"}), &mut f.nonces, &f.resolver, 1_020);
+    assert_eq!(replay, Err(Oid4vciError::InvalidNonce));
+
+    // The fresh c_nonce from the response is accepted
+    let jwt = proof_jwt(&f, ISSUER_URL, &response.c_nonce, 1_020);
+    assert!(f.issuer.handle_request(&request(jwt), json!({"degree": "MSc"}), &mut f.nonces, &f.resolver, 1_030).is_ok());
+  }
+
+  #[test]
+  fn test_invalid_proofs_rejected() {
+    let mut f = fixture();
+    let nonce = f.nonces.issue_at(1_000);
+
+    let wrong_audience = proof_jwt(&f, "https://other.example", &nonce.value, 1_000);
+    let stale = proof_jwt(&f, ISSUER_URL, &nonce.value, 100);
+    let future = proof_jwt(&f, ISSUER_URL, &nonce.value, 5_000);
+    for jwt in [wrong_audience, stale, future] {
+      let result = f.issuer.handle_request(&request(jwt), json!({}), &mut f.nonces, &f.resolver, 1_010);
+      assert_eq!(result, Err(Oid4vciError::InvalidProof));
+    }
+
+    // A proof signed by a key other than the kid's is rejected without burning the nonce
+    let header = JwtHeader::new(Some(PROOF_JWT_TYPE), Some(&f.holder_kid));
+    let forged = Jwt::sign(&header, &json!({"aud": ISSUER_URL, "nonce": nonce.value, "iat": 1_000}), Loquat::keygen().secret_key).unwrap();
+    assert_eq!(f.issuer.handle_request(&request(forged), json!({}), &mut f.nonces, &f.resolver, 1_010), Err(Oid4vciError::InvalidProof));
+    let genuine = proof_jwt(&f, ISSUER_URL, &nonce.value, 1_000);
+    assert!(f.issuer.handle_request(&request(genuine), json!({}), &mut f.nonces, &f.resolver, 1_010).is_ok());
+  }
+
+  #[test]
+  fn test_unsupported_requests() {
+    let mut f = fixture();
+    let nonce = f.nonces.issue_at(1_000);
+    let mut bad_format = request(proof_jwt(&f, ISSUER_URL, &nonce.value, 1_000));
+    bad_format.format = "mso_mdoc".to_string();
+    assert_eq!(f.issuer.handle_request(&bad_format, json!({}), &mut f.nonces, &f.resolver, 1_010).unwrap_err().code(), "unsupported_credential_format");
+
+    let mut bad_type = request(proof_jwt(&f, ISSUER_URL, &nonce.value, 1_000));
+    bad_type.credential_configuration_id = "DriverLicense".to_string();
+    assert_eq!(f.issuer.handle_request(&bad_type, json!({}), &mut f.nonces, &f.resolver, 1_010), Err(Oid4vciError::UnsupportedCredentialType));
+  }
+}
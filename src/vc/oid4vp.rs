@@ -0,0 +1,331 @@
+// OpenID for Verifiable Presentations (verifier side)
+// The verifier sends an authorization request carrying a presentation definition and a
+// single-use nonce; the wallet answers with a vp_token (a holder-signed presentation bound
+// to the nonce) and a presentation submission.
+
+use crate::utils::encoding::Encoding;
+use crate::vc::challenge::{ChallengeError, ChallengeManager, ChallengeStore, InMemoryChallengeStore};
+use crate::vc::credential::Credential;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::policy::{proof_metadata, PolicyDecision, VerificationPolicy};
+use crate::vc::presentation::{Presentation, LOQUAT_BOUND_PROOF_TYPE};
+use crate::vc::presentation_exchange::{PresentationDefinition, PresentationSubmission};
+use crate::vc::status::StatusListSource;
+use serde::{Deserialize, Serialize};
+
+// URI scheme used to hand authorization requests to a wallet
+pub const AUTHORIZATION_REQUEST_SCHEME: &str = "openid4vp://";
+
+// Response type requesting a presentation
+pub const VP_TOKEN_RESPONSE_TYPE: &str = "vp_token";
+
+// Errors reported by the verifier, mapped onto the OpenID4VP error codes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Oid4vpError {
+  InvalidRequest,
+  InvalidSubmission, // The submission does not satisfy the presentation definition
+  InvalidHolderProof, // Presentation proof missing, not signed by the holder, or not bound to the nonce
+  InvalidCredential, // An embedded credential failed signature, expiry or status checks
+  InvalidNonce, // Nonce unknown, already used or expired
+  PolicyRejected(PolicyDecision), // A verified credential was rejected by the verifier's policy
+}
+
+impl Oid4vpError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      Oid4vpError::InvalidRequest => "invalid_request",
+      Oid4vpError::InvalidSubmission => "invalid_presentation_definition_reference",
+      Oid4vpError::InvalidHolderProof | Oid4vpError::InvalidCredential
+      | Oid4vpError::InvalidNonce
+      | Oid4vpError::PolicyRejected(_) => "access_denied",
+    }
+  }
+}
+
+// Authorization request sent from the verifier to the wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+  pub response_type: String,
+  pub client_id: String,
+  pub nonce: String,
+  pub presentation_definition: PresentationDefinition,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub response_uri: Option<String>,
+}
+
+impl AuthorizationRequest {
+  // Serializes the request as an openid4vp:// URI with the definition passed by value
+  pub fn to_uri(&self) -> String {
+    let definition = serde_json::to_string(&self.presentation_definition).expect("Definition serialization failed");
+    let mut uri = format!(
+      "{}?response_type={}&client_id={}&nonce={}&presentation_definition={}",
+      AUTHORIZATION_REQUEST_SCHEME,
+      Encoding::percent_encode(&self.response_type),
+      Encoding::percent_encode(&self.client_id),
+      Encoding::percent_encode(&self.nonce),
+      Encoding::percent_encode(&definition),
+    );
+    if let Some(response_uri) = &self.response_uri {
+      uri.push_str(&format!("&response_uri={}", Encoding::percent_encode(response_uri)));
+    }
+    uri
+  }
+
+  // Parses an openid4vp:// URI produced by to_uri
+  pub fn from_uri(uri: &str) -> Result<Self, Oid4vpError> {
+    let query = uri
+      .strip_prefix(AUTHORIZATION_REQUEST_SCHEME)
+      .and_then(|rest| rest.split_once('?'))
+      .map(|(_, query)| query)
+      .ok_or(Oid4vpError::InvalidRequest)?;
+
+    let param = |name: &str| -> Option<String> {
+      query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+        .and_then(Encoding::percent_decode)
+    };
+
+    let response_type = param("response_type").ok_or(Oid4vpError::InvalidRequest)?;
+    if response_type != VP_TOKEN_RESPONSE_TYPE {
+      return Err(Oid4vpError::InvalidRequest);
+    }
+    let definition = param("presentation_definition").ok_or(Oid4vpError::InvalidRequest)?;
+    Ok(Self {
+      response_type,
+      client_id: param("client_id").ok_or(Oid4vpError::InvalidRequest)?,
+      nonce: param("nonce").ok_or(Oid4vpError::InvalidRequest)?,
+      presentation_definition: PresentationDefinition::from_json(&definition).ok_or(Oid4vpError::InvalidRequest)?,
+      response_uri: param("response_uri"),
+    })
+  }
+}
+
+// Wallet response to an authorization request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizationResponse {
+  pub vp_token: Presentation,
+  pub presentation_submission: PresentationSubmission,
+}
+
+impl AuthorizationResponse {
+  // Wallet side: selects credentials for the request and signs a presentation bound to its
+  // nonce and to the requesting client_id
+  pub fn create(
+    request: &AuthorizationRequest,
+    credentials: &[Credential],
+    holder: &str,
+    sk: u128,
+    verification_method: &str,
+  ) -> Option<Self> {
+    let (mut vp_token, presentation_submission) =
+      request.presentation_definition.present(credentials, holder, &request.nonce, sk, verification_method)?;
+    vp_token.sign_for_audience(sk, verification_method, &request.client_id).ok()?;
+    Some(Self { vp_token, presentation_submission })
+  }
+}
+
+// Verifier state: its client identifier, the outstanding request nonces and an optional
+// policy consulted for every credential that passes the cryptographic checks
+pub struct PresentationVerifier<S: ChallengeStore = InMemoryChallengeStore> {
+  pub client_id: String,
+  nonces: ChallengeManager<S>,
+  policy: Option<Box<dyn VerificationPolicy>>,
+}
+
+impl PresentationVerifier<InMemoryChallengeStore> {
+  pub fn new(client_id: &str, nonce_ttl_secs: u64) -> Self {
+    Self::with_nonces(client_id, ChallengeManager::new(nonce_ttl_secs))
+  }
+}
+
+impl<S: ChallengeStore> PresentationVerifier<S> {
+  pub fn with_nonces(client_id: &str, nonces: ChallengeManager<S>) -> Self {
+    Self {
+      client_id: client_id.to_string(),
+      nonces,
+      policy: None,
+    }
+  }
+
+  // Applies a policy (e.g. a trust registry) to every embedded credential
+  pub fn with_policy<P: VerificationPolicy + 'static>(mut self, policy: P) -> Self {
+    self.policy = Some(Box::new(policy));
+    self
+  }
+
+  // Creates a request for the given definition with a fresh nonce
+  pub fn authorization_request(&mut self, definition: PresentationDefinition, now: u64) -> AuthorizationRequest {
+    AuthorizationRequest {
+      response_type: VP_TOKEN_RESPONSE_TYPE.to_string(),
+      client_id: self.client_id.clone(),
+      nonce: self.nonces.issue_at(now).value,
+      presentation_definition: definition,
+      response_uri: None,
+    }
+  }
+
+  // Verifies a wallet response: the submission must satisfy the definition, the presentation
+  // must be signed by a key of the holder DID and bound to the request nonce (which is then
+  // consumed), and every embedded credential must verify against its issuer
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(client_id = %self.client_id), err(Debug)))]
+  pub fn verify_response<R, L>(
+    &mut self,
+    request: &AuthorizationRequest,
+    response: &AuthorizationResponse,
+    resolver: &R,
+    status_lists: &L,
+    now: u64,
+  ) -> Result<(), Oid4vpError>
+  where
+    R: DidResolver + ?Sized,
+    L: StatusListSource + ?Sized,
+  {
+    let presentation = &response.vp_token;
+    if !request.presentation_definition.verify_submission(&response.presentation_submission, presentation) {
+      return Err(Oid4vpError::InvalidSubmission);
+    }
+
+    let proof = presentation.proof.as_ref().ok_or(Oid4vpError::InvalidHolderProof)?;
+    if presentation.challenge != request.nonce || split_did_url(&proof.verification_method).0 != presentation.holder {
+      return Err(Oid4vpError::InvalidHolderProof);
+    }
+    let holder_pk = resolver.resolve_key_for(&proof.verification_method, KeyUsage::PRESENTATION).ok_or(Oid4vpError::InvalidHolderProof)?;
+    // Bound proofs must name this verifier; unbound ones from older wallets rely on the nonce
+    let checked = match proof.proof_type.as_str() {
+      LOQUAT_BOUND_PROOF_TYPE => self.nonces.verify_presentation_for(presentation, &holder_pk, &self.client_id, now),
+      _ => self.nonces.verify_presentation(presentation, &holder_pk, now),
+    };
+    checked.map_err(|err| match err {
+      ChallengeError::InvalidPresentation => Oid4vpError::InvalidHolderProof,
+      ChallengeError::Unknown | ChallengeError::Expired => Oid4vpError::InvalidNonce,
+    })?;
+
+    if !presentation.verifiable_credential.iter().all(|credential| credential.verify_with_resolver(resolver, status_lists, now)) {
+      return Err(Oid4vpError::InvalidCredential);
+    }
+    if let Some(policy) = &self.policy {
+      let decision = presentation
+        .verifiable_credential
+        .iter()
+        .fold(PolicyDecision::allow(), |decision, credential| {
+          let proof = proof_metadata(credential, now).expect("Verified credential has a proof");
+          decision.and(policy.evaluate(credential, &proof))
+        });
+      if !decision.is_allowed() {
+        return Err(Oid4vpError::PolicyRejected(decision));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  struct Fixture {
+    resolver: StaticDidResolver,
+    credentials: Vec<Credential>,
+    holder_sk: u128,
+    holder_kid: String,
+  }
+
+  fn fixture() -> Fixture {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let mut resolver = StaticDidResolver::new();
+    let mut issuer_doc = DidDocument::new("did:example:issuer");
+    let issuer_kid = issuer_doc.add_key("key-1", &issuer.public_key);
+    resolver.insert(issuer_doc);
+    let mut holder_doc = DidDocument::new("did:example:alice");
+    let holder_kid = holder_doc.add_key("key-1", &holder.public_key);
+    resolver.insert(holder_doc);
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "age": 21}));
+    credential.sign(issuer.secret_key, &issuer_kid).unwrap();
+    Fixture {
+      resolver,
+      credentials: vec![credential],
+      holder_sk: holder.secret_key,
+      holder_kid,
+    }
+  }
+
+  fn definition() -> PresentationDefinition {
+    PresentationDefinition::from_json(
+      r#"{"id": "adult", "input_descriptors": [{"id": "age", "constraints": {"fields": [
+        {"path": ["$.credentialSubject.age"], "filter": {"type": "integer", "minimum": 18}}
+      ]}}]}"#,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn test_request_uri_round_trip() {
+    let mut verifier = PresentationVerifier::new("https://verifier.example", 300);
+    let mut request = verifier.authorization_request(definition(), 1_000);
+    request.response_uri = Some("https://verifier.example/response".to_string());
+    assert_eq!(AuthorizationRequest::from_uri(&request.to_uri()), Ok(request));
+    assert_eq!(AuthorizationRequest::from_uri("openid4vp://?client_id=x"), Err(Oid4vpError::InvalidRequest));
+  }
+
+  #[test]
+  fn test_presentation_flow() {
+    let f = fixture();
+    let status_lists = InMemoryStatusLists::new();
+    let mut verifier = PresentationVerifier::new("https://verifier.example", 300);
+    let request = verifier.authorization_request(definition(), 1_000);
+
+    let response = AuthorizationResponse::create(&request, &f.credentials, "did:example:alice", f.holder_sk, &f.holder_kid).unwrap();
+    assert_eq!(response.vp_token.proof.as_ref().unwrap().proof_type, LOQUAT_BOUND_PROOF_TYPE);
+    assert_eq!(verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_010), Ok(()));
+
+    // The nonce is single use
+    assert_eq!(verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_020), Err(Oid4vpError::InvalidNonce));
+  }
+
+  #[test]
+  fn test_rejects_foreign_holder_key() {
+    let f = fixture();
+    let status_lists = InMemoryStatusLists::new();
+    let mut verifier = PresentationVerifier::new("https://verifier.example", 300);
+    let request = verifier.authorization_request(definition(), 1_000);
+
+    // Signed with a key that is not in the holder's DID document
+    let mallory = Loquat::keygen();
+    let response = AuthorizationResponse::create(&request, &f.credentials, "did:example:alice", mallory.secret_key, &f.holder_kid).unwrap();
+    assert_eq!(verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_010), Err(Oid4vpError::InvalidHolderProof));
+
+    // A key of another DID does not count as the holder's
+    let response = AuthorizationResponse::create(&request, &f.credentials, "did:example:alice", f.holder_sk, "did:example:issuer#key-1").unwrap();
+    assert_eq!(verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_010), Err(Oid4vpError::InvalidHolderProof));
+
+    // A response the holder made for another verifier, even one reusing this nonce
+    let foreign = AuthorizationRequest { client_id: "https://other.example".to_string(), ..request.clone() };
+    let response = AuthorizationResponse::create(&foreign, &f.credentials, "did:example:alice", f.holder_sk, &f.holder_kid).unwrap();
+    assert_eq!(verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_010), Err(Oid4vpError::InvalidHolderProof));
+  }
+
+  #[test]
+  fn test_policy_consulted_for_credentials() {
+    use crate::vc::policy::IssuerAllowlist;
+
+    let f = fixture();
+    let status_lists = InMemoryStatusLists::new();
+    let mut verifier =
+      PresentationVerifier::new("https://verifier.example", 300).with_policy(IssuerAllowlist::new(&["did:example:other"]));
+    let request = verifier.authorization_request(definition(), 1_000);
+
+    let response = AuthorizationResponse::create(&request, &f.credentials, "did:example:alice", f.holder_sk, &f.holder_kid).unwrap();
+    match verifier.verify_response(&request, &response, &f.resolver, &status_lists, 1_010) {
+      Err(Oid4vpError::PolicyRejected(decision)) => assert_eq!(decision.violations[0].rule, "issuer-allowlist"),
+      other => panic!("unexpected result: {:?}", other),
+    }
+  }
+}
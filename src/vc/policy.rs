@@ -0,0 +1,377 @@
+// Verification policies
+// Cryptographic verification answers "was this signed by that key"; a policy answers
+// "do we accept it". Policies run after the cryptographic checks have passed and see
+// the decoded credential together with the metadata of the proof that verified it.
+// Every policy returns a structured decision so a rejection can be explained.
+
+use crate::utils::time;
+use crate::vc::credential::Credential;
+use crate::vc::did::split_did_url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Metadata of the proof that verified a credential
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofMetadata {
+  pub proof_type: String,
+  pub verification_method: String, // Key that produced the proof
+  pub controller: String, // DID controlling the verification method
+  pub verified_at: u64, // Seconds since the Unix epoch
+}
+
+// A rule a credential failed to satisfy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+  pub rule: String, // Name of the violated rule
+  pub reason: String, // Human-readable explanation
+}
+
+// Outcome of evaluating one or more policies
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyDecision {
+  pub violations: Vec<PolicyViolation>,
+}
+
+impl PolicyDecision {
+  pub fn allow() -> Self {
+    Self::default()
+  }
+
+  pub fn deny(rule: &str, reason: &str) -> Self {
+    Self {
+      violations: vec![PolicyViolation {
+        rule: rule.to_string(),
+        reason: reason.to_string(),
+      }],
+    }
+  }
+
+  pub fn is_allowed(&self) -> bool {
+    self.violations.is_empty()
+  }
+
+  // Combines two decisions, keeping the violations of both
+  pub fn and(mut self, other: PolicyDecision) -> Self {
+    self.violations.extend(other.violations);
+    self
+  }
+}
+
+// Hook evaluated after a credential's proof has been verified
+// Policies are Send + Sync so verifiers holding them can be shared across worker threads
+pub trait VerificationPolicy: Send + Sync {
+  fn evaluate(&self, credential: &Credential, proof: &ProofMetadata) -> PolicyDecision;
+}
+
+// Any closure with the right shape is a policy
+impl<F> VerificationPolicy for F
+where
+  F: Fn(&Credential, &ProofMetadata) -> PolicyDecision + Send + Sync,
+{
+  fn evaluate(&self, credential: &Credential, proof: &ProofMetadata) -> PolicyDecision {
+    self(credential, proof)
+  }
+}
+
+// Accepts credentials only from listed issuer DIDs
+#[derive(Debug, Clone, Default)]
+pub struct IssuerAllowlist {
+  issuers: HashSet<String>,
+}
+
+impl IssuerAllowlist {
+  pub fn new(issuers: &[&str]) -> Self {
+    Self {
+      issuers: issuers.iter().map(|issuer| issuer.to_string()).collect(),
+    }
+  }
+}
+
+impl VerificationPolicy for IssuerAllowlist {
+  fn evaluate(&self, credential: &Credential, _proof: &ProofMetadata) -> PolicyDecision {
+    if self.issuers.contains(&credential.issuer) {
+      PolicyDecision::allow()
+    } else {
+      PolicyDecision::deny("issuer-allowlist", &format!("issuer {} is not allowed", credential.issuer))
+    }
+  }
+}
+
+// Accepts proofs only from listed verification methods, pinning individual keys
+#[derive(Debug, Clone, Default)]
+pub struct TrustList {
+  verification_methods: HashSet<String>,
+}
+
+impl TrustList {
+  pub fn new(verification_methods: &[&str]) -> Self {
+    Self {
+      verification_methods: verification_methods.iter().map(|vm| vm.to_string()).collect(),
+    }
+  }
+}
+
+impl VerificationPolicy for TrustList {
+  fn evaluate(&self, _credential: &Credential, proof: &ProofMetadata) -> PolicyDecision {
+    if self.verification_methods.contains(&proof.verification_method) {
+      PolicyDecision::allow()
+    } else {
+      PolicyDecision::deny("trust-list", &format!("key {} is not trusted", proof.verification_method))
+    }
+  }
+}
+
+// Requires credential types and top-level subject claims
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaPolicy {
+  pub required_types: Vec<String>,
+  pub required_claims: Vec<String>,
+}
+
+impl SchemaPolicy {
+  pub fn new(required_types: &[&str], required_claims: &[&str]) -> Self {
+    Self {
+      required_types: required_types.iter().map(|t| t.to_string()).collect(),
+      required_claims: required_claims.iter().map(|c| c.to_string()).collect(),
+    }
+  }
+}
+
+impl VerificationPolicy for SchemaPolicy {
+  fn evaluate(&self, credential: &Credential, _proof: &ProofMetadata) -> PolicyDecision {
+    let missing_types = self.required_types.iter().filter(|t| !credential.types.contains(t));
+    let missing_claims = self
+      .required_claims
+      .iter()
+      .filter(|claim| credential.credential_subject.get(claim.as_str()).is_none());
+
+    let mut decision = PolicyDecision::allow();
+    for missing in missing_types {
+      decision = decision.and(PolicyDecision::deny("schema", &format!("missing type {}", missing)));
+    }
+    for missing in missing_claims {
+      decision = decision.and(PolicyDecision::deny("schema", &format!("missing claim {}", missing)));
+    }
+    decision
+  }
+}
+
+// Rejects credentials issued too long before verification, or dated in the future
+#[derive(Debug, Clone, Copy)]
+pub struct FreshnessPolicy {
+  pub max_age: u64, // Seconds
+}
+
+impl VerificationPolicy for FreshnessPolicy {
+  fn evaluate(&self, credential: &Credential, proof: &ProofMetadata) -> PolicyDecision {
+    match time::parse_rfc3339(&credential.issuance_date) {
+      Some(issued) if issued > proof.verified_at => PolicyDecision::deny("freshness", "issued in the future"),
+      Some(issued) if proof.verified_at - issued > self.max_age => PolicyDecision::deny("freshness", "credential is too old"),
+      Some(_) => PolicyDecision::allow(),
+      None => PolicyDecision::deny("freshness", "unparseable issuance date"),
+    }
+  }
+}
+
+// Rejects credentials carrying terms of use the verifier does not know how to honour; terms
+// restrict what a verifier may do, so unknown ones cannot be ignored
+#[derive(Debug, Clone, Default)]
+pub struct TermsOfUsePolicy {
+  understood: HashSet<String>, // Terms types the verifier complies with
+}
+
+impl TermsOfUsePolicy {
+  pub fn new(understood: &[&str]) -> Self {
+    Self {
+      understood: understood.iter().map(|t| t.to_string()).collect(),
+    }
+  }
+}
+
+impl VerificationPolicy for TermsOfUsePolicy {
+  fn evaluate(&self, credential: &Credential, _proof: &ProofMetadata) -> PolicyDecision {
+    credential
+      .terms_of_use
+      .iter()
+      .filter(|terms| !self.understood.contains(&terms.terms_type))
+      .fold(PolicyDecision::allow(), |decision, terms| {
+        decision.and(PolicyDecision::deny("terms-of-use", &format!("unsupported terms of use {}", terms.terms_type)))
+      })
+  }
+}
+
+// Requires evidence of every listed type, e.g. an in-person document check
+#[derive(Debug, Clone, Default)]
+pub struct EvidencePolicy {
+  pub required_types: Vec<String>,
+}
+
+impl EvidencePolicy {
+  pub fn new(required_types: &[&str]) -> Self {
+    Self {
+      required_types: required_types.iter().map(|t| t.to_string()).collect(),
+    }
+  }
+}
+
+impl VerificationPolicy for EvidencePolicy {
+  fn evaluate(&self, credential: &Credential, _proof: &ProofMetadata) -> PolicyDecision {
+    self
+      .required_types
+      .iter()
+      .filter(|required| !credential.evidence.iter().any(|evidence| evidence.types.contains(required)))
+      .fold(PolicyDecision::allow(), |decision, missing| decision.and(PolicyDecision::deny("evidence", &format!("missing evidence {}", missing))))
+  }
+}
+
+// Evaluates every member policy and reports all violations
+#[derive(Default)]
+pub struct PolicySet {
+  policies: Vec<Box<dyn VerificationPolicy>>,
+}
+
+impl PolicySet {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with<P: VerificationPolicy + 'static>(mut self, policy: P) -> Self {
+    self.policies.push(Box::new(policy));
+    self
+  }
+}
+
+impl VerificationPolicy for PolicySet {
+  fn evaluate(&self, credential: &Credential, proof: &ProofMetadata) -> PolicyDecision {
+    self
+      .policies
+      .iter()
+      .fold(PolicyDecision::allow(), |decision, policy| decision.and(policy.evaluate(credential, proof)))
+  }
+}
+
+// Metadata of a credential's proof, if it carries one
+pub fn proof_metadata(credential: &Credential, verified_at: u64) -> Option<ProofMetadata> {
+  let proof = credential.proof.as_ref()?;
+  Some(ProofMetadata {
+    proof_type: proof.proof_type.clone(),
+    verification_method: proof.verification_method.clone(),
+    controller: split_did_url(&proof.verification_method).0.to_string(),
+    verified_at,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  fn signed(now: u64) -> (Credential, StaticDidResolver, String) {
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key("key-1", &keypair.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "degree": "BSc"}));
+    credential.types.push("UniversityDegree".to_string());
+    credential.issuance_date = time::format_rfc3339(now);
+    credential.sign(keypair.secret_key, &key_id).unwrap();
+    (credential, resolver, key_id)
+  }
+
+  #[test]
+  fn test_policy_set_collects_violations() {
+    let now = 1_700_000_000;
+    let (credential, resolver, key_id) = signed(now);
+    let status_lists = InMemoryStatusLists::new();
+
+    let accepting = PolicySet::new()
+      .with(IssuerAllowlist::new(&["did:example:issuer"]))
+      .with(TrustList::new(&[&key_id]))
+      .with(SchemaPolicy::new(&["UniversityDegree"], &["degree"]))
+      .with(FreshnessPolicy { max_age: 3600 });
+    assert!(credential.verify_with_policy(&resolver, &status_lists, &accepting, now + 60).is_allowed());
+
+    let rejecting = PolicySet::new()
+      .with(IssuerAllowlist::new(&["did:example:other"]))
+      .with(SchemaPolicy::new(&["DriverLicense"], &["degree", "licenseClass"]))
+      .with(FreshnessPolicy { max_age: 3600 });
+    let decision = credential.verify_with_policy(&resolver, &status_lists, &rejecting, now + 7200);
+    let rules: Vec<&str> = decision.violations.iter().map(|v| v.rule.as_str()).collect();
+    assert_eq!(rules, vec!["issuer-allowlist", "schema", "schema", "freshness"]);
+  }
+
+  #[test]
+  fn test_policy_runs_after_cryptographic_checks() {
+    let now = 1_700_000_000;
+    let (mut credential, resolver, _) = signed(now);
+    let status_lists = InMemoryStatusLists::new();
+    let accept_all = |_: &Credential, _: &ProofMetadata| PolicyDecision::allow();
+
+    credential.credential_subject = json!({"id": "did:example:alice", "degree": "PhD"});
+    let decision = credential.verify_with_policy(&resolver, &status_lists, &accept_all, now);
+    assert_eq!(decision.violations[0].rule, "cryptographic");
+
+    // Closures receive the proof metadata of the verified proof
+    let (credential, resolver, key_id) = signed(now);
+    let pinned = move |_: &Credential, proof: &ProofMetadata| {
+      if proof.verification_method == key_id && proof.controller == "did:example:issuer" {
+        PolicyDecision::allow()
+      } else {
+        PolicyDecision::deny("custom", "unexpected key")
+      }
+    };
+    assert!(credential.verify_with_policy(&resolver, &status_lists, &pinned, now).is_allowed());
+  }
+
+  #[test]
+  fn test_terms_of_use_and_evidence_policies() {
+    use crate::vc::credential::{Evidence, TermsOfUse};
+
+    let now = 1_700_000_000;
+    let keypair = Loquat::keygen();
+    let mut document = DidDocument::new("did:example:issuer");
+    let key_id = document.add_key("key-1", &keypair.public_key);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+    let status_lists = InMemoryStatusLists::new();
+
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.terms_of_use = vec![TermsOfUse::new("IssuerPolicy"), TermsOfUse::new("HolderPolicy")];
+    credential.evidence = vec![Evidence::new(&["DocumentVerification"])];
+    credential.sign(keypair.secret_key, &key_id).unwrap();
+
+    let accepting = PolicySet::new()
+      .with(TermsOfUsePolicy::new(&["IssuerPolicy", "HolderPolicy"]))
+      .with(EvidencePolicy::new(&["DocumentVerification"]));
+    assert!(credential.verify_with_policy(&resolver, &status_lists, &accepting, now).is_allowed());
+
+    let rejecting = PolicySet::new()
+      .with(TermsOfUsePolicy::new(&["IssuerPolicy"]))
+      .with(EvidencePolicy::new(&["DocumentVerification", "BiometricCheck"]));
+    let decision = credential.verify_with_policy(&resolver, &status_lists, &rejecting, now);
+    let reasons: Vec<&str> = decision.violations.iter().map(|v| v.reason.as_str()).collect();
+    assert_eq!(reasons, ["unsupported terms of use HolderPolicy", "missing evidence BiometricCheck"]);
+  }
+
+  #[test]
+  fn test_policy_shared_across_threads() {
+    let now = 1_700_000_000;
+    let (credential, resolver, _) = signed(now);
+    let policy = std::sync::Arc::new(PolicySet::new().with(IssuerAllowlist::new(&["did:example:issuer"])));
+    let shared = std::sync::Arc::new((credential, resolver));
+
+    let workers: Vec<_> = (0..4)
+      .map(|_| {
+        let (policy, shared) = (policy.clone(), shared.clone());
+        std::thread::spawn(move || shared.0.verify_with_policy(&shared.1, &InMemoryStatusLists::new(), policy.as_ref(), now).is_allowed())
+      })
+      .collect();
+    assert!(workers.into_iter().all(|worker| worker.join().unwrap()));
+  }
+}
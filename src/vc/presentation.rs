@@ -0,0 +1,188 @@
+// Verifiable presentations
+// A holder bundles one or more credentials together with a verifier-supplied
+// challenge and signs the result with the holder key.
+// sign_for_audience also absorbs the verifier identifier and the challenge (the nonce) into
+// the Loquat transcript itself, ahead of the JSON payload. verify_for_audience rebuilds that
+// transcript from the audience and nonce the verifier expects, not from the JSON fields, so a
+// presentation made for one verifier fails at every other even if the JSON checks are skipped.
+
+use crate::crypto::rng::RngHealthError;
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::SignableMessage;
+use crate::signature::timestamp::MaxAge;
+use crate::vc::credential::{Credential, Proof, CREDENTIALS_CONTEXT};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Proof type identifier for presentation signatures bound to an audience and nonce
+pub const LOQUAT_BOUND_PROOF_TYPE: &str = "LoquatBoundSignature2024";
+
+// Domain separation of the bound transcript
+const BINDING_DOMAIN: &[u8] = b"loquat-vc-presentation-binding";
+
+// Bytes actually signed: domain | u64 length | audience | u64 length | nonce | payload
+fn bound_transcript(audience: &str, nonce: &str, payload: &Value) -> Vec<u8> {
+  let mut out = BINDING_DOMAIN.to_vec();
+  for field in [audience, nonce] {
+    out.extend_from_slice(&(field.len() as u64).to_be_bytes());
+    out.extend_from_slice(field.as_bytes());
+  }
+  out.extend_from_slice(&payload.signing_bytes());
+  out
+}
+
+// A holder-signed presentation of credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Presentation {
+  #[serde(rename = "@context")]
+  pub context: Vec<String>,
+  #[serde(rename = "type")]
+  pub types: Vec<String>,
+  pub holder: String,
+  pub verifiable_credential: Vec<Credential>,
+  pub challenge: String, // Verifier challenge the presentation is bound to
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub audience: Option<String>, // Verifier identifier, set by sign_for_audience
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Proof>,
+}
+
+impl Presentation {
+  // Creates an unsigned presentation bound to a verifier challenge
+  pub fn new(holder: &str, credentials: Vec<Credential>, challenge: &str) -> Self {
+    Self {
+      context: vec![CREDENTIALS_CONTEXT.to_string()],
+      types: vec!["VerifiablePresentation".to_string()],
+      holder: holder.to_string(),
+      verifiable_credential: credentials,
+      challenge: challenge.to_string(),
+      audience: None,
+      proof: None,
+    }
+  }
+
+  // JSON payload covered by the holder signature: the presentation without its proof
+  pub fn signing_payload(&self) -> Value {
+    let mut unsigned = self.clone();
+    unsigned.proof = None;
+    serde_json::to_value(&unsigned).expect("Presentation serialization failed")
+  }
+
+  // Signs the presentation with the holder's secret key
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(holder = %self.holder)))]
+  pub fn sign(&mut self, sk: u128, verification_method: &str) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign(sk, verification_method, &self.signing_payload())?);
+    Ok(())
+  }
+
+  // Verifies the holder signature (embedded credentials are verified separately against their issuers)
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(holder = %self.holder), ret))]
+  pub fn verify(&self, holder_pk: &[u8]) -> bool {
+    match &self.proof {
+      Some(proof) => proof.verify(holder_pk, &self.signing_payload()),
+      None => false,
+    }
+  }
+
+  // Signs the presentation together with the holder's clock reading, for verifiers that
+  // only accept presentations made within a time window
+  pub fn sign_timestamped(&mut self, sk: u128, verification_method: &str, now: u64) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign_timestamped(sk, verification_method, &self.signing_payload(), now)?);
+    Ok(())
+  }
+
+  // Verifies a timestamped holder signature made within max_age of now
+  pub fn verify_within(&self, holder_pk: &[u8], now: u64, max_age: &MaxAge) -> bool {
+    match &self.proof {
+      Some(proof) => proof.verify_within(holder_pk, &self.signing_payload(), now, max_age),
+      None => false,
+    }
+  }
+
+  // Signs the presentation for one verifier, binding the audience and the challenge into the
+  // signed transcript
+  pub fn sign_for_audience(&mut self, sk: u128, verification_method: &str, audience: &str) -> Result<(), RngHealthError> {
+    self.audience = Some(audience.to_string());
+    let signature = Loquat::sign(sk, bound_transcript(audience, &self.challenge, &self.signing_payload()).as_slice())?;
+    self.proof = Some(Proof {
+      proof_type: LOQUAT_BOUND_PROOF_TYPE.to_string(),
+      verification_method: verification_method.to_string(),
+      proof_value: Proof::encode_value(&signature.to_bytes()),
+    });
+    Ok(())
+  }
+
+  // Verifies a bound holder signature against the verifier's own identifier and nonce
+  pub fn verify_for_audience(&self, holder_pk: &[u8], audience: &str, nonce: &str) -> bool {
+    let Some(proof) = self.proof.as_ref().filter(|proof| proof.proof_type == LOQUAT_BOUND_PROOF_TYPE) else {
+      return false;
+    };
+    match proof.decode_value().and_then(|bytes| LoquatSignature::from_bytes(&bytes)) {
+      Some(signature) => Loquat::verify(holder_pk, bound_transcript(audience, nonce, &self.signing_payload()).as_slice(), &signature),
+      None => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use serde_json::json;
+
+  #[test]
+  fn test_presentation_signature() {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    credential.sign(issuer.secret_key, "did:example:issuer#key-1").unwrap();
+
+    let mut presentation = Presentation::new("did:example:alice", vec![credential], "challenge-1");
+    presentation.sign(holder.secret_key, "did:example:alice#key-1").unwrap();
+
+    assert!(presentation.verify(&holder.public_key));
+    assert!(presentation.verifiable_credential[0].verify(&issuer.public_key));
+
+    // Rebinding to another challenge invalidates the holder signature
+    let mut rebound = presentation.clone();
+    rebound.challenge = "challenge-2".to_string();
+    assert!(!rebound.verify(&holder.public_key));
+  }
+
+  #[test]
+  fn test_time_bounded_presentation() {
+    let holder = Loquat::keygen();
+    let mut presentation = Presentation::new("did:example:alice", vec![], "challenge-1");
+    presentation.sign_timestamped(holder.secret_key, "did:example:alice#key-1", 10_000).unwrap();
+
+    let five_minutes = MaxAge::new(300);
+    assert!(presentation.verify_within(&holder.public_key, 10_120, &five_minutes));
+    assert!(!presentation.verify_within(&holder.public_key, 10_301, &five_minutes));
+    // Untimed verification does not silently accept the timestamped proof, nor the reverse
+    assert!(!presentation.verify(&holder.public_key));
+    presentation.sign(holder.secret_key, "did:example:alice#key-1").unwrap();
+    assert!(!presentation.verify_within(&holder.public_key, 10_120, &five_minutes));
+  }
+
+  #[test]
+  fn test_audience_bound_presentation() {
+    let holder = Loquat::keygen();
+    let mut presentation = Presentation::new("did:example:alice", vec![], "nonce-1");
+    presentation.sign_for_audience(holder.secret_key, "did:example:alice#key-1", "https://verifier-a.example").unwrap();
+    assert_eq!(presentation.audience.as_deref(), Some("https://verifier-a.example"));
+    assert!(presentation.verify_for_audience(&holder.public_key, "https://verifier-a.example", "nonce-1"));
+
+    // Another verifier, or another nonce, rebuilds a different transcript from its own values
+    assert!(!presentation.verify_for_audience(&holder.public_key, "https://verifier-b.example", "nonce-1"));
+    assert!(!presentation.verify_for_audience(&holder.public_key, "https://verifier-a.example", "nonce-2"));
+    let mut relabelled = presentation.clone();
+    relabelled.audience = Some("https://verifier-b.example".to_string());
+    assert!(!relabelled.verify_for_audience(&holder.public_key, "https://verifier-b.example", "nonce-1"));
+
+    // Bound and unbound proofs are not interchangeable
+    assert!(!presentation.verify(&holder.public_key));
+    presentation.sign(holder.secret_key, "did:example:alice#key-1").unwrap();
+    assert!(!presentation.verify_for_audience(&holder.public_key, "https://verifier-a.example", "nonce-1"));
+  }
+}
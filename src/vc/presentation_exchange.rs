@@ -0,0 +1,369 @@
+// DIF Presentation Exchange
+// A verifier describes the credentials it wants as a presentation definition made of
+// input descriptors; the holder answers with a presentation and a presentation
+// submission mapping each descriptor to a credential inside it.
+//
+// Supported subset:
+// - every input descriptor is required (submission_requirements are not evaluated)
+// - field paths use the JSONPath forms $, .name, ['name'], [n] and [*]
+// - filters use the JSON Schema keywords type, const, enum, minimum, maximum,
+//   exclusiveMinimum, exclusiveMaximum, minLength, maxLength and contains;
+//   any other keyword fails the match rather than being ignored
+
+use crate::crypto::test_rng;
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::Credential;
+use crate::vc::oid4vci::LOQUAT_VC_FORMAT;
+use crate::vc::presentation::Presentation;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Verifier request describing the credentials to present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresentationDefinition {
+  pub id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub purpose: Option<String>,
+  pub input_descriptors: Vec<InputDescriptor>,
+}
+
+// One requested credential
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputDescriptor {
+  pub id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub purpose: Option<String>,
+  #[serde(default)]
+  pub constraints: Constraints,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Constraints {
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub fields: Vec<Field>,
+}
+
+// Constraint on a credential field: some path must resolve to a value passing the filter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+  pub path: Vec<String>, // Alternative JSONPath expressions, the first match wins
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub filter: Option<Value>,
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub optional: bool,
+}
+
+// Holder response mapping input descriptors to credentials in the presentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresentationSubmission {
+  pub id: String,
+  pub definition_id: String,
+  pub descriptor_map: Vec<DescriptorMapEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DescriptorMapEntry {
+  pub id: String, // Input descriptor identifier
+  pub format: String,
+  pub path: String, // JSONPath of the credential within the presentation
+}
+
+impl PresentationDefinition {
+  // Parses a definition from JSON, rejecting definitions without input descriptors
+  pub fn from_json(json: &str) -> Option<Self> {
+    let definition: Self = serde_json::from_str(json).ok()?;
+    if definition.input_descriptors.is_empty() {
+      return None;
+    }
+    Some(definition)
+  }
+
+  // Picks a matching credential for every input descriptor (first match wins)
+  // Returns the index into `credentials` for each descriptor, or None if one cannot be satisfied
+  pub fn select(&self, credentials: &[Credential]) -> Option<Vec<usize>> {
+    let values: Vec<Value> = credentials
+      .iter()
+      .map(|credential| serde_json::to_value(credential).expect("Credential serialization failed"))
+      .collect();
+    self
+      .input_descriptors
+      .iter()
+      .map(|descriptor| values.iter().position(|value| descriptor.matches(value)))
+      .collect()
+  }
+
+  // Builds a signed presentation satisfying the definition, together with its submission
+  // A credential matching several descriptors is included once
+  pub fn present(
+    &self,
+    credentials: &[Credential],
+    holder: &str,
+    challenge: &str,
+    sk: u128,
+    verification_method: &str,
+  ) -> Option<(Presentation, PresentationSubmission)> {
+    let selection = self.select(credentials)?;
+
+    let mut included: Vec<usize> = vec![];
+    let mut descriptor_map = Vec::with_capacity(selection.len());
+    for (descriptor, index) in self.input_descriptors.iter().zip(selection) {
+      let position = match included.iter().position(|&i| i == index) {
+        Some(position) => position,
+        None => {
+          included.push(index);
+          included.len() - 1
+        }
+      };
+      descriptor_map.push(DescriptorMapEntry {
+        id: descriptor.id.clone(),
+        format: LOQUAT_VC_FORMAT.to_string(),
+        path: format!("$.verifiableCredential[{}]", position),
+      });
+    }
+
+    let selected = included.iter().map(|&i| credentials[i].clone()).collect();
+    let mut presentation = Presentation::new(holder, selected, challenge);
+    presentation.sign(sk, verification_method).ok()?;
+
+    let mut id = [0u8; 16];
+    test_rng::source().fill_bytes(&mut id);
+    let submission = PresentationSubmission {
+      id: Encoding::to_hex(&id),
+      definition_id: self.id.clone(),
+      descriptor_map,
+    };
+    Some((presentation, submission))
+  }
+
+  // Checks that a submission answers this definition: every descriptor is mapped, and the
+  // credential each entry points at satisfies that descriptor's constraints
+  // Signatures are not checked here
+  pub fn verify_submission(&self, submission: &PresentationSubmission, presentation: &Presentation) -> bool {
+    if submission.definition_id != self.id {
+      return false;
+    }
+    let presentation = serde_json::to_value(presentation).expect("Presentation serialization failed");
+
+    self.input_descriptors.iter().all(|descriptor| {
+      let entry = match submission.descriptor_map.iter().find(|entry| entry.id == descriptor.id) {
+        Some(entry) => entry,
+        None => return false,
+      };
+      if entry.format != LOQUAT_VC_FORMAT {
+        return false;
+      }
+      match select_path(&presentation, &entry.path).as_deref() {
+        Some([credential]) => descriptor.matches(credential),
+        _ => false,
+      }
+    })
+  }
+}
+
+impl InputDescriptor {
+  // Whether a credential (as JSON) satisfies all required field constraints
+  pub fn matches(&self, credential: &Value) -> bool {
+    self.constraints.fields.iter().all(|field| field.optional || field.matches(credential))
+  }
+}
+
+impl Field {
+  fn matches(&self, credential: &Value) -> bool {
+    self.path.iter().any(|path| {
+      let values = select_path(credential, path).unwrap_or_default();
+      match &self.filter {
+        Some(filter) => values.iter().any(|value| filter_matches(filter, value)),
+        None => !values.is_empty(),
+      }
+    })
+  }
+}
+
+// Evaluates a JSONPath expression, returning None if it is malformed or uses unsupported syntax
+pub fn select_path<'a>(root: &'a Value, path: &str) -> Option<Vec<&'a Value>> {
+  let mut rest = path.strip_prefix('$')?;
+  let mut current = vec![root];
+
+  while !rest.is_empty() {
+    let (segment, remainder) = if let Some(after) = rest.strip_prefix('.') {
+      let end = after.find(['.', '[']).unwrap_or(after.len());
+      (&after[..end], &after[end..])
+    } else if let Some(after) = rest.strip_prefix('[') {
+      let end = after.find(']')?;
+      (&after[..end], &after[end + 1..])
+    } else {
+      return None;
+    };
+    if segment.is_empty() {
+      return None;
+    }
+
+    let name = segment
+      .strip_prefix('\'')
+      .and_then(|s| s.strip_suffix('\''))
+      .or_else(|| segment.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+    current = current
+      .into_iter()
+      .flat_map(|value| -> Vec<&Value> {
+        match (name, segment) {
+          (Some(name), _) => value.get(name).into_iter().collect(),
+          (None, "*") => match value {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(map) => map.values().collect(),
+            _ => vec![],
+          },
+          (None, _) => match segment.parse::<usize>() {
+            Ok(index) => value.get(index).into_iter().collect(),
+            Err(_) => value.get(segment).into_iter().collect(),
+          },
+        }
+      })
+      .collect();
+    rest = remainder;
+  }
+
+  Some(current)
+}
+
+// Evaluates the supported subset of JSON Schema against a value
+fn filter_matches(filter: &Value, value: &Value) -> bool {
+  let rules = match filter.as_object() {
+    Some(rules) => rules,
+    None => return false,
+  };
+
+  rules.iter().all(|(keyword, rule)| match keyword.as_str() {
+    "type" => match rule.as_str() {
+      Some("string") => value.is_string(),
+      Some("number") => value.is_number(),
+      Some("integer") => value.is_i64() || value.is_u64(),
+      Some("boolean") => value.is_boolean(),
+      Some("array") => value.is_array(),
+      Some("object") => value.is_object(),
+      Some("null") => value.is_null(),
+      _ => false,
+    },
+    "const" => value == rule,
+    "enum" => rule.as_array().is_some_and(|options| options.contains(value)),
+    "minimum" => compare(value, rule, |v, r| v >= r),
+    "maximum" => compare(value, rule, |v, r| v <= r),
+    "exclusiveMinimum" => compare(value, rule, |v, r| v > r),
+    "exclusiveMaximum" => compare(value, rule, |v, r| v < r),
+    "minLength" => length(value).zip(rule.as_u64()).is_some_and(|(len, min)| len >= min),
+    "maxLength" => length(value).zip(rule.as_u64()).is_some_and(|(len, max)| len <= max),
+    "contains" => value.as_array().is_some_and(|items| items.iter().any(|item| filter_matches(rule, item))),
+    _ => false,
+  })
+}
+
+fn compare(value: &Value, rule: &Value, op: fn(f64, f64) -> bool) -> bool {
+  match (value.as_f64(), rule.as_f64()) {
+    (Some(v), Some(r)) => op(v, r),
+    _ => false,
+  }
+}
+
+fn length(value: &Value) -> Option<u64> {
+  value.as_str().map(|s| s.chars().count() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use serde_json::json;
+
+  fn definition() -> PresentationDefinition {
+    PresentationDefinition::from_json(
+      r#"{
+        "id": "degree-and-age",
+        "input_descriptors": [
+          {
+            "id": "degree",
+            "constraints": {"fields": [
+              {"path": ["$.type"], "filter": {"type": "array", "contains": {"const": "UniversityDegree"}}},
+              {"path": ["$.credentialSubject.degree.level", "$.credentialSubject.level"], "filter": {"enum": ["BSc", "MSc"]}}
+            ]}
+          },
+          {
+            "id": "age",
+            "constraints": {"fields": [
+              {"path": ["$.credentialSubject.age"], "filter": {"type": "integer", "minimum": 18}},
+              {"path": ["$.credentialSubject.nickname"], "optional": true}
+            ]}
+          }
+        ]
+      }"#,
+    )
+    .unwrap()
+  }
+
+  fn credential(types: &[&str], subject: Value, sk: u128) -> Credential {
+    let mut credential = Credential::new("did:example:issuer", subject);
+    credential.types.extend(types.iter().map(|t| t.to_string()));
+    credential.sign(sk, "did:example:issuer#key-1").unwrap();
+    credential
+  }
+
+  #[test]
+  fn test_select_path() {
+    let value = json!({"a": {"b": [1, 2, {"c": 3}]}, "d e": true});
+    assert_eq!(select_path(&value, "$.a.b[1]"), Some(vec![&json!(2)]));
+    assert_eq!(select_path(&value, "$['a'].b[2].c"), Some(vec![&json!(3)]));
+    assert_eq!(select_path(&value, "$.a.b[*]").unwrap().len(), 3);
+    assert_eq!(select_path(&value, "$[\"d e\"]"), Some(vec![&json!(true)]));
+    assert_eq!(select_path(&value, "$.missing"), Some(vec![]));
+    assert_eq!(select_path(&value, "a.b"), None);
+    assert_eq!(select_path(&value, "$..a"), None);
+  }
+
+  #[test]
+  fn test_filters() {
+    assert!(filter_matches(&json!({"type": "string", "minLength": 2}), &json!("ab")));
+    assert!(!filter_matches(&json!({"type": "string", "maxLength": 1}), &json!("ab")));
+    assert!(filter_matches(&json!({"exclusiveMaximum": 10}), &json!(9.5)));
+    assert!(!filter_matches(&json!({"minimum": 18}), &json!("20")));
+    // Unsupported keywords fail closed
+    assert!(!filter_matches(&json!({"pattern": ".*"}), &json!("anything")));
+  }
+
+  #[test]
+  fn test_present_and_verify_submission() {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let credentials = vec![
+      credential(&["Membership"], json!({"id": "did:example:alice"}), issuer.secret_key),
+      credential(&["UniversityDegree"], json!({"degree": {"level": "MSc"}, "age": 30}), issuer.secret_key),
+    ];
+
+    let definition = definition();
+    assert_eq!(definition.select(&credentials), Some(vec![1, 1]));
+
+    let (presentation, submission) = definition
+      .present(&credentials, "did:example:alice", "nonce-1", holder.secret_key, "did:example:alice#key-1")
+      .unwrap();
+    assert_eq!(presentation.verifiable_credential.len(), 1);
+    assert_eq!(submission.descriptor_map[1].path, "$.verifiableCredential[0]");
+    assert!(presentation.verify(&holder.public_key));
+    assert!(definition.verify_submission(&submission, &presentation));
+
+    // A submission pointing a descriptor at nothing is rejected
+    let mut broken = submission.clone();
+    broken.descriptor_map[0].path = "$.verifiableCredential[5]".to_string();
+    assert!(!definition.verify_submission(&broken, &presentation));
+  }
+
+  #[test]
+  fn test_unsatisfiable_definition() {
+    let issuer = Loquat::keygen();
+    let minor = vec![credential(&["UniversityDegree"], json!({"level": "BSc", "age": 16}), issuer.secret_key)];
+    assert_eq!(definition().select(&minor), None);
+    assert!(definition().present(&minor, "did:example:bob", "nonce", issuer.secret_key, "did:example:bob#key-1").is_none());
+    assert!(PresentationDefinition::from_json(r#"{"id": "empty", "input_descriptors": []}"#).is_none());
+  }
+}
@@ -0,0 +1,147 @@
+// Signature schemes behind credential proofs
+// A CredentialSignatureScheme verifies the proofs of one proof `type`. A SchemeRegistry maps
+// proof types to schemes, so a verifier can accept credentials signed with Loquat alongside
+// credentials from issuers still signing with Ed25519 or Dilithium while the ecosystem
+// migrates: those schemes are registered by implementing the trait over their own backend.
+// Every scheme sees the same canonical payload bytes Loquat proofs are made over.
+
+use crate::signature::loquat::{Loquat, LoquatSignature};
+use crate::signature::message::{SignableMessage, SigningBytes};
+use crate::vc::credential::{Credential, Proof, LOQUAT_PROOF_TYPE};
+use serde_json::Value;
+
+// Verifier for the proofs of one proof type
+pub trait CredentialSignatureScheme: Send + Sync {
+  // Value of the proof `type` field this scheme verifies
+  fn proof_type(&self) -> &str;
+
+  // Verifies a decoded proof value over the canonical payload bytes
+  fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool;
+}
+
+// Loquat proofs, "LoquatSignature2024"
+pub struct LoquatScheme;
+
+impl CredentialSignatureScheme for LoquatScheme {
+  fn proof_type(&self) -> &str {
+    LOQUAT_PROOF_TYPE
+  }
+
+  fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match LoquatSignature::from_bytes(signature) {
+      Some(signature) => Loquat::verify(public_key, &SigningBytes(message), &signature),
+      None => false,
+    }
+  }
+}
+
+// Schemes a verifier accepts, looked up by proof type
+pub struct SchemeRegistry {
+  schemes: Vec<Box<dyn CredentialSignatureScheme>>,
+}
+
+impl SchemeRegistry {
+  // A registry accepting no proof type
+  pub fn new() -> Self {
+    Self { schemes: vec![] }
+  }
+
+  // Adds a scheme, replacing any scheme registered for the same proof type
+  pub fn register<S: CredentialSignatureScheme + 'static>(&mut self, scheme: S) {
+    self.schemes.retain(|existing| existing.proof_type() != scheme.proof_type());
+    self.schemes.push(Box::new(scheme));
+  }
+
+  pub fn scheme(&self, proof_type: &str) -> Option<&dyn CredentialSignatureScheme> {
+    self.schemes.iter().find(|scheme| scheme.proof_type() == proof_type).map(|scheme| scheme.as_ref())
+  }
+
+  // Proof types in registration order
+  pub fn proof_types(&self) -> Vec<&str> {
+    self.schemes.iter().map(|scheme| scheme.proof_type()).collect()
+  }
+
+  // Verifies a proof with the scheme named by its type; unknown types are rejected
+  pub fn verify_proof(&self, proof: &Proof, public_key: &[u8], payload: &Value) -> bool {
+    let scheme = match self.scheme(&proof.proof_type) {
+      Some(scheme) => scheme,
+      None => return false,
+    };
+    match proof.decode_value() {
+      Some(signature) => scheme.verify(public_key, &payload.signing_bytes(), &signature),
+      None => false,
+    }
+  }
+
+  // Verifies the issuer's proof over a credential, whichever registered scheme made it
+  pub fn verify_credential(&self, credential: &Credential, issuer_pk: &[u8]) -> bool {
+    match &credential.proof {
+      Some(proof) => self.verify_proof(proof, issuer_pk, &credential.signing_payload()),
+      None => false,
+    }
+  }
+}
+
+// Accepts Loquat proofs only
+impl Default for SchemeRegistry {
+  fn default() -> Self {
+    let mut registry = Self::new();
+    registry.register(LoquatScheme);
+    registry
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  // Stand-in for a classical scheme: the "signature" is the key followed by the message
+  struct ConcatScheme;
+
+  impl CredentialSignatureScheme for ConcatScheme {
+    fn proof_type(&self) -> &str {
+      "ConcatSignature"
+    }
+
+    fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+      signature == [public_key, message].concat().as_slice()
+    }
+  }
+
+  #[test]
+  fn test_loquat_is_the_default_scheme() {
+    let keypair = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:subject"}));
+    credential.sign(keypair.secret_key, "did:example:issuer#key-1").unwrap();
+
+    let registry = SchemeRegistry::default();
+    assert_eq!(registry.proof_types(), vec![LOQUAT_PROOF_TYPE]);
+    assert!(registry.verify_credential(&credential, &keypair.public_key));
+    assert!(!registry.verify_credential(&credential, &Loquat::keygen().public_key));
+    assert!(!SchemeRegistry::new().verify_credential(&credential, &keypair.public_key));
+  }
+
+  #[test]
+  fn test_dispatch_on_proof_type() {
+    let mut registry = SchemeRegistry::default();
+    registry.register(ConcatScheme);
+
+    // A credential from an issuer that has not migrated to Loquat yet
+    let public_key = b"classical key".to_vec();
+    let mut credential = Credential::new("did:example:legacy", json!({"id": "did:example:subject"}));
+    let message = credential.signing_payload().signing_bytes();
+    credential.proof = Some(Proof {
+      proof_type: "ConcatSignature".to_string(),
+      verification_method: "did:example:legacy#key-1".to_string(),
+      proof_value: Proof::encode_value(&[public_key.as_slice(), &message].concat()),
+    });
+    assert!(registry.verify_credential(&credential, &public_key));
+    // Loquat's own verification does not know the type
+    assert!(!credential.verify(&public_key));
+
+    // The type field selects the scheme, so relabelling the proof breaks it
+    credential.proof.as_mut().unwrap().proof_type = LOQUAT_PROOF_TYPE.to_string();
+    assert!(!registry.verify_credential(&credential, &public_key));
+  }
+}
@@ -0,0 +1,241 @@
+// Proof-carrying credentials
+// A ProvenCredential replaces the issuer's signature with a succinct proof that one exists:
+// the envelope carries the credential payload, the verification method of the issuer's
+// IndexedPublicKey and a proof of circuit::signature::SignatureRelation for the payload under
+// that key, but not the signature itself. Where signatures dominate bandwidth, e.g. credentials
+// relayed over constrained links, the proof is what travels instead.
+// The crate defines the relation, not a proof system: proofs come from a ProofBackend, such as
+// a Groth16 or Spartan prover fed through export::r1cs. The envelope names the backend and the
+// content hash of the verifying key, which verifiers rebuild from the resolved issuer key and
+// compare before handing the proof to the backend.
+
+use crate::circuit::indexer::VerifyingKey;
+use crate::circuit::r1cs::ConstraintSystem;
+use crate::circuit::signature::{SignatureProver, SignatureStatement, SignatureWitness};
+use crate::signature::error::VerifyError;
+use crate::signature::public_indices::IndexedPublicKey;
+use crate::utils::constant_time;
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::{Credential, Proof};
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::status::StatusListSource;
+use serde::{Deserialize, Serialize};
+
+// Type identifier of proven credential envelopes
+pub const PROVEN_CREDENTIAL_TYPE: &str = "LoquatProvenCredential2024";
+
+// Succinct proof system over the crate's constraint systems
+pub trait ProofBackend {
+  // Identifier recorded in envelopes; verifiers only accept proofs of their own backend
+  fn proof_system(&self) -> &str;
+
+  // Proves a satisfied constraint system
+  fn prove(&self, cs: &ConstraintSystem) -> Option<Vec<u8>>;
+
+  fn verify(&self, verifying_key: &VerifyingKey, public_inputs: &[u128], proof: &[u8]) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenCredential {
+  #[serde(rename = "type")]
+  pub envelope_type: String,
+  pub credential: Credential, // Without its proof
+  pub verification_method: String, // Issuer's indexed public key
+  pub proof_system: String,
+  pub verifying_key: String, // Multibase (base58btc) of the verifying key's content hash
+  pub proof_value: String, // Multibase (base58btc) of the backend proof
+}
+
+impl ProvenCredential {
+  // Proves the Loquat signature of a signed credential under the issuer's indexed public key
+  // (encoded as IndexedPublicKey::encode) and drops the signature; None if the credential
+  // carries no Loquat signature that opens to the key, or the backend fails
+  pub fn prove<B: ProofBackend + ?Sized>(credential: &Credential, issuer_key: &[u8], backend: &B) -> Option<Self> {
+    let proof = credential.proof.as_ref()?;
+    let signature = proof.signature()?;
+    let (suite, public_key) = IndexedPublicKey::decode(issuer_key)?;
+    if signature.config != suite.config() {
+      return None;
+    }
+    let statement = SignatureStatement::new(public_key, &credential.signing_payload(), &suite.config())?;
+    let witness = SignatureWitness::new(&statement, &signature)?;
+    let prover = SignatureProver::new(&statement);
+    let proof_value = backend.prove(&prover.constraint_system(&witness)?)?;
+
+    let mut unsigned = credential.clone();
+    unsigned.proof = None;
+    Some(Self {
+      envelope_type: PROVEN_CREDENTIAL_TYPE.to_string(),
+      credential: unsigned,
+      verification_method: proof.verification_method.clone(),
+      proof_system: backend.proof_system().to_string(),
+      verifying_key: Proof::encode_value(&prover.verifying_key().content_hash()),
+      proof_value: Proof::encode_value(&proof_value),
+    })
+  }
+
+  // Verifies the proof against the issuer's indexed public key
+  pub fn verify<B: ProofBackend + ?Sized>(&self, issuer_key: &[u8], backend: &B) -> bool {
+    self.try_verify(issuer_key, backend).is_ok()
+  }
+
+  // verify, reporting why verification failed
+  pub fn try_verify<B: ProofBackend + ?Sized>(&self, issuer_key: &[u8], backend: &B) -> Result<(), VerifyError> {
+    if self.envelope_type != PROVEN_CREDENTIAL_TYPE || self.proof_system != backend.proof_system() {
+      return Err(VerifyError::UnsupportedProofType);
+    }
+    let (suite, public_key) = IndexedPublicKey::decode(issuer_key).ok_or(VerifyError::BadEncoding)?;
+    if !public_key.indices.validate(suite.config().transcript_hash) {
+      return Err(VerifyError::BadEncoding);
+    }
+    let statement = SignatureStatement::new(public_key, &self.credential.signing_payload(), &suite.config()).ok_or(VerifyError::SuiteMismatch)?;
+    let prover = SignatureProver::new(&statement);
+    let verifying_key = prover.verifying_key();
+
+    // A proof for the circuit of another index set is rejected before the backend sees it
+    let pinned = Encoding::from_multibase(&self.verifying_key).ok_or(VerifyError::BadEncoding)?;
+    if !constant_time::bytes_eq(&pinned, &verifying_key.content_hash()) {
+      return Err(VerifyError::KeyMismatch);
+    }
+    let proof = Encoding::from_multibase(&self.proof_value).ok_or(VerifyError::BadEncoding)?;
+    if !backend.verify(&verifying_key, &prover.public_inputs(), &proof) {
+      return Err(VerifyError::InvalidSignature);
+    }
+    Ok(())
+  }
+
+  // Verifies the envelope end to end, as Credential::try_verify_with_resolver does: the
+  // verification method must belong to the issuer and resolve to a key allowed to sign the
+  // credential, then the proof, expiry and revocation status are checked
+  pub fn try_verify_with_resolver<B, R, S>(&self, backend: &B, resolver: &R, status_lists: &S, now: u64) -> Result<(), VerifyError>
+  where
+    B: ProofBackend + ?Sized,
+    R: DidResolver + ?Sized,
+    S: StatusListSource + ?Sized,
+  {
+    if split_did_url(&self.verification_method).0 != self.credential.issuer {
+      return Err(VerifyError::IssuerMismatch);
+    }
+    let method = resolver.resolve_method(&self.verification_method).ok_or(VerifyError::UnknownKey)?;
+    if !method.permits(KeyUsage::for_credential(&self.credential)) {
+      return Err(VerifyError::KeyUsage);
+    }
+    let issuer_key = method.public_key().ok_or(VerifyError::UnknownKey)?;
+
+    self.try_verify(&issuer_key, backend)?;
+    if self.credential.is_expired(now) {
+      return Err(VerifyError::Expired);
+    }
+    match &self.credential.credential_status {
+      Some(status) if status_lists.is_revoked(status) => Err(VerifyError::Revoked),
+      _ => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::export::r1cs::{from_wtns_bytes, to_wtns_bytes};
+  use crate::signature::config::LoquatConfig;
+  use crate::vc::credential::CredentialBuilder;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  const ISSUER_SK: u128 = 0x0a1b_2c3d_4e5f_6071_8293_a4b5_c6d7_e8f9;
+  const ISSUER: &str = "did:example:issuer";
+
+  // Ships the whole assignment and checks it against the expected constraint system: neither
+  // succinct nor zero-knowledge, but it exercises the envelope the way a real backend would
+  struct Transparent {
+    shape: ConstraintSystem,
+  }
+
+  impl ProofBackend for Transparent {
+    fn proof_system(&self) -> &str {
+      "transparent-test"
+    }
+
+    fn prove(&self, cs: &ConstraintSystem) -> Option<Vec<u8>> {
+      Some(to_wtns_bytes(&cs.assignment()))
+    }
+
+    fn verify(&self, verifying_key: &VerifyingKey, public_inputs: &[u128], proof: &[u8]) -> bool {
+      match from_wtns_bytes(proof, public_inputs.len()) {
+        Some(assignment) => verifying_key.matches(&self.shape) && assignment.inputs == public_inputs && self.shape.is_satisfied_by(&assignment),
+        None => false,
+      }
+    }
+  }
+
+  fn issuer_key() -> Vec<u8> {
+    let config = LoquatConfig::default();
+    IndexedPublicKey::new(ISSUER_SK, &[6u8; 32], 16, &config).encode(&config)
+  }
+
+  fn backend() -> Transparent {
+    let (_, public_key) = IndexedPublicKey::decode(&issuer_key()).unwrap();
+    let statement = SignatureStatement::new(public_key, b"shape only", &LoquatConfig::default()).unwrap();
+    let shape = crate::circuit::witness::WitnessBuilder::<crate::circuit::signature::SignatureRelation>::new(&statement)
+      .constraint_system(&SignatureWitness::empty());
+    Transparent { shape }
+  }
+
+  fn signed() -> Credential {
+    CredentialBuilder::new(ISSUER)
+      .subject(json!({ "id": "did:example:holder", "degree": "BSc" }))
+      .verification_method(&format!("{}#indexed", ISSUER))
+      .build_and_sign(ISSUER_SK).unwrap()
+  }
+
+  #[test]
+  fn test_proven_credential_round_trip() {
+    let credential = signed();
+    let envelope = ProvenCredential::prove(&credential, &issuer_key(), &backend()).unwrap();
+    assert!(envelope.credential.proof.is_none());
+    assert_eq!(envelope.credential.signing_payload(), credential.signing_payload());
+    assert!(envelope.verify(&issuer_key(), &backend()));
+
+    let json = serde_json::to_string(&envelope).unwrap();
+    assert!(json.contains(PROVEN_CREDENTIAL_TYPE));
+    let decoded: ProvenCredential = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, envelope);
+
+    let mut document = DidDocument::new(ISSUER);
+    document.add_key("indexed", &issuer_key());
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document);
+    assert_eq!(envelope.try_verify_with_resolver(&backend(), &resolver, &InMemoryStatusLists::new(), 0), Ok(()));
+  }
+
+  #[test]
+  fn test_tampered_envelopes_rejected() {
+    let envelope = ProvenCredential::prove(&signed(), &issuer_key(), &backend()).unwrap();
+
+    // The payload is bound through the message hash input
+    let mut altered = envelope.clone();
+    altered.credential.credential_subject = json!({ "id": "did:example:holder", "degree": "PhD" });
+    assert_eq!(altered.try_verify(&issuer_key(), &backend()), Err(VerifyError::InvalidSignature));
+
+    // A key over other indices has another verifying key; one over the same indices has other
+    // public symbols
+    let config = LoquatConfig::default();
+    let other_indices = IndexedPublicKey::new(ISSUER_SK, &[8u8; 32], 16, &config).encode(&config);
+    assert_eq!(envelope.try_verify(&other_indices, &backend()), Err(VerifyError::KeyMismatch));
+    let other_key = IndexedPublicKey::new(ISSUER_SK ^ 7, &[6u8; 32], 16, &config).encode(&config);
+    assert_eq!(envelope.try_verify(&other_key, &backend()), Err(VerifyError::InvalidSignature));
+
+    let mut foreign = envelope.clone();
+    foreign.proof_system = "groth16".to_string();
+    assert_eq!(foreign.try_verify(&issuer_key(), &backend()), Err(VerifyError::UnsupportedProofType));
+
+    // Unsigned credentials and other issuers' signatures have nothing to prove
+    assert!(ProvenCredential::prove(&CredentialBuilder::new(ISSUER).build(), &issuer_key(), &backend()).is_none());
+    let mut forged = signed();
+    forged.sign(ISSUER_SK ^ 7, &format!("{}#indexed", ISSUER)).unwrap();
+    assert!(ProvenCredential::prove(&forged, &issuer_key(), &backend()).is_none());
+  }
+}
@@ -0,0 +1,153 @@
+// Per-relying-party pseudonyms
+// A holder keeps a link secret and has issuers bind its public key commitment into the
+// credentials they issue (the `linkSecretKey` subject claim). For each relying party the
+// holder derives the pseudonym VRF(link secret, rp_id): stable across visits to the same
+// relying party, unrelated across relying parties, and accompanied by a VRF proof that it
+// was derived from the link secret the credential is bound to.
+//
+// Experimental (feature `experimental`): verifying the VRF proof recovers the link secret,
+// so every relying party a pseudonym is shown to learns it. That relying party can then
+// compute the holder's pseudonym at any other relying party and produce proofs that verify
+// there, i.e. impersonate the holder everywhere the bound credential is accepted, not only
+// correlate visits. See Experimental proofs in the signature module.
+
+use crate::crypto::rng::RngHealthError;
+use crate::crypto::test_rng;
+use crate::signature::config::LoquatConfig;
+use crate::signature::loquat::Loquat;
+use crate::signature::vrf::{LegendreVRF, VrfProof};
+use crate::utils::encoding::{Encoding, Hex, Redacted};
+use crate::utils::field_operations::P;
+use crate::vc::credential::Credential;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Subject claim binding a credential to a holder link secret
+pub const LINK_SECRET_CLAIM: &str = "linkSecretKey";
+
+// Domain separation of pseudonym inputs
+const PSEUDONYM_DOMAIN: &str = "loquat-vc-pseudonym:";
+
+// Pseudonym presented to one relying party
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pseudonym {
+  pub rp_id: String,
+  pub value: String, // Base64url VRF output
+  pub proof: String, // Base64url VRF proof
+}
+
+impl Pseudonym {
+  // Checks the pseudonym for the relying party and its binding to the credential's link secret
+  // The credential itself must be verified separately
+  pub fn verify(&self, rp_id: &str, credential: &Credential) -> bool {
+    if self.rp_id != rp_id {
+      return false;
+    }
+    let key = match credential.credential_subject.get(LINK_SECRET_CLAIM).and_then(|k| k.as_str()).and_then(Encoding::from_base64url) {
+      Some(key) => key,
+      None => return false,
+    };
+    let (value, proof) = match (Encoding::from_base64url(&self.value), Encoding::from_base64url(&self.proof).and_then(|p| VrfProof::from_bytes(&p))) {
+      (Some(value), Some(proof)) => (value, proof),
+      _ => return false,
+    };
+    LegendreVRF::verify(&key, pseudonym_input(rp_id).as_bytes(), &value, &proof)
+  }
+}
+
+fn pseudonym_input(rp_id: &str) -> String {
+  format!("{}{}", PSEUDONYM_DOMAIN, rp_id)
+}
+
+// Holder identity for pseudonymous presentations
+pub struct Holder {
+  link_secret: u128,
+  link_secret_key: Vec<u8>,
+}
+
+impl fmt::Debug for Holder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Holder").field("link_secret", &Redacted).field("link_secret_key", &Hex(&self.link_secret_key)).finish()
+  }
+}
+
+impl Holder {
+  // Creates a holder with a fresh link secret
+  pub fn new() -> Self {
+    Self::with_link_secret(test_rng::source().gen_range(1..P))
+  }
+
+  pub fn with_link_secret(link_secret: u128) -> Self {
+    Self {
+      link_secret,
+      link_secret_key: Loquat::public_key_for(link_secret, &LoquatConfig::default()),
+    }
+  }
+
+  // Base64url public key commitment for issuers to place under LINK_SECRET_CLAIM
+  pub fn link_secret_key(&self) -> String {
+    Encoding::to_base64url(&self.link_secret_key)
+  }
+
+  // Derives the pseudonym for a relying party
+  pub fn pseudonym(&self, rp_id: &str) -> Result<Pseudonym, RngHealthError> {
+    let (value, proof) = LegendreVRF::prove(self.link_secret, pseudonym_input(rp_id).as_bytes())?;
+    Ok(Pseudonym {
+      rp_id: rp_id.to_string(),
+      value: Encoding::to_base64url(&value),
+      proof: Encoding::to_base64url(&proof.to_bytes()),
+    })
+  }
+}
+
+impl Default for Holder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn bound_credential(holder: &Holder) -> Credential {
+    let issuer = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", LINK_SECRET_CLAIM: holder.link_secret_key()}));
+    credential.sign(issuer.secret_key, "did:example:issuer#key-1").unwrap();
+    credential
+  }
+
+  #[test]
+  fn test_pseudonyms_per_relying_party() {
+    let holder = Holder::new();
+    let credential = bound_credential(&holder);
+
+    let shop = holder.pseudonym("https://shop.example").unwrap();
+    assert!(shop.verify("https://shop.example", &credential));
+    assert_eq!(holder.pseudonym("https://shop.example").unwrap().value, shop.value);
+
+    // Another relying party sees an unrelated value, and pseudonyms do not transfer
+    let bank = holder.pseudonym("https://bank.example").unwrap();
+    assert_ne!(bank.value, shop.value);
+    assert!(bank.verify("https://bank.example", &credential));
+    assert!(!shop.verify("https://bank.example", &credential));
+  }
+
+  #[test]
+  fn test_pseudonym_tied_to_credential() {
+    let holder = Holder::new();
+    let pseudonym = holder.pseudonym("https://rp.example").unwrap();
+
+    // A credential bound to another link secret does not back the pseudonym
+    assert!(!pseudonym.verify("https://rp.example", &bound_credential(&Holder::new())));
+    let unbound = Credential::new("did:example:issuer", json!({"id": "did:example:alice"}));
+    assert!(!pseudonym.verify("https://rp.example", &unbound));
+
+    let mut altered = pseudonym;
+    altered.value = Encoding::to_base64url(&[0u8; 32]);
+    assert!(!altered.verify("https://rp.example", &bound_credential(&holder)));
+  }
+}
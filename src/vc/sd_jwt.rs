@@ -0,0 +1,379 @@
+// SD-JWT selective disclosure (IETF draft-ietf-oauth-selective-disclosure-jwt)
+// Concealed claims are replaced in the signed payload by the digests of their disclosures,
+// base64url(JSON [salt, name, value]), collected in "_sd" arrays. The holder forwards only
+// the disclosures it wants to reveal, optionally followed by a key binding JWT.
+//
+// Disclosures are supported for object properties (nested via dotted paths such as
+// "address.street"); array element disclosures are not.
+
+use crate::crypto::hash_functions::{Hash, HashFunction};
+use crate::crypto::rng::RngHealthError;
+use crate::crypto::test_rng;
+use crate::utils::encoding::Encoding;
+use crate::utils::time;
+use crate::vc::credential::Credential;
+use crate::vc::jwt::{Jwt, JwtHeader};
+use rand::RngCore;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+// Digest algorithm recorded in the _sd_alg claim
+pub const SD_ALG: &str = "sha3-256";
+
+// typ header of issuer-signed SD-JWT credentials
+pub const SD_JWT_VC_TYPE: &str = "vc+sd-jwt";
+
+// typ header of key binding JWTs
+pub const KB_JWT_TYPE: &str = "kb+jwt";
+
+const SALT_BYTES: usize = 16;
+
+// base64url(SHA3-256(ascii input)), used for disclosure digests and sd_hash
+fn digest(input: &str) -> String {
+  Encoding::to_base64url(&Hash::new(HashFunction::Sha3_256).compute(input.as_bytes()))
+}
+
+// A single salted claim disclosure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disclosure {
+  pub salt: String,
+  pub name: String,
+  pub value: Value,
+  encoded: String, // Digests are computed over the encoding as received, not a re-encoding
+}
+
+impl Disclosure {
+  // Creates a disclosure with a fresh random salt
+  pub fn new(name: &str, value: Value) -> Self {
+    let mut salt = [0u8; SALT_BYTES];
+    test_rng::source().fill_bytes(&mut salt);
+    let salt = Encoding::to_base64url(&salt);
+    let encoded = Encoding::to_base64url(&serde_json::to_vec(&json!([salt, name, value])).expect("Disclosure serialization failed"));
+    Self {
+      salt,
+      name: name.to_string(),
+      value,
+      encoded,
+    }
+  }
+
+  // Parses an encoded object property disclosure
+  pub fn parse(encoded: &str) -> Option<Self> {
+    let array: Vec<Value> = serde_json::from_slice(&Encoding::from_base64url(encoded)?).ok()?;
+    let [salt, name, value]: [Value; 3] = array.try_into().ok()?;
+    let name = name.as_str()?;
+    if name == "_sd" || name == "..." {
+      return None;
+    }
+    Some(Self {
+      salt: salt.as_str()?.to_string(),
+      name: name.to_string(),
+      value,
+      encoded: encoded.to_string(),
+    })
+  }
+
+  pub fn encoded(&self) -> &str {
+    &self.encoded
+  }
+
+  // Digest embedded in the issuer-signed payload
+  pub fn digest(&self) -> String {
+    digest(&self.encoded)
+  }
+}
+
+// An SD-JWT: issuer-signed JWT, the disclosures being passed along, and an optional key binding JWT
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdJwt {
+  pub jwt: String,
+  pub disclosures: Vec<Disclosure>,
+  pub key_binding: Option<String>,
+}
+
+impl SdJwt {
+  // Issues an SD-JWT over a claims object, concealing the claims at the given dotted paths
+  // When holder_kid is given it is bound into the cnf claim for key binding
+  // Returns None if the claims are not an object or a path does not name an existing claim
+  pub fn issue(claims: &Value, disclosable: &[&str], holder_kid: Option<&str>, issuer_kid: &str, sk: u128) -> Option<Self> {
+    let mut payload = claims.as_object()?.clone();
+    if payload.contains_key("_sd") || payload.contains_key("_sd_alg") {
+      return None;
+    }
+
+    // Conceal the deepest claims first so that a concealed parent carries its children's digests
+    let mut paths: Vec<Vec<&str>> = disclosable.iter().map(|path| path.split('.').collect()).collect();
+    paths.sort_by_key(|path| std::cmp::Reverse(path.len()));
+
+    let mut disclosures = Vec::with_capacity(paths.len());
+    for path in paths {
+      let (name, parents) = path.split_last()?;
+      let mut parent = &mut payload;
+      for segment in parents {
+        parent = parent.get_mut(*segment)?.as_object_mut()?;
+      }
+
+      let disclosure = Disclosure::new(name, parent.remove(*name)?);
+      let digests = parent.entry("_sd").or_insert_with(|| Value::Array(vec![])).as_array_mut()?;
+      digests.push(Value::String(disclosure.digest()));
+      // Sorted digests do not reveal the original claim order
+      digests.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+      disclosures.push(disclosure);
+    }
+
+    payload.insert("_sd_alg".to_string(), Value::String(SD_ALG.to_string()));
+    if let Some(kid) = holder_kid {
+      payload.insert("cnf".to_string(), json!({ "kid": kid }));
+    }
+
+    let header = JwtHeader::new(Some(SD_JWT_VC_TYPE), Some(issuer_kid));
+    Some(Self {
+      jwt: Jwt::sign(&header, &Value::Object(payload), sk).ok()?,
+      disclosures,
+      key_binding: None,
+    })
+  }
+
+  // Issues a credential as an SD-JWT VC: iss, iat, exp and vct come from the credential,
+  // the subject id becomes sub and the remaining subject claims are top-level claims
+  pub fn issue_credential(credential: &Credential, disclosable: &[&str], holder_kid: Option<&str>, issuer_kid: &str, sk: u128) -> Option<Self> {
+    let mut claims = Map::new();
+    claims.insert("iss".to_string(), Value::String(credential.issuer.clone()));
+    claims.insert("iat".to_string(), json!(time::parse_rfc3339(&credential.issuance_date)?));
+    if let Some(expiration) = &credential.expiration_date {
+      claims.insert("exp".to_string(), json!(time::parse_rfc3339(expiration)?));
+    }
+    claims.insert("vct".to_string(), Value::String(credential.types.last()?.clone()));
+
+    for (name, value) in credential.credential_subject.as_object()? {
+      let name = if name == "id" { "sub" } else { name.as_str() };
+      claims.insert(name.to_string(), value.clone());
+    }
+    Self::issue(&Value::Object(claims), disclosable, holder_kid, issuer_kid, sk)
+  }
+
+  // Compact serialization: <jwt>~<disclosure>~...~<key binding jwt or empty>
+  pub fn serialize(&self) -> String {
+    format!("{}{}", self.serialize_without_key_binding(), self.key_binding.as_deref().unwrap_or(""))
+  }
+
+  fn serialize_without_key_binding(&self) -> String {
+    let mut out = format!("{}~", self.jwt);
+    for disclosure in &self.disclosures {
+      out.push_str(disclosure.encoded());
+      out.push('~');
+    }
+    out
+  }
+
+  // Parses the compact serialization
+  pub fn parse(serialized: &str) -> Option<Self> {
+    let mut parts: Vec<&str> = serialized.split('~').collect();
+    if parts.len() < 2 {
+      return None;
+    }
+    let key_binding = parts.pop().filter(|kb| !kb.is_empty()).map(str::to_string);
+    let jwt = parts.remove(0).to_string();
+    let disclosures = parts.into_iter().map(Disclosure::parse).collect::<Option<Vec<_>>>()?;
+    Some(Self { jwt, disclosures, key_binding })
+  }
+
+  // Holder side: keeps only the disclosures needed to reveal the given dotted paths
+  // (including concealed parents of a requested nested claim)
+  pub fn present(&self, paths: &[&str]) -> Option<Self> {
+    let payload = Jwt::parse(&self.jwt)?.claims;
+    let (_, disclosure_paths) = self.reveal(payload)?;
+
+    let disclosures = self
+      .disclosures
+      .iter()
+      .zip(disclosure_paths)
+      .filter(|(_, path)| paths.iter().any(|p| *p == path || p.starts_with(&format!("{}.", path))))
+      .map(|(disclosure, _)| disclosure.clone())
+      .collect();
+    Some(Self {
+      jwt: self.jwt.clone(),
+      disclosures,
+      key_binding: None,
+    })
+  }
+
+  // Holder side: appends a key binding JWT over this presentation for an audience and nonce
+  pub fn bind(&mut self, holder_sk: u128, audience: &str, nonce: &str, iat: u64) -> Result<(), RngHealthError> {
+    let claims = json!({
+      "iat": iat,
+      "aud": audience,
+      "nonce": nonce,
+      "sd_hash": digest(&self.serialize_without_key_binding()),
+    });
+    self.key_binding = Some(Jwt::sign(&JwtHeader::new(Some(KB_JWT_TYPE), None), &claims, holder_sk)?);
+    Ok(())
+  }
+
+  // Verifies the issuer signature and the disclosures, returning the revealed claims
+  // Fails if a disclosure is not referenced by the payload, is repeated, or collides with a plain claim
+  pub fn verify(&self, issuer_pk: &[u8]) -> Option<Value> {
+    let jwt = Jwt::parse(&self.jwt)?;
+    if !jwt.verify(issuer_pk) || jwt.claim_str("_sd_alg") != Some(SD_ALG) {
+      return None;
+    }
+    let (mut claims, _) = self.reveal(jwt.claims)?;
+    claims.as_object_mut()?.remove("_sd_alg");
+    Some(claims)
+  }
+
+  // Verifies the key binding JWT: signed by the holder key, for this audience and nonce,
+  // and covering exactly the issuer JWT and disclosures presented
+  // The holder key is the one named by the cnf claim of the verified payload
+  pub fn verify_key_binding(&self, holder_pk: &[u8], audience: &str, nonce: &str) -> bool {
+    let kb = match self.key_binding.as_deref().and_then(Jwt::parse) {
+      Some(kb) => kb,
+      None => return false,
+    };
+    kb.header.typ.as_deref() == Some(KB_JWT_TYPE)
+      && kb.claim_str("aud") == Some(audience)
+      && kb.claim_str("nonce") == Some(nonce)
+      && kb.claim_str("sd_hash") == Some(digest(&self.serialize_without_key_binding()).as_str())
+      && kb.verify(holder_pk)
+  }
+
+  // Replaces referenced digests with their disclosed claims
+  // Returns the processed payload and the dotted path revealed by each disclosure, in order
+  fn reveal(&self, mut payload: Value) -> Option<(Value, Vec<String>)> {
+    let mut by_digest = HashMap::new();
+    for (index, disclosure) in self.disclosures.iter().enumerate() {
+      if by_digest.insert(disclosure.digest(), index).is_some() {
+        return None;
+      }
+    }
+
+    let mut paths = vec![None; self.disclosures.len()];
+    self.reveal_object(&mut payload, "", &by_digest, &mut paths)?;
+    let paths = paths.into_iter().collect::<Option<Vec<String>>>()?;
+    Some((payload, paths))
+  }
+
+  fn reveal_object(&self, value: &mut Value, prefix: &str, by_digest: &HashMap<String, usize>, paths: &mut [Option<String>]) -> Option<()> {
+    match value {
+      Value::Object(map) => {
+        if let Some(digests) = map.remove("_sd") {
+          for digest in digests.as_array()? {
+            // Digests without a disclosure are undisclosed claims or decoys
+            let index = match by_digest.get(digest.as_str()?) {
+              Some(&index) => index,
+              None => continue,
+            };
+            let disclosure = &self.disclosures[index];
+            if paths[index].is_some() || map.contains_key(&disclosure.name) {
+              return None;
+            }
+            paths[index] = Some(format!("{}{}", prefix, disclosure.name));
+            map.insert(disclosure.name.clone(), disclosure.value.clone());
+          }
+        }
+        for (name, child) in map.iter_mut() {
+          self.reveal_object(child, &format!("{}{}.", prefix, name), by_digest, paths)?;
+        }
+      }
+      Value::Array(items) => {
+        for item in items {
+          self.reveal_object(item, prefix, by_digest, paths)?;
+        }
+      }
+      _ => {}
+    }
+    Some(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+
+  fn claims() -> Value {
+    json!({
+      "iss": "did:example:issuer",
+      "given_name": "Alice",
+      "birthdate": "1990-01-01",
+      "address": {"street": "1 Main St", "locality": "Springfield"}
+    })
+  }
+
+  #[test]
+  fn test_issue_and_verify() {
+    let issuer = Loquat::keygen();
+    let sd_jwt = SdJwt::issue(&claims(), &["given_name", "birthdate", "address", "address.street"], None, "did:example:issuer#key-1", issuer.secret_key).unwrap();
+    assert_eq!(sd_jwt.disclosures.len(), 4);
+
+    // Concealed claims are not visible in the signed payload
+    let payload = Jwt::parse(&sd_jwt.jwt).unwrap().claims;
+    assert!(payload.get("given_name").is_none() && payload.get("address").is_none());
+    assert_eq!(payload["_sd"].as_array().unwrap().len(), 3);
+
+    let parsed = SdJwt::parse(&sd_jwt.serialize()).unwrap();
+    assert_eq!(parsed, sd_jwt);
+    assert_eq!(parsed.verify(&issuer.public_key), Some(claims()));
+    assert_eq!(parsed.verify(&Loquat::keygen().public_key), None);
+  }
+
+  #[test]
+  fn test_holder_selected_disclosure() {
+    let issuer = Loquat::keygen();
+    let sd_jwt = SdJwt::issue(&claims(), &["given_name", "birthdate", "address", "address.street"], None, "did:example:issuer#key-1", issuer.secret_key).unwrap();
+
+    let presented = SdJwt::parse(&sd_jwt.present(&["address.street"]).unwrap().serialize()).unwrap();
+    assert_eq!(presented.disclosures.len(), 2);
+    let revealed = presented.verify(&issuer.public_key).unwrap();
+    assert_eq!(revealed, json!({"iss": "did:example:issuer", "address": {"street": "1 Main St", "locality": "Springfield"}}));
+
+    let revealed = sd_jwt.present(&["address"]).unwrap().verify(&issuer.public_key).unwrap();
+    assert_eq!(revealed["address"], json!({"locality": "Springfield"}));
+    assert!(revealed.get("birthdate").is_none());
+  }
+
+  #[test]
+  fn test_invalid_disclosures_rejected() {
+    let issuer = Loquat::keygen();
+    let sd_jwt = SdJwt::issue(&claims(), &["given_name"], None, "did:example:issuer#key-1", issuer.secret_key).unwrap();
+
+    // A disclosure the issuer never signed is not referenced by the payload
+    let mut injected = sd_jwt.clone();
+    injected.disclosures.push(Disclosure::new("admin", json!(true)));
+    assert_eq!(injected.verify(&issuer.public_key), None);
+
+    let mut repeated = sd_jwt.clone();
+    repeated.disclosures.push(sd_jwt.disclosures[0].clone());
+    assert_eq!(repeated.verify(&issuer.public_key), None);
+
+    assert!(SdJwt::issue(&claims(), &["missing"], None, "did:example:issuer#key-1", issuer.secret_key).is_none());
+    assert!(Disclosure::parse(&Encoding::to_base64url(br#"["salt", "_sd", 1]"#)).is_none());
+  }
+
+  #[test]
+  fn test_credential_with_key_binding() {
+    let issuer = Loquat::keygen();
+    let holder = Loquat::keygen();
+    let mut credential = Credential::new("did:example:issuer", json!({"id": "did:example:alice", "degree": "BSc"}));
+    credential.types.push("UniversityDegree".to_string());
+
+    let sd_jwt = SdJwt::issue_credential(&credential, &["degree"], Some("did:example:alice#key-1"), "did:example:issuer#key-1", issuer.secret_key).unwrap();
+    let mut presented = sd_jwt.present(&["degree"]).unwrap();
+    presented.bind(holder.secret_key, "https://verifier.example", "nonce-1", 1_000).unwrap();
+
+    let presented = SdJwt::parse(&presented.serialize()).unwrap();
+    let claims = presented.verify(&issuer.public_key).unwrap();
+    assert_eq!(claims["vct"], "UniversityDegree");
+    assert_eq!(claims["sub"], "did:example:alice");
+    assert_eq!(claims["degree"], "BSc");
+    assert_eq!(claims["cnf"]["kid"], "did:example:alice#key-1");
+
+    assert!(presented.verify_key_binding(&holder.public_key, "https://verifier.example", "nonce-1"));
+    assert!(!presented.verify_key_binding(&holder.public_key, "https://verifier.example", "nonce-2"));
+
+    // Dropping a disclosure after binding invalidates sd_hash
+    let mut stripped = presented.clone();
+    stripped.disclosures.clear();
+    assert!(!stripped.verify_key_binding(&holder.public_key, "https://verifier.example", "nonce-1"));
+  }
+}
@@ -0,0 +1,373 @@
+// Protocol sessions for issuance and presentation
+// IssuanceSession walks an OpenID4VCI exchange through offer -> request -> credential and
+// PresentationSession an OpenID4VP exchange through request -> presentation -> result. Each
+// step is only accepted in the state that precedes it, and every state serializes to JSON
+// (tagged by "state") so a server can persist the session between HTTP requests and resume
+// it from storage.
+//
+// Not to be confused with multi_issuer::IssuanceSession, which collects co-signatures on a
+// single credential.
+
+use crate::crypto::test_rng;
+use crate::utils::encoding::Encoding;
+use crate::vc::challenge::{ChallengeManager, ChallengeStore};
+use crate::vc::credential::Credential;
+use crate::vc::did::DidResolver;
+use crate::vc::oid4vci::{CredentialIssuer, CredentialOffer, CredentialRequest, CredentialResponse, Oid4vciError};
+use crate::vc::oid4vp::{AuthorizationRequest, AuthorizationResponse, Oid4vpError, PresentationVerifier};
+use crate::vc::presentation::Presentation;
+use crate::vc::presentation_exchange::PresentationDefinition;
+use crate::vc::status::StatusListSource;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Errors of session transitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+  OutOfOrder { state: &'static str, step: &'static str }, // Step not allowed in the current state
+  Issuance(Oid4vciError),
+  Presentation(Oid4vpError),
+}
+
+fn session_id() -> String {
+  let mut id = [0u8; 16];
+  test_rng::source().fill_bytes(&mut id);
+  Encoding::to_hex(&id)
+}
+
+// State of an issuance session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum IssuanceState {
+  Offered { offer: CredentialOffer },
+  Requested { offer: CredentialOffer, request: CredentialRequest },
+  Issued { offer: CredentialOffer, response: Box<CredentialResponse> },
+}
+
+impl IssuanceState {
+  pub fn name(&self) -> &'static str {
+    match self {
+      IssuanceState::Offered { .. } => "offered",
+      IssuanceState::Requested { .. } => "requested",
+      IssuanceState::Issued { .. } => "issued",
+    }
+  }
+
+  fn offer(&self) -> &CredentialOffer {
+    match self {
+      IssuanceState::Offered { offer } | IssuanceState::Requested { offer, .. } | IssuanceState::Issued { offer, .. } => offer,
+    }
+  }
+}
+
+// Issuer-side OpenID4VCI session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssuanceSession {
+  pub id: String,
+  #[serde(flatten)]
+  state: IssuanceState,
+}
+
+impl IssuanceSession {
+  // Starts a session by offering some of the issuer's credential configurations
+  pub fn offer(issuer: &CredentialIssuer, configuration_ids: &[&str], grants: Option<Value>) -> Self {
+    Self {
+      id: session_id(),
+      state: IssuanceState::Offered {
+        offer: issuer.offer(configuration_ids, grants),
+      },
+    }
+  }
+
+  pub fn state(&self) -> &IssuanceState {
+    &self.state
+  }
+
+  pub fn credential_offer(&self) -> &CredentialOffer {
+    self.state.offer()
+  }
+
+  // Records the wallet's credential request; it must ask for an offered configuration
+  pub fn receive_request(&mut self, request: CredentialRequest) -> Result<(), SessionError> {
+    let offer = match &self.state {
+      IssuanceState::Offered { offer } => offer.clone(),
+      state => return Err(SessionError::OutOfOrder { state: state.name(), step: "request" }),
+    };
+    if !offer.credential_configuration_ids.contains(&request.credential_configuration_id) {
+      return Err(SessionError::Issuance(Oid4vciError::UnsupportedCredentialType));
+    }
+    self.state = IssuanceState::Requested { offer, request };
+    Ok(())
+  }
+
+  // Validates the recorded request and issues the credential. A rejected request returns the
+  // session to the offered state so the wallet can retry, e.g. with a fresh c_nonce
+  pub fn issue<R, S>(
+    &mut self,
+    issuer: &CredentialIssuer,
+    claims: Value,
+    nonces: &mut ChallengeManager<S>,
+    resolver: &R,
+    now: u64,
+  ) -> Result<&CredentialResponse, SessionError>
+  where
+    R: DidResolver + ?Sized,
+    S: ChallengeStore,
+  {
+    let (offer, request) = match &self.state {
+      IssuanceState::Requested { offer, request } => (offer.clone(), request),
+      state => return Err(SessionError::OutOfOrder { state: state.name(), step: "issue" }),
+    };
+    match issuer.handle_request(request, claims, nonces, resolver, now) {
+      Ok(response) => {
+        self.state = IssuanceState::Issued {
+          offer,
+          response: Box::new(response),
+        }
+      }
+      Err(err) => {
+        self.state = IssuanceState::Offered { offer };
+        return Err(SessionError::Issuance(err));
+      }
+    }
+    Ok(self.response().expect("Issued session has a response"))
+  }
+
+  pub fn response(&self) -> Option<&CredentialResponse> {
+    match &self.state {
+      IssuanceState::Issued { response, .. } => Some(response),
+      _ => None,
+    }
+  }
+
+  pub fn credential(&self) -> Option<&Credential> {
+    self.response().map(|response| &response.credential)
+  }
+}
+
+// State of a presentation session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum PresentationState {
+  Requested { request: AuthorizationRequest },
+  Presented { request: AuthorizationRequest, response: AuthorizationResponse },
+  Verified { request: AuthorizationRequest, response: AuthorizationResponse },
+  Rejected { request: AuthorizationRequest, error: String }, // OpenID4VP error code
+}
+
+impl PresentationState {
+  pub fn name(&self) -> &'static str {
+    match self {
+      PresentationState::Requested { .. } => "requested",
+      PresentationState::Presented { .. } => "presented",
+      PresentationState::Verified { .. } => "verified",
+      PresentationState::Rejected { .. } => "rejected",
+    }
+  }
+}
+
+// Verifier-side OpenID4VP session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresentationSession {
+  pub id: String,
+  #[serde(flatten)]
+  state: PresentationState,
+}
+
+impl PresentationSession {
+  // Starts a session with an authorization request carrying a fresh nonce
+  pub fn request<S: ChallengeStore>(verifier: &mut PresentationVerifier<S>, definition: PresentationDefinition, now: u64) -> Self {
+    Self {
+      id: session_id(),
+      state: PresentationState::Requested {
+        request: verifier.authorization_request(definition, now),
+      },
+    }
+  }
+
+  pub fn state(&self) -> &PresentationState {
+    &self.state
+  }
+
+  pub fn authorization_request(&self) -> &AuthorizationRequest {
+    match &self.state {
+      PresentationState::Requested { request }
+      | PresentationState::Presented { request, .. }
+      | PresentationState::Verified { request, .. }
+      | PresentationState::Rejected { request, .. } => request,
+    }
+  }
+
+  // Records the wallet's response to the request
+  pub fn receive_response(&mut self, response: AuthorizationResponse) -> Result<(), SessionError> {
+    let request = match &self.state {
+      PresentationState::Requested { request } => request.clone(),
+      state => return Err(SessionError::OutOfOrder { state: state.name(), step: "present" }),
+    };
+    self.state = PresentationState::Presented { request, response };
+    Ok(())
+  }
+
+  // Verifies the recorded response; the session ends verified or rejected either way
+  pub fn verify<S, R, L>(&mut self, verifier: &mut PresentationVerifier<S>, resolver: &R, status_lists: &L, now: u64) -> Result<(), SessionError>
+  where
+    S: ChallengeStore,
+    R: DidResolver + ?Sized,
+    L: StatusListSource + ?Sized,
+  {
+    let (request, response) = match &self.state {
+      PresentationState::Presented { request, response } => (request.clone(), response.clone()),
+      state => return Err(SessionError::OutOfOrder { state: state.name(), step: "verify" }),
+    };
+    match verifier.verify_response(&request, &response, resolver, status_lists, now) {
+      Ok(()) => {
+        self.state = PresentationState::Verified { request, response };
+        Ok(())
+      }
+      Err(err) => {
+        self.state = PresentationState::Rejected {
+          request,
+          error: err.code().to_string(),
+        };
+        Err(SessionError::Presentation(err))
+      }
+    }
+  }
+
+  // The presentation, once verified
+  pub fn presentation(&self) -> Option<&Presentation> {
+    match &self.state {
+      PresentationState::Verified { response, .. } => Some(&response.vp_token),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::jwt::{Jwt, JwtHeader};
+  use crate::vc::oid4vci::{CredentialRequestProof, LOQUAT_VC_FORMAT, PROOF_JWT_TYPE};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  const ISSUER_URL: &str = "https://issuer.example";
+
+  struct Fixture {
+    issuer: CredentialIssuer,
+    resolver: StaticDidResolver,
+    holder_sk: u128,
+    holder_kid: String,
+  }
+
+  fn fixture() -> Fixture {
+    let issuer_keys = Loquat::keygen();
+    let holder_keys = Loquat::keygen();
+    let mut resolver = StaticDidResolver::new();
+    let mut issuer_doc = DidDocument::new("did:example:issuer");
+    let issuer_kid = issuer_doc.add_key("key-1", &issuer_keys.public_key);
+    resolver.insert(issuer_doc);
+    let mut holder_doc = DidDocument::new("did:example:alice");
+    let holder_kid = holder_doc.add_key("key-1", &holder_keys.public_key);
+    resolver.insert(holder_doc);
+    Fixture {
+      issuer: CredentialIssuer::new(ISSUER_URL, &issuer_kid, issuer_keys.secret_key, vec!["AgeCredential".to_string()]),
+      resolver,
+      holder_sk: holder_keys.secret_key,
+      holder_kid,
+    }
+  }
+
+  fn request(f: &Fixture, nonce: &str, now: u64) -> CredentialRequest {
+    let header = JwtHeader::new(Some(PROOF_JWT_TYPE), Some(&f.holder_kid));
+    CredentialRequest {
+      format: LOQUAT_VC_FORMAT.to_string(),
+      credential_configuration_id: "AgeCredential".to_string(),
+      proof: Some(CredentialRequestProof {
+        proof_type: "jwt".to_string(),
+        jwt: Jwt::sign(&header, &json!({"aud": ISSUER_URL, "nonce": nonce, "iat": now}), f.holder_sk).unwrap(),
+      }),
+    }
+  }
+
+  fn definition() -> PresentationDefinition {
+    PresentationDefinition::from_json(
+      r#"{"id": "adult", "input_descriptors": [{"id": "age", "constraints": {"fields": [
+        {"path": ["$.credentialSubject.age"], "filter": {"type": "integer", "minimum": 18}}
+      ]}}]}"#,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn test_issuance_session() {
+    let f = fixture();
+    let mut nonces = ChallengeManager::new(300);
+    let mut session = IssuanceSession::offer(&f.issuer, &["AgeCredential"], None);
+    assert_eq!(session.state().name(), "offered");
+
+    // Issuing before a request arrives is out of order
+    let early = session.issue(&f.issuer, json!({"age": 21}), &mut nonces, &f.resolver, 1_000);
+    assert_eq!(early.unwrap_err(), SessionError::OutOfOrder { state: "offered", step: "issue" });
+
+    // A rejected request sends the session back to the offered state
+    session.receive_request(request(&f, "unknown", 1_000)).unwrap();
+    let rejected = session.issue(&f.issuer, json!({"age": 21}), &mut nonces, &f.resolver, 1_000);
+    assert_eq!(rejected.unwrap_err(), SessionError::Issuance(Oid4vciError::InvalidNonce));
+    assert_eq!(session.state().name(), "offered");
+
+    // The session survives a round trip through storage between steps
+    let nonce = nonces.issue_at(1_000);
+    session.receive_request(request(&f, &nonce.value, 1_000)).unwrap();
+    let stored = serde_json::to_string(&session).unwrap();
+    assert!(stored.contains(r#""state":"requested""#));
+    let mut session: IssuanceSession = serde_json::from_str(&stored).unwrap();
+
+    session.issue(&f.issuer, json!({"age": 21}), &mut nonces, &f.resolver, 1_010).unwrap();
+    assert_eq!(session.credential().unwrap().credential_subject["id"], "did:example:alice");
+    assert_eq!(
+      session.receive_request(request(&f, &nonce.value, 1_020)),
+      Err(SessionError::OutOfOrder { state: "issued", step: "request" })
+    );
+  }
+
+  #[test]
+  fn test_presentation_session() {
+    let f = fixture();
+    let status_lists = InMemoryStatusLists::new();
+    let mut nonces = ChallengeManager::new(300);
+    let mut issuance = IssuanceSession::offer(&f.issuer, &["AgeCredential"], None);
+    let nonce = nonces.issue_at(1_000);
+    issuance.receive_request(request(&f, &nonce.value, 1_000)).unwrap();
+    let credential = issuance.issue(&f.issuer, json!({"age": 21}), &mut nonces, &f.resolver, 1_000).unwrap().credential.clone();
+
+    let mut verifier = PresentationVerifier::new("https://verifier.example", 300);
+    let mut session = PresentationSession::request(&mut verifier, definition(), 1_000);
+    assert_eq!(
+      session.verify(&mut verifier, &f.resolver, &status_lists, 1_010),
+      Err(SessionError::OutOfOrder { state: "requested", step: "verify" })
+    );
+
+    let response = AuthorizationResponse::create(session.authorization_request(), &[credential], "did:example:alice", f.holder_sk, &f.holder_kid).unwrap();
+    session.receive_response(response.clone()).unwrap();
+    let mut session: PresentationSession = serde_json::from_str(&serde_json::to_string(&session).unwrap()).unwrap();
+    assert_eq!(session.verify(&mut verifier, &f.resolver, &status_lists, 1_010), Ok(()));
+    assert_eq!(session.presentation(), Some(&response.vp_token));
+
+    // Replaying the consumed nonce ends the session rejected
+    let mut replay = session.clone();
+    replay.state = PresentationState::Presented {
+      request: session.authorization_request().clone(),
+      response,
+    };
+    assert_eq!(
+      replay.verify(&mut verifier, &f.resolver, &status_lists, 1_020),
+      Err(SessionError::Presentation(Oid4vpError::InvalidNonce))
+    );
+    assert_eq!(replay.state().name(), "rejected");
+    assert!(replay.presentation().is_none());
+  }
+}
@@ -0,0 +1,276 @@
+// Credential status lists
+// A status list is a bitstring published by the issuer; bit i set means the
+// credential holding status index i has been revoked (StatusList2021 style,
+// without the compression step).
+// Published lists are SignedStatusLists: the issuer signs them with a key whose usages
+// include KeyUsage::REVOCATION, and verifiers only accept lists signed by such a key of the
+// issuer they name.
+
+use crate::crypto::rng::RngHealthError;
+use crate::signature::error::VerifyError;
+use crate::utils::encoding::Encoding;
+use crate::vc::credential::Proof;
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Status entry type referencing a status list
+pub const STATUS_LIST_ENTRY_TYPE: &str = "StatusList2021Entry";
+
+// Status entry embedded in a credential
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialStatus {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub status_type: String,
+  pub status_list_index: usize,
+  pub status_list_credential: String, // URL of the published status list
+}
+
+impl CredentialStatus {
+  pub fn new(status_list_url: &str, index: usize) -> Self {
+    Self {
+      id: format!("{}#{}", status_list_url, index),
+      status_type: STATUS_LIST_ENTRY_TYPE.to_string(),
+      status_list_index: index,
+      status_list_credential: status_list_url.to_string(),
+    }
+  }
+}
+
+// A revocation bitstring
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusList {
+  bits: Vec<u8>,
+  len: usize,
+}
+
+impl StatusList {
+  // Creates a list with every entry unrevoked
+  pub fn new(len: usize) -> Self {
+    Self {
+      bits: vec![0; len.div_ceil(8)],
+      len,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // Marks an entry as revoked or unrevoked; returns false if the index is out of range
+  pub fn set_revoked(&mut self, index: usize, revoked: bool) -> bool {
+    if index >= self.len {
+      return false;
+    }
+    let mask = 0x80u8 >> (index % 8);
+    if revoked {
+      self.bits[index / 8] |= mask;
+    } else {
+      self.bits[index / 8] &= !mask;
+    }
+    true
+  }
+
+  // Whether an entry is revoked; out-of-range indices are reported as revoked
+  pub fn is_revoked(&self, index: usize) -> bool {
+    if index >= self.len {
+      return true;
+    }
+    self.bits[index / 8] & (0x80u8 >> (index % 8)) != 0
+  }
+
+  // Encodes the list as "<len>:<hex bitstring>"
+  pub fn encode(&self) -> String {
+    format!("{}:{}", self.len, Encoding::to_hex(&self.bits))
+  }
+
+  // Decodes a list written by encode
+  pub fn decode(encoded: &str) -> Option<Self> {
+    let (len, hex) = encoded.split_once(':')?;
+    let len: usize = len.parse().ok()?;
+    let bits = Encoding::from_hex(hex)?;
+    if bits.len() != len.div_ceil(8) {
+      return None;
+    }
+    Some(Self { bits, len })
+  }
+}
+
+// A status list as published at its URL, signed by its issuer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedStatusList {
+  pub id: String, // URL credentials name in statusListCredential
+  pub issuer: String,
+  pub encoded_list: String, // StatusList::encode form
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Proof>,
+}
+
+impl SignedStatusList {
+  // Unsigned list; sign it with SigningKey::sign_status_list
+  pub fn new(url: &str, issuer: &str, list: &StatusList) -> Self {
+    Self {
+      id: url.to_string(),
+      issuer: issuer.to_string(),
+      encoded_list: list.encode(),
+      proof: None,
+    }
+  }
+
+  // JSON payload covered by the issuer signature: the list without its proof
+  pub fn signing_payload(&self) -> Value {
+    let mut unsigned = self.clone();
+    unsigned.proof = None;
+    serde_json::to_value(&unsigned).expect("Status list serialization failed")
+  }
+
+  pub fn sign(&mut self, sk: u128, verification_method: &str) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign(sk, verification_method, &self.signing_payload())?);
+    Ok(())
+  }
+
+  // The list, once its proof verifies under a revocation key of its issuer
+  pub fn verify_with_resolver<R: DidResolver + ?Sized>(&self, resolver: &R) -> Result<StatusList, VerifyError> {
+    let proof = self.proof.as_ref().ok_or(VerifyError::MissingProof)?;
+    if split_did_url(&proof.verification_method).0 != self.issuer {
+      return Err(VerifyError::IssuerMismatch);
+    }
+    let method = resolver.resolve_method(&proof.verification_method).ok_or(VerifyError::UnknownKey)?;
+    if !method.permits(KeyUsage::REVOCATION) {
+      return Err(VerifyError::KeyUsage);
+    }
+    proof.try_verify(&method.public_key().ok_or(VerifyError::UnknownKey)?, &self.signing_payload())?;
+    StatusList::decode(&self.encoded_list).ok_or(VerifyError::BadEncoding)
+  }
+}
+
+// Source of published status lists, keyed by URL
+pub trait StatusListSource {
+  fn fetch(&self, url: &str) -> Option<StatusList>;
+
+  // Checks a credential status entry against the referenced list
+  // Unknown lists are treated as revoked
+  fn is_revoked(&self, status: &CredentialStatus) -> bool {
+    match self.fetch(&status.status_list_credential) {
+      Some(list) => list.is_revoked(status.status_list_index),
+      None => true,
+    }
+  }
+}
+
+// Status lists held in memory, for issuers serving their own lists and for tests
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStatusLists {
+  lists: HashMap<String, StatusList>,
+}
+
+impl InMemoryStatusLists {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, url: &str, list: StatusList) {
+    self.lists.insert(url.to_string(), list);
+  }
+
+  // Inserts a published list under its URL once it verifies
+  pub fn insert_signed<R: DidResolver + ?Sized>(&mut self, list: &SignedStatusList, resolver: &R) -> Result<(), VerifyError> {
+    let verified = list.verify_with_resolver(resolver)?;
+    self.insert(&list.id, verified);
+    Ok(())
+  }
+
+  pub fn get_mut(&mut self, url: &str) -> Option<&mut StatusList> {
+    self.lists.get_mut(url)
+  }
+}
+
+impl StatusListSource for InMemoryStatusLists {
+  fn fetch(&self, url: &str) -> Option<StatusList> {
+    self.lists.get(url).cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_status_list_bits() {
+    let mut list = StatusList::new(20);
+    assert!(!list.is_revoked(3));
+    assert!(list.set_revoked(3, true));
+    assert!(list.is_revoked(3));
+    assert!(!list.is_revoked(4));
+    assert!(list.set_revoked(3, false));
+    assert!(!list.is_revoked(3));
+
+    assert!(!list.set_revoked(20, true));
+    assert!(list.is_revoked(20));
+  }
+
+  #[test]
+  fn test_status_list_encoding() {
+    let mut list = StatusList::new(13);
+    list.set_revoked(0, true);
+    list.set_revoked(12, true);
+    assert_eq!(StatusList::decode(&list.encode()), Some(list));
+    assert_eq!(StatusList::decode("13:00"), None);
+    assert_eq!(StatusList::decode("garbage"), None);
+  }
+
+  #[test]
+  fn test_status_source() {
+    let url = "https://issuer.example/status/1";
+    let mut lists = InMemoryStatusLists::new();
+    lists.insert(url, StatusList::new(8));
+    lists.get_mut(url).unwrap().set_revoked(5, true);
+
+    assert!(lists.is_revoked(&CredentialStatus::new(url, 5)));
+    assert!(!lists.is_revoked(&CredentialStatus::new(url, 4)));
+    assert!(lists.is_revoked(&CredentialStatus::new("https://issuer.example/status/2", 0)));
+  }
+
+  #[test]
+  fn test_signed_status_list_needs_revocation_key() {
+    use crate::signature::loquat::Loquat;
+    use crate::vc::did::{DidDocument, StaticDidResolver};
+    use crate::vc::key_usage::SigningKey;
+
+    let url = "https://issuer.example/status/1";
+    let (issuance, revocation) = (Loquat::keygen(), Loquat::keygen());
+    let mut document = DidDocument::new("did:example:issuer");
+    let issuance_id = document.add_key_with_usage("key-1", &issuance.public_key, KeyUsage::ISSUANCE);
+    let revocation_id = document.add_key_with_usage("key-2", &revocation.public_key, KeyUsage::REVOCATION);
+    let mut resolver = StaticDidResolver::new();
+    resolver.insert(document.clone());
+
+    let mut list = StatusList::new(8);
+    list.set_revoked(5, true);
+    let mut published = SignedStatusList::new(url, "did:example:issuer", &list);
+    let key = SigningKey::from_document(revocation.secret_key, &document, &revocation_id).unwrap();
+    assert_eq!(key.sign_status_list(&mut published), Ok(()));
+    let mut lists = InMemoryStatusLists::new();
+    assert_eq!(lists.insert_signed(&published, &resolver), Ok(()));
+    assert!(lists.is_revoked(&CredentialStatus::new(url, 5)));
+
+    // The issuance key refuses to sign the list, and a list signed with it anyway is rejected
+    let issuance_key = SigningKey::from_document(issuance.secret_key, &document, &issuance_id).unwrap();
+    assert!(issuance_key.sign_status_list(&mut published.clone()).is_err());
+    published.sign(issuance.secret_key, &issuance_id).unwrap();
+    assert_eq!(published.verify_with_resolver(&resolver), Err(VerifyError::KeyUsage));
+
+    published.sign(revocation.secret_key, &revocation_id).unwrap();
+    published.encoded_list = StatusList::new(8).encode();
+    assert_eq!(published.verify_with_resolver(&resolver), Err(VerifyError::KeyMismatch));
+  }
+}
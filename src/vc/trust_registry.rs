@@ -0,0 +1,268 @@
+// Trust registry
+// A registry operator publishes a JSON (or, with the `toml` feature, TOML) document
+// listing accredited issuer DIDs and the credential types each one is accredited for.
+// The document carries the operator's Loquat proof over the canonical JSON form of the
+// registry, so the signature does not depend on the document format and a verifier
+// only needs to trust the operator's DID to trust the list. Accrediting an issuer hands it
+// issuance authority, as a delegation credential does, so the operator key must permit
+// KeyUsage::DELEGATION. A verified registry is a VerificationPolicy and plugs into
+// credential and presentation verification.
+
+use crate::crypto::rng::RngHealthError;
+use crate::utils::time;
+use crate::vc::credential::{Credential, Proof};
+use crate::vc::did::{split_did_url, DidResolver};
+use crate::vc::key_usage::KeyUsage;
+use crate::vc::policy::{PolicyDecision, ProofMetadata, VerificationPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Accreditation metadata of one issuer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Accreditation {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>, // Display name of the issuer
+  pub credential_types: Vec<String>, // Types the issuer may issue; empty means any type
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub valid_until: Option<String>, // RFC 3339 timestamp
+}
+
+impl Accreditation {
+  pub fn new(credential_types: &[&str]) -> Self {
+    Self {
+      name: None,
+      credential_types: credential_types.iter().map(|t| t.to_string()).collect(),
+      valid_until: None,
+    }
+  }
+
+  // Whether the accreditation has lapsed; unparseable dates count as lapsed
+  pub fn is_expired(&self, now: u64) -> bool {
+    match &self.valid_until {
+      Some(date) => time::parse_rfc3339(date).is_none_or(|until| now >= until),
+      None => false,
+    }
+  }
+
+  // Whether the accreditation covers the credential type
+  pub fn covers(&self, credential_type: &str) -> bool {
+    self.credential_types.is_empty() || self.credential_types.iter().any(|t| t == credential_type)
+  }
+}
+
+// Signed list of accredited issuers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustRegistry {
+  pub id: String,
+  pub operator: String, // DID of the registry operator
+  pub issued: String, // RFC 3339 timestamp
+  pub issuers: BTreeMap<String, Accreditation>, // Keyed by issuer DID
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Proof>,
+}
+
+impl TrustRegistry {
+  // Creates an empty, unsigned registry issued now
+  pub fn new(id: &str, operator: &str) -> Self {
+    Self {
+      id: id.to_string(),
+      operator: operator.to_string(),
+      issued: time::format_rfc3339(time::now_unix()),
+      issuers: BTreeMap::new(),
+      proof: None,
+    }
+  }
+
+  // Adds or replaces an issuer's accreditation
+  pub fn insert(&mut self, issuer: &str, accreditation: Accreditation) {
+    self.issuers.insert(issuer.to_string(), accreditation);
+  }
+
+  pub fn accreditation(&self, issuer: &str) -> Option<&Accreditation> {
+    self.issuers.get(issuer)
+  }
+
+  // JSON payload covered by the operator signature: the registry without its proof
+  pub fn signing_payload(&self) -> Value {
+    let mut unsigned = self.clone();
+    unsigned.proof = None;
+    serde_json::to_value(&unsigned).expect("Trust registry serialization failed")
+  }
+
+  // Signs the registry with the operator's secret key
+  pub fn sign(&mut self, sk: u128, verification_method: &str) -> Result<(), RngHealthError> {
+    self.proof = Some(Proof::sign(sk, verification_method, &self.signing_payload())?);
+    Ok(())
+  }
+
+  // Verifies the operator signature
+  pub fn verify(&self, operator_pk: &[u8]) -> bool {
+    match &self.proof {
+      Some(proof) => proof.verify(operator_pk, &self.signing_payload()),
+      None => false,
+    }
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).expect("Trust registry serialization failed")
+  }
+
+  // Parses a signed registry document and verifies it against the operator's DID
+  // Returns None if the document is malformed, unsigned, not operated by `operator`,
+  // or signed by a key the operator does not control or that may not delegate
+  pub fn load<R: DidResolver + ?Sized>(json: &str, operator: &str, resolver: &R) -> Option<Self> {
+    serde_json::from_str::<TrustRegistry>(json).ok()?.verified(operator, resolver)
+  }
+
+  #[cfg(feature = "toml")]
+  pub fn to_toml(&self) -> String {
+    toml::to_string(self).expect("Trust registry serialization failed")
+  }
+
+  // TOML counterpart of load
+  #[cfg(feature = "toml")]
+  pub fn load_toml<R: DidResolver + ?Sized>(document: &str, operator: &str, resolver: &R) -> Option<Self> {
+    toml::from_str::<TrustRegistry>(document).ok()?.verified(operator, resolver)
+  }
+
+  // Checks that the registry is signed by a key of the expected operator
+  fn verified<R: DidResolver + ?Sized>(self, operator: &str, resolver: &R) -> Option<Self> {
+    let proof = self.proof.as_ref()?;
+    if self.operator != operator || split_did_url(&proof.verification_method).0 != operator {
+      return None;
+    }
+    let operator_pk = resolver.resolve_key_for(&proof.verification_method, KeyUsage::DELEGATION)?;
+    if !self.verify(&operator_pk) {
+      return None;
+    }
+    Some(self)
+  }
+}
+
+impl VerificationPolicy for TrustRegistry {
+  // The issuer must hold an unexpired accreditation covering every specific type of the credential
+  fn evaluate(&self, credential: &Credential, proof: &ProofMetadata) -> PolicyDecision {
+    let accreditation = match self.accreditation(&credential.issuer) {
+      Some(accreditation) => accreditation,
+      None => {
+        return PolicyDecision::deny("trust-registry", &format!("issuer {} is not accredited", credential.issuer));
+      }
+    };
+    if accreditation.is_expired(proof.verified_at) {
+      return PolicyDecision::deny("trust-registry", &format!("accreditation of {} has expired", credential.issuer));
+    }
+
+    let mut decision = PolicyDecision::allow();
+    for credential_type in credential.types.iter().filter(|t| t.as_str() != "VerifiableCredential") {
+      if !accreditation.covers(credential_type) {
+        let reason = format!("issuer {} is not accredited for {}", credential.issuer, credential_type);
+        decision = decision.and(PolicyDecision::deny("trust-registry", &reason));
+      }
+    }
+    decision
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::signature::loquat::Loquat;
+  use crate::vc::did::{DidDocument, StaticDidResolver};
+  use crate::vc::status::InMemoryStatusLists;
+  use serde_json::json;
+
+  const OPERATOR: &str = "did:example:registry";
+  const ISSUER: &str = "did:example:university";
+
+  fn setup() -> (StaticDidResolver, String, Vec<(String, u128)>) {
+    let mut resolver = StaticDidResolver::new();
+    let mut keys = vec![];
+    for did in [OPERATOR, ISSUER] {
+      let keypair = Loquat::keygen();
+      let mut document = DidDocument::new(did);
+      keys.push((document.add_key("key-1", &keypair.public_key), keypair.secret_key));
+      resolver.insert(document);
+    }
+
+    let mut registry = TrustRegistry::new("https://registry.example/issuers", OPERATOR);
+    let mut accreditation = Accreditation::new(&["UniversityDegree"]);
+    accreditation.valid_until = Some("2030-01-01T00:00:00Z".to_string());
+    registry.insert(ISSUER, accreditation);
+    registry.sign(keys[0].1, &keys[0].0).unwrap();
+    (resolver, registry.to_json(), keys)
+  }
+
+  #[test]
+  fn test_load_signed_registry() {
+    let (resolver, json, keys) = setup();
+    let registry = TrustRegistry::load(&json, OPERATOR, &resolver).unwrap();
+    assert!(registry.accreditation(ISSUER).unwrap().covers("UniversityDegree"));
+
+    // The list is only trusted under its operator
+    assert!(TrustRegistry::load(&json, ISSUER, &resolver).is_none());
+
+    // Adding an issuer invalidates the operator signature
+    let mut tampered = registry.clone();
+    tampered.insert("did:example:mallory", Accreditation::new(&[]));
+    assert!(TrustRegistry::load(&tampered.to_json(), OPERATOR, &resolver).is_none());
+
+    // An issuer cannot sign a registry in the operator's name
+    let mut forged = registry;
+    forged.sign(keys[1].1, &keys[1].0).unwrap();
+    assert!(TrustRegistry::load(&forged.to_json(), OPERATOR, &resolver).is_none());
+  }
+
+  #[test]
+  fn test_operator_key_must_delegate() {
+    let (mut resolver, json, keys) = setup();
+    let operator_pk = Loquat::public_key_for(keys[0].1, &crate::signature::LoquatConfig::default());
+    let mut document = DidDocument::new(OPERATOR);
+    document.add_key_with_usage("key-1", &operator_pk, KeyUsage::ISSUANCE | KeyUsage::REVOCATION);
+    resolver.insert(document.clone());
+    assert!(TrustRegistry::load(&json, OPERATOR, &resolver).is_none());
+
+    let mut document = DidDocument::new(OPERATOR);
+    document.add_key_with_usage("key-1", &operator_pk, KeyUsage::DELEGATION);
+    resolver.insert(document);
+    assert!(TrustRegistry::load(&json, OPERATOR, &resolver).is_some());
+  }
+
+  #[cfg(feature = "toml")]
+  #[test]
+  fn test_load_toml_registry() {
+    let (resolver, json, _) = setup();
+    let registry = TrustRegistry::load(&json, OPERATOR, &resolver).unwrap();
+
+    // The JSON-signed registry verifies from its TOML form
+    let document = registry.to_toml();
+    assert_eq!(TrustRegistry::load_toml(&document, OPERATOR, &resolver), Some(registry));
+    let tampered = document.replace("UniversityDegree", "DriverLicense");
+    assert!(TrustRegistry::load_toml(&tampered, OPERATOR, &resolver).is_none());
+  }
+
+  #[test]
+  fn test_registry_policy() {
+    let (resolver, json, keys) = setup();
+    let registry = TrustRegistry::load(&json, OPERATOR, &resolver).unwrap();
+    let status_lists = InMemoryStatusLists::new();
+    let now = time::parse_rfc3339("2029-06-01T00:00:00Z").unwrap();
+
+    let mut degree = Credential::new(ISSUER, json!({"id": "did:example:alice"}));
+    degree.types.push("UniversityDegree".to_string());
+    degree.sign(keys[1].1, &keys[1].0).unwrap();
+    assert!(degree.verify_with_policy(&resolver, &status_lists, &registry, now).is_allowed());
+
+    // The accreditation lapses
+    let later = time::parse_rfc3339("2030-06-01T00:00:00Z").unwrap();
+    assert!(!degree.verify_with_policy(&resolver, &status_lists, &registry, later).is_allowed());
+
+    let mut license = Credential::new(ISSUER, json!({"id": "did:example:alice"}));
+    license.types.push("DriverLicense".to_string());
+    license.sign(keys[1].1, &keys[1].0).unwrap();
+    let decision = license.verify_with_policy(&resolver, &status_lists, &registry, now);
+    assert_eq!(decision.violations[0].reason, "issuer did:example:university is not accredited for DriverLicense");
+  }
+}
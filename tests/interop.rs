@@ -0,0 +1,126 @@
+//! Interoperability harness over W3C VC test suite style fixtures
+//!
+//! Fixtures live in tests/fixtures/vc-data-model as JSON-LD documents named after the suite's
+//! conventions: `example-N.jsonld` must be accepted, and `example-N-bad-<rule>.jsonld` must be
+//! rejected with the `invalid_<rule>` data model error (`bad-shape` documents must not
+//! deserialize at all). Accepted documents are signed, written out, read back and verified,
+//! so both directions of the format are covered.
+//! Run with: cargo test --test interop
+
+use loquat_vc::prelude::{Credential, Loquat, Presentation};
+use loquat_vc::vc::credential::LOQUAT_PROOF_TYPE;
+use loquat_vc::vc::data_model::{check_credential, check_presentation, DataModelError};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/vc-data-model");
+
+struct Fixture {
+  name: String,
+  json: Value,
+  expected: Option<String>, // Error code a conforming implementation reports
+}
+
+// Every fixture of one kind, in name order
+fn fixtures(kind: &str) -> Vec<Fixture> {
+  let mut paths: Vec<PathBuf> = fs::read_dir(Path::new(FIXTURES).join(kind))
+    .expect("Fixture directory is readable")
+    .map(|entry| entry.expect("Fixture entry is readable").path())
+    .filter(|path| path.extension().is_some_and(|ext| ext == "jsonld"))
+    .collect();
+  paths.sort();
+  assert!(!paths.is_empty(), "no {} fixtures", kind);
+
+  paths
+    .into_iter()
+    .map(|path| {
+      let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+      let json = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap_or_else(|e| panic!("{}: {}", name, e));
+      let expected = name.split_once("-bad-").map(|(_, rule)| format!("invalid_{}", rule.replace('-', "_")));
+      Fixture { name, json, expected }
+    })
+    .collect()
+}
+
+// Consumes a document, reporting "invalid_shape" when it does not deserialize
+fn consume<T: DeserializeOwned>(json: &Value, check: fn(&T) -> Result<(), DataModelError>) -> Result<T, String> {
+  let document: T = serde_json::from_value(json.clone()).map_err(|_| "invalid_shape".to_string())?;
+  check(&document).map_err(|e| e.code().to_string())?;
+  Ok(document)
+}
+
+// Writes a produced document where a suite runner would pick it up and reads it back
+fn produce(kind: &str, name: &str, document: &Value) -> Value {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("interop").join(kind);
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(format!("{}.jsonld", name));
+  fs::write(&path, serde_json::to_string_pretty(document).unwrap()).unwrap();
+  serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+// The proof in the shape the suites check: a type, a verification method and a proof value
+fn assert_proof_shape(name: &str, document: &Value) {
+  let proof = &document["proof"];
+  assert_eq!(proof["type"], LOQUAT_PROOF_TYPE, "{}", name);
+  assert!(proof["verificationMethod"].as_str().is_some_and(|vm| vm.contains('#')), "{}", name);
+  assert!(proof["proofValue"].as_str().is_some_and(|value| !value.is_empty()), "{}", name);
+}
+
+// The produced document is the input plus a proof: nothing dropped, renamed or rewritten
+fn assert_preserved(name: &str, input: &Value, produced: &Value) {
+  let mut unsigned = produced.clone();
+  unsigned.as_object_mut().unwrap().remove("proof");
+  assert_eq!(&unsigned, input, "{}", name);
+}
+
+#[test]
+fn test_credential_fixtures_are_classified() {
+  for fixture in fixtures("credentials") {
+    let outcome = consume::<Credential>(&fixture.json, check_credential);
+    assert_eq!(outcome.err(), fixture.expected, "{}", fixture.name);
+  }
+}
+
+#[test]
+fn test_signed_credentials_round_trip() {
+  let issuer = Loquat::keygen();
+  for fixture in fixtures("credentials").into_iter().filter(|f| f.expected.is_none()) {
+    let mut credential: Credential = consume(&fixture.json, check_credential).unwrap();
+    credential.sign(issuer.secret_key, &format!("{}#key-1", credential.issuer)).unwrap();
+
+    let produced = produce("credentials", &fixture.name, &serde_json::to_value(&credential).unwrap());
+    assert_proof_shape(&fixture.name, &produced);
+    assert_preserved(&fixture.name, &fixture.json, &produced);
+
+    let consumed: Credential = consume(&produced, check_credential).unwrap();
+    assert!(consumed.verify(&issuer.public_key), "{}", fixture.name);
+    let mut tampered = consumed;
+    tampered.issuance_date = "2000-01-01T00:00:00Z".to_string();
+    assert!(!tampered.verify(&issuer.public_key), "{}", fixture.name);
+  }
+}
+
+#[test]
+fn test_presentation_fixtures_round_trip() {
+  let holder = Loquat::keygen();
+  for fixture in fixtures("presentations") {
+    let outcome = consume::<Presentation>(&fixture.json, check_presentation);
+    let mut presentation = match outcome {
+      Ok(presentation) => presentation,
+      Err(code) => {
+        assert_eq!(Some(code), fixture.expected, "{}", fixture.name);
+        continue;
+      }
+    };
+    assert_eq!(fixture.expected, None, "{}", fixture.name);
+
+    presentation.sign(holder.secret_key, &format!("{}#key-1", presentation.holder)).unwrap();
+    let produced = produce("presentations", &fixture.name, &serde_json::to_value(&presentation).unwrap());
+    assert_proof_shape(&fixture.name, &produced);
+    assert_preserved(&fixture.name, &fixture.json, &produced);
+    let consumed: Presentation = consume(&produced, check_presentation).unwrap();
+    assert!(consumed.verify(&holder.public_key), "{}", fixture.name);
+  }
+}